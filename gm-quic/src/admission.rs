@@ -0,0 +1,576 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use dashmap::DashMap;
+use qbase::cid::ConnectionId;
+use ring::hmac;
+
+/// The number of source prefixes whose token buckets are tracked before the full buckets are pruned.
+const PRUNE_BUCKETS_THRESHOLD: usize = 4096;
+
+/// How long a retry token is valid after it is issued.
+const RETRY_TOKEN_LIFETIME: Duration = Duration::from_secs(10);
+
+/// What to do with a new connection which is not admitted by the [`AdmissionPolicy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AdmissionAction {
+    /// Silently drop the Initial packet, the client may retransmit it later.
+    #[default]
+    Drop,
+    /// Answer the client with a `CONNECTION_REFUSED` error.
+    ///
+    /// The error is sent in an Initial packet without creating the connection, so it costs no
+    /// handshake state.
+    Refuse,
+    /// Answer the client with a Retry packet, if the connection is not admitted by the rates.
+    ///
+    /// The client sends its Initial packet again with the token in the Retry packet, which proves
+    /// the client owns its source address, and the connection is then admitted regardless of the
+    /// rates. The connections not admitted by the number of handshakes are dropped.
+    Retry,
+}
+
+/// A token bucket rate, `per_second` tokens are refilled every second, up to `burst` tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rate {
+    per_second: f64,
+    burst: f64,
+}
+
+/// Policies deciding whether a new connection is admitted by the [`QuicListeners`].
+///
+/// Source addresses are grouped by their prefixes, `/32` for IPv4 and `/64` for IPv6 by default. The
+/// limits of a prefix are shared by all the addresses in it, so that a client can not bypass the
+/// limits by switching the addresses it owns.
+///
+/// By default, no limits are applied, only the `backlog` of [`QuicListenersBuilder::listen`] bounds
+/// the number of pending connections.
+///
+/// [`QuicListeners`]: crate::QuicListeners
+/// [`QuicListenersBuilder::listen`]: crate::QuicListenersBuilder::listen
+#[derive(Debug, Clone)]
+pub struct AdmissionPolicy {
+    ipv4_prefix_len: u8,
+    ipv6_prefix_len: u8,
+    max_handshakes_per_prefix: Option<usize>,
+    prefix_rate: Option<Rate>,
+    global_rate: Option<Rate>,
+    refused_action: AdmissionAction,
+}
+
+impl Default for AdmissionPolicy {
+    fn default() -> Self {
+        Self {
+            ipv4_prefix_len: 32,
+            ipv6_prefix_len: 64,
+            max_handshakes_per_prefix: None,
+            prefix_rate: None,
+            global_rate: None,
+            refused_action: AdmissionAction::Drop,
+        }
+    }
+}
+
+impl AdmissionPolicy {
+    /// Specify the prefix lengths used to group the source addresses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ipv4` is greater than 32 or `ipv6` is greater than 128.
+    pub fn with_prefix_len(mut self, ipv4: u8, ipv6: u8) -> Self {
+        assert!(ipv4 <= 32, "IPv4 prefix length must not exceed 32");
+        assert!(ipv6 <= 128, "IPv6 prefix length must not exceed 128");
+        self.ipv4_prefix_len = ipv4;
+        self.ipv6_prefix_len = ipv6;
+        self
+    }
+
+    /// Limit the number of concurrent handshakes from the same source prefix.
+    ///
+    /// A handshake is counted from the first Initial packet until the handshake is done or the
+    /// connection is closed.
+    pub fn with_max_handshakes_per_prefix(mut self, max: usize) -> Self {
+        self.max_handshakes_per_prefix = Some(max);
+        self
+    }
+
+    /// Limit the rate of new connections from the same source prefix.
+    ///
+    /// Each prefix has a token bucket of `burst` tokens, refilled at `per_second` tokens per second.
+    /// A new connection takes a token.
+    pub fn with_prefix_rate(mut self, per_second: f64, burst: u32) -> Self {
+        self.prefix_rate = Some(Rate {
+            per_second,
+            burst: burst as f64,
+        });
+        self
+    }
+
+    /// Limit the rate of new connections from all sources.
+    ///
+    /// Works the same as [`with_prefix_rate`], but the token bucket is shared by all sources.
+    ///
+    /// [`with_prefix_rate`]: AdmissionPolicy::with_prefix_rate
+    pub fn with_global_handshake_rate(mut self, per_second: f64, burst: u32) -> Self {
+        self.global_rate = Some(Rate {
+            per_second,
+            burst: burst as f64,
+        });
+        self
+    }
+
+    /// Specify what to do with the connections which are not admitted.
+    ///
+    /// Default to [`AdmissionAction::Drop`].
+    pub fn with_refused_action(mut self, action: AdmissionAction) -> Self {
+        self.refused_action = action;
+        self
+    }
+
+    /// Returns what to do with the connections which are not admitted.
+    pub fn refused_action(&self) -> AdmissionAction {
+        self.refused_action
+    }
+
+    fn prefix_of(&self, ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(v4) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.ipv4_prefix_len as u32)
+                    .unwrap_or(0);
+                IpAddr::V4((u32::from(v4) & mask).into())
+            }
+            IpAddr::V6(v6) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.ipv6_prefix_len as u32)
+                    .unwrap_or(0);
+                IpAddr::V6((u128::from(v6) & mask).into())
+            }
+        }
+    }
+}
+
+/// The reason why a new connection is not admitted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum AdmissionRefused {
+    #[error("too many concurrent handshakes from the source prefix")]
+    TooManyHandshakes,
+    #[error("new connection rate of the source prefix exceeded")]
+    PrefixRateExceeded,
+    #[error("global handshake rate exceeded")]
+    GlobalRateExceeded,
+}
+
+impl AdmissionRefused {
+    /// The reason phrase sent to the client when the connection is refused.
+    pub(crate) const REASON: &'static str = "connection is not admitted";
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(rate: Rate, now: Instant) -> Self {
+        Self {
+            tokens: rate.burst,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, rate: Rate, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.per_second).min(rate.burst);
+        self.updated_at = now;
+    }
+
+    fn can_take(&mut self, rate: Rate, now: Instant) -> bool {
+        self.refill(rate, now);
+        self.tokens >= 1.0
+    }
+
+    /// Take a token, which must have been checked by [`TokenBucket::can_take`].
+    fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+
+    fn try_take(&mut self, rate: Rate, now: Instant) -> bool {
+        let can_take = self.can_take(rate, now);
+        if can_take {
+            self.take();
+        }
+        can_take
+    }
+
+    fn is_full(&mut self, rate: Rate, now: Instant) -> bool {
+        self.refill(rate, now);
+        self.tokens >= rate.burst
+    }
+}
+
+/// The runtime state of the [`AdmissionPolicy`].
+#[derive(Debug)]
+pub(crate) struct Admission {
+    policy: AdmissionPolicy,
+    handshakes: DashMap<IpAddr, usize>,
+    buckets: DashMap<IpAddr, TokenBucket>,
+    global_bucket: Mutex<Option<TokenBucket>>,
+    retry_key: hmac::Key,
+}
+
+impl Admission {
+    pub(crate) fn new(policy: AdmissionPolicy) -> Arc<Self> {
+        let global_bucket = policy
+            .global_rate
+            .map(|rate| TokenBucket::full(rate, Instant::now()));
+        let retry_key = hmac::Key::generate(hmac::HMAC_SHA256, &ring::rand::SystemRandom::new())
+            .expect("failed to generate the retry token key");
+        Arc::new(Self {
+            policy,
+            handshakes: DashMap::new(),
+            buckets: DashMap::new(),
+            global_bucket: Mutex::new(global_bucket),
+            retry_key,
+        })
+    }
+
+    pub(crate) fn policy(&self) -> &AdmissionPolicy {
+        &self.policy
+    }
+
+    /// Decide whether a new connection from `source` is admitted.
+    ///
+    /// The returned permit must be held until the handshake is done. Sources which are not IP
+    /// addresses are only limited by the global rate, and the sources validated by a Retry
+    /// packet are only limited by the number of handshakes.
+    pub(crate) fn try_admit(
+        self: &Arc<Self>,
+        source: Option<IpAddr>,
+        validated: bool,
+    ) -> Result<AdmissionPermit, AdmissionRefused> {
+        let now = Instant::now();
+        let prefix = source.map(|ip| self.policy.prefix_of(ip));
+        let tracked_prefix = prefix.filter(|_| self.policy.max_handshakes_per_prefix.is_some());
+
+        // 计数在检查期间一直被锁定，并发的握手不会同时通过检查
+        let mut handshakes =
+            tracked_prefix.map(|prefix| self.handshakes.entry(prefix).or_default());
+        let admitted = match (&handshakes, self.policy.max_handshakes_per_prefix) {
+            (Some(count), Some(max)) if **count >= max => Err(AdmissionRefused::TooManyHandshakes),
+            _ if validated => Ok(()),
+            _ => self.take_tokens(prefix, now),
+        };
+
+        match admitted {
+            Ok(()) => {
+                if let Some(count) = handshakes.as_mut() {
+                    **count += 1;
+                }
+                Ok(AdmissionPermit {
+                    admission: self.clone(),
+                    prefix: tracked_prefix,
+                })
+            }
+            Err(refused) => {
+                drop(handshakes);
+                if let Some(prefix) = tracked_prefix {
+                    self.handshakes.remove_if(&prefix, |_, count| *count == 0);
+                }
+                Err(refused)
+            }
+        }
+    }
+
+    fn take_tokens(&self, prefix: Option<IpAddr>, now: Instant) -> Result<(), AdmissionRefused> {
+        let mut prefix_bucket = None;
+        if let (Some(prefix), Some(rate)) = (prefix, self.policy.prefix_rate) {
+            if !self.buckets.contains_key(&prefix) && self.buckets.len() >= PRUNE_BUCKETS_THRESHOLD
+            {
+                // a full bucket behaves the same as an untracked one
+                self.buckets.retain(|_, bucket| !bucket.is_full(rate, now));
+            }
+            let mut bucket = self
+                .buckets
+                .entry(prefix)
+                .or_insert_with(|| TokenBucket::full(rate, now));
+            if !bucket.can_take(rate, now) {
+                return Err(AdmissionRefused::PrefixRateExceeded);
+            }
+            prefix_bucket = Some(bucket);
+        }
+
+        if let Some(rate) = self.policy.global_rate {
+            let mut global_bucket = self.global_bucket.lock().unwrap();
+            let bucket = global_bucket.get_or_insert_with(|| TokenBucket::full(rate, now));
+            if !bucket.try_take(rate, now) {
+                // the prefix keeps its token, it is not the one to blame
+                return Err(AdmissionRefused::GlobalRateExceeded);
+            }
+        }
+        if let Some(mut bucket) = prefix_bucket {
+            bucket.take();
+        }
+        Ok(())
+    }
+
+    /// Issue the token of the Retry packet, which answers the Initial packet the `client` sent to
+    /// the `origin_dcid`, and asks the client to send it again to the `retry_scid`.
+    ///
+    /// The token is `issued_at(8) | odcid_len(1) | odcid | tag`, the tag also covers the client
+    /// address and the `retry_scid`, so that the token can not be used elsewhere.
+    pub(crate) fn issue_retry_token(
+        &self,
+        client: SocketAddr,
+        origin_dcid: &ConnectionId,
+        retry_scid: &ConnectionId,
+    ) -> Vec<u8> {
+        let issued_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut token = issued_at.to_be_bytes().to_vec();
+        token.push(origin_dcid.len() as u8);
+        token.extend_from_slice(origin_dcid);
+        let tag = hmac::sign(
+            &self.retry_key,
+            &retry_token_message(&token, client, retry_scid),
+        );
+        token.extend_from_slice(tag.as_ref());
+        token
+    }
+
+    /// Validate the `token` of the Initial packet the `client` sent to the `dcid`.
+    ///
+    /// Return the original destination connection ID if the token is issued by
+    /// [`Admission::issue_retry_token`] and not expired.
+    pub(crate) fn validate_retry_token(
+        &self,
+        token: &[u8],
+        client: SocketAddr,
+        dcid: &ConnectionId,
+    ) -> Option<ConnectionId> {
+        let issued_at: [u8; 8] = token.get(..8)?.try_into().ok()?;
+        let (&odcid_len, rest) = token[8..].split_first()?;
+        let odcid = rest.get(..odcid_len as usize)?;
+        let (body, tag) = token.split_at(8 + 1 + odcid.len());
+        // 令牌也可能来自NEW_TOKEN帧，校验失败时当作没有令牌
+        hmac::verify(
+            &self.retry_key,
+            &retry_token_message(body, client, dcid),
+            tag,
+        )
+        .ok()?;
+
+        let issued_at = Duration::from_secs(u64::from_be_bytes(issued_at));
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        (now.saturating_sub(issued_at) <= RETRY_TOKEN_LIFETIME)
+            .then(|| ConnectionId::from_slice(odcid))
+    }
+}
+
+fn retry_token_message(body: &[u8], client: SocketAddr, dcid: &ConnectionId) -> Vec<u8> {
+    let mut message = body.to_vec();
+    match client.ip() {
+        IpAddr::V4(ip) => message.extend_from_slice(&ip.octets()),
+        IpAddr::V6(ip) => message.extend_from_slice(&ip.octets()),
+    }
+    message.extend_from_slice(&client.port().to_be_bytes());
+    message.extend_from_slice(dcid);
+    message
+}
+
+/// A handshake admitted by the [`Admission`], released when dropped.
+#[derive(Debug)]
+pub(crate) struct AdmissionPermit {
+    admission: Arc<Admission>,
+    prefix: Option<IpAddr>,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        if let Some(prefix) = self.prefix {
+            if let Some(mut count) = self.admission.handshakes.get_mut(&prefix) {
+                *count = count.saturating_sub(1);
+            }
+            self.admission
+                .handshakes
+                .remove_if(&prefix, |_, count| *count == 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn token_bucket() {
+        let rate = Rate {
+            per_second: 2.0,
+            burst: 3.0,
+        };
+        let now = Instant::now();
+        let mut bucket = TokenBucket::full(rate, now);
+        assert!(bucket.is_full(rate, now));
+        for _ in 0..3 {
+            assert!(bucket.try_take(rate, now));
+        }
+        assert!(!bucket.try_take(rate, now));
+
+        // 0.5秒补充1个令牌
+        let later = now + Duration::from_millis(500);
+        assert!(bucket.try_take(rate, later));
+        assert!(!bucket.can_take(rate, later));
+
+        // 不会超过突发上限
+        let much_later = later + Duration::from_secs(60);
+        assert!(bucket.is_full(rate, much_later));
+        assert_eq!(bucket.tokens, 3.0);
+    }
+
+    #[test]
+    fn prefix_masking() {
+        let policy = AdmissionPolicy::default();
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(policy.prefix_of(ip("192.0.2.77")), ip("192.0.2.77"));
+        assert_eq!(
+            policy.prefix_of(ip("2001:db8:1:2:3:4:5:6")),
+            ip("2001:db8:1:2::")
+        );
+
+        let policy = AdmissionPolicy::default().with_prefix_len(24, 48);
+        assert_eq!(policy.prefix_of(ip("192.0.2.77")), ip("192.0.2.0"));
+        assert_eq!(
+            policy.prefix_of(ip("2001:db8:1:2:3:4:5:6")),
+            ip("2001:db8:1::")
+        );
+
+        let policy = AdmissionPolicy::default().with_prefix_len(0, 0);
+        assert_eq!(policy.prefix_of(ip("192.0.2.77")), ip("0.0.0.0"));
+        assert_eq!(policy.prefix_of(ip("2001:db8::1")), ip("::"));
+    }
+
+    #[test]
+    fn max_handshakes_per_prefix() {
+        let admission = Admission::new(
+            AdmissionPolicy::default()
+                .with_prefix_len(24, 64)
+                .with_max_handshakes_per_prefix(2),
+        );
+        let source = |s: &str| Some(s.parse().unwrap());
+        let first = admission.try_admit(source("192.0.2.1"), false).unwrap();
+        let _second = admission.try_admit(source("192.0.2.2"), false).unwrap();
+        assert_eq!(
+            admission.try_admit(source("192.0.2.3"), false).unwrap_err(),
+            AdmissionRefused::TooManyHandshakes
+        );
+        assert!(admission.try_admit(source("198.51.100.1"), false).is_ok());
+
+        drop(first);
+        assert!(admission.try_admit(source("192.0.2.3"), false).is_ok());
+    }
+
+    #[test]
+    fn global_rejection_keeps_prefix_token() {
+        let admission = Admission::new(
+            AdmissionPolicy::default()
+                .with_prefix_rate(0.0, 1)
+                .with_global_handshake_rate(0.0, 1),
+        );
+        let source = |s: &str| Some(s.parse().unwrap());
+        assert!(admission.try_admit(source("192.0.2.1"), false).is_ok());
+        // 全局令牌耗尽，另一个前缀的令牌不应被消耗
+        assert_eq!(
+            admission.try_admit(source("192.0.2.2"), false).unwrap_err(),
+            AdmissionRefused::GlobalRateExceeded
+        );
+        let prefix = "192.0.2.2".parse::<IpAddr>().unwrap();
+        assert_eq!(admission.buckets.get(&prefix).unwrap().tokens, 1.0);
+        assert_eq!(
+            admission.try_admit(source("192.0.2.1"), false).unwrap_err(),
+            AdmissionRefused::PrefixRateExceeded
+        );
+    }
+
+    #[test]
+    fn validated_source_skips_rates() {
+        let admission = Admission::new(
+            AdmissionPolicy::default()
+                .with_max_handshakes_per_prefix(2)
+                .with_prefix_rate(0.0, 1),
+        );
+        let source = || Some("192.0.2.1".parse().unwrap());
+        let _first = admission.try_admit(source(), false).unwrap();
+        assert_eq!(
+            admission.try_admit(source(), false).unwrap_err(),
+            AdmissionRefused::PrefixRateExceeded
+        );
+        let _second = admission.try_admit(source(), true).unwrap();
+        // 已验证的地址仍然受握手数量的限制
+        assert_eq!(
+            admission.try_admit(source(), true).unwrap_err(),
+            AdmissionRefused::TooManyHandshakes
+        );
+    }
+
+    #[test]
+    fn refused_handshake_is_not_counted() {
+        let admission = Admission::new(
+            AdmissionPolicy::default()
+                .with_max_handshakes_per_prefix(1)
+                .with_prefix_rate(0.0, 0),
+        );
+        let source = Some("192.0.2.1".parse().unwrap());
+        assert_eq!(
+            admission.try_admit(source, false).unwrap_err(),
+            AdmissionRefused::PrefixRateExceeded
+        );
+        assert!(admission.handshakes.is_empty());
+    }
+
+    #[test]
+    fn retry_token() {
+        let admission = Admission::new(AdmissionPolicy::default());
+        let client = "192.0.2.1:4433".parse().unwrap();
+        let origin_dcid = ConnectionId::from_slice(b"origin");
+        let retry_scid = ConnectionId::from_slice(b"retry");
+        let token = admission.issue_retry_token(client, &origin_dcid, &retry_scid);
+
+        assert_eq!(
+            admission.validate_retry_token(&token, client, &retry_scid),
+            Some(origin_dcid)
+        );
+        let other_client = "192.0.2.1:4434".parse().unwrap();
+        assert!(
+            admission
+                .validate_retry_token(&token, other_client, &retry_scid)
+                .is_none()
+        );
+        assert!(
+            admission
+                .validate_retry_token(&token, client, &origin_dcid)
+                .is_none()
+        );
+        assert!(
+            admission
+                .validate_retry_token(&token[..token.len() - 1], client, &retry_scid)
+                .is_none()
+        );
+        // 其他监听器签发的令牌无效
+        let other = Admission::new(AdmissionPolicy::default());
+        assert!(
+            other
+                .validate_retry_token(&token, client, &retry_scid)
+                .is_none()
+        );
+    }
+}
@@ -27,6 +27,18 @@ use crate::*;
 
 type TlsClientConfigBuilder<T> = ConfigBuilder<TlsClientConfig, T>;
 
+/// 与unix socket上的服务端通信时，客户端的socket也必须绑定在路径上，服务端才能回复
+///
+/// 路径名随机生成，使其他用户无法预先占用；bind在路径已存在时失败而不会复用已有的文件
+#[cfg(unix)]
+fn temporary_unix_bind_uri() -> Option<BindUri> {
+    let mut path = std::env::temp_dir();
+    path.push(format!("gm-quic-{:032x}.sock", rand::random::<u128>()));
+    qbase::net::addr::UnixSocketPath::try_from(path.as_path())
+        .ok()
+        .map(BindUri::from)
+}
+
 /// A QUIC client for initiating connections to servers.
 ///
 /// ## Creating Clients
@@ -46,6 +58,8 @@ type TlsClientConfigBuilder<T> = ConfigBuilder<TlsClientConfig, T>;
 pub struct QuicClient {
     bind_interfaces: Option<DashMap<BindUri, BindInterface>>,
     defer_idle_timeout: Duration,
+    scheduler_policy: SchedulerPolicy,
+    extension_frames: ExtensionFrames,
     parameters: ClientParameters,
     _prefer_versions: Vec<u32>,
     quic_iface_factory: Arc<dyn ProductQuicIO>,
@@ -82,7 +96,9 @@ impl QuicClient {
             bind_interfaces: DashMap::new(),
             prefer_versions: vec![1],
             defer_idle_timeout: Duration::ZERO,
-            quic_iface_factory: Arc::new(handy::DEFAULT_QUIC_IO_FACTORY),
+            scheduler_policy: SchedulerPolicy::default(),
+            extension_frames: ExtensionFrames::default(),
+            quic_iface_factory: Arc::new(handy::SchemeQuicIoFactory),
             parameters: handy::client_parameters(),
             tls_config,
             stream_strategy_factory: Box::new(ConsistentConcurrency::new),
@@ -136,7 +152,12 @@ impl QuicClient {
     ///
     /// `server_name` is the name of the server, it will be included in the `ClientHello` message.
     ///
-    /// `server_addr` is the address of the server, packets will be sent to this address.
+    /// `server_addr` is the address of the server, packets will be sent to this address. For a
+    /// [`SocketEndpointAddr::Relay`], the client binds to the server on the relay and sends the
+    /// packets to the relay, the relayed path can be replaced with a direct path later by
+    /// [`Connection::replace_relayed_paths`], once the server is reachable directly. For a
+    /// [`SocketEndpointAddr::Agent`], such as the one looked up on a [`RendezvousAgent`], the agent
+    /// has coordinated the hole punching, the packets are sent to the outer address of the server.
     ///
     /// Note that the returned connection may not yet be connected to the server, but you can use it to do anything you
     /// want, such as sending data, receiving data... operations will be pending until the connection is connected or
@@ -147,7 +168,8 @@ impl QuicClient {
     /// First, the client will select an interface to communicate with the server.
     ///
     /// If the client has already bound a set of addresses, The client will select the interface whose IP family of the
-    /// first address matches the server addr from the bound and not closed interfaces.
+    /// first address matches the server addr from the bound and not closed interfaces. For addresses of a custom scheme,
+    /// the interfaces of the same scheme are selected.
     ///
     /// ### Connect to server
     ///
@@ -177,6 +199,13 @@ impl QuicClient {
                     AddrKind::Internet(Family::V6) => BindUri::from_str("inet://[::]:0")
                         .expect("URL should be valid")
                         .alloc_port(),
+                    #[cfg(unix)]
+                    AddrKind::Unix => temporary_unix_bind_uri()
+                        .ok_or(ConnectEndpointError::NoSuitableInterface)?,
+                    AddrKind::Custom(kind) => kind
+                        .scheme()
+                        .client_bind_uri()
+                        .ok_or(ConnectEndpointError::NoSuitableInterface)?,
                     _ => return Err(ConnectEndpointError::NoSuitableInterface),
                 };
                 let iface = QuicInterfaces::global()
@@ -212,23 +241,35 @@ impl QuicClient {
                 select_or_bind_ifaces(&server_ep)
                     .map_err(|connect_error| {
                         assert!(
-                            error_accumulator.insert(server_ep, connect_error).is_none(),
+                            error_accumulator
+                                .insert(server_ep.clone(), connect_error)
+                                .is_none(),
                             "Duplicate error for the same server endpoint"
                         );
                     })
                     .into_iter()
                     .flatten()
                     .map(move |(real_addr, iface)| {
-                        let dst = match server_ep {
+                        let dst = match &server_ep {
+                            // 中继转发的路径，包发往中继
+                            EndpointAddr::Socket(SocketEndpointAddr::Relay { relay, .. }) => {
+                                RealAddr::Internet(*relay)
+                            }
+                            // 代理已经协调对端打洞，包直接发往对端的外部地址
+                            EndpointAddr::Socket(SocketEndpointAddr::Agent { outer, .. }) => {
+                                RealAddr::Internet(*outer)
+                            }
                             EndpointAddr::Socket(socket_endpoint_addr) => {
-                                RealAddr::Internet(*socket_endpoint_addr)
+                                RealAddr::Internet(**socket_endpoint_addr)
                             }
                             EndpointAddr::Ble(ble_endpont_addr) => {
-                                RealAddr::Bluetooth(*ble_endpont_addr)
+                                RealAddr::Bluetooth(**ble_endpont_addr)
                             }
+                            EndpointAddr::Unix(path) => RealAddr::Unix(path.clone()),
+                            EndpointAddr::Custom(addr) => RealAddr::Custom(addr.clone()),
                         };
-                        let link = Link::new(real_addr, dst);
-                        let pathway = Pathway::new(real_addr.into(), server_ep);
+                        let link = Link::new(real_addr.clone(), dst);
+                        let pathway = Pathway::new(real_addr.into(), server_ep.clone());
                         (iface, link, pathway)
                     })
             })
@@ -248,13 +289,42 @@ impl QuicClient {
                 .with_streams_concurrency_strategy(self.stream_strategy_factory.as_ref())
                 .with_zero_rtt(self.tls_config.enable_early_data)
                 .with_defer_idle_timeout(self.defer_idle_timeout)
+                .with_scheduler_policy(self.scheduler_policy)
+                .with_extension_frames(self.extension_frames.clone())
                 .with_cids(ConnectionId::random_gen(8))
                 .with_qlog(self.logger.clone())
                 .run(),
         );
 
         for (iface, link, pathway) in paths {
-            _ = connection.add_path(iface.bind_uri(), link, pathway);
+            match pathway.remote() {
+                EndpointAddr::Socket(SocketEndpointAddr::Relay { relay, peer }) => {
+                    // 先让中继绑定到对端，再经中继发送Initial包。BIND没有确认，可能丢失，
+                    // 握手完成前定期重发，丢失的Initial包随后会被重传
+                    let connection = connection.clone();
+                    tokio::spawn(async move {
+                        let mut handshaked = std::pin::pin!(connection.handshaked());
+                        let mut path = Some((link, pathway));
+                        let mut interval = Duration::from_millis(100);
+                        loop {
+                            if let Err(e) = qrelay::bind(&iface, relay, peer).await {
+                                tracing::warn!(%relay, %peer, "Failed to bind on the relay: {e}");
+                                return;
+                            }
+                            if let Some((link, pathway)) = path.take() {
+                                _ = connection.add_path(iface.bind_uri(), link, pathway);
+                            }
+                            tokio::select! {
+                                _ = handshaked.as_mut() => return,
+                                _ = tokio::time::sleep(interval) => {
+                                    interval = (interval * 2).min(Duration::from_secs(2));
+                                }
+                            }
+                        }
+                    });
+                }
+                _ => _ = connection.add_path(iface.bind_uri(), link, pathway),
+            }
         }
 
         Ok(connection)
@@ -267,6 +337,8 @@ pub struct QuicClientBuilder<T> {
     prefer_versions: Vec<u32>,
     quic_iface_factory: Arc<dyn ProductQuicIO>,
     defer_idle_timeout: Duration,
+    scheduler_policy: SchedulerPolicy,
+    extension_frames: ExtensionFrames,
     parameters: ClientParameters,
     tls_config: T,
     stream_strategy_factory: Box<dyn ProductStreamsConcurrencyController>,
@@ -280,9 +352,10 @@ impl<T> QuicClientBuilder<T> {
     /// The given factory will be used by [`Self::bind`],
     /// and/or [`QuicClient::connect`] if no interface bound when client built.
     ///
-    /// The default quic interface is provided by [`handy::DEFAULT_QUIC_IO_FACTORY`].
-    /// For Unix and Windows targets, this is a high performance UDP library supporting GSO and GRO
-    /// provided by `qudp` crate. For other platforms, please specify you own factory.
+    /// The default quic interface is provided by [`handy::SchemeQuicIoFactory`], which binds
+    /// `unix` scheme interfaces on unix domain sockets, and the others with
+    /// [`handy::DEFAULT_QUIC_IO_FACTORY`]. For Unix and Windows targets, this is a high performance
+    /// UDP library supporting GSO and GRO provided by `qudp` crate. For other platforms, please specify you own factory.
     pub fn with_iface_factory(self, factory: impl ProductQuicIO + 'static) -> Self {
         Self {
             quic_iface_factory: Arc::new(factory),
@@ -294,9 +367,10 @@ impl<T> QuicClientBuilder<T> {
     ///
     /// If the bind failed, the error will be returned immediately.
     ///
-    /// The default quic interface is provided by [`handy::DEFAULT_QUIC_IO_FACTORY`].
-    /// For Unix and Windows targets, this is a high performance UDP library supporting GSO and GRO
-    /// provided by `qudp` crate. For other platforms, please specify you own factory with
+    /// The default quic interface is provided by [`handy::SchemeQuicIoFactory`], which binds
+    /// `unix` scheme interfaces on unix domain sockets, and the others with
+    /// [`handy::DEFAULT_QUIC_IO_FACTORY`]. For Unix and Windows targets, this is a high performance
+    /// UDP library supporting GSO and GRO provided by `qudp` crate. For other platforms, please specify you own factory with
     /// [`QuicClientBuilder::with_iface_factory`].
     ///
     /// If you dont bind any address, each time the client initiates a new connection,
@@ -347,6 +421,30 @@ impl<T> QuicClientBuilder<T> {
         self
     }
 
+    /// Specify how the data of the client connections is scheduled over their paths.
+    ///
+    /// It only matters when a connection has more than one validated path,
+    /// for example when multipath is negotiated with [`ParameterId::InitialMaxPathId`].
+    ///
+    /// Default to [`SchedulerPolicy::Greedy`], every path sends data as soon as it is ready.
+    pub fn scheduler_policy(mut self, policy: SchedulerPolicy) -> Self {
+        self.scheduler_policy = policy;
+        self
+    }
+
+    /// Register the extension frames that the client connections understand.
+    ///
+    /// The received frames of the registered types are given to the handlers of their
+    /// [`ExtensionFrameDef`], and frames are sent with [`Connection::send_extension_frame`].
+    /// Both endpoints must register the frame types, usually the extension is negotiated with a
+    /// custom transport parameter, see [`Connection::peer_custom_parameter`].
+    ///
+    /// Default to no extension frames.
+    pub fn with_extension_frames(mut self, extension_frames: ExtensionFrames) -> Self {
+        self.extension_frames = extension_frames;
+        self
+    }
+
     /// Specify the [transport parameters] for the client.
     ///
     /// If you call this multiple times, only the last `parameters` will be used.
@@ -385,14 +483,34 @@ impl<T> QuicClientBuilder<T> {
     ///   - `LegacySeqLogger::new(PathBuf::from("/dir"))`: Write to files `{connection_id}_{role}.sqlog` in `dir`
     ///   - `LegacySeqLogger::new(tokio::io::stdout())`: Stream to stdout
     ///   - `LegacySeqLogger::new(tokio::io::stderr())`: Stream to stderr
+    ///   - `LegacySeqLogger::new(RotatingStorage::new("/dir"))`: Write to files in `dir`, within a size
+    ///     budget and a file count, optionally compressed, see [`RotatingStorage`]
     ///
     ///   Output format: JSON-SEQ ([RFC7464]), one JSON event per line.
     ///
+    /// - [`SeqLogger`]: Same as [`LegacySeqLogger`], but writes the qlog main schema, events that have no
+    ///   legacy form are kept.
+    ///
+    ///   Both can be given an [`EventFilter`] with `with_filter`, which selects the events by
+    ///   importance level and name, and turns on the capture of raw packets.
+    ///
+    /// - [`MetricsLogger`]: Aggregates packets, losses, RTT, congestion window and closures of all
+    ///   connections into counters and histograms, rendered in the Prometheus text format on demand.
+    ///
+    /// - [`SampledLogger`]: Passes only some connections to another logger: a share of them, those to
+    ///   some server names or from some peer addresses, or those closed with an error. Recording
+    ///   can also be started and stopped on a live connection with [`Connection::start_qlog`].
+    ///
     /// - [`NoopLogger`]: Ignores all qlog events (default, recommended for production).
     ///
     /// [qvis]: https://qvis.quictools.info/
     /// [RFC7464]: https://www.rfc-editor.org/rfc/rfc7464
     /// [`LegacySeqLogger`]: qevent::telemetry::handy::LegacySeqLogger
+    /// [`SeqLogger`]: qevent::telemetry::handy::SeqLogger
+    /// [`EventFilter`]: qevent::telemetry::filter::EventFilter
+    /// [`MetricsLogger`]: qevent::telemetry::metrics::MetricsLogger
+    /// [`RotatingStorage`]: qevent::telemetry::rotating::RotatingStorage
+    /// [`SampledLogger`]: qevent::telemetry::sampling::SampledLogger
     pub fn with_qlog(mut self, logger: Arc<dyn Log + Send + Sync>) -> Self {
         self.logger = Some(logger);
         self
@@ -423,6 +541,8 @@ impl QuicClientBuilder<TlsClientConfigBuilder<WantsVerifier>> {
             bind_interfaces: self.bind_interfaces,
             prefer_versions: self.prefer_versions,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             quic_iface_factory: self.quic_iface_factory,
             parameters: self.parameters,
             tls_config: self.tls_config.with_root_certificates(root_store),
@@ -443,6 +563,8 @@ impl QuicClientBuilder<TlsClientConfigBuilder<WantsVerifier>> {
             bind_interfaces: self.bind_interfaces,
             prefer_versions: self.prefer_versions,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             quic_iface_factory: self.quic_iface_factory,
             parameters: self.parameters,
             tls_config: self.tls_config.with_webpki_verifier(verifier),
@@ -511,6 +633,8 @@ impl QuicClientBuilder<TlsClientConfigBuilder<WantsVerifier>> {
             bind_interfaces: self.bind_interfaces,
             prefer_versions: self.prefer_versions,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             quic_iface_factory: self.quic_iface_factory,
             parameters: self.parameters,
             tls_config: self
@@ -538,6 +662,8 @@ impl QuicClientBuilder<TlsClientConfigBuilder<WantsClientCert>> {
             bind_interfaces: self.bind_interfaces,
             prefer_versions: self.prefer_versions,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             quic_iface_factory: self.quic_iface_factory,
             parameters: self.parameters,
             tls_config: self
@@ -556,6 +682,8 @@ impl QuicClientBuilder<TlsClientConfigBuilder<WantsClientCert>> {
             bind_interfaces: self.bind_interfaces,
             prefer_versions: self.prefer_versions,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             quic_iface_factory: self.quic_iface_factory,
             parameters: self.parameters,
             tls_config: self.tls_config.with_no_client_auth(),
@@ -575,6 +703,8 @@ impl QuicClientBuilder<TlsClientConfigBuilder<WantsClientCert>> {
             prefer_versions: self.prefer_versions,
             quic_iface_factory: self.quic_iface_factory,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             parameters: self.parameters,
             tls_config: self.tls_config.with_client_cert_resolver(cert_resolver),
             stream_strategy_factory: self.stream_strategy_factory,
@@ -628,6 +758,8 @@ impl QuicClientBuilder<TlsClientConfig> {
             _prefer_versions: self.prefer_versions,
             quic_iface_factory: self.quic_iface_factory,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             parameters: self.parameters,
             tls_config: self.tls_config,
             stream_strategy_factory: self.stream_strategy_factory,
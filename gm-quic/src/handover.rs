@@ -0,0 +1,369 @@
+use std::{
+    fmt::Write as _,
+    io::{self, IoSlice, IoSliceMut},
+    net::{SocketAddr, UdpSocket},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use bytes::{BufMut, BytesMut};
+use nix::{
+    cmsg_space,
+    fcntl::{FcntlArg, FdFlag, fcntl},
+    sys::socket::{ControlMessage, ControlMessageOwned, MsgFlags, recvmsg, sendmsg},
+};
+use qbase::{
+    net::{
+        addr::{BindUri, RealAddr},
+        route::{Link, Pathway},
+    },
+    packet::{DataHeader, GetDcid, Packet, PacketReader, long},
+};
+use qinterface::{
+    QuicIO,
+    factory::ProductQuicIO,
+    route::{Router, Way},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, Interest},
+    net::UnixStream,
+    sync::mpsc,
+};
+
+// 交接的流程：旧进程经unix socket以SCM_RIGHTS把各接口的UDP socket交给新进程，
+// 消息为 u32长度 + 每行一个接口的"<bind_uri> <socket数量>"，socket按行的顺序排列。
+// 此后同一条unix socket用作转发通道，双方把无法路由的包转发给对方，每帧为
+//   u16帧长 + u16头部长 + 头部"<bind_uri> <本地地址> <对端地址> <DCID长度>" + 包
+// 短包头不含DCID长度，由转发方随包携带
+
+/// The maximum number of sockets handed over at once, `SCM_RIGHTS` carries at most 253.
+const MAX_HANDED_OVER_SOCKETS: usize = 64;
+
+/// The sockets received are closed on exec, so that they do not leak into the child processes.
+#[cfg(any(
+    target_os = "android",
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+const RECV_FLAGS: MsgFlags = MsgFlags::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(
+    target_os = "android",
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+)))]
+const RECV_FLAGS: MsgFlags = MsgFlags::empty();
+
+/// The sockets handed over by an old process of the server, for a zero-downtime restart.
+///
+/// The new process connects to the unix socket the old process waits on, receives the sockets
+/// with [`Handover::receive`], and listens on them with [`QuicListenersBuilder::with_handover`],
+/// on the same [`BindUri`]s as the old process, which are returned by [`Handover::bind_uris`].
+///
+/// From then on, both processes receive on the same sockets. The new process accepts the new
+/// connections, and forwards the other packets it can not route to the old process through the
+/// unix socket; the old process serves its existing connections until they drain, and forwards
+/// the packets it can not route back, see [`QuicListeners::hand_over`].
+///
+/// [`QuicListenersBuilder::with_handover`]: crate::QuicListenersBuilder::with_handover
+/// [`QuicListeners::hand_over`]: crate::QuicListeners::hand_over
+#[derive(Debug)]
+pub struct Handover {
+    bind_uris: Vec<BindUri>,
+    sockets: Vec<(BindUri, OwnedFd)>,
+    stream: UnixStream,
+}
+
+impl Handover {
+    /// Receive the sockets handed over by the old process on the `stream` connected to it.
+    pub async fn receive(mut stream: UnixStream) -> io::Result<Self> {
+        let mut len = [0u8; 4];
+        let mut cmsg_buffer = cmsg_space!([RawFd; MAX_HANDED_OVER_SOCKETS]);
+        let (read, fds) = stream
+            .async_io(Interest::READABLE, || {
+                let mut iov = [IoSliceMut::new(&mut len)];
+                let msg = recvmsg::<()>(
+                    stream.as_raw_fd(),
+                    &mut iov,
+                    Some(&mut cmsg_buffer),
+                    RECV_FLAGS,
+                )?;
+                let mut fds = vec![];
+                for cmsg in msg.cmsgs()? {
+                    if let ControlMessageOwned::ScmRights(rights) = cmsg {
+                        // SAFETY: 内核刚为本进程创建的描述符，由此处独占
+                        fds.extend(
+                            rights
+                                .into_iter()
+                                .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) }),
+                        );
+                    }
+                }
+                if RECV_FLAGS.is_empty() {
+                    // 不支持MSG_CMSG_CLOEXEC的平台上，只能在收到后设置
+                    for fd in &fds {
+                        fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+                    }
+                }
+                Ok((msg.bytes, fds))
+            })
+            .await?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        stream.read_exact(&mut len[read..]).await?;
+        let mut message = vec![0; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut message).await?;
+        let message = String::from_utf8(message).map_err(invalid_data)?;
+
+        let mut bind_uris = vec![];
+        let mut sockets = vec![];
+        let mut fds = fds.into_iter();
+        for line in message.lines() {
+            let (bind_uri, count) = line.rsplit_once(' ').ok_or_else(|| invalid_data(line))?;
+            let bind_uri = BindUri::from_str(bind_uri).map_err(invalid_data)?;
+            for _ in 0..count.parse::<usize>().map_err(invalid_data)? {
+                let fd = fds.next().ok_or_else(|| invalid_data("missing socket"))?;
+                sockets.push((bind_uri.clone(), fd));
+            }
+            bind_uris.push(bind_uri);
+        }
+
+        Ok(Self {
+            bind_uris,
+            sockets,
+            stream,
+        })
+    }
+
+    /// Returns the [`BindUri`]s of the interfaces handed over.
+    pub fn bind_uris(&self) -> &[BindUri] {
+        &self.bind_uris
+    }
+
+    pub(crate) fn into_parts(
+        self,
+        factory: Arc<dyn ProductQuicIO>,
+    ) -> (InheritedIoFactory, UnixStream) {
+        let sockets = self
+            .sockets
+            .into_iter()
+            .map(|(bind_uri, fd)| {
+                let socket = UdpSocket::from(fd);
+                let local = socket.local_addr().ok();
+                (bind_uri, local, socket)
+            })
+            .collect();
+        let factory = InheritedIoFactory {
+            sockets: Mutex::new(sockets),
+            factory,
+        };
+        (factory, self.stream)
+    }
+}
+
+/// Hand the sockets of the interfaces over to the new process on the `stream`.
+pub(crate) async fn send(
+    stream: &mut UnixStream,
+    sockets: Vec<(BindUri, Vec<OwnedFd>)>,
+) -> io::Result<()> {
+    let mut message = String::new();
+    let mut fds = vec![];
+    for (bind_uri, socket_fds) in &sockets {
+        _ = writeln!(message, "{bind_uri} {}", socket_fds.len());
+        fds.extend(socket_fds.iter().map(AsRawFd::as_raw_fd));
+    }
+    if fds.len() > MAX_HANDED_OVER_SOCKETS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Too many sockets to hand over: {}", fds.len()),
+        ));
+    }
+
+    let mut buf = Vec::with_capacity(4 + message.len());
+    buf.put_u32(message.len() as u32);
+    buf.put_slice(message.as_bytes());
+    let sent = stream
+        .async_io(Interest::WRITABLE, || {
+            let cmsgs = [ControlMessage::ScmRights(&fds)];
+            let sent = sendmsg::<()>(
+                stream.as_raw_fd(),
+                &[IoSlice::new(&buf)],
+                &cmsgs,
+                MsgFlags::empty(),
+                None,
+            )?;
+            Ok(sent)
+        })
+        .await?;
+    // socket随第一个字节送达，剩余部分按普通数据发送
+    stream.write_all(&buf[sent..]).await
+}
+
+/// Binds the interfaces on the sockets handed over with [`ProductQuicIO::bind_inherited`] of the
+/// factory the user specified, and the others with its [`ProductQuicIO::bind`].
+pub(crate) struct InheritedIoFactory {
+    sockets: Mutex<Vec<(BindUri, Option<SocketAddr>, UdpSocket)>>,
+    factory: Arc<dyn ProductQuicIO>,
+}
+
+impl ProductQuicIO for InheritedIoFactory {
+    fn bind(&self, bind_uri: BindUri) -> io::Result<Box<dyn QuicIO>> {
+        // 分片的其余socket以实际端口绑定，按地址匹配
+        let addr = SocketAddr::try_from(&bind_uri)
+            .ok()
+            .filter(|addr| addr.port() != 0);
+        let mut sockets = self.sockets.lock().unwrap();
+        let inherited = sockets.iter().position(|(uri, local, _)| {
+            *uri == bind_uri || addr.is_some_and(|addr| Some(addr) == *local)
+        });
+        match inherited {
+            Some(index) => {
+                let (_, _, socket) = sockets.remove(index);
+                tracing::debug!(%bind_uri, "Bind on the socket handed over");
+                self.factory.bind_inherited(bind_uri, socket)
+            }
+            None => self.factory.bind(bind_uri),
+        }
+    }
+}
+
+/// 等待转发的数据包数上限，unix socket写入阻塞时超出的数据包被丢弃，和UDP丢包一样
+const FORWARD_QUEUE_SIZE: usize = 1024;
+
+/// Forwards the packets to the other process through the unix socket.
+#[derive(Debug, Clone)]
+pub(crate) struct Forwarder(mpsc::Sender<(BytesMut, usize, Way)>);
+
+impl Forwarder {
+    pub(crate) fn forward(&self, packet: Packet, way: Way) {
+        // 只有数据包保留了原始的字节
+        if let Packet::Data(packet) = packet {
+            let dcid_len = packet.header.dcid().len();
+            if let Err(mpsc::error::TrySendError::Full(_)) =
+                self.0.try_send((packet.bytes, dcid_len, way))
+            {
+                tracing::debug!("Forwarding queue is full, drop the packet");
+            }
+        }
+    }
+
+    /// Resolves when the forwarding has finished, the other process is gone then.
+    pub(crate) async fn closed(&self) {
+        self.0.closed().await
+    }
+
+    pub(crate) fn same_channel(&self, other: &Self) -> bool {
+        self.0.same_channel(&other.0)
+    }
+}
+
+/// Whether the packet may open a new connection, which the new process accepts.
+pub(crate) fn opens_connection(packet: &Packet) -> bool {
+    matches!(
+        packet,
+        Packet::Data(packet) if matches!(
+            packet.header,
+            DataHeader::Long(long::DataHeader::Initial(..) | long::DataHeader::ZeroRtt(..))
+        )
+    )
+}
+
+fn invalid_data(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+fn encode_frame(
+    (bytes, dcid_len, (bind_uri, _pathway, link)): (BytesMut, usize, Way),
+) -> Option<Vec<u8>> {
+    let (RealAddr::Internet(local), RealAddr::Internet(peer)) = (link.src(), link.dst()) else {
+        return None;
+    };
+    let header = format!("{bind_uri} {local} {peer} {dcid_len}");
+    if 2 + header.len() + bytes.len() > u16::MAX as usize {
+        return None;
+    }
+    let mut frame = Vec::with_capacity(4 + header.len() + bytes.len());
+    frame.put_u16((2 + header.len() + bytes.len()) as u16);
+    frame.put_u16(header.len() as u16);
+    frame.put_slice(header.as_bytes());
+    frame.put_slice(&bytes);
+    Some(frame)
+}
+
+fn decode_frame(frame: &[u8]) -> io::Result<(BytesMut, usize, Way)> {
+    let truncated = || invalid_data("truncated frame");
+    let header_len = frame.get(..2).ok_or_else(truncated)?;
+    let header_len = 2 + u16::from_be_bytes([header_len[0], header_len[1]]) as usize;
+    let header = frame.get(2..header_len).ok_or_else(truncated)?;
+    let packet = &frame[header_len..];
+    let header = std::str::from_utf8(header).map_err(invalid_data)?;
+    let mut fields = header.rsplitn(4, ' ');
+    let (Some(dcid_len), Some(peer), Some(local), Some(bind_uri)) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return Err(invalid_data(header));
+    };
+    let dcid_len = dcid_len.parse().map_err(invalid_data)?;
+    let bind_uri = BindUri::from_str(bind_uri).map_err(invalid_data)?;
+    let local = RealAddr::Internet(local.parse().map_err(invalid_data)?);
+    let peer = RealAddr::Internet(peer.parse().map_err(invalid_data)?);
+    let way = (
+        bind_uri,
+        Pathway::new(local.clone().into(), peer.clone().into()),
+        Link::new(local, peer),
+    );
+    Ok((BytesMut::from(packet), dcid_len, way))
+}
+
+/// Forward the packets between the processes through the `stream`, until the stream is closed or
+/// all the [`Forwarder`]s are dropped.
+///
+/// The packets forwarded by the other process are delivered to the connections, those still not
+/// routable are passed to the connectionless handler if `deliver_unrouted`, or dropped, so that a
+/// packet is never forwarded back and forth.
+pub(crate) fn forward_through(stream: UnixStream, deliver_unrouted: bool) -> Forwarder {
+    let (tx, mut rx) = mpsc::channel(FORWARD_QUEUE_SIZE);
+    let (mut reader, mut writer) = stream.into_split();
+    tokio::spawn(async move {
+        let receive = async {
+            loop {
+                let len = reader.read_u16().await? as usize;
+                let mut frame = vec![0; len];
+                reader.read_exact(&mut frame).await?;
+                let (bytes, dcid_len, way) = decode_frame(&frame)?;
+                for packet in PacketReader::new(bytes, dcid_len).flatten() {
+                    match deliver_unrouted {
+                        true => Router::global().deliver(packet, way.clone()).await,
+                        false => _ = Router::global().deliver_routed(packet, way.clone()).await,
+                    }
+                }
+            }
+        };
+        let send = async {
+            while let Some(forwarded) = rx.recv().await {
+                if let Some(frame) = encode_frame(forwarded) {
+                    writer.write_all(&frame).await?;
+                }
+            }
+            io::Result::Ok(())
+        };
+        let result: io::Result<()> = tokio::select! {
+            result = receive => result,
+            result = send => result,
+        };
+        match result {
+            Err(e) if e.kind() != io::ErrorKind::UnexpectedEof => {
+                tracing::warn!("Forwarding to the other process failed: {e}")
+            }
+            _ => tracing::info!("Forwarding to the other process finished"),
+        }
+    });
+    Forwarder(tx)
+}
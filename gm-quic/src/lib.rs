@@ -8,14 +8,24 @@ pub use qconnection::{
 };
 pub use qinterface::factory::ProductQuicIO;
 
+#[cfg(unix)]
+pub use crate::handover::Handover;
 pub use crate::{
+    admission::{AdmissionAction, AdmissionPolicy},
     cert::{ToCertificate, ToPrivateKey},
     client::{ConnectEndpointError, ConnectServerError, QuicClient, QuicClientBuilder},
-    server::{BuildServerError, QuicListeners, QuicListenersBuilder, ServerError},
+    rendezvous::{Registration, Rendezvous, RendezvousAgent},
+    server::{
+        BuildServerError, GracefulShutdown, QuicListeners, QuicListenersBuilder, ServerError,
+    },
 };
 
+mod admission;
 mod cert;
 mod client;
+#[cfg(unix)]
+mod handover;
+mod rendezvous;
 mod server;
 #[cfg(test)]
 mod tests;
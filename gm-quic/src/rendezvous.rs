@@ -0,0 +1,281 @@
+use std::{io, net::SocketAddr, sync::Arc};
+
+use dashmap::DashMap;
+use qbase::net::{
+    addr::{BindUri, RealAddr},
+    route::{Link, PacketHeader, Pathway, SocketEndpointAddr},
+};
+use qconnection::prelude::{Connection, StreamReader, StreamWriter};
+use qinterface::{QuicIO, QuicIoExt, iface::QuicInterfaces};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    sync::mpsc,
+};
+
+// 与代理之间的协议：每个请求占用一条双向流，以行为单位
+//   REGISTER <name>  ->  OUTER <addr>，随后该流上持续推送 PUNCH <addr>
+//   CONNECT <name>   ->  PEER <addr> | UNKNOWN
+
+#[derive(Debug)]
+struct Peer {
+    outer: SocketAddr,
+    punch: mpsc::UnboundedSender<SocketAddr>,
+}
+
+/// A minimal rendezvous agent coordinating hole punching between peers behind NATs.
+///
+/// The agent is a QUIC server reachable by all the peers. A peer registers under a name from the
+/// interface it listens on, the agent records the address it observes the peer at, which is the
+/// outer address the peer's NAT maps the interface to. Another peer then looks the name up to get
+/// the outer address, the agent tells the registered peer to punch towards the requester before it
+/// replies, so that the registered peer's NAT already expects the requester when it connects to
+/// the outer address.
+///
+/// The looked up peer is a [`SocketEndpointAddr::Agent`] endpoint, made of the address of the
+/// agent coordinating the punching and the outer address of the peer. The agent never forwards
+/// QUIC packets, as the peer is already punching when the lookup returns, a connection to the
+/// endpoint sends its packets to the outer address at once. Forward the packets with a relay
+/// for the peers that can not be punched, see [`qrelay`].
+///
+/// Each connection accepted by the [`QuicListeners`] for the agent is handed to
+/// [`RendezvousAgent::serve`], peers talk to the agent with [`Rendezvous`].
+///
+/// [`QuicListeners`]: crate::QuicListeners
+#[derive(Debug, Default)]
+pub struct RendezvousAgent {
+    peers: DashMap<String, Peer>,
+}
+
+impl RendezvousAgent {
+    /// Create a new agent without any peer registered.
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    /// Serve the requests of a peer on the `connection`, until the connection is closed.
+    ///
+    /// The `link` is the one returned by [`QuicListeners::accept`] together with the connection,
+    /// its destination is the outer address of the peer.
+    ///
+    /// [`QuicListeners::accept`]: crate::QuicListeners::accept
+    pub async fn serve(self: Arc<Self>, connection: Arc<Connection>, link: Link) -> io::Result<()> {
+        let RealAddr::Internet(outer) = link.dst() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Peer address {} is not an internet address", link.dst()),
+            ));
+        };
+
+        loop {
+            let (_sid, (reader, writer)) = connection.accept_bi_stream().await?;
+            let agent = self.clone();
+            let connection = connection.clone();
+            tokio::spawn(async move {
+                if let Err(e) = agent.handle(&connection, outer, reader, writer).await {
+                    tracing::debug!(%outer, "Rendezvous request failed: {e}");
+                }
+            });
+        }
+    }
+
+    async fn handle(
+        &self,
+        connection: &Connection,
+        outer: SocketAddr,
+        reader: StreamReader,
+        mut writer: StreamWriter,
+    ) -> io::Result<()> {
+        let mut lines = BufReader::new(reader).lines();
+        let Some(request) = lines.next_line().await? else {
+            return Ok(());
+        };
+
+        match request.split_once(' ') {
+            Some(("REGISTER", name)) => {
+                let (punch, mut punched) = mpsc::unbounded_channel();
+                tracing::debug!(name, %outer, "Peer registered");
+                self.peers.insert(
+                    name.to_owned(),
+                    Peer {
+                        outer,
+                        punch: punch.clone(),
+                    },
+                );
+
+                let notify = async {
+                    writer
+                        .write_all(format!("OUTER {outer}\n").as_bytes())
+                        .await?;
+                    writer.flush().await?;
+                    while let Some(requester) = punched.recv().await {
+                        writer
+                            .write_all(format!("PUNCH {requester}\n").as_bytes())
+                            .await?;
+                        writer.flush().await?;
+                    }
+                    io::Result::Ok(())
+                };
+                let result = tokio::select! {
+                    result = notify => result,
+                    _ = connection.terminated() => Ok(()),
+                };
+
+                // 同名的节点可能已经重新注册
+                self.peers
+                    .remove_if(name, |_, peer| peer.punch.same_channel(&punch));
+                result
+            }
+            Some(("CONNECT", name)) => {
+                let reply = match self.peers.get(name) {
+                    // 先通知被连接的节点打洞，再答复请求者
+                    Some(peer) if peer.punch.send(outer).is_ok() => {
+                        format!("PEER {}\n", peer.outer)
+                    }
+                    _ => "UNKNOWN\n".to_owned(),
+                };
+                writer.write_all(reply.as_bytes()).await?;
+                writer.shutdown().await
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid rendezvous request: {request}"),
+            )),
+        }
+    }
+}
+
+/// The client side of a [`RendezvousAgent`], over a connection to the agent.
+#[derive(Clone)]
+pub struct Rendezvous {
+    connection: Arc<Connection>,
+    agent: SocketAddr,
+}
+
+impl Rendezvous {
+    /// Talk to the agent at the address `agent` through the `connection` to it.
+    pub fn new(connection: Arc<Connection>, agent: SocketAddr) -> Self {
+        Self { connection, agent }
+    }
+
+    async fn request(
+        &self,
+        request: String,
+    ) -> io::Result<(Lines<BufReader<StreamReader>>, StreamWriter)> {
+        let (_sid, (reader, mut writer)) = self
+            .connection
+            .open_bi_stream()
+            .await?
+            .ok_or_else(|| io::Error::other("No stream available to the agent"))?;
+        writer.write_all(request.as_bytes()).await?;
+        writer.flush().await?;
+        Ok((BufReader::new(reader).lines(), writer))
+    }
+
+    /// Register under the `name`, which must not contain line breaks.
+    ///
+    /// The connection to the agent must be made from the interface the peer listens on, so that
+    /// the address the agent observes is the one others can reach the peer at. The registration
+    /// is kept as long as the returned [`Registration`] is alive.
+    pub async fn register(&self, name: &str) -> io::Result<Registration> {
+        let (mut lines, writer) = self.request(format!("REGISTER {name}\n")).await?;
+        let outer = match lines
+            .next_line()
+            .await?
+            .as_deref()
+            .map(|l| l.split_once(' '))
+        {
+            Some(Some(("OUTER", outer))) => parse_addr(outer)?,
+            _ => return Err(invalid_reply()),
+        };
+        Ok(Registration {
+            outer,
+            lines,
+            _writer: writer,
+        })
+    }
+
+    /// Look up the peer registered under the `name`.
+    ///
+    /// The agent tells the peer to punch towards this side before replying, connect to the
+    /// returned [`SocketEndpointAddr::Agent`] endpoint soon after, the packets are sent to the
+    /// outer address of the peer, see [`SocketEndpointAddr::addr`].
+    pub async fn lookup(&self, name: &str) -> io::Result<SocketEndpointAddr> {
+        let (mut lines, mut writer) = self.request(format!("CONNECT {name}\n")).await?;
+        writer.shutdown().await?;
+        match lines
+            .next_line()
+            .await?
+            .as_deref()
+            .map(|l| l.split_once(' '))
+        {
+            Some(Some(("PEER", outer))) => Ok(SocketEndpointAddr::with_agent(
+                self.agent,
+                parse_addr(outer)?,
+            )),
+            Some(None) => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Peer {name} is not registered"),
+            )),
+            _ => Err(invalid_reply()),
+        }
+    }
+
+    /// Punch towards `dst` from the interface bound on `bind_uri`, by sending a datagram which
+    /// is not a QUIC packet, the peer drops it once it gets through.
+    ///
+    /// This opens the mapping of the local NAT for `dst`, so that the packets `dst` sends to the
+    /// outer address of the interface get through.
+    pub async fn punch(bind_uri: &BindUri, dst: SocketAddr) -> io::Result<()> {
+        let interface = QuicInterfaces::global().get(bind_uri).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Interface {bind_uri} is not bound"),
+            )
+        })?;
+        let local = interface.real_addr()?;
+        let pathway = Pathway::new(local.clone().into(), dst.into());
+        let link = Link::new(local, dst.into());
+        // 首字节不带固定位，接收方解析失败后直接丢弃
+        let probe = [0u8; 1];
+        let hdr = PacketHeader::new(pathway, link, 64, None, probe.len() as u16);
+        interface.sendmmsg(&[io::IoSlice::new(&probe)], hdr).await
+    }
+}
+
+/// The registration of a peer on a [`RendezvousAgent`].
+#[derive(Debug)]
+pub struct Registration {
+    outer: SocketAddr,
+    lines: Lines<BufReader<StreamReader>>,
+    _writer: StreamWriter,
+}
+
+impl Registration {
+    /// Return the outer address the agent observed, others reach this peer at it.
+    pub fn outer(&self) -> SocketAddr {
+        self.outer
+    }
+
+    /// Wait for the next peer looking this one up, and return its outer address to punch
+    /// towards with [`Rendezvous::punch`].
+    ///
+    /// Return `None` if the agent closed the registration.
+    pub async fn next_punch(&mut self) -> io::Result<Option<SocketAddr>> {
+        match self.lines.next_line().await? {
+            Some(line) => match line.split_once(' ') {
+                Some(("PUNCH", requester)) => parse_addr(requester).map(Some),
+                _ => Err(invalid_reply()),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+fn parse_addr(addr: &str) -> io::Result<SocketAddr> {
+    addr.parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn invalid_reply() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Invalid reply from the agent")
+}
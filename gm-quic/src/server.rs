@@ -1,17 +1,32 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fmt::{Debug, Display},
+    future::Future,
     io,
+    net::SocketAddr,
     ops::Deref,
-    sync::{Arc, RwLock, Weak},
+    sync::{Arc, Mutex, RwLock, Weak},
     time::Duration,
 };
 
 use dashmap::DashMap;
-use qbase::util::BoundQueue;
+use qbase::{
+    frame::FrameType,
+    packet::{RetryHeader, header::io::WriteHeader},
+    util::{self, BoundQueue},
+};
 use qconnection::{builder::*, prelude::handy::ConsistentConcurrency};
-use qevent::telemetry::{Log, handy::NoopLogger};
+use qevent::{
+    GroupID, VantagePointType,
+    quic::{
+        PacketHeaderBuilder,
+        transport::{PacketDropped, PacketDroppedTrigger},
+    },
+    telemetry::{Log, Span, handy::NoopLogger},
+};
 use qinterface::{
+    QuicIoExt,
     factory::ProductQuicIO,
     iface::{BindInterface, QuicInterfaces},
     route::{Router, Way},
@@ -22,9 +37,16 @@ use rustls::{
     server::{NoClientAuth, ResolvesServerCert, danger::ClientCertVerifier},
     sign::SigningKey,
 };
+#[cfg(unix)]
+use tokio::net::UnixStream;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-use crate::*;
+#[cfg(unix)]
+use crate::handover;
+use crate::{
+    admission::{Admission, AdmissionRefused},
+    *,
+};
 
 /// Errors that can occur during server management operations.
 #[derive(Debug, thiserror::Error)]
@@ -173,6 +195,58 @@ type Incomings = BoundQueue<(
     OwnedSemaphorePermit,
 )>;
 
+/// Options for [`QuicListeners::shutdown_gracefully`].
+///
+/// By default, new connections are ignored, live connections are given 30 seconds to finish their
+/// streams, and they are closed with application error code 0 and an empty reason.
+#[derive(Debug, Clone)]
+pub struct GracefulShutdown {
+    timeout: Duration,
+    refuse_new_connections: bool,
+    error_code: u64,
+    reason: Cow<'static, str>,
+}
+
+impl Default for GracefulShutdown {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            refuse_new_connections: false,
+            error_code: 0,
+            reason: Cow::Borrowed(""),
+        }
+    }
+}
+
+impl GracefulShutdown {
+    /// Specify how long live connections are given to finish their streams.
+    ///
+    /// Connections that still have active streams when the timeout elapses are closed forcibly.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Specify whether new connections are answered with `CONNECTION_REFUSED` while draining.
+    ///
+    /// If disabled, Initial packets of new connections are silently ignored.
+    pub fn with_refuse_new_connections(mut self, enabled: bool) -> Self {
+        self.refuse_new_connections = enabled;
+        self
+    }
+
+    /// Specify the application error code and reason used to close the live connections.
+    pub fn with_application_close(
+        mut self,
+        error_code: u64,
+        reason: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.error_code = error_code;
+        self.reason = reason.into();
+        self
+    }
+}
+
 /// A QUIC listener that can serve multiple virtual servers, accepting incoming connections.
 ///
 /// ## Creating Listeners
@@ -182,6 +256,7 @@ type Incomings = BoundQueue<(
 ///
 /// **Note**: Only one [`QuicListeners`] instance can run at a time globally.
 /// To stop the listeners, call [`QuicListeners::shutdown`] or drop all references to the [`Arc<QuicListeners>`].
+/// To wait for the live connections to finish, call [`QuicListeners::shutdown_gracefully`].
 ///
 /// ## Managing Servers
 ///
@@ -204,6 +279,13 @@ pub struct QuicListeners {
     backlog: Arc<Semaphore>,
     #[allow(clippy::type_complexity)]
     incomings: Arc<Incomings>,
+    // all connections created by the listeners, keyed by the dcid of their first Initial packets
+    connections: Arc<DashMap<ConnectionId, Weak<Connection>>>,
+    // set once draining, whether new connections are refused
+    draining: Arc<util::Future<bool>>,
+    // forwards the packets not accepted to the other process of a handover
+    #[cfg(unix)]
+    forwarder: Mutex<Option<handover::Forwarder>>,
 
     token_provider: Arc<dyn TokenProvider>,
     parameters: ServerParameters,
@@ -212,7 +294,12 @@ pub struct QuicListeners {
     tls_config: TlsServerConfig,
     stream_strategy_factory: Box<dyn ProductStreamsConcurrencyController>,
     defer_idle_timeout: Duration,
+    scheduler_policy: SchedulerPolicy,
+    extension_frames: ExtensionFrames,
+    admission: Arc<Admission>,
     logger: Arc<dyn Log + Send + Sync>,
+    // one trace for all the refused packets, opened on the first refusal
+    refused_trace: OnceLock<Span>,
     _supported_versions: Vec<u32>,
 }
 
@@ -254,7 +341,7 @@ impl QuicListeners {
 
         Ok(QuicListenersBuilder {
             incomings,
-            quic_iface_factory: Arc::new(handy::DEFAULT_QUIC_IO_FACTORY),
+            quic_iface_factory: Arc::new(handy::SchemeQuicIoFactory),
             servers: Arc::default(),
             token_provider: None,
             parameters: handy::server_parameters(),
@@ -263,7 +350,12 @@ impl QuicListeners {
             tls_config,
             stream_strategy_factory: Box::new(ConsistentConcurrency::new),
             defer_idle_timeout: Duration::ZERO,
+            scheduler_policy: SchedulerPolicy::default(),
+            extension_frames: ExtensionFrames::default(),
+            admission_policy: AdmissionPolicy::default(),
             logger: None,
+            #[cfg(unix)]
+            handover: None,
             _supported_versions: vec![],
         })
     }
@@ -465,15 +557,146 @@ impl QuicListeners {
     ///
     /// Unaccepted connections will be closed
     pub fn shutdown(&self) {
+        Self::stop_listening(&self.incomings, &self.backlog);
+    }
+
+    /// Gracefully shutdown the QuicListeners, wait for the live connections to finish.
+    ///
+    /// The listeners stop accepting new connections immediately, and unaccepted connections will be
+    /// refused. New connections are either ignored or refused with `CONNECTION_REFUSED`, depending
+    /// on [`GracefulShutdown::with_refuse_new_connections`].
+    ///
+    /// Each live connection is closed with the application close specified in the options once all
+    /// of its streams have finished, or forcibly when the timeout elapses. Applications can wait
+    /// [`QuicListeners::draining`] to notify the peers in advance, for example, to send an HTTP/3
+    /// GOAWAY frame.
+    ///
+    /// The returned future resolves when all connections have terminated. It does not need to be
+    /// polled to start the shutdown, but must be polled to close the connections.
+    pub fn shutdown_gracefully(
+        &self,
+        options: GracefulShutdown,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        self.draining.set(options.refuse_new_connections);
+        // keep the connectionless handler to refuse or forward new connections until all
+        // connections end, they are ignored otherwise
         self.incomings.close();
-        self.backlog.close();
 
-        let global = Self::global().read().unwrap();
-        if let Some(global) = global.upgrade() {
-            if global.same_queue(&self.incomings) {
-                Router::global().on_connectless_packets(|_, _| {});
+        let connections = self
+            .connections
+            .iter()
+            .filter_map(|entry| entry.value().upgrade())
+            .collect::<Vec<_>>();
+        let incomings = self.incomings.clone();
+        let backlog = self.backlog.clone();
+
+        async move {
+            let deadline = tokio::time::Instant::now() + options.timeout;
+            let drain_all = connections.iter().map(|connection| {
+                let options = &options;
+                async move {
+                    if tokio::time::timeout_at(deadline, connection.streams_idle())
+                        .await
+                        .is_err()
+                    {
+                        tracing::debug!(
+                            odcid = ?connection.origin_dcid().ok(),
+                            "Streams are not finished before deadline, close the connection forcibly"
+                        );
+                    }
+                    connection.close(options.reason.clone(), options.error_code);
+                    connection.terminated().await;
+                }
+            });
+            futures::future::join_all(drain_all).await;
+            Self::stop_listening(&incomings, &backlog);
+        }
+    }
+
+    /// Hand the sockets and the new connections over to the new process of the server, for a
+    /// zero-downtime restart.
+    ///
+    /// The sockets of the interfaces the servers bound are sent through the `stream`, which is
+    /// connected to the new process that calls [`Handover::receive`]. Interfaces that can not be
+    /// handed over, for example those not backed by sockets, are skipped.
+    ///
+    /// The listeners then shutdown gracefully with the `options`, and the new connections are
+    /// forwarded to the new process instead of being ignored or refused. The live connections are
+    /// still served, and the packets of the new process received on the shared sockets are
+    /// forwarded back to it. Connections whose handshakes are in progress may be disrupted.
+    ///
+    /// Resolves when all connections have terminated, the interfaces handed over are then removed
+    /// and unbound from the servers, and the stream is closed.
+    #[cfg(unix)]
+    pub async fn hand_over(
+        &self,
+        mut stream: UnixStream,
+        options: GracefulShutdown,
+    ) -> io::Result<()> {
+        let bind_uris = self
+            .servers
+            .iter()
+            .flat_map(|server| {
+                (server.bind_ifaces.iter())
+                    .map(|entry| entry.key().clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<std::collections::HashSet<_>>();
+
+        let mut sockets = vec![];
+        for bind_uri in bind_uris {
+            let Some(iface) = self.ifaces.get(&bind_uri) else {
+                continue;
+            };
+            match iface.try_clone_fds() {
+                Ok(fds) => sockets.push((bind_uri, fds)),
+                Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                    tracing::debug!(%bind_uri, "Skip the interface can not be handed over")
+                }
+                Err(e) => return Err(e),
             }
         }
+        let handed_over = sockets
+            .iter()
+            .map(|(bind_uri, _)| bind_uri.clone())
+            .collect::<Vec<_>>();
+        handover::send(&mut stream, sockets).await?;
+
+        // 先设置转发再开始关闭，以免其间的新连接被忽略
+        let forwarder = handover::forward_through(stream, false);
+        *self.forwarder.lock().unwrap() = Some(forwarder);
+        self.shutdown_gracefully(options).await;
+        // 转发器全部释放后转发结束，unix socket随之关闭
+        self.forwarder.lock().unwrap().take();
+
+        for bind_uri in handed_over {
+            // 即使接口仍被引用，也不再从交出的socket接收
+            self.ifaces.remove(&bind_uri);
+            self.unbind_interface(None::<[&str; 0]>, bind_uri);
+        }
+        Ok(())
+    }
+
+    /// Whether the packets not accepted are still forwarded to the other process of a handover.
+    #[cfg(all(test, unix))]
+    pub(crate) fn is_forwarding(&self) -> bool {
+        self.forwarder.lock().unwrap().is_some()
+    }
+
+    /// Returns `true` if [`QuicListeners::shutdown_gracefully`] has been called.
+    pub fn is_draining(&self) -> bool {
+        self.draining.try_get().is_some()
+    }
+
+    /// Wait for the QuicListeners to start draining.
+    ///
+    /// Resolves when [`QuicListeners::shutdown_gracefully`] is called. Applications can use it to
+    /// notify their peers to stop sending new requests, for example, send an HTTP/3 GOAWAY frame.
+    pub fn draining(&self) -> impl Future<Output = ()> + Send + 'static {
+        let draining = self.draining.clone();
+        async move {
+            draining.get().await;
+        }
     }
 }
 
@@ -507,16 +730,45 @@ impl QuicListeners {
         INCOMINGS.get_or_init(Default::default)
     }
 
-    pub(crate) fn try_accept_connection(&self, packet: Packet, (bind_uri, pathway, link): Way) {
-        // Acquire a permit from the backlog semaphore to limit the number of concurrent connections.
-        let Ok(premit) = self.backlog.clone().try_acquire_owned() else {
-            return;
-        };
+    fn stop_listening(incomings: &Arc<Incomings>, backlog: &Semaphore) {
+        incomings.close();
+        backlog.close();
+
+        let global = Self::global().read().unwrap();
+        if let Some(global) = global.upgrade() {
+            if global.same_queue(incomings) {
+                Router::global().on_connectless_packets(|_, _| {});
+            }
+        }
+    }
+
+    /// Handle the packets not routed to any connection.
+    fn on_connectless_packet(&self, packet: Packet, way: Way) {
+        #[cfg(unix)]
+        if let Some(forwarder) = self.forwarder.lock().unwrap().clone() {
+            // 交出后无法路由的包都交给新进程；接手时只接受新连接，其余的属于旧进程的连接
+            if self.is_draining() || !handover::opens_connection(&packet) {
+                return forwarder.forward(packet, way);
+            }
+        }
+        self.try_accept_connection(packet, way)
+    }
 
-        let origin_dcid = match &packet {
+    pub(crate) fn try_accept_connection(&self, packet: Packet, (bind_uri, pathway, link): Way) {
+        let (origin_dcid, client_scid, token, qlog_header) = match &packet {
             Packet::Data(data_packet) => match &data_packet.header {
-                DataHeader::Long(LongHeader::Initial(hdr)) => *hdr.dcid(),
-                DataHeader::Long(LongHeader::ZeroRtt(hdr)) => *hdr.dcid(),
+                DataHeader::Long(LongHeader::Initial(hdr)) => (
+                    *hdr.dcid(),
+                    *hdr.scid(),
+                    Some(hdr.token().as_slice()),
+                    PacketHeaderBuilder::from(hdr).build(),
+                ),
+                DataHeader::Long(LongHeader::ZeroRtt(hdr)) => (
+                    *hdr.dcid(),
+                    *hdr.scid(),
+                    None,
+                    PacketHeaderBuilder::from(hdr).build(),
+                ),
                 _ => return,
             },
             _ => return,
@@ -527,33 +779,148 @@ impl QuicListeners {
             return;
         }
 
+        // 被拒绝的包不配各自的trace：Initial洪泛时那会为每个伪造的DCID新建一个trace
+        let drop_packet = |reason: &str| {
+            let qlog_span = self.refused_trace.get_or_init(|| {
+                self.logger.new_trace(
+                    VantagePointType::Server,
+                    GroupID::from("refused_packets".to_owned()),
+                )
+            });
+            let _span = qlog_span.enter();
+            qevent::event!(
+                PacketDropped {
+                    header: qlog_header.clone(),
+                    trigger: PacketDroppedTrigger::Rejected
+                },
+                details = Map { reason: reason }
+            );
+        };
+
+        // 正在关闭时不再创建新连接，按选项拒绝或忽略，免去为其构建TLS会话的开销
+        if let Some(refuse) = self.draining.try_get().map(|refuse| *refuse) {
+            drop_packet("server is shutting down");
+            if refuse && token.is_some() {
+                let response =
+                    self.refuse_statelessly(&origin_dcid, &client_scid, "server is shutting down");
+                if let Some(response) = response {
+                    self.respond_statelessly(&bind_uri, pathway, link, response);
+                }
+            }
+            return;
+        }
+
+        // Acquire a permit from the backlog semaphore to limit the number of concurrent connections.
+        let Ok(premit) = self.backlog.clone().try_acquire_owned() else {
+            drop_packet("backlog full");
+            return;
+        };
+
+        let client = match link.dst() {
+            RealAddr::Internet(addr) => Some(addr),
+            _ => None,
+        };
+        // 携带有效Retry令牌的Initial包证明了客户端拥有其地址
+        let retried = match (token, client) {
+            (Some(token), Some(client)) if !token.is_empty() => self
+                .admission
+                .validate_retry_token(token, client, &origin_dcid),
+            _ => None,
+        };
+        let admission = match self
+            .admission
+            .try_admit(client.map(|addr| addr.ip()), retried.is_some())
+        {
+            Ok(admission) => admission,
+            Err(refused) => {
+                drop_packet(&refused.to_string());
+                let response = match (self.admission.policy().refused_action(), refused) {
+                    (AdmissionAction::Refuse, _) => self.refuse_statelessly(
+                        &origin_dcid,
+                        &client_scid,
+                        AdmissionRefused::REASON,
+                    ),
+                    (
+                        AdmissionAction::Retry,
+                        AdmissionRefused::PrefixRateExceeded | AdmissionRefused::GlobalRateExceeded,
+                    ) => client
+                        .filter(|_| token.is_some())
+                        .map(|client| self.retry(client, &origin_dcid, &client_scid)),
+                    _ => None,
+                };
+                if let Some(response) = response {
+                    self.respond_statelessly(&bind_uri, pathway, link, response);
+                }
+                return;
+            }
+        };
+
         let server_auther = ServerAuther {
             iface: bind_uri.clone(),
             servers: self.servers.clone(),
         };
 
-        let connection = Arc::new(
-            Connection::new_server(self.token_provider.clone())
-                .with_parameters(self.parameters.clone())
-                .with_anti_port_scan(self.anti_port_scan)
-                .with_client_auther(Box::new((server_auther, self.client_auther.clone())))
-                .with_tls_config(self.tls_config.clone())
-                .with_streams_concurrency_strategy(self.stream_strategy_factory.as_ref())
-                .with_zero_rtt(self.tls_config.max_early_data_size == 0xffffffff)
-                .with_defer_idle_timeout(self.defer_idle_timeout)
-                .with_cids(origin_dcid)
-                .with_qlog(self.logger.clone())
-                .run(),
-        );
+        let foundation = Connection::new_server(self.token_provider.clone())
+            .with_parameters(self.parameters.clone())
+            .with_anti_port_scan(self.anti_port_scan)
+            .with_client_auther(Box::new((server_auther, self.client_auther.clone())))
+            .with_tls_config(self.tls_config.clone())
+            .with_streams_concurrency_strategy(self.stream_strategy_factory.as_ref())
+            .with_zero_rtt(self.tls_config.max_early_data_size == 0xffffffff)
+            .with_defer_idle_timeout(self.defer_idle_timeout)
+            .with_scheduler_policy(self.scheduler_policy)
+            .with_extension_frames(self.extension_frames.clone());
+        let pending = match retried {
+            Some(odcid) => foundation.with_retried_cids(odcid, origin_dcid),
+            None => foundation.with_cids(origin_dcid),
+        };
+        let connection = Arc::new(pending.with_qlog(self.logger.clone()).run());
+
+        let weak_connection = Arc::downgrade(&connection);
+        self.connections
+            .insert(origin_dcid, weak_connection.clone());
+        tokio::spawn({
+            let connections = self.connections.clone();
+            let terminated = connection.terminated();
+            async move {
+                terminated.await;
+                connections.remove_if(&origin_dcid, |_, conn| conn.ptr_eq(&weak_connection));
+            }
+        });
+
+        // 检查之后才开始的优雅关闭可能没有看到这个连接，也就不会等待它，按关闭时的新连接处理
+        if self.draining.try_get().is_some() {
+            let error = QuicError::with_default_fty(
+                ErrorKind::ConnectionRefused,
+                "server is shutting down",
+            );
+            connection.close_with_error(error);
+            return;
+        }
+
+        // the permit is held until the handshake is done or the connection is closed
+        let handshaked = connection.handshaked();
+        tokio::spawn(async move {
+            handshaked.await;
+            drop(admission);
+        });
 
         let incomings = self.incomings.clone();
+        let draining = self.draining.clone();
 
         tokio::spawn(async move {
             Router::global()
-                .deliver(packet, (bind_uri.clone(), pathway, link))
+                .deliver(packet, (bind_uri.clone(), pathway.clone(), link.clone()))
                 .await;
 
             match connection.server_name().await {
+                Ok(_) if draining.try_get().is_some() => {
+                    let error = QuicError::with_default_fty(
+                        ErrorKind::ConnectionRefused,
+                        "server is shutting down",
+                    );
+                    connection.close_with_error(error);
+                }
                 Ok(server_name) => {
                     let incoming = (connection.clone(), server_name, pathway, link);
                     if incomings.send((incoming, premit)).await.is_err() {
@@ -572,6 +939,67 @@ impl QuicListeners {
             }
         });
     }
+
+    /// Assemble an Initial packet closing the connection with `CONNECTION_REFUSED` and the
+    /// `reason`, which the client initiates from the `client_scid` to the `origin_dcid`.
+    fn refuse_statelessly(
+        &self,
+        origin_dcid: &ConnectionId,
+        client_scid: &ConnectionId,
+        reason: &'static str,
+    ) -> Option<Vec<u8>> {
+        let ccf = ConnectionCloseFrame::new_quic(
+            ErrorKind::ConnectionRefused,
+            FrameType::Padding.into(),
+            reason,
+        );
+        let mut datagram = vec![0; 1200];
+        let size = assemble_stateless_close(
+            self.tls_config.crypto_provider(),
+            (origin_dcid, client_scid),
+            &ccf,
+            &mut datagram,
+        )?;
+        datagram.truncate(size);
+        Some(datagram)
+    }
+
+    /// Assemble a Retry packet answering the Initial packet the `client` sent from the
+    /// `client_scid` to the `origin_dcid`.
+    fn retry(
+        &self,
+        client: SocketAddr,
+        origin_dcid: &ConnectionId,
+        client_scid: &ConnectionId,
+    ) -> Vec<u8> {
+        let retry_scid = ConnectionId::random_gen(8);
+        let token = self
+            .admission
+            .issue_retry_token(client, origin_dcid, &retry_scid);
+        let retry = RetryHeader::new_retry(*client_scid, retry_scid, token, origin_dcid);
+        let mut datagram = Vec::new();
+        datagram.put_header(&retry);
+        datagram
+    }
+
+    /// Send the `datagram` back on the way the new connection came in, without creating it.
+    fn respond_statelessly(
+        &self,
+        bind_uri: &BindUri,
+        pathway: Pathway,
+        link: Link,
+        datagram: Vec<u8>,
+    ) {
+        let Some(iface) = self.ifaces.get(bind_uri) else {
+            return;
+        };
+        tokio::spawn(async move {
+            let hdr = PacketHeader::new(pathway, link.clone(), 64, None, datagram.len() as u16);
+            if let Err(error) = iface.sendmmsg(&[io::IoSlice::new(&datagram)], hdr).await {
+                tracing::debug!(%link, "Failed to respond to the new connection: {error}");
+            }
+        });
+    }
 }
 
 /// The builder for the quic listeners.
@@ -587,7 +1015,12 @@ pub struct QuicListenersBuilder<T> {
     tls_config: T,
     stream_strategy_factory: Box<dyn ProductStreamsConcurrencyController>,
     defer_idle_timeout: Duration,
+    scheduler_policy: SchedulerPolicy,
+    extension_frames: ExtensionFrames,
+    admission_policy: AdmissionPolicy,
     logger: Option<Arc<dyn Log + Send + Sync>>,
+    #[cfg(unix)]
+    handover: Option<Handover>,
     _supported_versions: Vec<u32>,
 }
 
@@ -639,6 +1072,42 @@ impl<T> QuicListenersBuilder<T> {
         self
     }
 
+    /// Specify how the data of the server connections is scheduled over their paths.
+    ///
+    /// It only matters when a connection has more than one validated path,
+    /// for example when multipath is negotiated with [`ParameterId::InitialMaxPathId`].
+    ///
+    /// Default to [`SchedulerPolicy::Greedy`], every path sends data as soon as it is ready.
+    pub fn scheduler_policy(mut self, policy: SchedulerPolicy) -> Self {
+        self.scheduler_policy = policy;
+        self
+    }
+
+    /// Register the extension frames that the server connections understand.
+    ///
+    /// The received frames of the registered types are given to the handlers of their
+    /// [`ExtensionFrameDef`], and frames are sent with [`Connection::send_extension_frame`].
+    /// Both endpoints must register the frame types, usually the extension is negotiated with a
+    /// custom transport parameter, see [`Connection::peer_custom_parameter`].
+    ///
+    /// Default to no extension frames.
+    pub fn with_extension_frames(mut self, extension_frames: ExtensionFrames) -> Self {
+        self.extension_frames = extension_frames;
+        self
+    }
+
+    /// Specify the policies deciding whether a new connection is admitted.
+    ///
+    /// Connections can be limited by the concurrent handshakes and the new connection rate of the
+    /// source address prefix, and by the global handshake rate. Connections which are not admitted
+    /// are dropped or refused, see [`AdmissionPolicy`] for more.
+    ///
+    /// Default to no limits.
+    pub fn with_admission_policy(mut self, policy: AdmissionPolicy) -> Self {
+        self.admission_policy = policy;
+        self
+    }
+
     /// Specify the [transport parameters] for the server connections.
     ///
     /// If you call this multiple times, only the last `parameters` will be used.
@@ -655,9 +1124,10 @@ impl<T> QuicListenersBuilder<T> {
     ///
     /// If you call this multiple times, only the last `factory` will be used.
     ///
-    /// The default quic interface is provided by [`handy::DEFAULT_QUIC_IO_FACTORY`].
-    /// For Unix and Windows targets, this is a high performance UDP library supporting GSO and GRO
-    /// provided by `qudp` crate. For other platforms, please specify you own factory.
+    /// The default quic interface is provided by [`handy::SchemeQuicIoFactory`], which binds
+    /// `unix` scheme interfaces on unix domain sockets, and the others with
+    /// [`handy::DEFAULT_QUIC_IO_FACTORY`]. For Unix and Windows targets, this is a high performance
+    /// UDP library supporting GSO and GRO provided by `qudp` crate. For other platforms, please specify you own factory.
     pub fn with_iface_factory(self, factory: impl ProductQuicIO + 'static) -> Self {
         Self {
             quic_iface_factory: Arc::new(factory),
@@ -665,6 +1135,27 @@ impl<T> QuicListenersBuilder<T> {
         }
     }
 
+    /// Take over the sockets and the new connections from the old process of the server.
+    ///
+    /// The interfaces whose [`BindUri`]s are in [`Handover::bind_uris`] are bound on the sockets
+    /// handed over, other interfaces are bound by the factory as usual. Add the servers on the same
+    /// [`BindUri`]s as the old process after [`listen`] to keep serving on the same addresses.
+    ///
+    /// The listeners accept the new connections, and forward the other packets that can not be
+    /// routed to the old process, until the old process closes the unix socket after its
+    /// connections drained. See [`QuicListeners::hand_over`] for the old side.
+    ///
+    /// [`listen`] must be called in a tokio runtime if a handover is specified.
+    ///
+    /// [`listen`]: QuicListenersBuilder::listen
+    #[cfg(unix)]
+    pub fn with_handover(self, handover: Handover) -> Self {
+        Self {
+            handover: Some(handover),
+            ..self
+        }
+    }
+
     /// Specify qlog collector for server connections.
     ///
     /// If you call this multiple times, only the last `logger` will be used.
@@ -674,14 +1165,34 @@ impl<T> QuicListenersBuilder<T> {
     ///   - `LegacySeqLogger::new(PathBuf::from("/dir"))`: Write to files `{connection_id}_{role}.sqlog` in `dir`
     ///   - `LegacySeqLogger::new(tokio::io::stdout())`: Stream to stdout
     ///   - `LegacySeqLogger::new(tokio::io::stderr())`: Stream to stderr
+    ///   - `LegacySeqLogger::new(RotatingStorage::new("/dir"))`: Write to files in `dir`, within a size
+    ///     budget and a file count, optionally compressed, see [`RotatingStorage`]
     ///
     ///   Output format: JSON-SEQ ([RFC7464]), one JSON event per line.
     ///
+    /// - [`SeqLogger`]: Same as [`LegacySeqLogger`], but writes the qlog main schema, events that have no
+    ///   legacy form are kept.
+    ///
+    ///   Both can be given an [`EventFilter`] with `with_filter`, which selects the events by
+    ///   importance level and name, and turns on the capture of raw packets.
+    ///
+    /// - [`MetricsLogger`]: Aggregates packets, losses, RTT, congestion window and closures of all
+    ///   connections into counters and histograms, rendered in the Prometheus text format on demand.
+    ///
+    /// - [`SampledLogger`]: Passes only some connections to another logger: a share of them, those to
+    ///   some server names or from some peer addresses, or those closed with an error. Recording
+    ///   can also be started and stopped on a live connection with [`Connection::start_qlog`].
+    ///
     /// - [`NoopLogger`]: Ignores all qlog events (default, recommended for production).
     ///
     /// [qvis]: https://qvis.quictools.info/
     /// [RFC7464]: https://www.rfc-editor.org/rfc/rfc7464
     /// [`LegacySeqLogger`]: qevent::telemetry::handy::LegacySeqLogger
+    /// [`SeqLogger`]: qevent::telemetry::handy::SeqLogger
+    /// [`EventFilter`]: qevent::telemetry::filter::EventFilter
+    /// [`MetricsLogger`]: qevent::telemetry::metrics::MetricsLogger
+    /// [`RotatingStorage`]: qevent::telemetry::rotating::RotatingStorage
+    /// [`SampledLogger`]: qevent::telemetry::sampling::SampledLogger
     pub fn with_qlog(mut self, logger: Arc<dyn Log + Send + Sync>) -> Self {
         self.logger = Some(logger);
         self
@@ -774,7 +1285,12 @@ impl QuicListenersBuilder<TlsServerConfigBuilder<WantsVerifier>> {
                 .with_cert_resolver(Arc::new(VirtualHosts(self.servers))),
             stream_strategy_factory: self.stream_strategy_factory,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
+            admission_policy: self.admission_policy,
             logger: self.logger,
+            #[cfg(unix)]
+            handover: self.handover,
             _supported_versions: self._supported_versions,
         }
     }
@@ -795,7 +1311,12 @@ impl QuicListenersBuilder<TlsServerConfigBuilder<WantsVerifier>> {
                 .with_cert_resolver(Arc::new(VirtualHosts(self.servers))),
             stream_strategy_factory: self.stream_strategy_factory,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
+            admission_policy: self.admission_policy,
             logger: self.logger,
+            #[cfg(unix)]
+            handover: self.handover,
             _supported_versions: self._supported_versions,
         }
     }
@@ -839,12 +1360,28 @@ impl QuicListenersBuilder<TlsServerConfig> {
         assert!(backlog > 0, "backlog must be greater than 0");
         debug_assert!(self.servers.is_empty());
 
+        #[cfg(unix)]
+        let (quic_iface_factory, forwarder) = match self.handover {
+            Some(handover) => {
+                let (factory, stream) = handover.into_parts(self.quic_iface_factory);
+                let forwarder = handover::forward_through(stream, true);
+                (Arc::new(factory) as Arc<dyn ProductQuicIO>, Some(forwarder))
+            }
+            None => (self.quic_iface_factory, None),
+        };
+        #[cfg(not(unix))]
+        let quic_iface_factory = self.quic_iface_factory;
+
         let quic_listeners = Arc::new(QuicListeners {
-            quic_iface_factory: self.quic_iface_factory,
+            quic_iface_factory,
             ifaces: QuicInterfaces::global().clone(),
             servers: self.servers,
             backlog: Arc::new(Semaphore::new(backlog)),
             incomings: self.incomings, // size: any number greater than 0
+            connections: Arc::default(),
+            draining: Arc::default(),
+            #[cfg(unix)]
+            forwarder: Mutex::new(forwarder),
             token_provider: self
                 .token_provider
                 .unwrap_or_else(|| Arc::new(handy::NoopTokenRegistry)),
@@ -854,15 +1391,36 @@ impl QuicListenersBuilder<TlsServerConfig> {
             tls_config: self.tls_config,
             stream_strategy_factory: self.stream_strategy_factory,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
+            admission: Admission::new(self.admission_policy),
             logger: self.logger.unwrap_or_else(|| Arc::new(NoopLogger)),
+            refused_trace: OnceLock::new(),
             _supported_versions: self._supported_versions,
         });
 
         Router::global().on_connectless_packets({
             let quic_listeners = quic_listeners.clone();
-            move |packet, way| quic_listeners.try_accept_connection(packet, way)
+            move |packet, way| quic_listeners.on_connectless_packet(packet, way)
         });
 
+        #[cfg(unix)]
+        let forwarder = quic_listeners.forwarder.lock().unwrap().clone();
+        #[cfg(unix)]
+        if let Some(forwarder) = forwarder {
+            // 旧进程退出后转发结束，此后无法路由的包按常规处理
+            let quic_listeners = Arc::downgrade(&quic_listeners);
+            tokio::spawn(async move {
+                forwarder.closed().await;
+                if let Some(quic_listeners) = quic_listeners.upgrade() {
+                    let mut current = quic_listeners.forwarder.lock().unwrap();
+                    if current.as_ref().is_some_and(|f| f.same_channel(&forwarder)) {
+                        current.take();
+                    }
+                }
+            });
+        }
+
         quic_listeners
     }
 }
@@ -1,6 +1,10 @@
-use std::{future::Future, net::SocketAddr, sync::Arc, time::Duration};
+use std::{collections::HashSet, future::Future, net::SocketAddr, sync::Arc, time::Duration};
 
-use qevent::telemetry::{Log, handy::*};
+use qevent::{
+    Event, GroupID, VantagePointType,
+    telemetry::{ExportEvent, Log, Span, handy::*},
+};
+use qinterface::iface::QuicInterfaces;
 use rustls::server::WebPkiClientVerifier;
 use tokio::{
     io::{self, AsyncReadExt, AsyncWriteExt},
@@ -16,11 +20,62 @@ use x509_parser::prelude::FromDer;
 
 use crate::{handy::*, *};
 
+mod extensions;
+mod handover;
+mod listeners;
+mod migration;
+mod qlog;
+mod streams;
+mod transports;
+mod traversal;
+
 fn qlogger() -> Arc<dyn Log + Send + Sync> {
     static QLOGGER: OnceLock<Arc<dyn Log + Send + Sync>> = OnceLock::new();
     QLOGGER.get_or_init(|| Arc::new(NoopLogger)).clone()
 }
 
+/// Keeps the qlog events of an endpoint as JSON, for the tests to check what was sent or received.
+#[derive(Default, Clone)]
+struct QlogRecorder(Arc<std::sync::Mutex<Vec<serde_json::Value>>>);
+
+impl QlogRecorder {
+    /// The events named `name`, such as `quic:packet_sent`, that carry a frame of `frame_type`.
+    fn frame_events(&self, name: &str, frame_type: &str) -> Vec<serde_json::Value> {
+        let events = self.0.lock().unwrap();
+        events
+            .iter()
+            .filter(|event| event["name"] == name)
+            .filter(|event| {
+                event["data"]["frames"]
+                    .as_array()
+                    .is_some_and(|frames| frames.iter().any(|f| f["frame_type"] == frame_type))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The paths on which the events named `name` carried a frame of `frame_type`.
+    fn frame_paths(&self, name: &str, frame_type: &str) -> HashSet<String> {
+        self.frame_events(name, frame_type)
+            .iter()
+            .filter_map(|event| event["path"].as_str().map(str::to_owned))
+            .collect()
+    }
+}
+
+impl Log for QlogRecorder {
+    fn new_trace(&self, _: VantagePointType, group_id: GroupID) -> Span {
+        qevent::span!(Arc::new(self.clone()), group_id = group_id)
+    }
+}
+
+impl ExportEvent for QlogRecorder {
+    fn emit(&self, event: Event) {
+        let event = serde_json::to_value(event).expect("event is serializable");
+        self.0.lock().unwrap().push(event);
+    }
+}
+
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 
 pub fn test_serially<C, Sl, St>(
@@ -31,6 +86,21 @@ where
     C: Future<Output = Result<(), Error>> + 'static,
     Sl: Future<Output = Result<(Arc<QuicListeners>, St), Error>> + Send + 'static,
     St: Future<Output: Send> + Send + 'static,
+{
+    test_serially_at(launch_server, launch_client)
+}
+
+/// Like [`test_serially`], but the client gets the server address as `A`, for the servers that
+/// are not bound on internet interfaces.
+pub fn test_serially_at<A, C, Sl, St>(
+    launch_server: impl FnOnce() -> Sl,
+    launch_client: impl FnOnce(A) -> C,
+) -> Result<(), Error>
+where
+    A: TryFrom<RealAddr, Error: std::fmt::Debug>,
+    C: Future<Output = Result<(), Error>> + 'static,
+    Sl: Future<Output = Result<(Arc<QuicListeners>, St), Error>> + Send + 'static,
+    St: Future<Output: Send> + Send + 'static,
 {
     static SUBSCRIBER: OnceLock<WorkerGuard> = OnceLock::new();
 
@@ -71,7 +141,7 @@ where
             .borrow()?
             .real_addr()?
             .try_into()
-            .expect("This test support only SocketAddr");
+            .expect("The server address is not supported by this test");
 
         let result = time::timeout(Duration::from_secs(30), launch_client(server_addr)).await;
 
@@ -135,11 +205,18 @@ async fn send_and_verify_echo(connection: &Connection, data: &[u8]) -> Result<()
 
 async fn launch_echo_server(
     parameters: ServerParameters,
+) -> Result<(Arc<QuicListeners>, impl Future<Output: Send>), Error> {
+    launch_echo_server_with_qlog(parameters, qlogger()).await
+}
+
+async fn launch_echo_server_with_qlog(
+    parameters: ServerParameters,
+    qlog: impl Log + Send + Sync + 'static,
 ) -> Result<(Arc<QuicListeners>, impl Future<Output: Send>), Error> {
     let listeners = QuicListeners::builder()?
         .without_client_cert_verifier()
         .with_parameters(parameters)
-        .with_qlog(qlogger())
+        .with_qlog(Arc::new(qlog))
         .listen(128);
     listeners.add_server(
         "localhost",
@@ -152,13 +229,20 @@ async fn launch_echo_server(
 }
 
 fn launch_test_client(parameters: ClientParameters) -> Arc<QuicClient> {
+    launch_test_client_with_qlog(parameters, qlogger())
+}
+
+fn launch_test_client_with_qlog(
+    parameters: ClientParameters,
+    qlog: impl Log + Send + Sync + 'static,
+) -> Arc<QuicClient> {
     let mut roots = rustls::RootCertStore::empty();
     roots.add_parsable_certificates(CA_CERT.to_certificate());
     let client = QuicClient::builder()
         .with_root_certificates(roots)
         .with_parameters(parameters)
         .without_cert()
-        .with_qlog(qlogger())
+        .with_qlog(Arc::new(qlog))
         .enable_sslkeylog()
         .build();
 
@@ -0,0 +1,125 @@
+use super::*;
+
+#[test]
+fn extension_frames() -> Result<(), Error> {
+    use bytes::Bytes;
+    use qbase::varint::be_varint;
+
+    // ECHO frame: a length-prefixed body, sent back by the server
+    let echo = ExtensionFrameType::new(VarInt::from_u32(0x2ab2));
+    let echo_body_len = |body: &[u8]| {
+        let (remain, len) = be_varint(body).ok()?;
+        Some(body.len() - remain.len() + len.into_inner() as usize)
+    };
+    let negotiate = VarInt::from_u32(0x2ab3);
+
+    let launch_server = move || async move {
+        let mut parameters = server_parameters();
+        parameters.set_custom(negotiate, Bytes::from_static(b"server"))?;
+        let echo_frames = ExtensionFrames::new().with_frame(
+            ExtensionFrameDef::new(echo, echo_body_len).with_handler(move |frame, reply| {
+                reply(ExtensionFrame::new(echo, frame.body().clone()));
+                Ok(())
+            }),
+        );
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(parameters)
+            .with_extension_frames(echo_frames)
+            .with_qlog(qlogger())
+            .listen(128);
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port()],
+            None,
+        )?;
+        Ok((listeners.clone(), serve_echo(listeners)))
+    };
+    let launch_client = move |server_addr| async move {
+        let (echoed, mut echoes) = tokio::sync::mpsc::unbounded_channel();
+        let echo_frames = ExtensionFrames::new().with_frame(
+            ExtensionFrameDef::new(echo, echo_body_len).with_handler(move |frame, _reply| {
+                _ = echoed.send(frame.body().clone());
+                Ok(())
+            }),
+        );
+        let mut parameters = client_parameters();
+        parameters.set_custom(negotiate, Bytes::from_static(b"client"))?;
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_root_certificates(roots)
+            .with_parameters(parameters)
+            .without_cert()
+            .with_extension_frames(echo_frames)
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+        assert_eq!(
+            connection.peer_custom_parameter(negotiate).await?,
+            Some(Bytes::from_static(b"server"))
+        );
+
+        let body = Bytes::from_static(b"\x05hello");
+        connection.send_extension_frame(ExtensionFrame::new(echo, body.clone()))?;
+        assert_eq!(echoes.recv().await, Some(body));
+        // the connection still works
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
+
+/// Both endpoints support the ACK_FREQUENCY extension, and accept an acknowledgement delay down to
+/// 1ms.
+fn with_min_ack_delay<R: qbase::role::IntoRole + Default>(
+    mut params: qbase::param::core::Parameters<R>,
+) -> qbase::param::core::Parameters<R> {
+    params
+        .set(ParameterId::MinAckDelay, VarInt::from_u32(1000))
+        .expect("unreachable");
+    params
+}
+
+#[test]
+fn ack_frequency() -> Result<(), Error> {
+    let server_qlog = QlogRecorder::default();
+    let client_qlog = QlogRecorder::default();
+
+    let launch_server = {
+        let server_qlog = server_qlog.clone();
+        || launch_echo_server_with_qlog(with_min_ack_delay(server_parameters()), server_qlog)
+    };
+    let launch_client = {
+        let client_qlog = client_qlog.clone();
+        |server_addr| async move {
+            let client =
+                launch_test_client_with_qlog(with_min_ack_delay(client_parameters()), client_qlog);
+            let connection = client.connect("localhost", [server_addr])?;
+            // both endpoints ask the peer for sparser acknowledgements as the window grows
+            send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(64)).await?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+
+            Ok(())
+        }
+    };
+    test_serially(launch_server, launch_client)?;
+
+    for (sender, receiver) in [(&client_qlog, &server_qlog), (&server_qlog, &client_qlog)] {
+        assert!(
+            !sender
+                .frame_events("quic:packet_sent", "ack_frequency")
+                .is_empty()
+        );
+        assert!(
+            !receiver
+                .frame_events("quic:packet_received", "ack_frequency")
+                .is_empty()
+        );
+    }
+    Ok(())
+}
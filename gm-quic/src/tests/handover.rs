@@ -0,0 +1,148 @@
+use super::*;
+
+#[test]
+#[cfg(unix)]
+fn handover() -> Result<(), Error> {
+    let mut path = std::env::temp_dir();
+    path.push(format!("gm-quic-handover-{}.sock", std::process::id()));
+    _ = std::fs::remove_file(&path);
+
+    let running_listeners = Arc::new(OnceLock::new());
+    let launch_server = {
+        let running_listeners = running_listeners.clone();
+        || async move {
+            let (listeners, serve) = launch_echo_server(server_parameters()).await?;
+            _ = running_listeners.set(listeners.clone());
+            Ok((listeners, serve))
+        }
+    };
+    let launch_client = |server_addr| async move {
+        let listeners: Arc<QuicListeners> = running_listeners.get().cloned().unwrap();
+        let client = launch_test_client(client_parameters());
+        let old_connection = client.connect("localhost", [server_addr])?;
+        let (_sid, (mut reader, mut writer)) = old_connection.open_bi_stream().await?.unwrap();
+        let (first_half, second_half) = TEST_DATA.split_at(TEST_DATA.len() / 2);
+        writer.write_all(first_half).await?;
+        writer.flush().await?;
+
+        // 新进程即本测试程序，只运行handover_successor
+        let unix_listener = tokio::net::UnixListener::bind(&path)?;
+        let mut successor = std::process::Command::new(std::env::current_exe()?)
+            .args([
+                "--exact",
+                "tests::handover::handover_successor",
+                "--nocapture",
+            ])
+            .env("GM_QUIC_HANDOVER", &path)
+            .spawn()?;
+        let (stream, _) = unix_listener.accept().await?;
+        _ = std::fs::remove_file(&path);
+
+        let hand_over = tokio::spawn({
+            let listeners = listeners.clone();
+            async move {
+                let options = GracefulShutdown::default().with_timeout(Duration::from_secs(10));
+                listeners.hand_over(stream, options).await
+            }
+        });
+        listeners.draining().await;
+
+        // 新连接由新进程接受
+        let new_connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&new_connection, TEST_DATA).await?;
+        // 旧连接仍由旧进程服务，直到其流结束
+        let mut back = Vec::new();
+        tokio::try_join!(
+            async {
+                writer.write_all(second_half).await?;
+                writer.shutdown().await
+            },
+            reader.read_to_end(&mut back)
+        )?;
+        assert_eq!(back, TEST_DATA);
+
+        hand_over.await??;
+        old_connection.terminated().await;
+        // 旧进程退出后，新进程独自服务
+        send_and_verify_echo(&new_connection, TEST_DATA).await?;
+
+        // 转发随旧进程结束，新进程关闭时按常规拒绝新连接，而非转发给已退出的旧进程
+        let (_sid, (mut reader, mut writer)) = new_connection.open_bi_stream().await?.unwrap();
+        writer.write_all(first_half).await?;
+        writer.flush().await?;
+        let (_sid, mut draining) = new_connection.accept_uni_stream().await?;
+        draining.read_to_end(&mut Vec::new()).await?;
+        let refused = client.connect("localhost", [server_addr])?;
+        let error = refused
+            .open_bi_stream()
+            .await
+            .expect_err("Client should be refused");
+        assert_eq!(error.kind(), ErrorKind::ConnectionRefused);
+
+        let mut back = Vec::new();
+        tokio::try_join!(
+            async {
+                writer.write_all(second_half).await?;
+                writer.shutdown().await
+            },
+            reader.read_to_end(&mut back)
+        )?;
+        assert_eq!(back, TEST_DATA);
+
+        new_connection.close("Bye bye", 0);
+        let status = tokio::task::spawn_blocking(move || successor.wait()).await??;
+        assert!(status.success());
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
+
+/// The new process of the [`handover`] test, does nothing if run alone.
+#[test]
+#[cfg(unix)]
+fn handover_successor() -> Result<(), Error> {
+    let Some(path) = std::env::var_os("GM_QUIC_HANDOVER") else {
+        return Ok(());
+    };
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let successor = async move {
+        let stream = tokio::net::UnixStream::connect(path).await?;
+        let handover = Handover::receive(stream).await?;
+        let bind_uris = handover.bind_uris().to_vec();
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_qlog(qlogger())
+            .with_handover(handover)
+            .listen(128);
+        listeners.add_server("localhost", SERVER_CERT, SERVER_KEY, bind_uris, None)?;
+
+        let (connection, ..) = listeners.accept().await?;
+        tokio::spawn({
+            let connection = connection.clone();
+            async move {
+                while let Ok((_sid, (reader, writer))) = connection.accept_bi_stream().await {
+                    tokio::spawn(echo_stream(reader, writer));
+                }
+            }
+        });
+
+        // 旧进程退出后不再转发，随即关闭并拒绝新连接，开一条单向流告知客户端
+        while listeners.is_forwarding() {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        let shutdown = listeners.shutdown_gracefully(
+            GracefulShutdown::default()
+                .with_timeout(Duration::from_secs(10))
+                .with_refuse_new_connections(true),
+        );
+        let (_sid, mut draining) = connection.open_uni_stream().await?.unwrap();
+        draining.shutdown().await?;
+        shutdown.await;
+        Result::<(), Error>::Ok(())
+    };
+    rt.block_on(async move { time::timeout(Duration::from_secs(30), successor).await? })
+}
@@ -0,0 +1,130 @@
+use super::*;
+
+#[test]
+fn graceful_shutdown() -> Result<(), Error> {
+    let running_listeners = Arc::new(OnceLock::new());
+    let launch_server = {
+        let running_listeners = running_listeners.clone();
+        || async move {
+            let (listeners, serve) = launch_echo_server(server_parameters()).await?;
+            _ = running_listeners.set(listeners.clone());
+            Ok((listeners, serve))
+        }
+    };
+    let launch_client = |server_addr| async move {
+        let listeners: Arc<QuicListeners> = running_listeners.get().cloned().unwrap();
+        let client = launch_test_client(client_parameters());
+        let connection = client.connect("localhost", [server_addr])?;
+        let (_sid, (mut reader, mut writer)) = connection.open_bi_stream().await?.unwrap();
+        let (first_half, second_half) = TEST_DATA.split_at(TEST_DATA.len() / 2);
+        writer.write_all(first_half).await?;
+        writer.flush().await?;
+
+        let shutdown = tokio::spawn(
+            listeners.shutdown_gracefully(
+                GracefulShutdown::default()
+                    .with_timeout(Duration::from_secs(10))
+                    .with_refuse_new_connections(true)
+                    .with_application_close(0, "Bye bye"),
+            ),
+        );
+        assert!(listeners.is_draining());
+        listeners.draining().await;
+
+        // new connections are refused while draining
+        let refused = client.connect("localhost", [server_addr])?;
+        assert!(send_and_verify_echo(&refused, b"").await.is_err());
+
+        // the in-flight stream is still served
+        let mut back = Vec::new();
+        tokio::try_join!(
+            async {
+                writer.write_all(second_half).await?;
+                writer.shutdown().await
+            },
+            reader.read_to_end(&mut back)
+        )?;
+        assert_eq!(back, TEST_DATA);
+
+        shutdown.await?;
+        connection.terminated().await;
+        assert!(!connection.is_active());
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
+
+#[test]
+fn admission_policy() -> Result<(), Error> {
+    let launch_server = || async {
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_admission_policy(
+                AdmissionPolicy::default()
+                    .with_max_handshakes_per_prefix(4)
+                    .with_prefix_rate(0.001, 1)
+                    .with_refused_action(AdmissionAction::Refuse),
+            )
+            .with_qlog(qlogger())
+            .listen(128);
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port()],
+            None,
+        )?;
+        Ok((listeners.clone(), serve_echo(listeners)))
+    };
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+        let admitted = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&admitted, TEST_DATA).await?;
+
+        // the token bucket of 127.0.0.1/32 is exhausted
+        let refused = client.connect("localhost", [server_addr])?;
+        assert!(send_and_verify_echo(&refused, TEST_DATA).await.is_err());
+        assert!(admitted.is_active());
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
+
+#[test]
+fn admission_retry() -> Result<(), Error> {
+    let launch_server = || async {
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_admission_policy(
+                AdmissionPolicy::default()
+                    .with_prefix_rate(0.001, 1)
+                    .with_refused_action(AdmissionAction::Retry),
+            )
+            .with_qlog(qlogger())
+            .listen(128);
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port()],
+            None,
+        )?;
+        Ok((listeners.clone(), serve_echo(listeners)))
+    };
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+        let admitted = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&admitted, TEST_DATA).await?;
+
+        // the token bucket of 127.0.0.1/32 is exhausted, the client proves its address by Retry
+        let retried = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&retried, TEST_DATA).await?;
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
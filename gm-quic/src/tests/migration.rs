@@ -0,0 +1,650 @@
+use qconnection::path::MigrateFailure;
+
+use super::*;
+
+#[test]
+fn active_migration() -> Result<(), Error> {
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        let new_iface = QuicInterfaces::global().bind(
+            BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port(),
+            Arc::new(DEFAULT_QUIC_IO_FACTORY),
+        );
+        connection.migrate(new_iface.bind_uri()).await?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
+
+#[test]
+fn server_cannot_migrate() -> Result<(), Error> {
+    let (refused_tx, refused_rx) = tokio::sync::oneshot::channel();
+    let launch_server = || async move {
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_qlog(qlogger())
+            .listen(128);
+        let bind_uri = BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port();
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [bind_uri.clone()],
+            None,
+        )?;
+        let serve = {
+            let listeners = listeners.clone();
+            async move {
+                let (connection, ..) = listeners.accept().await?;
+                _ = refused_tx.send(connection.migrate(bind_uri).await);
+                while let Ok((_sid, (reader, writer))) = connection.accept_bi_stream().await {
+                    tokio::spawn(echo_stream(reader, writer));
+                }
+                io::Result::Ok(())
+            }
+        };
+        Ok((listeners, serve))
+    };
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        let refused = refused_rx.await?;
+        assert!(matches!(refused, Err(MigrateFailure::NotClient)));
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
+
+#[test]
+fn migration_disabled_by_server() -> Result<(), Error> {
+    let server_parameters = || {
+        let mut params = server_parameters();
+        params
+            .set(
+                ParameterId::DisableActiveMigration,
+                qbase::param::ParameterValue::True,
+            )
+            .expect("unreachable");
+        params
+    };
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        let new_iface = QuicInterfaces::global().bind(
+            BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port(),
+            Arc::new(DEFAULT_QUIC_IO_FACTORY),
+        );
+        let refused = connection.migrate(new_iface.bind_uri()).await;
+        assert!(matches!(
+            refused,
+            Err(MigrateFailure::ActiveMigrationDisabled)
+        ));
+        // the connection stays on the original path
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
+
+#[test]
+fn migrate_before_handshaked() -> Result<(), Error> {
+    let launch_client = |server_addr| async move {
+        // the server certificate can not be verified without the CA, the handshake fails
+        let client = QuicClient::builder()
+            .with_root_certificates(rustls::RootCertStore::empty())
+            .with_parameters(client_parameters())
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+
+        let new_iface = QuicInterfaces::global().bind(
+            BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port(),
+            Arc::new(DEFAULT_QUIC_IO_FACTORY),
+        );
+        let refused = connection.migrate(new_iface.bind_uri()).await;
+        assert!(matches!(refused, Err(MigrateFailure::NotHandshaked)));
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
+
+#[test]
+fn deactivate_superseded_path() -> Result<(), Error> {
+    use std::{
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        task::{Context, Poll, ready},
+    };
+
+    use bytes::BytesMut;
+
+    // 被切断后收发的数据包都被丢弃，模拟接口所在的网络已经断开
+    #[derive(Clone)]
+    struct Blackhole {
+        cut: Arc<AtomicBool>,
+        sent: Arc<AtomicUsize>,
+    }
+
+    struct BlackholeIO {
+        io: Box<dyn QuicIO>,
+        blackhole: Blackhole,
+    }
+
+    impl QuicIO for BlackholeIO {
+        fn bind_uri(&self) -> BindUri {
+            self.io.bind_uri()
+        }
+
+        fn real_addr(&self) -> io::Result<RealAddr> {
+            self.io.real_addr()
+        }
+
+        fn max_segment_size(&self) -> io::Result<usize> {
+            self.io.max_segment_size()
+        }
+
+        fn max_segments(&self) -> io::Result<usize> {
+            self.io.max_segments()
+        }
+
+        fn poll_send(
+            &self,
+            cx: &mut Context,
+            pkts: &[std::io::IoSlice],
+            hdr: PacketHeader,
+        ) -> Poll<io::Result<usize>> {
+            self.blackhole.sent.fetch_add(1, Ordering::SeqCst);
+            match self.blackhole.cut.load(Ordering::SeqCst) {
+                true => Poll::Ready(Ok(pkts.len())),
+                false => self.io.poll_send(cx, pkts, hdr),
+            }
+        }
+
+        fn poll_recv(
+            &self,
+            cx: &mut Context,
+            pkts: &mut [BytesMut],
+            hdrs: &mut [PacketHeader],
+        ) -> Poll<io::Result<usize>> {
+            loop {
+                let rcvd = ready!(self.io.poll_recv(cx, pkts, hdrs))?;
+                if !self.blackhole.cut.load(Ordering::SeqCst) {
+                    return Poll::Ready(Ok(rcvd));
+                }
+            }
+        }
+
+        fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
+            self.io.poll_close(cx)
+        }
+    }
+
+    let launch_client = |server_addr: SocketAddr| async move {
+        let blackhole = Blackhole {
+            cut: Arc::default(),
+            sent: Arc::default(),
+        };
+        let factory = {
+            let blackhole = blackhole.clone();
+            move |bind_uri: BindUri| {
+                Ok(BlackholeIO {
+                    io: DEFAULT_QUIC_IO_FACTORY.bind(bind_uri)?,
+                    blackhole: blackhole.clone(),
+                })
+            }
+        };
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_iface_factory(factory)
+            .bind([BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port()])
+            .with_root_certificates(roots)
+            .with_parameters(client_parameters())
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        let new_iface = QuicInterfaces::global().bind(
+            BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port(),
+            Arc::new(DEFAULT_QUIC_IO_FACTORY),
+        );
+        let new_addr: SocketAddr = new_iface
+            .borrow()?
+            .real_addr()?
+            .try_into()
+            .expect("This test support only SocketAddr");
+        let link = Link::new(new_addr, server_addr).into();
+        let pathway = Pathway::new(new_addr.into(), server_addr.into());
+        connection.add_path(new_iface.bind_uri(), link, pathway)?;
+        send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(16)).await?;
+
+        // the original path stops being acked while the new path still is. Once it sends again,
+        // it is deactivated within a few PTOs, long before it could time out, and the data keeps
+        // flowing on the new path
+        blackhole.cut.store(true, Ordering::SeqCst);
+        let cut_at = blackhole.sent.load(Ordering::SeqCst);
+        let deactivated = async {
+            // probes may pause for a while after backing off, only two quiet rounds in a row count
+            let mut quiet_rounds = 0;
+            while quiet_rounds < 2 {
+                let sent = blackhole.sent.load(Ordering::SeqCst);
+                send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(16)).await?;
+                time::sleep(Duration::from_millis(300)).await;
+                match sent > cut_at && blackhole.sent.load(Ordering::SeqCst) == sent {
+                    true => quiet_rounds += 1,
+                    false => quiet_rounds = 0,
+                }
+            }
+            Result::<(), Error>::Ok(())
+        };
+        time::timeout(Duration::from_secs(10), deactivated).await??;
+        let sent = blackhole.sent.load(Ordering::SeqCst);
+        send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(16)).await?;
+        assert_eq!(blackhole.sent.load(Ordering::SeqCst), sent);
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
+
+/// Both endpoints negotiate the multipath extension, with up to 4 more paths.
+fn with_multipath<R: qbase::role::IntoRole + Default>(
+    mut params: qbase::param::core::Parameters<R>,
+) -> qbase::param::core::Parameters<R> {
+    params
+        .set(ParameterId::InitialMaxPathId, VarInt::from_u32(4))
+        .expect("unreachable");
+    params
+}
+
+#[test]
+fn multipath_migration() -> Result<(), Error> {
+    let client_qlog = QlogRecorder::default();
+    let launch_client = {
+        let client_qlog = client_qlog.clone();
+        |server_addr| async move {
+            let client =
+                launch_test_client_with_qlog(with_multipath(client_parameters()), client_qlog);
+            let connection = client.connect("localhost", [server_addr])?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+
+            // the new path uses a new connection id, so its packets are sent in another
+            // packet number space with the multipath nonce
+            let new_iface = QuicInterfaces::global().bind(
+                BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port(),
+                Arc::new(DEFAULT_QUIC_IO_FACTORY),
+            );
+            connection.migrate(new_iface.bind_uri()).await?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+
+            Ok(())
+        }
+    };
+    test_serially(
+        || launch_echo_server(with_multipath(server_parameters())),
+        launch_client,
+    )?;
+
+    // the data is sent on the initial path, then on the migrated path
+    assert_eq!(
+        client_qlog.frame_paths("quic:packet_sent", "stream").len(),
+        2
+    );
+    Ok(())
+}
+
+#[test]
+fn multipath_del_path() -> Result<(), Error> {
+    let client_qlog = QlogRecorder::default();
+    let launch_client = {
+        let client_qlog = client_qlog.clone();
+        |server_addr: SocketAddr| async move {
+            let client =
+                launch_test_client_with_qlog(with_multipath(client_parameters()), client_qlog);
+            let connection = client.connect("localhost", [server_addr])?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+
+            let new_iface = QuicInterfaces::global().bind(
+                BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port(),
+                Arc::new(DEFAULT_QUIC_IO_FACTORY),
+            );
+            let new_addr: SocketAddr = new_iface
+                .borrow()?
+                .real_addr()?
+                .try_into()
+                .expect("This test support only SocketAddr");
+            let link = Link::new(new_addr, server_addr).into();
+            let pathway = Pathway::new(new_addr.into(), server_addr.into());
+            connection.add_path(new_iface.bind_uri(), link, pathway.clone())?;
+            send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(16)).await?;
+
+            // the server abandons its path on the PATH_ABANDON frame, and keeps echoing on the
+            // remaining path
+            connection.del_path(&pathway)?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+
+            Ok(())
+        }
+    };
+    test_serially(
+        || launch_echo_server(with_multipath(server_parameters())),
+        launch_client,
+    )?;
+
+    assert_eq!(
+        client_qlog.frame_paths("quic:packet_sent", "stream").len(),
+        2
+    );
+    assert!(
+        !client_qlog
+            .frame_events("quic:packet_sent", "path_abandon")
+            .is_empty()
+    );
+    Ok(())
+}
+
+#[test]
+fn multipath_scheduler_policies() -> Result<(), Error> {
+    let launch_client = |server_addr: SocketAddr| async move {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let new_iface = QuicInterfaces::global().bind(
+            BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port(),
+            Arc::new(DEFAULT_QUIC_IO_FACTORY),
+        );
+        let new_addr: SocketAddr = new_iface
+            .borrow()?
+            .real_addr()?
+            .try_into()
+            .expect("This test support only SocketAddr");
+
+        for policy in [
+            SchedulerPolicy::MinRtt,
+            SchedulerPolicy::WeightedRoundRobin,
+            SchedulerPolicy::PrimaryBackup,
+            SchedulerPolicy::Redundant,
+        ] {
+            let client_qlog = QlogRecorder::default();
+            let client = QuicClient::builder()
+                .with_root_certificates(roots.clone())
+                .with_parameters(with_multipath(client_parameters()))
+                .without_cert()
+                .scheduler_policy(policy)
+                .with_qlog(Arc::new(client_qlog.clone()))
+                .build();
+            let connection = client.connect("localhost", [server_addr])?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+
+            // data is scheduled over both paths once the new path is validated
+            let link = Link::new(new_addr, server_addr).into();
+            let pathway = Pathway::new(new_addr.into(), server_addr.into());
+            connection.add_path(new_iface.bind_uri(), link, pathway)?;
+            send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(64)).await?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+
+            // the new path is validated, and used as the policy decides
+            let validated = client_qlog.frame_paths("quic:packet_sent", "path_response");
+            assert!(!validated.is_empty(), "{policy:?}");
+            let stream_paths = client_qlog.frame_paths("quic:packet_sent", "stream");
+            let control_paths = client_qlog.frame_paths("quic:packet_sent", "max_stream_data");
+            match policy {
+                SchedulerPolicy::WeightedRoundRobin => assert_eq!(stream_paths.len(), 2),
+                SchedulerPolicy::PrimaryBackup => assert_eq!(stream_paths.len(), 1),
+                SchedulerPolicy::Redundant => assert_eq!(control_paths.len(), 2),
+                _ => assert!(!stream_paths.is_empty()),
+            }
+        }
+
+        Ok(())
+    };
+    test_serially(
+        || launch_echo_server(with_multipath(server_parameters())),
+        launch_client,
+    )
+}
+
+#[test]
+fn rebind_migration() -> Result<(), Error> {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        task::{Context, Poll},
+    };
+
+    use bytes::BytesMut;
+    use qinterface::iface::monitor::InterfacesMonitor;
+
+    // 被标记为已移动后无法获取地址，接口检查时会重新绑定，模拟接口地址变化
+    struct MovableIO {
+        io: Box<dyn QuicIO>,
+        moved: Arc<AtomicBool>,
+    }
+
+    impl QuicIO for MovableIO {
+        fn bind_uri(&self) -> BindUri {
+            self.io.bind_uri()
+        }
+
+        fn real_addr(&self) -> io::Result<RealAddr> {
+            match self.moved.load(Ordering::SeqCst) {
+                true => Err(io::ErrorKind::AddrNotAvailable.into()),
+                false => self.io.real_addr(),
+            }
+        }
+
+        fn max_segment_size(&self) -> io::Result<usize> {
+            self.io.max_segment_size()
+        }
+
+        fn max_segments(&self) -> io::Result<usize> {
+            self.io.max_segments()
+        }
+
+        fn poll_send(
+            &self,
+            cx: &mut Context,
+            pkts: &[std::io::IoSlice],
+            hdr: PacketHeader,
+        ) -> Poll<io::Result<usize>> {
+            self.io.poll_send(cx, pkts, hdr)
+        }
+
+        fn poll_recv(
+            &self,
+            cx: &mut Context,
+            pkts: &mut [BytesMut],
+            hdrs: &mut [PacketHeader],
+        ) -> Poll<io::Result<usize>> {
+            self.io.poll_recv(cx, pkts, hdrs)
+        }
+
+        fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
+            self.io.poll_close(cx)
+        }
+    }
+
+    let launch_client = |server_addr| async move {
+        let moved = Arc::new(AtomicBool::new(false));
+        let factory = {
+            let moved = moved.clone();
+            let rebound = AtomicBool::new(false);
+            move |bind_uri: BindUri| {
+                Ok(MovableIO {
+                    io: DEFAULT_QUIC_IO_FACTORY.bind(bind_uri)?,
+                    // 只有首次绑定的接口会被移动
+                    moved: match rebound.swap(true, Ordering::SeqCst) {
+                        false => moved.clone(),
+                        true => Arc::default(),
+                    },
+                })
+            }
+        };
+        let bind_uri = BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port();
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_iface_factory(factory)
+            .bind([bind_uri.clone()])
+            .with_root_certificates(roots)
+            .with_parameters(client_parameters())
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+        // 握手确认之前不能迁移
+        assert!(connection.handshaked().await);
+
+        let old_addr = QuicInterfaces::global()
+            .get(&bind_uri)
+            .expect("Interface should be bound")
+            .real_addr()?;
+        moved.store(true, Ordering::SeqCst);
+        InterfacesMonitor::global().on_interface_changed();
+        time::sleep(Duration::from_millis(100)).await;
+        let new_addr = QuicInterfaces::global()
+            .get(&bind_uri)
+            .expect("Interface should be rebound")
+            .real_addr()?;
+        assert_ne!(old_addr, new_addr);
+
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
+
+#[test]
+fn rebind_at_same_address() -> Result<(), Error> {
+    use std::{
+        sync::atomic::{AtomicBool, Ordering},
+        task::{Context, Poll},
+    };
+
+    use bytes::BytesMut;
+    use qinterface::iface::monitor::InterfacesMonitor;
+
+    // 被标记为损坏后接收失败，接收任务停止，接口检查时会在原地址上重新绑定
+    struct BrokenIO {
+        io: Box<dyn QuicIO>,
+        broken: Arc<AtomicBool>,
+    }
+
+    impl QuicIO for BrokenIO {
+        fn bind_uri(&self) -> BindUri {
+            self.io.bind_uri()
+        }
+
+        fn real_addr(&self) -> io::Result<RealAddr> {
+            self.io.real_addr()
+        }
+
+        fn max_segment_size(&self) -> io::Result<usize> {
+            self.io.max_segment_size()
+        }
+
+        fn max_segments(&self) -> io::Result<usize> {
+            self.io.max_segments()
+        }
+
+        fn poll_send(
+            &self,
+            cx: &mut Context,
+            pkts: &[std::io::IoSlice],
+            hdr: PacketHeader,
+        ) -> Poll<io::Result<usize>> {
+            self.io.poll_send(cx, pkts, hdr)
+        }
+
+        fn poll_recv(
+            &self,
+            cx: &mut Context,
+            pkts: &mut [BytesMut],
+            hdrs: &mut [PacketHeader],
+        ) -> Poll<io::Result<usize>> {
+            match self.broken.load(Ordering::SeqCst) {
+                true => Poll::Ready(Err(io::ErrorKind::BrokenPipe.into())),
+                false => self.io.poll_recv(cx, pkts, hdrs),
+            }
+        }
+
+        fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
+            self.io.poll_close(cx)
+        }
+    }
+
+    let launch_client = |server_addr| async move {
+        let broken = Arc::new(AtomicBool::new(false));
+        let factory = {
+            let broken = broken.clone();
+            let rebound = AtomicBool::new(false);
+            move |bind_uri: BindUri| {
+                Ok(BrokenIO {
+                    io: DEFAULT_QUIC_IO_FACTORY.bind(bind_uri)?,
+                    // 只有首次绑定的接口会损坏
+                    broken: match rebound.swap(true, Ordering::SeqCst) {
+                        false => broken.clone(),
+                        true => Arc::default(),
+                    },
+                })
+            }
+        };
+        // 固定端口，重新绑定后地址不变
+        let addr = std::net::UdpSocket::bind("127.0.0.1:0")?.local_addr()?;
+        let bind_uri = BindUri::from(addr);
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_iface_factory(factory)
+            .bind([bind_uri.clone()])
+            .with_root_certificates(roots)
+            .with_parameters(client_parameters())
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        let old_addr = QuicInterfaces::global()
+            .get(&bind_uri)
+            .expect("Interface should be bound")
+            .real_addr()?;
+        broken.store(true, Ordering::SeqCst);
+        time::sleep(Duration::from_millis(100)).await;
+        InterfacesMonitor::global().on_interface_changed();
+        time::sleep(Duration::from_millis(100)).await;
+        let new_addr = QuicInterfaces::global()
+            .get(&bind_uri)
+            .expect("Interface should be rebound")
+            .real_addr()?;
+        assert_eq!(old_addr, new_addr);
+
+        // 原路径换用新的绑定继续收发
+        time::timeout(
+            Duration::from_secs(5),
+            send_and_verify_echo(&connection, TEST_DATA),
+        )
+        .await??;
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
@@ -0,0 +1,70 @@
+use super::*;
+
+#[test]
+fn sampled_qlog() -> Result<(), Error> {
+    use qevent::telemetry::{
+        metrics::MetricsLogger,
+        sampling::{SampledLogger, SamplingPolicy},
+    };
+
+    let server_metrics = MetricsLogger::new();
+    let client_metrics = MetricsLogger::new();
+
+    let launch_server = {
+        let server_metrics = server_metrics.clone();
+        || async move {
+            let policy = SamplingPolicy::new().with_server_name("localhost");
+            let listeners = QuicListeners::builder()?
+                .without_client_cert_verifier()
+                .with_parameters(server_parameters())
+                .with_qlog(Arc::new(SampledLogger::new(server_metrics, policy)))
+                .listen(128);
+            listeners.add_server(
+                "localhost",
+                SERVER_CERT,
+                SERVER_KEY,
+                [BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port()],
+                None,
+            )?;
+            Ok((listeners.clone(), serve_echo(listeners)))
+        }
+    };
+    let launch_client = {
+        let client_metrics = client_metrics.clone();
+        |server_addr| async move {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add_parsable_certificates(CA_CERT.to_certificate());
+            let client = QuicClient::builder()
+                .with_root_certificates(roots)
+                .with_parameters(client_parameters())
+                .without_cert()
+                .with_qlog(Arc::new(SampledLogger::new(
+                    client_metrics,
+                    SamplingPolicy::new(),
+                )))
+                .build();
+
+            let connection = client.connect("localhost", [server_addr])?;
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+            assert!(connection.start_qlog());
+            send_and_verify_echo(&connection, TEST_DATA).await?;
+            assert!(connection.stop_qlog());
+
+            Ok(())
+        }
+    };
+    test_serially(launch_server, launch_client)?;
+
+    let server_metrics = server_metrics.render();
+    assert!(
+        server_metrics.contains("quic_handshakes_completed_total{vantage_point=\"server\"} 1\n")
+    );
+    // Recording started after the handshake
+    let client_metrics = client_metrics.render();
+    assert!(client_metrics.contains("quic_connections_total{vantage_point=\"client\"} 1\n"));
+    assert!(
+        client_metrics.contains("quic_handshakes_completed_total{vantage_point=\"client\"} 0\n")
+    );
+    assert!(!client_metrics.contains("quic_packets_sent_total{vantage_point=\"client\"} 0\n"));
+    Ok(())
+}
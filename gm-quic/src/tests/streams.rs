@@ -0,0 +1,55 @@
+use super::*;
+
+#[test]
+fn bytes_stream() -> Result<(), Error> {
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+        let connection = client.connect("localhost", [server_addr])?;
+        let (_sid, (mut reader, mut writer)) = connection.open_bi_stream().await?.unwrap();
+
+        let data = bytes::Bytes::from(TEST_DATA.repeat(64));
+        let chunks = (0..data.len())
+            .step_by(4096)
+            .map(|start| data.slice(start..(start + 4096).min(data.len())));
+        let mut back = Vec::new();
+        tokio::try_join!(
+            async {
+                writer.write_chunks(chunks).await?;
+                writer.shutdown().await
+            },
+            reader.read_to_end(&mut back),
+        )?;
+        assert_eq!(back, data);
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
+
+#[test]
+fn unordered_chunks() -> Result<(), Error> {
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+        let connection = client.connect("localhost", [server_addr])?;
+        let (_sid, (mut reader, mut writer)) = connection.open_bi_stream().await?.unwrap();
+
+        let data = TEST_DATA.repeat(64);
+        let mut back = vec![0; data.len()];
+        tokio::try_join!(
+            async {
+                writer.write_all(&data).await?;
+                writer.shutdown().await
+            },
+            async {
+                while let Some((offset, chunk)) = reader.read_chunk_unordered(4096).await? {
+                    back[offset as usize..][..chunk.len()].copy_from_slice(&chunk);
+                }
+                io::Result::Ok(())
+            },
+        )?;
+        assert_eq!(back, data);
+
+        Ok(())
+    };
+    test_serially(|| launch_echo_server(server_parameters()), launch_client)
+}
@@ -0,0 +1,314 @@
+use super::*;
+
+#[test]
+#[cfg(unix)]
+fn sharded_interface() -> Result<(), Error> {
+    let launch_server = || async {
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_qlog(qlogger())
+            .listen(128);
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [BindUri::from("inet://127.0.0.1:0?alloc_port=true&shards=4").alloc_port()],
+            None,
+        )?;
+        Ok((listeners.clone(), serve_echo(listeners)))
+    };
+    let launch_client = |server_addr| async move {
+        let client = launch_test_client(client_parameters());
+
+        // 不同源端口的连接会被内核分散到不同的shard上
+        let mut connections = JoinSet::new();
+        for conn_idx in 0..8 {
+            let connection = client.connect("localhost", [server_addr])?;
+            connections.spawn(
+                async move { send_and_verify_echo(&connection, TEST_DATA).await }
+                    .instrument(tracing::info_span!("connection", conn_idx)),
+            );
+        }
+
+        connections
+            .join_all()
+            .await
+            .into_iter()
+            .collect::<Result<(), Error>>()
+    };
+    test_serially(launch_server, launch_client)
+}
+
+#[test]
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+fn io_uring_backend() -> Result<(), Error> {
+    let launch_server = || async {
+        let listeners = QuicListeners::builder()?
+            .with_iface_factory(uring::UdpSocketController::bind)
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_qlog(qlogger())
+            .listen(128);
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port()],
+            None,
+        )?;
+        Ok((listeners.clone(), serve_echo(listeners)))
+    };
+    let launch_client = |server_addr| async move {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_iface_factory(uring::UdpSocketController::bind)
+            .with_root_certificates(roots)
+            .with_parameters(client_parameters())
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(64)).await?;
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
+
+#[test]
+#[cfg(unix)]
+fn unix_socket() -> Result<(), Error> {
+    let mut path = std::env::temp_dir();
+    path.push(format!("gm-quic-test-{}.sock", std::process::id()));
+    _ = std::fs::remove_file(&path);
+    let launch_server = || async move {
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_qlog(qlogger())
+            .listen(128);
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [BindUri::from(
+                format!("unix://localhost{}", path.display()).as_str(),
+            )],
+            None,
+        )?;
+        Ok((listeners.clone(), serve_echo(listeners)))
+    };
+    let launch_client = |server_addr: EndpointAddr| async move {
+        assert!(matches!(server_addr, EndpointAddr::Unix(..)));
+        let client = launch_test_client(client_parameters());
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(64)).await?;
+
+        Ok(())
+    };
+    test_serially_at(launch_server, launch_client)
+}
+
+/// An in-process transport of the custom `mem` scheme, `mem://server` binds the address `server`.
+mod mem {
+    use std::{
+        collections::HashMap,
+        io,
+        sync::{
+            Mutex, OnceLock,
+            atomic::{AtomicUsize, Ordering},
+        },
+        task::{Context, Poll, ready},
+    };
+
+    use bytes::{Bytes, BytesMut};
+    use qbase::net::{
+        addr::{BindUri, BindUriSchema, RealAddr},
+        route::{Link, PacketHeader, Pathway},
+        scheme::{self, CustomAddr, CustomKind, CustomScheme, InvalidCustomBindUri},
+    };
+    use qinterface::{QuicIO, factory::handy::DEFAULT_QUIC_IO_FACTORY};
+    use tokio::sync::mpsc;
+
+    use crate::ProductQuicIO;
+
+    type Hub = Mutex<HashMap<CustomAddr, mpsc::UnboundedSender<(CustomAddr, Bytes)>>>;
+
+    fn hub() -> &'static Hub {
+        static HUB: OnceLock<Hub> = OnceLock::new();
+        HUB.get_or_init(Default::default)
+    }
+
+    struct MemScheme;
+
+    impl CustomScheme for MemScheme {
+        fn parse(&self, uri: &http::Uri) -> Result<(), InvalidCustomBindUri> {
+            match (uri.port(), uri.path()) {
+                (None, "/") => Ok(()),
+                _ => Err("mem scheme BindUri has only a host".into()),
+            }
+        }
+
+        fn client_bind_uri(&self) -> Option<BindUri> {
+            static CLIENTS: AtomicUsize = AtomicUsize::new(0);
+            let client = CLIENTS.fetch_add(1, Ordering::Relaxed);
+            Some(BindUri::from(format!("mem://client-{client}").as_str()))
+        }
+
+        fn fmt_addr(&self, addr: &[u8], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&String::from_utf8_lossy(addr))
+        }
+    }
+
+    pub fn kind() -> CustomKind {
+        static KIND: OnceLock<CustomKind> = OnceLock::new();
+        *KIND.get_or_init(|| scheme::register("mem", MemScheme).unwrap())
+    }
+
+    struct MemIO {
+        bind_uri: BindUri,
+        addr: CustomAddr,
+        rx: Mutex<mpsc::UnboundedReceiver<(CustomAddr, Bytes)>>,
+    }
+
+    impl Drop for MemIO {
+        fn drop(&mut self) {
+            hub().lock().unwrap().remove(&self.addr);
+        }
+    }
+
+    impl QuicIO for MemIO {
+        fn bind_uri(&self) -> BindUri {
+            self.bind_uri.clone()
+        }
+
+        fn real_addr(&self) -> io::Result<RealAddr> {
+            Ok(RealAddr::Custom(self.addr.clone()))
+        }
+
+        fn max_segment_size(&self) -> io::Result<usize> {
+            Ok(1500)
+        }
+
+        fn max_segments(&self) -> io::Result<usize> {
+            Ok(16)
+        }
+
+        fn poll_send(
+            &self,
+            _cx: &mut Context,
+            pkts: &[io::IoSlice],
+            hdr: PacketHeader,
+        ) -> Poll<io::Result<usize>> {
+            let RealAddr::Custom(dst) = hdr.link().dst() else {
+                return Poll::Ready(Err(io::ErrorKind::InvalidInput.into()));
+            };
+            // 对端不存在时与UDP一样静默丢弃
+            if let Some(peer) = hub().lock().unwrap().get(&dst) {
+                for pkt in pkts {
+                    _ = peer.send((self.addr.clone(), Bytes::copy_from_slice(pkt)));
+                }
+            }
+            Poll::Ready(Ok(pkts.len()))
+        }
+
+        fn poll_recv(
+            &self,
+            cx: &mut Context,
+            pkts: &mut [BytesMut],
+            hdrs: &mut [PacketHeader],
+        ) -> Poll<io::Result<usize>> {
+            let mut rx = self.rx.lock().unwrap();
+            let mut received = vec![];
+            let limit = pkts.len().min(hdrs.len());
+            let rcvd = ready!(rx.poll_recv_many(cx, &mut received, limit));
+            if rcvd == 0 {
+                return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+            }
+            let local = RealAddr::Custom(self.addr.clone());
+            for ((src, pkt), (buf, hdr)) in received.into_iter().zip(pkts.iter_mut().zip(hdrs)) {
+                let src = RealAddr::Custom(src);
+                buf[..pkt.len()].copy_from_slice(&pkt);
+                let way = Pathway::new(src.clone().into(), local.clone().into());
+                let link = Link::new(src, local.clone());
+                *hdr = PacketHeader::new(way.flip(), link.flip(), 64, None, pkt.len() as u16);
+            }
+            Poll::Ready(Ok(rcvd))
+        }
+
+        fn poll_close(&self, _cx: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Bind `mem` scheme interfaces in memory, and the others with the default factory.
+    pub struct MemFactory;
+
+    impl ProductQuicIO for MemFactory {
+        fn bind(&self, bind_uri: BindUri) -> io::Result<Box<dyn QuicIO>> {
+            if bind_uri.scheme() != BindUriSchema::Custom(kind()) {
+                return DEFAULT_QUIC_IO_FACTORY.bind(bind_uri);
+            }
+            let host = bind_uri.as_uri().host().expect("BindUri must have a host");
+            let addr = CustomAddr::new(kind(), host.as_bytes())
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            let (tx, rx) = mpsc::unbounded_channel();
+            match hub().lock().unwrap().entry(addr.clone()) {
+                std::collections::hash_map::Entry::Occupied(..) => {
+                    return Err(io::ErrorKind::AddrInUse.into());
+                }
+                std::collections::hash_map::Entry::Vacant(entry) => _ = entry.insert(tx),
+            }
+            Ok(Box::new(MemIO {
+                bind_uri,
+                addr,
+                rx: Mutex::new(rx),
+            }))
+        }
+    }
+}
+
+#[test]
+fn custom_scheme() -> Result<(), Error> {
+    let kind = mem::kind();
+    let launch_server = || async move {
+        let listeners = QuicListeners::builder()?
+            .without_client_cert_verifier()
+            .with_parameters(server_parameters())
+            .with_iface_factory(mem::MemFactory)
+            .with_qlog(qlogger())
+            .listen(128);
+        listeners.add_server(
+            "localhost",
+            SERVER_CERT,
+            SERVER_KEY,
+            [BindUri::from("mem://server")],
+            None,
+        )?;
+        Ok((listeners.clone(), serve_echo(listeners)))
+    };
+    let launch_client = move |server_addr: EndpointAddr| async move {
+        assert_eq!(
+            server_addr,
+            EndpointAddr::Custom(scheme::CustomAddr::new(kind, b"server")?)
+        );
+        assert_eq!(server_addr.to_string(), "mem:server");
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_root_certificates(roots)
+            .with_parameters(client_parameters())
+            .with_iface_factory(mem::MemFactory)
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, &TEST_DATA.to_vec().repeat(64)).await?;
+
+        Ok(())
+    };
+    test_serially_at(launch_server, launch_client)
+}
@@ -0,0 +1,475 @@
+use super::*;
+
+/// A simulated NAT which only lets in the packets from the addresses the interface has sent to,
+/// like an address-restricted cone NAT.
+///
+/// The interfaces bound with the `nat=filter` property are only filtered, the ones bound with the
+/// `nat=map` property also send and receive on another socket, the outer address, so the peers
+/// observe the outer address and can not reach the address the interface is bound on.
+mod nat {
+    use std::{
+        borrow::Cow,
+        collections::HashSet,
+        io,
+        net::SocketAddr,
+        sync::Mutex,
+        task::{Context, Poll, ready},
+    };
+
+    use bytes::BytesMut;
+    use qbase::net::{
+        addr::{BindUri, RealAddr},
+        route::{Link, PacketHeader, Pathway},
+    };
+    use qinterface::{QuicIO, factory::handy::DEFAULT_QUIC_IO_FACTORY};
+
+    use crate::ProductQuicIO;
+
+    fn nat_bind_uri(nat: &str) -> BindUri {
+        let mut bind_uri = BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port();
+        bind_uri.add_prop("nat", nat);
+        bind_uri
+    }
+
+    pub fn filtered_bind_uri() -> BindUri {
+        nat_bind_uri("filter")
+    }
+
+    pub fn mapped_bind_uri() -> BindUri {
+        nat_bind_uri("map")
+    }
+
+    struct NatIO {
+        // 绑定在BindUri上的socket，映射时只占用内部地址，不再收发
+        inner: Box<dyn QuicIO>,
+        // 映射时实际收发的socket，对端观察到的是它的地址
+        outer: Option<Box<dyn QuicIO>>,
+        sent_to: Mutex<HashSet<SocketAddr>>,
+    }
+
+    impl NatIO {
+        fn io(&self) -> &dyn QuicIO {
+            self.outer.as_deref().unwrap_or(self.inner.as_ref())
+        }
+    }
+
+    impl QuicIO for NatIO {
+        fn bind_uri(&self) -> BindUri {
+            self.inner.bind_uri()
+        }
+
+        fn real_addr(&self) -> io::Result<RealAddr> {
+            self.inner.real_addr()
+        }
+
+        fn max_segment_size(&self) -> io::Result<usize> {
+            self.inner.max_segment_size()
+        }
+
+        fn max_segments(&self) -> io::Result<usize> {
+            self.inner.max_segments()
+        }
+
+        fn poll_send(
+            &self,
+            cx: &mut Context,
+            pkts: &[io::IoSlice],
+            hdr: PacketHeader,
+        ) -> Poll<io::Result<usize>> {
+            if let RealAddr::Internet(dst) = hdr.link().dst() {
+                self.sent_to.lock().unwrap().insert(dst);
+            }
+            let hdr = match &self.outer {
+                Some(outer) => {
+                    let outer = outer.real_addr()?;
+                    PacketHeader::new(
+                        Pathway::new(outer.clone().into(), hdr.pathway().remote()),
+                        Link::new(outer, hdr.link().dst()),
+                        hdr.ttl(),
+                        hdr.ecn(),
+                        hdr.seg_size(),
+                    )
+                }
+                None => hdr,
+            };
+            self.io().poll_send(cx, pkts, hdr)
+        }
+
+        fn poll_recv(
+            &self,
+            cx: &mut Context,
+            pkts: &mut [BytesMut],
+            hdrs: &mut [PacketHeader],
+        ) -> Poll<io::Result<usize>> {
+            let inner = self.inner.real_addr()?;
+            loop {
+                let rcvd = ready!(self.io().poll_recv(cx, pkts, hdrs))?;
+                let sent_to = self.sent_to.lock().unwrap();
+                let mut passed = 0;
+                for idx in 0..rcvd {
+                    let RealAddr::Internet(src) = hdrs[idx].link().dst() else {
+                        continue;
+                    };
+                    if !sent_to.contains(&src) {
+                        continue;
+                    }
+                    if self.outer.is_some() {
+                        let hdr = &hdrs[idx];
+                        hdrs[idx] = PacketHeader::new(
+                            Pathway::new(inner.clone().into(), hdr.pathway().remote()),
+                            Link::new(inner.clone(), hdr.link().dst()),
+                            hdr.ttl(),
+                            hdr.ecn(),
+                            hdr.seg_size(),
+                        );
+                    }
+                    pkts.swap(passed, idx);
+                    hdrs.swap(passed, idx);
+                    passed += 1;
+                }
+                if passed > 0 {
+                    return Poll::Ready(Ok(passed));
+                }
+            }
+        }
+
+        fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
+            if let Some(outer) = &self.outer {
+                ready!(outer.poll_close(cx))?;
+            }
+            self.inner.poll_close(cx)
+        }
+    }
+
+    /// Bind the interfaces with the default factory, behind a simulated NAT if the BindUri has
+    /// the `nat` property.
+    pub struct NatFactory;
+
+    impl ProductQuicIO for NatFactory {
+        fn bind(&self, bind_uri: BindUri) -> io::Result<Box<dyn QuicIO>> {
+            let nat = bind_uri.prop("nat").map(Cow::into_owned);
+            let inner = DEFAULT_QUIC_IO_FACTORY.bind(bind_uri)?;
+            let outer = match nat.as_deref() {
+                Some("filter") => None,
+                Some("map") => {
+                    let outer_uri =
+                        BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port();
+                    Some(DEFAULT_QUIC_IO_FACTORY.bind(outer_uri)?)
+                }
+                _ => return Ok(inner),
+            };
+            Ok(Box::new(NatIO {
+                inner,
+                outer,
+                sent_to: Mutex::default(),
+            }))
+        }
+    }
+}
+
+#[test]
+fn hole_punching() -> Result<(), Error> {
+    let server_parameters = || {
+        let mut params = server_parameters();
+        params
+            .set(
+                ParameterId::NatTraversal,
+                qbase::param::ParameterValue::True,
+            )
+            .expect("unreachable");
+        params
+    };
+    // 服务端额外在NAT后的接口上通告地址，客户端只有打洞后才能通过该地址与服务端通信
+    let punch_uri = nat::filtered_bind_uri();
+    let launch_server = {
+        let punch_uri = punch_uri.clone();
+        || async move {
+            let listeners = QuicListeners::builder()?
+                .without_client_cert_verifier()
+                .with_parameters(server_parameters())
+                .with_qlog(qlogger())
+                .listen(128);
+            listeners.add_server(
+                "localhost",
+                SERVER_CERT,
+                SERVER_KEY,
+                [BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port()],
+                None,
+            )?;
+            let punch_iface = QuicInterfaces::global().bind(punch_uri, Arc::new(nat::NatFactory));
+            let serve = {
+                let listeners = listeners.clone();
+                async move {
+                    let _punch_iface = punch_iface;
+                    loop {
+                        let (connection, _server, _pathway, _link) = listeners.accept().await?;
+                        let punch_uri = _punch_iface.bind_uri();
+                        tokio::spawn(async move {
+                            connection.advertise_address(punch_uri).await.unwrap();
+                            while let Ok((_sid, (reader, writer))) =
+                                connection.accept_bi_stream().await
+                            {
+                                tokio::spawn(echo_stream(reader, writer));
+                            }
+                        });
+                    }
+                    #[allow(unreachable_code)]
+                    io::Result::Ok(())
+                }
+            };
+            Ok((listeners, serve))
+        }
+    };
+    let launch_client = |server_addr: SocketAddr| async move {
+        let punch_addr = QuicInterfaces::global()
+            .get(&punch_uri)
+            .expect("Interface should be bound")
+            .real_addr()?;
+        // 客户端在地址转换的NAT后，服务端只能向观察到的外部地址打洞
+        let client_uri = nat::mapped_bind_uri();
+
+        let mut params = client_parameters();
+        params
+            .set(
+                ParameterId::NatTraversal,
+                qbase::param::ParameterValue::True,
+            )
+            .expect("unreachable");
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_iface_factory(nat::NatFactory)
+            .bind([client_uri.clone()])
+            .with_root_certificates(roots)
+            .with_parameters(params)
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect("localhost", [server_addr])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        let client_addr = QuicInterfaces::global()
+            .get(&client_uri)
+            .expect("Interface should be bound")
+            .real_addr()?;
+        let punched = connection.punch().await?;
+        assert_eq!(
+            punched,
+            [Pathway::new(client_addr.clone().into(), punch_addr.into())]
+        );
+
+        // 只保留打洞得到的直连路径
+        connection.del_path(&Pathway::new(client_addr.into(), server_addr.into()))?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        Ok(())
+    };
+    test_serially(launch_server, launch_client)
+}
+
+#[test]
+fn rendezvous_agent() -> Result<(), Error> {
+    // 同一个监听器同时作为代理和NAT后的对端B
+    let agent_uri = BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port();
+    let peer_uri = nat::mapped_bind_uri();
+    let real_addr = |bind_uri: &BindUri| {
+        QuicInterfaces::global()
+            .get(bind_uri)
+            .expect("Interface should be bound")
+            .real_addr()
+    };
+
+    let launch_server = {
+        let agent_uri = agent_uri.clone();
+        let peer_uri = peer_uri.clone();
+        || async move {
+            let listeners = QuicListeners::builder()?
+                .with_iface_factory(nat::NatFactory)
+                .without_client_cert_verifier()
+                .with_parameters(server_parameters())
+                .with_qlog(qlogger())
+                .listen(128);
+            listeners.add_server(
+                "localhost",
+                SERVER_CERT,
+                SERVER_KEY,
+                [agent_uri.clone(), peer_uri],
+                None,
+            )?;
+            let agent_addr = real_addr(&agent_uri)?;
+            let serve = {
+                let listeners = listeners.clone();
+                async move {
+                    let agent = RendezvousAgent::new();
+                    loop {
+                        let (connection, _server, _pathway, link) = listeners.accept().await?;
+                        if link.src() == agent_addr {
+                            tokio::spawn(agent.clone().serve(connection, link));
+                            continue;
+                        }
+                        tokio::spawn(async move {
+                            while let Ok((_sid, (reader, writer))) =
+                                connection.accept_bi_stream().await
+                            {
+                                tokio::spawn(echo_stream(reader, writer));
+                            }
+                        });
+                    }
+                    #[allow(unreachable_code)]
+                    io::Result::Ok(())
+                }
+            };
+            Ok((listeners, serve))
+        }
+    };
+    let launch_client = |_: RealAddr| async move {
+        let RealAddr::Internet(agent_addr) = real_addr(&agent_uri)? else {
+            unreachable!("agent is bound on an internet address")
+        };
+        let RealAddr::Internet(peer_addr) = real_addr(&peer_uri)? else {
+            unreachable!("peer is bound on an internet address")
+        };
+        let launch_client = |bind_uri: BindUri, qlog: Arc<dyn Log + Send + Sync>| {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add_parsable_certificates(CA_CERT.to_certificate());
+            QuicClient::builder()
+                .with_iface_factory(nat::NatFactory)
+                .bind([bind_uri])
+                .with_root_certificates(roots)
+                .with_parameters(client_parameters())
+                .without_cert()
+                .with_qlog(qlog)
+                .build()
+        };
+
+        // B从监听的接口向代理注册，并按代理的通知向请求者打洞
+        let peer_client = launch_client(peer_uri.clone(), qlogger());
+        let peer_rendezvous =
+            Rendezvous::new(peer_client.connect("localhost", [agent_addr])?, agent_addr);
+        let mut registration = peer_rendezvous.register("b").await?;
+        // B在地址转换的NAT后，代理观察到的是它的外部地址
+        let peer_outer = registration.outer();
+        assert_ne!(peer_outer, peer_addr);
+        let _punching = AbortOnDropHandle::new(tokio::spawn(async move {
+            while let Some(requester) = registration.next_punch().await? {
+                Rendezvous::punch(&peer_uri, requester).await?;
+            }
+            io::Result::Ok(())
+        }));
+
+        let client_qlog = QlogRecorder::default();
+        let client = launch_client(nat::mapped_bind_uri(), Arc::new(client_qlog.clone()));
+        let rendezvous = Rendezvous::new(client.connect("localhost", [agent_addr])?, agent_addr);
+        let not_found = rendezvous.lookup("c").await.unwrap_err();
+        assert_eq!(not_found.kind(), io::ErrorKind::NotFound);
+
+        let peer = rendezvous.lookup("b").await?;
+        assert_eq!(peer, SocketEndpointAddr::with_agent(agent_addr, peer_outer));
+        let connection = client.connect("localhost", [peer])?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+        // 从对端收到的包归属于经代理的路径，数据只在这条路径上发送
+        let peer_paths = client_qlog
+            .frame_paths("quic:packet_sent", "stream")
+            .into_iter()
+            .filter(|path| path.contains(&peer_outer.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(peer_paths.len(), 1);
+        assert!(peer_paths[0].ends_with(&peer.to_string()));
+
+        Ok(())
+    };
+    test_serially_at(launch_server, launch_client)
+}
+
+#[test]
+fn relay() -> Result<(), Error> {
+    // B在NAT后监听，A先经中继连接B，B向A打洞后再换成直连路径
+    let peer_uri = nat::filtered_bind_uri();
+    let real_addr = |bind_uri: &BindUri| {
+        QuicInterfaces::global()
+            .get(bind_uri)
+            .expect("Interface should be bound")
+            .real_addr()
+    };
+
+    let launch_server = {
+        let peer_uri = peer_uri.clone();
+        || async move {
+            let listeners = QuicListeners::builder()?
+                .with_iface_factory(nat::NatFactory)
+                .without_client_cert_verifier()
+                .with_parameters(server_parameters())
+                .with_qlog(qlogger())
+                .listen(128);
+            listeners.add_server("localhost", SERVER_CERT, SERVER_KEY, [peer_uri], None)?;
+            let serve = {
+                let listeners = listeners.clone();
+                async move {
+                    loop {
+                        let (connection, _server, _pathway, _link) = listeners.accept().await?;
+                        tokio::spawn(async move {
+                            while let Ok((_sid, (reader, writer))) =
+                                connection.accept_bi_stream().await
+                            {
+                                tokio::spawn(echo_stream(reader, writer));
+                            }
+                        });
+                    }
+                    #[allow(unreachable_code)]
+                    io::Result::Ok(())
+                }
+            };
+            Ok((listeners, serve))
+        }
+    };
+    let launch_client = |_: RealAddr| async move {
+        let RealAddr::Internet(peer_addr) = real_addr(&peer_uri)? else {
+            unreachable!("peer is bound on an internet address")
+        };
+        let relay_uri = BindUri::from("inet://127.0.0.1:0?alloc_port=true").alloc_port();
+        let relay = Arc::new(qrelay::RelayServer::new(
+            qinterface::factory::handy::DEFAULT_QUIC_IO_FACTORY.bind(relay_uri)?,
+        ));
+        let relay_addr = relay.local_addr()?;
+        let relaying = AbortOnDropHandle::new(tokio::spawn(async move { relay.run().await }));
+
+        let peer_iface = QuicInterfaces::global()
+            .get(&peer_uri)
+            .expect("Interface should be bound");
+        qrelay::register(&peer_iface, relay_addr).await?;
+
+        let client_uri = nat::filtered_bind_uri();
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add_parsable_certificates(CA_CERT.to_certificate());
+        let client = QuicClient::builder()
+            .with_iface_factory(nat::NatFactory)
+            .bind([client_uri.clone()])
+            .with_root_certificates(roots)
+            .with_parameters(client_parameters())
+            .without_cert()
+            .with_qlog(qlogger())
+            .build();
+        let connection = client.connect(
+            "localhost",
+            [SocketEndpointAddr::with_relay(relay_addr, peer_addr)],
+        )?;
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        let RealAddr::Internet(client_addr) = real_addr(&client_uri)? else {
+            unreachable!("client is bound on an internet address")
+        };
+        Rendezvous::punch(&peer_uri, client_addr).await?;
+        let replaced = connection.replace_relayed_paths().await?;
+        assert_eq!(
+            replaced,
+            [Pathway::new(client_addr.into(), peer_addr.into())]
+        );
+
+        // 中继停止后仍能通过直连路径通信
+        drop(relaying);
+        send_and_verify_echo(&connection, TEST_DATA).await?;
+
+        Ok(())
+    };
+    test_serially_at(launch_server, launch_client)
+}
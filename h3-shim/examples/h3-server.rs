@@ -1,12 +1,13 @@
-use std::{ops::Deref, path::PathBuf, sync::Arc};
+use std::{ops::Deref, path::PathBuf, pin::pin, sync::Arc};
 
 use bytes::{Bytes, BytesMut};
 use clap::Parser;
 use gm_quic::{
-    BindUri,
+    BindUri, GracefulShutdown,
     handy::{LegacySeqLogger, NoopLogger, server_parameters},
 };
 use h3::{quic::BidiStream, server::RequestStream};
+use h3_shim::{GracefulConnection, H3_NO_ERROR};
 use http::{Request, StatusCode};
 use tokio::{fs::File, io::AsyncReadExt};
 use tracing::level_filters::LevelFilter;
@@ -150,42 +151,53 @@ async fn run(options: Options) -> Result<(), Box<dyn std::error::Error + Send +
         &*listeners.get_server(server_name.as_str()).unwrap()
     );
 
-    // handle incoming connections and requests
-    while let Ok((new_conn, _server, _pathway, _link)) = listeners.accept().await {
-        let h3_conn =
-            match h3::server::Connection::new(h3_shim::QuicConnection::new(new_conn)).await {
-                Ok(h3_conn) => {
-                    tracing::info!("Accept a new quic connection");
-                    h3_conn
-                }
-                Err(error) => {
-                    tracing::error!("Failed to establish h3 connection: {}", error);
-                    continue;
-                }
-            };
+    // handle incoming connections and requests, until ctrl-c is pressed
+    let mut ctrl_c = pin!(tokio::signal::ctrl_c());
+    loop {
+        let new_conn = tokio::select! {
+            accepted = listeners.accept() => match accepted {
+                Ok((new_conn, _server, _pathway, _link)) => new_conn,
+                Err(_) => break,
+            },
+            _ = &mut ctrl_c => break,
+        };
+        let h3_conn = match GracefulConnection::new(new_conn, &listeners).await {
+            Ok(h3_conn) => {
+                tracing::info!("Accept a new quic connection");
+                h3_conn
+            }
+            Err(error) => {
+                tracing::error!("Failed to establish h3 connection: {}", error);
+                continue;
+            }
+        };
         let root = root.clone();
         tokio::spawn(handle_connection(root, h3_conn));
     }
 
+    tracing::info!("Shutting down, waiting for the in-flight requests");
+    listeners
+        .shutdown_gracefully(GracefulShutdown::default().with_application_close(H3_NO_ERROR, ""))
+        .await;
     Ok(())
 }
 
-async fn handle_connection<T>(
-    serve_root: Arc<PathBuf>,
-    mut connection: h3::server::Connection<T, Bytes>,
-) where
-    T: h3::quic::Connection<Bytes> + 'static,
-    <T as h3::quic::OpenStreams<Bytes>>::BidiStream: h3::quic::BidiStream<Bytes> + Send + 'static,
-{
+async fn handle_connection(serve_root: Arc<PathBuf>, mut connection: GracefulConnection<Bytes>) {
+    let mut requests = tokio::task::JoinSet::new();
     loop {
-        match connection.accept().await {
+        let accepted = tokio::select! {
+            accepted = connection.accept() => accepted,
+            // reap the finished requests
+            Some(_) = requests.join_next() => continue,
+        };
+        match accepted {
             Ok(Some(request_resolver)) => {
                 let serve_root = serve_root.clone();
                 let handle_request = async move {
                     let (request, stream) = request_resolver.resolve_request().await?;
                     handle_request(request, stream, serve_root).await
                 };
-                tokio::spawn(async move {
+                requests.spawn(async move {
                     if let Err(e) = handle_request.await {
                         tracing::error!("Handling request failed: {}", e);
                     }
@@ -195,6 +207,12 @@ async fn handle_connection<T>(
             Err(..) => break,
         }
     }
+
+    // the control streams of h3 never finish, close the connection once all requests are served
+    while requests.join_next().await.is_some() {}
+    if connection.goaway_sent() {
+        connection.close();
+    }
 }
 
 #[tracing::instrument(skip_all)]
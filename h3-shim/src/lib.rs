@@ -1,7 +1,9 @@
 pub mod conn;
 mod error;
 pub mod pool;
+pub mod server;
 pub use conn::{OpenStreams, QuicConnection};
+pub use server::{GracefulConnection, H3_NO_ERROR};
 #[cfg(feature = "unreliable")]
 pub mod ext;
 #[cfg(feature = "unreliable")]
@@ -0,0 +1,80 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use bytes::Buf;
+use futures::future::{Either, FusedFuture, FutureExt};
+use gm_quic::QuicListeners;
+use h3::{error::ConnectionError, server::RequestResolver};
+
+use crate::QuicConnection;
+
+/// The HTTP/3 error code `H3_NO_ERROR`, used to close the connection gracefully.
+pub const H3_NO_ERROR: u64 = 0x100;
+
+type Draining = futures::future::Fuse<Pin<Box<dyn Future<Output = ()> + Send>>>;
+
+/// An HTTP/3 server connection that sends a GOAWAY frame once the [`QuicListeners`] that accepted
+/// it start draining.
+///
+/// After the GOAWAY frame is sent, the peer stops sending new requests, the requests already
+/// accepted are still served, and [`GracefulConnection::accept`] returns `Ok(None)` once all of
+/// them are finished. The control streams of HTTP/3 never finish, so the QUIC connection does not
+/// become idle by itself, call [`GracefulConnection::close`] after serving the accepted requests
+/// to let [`QuicListeners::shutdown_gracefully`] complete without waiting for its timeout.
+pub struct GracefulConnection<B: Buf> {
+    quic: Arc<gm_quic::Connection>,
+    h3: h3::server::Connection<QuicConnection, B>,
+    draining: Draining,
+    goaway_sent: bool,
+}
+
+impl<B: Buf> GracefulConnection<B> {
+    /// Establish the HTTP/3 connection on the QUIC `connection` accepted by the `listeners`.
+    pub async fn new(
+        connection: Arc<gm_quic::Connection>,
+        listeners: &QuicListeners,
+    ) -> Result<Self, ConnectionError> {
+        let h3 = h3::server::Connection::new(QuicConnection::new(connection.clone())).await?;
+        let draining: Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(listeners.draining());
+        Ok(Self {
+            quic: connection,
+            h3,
+            draining: draining.fuse(),
+            goaway_sent: false,
+        })
+    }
+
+    /// Accept the next request, send a GOAWAY frame if the listeners start draining meanwhile.
+    ///
+    /// Return `Ok(None)` if the connection is closed, or all accepted requests are finished after
+    /// the GOAWAY frame is sent.
+    pub async fn accept(
+        &mut self,
+    ) -> Result<Option<RequestResolver<QuicConnection, B>>, ConnectionError> {
+        loop {
+            // 如果上次发送GOAWAY时被取消，这里重新发送，GOAWAY中的流ID不变
+            if self.draining.is_terminated() && !self.goaway_sent {
+                // 告知客户端不再发送新的请求，已经接受的请求仍会被处理
+                self.h3.shutdown(0).await?;
+                self.goaway_sent = true;
+            }
+            if self.goaway_sent {
+                return self.h3.accept().await;
+            }
+            let accept = std::pin::pin!(self.h3.accept());
+            match futures::future::select(accept, &mut self.draining).await {
+                Either::Left((accepted, _)) => return accepted,
+                Either::Right(((), _)) => continue,
+            }
+        }
+    }
+
+    /// Whether the GOAWAY frame has been sent.
+    pub fn goaway_sent(&self) -> bool {
+        self.goaway_sent
+    }
+
+    /// Close the QUIC connection with `H3_NO_ERROR`.
+    pub fn close(&self) {
+        self.quic.close("", H3_NO_ERROR);
+    }
+}
@@ -60,10 +60,26 @@ where
         }
     }
 
+    /// Create a new local connection ID manager without the initial source connection ID,
+    /// the connection IDs are issued from the sequence number 0 once the limit is set.
+    fn without_scid(issued_cids: ISSUED) -> Self {
+        Self {
+            cid_deque: IndexDeque::default(),
+            issued_cids,
+            active_cid_limit: None,
+        }
+    }
+
     fn initial_scid(&self) -> Option<ConnectionId> {
         self.cid_deque.get(0)?.map(|(cid, _)| cid)
     }
 
+    fn sequence_of(&self, cid: &ConnectionId) -> Option<u64> {
+        self.cid_deque
+            .enumerate()
+            .find_map(|(seq, item)| item.filter(|(c, _)| c == cid).map(|_| seq))
+    }
+
     /// Set the maximum number of active connection IDs.
     ///
     /// The value of the active_connection_id_limit parameter MUST be at least 2.
@@ -182,6 +198,15 @@ where
         Self(Arc::new(Mutex::new(raw_local_cids)))
     }
 
+    /// Create a new share local connection ID manager without the initial source connection ID.
+    ///
+    /// It is used for the paths of multipath other than the path 0, whose connection IDs are
+    /// numbered from 0 independently. No connection ID is issued until [`ArcLocalCids::set_limit`]
+    /// is called, and then the connection IDs of the sequence numbers from 0 are issued.
+    pub fn without_scid(issued_cids: ISSUED) -> Self {
+        Self(Arc::new(Mutex::new(LocalCids::without_scid(issued_cids))))
+    }
+
     /// Get the initial source connection ID.
     ///
     /// 0-RTT packets in the first flight use the same Destination Connection ID
@@ -223,6 +248,13 @@ where
         self.0.lock().unwrap().initial_scid()
     }
 
+    /// Return the sequence number of an active local connection ID.
+    ///
+    /// Return None if the connection ID is not issued by this endpoint or has been retired.
+    pub fn sequence_of(&self, cid: &ConnectionId) -> Option<u64> {
+        self.0.lock().unwrap().sequence_of(cid)
+    }
+
     /// Unilaterally no longer use all local connection IDs.
     ///
     /// No longer used means that packets sent by the peer to that connection ID are no
@@ -320,6 +352,25 @@ mod tests {
 
         local_cids.set_limit(3).unwrap();
         assert_eq!(local_cids.cid_deque.len(), 3);
+
+        assert_eq!(local_cids.sequence_of(&initial_scid), Some(0));
+        let (cid, _) = local_cids.cid_deque.get(2).unwrap().unwrap();
+        assert_eq!(local_cids.sequence_of(&cid), Some(2));
+        assert_eq!(local_cids.sequence_of(&ConnectionId::random_gen(8)), None);
+    }
+
+    #[test]
+    fn test_issue_cid_without_scid() {
+        let local_cids = ArcLocalCids::without_scid(IssuedCids::default());
+        assert_eq!(local_cids.initial_scid(), None);
+        assert!(local_cids.0.lock().unwrap().issued_cids.frames().is_empty());
+
+        local_cids.set_limit(2).unwrap();
+        let local_cids = local_cids.0.lock().unwrap();
+        let frames = local_cids.issued_cids.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].sequence(), 0);
+        assert_eq!(local_cids.sequence_of(frames[1].connection_id()), Some(1));
     }
 
     #[test]
@@ -9,20 +9,33 @@ use super::varint::VarInt;
 use crate::packet::r#type::Type;
 
 mod ack;
+mod ack_frequency;
+mod add_address;
 mod connection_close;
 mod crypto;
 mod data_blocked;
 mod datagram;
+mod extension;
 mod handshake_done;
+mod immediate_ack;
 mod max_data;
+mod max_path_id;
 mod max_stream_data;
 mod max_streams;
 mod new_connection_id;
 mod new_token;
 mod padding;
+mod path_abandon;
+mod path_ack;
 mod path_challenge;
+mod path_cids_blocked;
+mod path_new_connection_id;
 mod path_response;
+mod path_retire_connection_id;
+mod path_status;
+mod paths_blocked;
 mod ping;
+mod punch_me_now;
 mod reset_stream;
 mod retire_connection_id;
 mod stop_sending;
@@ -36,22 +49,35 @@ pub mod error;
 pub mod io;
 
 pub use ack::{AckFrame, EcnCounts};
+pub use ack_frequency::AckFrequencyFrame;
+pub use add_address::AddAddressFrame;
 pub use connection_close::{AppCloseFrame, ConnectionCloseFrame, QuicCloseFrame};
 pub use crypto::CryptoFrame;
 pub use data_blocked::DataBlockedFrame;
 pub use datagram::DatagramFrame;
 #[doc(hidden)]
 pub use error::Error;
+pub use extension::{ExtensionFrame, ExtensionFrameDef, ExtensionFrameType, ExtensionFrames};
 pub use handshake_done::HandshakeDoneFrame;
+pub use immediate_ack::ImmediateAckFrame;
 pub use max_data::MaxDataFrame;
+pub use max_path_id::MaxPathIdFrame;
 pub use max_stream_data::MaxStreamDataFrame;
 pub use max_streams::MaxStreamsFrame;
 pub use new_connection_id::NewConnectionIdFrame;
 pub use new_token::NewTokenFrame;
 pub use padding::PaddingFrame;
+pub use path_abandon::PathAbandonFrame;
+pub use path_ack::PathAckFrame;
 pub use path_challenge::PathChallengeFrame;
+pub use path_cids_blocked::PathCidsBlockedFrame;
+pub use path_new_connection_id::PathNewConnectionIdFrame;
 pub use path_response::PathResponseFrame;
+pub use path_retire_connection_id::PathRetireConnectionIdFrame;
+pub use path_status::{PathAvailability, PathStatusFrame};
+pub use paths_blocked::PathsBlockedFrame;
 pub use ping::PingFrame;
+pub use punch_me_now::PunchMeNowFrame;
 pub use reset_stream::{ResetStreamError, ResetStreamFrame};
 pub use retire_connection_id::RetireConnectionIdFrame;
 pub use stop_sending::StopSendingFrame;
@@ -180,6 +206,32 @@ pub enum FrameType {
     HandshakeDone,
     /// DATAGRAM frame, see [`DatagramFrame`].
     Datagram(u8),
+    /// PATH_ACK frame, see [`PathAckFrame`].
+    PathAck(u8),
+    /// PATH_ABANDON frame, see [`PathAbandonFrame`].
+    PathAbandon,
+    /// PATH_STATUS_BACKUP or PATH_STATUS_AVAILABLE frame, see [`PathStatusFrame`].
+    PathStatus(u8),
+    /// PATH_NEW_CONNECTION_ID frame, see [`PathNewConnectionIdFrame`].
+    PathNewConnectionId,
+    /// PATH_RETIRE_CONNECTION_ID frame, see [`PathRetireConnectionIdFrame`].
+    PathRetireConnectionId,
+    /// MAX_PATH_ID frame, see [`MaxPathIdFrame`].
+    MaxPathId,
+    /// PATHS_BLOCKED frame, see [`PathsBlockedFrame`].
+    PathsBlocked,
+    /// PATH_CIDS_BLOCKED frame, see [`PathCidsBlockedFrame`].
+    PathCidsBlocked,
+    /// ACK_FREQUENCY frame, see [`AckFrequencyFrame`].
+    AckFrequency,
+    /// IMMEDIATE_ACK frame, see [`ImmediateAckFrame`].
+    ImmediateAck,
+    /// ADD_ADDRESS frame, see [`AddAddressFrame`].
+    AddAddress(u8),
+    /// PUNCH_ME_NOW frame, see [`PunchMeNowFrame`].
+    PunchMeNow(u8),
+    /// Frame of a type registered by the application, see [`ExtensionFrame`].
+    Extension(ExtensionFrameType),
 }
 
 #[enum_dispatch]
@@ -247,6 +299,23 @@ impl FrameFeature for FrameType {
             }
             FrameType::HandshakeDone => l,
             FrameType::Datagram(_) => o | l,
+            // Path identifiers are only available after the handshake,
+            // see [Section 4](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#section-4).
+            FrameType::PathAck(_) => l,
+            FrameType::PathAbandon => l,
+            FrameType::PathStatus(_) => l,
+            FrameType::PathNewConnectionId => l,
+            FrameType::PathRetireConnectionId => l,
+            FrameType::MaxPathId => l,
+            FrameType::PathsBlocked => l,
+            FrameType::PathCidsBlocked => l,
+            FrameType::AckFrequency => o | l,
+            FrameType::ImmediateAck => o | l,
+            // Addresses are exchanged only after the handshake,
+            // see [Section 4](https://www.ietf.org/archive/id/draft-seemann-quic-nat-traversal-02.html#section-4).
+            FrameType::AddAddress(_) => l,
+            FrameType::PunchMeNow(_) => l,
+            FrameType::Extension(_) => o | l,
         }
     }
 
@@ -260,13 +329,16 @@ impl FrameFeature for FrameType {
         match self {
             FrameType::Padding => n | p,
             FrameType::Ack(_) => n | c,
+            FrameType::PathAck(_) => n | c,
             FrameType::Stream(_) => f,
             FrameType::NewConnectionId => p,
+            FrameType::PathNewConnectionId => p,
             FrameType::PathChallenge => p,
             FrameType::PathResponse => p,
             // different from [table 3](https://www.rfc-editor.org/rfc/rfc9000.html#table-3),
             // add the [`Spec::Con`] for the CONNECTION_CLOSE frame
             FrameType::ConnectionClose(_) => n | c,
+            FrameType::Extension(ty) if !ty.is_ack_eliciting() => n,
             _ => 0,
         }
     }
@@ -302,9 +374,25 @@ impl TryFrom<VarInt> for FrameType {
             // The last bit is the layer flag bit, 0 indicates application layer, 1 indicates transport layer.
             ty @ (0x1c | 0x1d) => FrameType::ConnectionClose(ty as u8 & 0x1),
             0x1e => FrameType::HandshakeDone,
+            0x1f => FrameType::ImmediateAck,
             // The last bit is the length flag bit, 0 the length field is absent and the Datagram Data
             // field extends to the end of the packet, 1 the length field is present.
             ty @ (0x30 | 0x31) => FrameType::Datagram(ty as u8 & 1),
+            // The last bit is the ECN flag.
+            ty @ (0x15228c00 | 0x15228c01) => FrameType::PathAck(ty as u8 & 0b1),
+            0x15228c05 => FrameType::PathAbandon,
+            // 0x15228c07 is PATH_STATUS_BACKUP, 0x15228c08 is PATH_STATUS_AVAILABLE.
+            0x15228c07 => FrameType::PathStatus(0),
+            0x15228c08 => FrameType::PathStatus(1),
+            0x15228c09 => FrameType::PathNewConnectionId,
+            0x15228c0a => FrameType::PathRetireConnectionId,
+            0x15228c0c => FrameType::MaxPathId,
+            0x15228c0d => FrameType::PathsBlocked,
+            0x15228c0e => FrameType::PathCidsBlocked,
+            0xaf => FrameType::AckFrequency,
+            // The last bit is the address family flag, 0 indicates IPv4, 1 indicates IPv6.
+            ty @ (0x3d7e90 | 0x3d7e91) => FrameType::AddAddress(ty as u8 & 0b1),
+            ty @ (0x3d7e92 | 0x3d7e93) => FrameType::PunchMeNow(ty as u8 & 0b1),
             // May be extension frame
             _ => return Err(Self::Error::InvalidType(frame_type)),
         })
@@ -335,6 +423,19 @@ impl From<FrameType> for VarInt {
             FrameType::ConnectionClose(layer) => VarInt::from(0x1c | layer),
             FrameType::HandshakeDone => VarInt::from_u32(0x1e),
             FrameType::Datagram(with_len) => VarInt::from(0x30 | with_len),
+            FrameType::PathAck(ecn) => VarInt::from_u32(0x15228c00 | ecn as u32),
+            FrameType::PathAbandon => VarInt::from_u32(0x15228c05),
+            FrameType::PathStatus(available) => VarInt::from_u32(0x15228c07 + available as u32),
+            FrameType::PathNewConnectionId => VarInt::from_u32(0x15228c09),
+            FrameType::PathRetireConnectionId => VarInt::from_u32(0x15228c0a),
+            FrameType::MaxPathId => VarInt::from_u32(0x15228c0c),
+            FrameType::PathsBlocked => VarInt::from_u32(0x15228c0d),
+            FrameType::PathCidsBlocked => VarInt::from_u32(0x15228c0e),
+            FrameType::AckFrequency => VarInt::from_u32(0xaf),
+            FrameType::ImmediateAck => VarInt::from_u32(0x1f),
+            FrameType::AddAddress(family) => VarInt::from_u32(0x3d7e90 | family as u32),
+            FrameType::PunchMeNow(family) => VarInt::from_u32(0x3d7e92 | family as u32),
+            FrameType::Extension(ty) => ty.value(),
         }
     }
 }
@@ -387,6 +488,30 @@ pub enum ReliableFrame {
     HandshakeDone(HandshakeDoneFrame),
     /// STREAM control frame, see [`StreamCtlFrame`].
     StreamCtl(StreamCtlFrame),
+    /// PATH_ABANDON frame, see [`PathAbandonFrame`].
+    PathAbandon(PathAbandonFrame),
+    /// PATH_STATUS frame, see [`PathStatusFrame`].
+    PathStatus(PathStatusFrame),
+    /// PATH_NEW_CONNECTION_ID frame, see [`PathNewConnectionIdFrame`].
+    PathNewConnectionId(PathNewConnectionIdFrame),
+    /// PATH_RETIRE_CONNECTION_ID frame, see [`PathRetireConnectionIdFrame`].
+    PathRetireConnectionId(PathRetireConnectionIdFrame),
+    /// MAX_PATH_ID frame, see [`MaxPathIdFrame`].
+    MaxPathId(MaxPathIdFrame),
+    /// PATHS_BLOCKED frame, see [`PathsBlockedFrame`].
+    PathsBlocked(PathsBlockedFrame),
+    /// PATH_CIDS_BLOCKED frame, see [`PathCidsBlockedFrame`].
+    PathCidsBlocked(PathCidsBlockedFrame),
+    /// ACK_FREQUENCY frame, see [`AckFrequencyFrame`].
+    AckFrequency(AckFrequencyFrame),
+    /// IMMEDIATE_ACK frame, see [`ImmediateAckFrame`].
+    ImmediateAck(ImmediateAckFrame),
+    /// ADD_ADDRESS frame, see [`AddAddressFrame`].
+    AddAddress(AddAddressFrame),
+    /// PUNCH_ME_NOW frame, see [`PunchMeNowFrame`].
+    PunchMeNow(PunchMeNowFrame),
+    /// Extension frame, see [`ExtensionFrame`].
+    Extension(ExtensionFrame),
 }
 
 /// Sum type of all the frames.
@@ -426,6 +551,32 @@ pub enum Frame<D = Bytes> {
     Crypto(CryptoFrame, D),
     /// DATAGRAM frame and its data, see [`DatagramFrame`].
     Datagram(DatagramFrame, D),
+    /// PATH_ACK frame, see [`PathAckFrame`].
+    PathAck(PathAckFrame),
+    /// PATH_ABANDON frame, see [`PathAbandonFrame`].
+    PathAbandon(PathAbandonFrame),
+    /// PATH_STATUS frame, see [`PathStatusFrame`].
+    PathStatus(PathStatusFrame),
+    /// PATH_NEW_CONNECTION_ID frame, see [`PathNewConnectionIdFrame`].
+    PathNewConnectionId(PathNewConnectionIdFrame),
+    /// PATH_RETIRE_CONNECTION_ID frame, see [`PathRetireConnectionIdFrame`].
+    PathRetireConnectionId(PathRetireConnectionIdFrame),
+    /// MAX_PATH_ID frame, see [`MaxPathIdFrame`].
+    MaxPathId(MaxPathIdFrame),
+    /// PATHS_BLOCKED frame, see [`PathsBlockedFrame`].
+    PathsBlocked(PathsBlockedFrame),
+    /// PATH_CIDS_BLOCKED frame, see [`PathCidsBlockedFrame`].
+    PathCidsBlocked(PathCidsBlockedFrame),
+    /// ACK_FREQUENCY frame, see [`AckFrequencyFrame`].
+    AckFrequency(AckFrequencyFrame),
+    /// IMMEDIATE_ACK frame, see [`ImmediateAckFrame`].
+    ImmediateAck(ImmediateAckFrame),
+    /// ADD_ADDRESS frame, see [`AddAddressFrame`].
+    AddAddress(AddAddressFrame),
+    /// PUNCH_ME_NOW frame, see [`PunchMeNowFrame`].
+    PunchMeNow(PunchMeNowFrame),
+    /// Extension frame, see [`ExtensionFrame`].
+    Extension(ExtensionFrame),
 }
 
 impl<D> From<ReliableFrame> for Frame<D> {
@@ -447,6 +598,24 @@ impl<D> From<ReliableFrame> for Frame<D> {
                 Frame::HandshakeDone(handshake_done_frame)
             }
             ReliableFrame::StreamCtl(stream_frame) => Frame::StreamCtl(stream_frame),
+            ReliableFrame::PathAbandon(path_abandon_frame) => {
+                Frame::PathAbandon(path_abandon_frame)
+            }
+            ReliableFrame::PathStatus(path_status_frame) => Frame::PathStatus(path_status_frame),
+            ReliableFrame::PathNewConnectionId(frame) => Frame::PathNewConnectionId(frame),
+            ReliableFrame::PathRetireConnectionId(frame) => Frame::PathRetireConnectionId(frame),
+            ReliableFrame::MaxPathId(frame) => Frame::MaxPathId(frame),
+            ReliableFrame::PathsBlocked(frame) => Frame::PathsBlocked(frame),
+            ReliableFrame::PathCidsBlocked(frame) => Frame::PathCidsBlocked(frame),
+            ReliableFrame::AckFrequency(ack_frequency_frame) => {
+                Frame::AckFrequency(ack_frequency_frame)
+            }
+            ReliableFrame::ImmediateAck(immediate_ack_frame) => {
+                Frame::ImmediateAck(immediate_ack_frame)
+            }
+            ReliableFrame::AddAddress(add_address_frame) => Frame::AddAddress(add_address_frame),
+            ReliableFrame::PunchMeNow(punch_me_now_frame) => Frame::PunchMeNow(punch_me_now_frame),
+            ReliableFrame::Extension(extension_frame) => Frame::Extension(extension_frame),
         }
     }
 }
@@ -486,6 +655,34 @@ impl<'f, D> TryFrom<&'f Frame<D>> for ReliableFrame {
                 Ok(ReliableFrame::HandshakeDone(*handshake_done_frame))
             }
             Frame::StreamCtl(stream_frame) => Ok(ReliableFrame::StreamCtl(*stream_frame)),
+            Frame::PathAbandon(path_abandon_frame) => {
+                Ok(ReliableFrame::PathAbandon(*path_abandon_frame))
+            }
+            Frame::PathStatus(path_status_frame) => {
+                Ok(ReliableFrame::PathStatus(*path_status_frame))
+            }
+            Frame::PathNewConnectionId(frame) => Ok(ReliableFrame::PathNewConnectionId(*frame)),
+            Frame::PathRetireConnectionId(frame) => {
+                Ok(ReliableFrame::PathRetireConnectionId(*frame))
+            }
+            Frame::MaxPathId(frame) => Ok(ReliableFrame::MaxPathId(*frame)),
+            Frame::PathsBlocked(frame) => Ok(ReliableFrame::PathsBlocked(*frame)),
+            Frame::PathCidsBlocked(frame) => Ok(ReliableFrame::PathCidsBlocked(*frame)),
+            Frame::AckFrequency(ack_frequency_frame) => {
+                Ok(ReliableFrame::AckFrequency(*ack_frequency_frame))
+            }
+            Frame::ImmediateAck(immediate_ack_frame) => {
+                Ok(ReliableFrame::ImmediateAck(*immediate_ack_frame))
+            }
+            Frame::AddAddress(add_address_frame) => {
+                Ok(ReliableFrame::AddAddress(*add_address_frame))
+            }
+            Frame::PunchMeNow(punch_me_now_frame) => {
+                Ok(ReliableFrame::PunchMeNow(*punch_me_now_frame))
+            }
+            Frame::Extension(extension_frame) => {
+                Ok(ReliableFrame::Extension(extension_frame.clone()))
+            }
             frame => Err(frame),
         }
     }
@@ -512,6 +709,19 @@ impl<D> GetFrameType for Frame<D> {
             Frame::Stream(f, _) => f.frame_type(),
             Frame::Crypto(f, _) => f.frame_type(),
             Frame::Datagram(f, _) => f.frame_type(),
+            Frame::PathAck(f) => f.frame_type(),
+            Frame::PathAbandon(f) => f.frame_type(),
+            Frame::PathStatus(f) => f.frame_type(),
+            Frame::PathNewConnectionId(f) => f.frame_type(),
+            Frame::PathRetireConnectionId(f) => f.frame_type(),
+            Frame::MaxPathId(f) => f.frame_type(),
+            Frame::PathsBlocked(f) => f.frame_type(),
+            Frame::PathCidsBlocked(f) => f.frame_type(),
+            Frame::AckFrequency(f) => f.frame_type(),
+            Frame::ImmediateAck(f) => f.frame_type(),
+            Frame::AddAddress(f) => f.frame_type(),
+            Frame::PunchMeNow(f) => f.frame_type(),
+            Frame::Extension(f) => f.frame_type(),
         }
     }
 }
@@ -544,6 +754,19 @@ impl<D> EncodeSize for Frame<D> {
             Frame::Stream(f, _) => f.max_encoding_size(),
             Frame::Crypto(f, _) => f.max_encoding_size(),
             Frame::Datagram(f, _) => f.max_encoding_size(),
+            Frame::PathAck(f) => f.max_encoding_size(),
+            Frame::PathAbandon(f) => f.max_encoding_size(),
+            Frame::PathStatus(f) => f.max_encoding_size(),
+            Frame::PathNewConnectionId(f) => f.max_encoding_size(),
+            Frame::PathRetireConnectionId(f) => f.max_encoding_size(),
+            Frame::MaxPathId(f) => f.max_encoding_size(),
+            Frame::PathsBlocked(f) => f.max_encoding_size(),
+            Frame::PathCidsBlocked(f) => f.max_encoding_size(),
+            Frame::AckFrequency(f) => f.max_encoding_size(),
+            Frame::ImmediateAck(f) => f.max_encoding_size(),
+            Frame::AddAddress(f) => f.max_encoding_size(),
+            Frame::PunchMeNow(f) => f.max_encoding_size(),
+            Frame::Extension(f) => f.max_encoding_size(),
         }
     }
 
@@ -567,6 +790,19 @@ impl<D> EncodeSize for Frame<D> {
             Frame::Stream(f, _) => f.encoding_size(),
             Frame::Crypto(f, _) => f.encoding_size(),
             Frame::Datagram(f, _) => f.encoding_size(),
+            Frame::PathAck(f) => f.encoding_size(),
+            Frame::PathAbandon(f) => f.encoding_size(),
+            Frame::PathStatus(f) => f.encoding_size(),
+            Frame::PathNewConnectionId(f) => f.encoding_size(),
+            Frame::PathRetireConnectionId(f) => f.encoding_size(),
+            Frame::MaxPathId(f) => f.encoding_size(),
+            Frame::PathsBlocked(f) => f.encoding_size(),
+            Frame::PathCidsBlocked(f) => f.encoding_size(),
+            Frame::AckFrequency(f) => f.encoding_size(),
+            Frame::ImmediateAck(f) => f.encoding_size(),
+            Frame::AddAddress(f) => f.encoding_size(),
+            Frame::PunchMeNow(f) => f.encoding_size(),
+            Frame::Extension(f) => f.encoding_size(),
         }
     }
 }
@@ -600,6 +836,7 @@ pub struct FrameReader {
     #[deref_mut]
     payload: Bytes,
     packet_type: Type,
+    extensions: ExtensionFrames,
 }
 
 impl FrameReader {
@@ -608,8 +845,15 @@ impl FrameReader {
         Self {
             payload,
             packet_type,
+            extensions: ExtensionFrames::default(),
         }
     }
+
+    /// Also read the extension frames of the types registered in `extensions`.
+    pub fn with_extensions(mut self, extensions: ExtensionFrames) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Iterator for FrameReader {
@@ -620,7 +864,7 @@ impl Iterator for FrameReader {
             return None;
         }
 
-        match io::be_frame(&self.payload, self.packet_type) {
+        match io::be_frame(&self.payload, self.packet_type, &self.extensions) {
             Ok((consumed, frame, frame_type)) => {
                 self.payload.advance(consumed);
                 Some(Ok((frame, frame_type)))
@@ -653,6 +897,18 @@ impl<T: BufMut> WriteFrame<ReliableFrame> for T {
             ReliableFrame::RetireConnectionId(frame) => self.put_frame(frame),
             ReliableFrame::HandshakeDone(frame) => self.put_frame(frame),
             ReliableFrame::StreamCtl(frame) => self.put_frame(frame),
+            ReliableFrame::PathAbandon(frame) => self.put_frame(frame),
+            ReliableFrame::PathStatus(frame) => self.put_frame(frame),
+            ReliableFrame::PathNewConnectionId(frame) => self.put_frame(frame),
+            ReliableFrame::PathRetireConnectionId(frame) => self.put_frame(frame),
+            ReliableFrame::MaxPathId(frame) => self.put_frame(frame),
+            ReliableFrame::PathsBlocked(frame) => self.put_frame(frame),
+            ReliableFrame::PathCidsBlocked(frame) => self.put_frame(frame),
+            ReliableFrame::AckFrequency(frame) => self.put_frame(frame),
+            ReliableFrame::ImmediateAck(frame) => self.put_frame(frame),
+            ReliableFrame::AddAddress(frame) => self.put_frame(frame),
+            ReliableFrame::PunchMeNow(frame) => self.put_frame(frame),
+            ReliableFrame::Extension(frame) => self.put_frame(frame),
         }
     }
 }
@@ -685,6 +941,22 @@ mod tests {
             FrameType::ConnectionClose(0),
             FrameType::HandshakeDone,
             FrameType::Datagram(0),
+            FrameType::PathAck(0),
+            FrameType::PathAck(1),
+            FrameType::PathAbandon,
+            FrameType::PathStatus(0),
+            FrameType::PathStatus(1),
+            FrameType::PathNewConnectionId,
+            FrameType::PathRetireConnectionId,
+            FrameType::MaxPathId,
+            FrameType::PathsBlocked,
+            FrameType::PathCidsBlocked,
+            FrameType::AckFrequency,
+            FrameType::ImmediateAck,
+            FrameType::AddAddress(0),
+            FrameType::AddAddress(1),
+            FrameType::PunchMeNow(0),
+            FrameType::PunchMeNow(1),
         ];
 
         for frame_type in frame_types {
@@ -712,6 +984,15 @@ mod tests {
         assert!(FrameType::Ping.belongs_to(initial));
         assert!(FrameType::Ack(0).belongs_to(initial));
         assert!(!FrameType::Stream(0).belongs_to(initial));
+
+        let one_rtt = Type::Short(OneRtt(0.into()));
+        assert!(FrameType::PathAck(0).belongs_to(one_rtt));
+        assert!(!FrameType::PathAbandon.belongs_to(initial));
+        assert!(!FrameType::PathStatus(1).belongs_to(Type::Long(V1(Ver1::ZERO_RTT))));
+        assert!(FrameType::AckFrequency.belongs_to(one_rtt));
+        assert!(!FrameType::ImmediateAck.belongs_to(initial));
+        assert!(FrameType::AddAddress(1).belongs_to(one_rtt));
+        assert!(!FrameType::PunchMeNow(0).belongs_to(Type::Long(V1(Ver1::ZERO_RTT))));
     }
 
     #[test]
@@ -847,4 +1128,77 @@ mod tests {
 
         assert_eq!(padding_count, 2);
     }
+
+    #[test]
+    fn test_registered_extension_frame() {
+        // a frame which body is a length-prefixed bytes
+        let ty = ExtensionFrameType::new(VarInt::from_u32(0xfe)).with_ack_eliciting(false);
+        let extensions = ExtensionFrames::new().with_frame(ExtensionFrameDef::new(ty, |body| {
+            let (remain, len) = be_varint(body).ok()?;
+            Some(body.len() - remain.len() + len.into_inner() as usize)
+        }));
+        let frame = ExtensionFrame::new(ty, Bytes::from_static(b"\x05hello"));
+        assert_eq!(frame.encoding_size(), 8);
+        assert_eq!(frame.specs(), Spec::NonAckEliciting as u8);
+
+        let mut buf = bytes::BytesMut::new();
+        buf.put_frame(&frame);
+        buf.put_frame(&PingFrame);
+        let packet_type = Type::Short(OneRtt(0.into()));
+
+        let mut reader =
+            FrameReader::new(buf.clone().freeze(), packet_type).with_extensions(extensions.clone());
+        assert_eq!(
+            reader.next(),
+            Some(Ok((
+                Frame::Extension(frame.clone()),
+                FrameType::Extension(ty)
+            )))
+        );
+        assert_eq!(
+            reader.next(),
+            Some(Ok((Frame::Ping(PingFrame), FrameType::Ping)))
+        );
+        assert_eq!(reader.next(), None);
+
+        // not allowed in the handshake packets
+        let mut reader = FrameReader::new(buf.freeze(), Type::Long(V1(Ver1::HANDSHAKE)))
+            .with_extensions(extensions.clone());
+        assert_eq!(
+            reader.next(),
+            Some(Err(Error::WrongType(
+                FrameType::Extension(ty),
+                Type::Long(V1(Ver1::HANDSHAKE))
+            )))
+        );
+
+        // the frame type is not minimally encoded
+        let mut buf = bytes::BytesMut::new();
+        buf.put_slice(&[0x80, 0x00, 0x00, 0xfe]);
+        buf.put_slice(b"\x05hello");
+        buf.put_frame(&PingFrame);
+        let mut reader =
+            FrameReader::new(buf.freeze(), packet_type).with_extensions(extensions.clone());
+        assert_eq!(
+            reader.next(),
+            Some(Ok((
+                Frame::Extension(frame.clone()),
+                FrameType::Extension(ty)
+            )))
+        );
+        assert_eq!(
+            reader.next(),
+            Some(Ok((Frame::Ping(PingFrame), FrameType::Ping)))
+        );
+
+        // the body is truncated
+        let mut buf = bytes::BytesMut::new();
+        buf.put_varint(&VarInt::from_u32(0xfe));
+        buf.put_slice(b"\x05hel");
+        let mut reader = FrameReader::new(buf.freeze(), packet_type).with_extensions(extensions);
+        assert!(matches!(
+            reader.next(),
+            Some(Err(Error::IncompleteFrame(FrameType::Extension(_), _)))
+        ));
+    }
 }
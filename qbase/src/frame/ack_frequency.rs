@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// ACK_FREQUENCY frame.
+///
+/// ```text
+/// ACK_FREQUENCY Frame {
+///   Type (i) = 0xaf,
+///   Sequence Number (i),
+///   Ack-Eliciting Threshold (i),
+///   Requested Max Ack Delay (i),
+///   Reordering Threshold (i),
+/// }
+/// ```
+///
+/// The sender asks the peer to acknowledge after receiving more than `Ack-Eliciting Threshold`
+/// ack-eliciting packets, or after `Requested Max Ack Delay` microseconds, whichever comes first.
+/// A `Reordering Threshold` of 0 means the peer need not acknowledge out-of-order packets
+/// immediately.
+///
+/// See [ACK_FREQUENCY Frame](https://www.ietf.org/archive/id/draft-ietf-quic-ack-frequency-11.html#name-ack_frequency-frame)
+/// of [QUIC Acknowledgment Frequency](https://datatracker.ietf.org/doc/draft-ietf-quic-ack-frequency/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AckFrequencyFrame {
+    sequence: VarInt,
+    ack_eliciting_threshold: VarInt,
+    request_max_ack_delay: VarInt,
+    reordering_threshold: VarInt,
+}
+
+const ACK_FREQUENCY_FRAME_TYPE: u8 = 0xaf;
+
+impl super::GetFrameType for AckFrequencyFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::AckFrequency
+    }
+}
+
+impl super::EncodeSize for AckFrequencyFrame {
+    fn max_encoding_size(&self) -> usize {
+        2 + 8 + 8 + 8 + 8
+    }
+
+    fn encoding_size(&self) -> usize {
+        2 + self.sequence.encoding_size()
+            + self.ack_eliciting_threshold.encoding_size()
+            + self.request_max_ack_delay.encoding_size()
+            + self.reordering_threshold.encoding_size()
+    }
+}
+
+impl AckFrequencyFrame {
+    /// Create a new [`AckFrequencyFrame`], the `request_max_ack_delay` is in microseconds.
+    pub fn new(
+        sequence: VarInt,
+        ack_eliciting_threshold: VarInt,
+        request_max_ack_delay: VarInt,
+        reordering_threshold: VarInt,
+    ) -> Self {
+        Self {
+            sequence,
+            ack_eliciting_threshold,
+            request_max_ack_delay,
+            reordering_threshold,
+        }
+    }
+
+    /// Return the sequence number, only the frame with the largest sequence number takes effect.
+    pub fn sequence(&self) -> u64 {
+        self.sequence.into_inner()
+    }
+
+    /// Return the number of ack-eliciting packets the peer may receive without acknowledging.
+    pub fn ack_eliciting_threshold(&self) -> u64 {
+        self.ack_eliciting_threshold.into_inner()
+    }
+
+    /// Return the maximum time the peer may delay acknowledging an ack-eliciting packet.
+    pub fn request_max_ack_delay(&self) -> Duration {
+        Duration::from_micros(self.request_max_ack_delay.into_inner())
+    }
+
+    /// Return the packet number gap that makes the peer acknowledge immediately,
+    /// 0 to never acknowledge out-of-order packets immediately.
+    pub fn reordering_threshold(&self) -> u64 {
+        self.reordering_threshold.into_inner()
+    }
+}
+
+/// Parse an ACK_FREQUENCY frame from the input buffer,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn be_ack_frequency_frame(input: &[u8]) -> nom::IResult<&[u8], AckFrequencyFrame> {
+    use nom::{Parser, combinator::map};
+    map(
+        (be_varint, be_varint, be_varint, be_varint),
+        |(sequence, ack_eliciting_threshold, request_max_ack_delay, reordering_threshold)| {
+            AckFrequencyFrame::new(
+                sequence,
+                ack_eliciting_threshold,
+                request_max_ack_delay,
+                reordering_threshold,
+            )
+        },
+    )
+    .parse(input)
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<AckFrequencyFrame> for T {
+    fn put_frame(&mut self, frame: &AckFrequencyFrame) {
+        self.put_varint(&VarInt::from(ACK_FREQUENCY_FRAME_TYPE));
+        self.put_varint(&frame.sequence);
+        self.put_varint(&frame.ack_eliciting_threshold);
+        self.put_varint(&frame.request_max_ack_delay);
+        self.put_varint(&frame.reordering_threshold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{AckFrequencyFrame, be_ack_frequency_frame};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    fn frame() -> AckFrequencyFrame {
+        AckFrequencyFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(9),
+            VarInt::from_u32(25_000),
+            VarInt::from_u32(0),
+        )
+    }
+
+    #[test]
+    fn test_ack_frequency_frame() {
+        let frame = frame();
+        assert_eq!(frame.frame_type(), FrameType::AckFrequency);
+        assert_eq!(frame.max_encoding_size(), 2 + 8 + 8 + 8 + 8);
+        assert_eq!(frame.encoding_size(), 2 + 1 + 1 + 4 + 1);
+        assert_eq!(frame.sequence(), 1);
+        assert_eq!(frame.ack_eliciting_threshold(), 9);
+        assert_eq!(frame.request_max_ack_delay(), Duration::from_millis(25));
+        assert_eq!(frame.reordering_threshold(), 0);
+    }
+
+    #[test]
+    fn test_read_ack_frequency_frame() {
+        let buf = vec![0x01, 0x09, 0x80, 0x00, 0x61, 0xa8, 0x00];
+        let (remain, parsed) = be_ack_frequency_frame(&buf).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(parsed, frame());
+    }
+
+    #[test]
+    fn test_write_ack_frequency_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&frame());
+        assert_eq!(
+            buf,
+            vec![0x40, 0xaf, 0x01, 0x09, 0x80, 0x00, 0x61, 0xa8, 0x00]
+        );
+    }
+}
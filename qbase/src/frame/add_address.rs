@@ -0,0 +1,167 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use nom::Parser;
+
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// ADD_ADDRESS frame.
+///
+/// ```text
+/// ADD_ADDRESS Frame {
+///   Type (i) = 0x3d7e90..0x3d7e91,
+///   Sequence Number (i),
+///   [ IPv4 (32) ],
+///   [ IPv6 (128) ],
+///   Port (16),
+/// }
+/// ```
+///
+/// Advertises an address the sender can be reached at, a candidate for hole punching. The least
+/// significant bit of the frame type tells the family of the address.
+///
+/// See [ADD_ADDRESS Frame](https://www.ietf.org/archive/id/draft-seemann-quic-nat-traversal-02.html#name-add_address-frame)
+/// of [Using QUIC to traverse NATs](https://datatracker.ietf.org/doc/draft-seemann-quic-nat-traversal/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddAddressFrame {
+    sequence: VarInt,
+    addr: SocketAddr,
+}
+
+const ADD_ADDRESS_FRAME_TYPE: u32 = 0x3d7e90;
+
+impl super::GetFrameType for AddAddressFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::AddAddress(self.addr.is_ipv6() as u8)
+    }
+}
+
+impl super::EncodeSize for AddAddressFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8 + 16 + 2
+    }
+
+    fn encoding_size(&self) -> usize {
+        4 + self.sequence.encoding_size() + socket_addr_size(&self.addr)
+    }
+}
+
+impl AddAddressFrame {
+    /// Create a new [`AddAddressFrame`].
+    pub fn new(sequence: VarInt, addr: SocketAddr) -> Self {
+        Self { sequence, addr }
+    }
+
+    /// Return the sequence number of the address, which PUNCH_ME_NOW frames refer to.
+    pub fn sequence(&self) -> VarInt {
+        self.sequence
+    }
+
+    /// Return the advertised address.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+pub(super) fn socket_addr_size(addr: &SocketAddr) -> usize {
+    match addr {
+        SocketAddr::V4(_) => 4 + 2,
+        SocketAddr::V6(_) => 16 + 2,
+    }
+}
+
+/// Parse a socket address of the family given by the frame type, 0 for IPv4 and 1 for IPv6.
+pub(super) fn socket_addr_of_family(
+    family: u8,
+) -> impl Fn(&[u8]) -> nom::IResult<&[u8], SocketAddr> {
+    use nom::{
+        bytes::streaming::take,
+        combinator::map,
+        number::streaming::{be_u16, be_u32},
+    };
+    move |input| match family {
+        0 => map((be_u32, be_u16), |(ip, port)| {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::from(ip)), port)
+        })
+        .parse(input),
+        _ => map((take(16usize), be_u16), |(ip, port): (&[u8], u16)| {
+            let ip: [u8; 16] = ip.try_into().expect("16 bytes are taken");
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::from(ip)), port)
+        })
+        .parse(input),
+    }
+}
+
+pub(super) fn put_socket_addr(buf: &mut impl bytes::BufMut, addr: &SocketAddr) {
+    match addr.ip() {
+        IpAddr::V4(ip) => buf.put_slice(&ip.octets()),
+        IpAddr::V6(ip) => buf.put_slice(&ip.octets()),
+    }
+    buf.put_u16(addr.port());
+}
+
+/// Return a parser for an ADD_ADDRESS frame with the family flag,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn add_address_frame_with_flag(
+    family: u8,
+) -> impl Fn(&[u8]) -> nom::IResult<&[u8], AddAddressFrame> {
+    move |input| {
+        (be_varint, socket_addr_of_family(family))
+            .map(|(sequence, addr)| AddAddressFrame::new(sequence, addr))
+            .parse(input)
+    }
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<AddAddressFrame> for T {
+    fn put_frame(&mut self, frame: &AddAddressFrame) {
+        let frame_type = ADD_ADDRESS_FRAME_TYPE | frame.addr.is_ipv6() as u32;
+        self.put_varint(&VarInt::from_u32(frame_type));
+        self.put_varint(&frame.sequence);
+        put_socket_addr(self, &frame.addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddAddressFrame, add_address_frame_with_flag};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    #[test]
+    fn test_add_address_frame() {
+        let frame = AddAddressFrame::new(VarInt::from_u32(1), "1.2.3.4:443".parse().unwrap());
+        assert_eq!(frame.frame_type(), FrameType::AddAddress(0));
+        assert_eq!(frame.max_encoding_size(), 4 + 8 + 16 + 2);
+        assert_eq!(frame.encoding_size(), 4 + 1 + 4 + 2);
+
+        let frame = AddAddressFrame::new(VarInt::from_u32(1), "[::1]:443".parse().unwrap());
+        assert_eq!(frame.frame_type(), FrameType::AddAddress(1));
+        assert_eq!(frame.encoding_size(), 4 + 1 + 16 + 2);
+    }
+
+    #[test]
+    fn test_read_add_address_frame() {
+        let buf = vec![0x01, 1, 2, 3, 4, 0x01, 0xbb];
+        let (remain, frame) = add_address_frame_with_flag(0)(&buf).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(
+            frame,
+            AddAddressFrame::new(VarInt::from_u32(1), "1.2.3.4:443".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_write_add_address_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&AddAddressFrame::new(
+            VarInt::from_u32(1),
+            "[::1]:443".parse().unwrap(),
+        ));
+        let mut expected = vec![0x80, 0x3d, 0x7e, 0x91, 0x01];
+        expected.extend_from_slice(&std::net::Ipv6Addr::LOCALHOST.octets());
+        expected.extend_from_slice(&[0x01, 0xbb]);
+        assert_eq!(buf, expected);
+    }
+}
@@ -0,0 +1,248 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use bytes::Bytes;
+
+use crate::{
+    error::QuicError,
+    varint::{VarInt, WriteVarInt},
+};
+
+/// The type of an extension frame, and how the transport treats the frames of this type.
+///
+/// Extension frames are ack-eliciting and retransmittable by default, like most of the frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExtensionFrameType {
+    value: VarInt,
+    ack_eliciting: bool,
+    retransmittable: bool,
+}
+
+impl ExtensionFrameType {
+    /// Create an ack-eliciting and retransmittable extension frame type.
+    pub fn new(value: VarInt) -> Self {
+        Self {
+            value,
+            ack_eliciting: true,
+            retransmittable: true,
+        }
+    }
+
+    /// Set whether the packets carrying the frames of this type are ack-eliciting.
+    pub fn with_ack_eliciting(mut self, ack_eliciting: bool) -> Self {
+        self.ack_eliciting = ack_eliciting;
+        self
+    }
+
+    /// Set whether the frames of this type are sent again if the packets carrying them are lost.
+    pub fn with_retransmittable(mut self, retransmittable: bool) -> Self {
+        self.retransmittable = retransmittable;
+        self
+    }
+
+    /// Return the value of the frame type on the wire.
+    pub fn value(&self) -> VarInt {
+        self.value
+    }
+
+    /// Return whether the packets carrying the frames of this type are ack-eliciting.
+    pub fn is_ack_eliciting(&self) -> bool {
+        self.ack_eliciting
+    }
+
+    /// Return whether the frames of this type are sent again if they are lost.
+    pub fn is_retransmittable(&self) -> bool {
+        self.retransmittable
+    }
+}
+
+/// An extension frame, a frame of the type registered in [`ExtensionFrames`].
+///
+/// ```text
+/// Extension Frame {
+///   Type (i),
+///   Body (..),
+/// }
+/// ```
+///
+/// The body is opaque to the transport, it is encoded and decoded by the application. The end of
+/// the body is found by the [`ExtensionFrameDef`] of the frame type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionFrame {
+    frame_type: ExtensionFrameType,
+    body: Bytes,
+}
+
+impl ExtensionFrame {
+    /// Create a new [`ExtensionFrame`] with the encoded body.
+    pub fn new(frame_type: ExtensionFrameType, body: Bytes) -> Self {
+        Self { frame_type, body }
+    }
+
+    /// Return the type of the extension frame.
+    pub fn extension_type(&self) -> ExtensionFrameType {
+        self.frame_type
+    }
+
+    /// Return the encoded body of the frame.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+}
+
+impl super::GetFrameType for ExtensionFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::Extension(self.frame_type)
+    }
+}
+
+impl super::EncodeSize for ExtensionFrame {
+    fn max_encoding_size(&self) -> usize {
+        self.encoding_size()
+    }
+
+    fn encoding_size(&self) -> usize {
+        self.frame_type.value.encoding_size() + self.body.len()
+    }
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<ExtensionFrame> for T {
+    fn put_frame(&mut self, frame: &ExtensionFrame) {
+        self.put_varint(&frame.frame_type.value);
+        self.put_slice(&frame.body);
+    }
+}
+
+type BodyLen = dyn Fn(&[u8]) -> Option<usize> + Send + Sync;
+type Handler =
+    dyn Fn(&ExtensionFrame, &dyn Fn(ExtensionFrame)) -> Result<(), QuicError> + Send + Sync;
+
+/// The definition of an extension frame type: how to find the end of the frames in a packet,
+/// and what to do when one is received.
+#[derive(Clone)]
+pub struct ExtensionFrameDef {
+    frame_type: ExtensionFrameType,
+    body_len: Arc<BodyLen>,
+    handler: Arc<Handler>,
+}
+
+impl ExtensionFrameDef {
+    /// Define an extension frame type.
+    ///
+    /// `body_len` is given the bytes following the frame type, to the end of the packet, and
+    /// returns the length of the frame body, or `None` if the frame is malformed, which is a
+    /// connection error of type FRAME_ENCODING_ERROR.
+    ///
+    /// The received frames are ignored until a handler is set by [`ExtensionFrameDef::with_handler`].
+    pub fn new(
+        frame_type: ExtensionFrameType,
+        body_len: impl Fn(&[u8]) -> Option<usize> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            frame_type,
+            body_len: Arc::new(body_len),
+            handler: Arc::new(|_, _| Ok(())),
+        }
+    }
+
+    /// Set the handler of the received frames.
+    ///
+    /// The handler is given the frame, and a function to send extension frames back on the same
+    /// connection. Returning an error closes the connection with that error.
+    pub fn with_handler(
+        mut self,
+        handler: impl Fn(&ExtensionFrame, &dyn Fn(ExtensionFrame)) -> Result<(), QuicError>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.handler = Arc::new(handler);
+        self
+    }
+
+    /// Return the type of the extension frame.
+    pub fn frame_type(&self) -> ExtensionFrameType {
+        self.frame_type
+    }
+
+    /// Return the length of the frame body at the beginning of the `input`.
+    pub fn body_len(&self, input: &[u8]) -> Option<usize> {
+        (self.body_len)(input)
+    }
+
+    /// Handle a received frame, frames sent by `reply` go to the peer of the frame.
+    pub fn handle(
+        &self,
+        frame: &ExtensionFrame,
+        reply: &dyn Fn(ExtensionFrame),
+    ) -> Result<(), QuicError> {
+        (self.handler)(frame, reply)
+    }
+}
+
+impl fmt::Debug for ExtensionFrameDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionFrameDef")
+            .field("frame_type", &self.frame_type)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The registry of the extension frame types an endpoint understands.
+///
+/// Frames of the unregistered types are still rejected with FRAME_ENCODING_ERROR, as the
+/// transport has no way to tell where they end.
+///
+/// # Example
+///
+/// ```
+/// use qbase::{
+///     frame::{ExtensionFrame, ExtensionFrameDef, ExtensionFrameType, ExtensionFrames},
+///     varint::VarInt,
+/// };
+///
+/// // a frame which body is a single byte, echoed back to the peer
+/// let echo = ExtensionFrameType::new(VarInt::from_u32(0x2ab2));
+/// let extensions = ExtensionFrames::new().with_frame(
+///     ExtensionFrameDef::new(echo, |body| (!body.is_empty()).then_some(1)).with_handler(
+///         move |frame, reply| {
+///             reply(ExtensionFrame::new(echo, frame.body().clone()));
+///             Ok(())
+///         },
+///     ),
+/// );
+/// assert!(extensions.get(VarInt::from_u32(0x2ab2)).is_some());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionFrames(Arc<HashMap<VarInt, ExtensionFrameDef>>);
+
+impl ExtensionFrames {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an extension frame type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame type is one of the frame types the transport knows.
+    pub fn with_frame(mut self, def: ExtensionFrameDef) -> Self {
+        let value = def.frame_type().value();
+        assert!(
+            super::FrameType::try_from(value).is_err(),
+            "frame type {value} is defined by the transport"
+        );
+        Arc::make_mut(&mut self.0).insert(value, def);
+        self
+    }
+
+    /// Return the definition of the extension frame type.
+    pub fn get(&self, frame_type: VarInt) -> Option<&ExtensionFrameDef> {
+        self.0.get(&frame_type)
+    }
+
+    /// Return whether no extension frame type is registered.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
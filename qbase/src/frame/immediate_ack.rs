@@ -0,0 +1,59 @@
+use super::EncodeSize;
+
+/// IMMEDIATE_ACK frame
+///
+/// ```text
+/// IMMEDIATE_ACK Frame {
+///   Type (i) = 0x1f,
+/// }
+/// ```
+///
+/// Asks the peer to send an acknowledgement immediately, regardless of the ACK_FREQUENCY frame.
+///
+/// See [IMMEDIATE_ACK Frame](https://www.ietf.org/archive/id/draft-ietf-quic-ack-frequency-11.html#name-immediate_ack-frame)
+/// of [QUIC Acknowledgment Frequency](https://datatracker.ietf.org/doc/draft-ietf-quic-ack-frequency/)
+/// for more details.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImmediateAckFrame;
+
+const IMMEDIATE_ACK_FRAME_TYPE: u8 = 0x1f;
+
+impl super::GetFrameType for ImmediateAckFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::ImmediateAck
+    }
+}
+
+impl EncodeSize for ImmediateAckFrame {}
+
+/// Parse an IMMEDIATE_ACK frame from the input buffer,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+#[allow(unused)]
+pub fn be_immediate_ack_frame(input: &[u8]) -> nom::IResult<&[u8], ImmediateAckFrame> {
+    Ok((input, ImmediateAckFrame))
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<ImmediateAckFrame> for T {
+    fn put_frame(&mut self, _: &ImmediateAckFrame) {
+        self.put_u8(IMMEDIATE_ACK_FRAME_TYPE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frame::{EncodeSize, FrameType, GetFrameType, ImmediateAckFrame, io::WriteFrame};
+
+    #[test]
+    fn test_immediate_ack_frame() {
+        assert_eq!(ImmediateAckFrame.frame_type(), FrameType::ImmediateAck);
+        assert_eq!(ImmediateAckFrame.max_encoding_size(), 1);
+        assert_eq!(ImmediateAckFrame.encoding_size(), 1);
+    }
+
+    #[test]
+    fn test_write_immediate_ack_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&ImmediateAckFrame);
+        assert_eq!(buf, vec![super::IMMEDIATE_ACK_FRAME_TYPE]);
+    }
+}
@@ -1,15 +1,21 @@
 use bytes::Bytes;
 
 use super::{
-    ack::ack_frame_with_flag, connection_close::connection_close_frame_at_layer,
+    ack::ack_frame_with_flag, ack_frequency::be_ack_frequency_frame,
+    add_address::add_address_frame_with_flag, connection_close::connection_close_frame_at_layer,
     crypto::be_crypto_frame, data_blocked::be_data_blocked_frame,
     datagram::datagram_frame_with_flag, max_data::be_max_data_frame,
-    max_stream_data::be_max_stream_data_frame, max_streams::max_streams_frame_with_dir,
-    new_connection_id::be_new_connection_id_frame, new_token::be_new_token_frame,
-    path_challenge::be_path_challenge_frame, path_response::be_path_response_frame,
-    reset_stream::be_reset_stream_frame, retire_connection_id::be_retire_connection_id_frame,
-    stop_sending::be_stop_sending_frame, stream::stream_frame_with_flag,
-    stream_data_blocked::be_stream_data_blocked_frame,
+    max_path_id::be_max_path_id_frame, max_stream_data::be_max_stream_data_frame,
+    max_streams::max_streams_frame_with_dir, new_connection_id::be_new_connection_id_frame,
+    new_token::be_new_token_frame, path_abandon::be_path_abandon_frame,
+    path_ack::path_ack_frame_with_flag, path_challenge::be_path_challenge_frame,
+    path_cids_blocked::be_path_cids_blocked_frame,
+    path_new_connection_id::be_path_new_connection_id_frame, path_response::be_path_response_frame,
+    path_retire_connection_id::be_path_retire_connection_id_frame,
+    path_status::path_status_frame_with_flag, paths_blocked::be_paths_blocked_frame,
+    punch_me_now::punch_me_now_frame_with_flag, reset_stream::be_reset_stream_frame,
+    retire_connection_id::be_retire_connection_id_frame, stop_sending::be_stop_sending_frame,
+    stream::stream_frame_with_flag, stream_data_blocked::be_stream_data_blocked_frame,
     streams_blocked::streams_blocked_frame_with_dir, *,
 };
 use crate::util::ContinuousData;
@@ -19,10 +25,13 @@ use crate::util::ContinuousData;
 ///
 /// Some frames like [`StreamFrame`] and [`CryptoFrame`] have a data body,
 /// which use `bytes::Bytes` to store.
+///
+/// The body of an [`ExtensionFrame`] is found by its definition in the `extensions`.
 fn complete_frame(
     frame_type: FrameType,
     raw: Bytes,
-) -> impl Fn(&[u8]) -> nom::IResult<&[u8], Frame> {
+    extensions: &ExtensionFrames,
+) -> impl Fn(&[u8]) -> nom::IResult<&[u8], Frame> + '_ {
     use nom::{Parser, combinator::map};
     move |input: &[u8]| match frame_type {
         FrameType::Padding => Ok((input, Frame::Padding(PaddingFrame))),
@@ -43,6 +52,32 @@ fn complete_frame(
         FrameType::HandshakeDone => Ok((input, Frame::HandshakeDone(HandshakeDoneFrame))),
         FrameType::NewToken => map(be_new_token_frame, Frame::NewToken).parse(input),
         FrameType::Ack(ecn) => map(ack_frame_with_flag(ecn), Frame::Ack).parse(input),
+        FrameType::PathAck(ecn) => map(path_ack_frame_with_flag(ecn), Frame::PathAck).parse(input),
+        FrameType::PathAbandon => map(be_path_abandon_frame, Frame::PathAbandon).parse(input),
+        FrameType::PathStatus(flag) => {
+            map(path_status_frame_with_flag(flag), Frame::PathStatus).parse(input)
+        }
+        FrameType::PathNewConnectionId => {
+            map(be_path_new_connection_id_frame, Frame::PathNewConnectionId).parse(input)
+        }
+        FrameType::PathRetireConnectionId => map(
+            be_path_retire_connection_id_frame,
+            Frame::PathRetireConnectionId,
+        )
+        .parse(input),
+        FrameType::MaxPathId => map(be_max_path_id_frame, Frame::MaxPathId).parse(input),
+        FrameType::PathsBlocked => map(be_paths_blocked_frame, Frame::PathsBlocked).parse(input),
+        FrameType::PathCidsBlocked => {
+            map(be_path_cids_blocked_frame, Frame::PathCidsBlocked).parse(input)
+        }
+        FrameType::AckFrequency => map(be_ack_frequency_frame, Frame::AckFrequency).parse(input),
+        FrameType::ImmediateAck => Ok((input, Frame::ImmediateAck(ImmediateAckFrame))),
+        FrameType::AddAddress(family) => {
+            map(add_address_frame_with_flag(family), Frame::AddAddress).parse(input)
+        }
+        FrameType::PunchMeNow(family) => {
+            map(punch_me_now_frame_with_flag(family), Frame::PunchMeNow).parse(input)
+        }
         FrameType::ResetStream => {
             map(be_reset_stream_frame, |f| Frame::StreamCtl(f.into())).parse(input)
         }
@@ -105,33 +140,67 @@ fn complete_frame(
                 }
             }
         }
+        FrameType::Extension(ty) => {
+            let start = raw.len() - input.len();
+            match extensions
+                .get(ty.value())
+                .and_then(|def| def.body_len(input))
+            {
+                Some(len) if len <= input.len() => {
+                    let body = raw.slice(start..start + len);
+                    Ok((
+                        &input[len..],
+                        Frame::Extension(ExtensionFrame::new(ty, body)),
+                    ))
+                }
+                Some(len) => Err(nom::Err::Incomplete(nom::Needed::new(len - input.len()))),
+                None => Err(nom::Err::Incomplete(nom::Needed::Unknown)),
+            }
+        }
     }
 }
 
 /// Parse a frame type from the raw bytes, [nom](https://docs.rs/nom/latest/nom/) parser style.
-pub fn be_frame(raw: &Bytes, packet_type: Type) -> Result<(usize, Frame, FrameType), Error> {
+///
+/// The frame types unknown to the transport are looked up in the `extensions`.
+pub fn be_frame(
+    raw: &Bytes,
+    packet_type: Type,
+    extensions: &ExtensionFrames,
+) -> Result<(usize, Frame, FrameType), Error> {
     let input = raw.as_ref();
-    let (remain, frame_type) = be_frame_type(input)?;
+    // 帧类型可能不是最短编码，剩余部分以实际解析消耗的字节为准
+    let (remain, ty) = crate::varint::be_varint(input).map_err(|_| {
+        Error::IncompleteType(format!("Incomplete frame type from input: {input:?}"))
+    })?;
+    let frame_type = match FrameType::try_from(ty) {
+        Err(Error::InvalidType(ty)) => match extensions.get(ty) {
+            Some(def) => FrameType::Extension(def.frame_type()),
+            None => return Err(Error::InvalidType(ty)),
+        },
+        result => result?,
+    };
     if !frame_type.belongs_to(packet_type) {
         tracing::error!("   Cause by: parsed an wrong frame");
         return Err(Error::WrongType(frame_type, packet_type));
     }
 
-    let (remain, frame) = complete_frame(frame_type, raw.clone())(remain).map_err(|e| match e {
-        ne @ nom::Err::Incomplete(_) => {
-            nom::Err::Error(Error::IncompleteFrame(frame_type, ne.to_string()))
-        }
-        nom::Err::Error(ne) => {
-            // may be TooLarge in MaxStreamsFrame/CryptoFrame/StreamFrame,
-            // or may be Verify in NewConnectionIdFrame,
-            // or may be Alt in ConnectionCloseFrame
-            nom::Err::Error(Error::ParseError(
-                frame_type,
-                ne.code.description().to_owned(),
-            ))
-        }
-        _ => unreachable!("parsing frame never fails"),
-    })?;
+    let (remain, frame) =
+        complete_frame(frame_type, raw.clone(), extensions)(remain).map_err(|e| match e {
+            ne @ nom::Err::Incomplete(_) => {
+                nom::Err::Error(Error::IncompleteFrame(frame_type, ne.to_string()))
+            }
+            nom::Err::Error(ne) => {
+                // may be TooLarge in MaxStreamsFrame/CryptoFrame/StreamFrame,
+                // or may be Verify in NewConnectionIdFrame,
+                // or may be Alt in ConnectionCloseFrame
+                nom::Err::Error(Error::ParseError(
+                    frame_type,
+                    ne.code.description().to_owned(),
+                ))
+            }
+            _ => unreachable!("parsing frame never fails"),
+        })?;
     Ok((input.len() - remain.len(), frame, frame_type))
 }
 
@@ -177,6 +246,33 @@ where
             Frame::Stream(f, d) => buf.put_data_frame(f, d),
             Frame::Crypto(f, d) => buf.put_data_frame(f, d),
             Frame::Datagram(f, d) => buf.put_data_frame(f, d),
+            Frame::PathAck(f) => <&mut B as WriteFrame<PathAckFrame>>::put_frame(&mut buf, f),
+            Frame::PathAbandon(f) => {
+                <&mut B as WriteFrame<PathAbandonFrame>>::put_frame(&mut buf, f)
+            }
+            Frame::PathStatus(f) => <&mut B as WriteFrame<PathStatusFrame>>::put_frame(&mut buf, f),
+            Frame::PathNewConnectionId(f) => {
+                <&mut B as WriteFrame<PathNewConnectionIdFrame>>::put_frame(&mut buf, f)
+            }
+            Frame::PathRetireConnectionId(f) => {
+                <&mut B as WriteFrame<PathRetireConnectionIdFrame>>::put_frame(&mut buf, f)
+            }
+            Frame::MaxPathId(f) => <&mut B as WriteFrame<MaxPathIdFrame>>::put_frame(&mut buf, f),
+            Frame::PathsBlocked(f) => {
+                <&mut B as WriteFrame<PathsBlockedFrame>>::put_frame(&mut buf, f)
+            }
+            Frame::PathCidsBlocked(f) => {
+                <&mut B as WriteFrame<PathCidsBlockedFrame>>::put_frame(&mut buf, f)
+            }
+            Frame::AckFrequency(f) => {
+                <&mut B as WriteFrame<AckFrequencyFrame>>::put_frame(&mut buf, f)
+            }
+            Frame::ImmediateAck(f) => {
+                <&mut B as WriteFrame<ImmediateAckFrame>>::put_frame(&mut buf, f)
+            }
+            Frame::AddAddress(f) => <&mut B as WriteFrame<AddAddressFrame>>::put_frame(&mut buf, f),
+            Frame::PunchMeNow(f) => <&mut B as WriteFrame<PunchMeNowFrame>>::put_frame(&mut buf, f),
+            Frame::Extension(f) => <&mut B as WriteFrame<ExtensionFrame>>::put_frame(&mut buf, f),
         }
     }
 }
@@ -0,0 +1,91 @@
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// MAX_PATH_ID frame.
+///
+/// ```text
+/// MAX_PATH_ID Frame {
+///   Type (i) = 0x15228c0c,
+///   Maximum Path Identifier (i),
+/// }
+/// ```
+///
+/// See [MAX_PATH_ID Frame](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-max_path_id-frame)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxPathIdFrame {
+    max_path_id: VarInt,
+}
+
+const MAX_PATH_ID_FRAME_TYPE: u32 = 0x15228c0c;
+
+impl super::GetFrameType for MaxPathIdFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::MaxPathId
+    }
+}
+
+impl super::EncodeSize for MaxPathIdFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8
+    }
+
+    fn encoding_size(&self) -> usize {
+        4 + self.max_path_id.encoding_size()
+    }
+}
+
+impl MaxPathIdFrame {
+    /// Create a new [`MaxPathIdFrame`].
+    pub fn new(max_path_id: VarInt) -> Self {
+        Self { max_path_id }
+    }
+
+    /// Return the maximum path identifier the peer is allowed to use.
+    pub fn max_path_id(&self) -> VarInt {
+        self.max_path_id
+    }
+}
+
+/// Parse a MAX_PATH_ID frame from the input buffer,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn be_max_path_id_frame(input: &[u8]) -> nom::IResult<&[u8], MaxPathIdFrame> {
+    use nom::{Parser, combinator::map};
+    map(be_varint, MaxPathIdFrame::new).parse(input)
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<MaxPathIdFrame> for T {
+    fn put_frame(&mut self, frame: &MaxPathIdFrame) {
+        self.put_varint(&VarInt::from_u32(MAX_PATH_ID_FRAME_TYPE));
+        self.put_varint(&frame.max_path_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MaxPathIdFrame, be_max_path_id_frame};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    #[test]
+    fn test_max_path_id_frame() {
+        let frame = MaxPathIdFrame::new(VarInt::from_u32(8));
+        assert_eq!(frame.frame_type(), FrameType::MaxPathId);
+        assert_eq!(frame.max_encoding_size(), 4 + 8);
+        assert_eq!(frame.encoding_size(), 4 + 1);
+        assert_eq!(frame.max_path_id(), VarInt::from_u32(8));
+    }
+
+    #[test]
+    fn test_read_write_max_path_id_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&MaxPathIdFrame::new(VarInt::from_u32(8)));
+        assert_eq!(buf, vec![0x95, 0x22, 0x8c, 0x0c, 0x08]);
+
+        let (remain, frame) = be_max_path_id_frame(&buf[4..]).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(frame, MaxPathIdFrame::new(VarInt::from_u32(8)));
+    }
+}
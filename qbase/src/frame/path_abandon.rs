@@ -0,0 +1,116 @@
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// PATH_ABANDON frame.
+///
+/// ```text
+/// PATH_ABANDON Frame {
+///   Type (i) = 0x15228c05,
+///   Path Identifier (i),
+///   Error Code (i),
+/// }
+/// ```
+///
+/// See [PATH_ABANDON Frame](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-path_abandon-frame)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathAbandonFrame {
+    path_id: VarInt,
+    error_code: VarInt,
+}
+
+const PATH_ABANDON_FRAME_TYPE: u32 = 0x15228c05;
+
+impl super::GetFrameType for PathAbandonFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::PathAbandon
+    }
+}
+
+impl super::EncodeSize for PathAbandonFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8 + 8
+    }
+
+    fn encoding_size(&self) -> usize {
+        4 + self.path_id.encoding_size() + self.error_code.encoding_size()
+    }
+}
+
+impl PathAbandonFrame {
+    /// Create a new [`PathAbandonFrame`].
+    pub fn new(path_id: VarInt, error_code: VarInt) -> Self {
+        Self {
+            path_id,
+            error_code,
+        }
+    }
+
+    /// Return the identifier of the abandoned path.
+    pub fn path_id(&self) -> VarInt {
+        self.path_id
+    }
+
+    /// Return the multipath error code explaining why the path is abandoned.
+    pub fn error_code(&self) -> u64 {
+        self.error_code.into_inner()
+    }
+}
+
+/// Parse a PATH_ABANDON frame from the input buffer,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn be_path_abandon_frame(input: &[u8]) -> nom::IResult<&[u8], PathAbandonFrame> {
+    use nom::{Parser, combinator::map};
+    map((be_varint, be_varint), |(path_id, error_code)| {
+        PathAbandonFrame::new(path_id, error_code)
+    })
+    .parse(input)
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<PathAbandonFrame> for T {
+    fn put_frame(&mut self, frame: &PathAbandonFrame) {
+        self.put_varint(&VarInt::from_u32(PATH_ABANDON_FRAME_TYPE));
+        self.put_varint(&frame.path_id);
+        self.put_varint(&frame.error_code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathAbandonFrame, be_path_abandon_frame};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    #[test]
+    fn test_path_abandon_frame() {
+        let frame = PathAbandonFrame::new(VarInt::from_u32(1), VarInt::from_u32(0x1234));
+        assert_eq!(frame.frame_type(), FrameType::PathAbandon);
+        assert_eq!(frame.max_encoding_size(), 4 + 8 + 8);
+        assert_eq!(frame.encoding_size(), 4 + 1 + 2);
+        assert_eq!(frame.path_id(), VarInt::from_u32(1));
+        assert_eq!(frame.error_code(), 0x1234);
+    }
+
+    #[test]
+    fn test_read_path_abandon_frame() {
+        let buf = vec![0x01, 0x52, 0x34];
+        let (remain, frame) = be_path_abandon_frame(&buf).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(
+            frame,
+            PathAbandonFrame::new(VarInt::from_u32(1), VarInt::from_u32(0x1234))
+        );
+    }
+
+    #[test]
+    fn test_write_path_abandon_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&PathAbandonFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(0x1234),
+        ));
+        assert_eq!(buf, vec![0x95, 0x22, 0x8c, 0x05, 0x01, 0x52, 0x34]);
+    }
+}
@@ -0,0 +1,182 @@
+use derive_more::Deref;
+use nom::Parser;
+
+use super::ack::{AckFrame, ack_frame_with_flag, put_ack_fields};
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// PATH_ACK frame.
+///
+/// ```text
+/// PATH_ACK Frame {
+///   Type (i) = 0x15228c00..0x15228c01,
+///   Path Identifier (i),
+///   Largest Acknowledged (i),
+///   ACK Delay (i),
+///   ACK Range Count (i),
+///   First ACK Range (i),
+///   ACK Range (..) ...,
+///   [ECN Counts (..)],
+/// }
+/// ```
+///
+/// The PATH_ACK frame acknowledges the packets of the packet number space
+/// belonging to the path with the given identifier, the ack fields are the
+/// same as [`AckFrame`].
+///
+/// See [PATH_ACK Frame](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-path_ack-frame)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+#[derive(Debug, Clone, PartialEq, Eq, Deref)]
+pub struct PathAckFrame {
+    path_id: VarInt,
+    #[deref]
+    ack: AckFrame,
+}
+
+const PATH_ACK_FRAME_TYPE: u32 = 0x15228c00;
+
+const ECN_OPT: u32 = 0x1;
+
+impl PathAckFrame {
+    /// Create a new [`PathAckFrame`] acknowledging packets on the path `path_id`.
+    pub fn new(path_id: VarInt, ack: AckFrame) -> Self {
+        Self { path_id, ack }
+    }
+
+    /// Return the identifier of the path whose packets are acknowledged.
+    pub fn path_id(&self) -> VarInt {
+        self.path_id
+    }
+
+    /// Return the ack fields as an [`AckFrame`].
+    pub fn ack(&self) -> &AckFrame {
+        &self.ack
+    }
+
+    /// Split the frame into the path identifier and the [`AckFrame`].
+    pub fn into_parts(self) -> (VarInt, AckFrame) {
+        (self.path_id, self.ack)
+    }
+}
+
+impl super::GetFrameType for PathAckFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::PathAck(if self.ack.ecn().is_some() { 1 } else { 0 })
+    }
+}
+
+impl super::EncodeSize for PathAckFrame {
+    fn max_encoding_size(&self) -> usize {
+        // the ACK frame type is 1 byte, and the PATH_ACK frame type is 4 bytes
+        4 + 8 + self.ack.max_encoding_size() - 1
+    }
+
+    fn encoding_size(&self) -> usize {
+        4 + self.path_id.encoding_size() + self.ack.encoding_size() - 1
+    }
+}
+
+/// Parser for parsing a PATH_ACK frame with the given ECN flag,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn path_ack_frame_with_flag(
+    ecn_flag: u8,
+) -> impl Fn(&[u8]) -> nom::IResult<&[u8], PathAckFrame> {
+    move |input: &[u8]| {
+        (be_varint, ack_frame_with_flag(ecn_flag))
+            .map(|(path_id, ack)| PathAckFrame { path_id, ack })
+            .parse(input)
+    }
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<PathAckFrame> for T {
+    fn put_frame(&mut self, frame: &PathAckFrame) {
+        let mut frame_type = PATH_ACK_FRAME_TYPE;
+        if frame.ack.ecn().is_some() {
+            frame_type |= ECN_OPT;
+        }
+        self.put_varint(&VarInt::from_u32(frame_type));
+        self.put_varint(&frame.path_id);
+        put_ack_fields(self, &frame.ack);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::{Parser, combinator::flat_map};
+
+    use super::{PATH_ACK_FRAME_TYPE, PathAckFrame, path_ack_frame_with_flag};
+    use crate::{
+        frame::{AckFrame, EcnCounts, EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::{VarInt, be_varint},
+    };
+
+    fn ack_frame(ecn: Option<EcnCounts>) -> AckFrame {
+        AckFrame::new(
+            VarInt::from_u32(0x1234),
+            VarInt::from_u32(0x1234),
+            VarInt::from_u32(0x1234),
+            vec![(VarInt::from_u32(3), VarInt::from_u32(20))],
+            ecn,
+        )
+    }
+
+    #[test]
+    fn test_path_ack_frame() {
+        let frame = PathAckFrame::new(VarInt::from_u32(1), ack_frame(None));
+        assert_eq!(frame.frame_type(), FrameType::PathAck(0));
+        assert_eq!(frame.encoding_size(), 4 + 1 + 2 * 3 + 1 + 2);
+        assert_eq!(frame.largest(), 0x1234);
+
+        let ecn = EcnCounts::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(2),
+            VarInt::from_u32(3),
+        );
+        let frame = PathAckFrame::new(VarInt::from_u32(1), ack_frame(Some(ecn)));
+        assert_eq!(frame.frame_type(), FrameType::PathAck(1));
+        assert_eq!(frame.encoding_size(), 4 + 1 + 2 * 3 + 1 + 2 + 3);
+    }
+
+    #[test]
+    fn test_read_path_ack_frame() {
+        let input = vec![
+            0x95, 0x22, 0x8c, 0x00, 0x02, 0x52, 0x34, 0x52, 0x34, 0x01, 0x52, 0x34, 3, 20,
+        ];
+        let (input, frame) = flat_map(be_varint, |frame_type| {
+            if frame_type.into_inner() & !1 == PATH_ACK_FRAME_TYPE as u64 {
+                path_ack_frame_with_flag(frame_type.into_inner() as u8 & 1)
+            } else {
+                panic!("wrong frame type: {frame_type}")
+            }
+        })
+        .parse(&input)
+        .unwrap();
+        assert!(input.is_empty());
+        assert_eq!(
+            frame,
+            PathAckFrame::new(VarInt::from_u32(2), ack_frame(None))
+        );
+    }
+
+    #[test]
+    fn test_write_path_ack_frame() {
+        let mut buf = Vec::new();
+        let ecn = EcnCounts::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(2),
+            VarInt::from_u32(3),
+        );
+        let frame = PathAckFrame::new(VarInt::from_u32(2), ack_frame(Some(ecn)));
+        buf.put_frame(&frame);
+        assert_eq!(
+            buf,
+            vec![
+                0x95, 0x22, 0x8c, 0x01, // frame type
+                0x02, // path id
+                0x52, 0x34, 0x52, 0x34, 0x01, 0x52, 0x34, 3, 20, // ack
+                1, 2, 3 // ecn
+            ]
+        );
+        assert_eq!(buf.len(), frame.encoding_size());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// PATH_CIDS_BLOCKED frame.
+///
+/// ```text
+/// PATH_CIDS_BLOCKED Frame {
+///   Type (i) = 0x15228c0e,
+///   Path Identifier (i),
+///   Next Sequence Number (i),
+/// }
+/// ```
+///
+/// See [PATH_CIDS_BLOCKED Frame](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-paths_blocked-and-path_cids)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathCidsBlockedFrame {
+    path_id: VarInt,
+    next_sequence: VarInt,
+}
+
+const PATH_CIDS_BLOCKED_FRAME_TYPE: u32 = 0x15228c0e;
+
+impl super::GetFrameType for PathCidsBlockedFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::PathCidsBlocked
+    }
+}
+
+impl super::EncodeSize for PathCidsBlockedFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8 + 8
+    }
+
+    fn encoding_size(&self) -> usize {
+        4 + self.path_id.encoding_size() + self.next_sequence.encoding_size()
+    }
+}
+
+impl PathCidsBlockedFrame {
+    /// Create a new [`PathCidsBlockedFrame`].
+    pub fn new(path_id: VarInt, next_sequence: VarInt) -> Self {
+        Self {
+            path_id,
+            next_sequence,
+        }
+    }
+
+    /// Return the identifier of the path that has no connection ID available.
+    pub fn path_id(&self) -> VarInt {
+        self.path_id
+    }
+
+    /// Return the sequence number of the next connection ID expected from the peer.
+    pub fn next_sequence(&self) -> VarInt {
+        self.next_sequence
+    }
+}
+
+/// Parse a PATH_CIDS_BLOCKED frame from the input buffer,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn be_path_cids_blocked_frame(input: &[u8]) -> nom::IResult<&[u8], PathCidsBlockedFrame> {
+    use nom::{Parser, combinator::map};
+    map((be_varint, be_varint), |(path_id, next_sequence)| {
+        PathCidsBlockedFrame::new(path_id, next_sequence)
+    })
+    .parse(input)
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<PathCidsBlockedFrame> for T {
+    fn put_frame(&mut self, frame: &PathCidsBlockedFrame) {
+        self.put_varint(&VarInt::from_u32(PATH_CIDS_BLOCKED_FRAME_TYPE));
+        self.put_varint(&frame.path_id);
+        self.put_varint(&frame.next_sequence);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathCidsBlockedFrame, be_path_cids_blocked_frame};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    #[test]
+    fn test_path_cids_blocked_frame() {
+        let frame = PathCidsBlockedFrame::new(VarInt::from_u32(1), VarInt::from_u32(2));
+        assert_eq!(frame.frame_type(), FrameType::PathCidsBlocked);
+        assert_eq!(frame.encoding_size(), 4 + 1 + 1);
+        assert_eq!(frame.path_id(), VarInt::from_u32(1));
+        assert_eq!(frame.next_sequence(), VarInt::from_u32(2));
+    }
+
+    #[test]
+    fn test_read_write_path_cids_blocked_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&PathCidsBlockedFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(2),
+        ));
+        assert_eq!(buf, vec![0x95, 0x22, 0x8c, 0x0e, 0x01, 0x02]);
+
+        let (remain, frame) = be_path_cids_blocked_frame(&buf[4..]).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(
+            frame,
+            PathCidsBlockedFrame::new(VarInt::from_u32(1), VarInt::from_u32(2))
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use super::{NewConnectionIdFrame, new_connection_id::be_new_connection_id_frame};
+use crate::{
+    cid::WriteConnectionId,
+    token::RESET_TOKEN_SIZE,
+    varint::{VarInt, WriteVarInt, be_varint},
+};
+
+/// PATH_NEW_CONNECTION_ID frame.
+///
+/// ```text
+/// PATH_NEW_CONNECTION_ID Frame {
+///   Type (i) = 0x15228c09,
+///   Path Identifier (i),
+///   Sequence Number (i),
+///   Retire Prior To (i),
+///   Length (8),
+///   Connection ID (8..160),
+///   Stateless Reset Token (128),
+/// }
+/// ```
+///
+/// Except the path identifier, the fields are the same as the [`NewConnectionIdFrame`],
+/// but the sequence numbers are scoped to the path.
+///
+/// See [PATH_NEW_CONNECTION_ID Frame](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-path_new_connection_id-fram)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathNewConnectionIdFrame {
+    path_id: VarInt,
+    frame: NewConnectionIdFrame,
+}
+
+const PATH_NEW_CONNECTION_ID_FRAME_TYPE: u32 = 0x15228c09;
+
+impl super::GetFrameType for PathNewConnectionIdFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::PathNewConnectionId
+    }
+}
+
+impl super::EncodeSize for PathNewConnectionIdFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8 + 8 + 8 + 21 + RESET_TOKEN_SIZE
+    }
+
+    fn encoding_size(&self) -> usize {
+        // the NEW_CONNECTION_ID frame type takes 1 byte
+        4 + self.path_id.encoding_size() + self.frame.encoding_size() - 1
+    }
+}
+
+impl PathNewConnectionIdFrame {
+    /// Create a new [`PathNewConnectionIdFrame`], issuing the connection ID in `frame`
+    /// for the path `path_id`.
+    pub fn new(path_id: VarInt, frame: NewConnectionIdFrame) -> Self {
+        Self { path_id, frame }
+    }
+
+    /// Return the identifier of the path the connection ID is issued for.
+    pub fn path_id(&self) -> VarInt {
+        self.path_id
+    }
+
+    /// Return the connection ID issued, as a [`NewConnectionIdFrame`] of the path.
+    pub fn new_cid_frame(&self) -> &NewConnectionIdFrame {
+        &self.frame
+    }
+}
+
+/// Parse a PATH_NEW_CONNECTION_ID frame from the input buffer,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn be_path_new_connection_id_frame(
+    input: &[u8],
+) -> nom::IResult<&[u8], PathNewConnectionIdFrame> {
+    use nom::{Parser, combinator::map};
+    map(
+        (be_varint, be_new_connection_id_frame),
+        |(path_id, frame)| PathNewConnectionIdFrame::new(path_id, frame),
+    )
+    .parse(input)
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<PathNewConnectionIdFrame> for T {
+    fn put_frame(&mut self, frame: &PathNewConnectionIdFrame) {
+        let new_cid = &frame.frame;
+        self.put_varint(&VarInt::from_u32(PATH_NEW_CONNECTION_ID_FRAME_TYPE));
+        self.put_varint(&frame.path_id);
+        self.put_varint(&VarInt::from_u64(new_cid.sequence()).expect("sequence is a varint"));
+        self.put_varint(
+            &VarInt::from_u64(new_cid.retire_prior_to()).expect("retire prior to is a varint"),
+        );
+        self.put_connection_id(new_cid.connection_id());
+        self.put_slice(new_cid.reset_token().as_slice());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathNewConnectionIdFrame, be_path_new_connection_id_frame};
+    use crate::{
+        cid::ConnectionId,
+        frame::{EncodeSize, FrameType, GetFrameType, NewConnectionIdFrame, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    fn new_cid_frame() -> NewConnectionIdFrame {
+        NewConnectionIdFrame::new(
+            ConnectionId::from_slice(&[0x01, 0x02, 0x03, 0x04]),
+            VarInt::from_u32(2),
+            VarInt::from_u32(1),
+        )
+    }
+
+    #[test]
+    fn test_path_new_connection_id_frame() {
+        let frame = PathNewConnectionIdFrame::new(VarInt::from_u32(3), new_cid_frame());
+        assert_eq!(frame.frame_type(), FrameType::PathNewConnectionId);
+        assert_eq!(frame.encoding_size(), 4 + 1 + 1 + 1 + 1 + 4 + 16);
+        assert_eq!(frame.path_id(), VarInt::from_u32(3));
+        assert_eq!(frame.new_cid_frame().sequence(), 2);
+    }
+
+    #[test]
+    fn test_read_write_path_new_connection_id_frame() {
+        let frame = PathNewConnectionIdFrame::new(VarInt::from_u32(3), new_cid_frame());
+        let mut buf = Vec::new();
+        buf.put_frame(&frame);
+        assert_eq!(buf.len(), frame.encoding_size());
+        assert_eq!(&buf[..5], &[0x95, 0x22, 0x8c, 0x09, 0x03]);
+
+        let (remain, parsed) = be_path_new_connection_id_frame(&buf[4..]).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(parsed, frame);
+    }
+}
@@ -0,0 +1,124 @@
+use super::RetireConnectionIdFrame;
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// PATH_RETIRE_CONNECTION_ID frame.
+///
+/// ```text
+/// PATH_RETIRE_CONNECTION_ID Frame {
+///   Type (i) = 0x15228c0a,
+///   Path Identifier (i),
+///   Sequence Number (i),
+/// }
+/// ```
+///
+/// See [PATH_RETIRE_CONNECTION_ID Frame](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-path_retire_connection_id-f)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathRetireConnectionIdFrame {
+    path_id: VarInt,
+    frame: RetireConnectionIdFrame,
+}
+
+const PATH_RETIRE_CONNECTION_ID_FRAME_TYPE: u32 = 0x15228c0a;
+
+impl super::GetFrameType for PathRetireConnectionIdFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::PathRetireConnectionId
+    }
+}
+
+impl super::EncodeSize for PathRetireConnectionIdFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8 + 8
+    }
+
+    fn encoding_size(&self) -> usize {
+        // the RETIRE_CONNECTION_ID frame type takes 1 byte
+        4 + self.path_id.encoding_size() + self.frame.encoding_size() - 1
+    }
+}
+
+impl PathRetireConnectionIdFrame {
+    /// Create a new [`PathRetireConnectionIdFrame`], retiring the connection ID in `frame`
+    /// of the path `path_id`.
+    pub fn new(path_id: VarInt, frame: RetireConnectionIdFrame) -> Self {
+        Self { path_id, frame }
+    }
+
+    /// Return the identifier of the path the connection ID belongs to.
+    pub fn path_id(&self) -> VarInt {
+        self.path_id
+    }
+
+    /// Return the connection ID retired, as a [`RetireConnectionIdFrame`] of the path.
+    pub fn retire_cid_frame(&self) -> &RetireConnectionIdFrame {
+        &self.frame
+    }
+}
+
+/// Parse a PATH_RETIRE_CONNECTION_ID frame from the input buffer,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn be_path_retire_connection_id_frame(
+    input: &[u8],
+) -> nom::IResult<&[u8], PathRetireConnectionIdFrame> {
+    use nom::{Parser, combinator::map};
+    map((be_varint, be_varint), |(path_id, sequence)| {
+        PathRetireConnectionIdFrame::new(path_id, RetireConnectionIdFrame::new(sequence))
+    })
+    .parse(input)
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<PathRetireConnectionIdFrame> for T {
+    fn put_frame(&mut self, frame: &PathRetireConnectionIdFrame) {
+        self.put_varint(&VarInt::from_u32(PATH_RETIRE_CONNECTION_ID_FRAME_TYPE));
+        self.put_varint(&frame.path_id);
+        self.put_varint(&VarInt::from_u64(frame.frame.sequence()).expect("sequence is a varint"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathRetireConnectionIdFrame, be_path_retire_connection_id_frame};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, RetireConnectionIdFrame, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    #[test]
+    fn test_path_retire_connection_id_frame() {
+        let frame = PathRetireConnectionIdFrame::new(
+            VarInt::from_u32(1),
+            RetireConnectionIdFrame::new(VarInt::from_u32(0x1234)),
+        );
+        assert_eq!(frame.frame_type(), FrameType::PathRetireConnectionId);
+        assert_eq!(frame.max_encoding_size(), 4 + 8 + 8);
+        assert_eq!(frame.encoding_size(), 4 + 1 + 2);
+        assert_eq!(frame.path_id(), VarInt::from_u32(1));
+        assert_eq!(frame.retire_cid_frame().sequence(), 0x1234);
+    }
+
+    #[test]
+    fn test_read_path_retire_connection_id_frame() {
+        let buf = vec![0x01, 0x52, 0x34];
+        let (remain, frame) = be_path_retire_connection_id_frame(&buf).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(
+            frame,
+            PathRetireConnectionIdFrame::new(
+                VarInt::from_u32(1),
+                RetireConnectionIdFrame::new(VarInt::from_u32(0x1234))
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_path_retire_connection_id_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&PathRetireConnectionIdFrame::new(
+            VarInt::from_u32(1),
+            RetireConnectionIdFrame::new(VarInt::from_u32(0x1234)),
+        ));
+        assert_eq!(buf, vec![0x95, 0x22, 0x8c, 0x0a, 0x01, 0x52, 0x34]);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// The status a peer advertises for a path with the PATH_STATUS frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PathAvailability {
+    /// The path should only be used when no available path is usable.
+    Backup,
+    /// The path can be used to send data, this is the default status of a path.
+    #[default]
+    Available,
+}
+
+/// PATH_STATUS_BACKUP and PATH_STATUS_AVAILABLE frames.
+///
+/// ```text
+/// PATH_STATUS_BACKUP Frame {
+///   Type (i) = 0x15228c07,
+///   Path Identifier (i),
+///   Path Status Sequence Number (i),
+/// }
+///
+/// PATH_STATUS_AVAILABLE Frame {
+///   Type (i) = 0x15228c08,
+///   Path Identifier (i),
+///   Path Status Sequence Number (i),
+/// }
+/// ```
+///
+/// The two frames only differ in their frame types, so they are represented
+/// by one struct carrying a [`PathAvailability`].
+///
+/// See [PATH_STATUS_BACKUP and PATH_STATUS_AVAILABLE Frames](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-path_status_backup-and-path)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathStatusFrame {
+    path_id: VarInt,
+    sequence: VarInt,
+    availability: PathAvailability,
+}
+
+const PATH_STATUS_BACKUP_FRAME_TYPE: u32 = 0x15228c07;
+const PATH_STATUS_AVAILABLE_FRAME_TYPE: u32 = 0x15228c08;
+
+impl super::GetFrameType for PathStatusFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::PathStatus(match self.availability {
+            PathAvailability::Backup => 0,
+            PathAvailability::Available => 1,
+        })
+    }
+}
+
+impl super::EncodeSize for PathStatusFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8 + 8
+    }
+
+    fn encoding_size(&self) -> usize {
+        4 + self.path_id.encoding_size() + self.sequence.encoding_size()
+    }
+}
+
+impl PathStatusFrame {
+    /// Create a new [`PathStatusFrame`].
+    pub fn new(path_id: VarInt, sequence: VarInt, availability: PathAvailability) -> Self {
+        Self {
+            path_id,
+            sequence,
+            availability,
+        }
+    }
+
+    /// Return the identifier of the path whose status changes.
+    pub fn path_id(&self) -> VarInt {
+        self.path_id
+    }
+
+    /// Return the path status sequence number, only the frame with the
+    /// largest sequence number of a path takes effect.
+    pub fn sequence(&self) -> u64 {
+        self.sequence.into_inner()
+    }
+
+    /// Return the advertised status of the path.
+    pub fn availability(&self) -> PathAvailability {
+        self.availability
+    }
+}
+
+/// Return a parser for PATH_STATUS frames with the status flag,
+/// 0 for PATH_STATUS_BACKUP and 1 for PATH_STATUS_AVAILABLE,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn path_status_frame_with_flag(
+    flag: u8,
+) -> impl Fn(&[u8]) -> nom::IResult<&[u8], PathStatusFrame> {
+    use nom::{Parser, combinator::map};
+    let availability = match flag {
+        0 => PathAvailability::Backup,
+        _ => PathAvailability::Available,
+    };
+    move |input| {
+        map((be_varint, be_varint), |(path_id, sequence)| {
+            PathStatusFrame::new(path_id, sequence, availability)
+        })
+        .parse(input)
+    }
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<PathStatusFrame> for T {
+    fn put_frame(&mut self, frame: &PathStatusFrame) {
+        let frame_type = match frame.availability {
+            PathAvailability::Backup => PATH_STATUS_BACKUP_FRAME_TYPE,
+            PathAvailability::Available => PATH_STATUS_AVAILABLE_FRAME_TYPE,
+        };
+        self.put_varint(&VarInt::from_u32(frame_type));
+        self.put_varint(&frame.path_id);
+        self.put_varint(&frame.sequence);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathAvailability, PathStatusFrame, path_status_frame_with_flag};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    #[test]
+    fn test_path_status_frame() {
+        let frame = PathStatusFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(0x1234),
+            PathAvailability::Backup,
+        );
+        assert_eq!(frame.frame_type(), FrameType::PathStatus(0));
+        assert_eq!(frame.max_encoding_size(), 4 + 8 + 8);
+        assert_eq!(frame.encoding_size(), 4 + 1 + 2);
+        assert_eq!(frame.sequence(), 0x1234);
+
+        let frame = PathStatusFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(0x1234),
+            PathAvailability::Available,
+        );
+        assert_eq!(frame.frame_type(), FrameType::PathStatus(1));
+    }
+
+    #[test]
+    fn test_read_path_status_frame() {
+        let buf = vec![0x01, 0x52, 0x34];
+        let (remain, frame) = path_status_frame_with_flag(0)(&buf).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(
+            frame,
+            PathStatusFrame::new(
+                VarInt::from_u32(1),
+                VarInt::from_u32(0x1234),
+                PathAvailability::Backup
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_path_status_frame() {
+        let mut buf = Vec::new();
+        buf.put_frame(&PathStatusFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(0x1234),
+            PathAvailability::Available,
+        ));
+        assert_eq!(buf, vec![0x95, 0x22, 0x8c, 0x08, 0x01, 0x52, 0x34]);
+    }
+}
@@ -0,0 +1,141 @@
+use std::net::SocketAddr;
+
+use nom::Parser;
+
+use super::add_address::{put_socket_addr, socket_addr_of_family, socket_addr_size};
+use crate::varint::{VarInt, WriteVarInt, be_varint};
+
+/// PUNCH_ME_NOW frame.
+///
+/// ```text
+/// PUNCH_ME_NOW Frame {
+///   Type (i) = 0x3d7e92..0x3d7e93,
+///   Round (i),
+///   Paired With Sequence Number (i),
+///   [ IPv4 (32) ],
+///   [ IPv6 (128) ],
+///   Port (16),
+/// }
+/// ```
+///
+/// Sent by the client to start hole punching: it pairs an address of the client with the address
+/// the server advertised in the ADD_ADDRESS frame of the sequence number, and the server probes
+/// the client's address from that address at once, while the client probes the other way.
+///
+/// See [PUNCH_ME_NOW Frame](https://www.ietf.org/archive/id/draft-seemann-quic-nat-traversal-02.html#name-punch_me_now-frame)
+/// of [Using QUIC to traverse NATs](https://datatracker.ietf.org/doc/draft-seemann-quic-nat-traversal/)
+/// for more details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PunchMeNowFrame {
+    round: VarInt,
+    paired_with: VarInt,
+    addr: SocketAddr,
+}
+
+const PUNCH_ME_NOW_FRAME_TYPE: u32 = 0x3d7e92;
+
+impl super::GetFrameType for PunchMeNowFrame {
+    fn frame_type(&self) -> super::FrameType {
+        super::FrameType::PunchMeNow(self.addr.is_ipv6() as u8)
+    }
+}
+
+impl super::EncodeSize for PunchMeNowFrame {
+    fn max_encoding_size(&self) -> usize {
+        4 + 8 + 8 + 16 + 2
+    }
+
+    fn encoding_size(&self) -> usize {
+        4 + self.round.encoding_size()
+            + self.paired_with.encoding_size()
+            + socket_addr_size(&self.addr)
+    }
+}
+
+impl PunchMeNowFrame {
+    /// Create a new [`PunchMeNowFrame`].
+    pub fn new(round: VarInt, paired_with: VarInt, addr: SocketAddr) -> Self {
+        Self {
+            round,
+            paired_with,
+            addr,
+        }
+    }
+
+    /// Return the round of hole punching this frame starts.
+    pub fn round(&self) -> VarInt {
+        self.round
+    }
+
+    /// Return the sequence number of the receiver's address to punch from.
+    pub fn paired_with(&self) -> VarInt {
+        self.paired_with
+    }
+
+    /// Return the sender's address to punch to.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+/// Return a parser for a PUNCH_ME_NOW frame with the family flag,
+/// [nom](https://docs.rs/nom/latest/nom/) parser style.
+pub fn punch_me_now_frame_with_flag(
+    family: u8,
+) -> impl Fn(&[u8]) -> nom::IResult<&[u8], PunchMeNowFrame> {
+    move |input| {
+        (be_varint, be_varint, socket_addr_of_family(family))
+            .map(|(round, paired_with, addr)| PunchMeNowFrame::new(round, paired_with, addr))
+            .parse(input)
+    }
+}
+
+impl<T: bytes::BufMut> super::io::WriteFrame<PunchMeNowFrame> for T {
+    fn put_frame(&mut self, frame: &PunchMeNowFrame) {
+        let frame_type = PUNCH_ME_NOW_FRAME_TYPE | frame.addr.is_ipv6() as u32;
+        self.put_varint(&VarInt::from_u32(frame_type));
+        self.put_varint(&frame.round);
+        self.put_varint(&frame.paired_with);
+        put_socket_addr(self, &frame.addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PunchMeNowFrame, punch_me_now_frame_with_flag};
+    use crate::{
+        frame::{EncodeSize, FrameType, GetFrameType, io::WriteFrame},
+        varint::VarInt,
+    };
+
+    #[test]
+    fn test_punch_me_now_frame() {
+        let frame = PunchMeNowFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(2),
+            "1.2.3.4:443".parse().unwrap(),
+        );
+        assert_eq!(frame.frame_type(), FrameType::PunchMeNow(0));
+        assert_eq!(frame.max_encoding_size(), 4 + 8 + 8 + 16 + 2);
+        assert_eq!(frame.encoding_size(), 4 + 1 + 1 + 4 + 2);
+        assert_eq!(frame.round(), VarInt::from_u32(1));
+        assert_eq!(frame.paired_with(), VarInt::from_u32(2));
+    }
+
+    #[test]
+    fn test_read_write_punch_me_now_frame() {
+        let frame = PunchMeNowFrame::new(
+            VarInt::from_u32(1),
+            VarInt::from_u32(2),
+            "[::1]:443".parse().unwrap(),
+        );
+        let mut buf = Vec::new();
+        buf.put_frame(&frame);
+        assert_eq!(&buf[..4], &[0x80, 0x3d, 0x7e, 0x93]);
+        assert_eq!(buf.len(), frame.encoding_size());
+
+        let (remain, parsed) = punch_me_now_frame_with_flag(1)(&buf[4..]).unwrap();
+        assert!(remain.is_empty());
+        assert_eq!(parsed, frame);
+    }
+}
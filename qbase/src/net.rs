@@ -5,6 +5,7 @@ use thiserror::Error;
 
 pub mod addr;
 pub mod route;
+pub mod scheme;
 pub mod tx;
 
 /// IP protocol family
@@ -1,8 +1,11 @@
 use std::{
     borrow::Cow,
-    fmt::Display,
+    cmp::Ordering,
+    fmt::{Display, Write as _},
+    hash::{Hash, Hasher},
     net::{AddrParseError, IpAddr, SocketAddr},
     str::FromStr,
+    sync::Arc,
 };
 
 use derive_more::{Display, From, Into, TryInto};
@@ -10,7 +13,13 @@ use http::Uri;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{net::Family, util::UniqueIdGenerator};
+use crate::{
+    net::{
+        Family,
+        scheme::{CustomAddr, CustomKind, InvalidCustomBindUri},
+    },
+    util::UniqueIdGenerator,
+};
 
 #[derive(Debug, Display, Clone, Into, PartialEq, Eq, Hash)]
 pub struct BindUri(Uri);
@@ -33,6 +42,17 @@ pub enum ParseBindUriError {
     InvalidIpFamily,
     #[error("Invalid IP address for inet scheme BindUri: {0}")]
     InvalidIpAddr(AddrParseError),
+    #[error("Host must be empty or localhost for unix scheme BindUri")]
+    InvalidUnixHost,
+    #[error("Invalid socket path for unix scheme BindUri: {0}")]
+    InvalidUnixPath(InvalidUnixSocketPath),
+    #[error("Invalid custom scheme BindUri: {0}")]
+    Custom(InvalidCustomBindUri),
+    #[error(
+        "Too many shards, at most {} sockets can be opened for an interface",
+        BindUri::MAX_SHARDS
+    )]
+    TooManyShards,
 }
 
 fn parse_iface_bind_uri(uri: &Uri) -> Result<(Family, String, u16), ParseBindUriError> {
@@ -61,6 +81,52 @@ fn parse_inet_bind_uri(uri: &Uri) -> Result<SocketAddr, ParseBindUriError> {
     }
 }
 
+fn parse_unix_bind_uri(uri: &Uri) -> Result<UnixSocketPath, ParseBindUriError> {
+    let authority = uri.authority().expect("BindUri is absolute URI");
+    if authority.as_str() != "localhost" {
+        return Err(ParseBindUriError::InvalidUnixHost);
+    }
+    match uri.path() {
+        "/" => Err(ParseBindUriError::InvalidUnixPath(
+            InvalidUnixSocketPath::Empty,
+        )),
+        path => percent_decode(path)
+            .and_then(|path| UnixSocketPath::from_bytes(&path))
+            .map_err(ParseBindUriError::InvalidUnixPath),
+    }
+}
+
+/// URI的路径中，保留字符与非ASCII字节都以`%XX`的形式出现，解码后才是socket的真实路径
+fn percent_decode(path: &str) -> Result<Vec<u8>, InvalidUnixSocketPath> {
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut bytes = path.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'%' {
+            decoded.push(byte);
+            continue;
+        }
+        let hex = [bytes.next(), bytes.next()];
+        let [Some(high), Some(low)] = hex.map(|b| b.and_then(|b| (b as char).to_digit(16))) else {
+            return Err(InvalidUnixSocketPath::InvalidEscape);
+        };
+        decoded.push((high * 16 + low) as u8);
+    }
+    Ok(decoded)
+}
+
+/// 路径分隔符与URI中无歧义的字符保持原样，其余字节编码为`%XX`
+fn percent_encode(path: &[u8]) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for &byte in path {
+        match byte {
+            b'/' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ if byte.is_ascii_alphanumeric() => encoded.push(byte as char),
+            _ => _ = write!(encoded, "%{byte:02X}"),
+        }
+    }
+    encoded
+}
+
 fn parse_ble_bind_uri(_: &Uri) -> ! {
     unimplemented!("BLE address is not implemented yet")
 }
@@ -73,7 +139,12 @@ impl FromStr for BindUri {
             return Ok(socket_addr.into());
         }
 
-        let uri: Uri = s.parse().map_err(ParseBindUriError::InvalidUri)?;
+        // unix:///path 的authority为空，http::Uri无法解析，按RFC 8089视为localhost
+        let uri: Uri = match s.strip_prefix("unix:///") {
+            Some(path) => format!("unix://localhost/{path}").parse(),
+            None => s.parse(),
+        }
+        .map_err(ParseBindUriError::InvalidUri)?;
 
         let schema = uri
             .scheme()
@@ -83,7 +154,11 @@ impl FromStr for BindUri {
             .map_err(ParseBindUriError::InvalidSchema)?;
         debug_assert!(uri.authority().is_some(), "BindUri should be absolute URI");
 
-        if uri.path() != "/" {
+        if matches!(
+            schema,
+            BindUriSchema::Iface | BindUriSchema::Inet | BindUriSchema::Ble
+        ) && uri.path() != "/"
+        {
             return Err(ParseBindUriError::HasPath);
         }
 
@@ -97,9 +172,25 @@ impl FromStr for BindUri {
             BindUriSchema::Ble => {
                 parse_ble_bind_uri(&uri);
             }
+            BindUriSchema::Unix => {
+                parse_unix_bind_uri(&uri)?;
+            }
+            BindUriSchema::Custom(kind) => {
+                kind.scheme()
+                    .parse(&uri)
+                    .map_err(ParseBindUriError::Custom)?;
+            }
         }
 
-        Ok(Self(uri))
+        let bind_uri = Self(uri);
+        if bind_uri
+            .prop(Self::SHARDS)
+            .and_then(|shards| shards.parse::<usize>().ok())
+            .is_some_and(|shards| shards > Self::MAX_SHARDS)
+        {
+            return Err(ParseBindUriError::TooManyShards);
+        }
+        Ok(bind_uri)
     }
 }
 
@@ -123,6 +214,17 @@ impl From<SocketAddr> for BindUri {
     }
 }
 
+impl From<UnixSocketPath> for BindUri {
+    #[inline]
+    fn from(value: UnixSocketPath) -> Self {
+        let uri = format!("unix://localhost{}", percent_encode(value.as_bytes()));
+        match BindUri::from_str(&uri) {
+            Ok(bind_uri) => bind_uri,
+            Err(e) => panic!("{e}"),
+        }
+    }
+}
+
 impl<T: Copy + Into<BindUri>> From<&T> for BindUri {
     #[inline]
     fn from(value: &T) -> Self {
@@ -162,6 +264,8 @@ impl BindUri {
                 }
             }
             BindUriSchema::Ble => AddrKind::Bluetooth,
+            BindUriSchema::Unix => AddrKind::Unix,
+            BindUriSchema::Custom(kind) => AddrKind::Custom(kind),
         }
     }
 
@@ -179,6 +283,13 @@ impl BindUri {
         Some(parse_inet_bind_uri(&self.0).expect("BindUri should be valid"))
     }
 
+    pub fn as_unix_bind_uri(&self) -> Option<UnixSocketPath> {
+        if self.scheme() != BindUriSchema::Unix {
+            return None;
+        }
+        Some(parse_unix_bind_uri(&self.0).expect("BindUri should be valid"))
+    }
+
     pub fn as_ble_bind_uri(&self) -> ! {
         parse_ble_bind_uri(&self.0)
     }
@@ -199,6 +310,10 @@ impl BindUri {
 
     pub const TEMPORARY: &'static str = "temporary";
     pub const ALLOC_PORT_ID: &'static str = "alloc_port_id";
+    pub const SHARDS: &'static str = "shards";
+    /// The most sockets [`BindUri::shards`] can open for an interface, each of them is received on
+    /// by its own task.
+    pub const MAX_SHARDS: usize = 64;
 
     pub fn alloc_port(&self) -> Self {
         match self.scheme() {
@@ -215,6 +330,8 @@ impl BindUri {
                 assert_eq!(addr.port(), 0, "Only port 0 is allocatable");
             }
             BindUriSchema::Ble => panic!("BLE address cannot allocate port"),
+            BindUriSchema::Unix => panic!("Unix socket address cannot allocate port"),
+            BindUriSchema::Custom(kind) => panic!("{kind} address cannot allocate port"),
         }
 
         let mut new_uri = self.clone();
@@ -245,11 +362,44 @@ impl BindUri {
             None | Some(..) => false,
         }
     }
+
+    /// Return the number of sockets to open for the interface, set by the `shards` property.
+    ///
+    /// The sockets share the address with `SO_REUSEPORT`, so that the received packets are
+    /// distributed among them by the kernel. Default to 1, which means no sharding. At most
+    /// [`BindUri::MAX_SHARDS`] sockets are opened, a [`BindUri`] asking for more is rejected.
+    pub fn shards(&self) -> usize {
+        self.prop(Self::SHARDS)
+            .and_then(|shards| shards.parse().ok())
+            .filter(|&shards| (1..=Self::MAX_SHARDS).contains(&shards))
+            .unwrap_or(1)
+    }
+
+    /// Return a copy of the inet or iface scheme [`BindUri`] with the port replaced.
+    pub fn with_port(&self, port: u16) -> Self {
+        assert!(
+            matches!(self.scheme(), BindUriSchema::Iface | BindUriSchema::Inet),
+            "Only inet and iface BindUri have port"
+        );
+        let mut uri_parts = self.0.clone().into_parts();
+        let host = uri_parts
+            .authority
+            .as_ref()
+            .expect("BindUri is absolute URI")
+            .host()
+            .to_string();
+        uri_parts.authority = Some(
+            format!("{host}:{port}")
+                .parse()
+                .expect("Authority should be valid"),
+        );
+        Self(Uri::from_parts(uri_parts).expect("BindUri should be valid"))
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum TryIntoSocketAddrError {
-    #[error("Only inet or iface schema BindUri can be converted to SocketAddr")]
+    #[error("Only inet or iface scheme BindUri can be converted to SocketAddr")]
     NotSocketBindUri,
     #[error("Device not found")]
     InterfaceNotFound,
@@ -300,7 +450,9 @@ impl TryFrom<&BindUri> for SocketAddr {
             BindUriSchema::Inet => Ok(bind_uri
                 .as_inet_bind_uri()
                 .expect("Already checked BindUriSchema is inet")),
-            BindUriSchema::Ble => Err(TryIntoSocketAddrError::NotSocketBindUri),
+            BindUriSchema::Ble | BindUriSchema::Unix | BindUriSchema::Custom(..) => {
+                Err(TryIntoSocketAddrError::NotSocketBindUri)
+            }
         }
     }
 }
@@ -318,10 +470,13 @@ pub enum BindUriSchema {
     Iface,
     Inet,
     Ble,
+    Unix,
+    /// A scheme registered with [`scheme::register`](crate::net::scheme::register)
+    Custom(CustomKind),
 }
 
 #[derive(Debug, Error)]
-#[error("Expect one of: iface, inet, ble")]
+#[error("Expect one of: iface, inet, ble, unix, or a registered custom scheme")]
 pub struct ParseBindUriSchemeError;
 
 impl FromStr for BindUriSchema {
@@ -332,7 +487,10 @@ impl FromStr for BindUriSchema {
             "iface" => Ok(BindUriSchema::Iface),
             "inet" => Ok(BindUriSchema::Inet),
             "ble" => Ok(BindUriSchema::Ble),
-            _ => Err(ParseBindUriSchemeError),
+            "unix" => Ok(BindUriSchema::Unix),
+            custom => CustomKind::lookup(custom)
+                .map(BindUriSchema::Custom)
+                .ok_or(ParseBindUriSchemeError),
         }
     }
 }
@@ -343,6 +501,8 @@ impl Display for BindUriSchema {
             BindUriSchema::Iface => write!(f, "iface"),
             BindUriSchema::Inet => write!(f, "inet"),
             BindUriSchema::Ble => write!(f, "ble"),
+            BindUriSchema::Unix => write!(f, "unix"),
+            BindUriSchema::Custom(kind) => write!(f, "{kind}"),
         }
     }
 }
@@ -357,16 +517,26 @@ pub enum AddrKind {
     Internet(Family),
     /// Bluetooth address
     Bluetooth,
+    /// Unix domain socket address
+    Unix,
+    /// Address of a custom scheme
+    Custom(CustomKind),
 }
 
 #[non_exhaustive]
-#[derive(Debug, Clone, Copy, From, TryInto, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, From, TryInto, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum RealAddr {
     /// Internet socket address (IPv4 or IPv6)
     // Iface/Inet => Inet
     Internet(SocketAddr),
     // TODO
     Bluetooth([u8; 6]),
+    /// Path of an unix domain datagram socket
+    // Unix => Unix
+    Unix(UnixSocketPath),
+    /// Opaque address of a custom scheme transport
+    // Custom => Custom
+    Custom(CustomAddr),
 }
 
 impl RealAddr {
@@ -376,6 +546,8 @@ impl RealAddr {
             RealAddr::Internet(SocketAddr::V4(_)) => AddrKind::Internet(Family::V4),
             RealAddr::Internet(SocketAddr::V6(_)) => AddrKind::Internet(Family::V6),
             RealAddr::Bluetooth(_) => AddrKind::Bluetooth,
+            RealAddr::Unix(_) => AddrKind::Unix,
+            RealAddr::Custom(addr) => AddrKind::Custom(addr.kind()),
         }
     }
 }
@@ -385,6 +557,8 @@ impl Display for RealAddr {
         match self {
             RealAddr::Internet(addr) => write!(f, "{addr}"),
             RealAddr::Bluetooth(addr) => write!(f, "{addr:02x?}"),
+            RealAddr::Unix(path) => write!(f, "{path}"),
+            RealAddr::Custom(addr) => write!(f, "{addr}"),
         }
     }
 }
@@ -397,11 +571,131 @@ impl FromStr for RealAddr {
     type Err = ParseRealAddrError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // 绝对路径是unix socket的地址
+        if let Ok(path) = s.parse::<UnixSocketPath>() {
+            return Ok(RealAddr::Unix(path));
+        }
         let addr: SocketAddr = s.parse().map_err(ParseRealAddrError)?;
         Ok(RealAddr::Internet(addr))
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum InvalidUnixSocketPath {
+    #[error("Path is empty")]
+    Empty,
+    #[error("Path is not absolute")]
+    NotAbsolute,
+    #[error("Path is longer than {} bytes", UnixSocketPath::MAX_LEN)]
+    TooLong,
+    #[error("Path contains an invalid percent-encoded byte")]
+    InvalidEscape,
+}
+
+/// Path of an unix domain socket, the address of the `unix` scheme [`BindUri`].
+///
+/// The path is shared behind a pointer, so that it costs [`RealAddr`] no more than a socket address.
+/// Only absolute pathname sockets are supported, unnamed and abstract sockets cannot be addressed.
+#[derive(Clone)]
+pub struct UnixSocketPath(Arc<[u8]>);
+
+impl UnixSocketPath {
+    /// The longest path fits in `sockaddr_un::sun_path` with the trailing nul on Linux.
+    pub const MAX_LEN: usize = 107;
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InvalidUnixSocketPath> {
+        match bytes {
+            [] => Err(InvalidUnixSocketPath::Empty),
+            [b'/', ..] if bytes.len() <= Self::MAX_LEN => Ok(Self(Arc::from(bytes))),
+            [b'/', ..] => Err(InvalidUnixSocketPath::TooLong),
+            _ => Err(InvalidUnixSocketPath::NotAbsolute),
+        }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    #[cfg(unix)]
+    #[inline]
+    pub fn as_path(&self) -> &std::path::Path {
+        use std::os::unix::ffi::OsStrExt;
+        std::ffi::OsStr::from_bytes(self.as_bytes()).as_ref()
+    }
+}
+
+#[cfg(unix)]
+impl TryFrom<&std::path::Path> for UnixSocketPath {
+    type Error = InvalidUnixSocketPath;
+
+    fn try_from(path: &std::path::Path) -> Result<Self, Self::Error> {
+        use std::os::unix::ffi::OsStrExt;
+        Self::from_bytes(path.as_os_str().as_bytes())
+    }
+}
+
+impl PartialEq for UnixSocketPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for UnixSocketPath {}
+
+impl PartialOrd for UnixSocketPath {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UnixSocketPath {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl Hash for UnixSocketPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl FromStr for UnixSocketPath {
+    type Err = InvalidUnixSocketPath;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_bytes(s.as_bytes())
+    }
+}
+
+impl Display for UnixSocketPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(self.as_bytes()))
+    }
+}
+
+impl std::fmt::Debug for UnixSocketPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("UnixSocketPath")
+            .field(&String::from_utf8_lossy(self.as_bytes()))
+            .finish()
+    }
+}
+
+impl Serialize for UnixSocketPath {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for UnixSocketPath {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        path.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -493,6 +787,127 @@ mod tests {
         assert!(bind_uri.as_uri().query().is_none());
     }
 
+    #[test]
+    fn unix_bind_uri() {
+        let bind_uri = BindUri::from_str("unix:///tmp/gm-quic.sock?temporary=true").unwrap();
+        assert_eq!(bind_uri.scheme(), BindUriSchema::Unix);
+        assert_eq!(bind_uri.addr_kind(), AddrKind::Unix);
+        assert_eq!(
+            bind_uri.to_string(),
+            "unix://localhost/tmp/gm-quic.sock?temporary=true"
+        );
+        let path = bind_uri.as_unix_bind_uri().unwrap();
+        assert_eq!(path.as_bytes(), b"/tmp/gm-quic.sock");
+        assert!(bind_uri.is_templorary());
+
+        assert_eq!(
+            BindUri::from_str("unix://localhost/tmp/gm-quic.sock").unwrap(),
+            BindUri::from_str("unix:///tmp/gm-quic.sock").unwrap()
+        );
+        assert!(matches!(
+            SocketAddr::try_from(bind_uri),
+            Err(TryIntoSocketAddrError::NotSocketBindUri)
+        ));
+    }
+
+    #[test]
+    fn invalid_unix_bind_uri() {
+        assert!(matches!(
+            BindUri::from_str("unix://example.com/tmp/gm-quic.sock"),
+            Err(ParseBindUriError::InvalidUnixHost)
+        ));
+        assert!(matches!(
+            BindUri::from_str("unix://localhost"),
+            Err(ParseBindUriError::InvalidUnixPath(
+                InvalidUnixSocketPath::Empty
+            ))
+        ));
+        let too_long = format!("unix:///{}", "a".repeat(UnixSocketPath::MAX_LEN));
+        assert!(matches!(
+            BindUri::from_str(&too_long),
+            Err(ParseBindUriError::InvalidUnixPath(
+                InvalidUnixSocketPath::TooLong
+            ))
+        ));
+    }
+
+    #[test]
+    fn percent_encoded_unix_bind_uri() {
+        let bind_uri = BindUri::from_str("unix:///tmp/gm%20quic/%E6%B5%8B%E8%AF%95.sock").unwrap();
+        let path = bind_uri.as_unix_bind_uri().unwrap();
+        assert_eq!(path.as_bytes(), "/tmp/gm quic/测试.sock".as_bytes());
+        assert_eq!(BindUri::from(path), bind_uri);
+
+        assert!(matches!(
+            BindUri::from_str("unix:///tmp/gm%2"),
+            Err(ParseBindUriError::InvalidUnixPath(
+                InvalidUnixSocketPath::InvalidEscape
+            ))
+        ));
+        assert!(matches!(
+            BindUri::from_str("unix:///tmp/gm%zz.sock"),
+            Err(ParseBindUriError::InvalidUnixPath(
+                InvalidUnixSocketPath::InvalidEscape
+            ))
+        ));
+    }
+
+    #[test]
+    fn unix_real_addr() {
+        let addr: RealAddr = "/tmp/gm-quic.sock".parse().unwrap();
+        assert_eq!(addr.kind(), AddrKind::Unix);
+        assert_eq!(addr.to_string(), "/tmp/gm-quic.sock");
+        assert_eq!(
+            "relative.sock".parse::<UnixSocketPath>(),
+            Err(InvalidUnixSocketPath::NotAbsolute)
+        );
+    }
+
+    #[test]
+    fn custom_bind_uri() {
+        use crate::net::scheme::{self, CustomScheme, InvalidCustomBindUri};
+
+        struct Radio;
+
+        impl CustomScheme for Radio {
+            fn parse(&self, uri: &Uri) -> Result<(), InvalidCustomBindUri> {
+                match uri.port() {
+                    Some(_) => Ok(()),
+                    None => Err("Missing channel".into()),
+                }
+            }
+        }
+
+        assert!(matches!(
+            BindUri::from_str("test-radio://wlan0:11"),
+            Err(ParseBindUriError::InvalidSchema(..))
+        ));
+        let kind = scheme::register("test-radio", Radio).unwrap();
+
+        let bind_uri = BindUri::from_str("test-radio://wlan0:11/mesh").unwrap();
+        assert_eq!(bind_uri.scheme(), BindUriSchema::Custom(kind));
+        assert_eq!(bind_uri.addr_kind(), AddrKind::Custom(kind));
+        assert_eq!(bind_uri.to_string(), "test-radio://wlan0:11/mesh");
+        assert!(matches!(
+            SocketAddr::try_from(bind_uri),
+            Err(TryIntoSocketAddrError::NotSocketBindUri)
+        ));
+        assert!(matches!(
+            BindUri::from_str("test-radio://wlan0"),
+            Err(ParseBindUriError::Custom(..))
+        ));
+
+        let addr = RealAddr::Custom(CustomAddr::new(kind, &[0x0b]).unwrap());
+        assert_eq!(addr.kind(), AddrKind::Custom(kind));
+        assert_eq!(addr.to_string(), "test-radio:0b");
+    }
+
+    #[test]
+    fn real_addr_size() {
+        // unix paths and custom addresses are behind pointers, socket addresses pay nothing for them
+        assert_eq!(size_of::<RealAddr>(), size_of::<SocketAddr>());
+    }
+
     #[test]
     fn interface_not_found() {
         let bind_uri = BindUri::from_str(
@@ -534,6 +949,38 @@ mod tests {
         bind_uri.alloc_port();
     }
 
+    #[test]
+    fn shards() {
+        assert_eq!(BindUri::from("inet://0.0.0.0:0").shards(), 1);
+        assert_eq!(BindUri::from("inet://0.0.0.0:0?shards=4").shards(), 4);
+        assert_eq!(BindUri::from("inet://0.0.0.0:0?shards=0").shards(), 1);
+        assert_eq!(BindUri::from("iface://v4.eth0:0?shards=x").shards(), 1);
+        assert_eq!(BindUri::from("inet://0.0.0.0:0?shards=64").shards(), 64);
+        assert!(matches!(
+            BindUri::from_str("inet://0.0.0.0:0?shards=100000"),
+            Err(ParseBindUriError::TooManyShards)
+        ));
+    }
+
+    #[test]
+    fn with_port() {
+        let bind_uri = BindUri::from("inet://[::1]:0?shards=4").alloc_port();
+        let with_port = bind_uri.with_port(4433);
+        assert_eq!(
+            with_port.as_inet_bind_uri(),
+            Some("[::1]:4433".parse().unwrap())
+        );
+        assert_eq!(with_port.shards(), 4);
+        assert_eq!(
+            with_port.prop(BindUri::ALLOC_PORT_ID),
+            bind_uri.prop(BindUri::ALLOC_PORT_ID)
+        );
+
+        let bind_uri = BindUri::from("iface://v4.wlan0:0");
+        let (.., port) = bind_uri.with_port(8080).as_iface_bind_uri().unwrap();
+        assert_eq!(port, 8080);
+    }
+
     #[test]
     fn temporary() {
         let bind_uri = BindUri::from_str("iface://v4.wlan0:8080?temporary=true").unwrap();
@@ -11,7 +11,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::net::{
     Family,
-    addr::{AddrKind, RealAddr},
+    addr::{AddrKind, RealAddr, UnixSocketPath},
+    scheme::CustomAddr,
 };
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -19,10 +20,18 @@ pub enum SocketEndpointAddr {
     Direct {
         addr: SocketAddr,
     },
+    /// The peer behind a NAT at the `outer` address, the hole punching towards which is
+    /// coordinated by the `agent`, for example a rendezvous agent.
     Agent {
         agent: SocketAddr,
         outer: SocketAddr,
     },
+    /// The peer reached through a relay, which forwards the packets to the `peer` by their
+    /// connection IDs, for the peers that can not reach each other directly.
+    Relay {
+        relay: SocketAddr,
+        peer: SocketAddr,
+    },
 }
 
 impl SocketEndpointAddr {
@@ -34,17 +43,30 @@ impl SocketEndpointAddr {
         SocketEndpointAddr::Agent { agent, outer }
     }
 
+    pub fn with_relay(relay: SocketAddr, peer: SocketAddr) -> Self {
+        SocketEndpointAddr::Relay { relay, peer }
+    }
+
     /// Returns the outer addr of this SocketEndpointAddr
     ///
     /// Note: Before successful hole punching with this Endpoint, packets should be sent to the addr
     /// returned by deref() to establish communication. Once hole punching is successful or about to
     /// begin, use the addr returned by this function.
+    ///
+    /// For a relayed endpoint, this is the address of the peer, which the relayed path can be
+    /// replaced with once the peer is reachable directly.
     pub fn addr(&self) -> SocketAddr {
         match self {
             SocketEndpointAddr::Direct { addr } => *addr,
             SocketEndpointAddr::Agent { outer, .. } => *outer,
+            SocketEndpointAddr::Relay { peer, .. } => *peer,
         }
     }
+
+    /// Whether the packets to this endpoint are forwarded by a relay.
+    pub fn is_relayed(&self) -> bool {
+        matches!(self, SocketEndpointAddr::Relay { .. })
+    }
 }
 
 impl Display for SocketEndpointAddr {
@@ -52,6 +74,7 @@ impl Display for SocketEndpointAddr {
         match self {
             SocketEndpointAddr::Direct { addr } => write!(f, "Direct({addr})"),
             SocketEndpointAddr::Agent { agent, outer } => write!(f, "Agent({agent}-{outer})"),
+            SocketEndpointAddr::Relay { relay, peer } => write!(f, "Relay({relay}-{peer})"),
         }
     }
 }
@@ -63,6 +86,7 @@ impl Deref for SocketEndpointAddr {
         match self {
             SocketEndpointAddr::Direct { addr } => addr,
             SocketEndpointAddr::Agent { agent, .. } => agent,
+            SocketEndpointAddr::Relay { relay, .. } => relay,
         }
     }
 }
@@ -71,15 +95,33 @@ impl FromStr for SocketEndpointAddr {
     type Err = AddrParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Some((first, second)) = s.split_once("-") {
+        // Display format: "Direct(1.12.124.56:1234)", "Agent(1.12.124.56:1234-202.106.68.43:6080)"
+        // or "Relay(1.12.124.56:1234-202.106.68.43:6080)"
+        let (kind, addrs) = match s.trim().split_once('(') {
+            Some((kind, rest)) if rest.ends_with(')') => {
+                (Some(kind.trim()), &rest[..rest.len() - 1])
+            }
+            _ => (None, s),
+        };
+        match (kind, addrs.split_once('-')) {
+            (Some("Relay"), Some((relay, peer))) => {
+                let relay = relay.trim().parse()?;
+                let peer = peer.trim().parse()?;
+                Ok(SocketEndpointAddr::with_relay(relay, peer))
+            }
             // Agent format: "inet:1.12.124.56:1234-inet:202.106.68.43:6080"
-            let agent = first.trim().parse()?;
-            let outer = second.trim().parse()?;
-            Ok(SocketEndpointAddr::with_agent(agent, outer))
-        } else {
+            (None | Some("Agent"), Some((first, second))) => {
+                let agent = first.trim().parse()?;
+                let outer = second.trim().parse()?;
+                Ok(SocketEndpointAddr::with_agent(agent, outer))
+            }
             // Direct format: "1.12.124.56:1234"
-            let addr = s.trim().parse()?;
-            Ok(SocketEndpointAddr::direct(addr))
+            (None | Some("Direct"), None) => {
+                let addr = addrs.trim().parse()?;
+                Ok(SocketEndpointAddr::direct(addr))
+            }
+            // 未知的格式，解析整个字符串以返回地址解析错误
+            _ => Ok(SocketEndpointAddr::direct(s.trim().parse()?)),
         }
     }
 }
@@ -122,11 +164,13 @@ impl FromStr for BleEndpontAddr {
 }
 
 #[derive(
-    Debug, Clone, Copy, From, TryInto, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+    Debug, Clone, From, TryInto, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
 )]
 pub enum EndpointAddr {
     Socket(SocketEndpointAddr),
     Ble(BleEndpontAddr),
+    Unix(UnixSocketPath),
+    Custom(CustomAddr),
 }
 
 impl EndpointAddr {
@@ -137,8 +181,16 @@ impl EndpointAddr {
                 SocketAddr::V6(..) => Family::V6,
             }),
             EndpointAddr::Ble(_) => AddrKind::Bluetooth,
+            EndpointAddr::Unix(_) => AddrKind::Unix,
+            EndpointAddr::Custom(addr) => AddrKind::Custom(addr.kind()),
         }
     }
+
+    /// Whether the packets to this endpoint are forwarded by a relay, see
+    /// [`SocketEndpointAddr::Relay`].
+    pub fn is_relayed(&self) -> bool {
+        matches!(self, EndpointAddr::Socket(addr) if addr.is_relayed())
+    }
 }
 
 impl Display for EndpointAddr {
@@ -146,6 +198,8 @@ impl Display for EndpointAddr {
         match self {
             EndpointAddr::Socket(addr) => addr.fmt(f),
             EndpointAddr::Ble(addr) => addr.fmt(f),
+            EndpointAddr::Unix(path) => path.fmt(f),
+            EndpointAddr::Custom(addr) => addr.fmt(f),
         }
     }
 }
@@ -155,6 +209,8 @@ impl From<RealAddr> for EndpointAddr {
         match addr {
             RealAddr::Internet(socket_addr) => SocketEndpointAddr::direct(socket_addr).into(),
             RealAddr::Bluetooth(ble_addr) => BleEndpontAddr::new(ble_addr).into(),
+            RealAddr::Unix(path) => EndpointAddr::Unix(path),
+            RealAddr::Custom(addr) => EndpointAddr::Custom(addr),
         }
     }
 }
@@ -275,7 +331,7 @@ impl From<Link<SocketAddr>> for Link<RealAddr> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct PacketHeader {
     pathway: Pathway,
     link: Link,
@@ -300,15 +356,15 @@ impl PacketHeader {
         let src = SocketAddr::from(([0, 0, 0, 0], 0));
         let dst = SocketAddr::from(([0, 0, 0, 0], 0));
         let link = Link::new(RealAddr::from(src), RealAddr::from(dst));
-        Self::new(link.into(), link, 0, None, 0)
+        Self::new(link.clone().into(), link, 0, None, 0)
     }
 
     pub fn pathway(&self) -> Pathway {
-        self.pathway
+        self.pathway.clone()
     }
 
     pub fn link(&self) -> Link {
-        self.link
+        self.link.clone()
     }
 
     pub fn ttl(&self) -> u8 {
@@ -348,5 +404,41 @@ mod tests {
 
         // Test invalid format
         assert!("invalid".parse::<SocketEndpointAddr>().is_err());
+        assert!(
+            "Relay(127.0.0.1:8080)"
+                .parse::<SocketEndpointAddr>()
+                .is_err()
+        );
+        assert!(
+            "Unknown(127.0.0.1:8080)"
+                .parse::<SocketEndpointAddr>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_endpoint_addr_display_round_trip() {
+        let direct: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        let other: SocketAddr = "[::1]:9000".parse().unwrap();
+        for addr in [
+            SocketEndpointAddr::direct(direct),
+            SocketEndpointAddr::direct(other),
+            SocketEndpointAddr::with_agent(direct, other),
+            SocketEndpointAddr::with_relay(direct, other),
+        ] {
+            assert_eq!(addr.to_string().parse::<SocketEndpointAddr>(), Ok(addr));
+        }
+    }
+
+    #[test]
+    fn test_relayed_endpoint_addr() {
+        let relay = "1.1.1.1:443".parse().unwrap();
+        let peer = "2.2.2.2:4433".parse().unwrap();
+        let addr = SocketEndpointAddr::with_relay(relay, peer);
+        assert_eq!(*addr, relay);
+        assert_eq!(addr.addr(), peer);
+        assert_eq!(addr.to_string(), "Relay(1.1.1.1:443-2.2.2.2:4433)");
+        assert!(EndpointAddr::from(addr).is_relayed());
+        assert!(!EndpointAddr::from(peer).is_relayed());
     }
 }
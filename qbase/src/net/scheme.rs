@@ -0,0 +1,303 @@
+//! Custom [`BindUri`] schemes, for transports other than the built-in ones.
+//!
+//! A custom `ProductQuicIO` can introduce its own addressing, such as a serial link or a mesh
+//! radio, by registering a scheme with [`register`]. Once registered:
+//!
+//! - [`BindUri`]s of the scheme are accepted and validated by [`CustomScheme::parse`],
+//!   their [`BindUriSchema`] and [`AddrKind`] are `Custom`;
+//! - the transport reports its addresses as [`RealAddr::Custom`], which is carried by
+//!   `Link`, `Pathway` and `EndpointAddr::Custom` like any other address;
+//! - clients pick the interfaces of the same [`CustomKind`] to connect a [`CustomAddr`],
+//!   or bind [`CustomScheme::client_bind_uri`] if they have none.
+//!
+//! [`BindUriSchema`]: crate::net::addr::BindUriSchema
+//! [`AddrKind`]: crate::net::addr::AddrKind
+//! [`RealAddr::Custom`]: crate::net::addr::RealAddr::Custom
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use http::Uri;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::net::addr::BindUri;
+
+/// Error returned by [`CustomScheme::parse`] for an invalid [`BindUri`].
+pub type InvalidCustomBindUri = Box<dyn std::error::Error + Send + Sync>;
+
+/// The behavior of a custom [`BindUri`] scheme, see the [module level documentation](self).
+pub trait CustomScheme: Send + Sync + 'static {
+    /// Validate a bind uri of this scheme.
+    ///
+    /// Unlike the built-in schemes, the uri may have a path. Note that the uri must have an
+    /// authority, such as `serial://usb0` or `serial://localhost/dev/ttyUSB0`.
+    fn parse(&self, uri: &Uri) -> Result<(), InvalidCustomBindUri>;
+
+    /// The [`BindUri`] for a client to bind, when it connects to an address of this scheme but
+    /// has no interface of this scheme bound.
+    ///
+    /// Return `None` by default, which means the client must bind the interface explicitly.
+    fn client_bind_uri(&self) -> Option<BindUri> {
+        None
+    }
+
+    /// Format an address of this scheme, hex bytes by default.
+    fn fmt_addr(&self, addr: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        addr.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RegisterSchemeError {
+    #[error("Scheme {0} is built-in")]
+    Reserved(String),
+    #[error("Scheme {0} is already registered")]
+    AlreadyRegistered(String),
+    #[error(
+        "Invalid scheme name {0:?}, expect a lowercase letter followed by lowercase letters, digits, '+', '-' or '.'"
+    )]
+    InvalidName(String),
+}
+
+type Registry = RwLock<HashMap<&'static str, Arc<dyn CustomScheme>>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+/// Register a custom [`BindUri`] scheme, return the [`CustomKind`] of it.
+///
+/// Schemes are registered for the lifetime of the process and cannot be unregistered.
+pub fn register(name: &str, scheme: impl CustomScheme) -> Result<CustomKind, RegisterSchemeError> {
+    let valid = name.bytes().enumerate().all(|(idx, byte)| match byte {
+        b'a'..=b'z' => true,
+        b'0'..=b'9' | b'+' | b'-' | b'.' => idx > 0,
+        _ => false,
+    });
+    if name.is_empty() || !valid {
+        return Err(RegisterSchemeError::InvalidName(name.to_string()));
+    }
+    if matches!(name, "iface" | "inet" | "ble" | "unix") {
+        return Err(RegisterSchemeError::Reserved(name.to_string()));
+    }
+
+    let mut registry = registry().write().unwrap();
+    if registry.contains_key(name) {
+        return Err(RegisterSchemeError::AlreadyRegistered(name.to_string()));
+    }
+    // 注册的scheme不会被注销，泄漏名字使CustomKind可以是Copy的
+    let name: &'static str = Box::leak(name.into());
+    registry.insert(name, Arc::new(scheme));
+    Ok(CustomKind(name))
+}
+
+/// The identity of a registered custom scheme, and the kind of the addresses it binds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CustomKind(&'static str);
+
+impl CustomKind {
+    /// Find a registered custom scheme by name.
+    pub fn lookup(name: &str) -> Option<Self> {
+        let registry = registry().read().unwrap();
+        registry.get_key_value(name).map(|(name, _)| Self(name))
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+
+    /// The registered behavior of this scheme.
+    pub fn scheme(&self) -> Arc<dyn CustomScheme> {
+        registry().read().unwrap()[self.0].clone()
+    }
+}
+
+impl Display for CustomKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Scheme {0} is not registered")]
+pub struct UnregisteredScheme(String);
+
+impl FromStr for CustomKind {
+    type Err = UnregisteredScheme;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::lookup(s).ok_or_else(|| UnregisteredScheme(s.to_string()))
+    }
+}
+
+impl Serialize for CustomKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Custom address is longer than {} bytes", CustomAddr::MAX_LEN)]
+pub struct CustomAddrTooLong;
+
+/// An opaque address of a custom scheme.
+///
+/// The bytes are interpreted only by the transport of the scheme, at most [`CustomAddr::MAX_LEN`]
+/// of them. They are shared behind a pointer, so that the addresses of the other transports do
+/// not grow for them.
+#[derive(Clone)]
+pub struct CustomAddr(Arc<(CustomKind, Box<[u8]>)>);
+
+impl CustomAddr {
+    pub const MAX_LEN: usize = 64;
+
+    pub fn new(kind: CustomKind, addr: &[u8]) -> Result<Self, CustomAddrTooLong> {
+        if addr.len() > Self::MAX_LEN {
+            return Err(CustomAddrTooLong);
+        }
+        Ok(Self(Arc::new((kind, addr.into()))))
+    }
+
+    #[inline]
+    pub fn kind(&self) -> CustomKind {
+        self.0.0
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0.1
+    }
+}
+
+impl PartialEq for CustomAddr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind() && self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for CustomAddr {}
+
+impl PartialOrd for CustomAddr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CustomAddr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.kind(), self.as_bytes()).cmp(&(other.kind(), other.as_bytes()))
+    }
+}
+
+impl Hash for CustomAddr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind().hash(state);
+        self.as_bytes().hash(state);
+    }
+}
+
+impl Display for CustomAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.kind())?;
+        self.kind().scheme().fmt_addr(self.as_bytes(), f)
+    }
+}
+
+impl fmt::Debug for CustomAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomAddr")
+            .field("kind", &self.kind())
+            .field("bytes", &self.as_bytes())
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CustomAddrRepr {
+    kind: CustomKind,
+    bytes: Vec<u8>,
+}
+
+impl Serialize for CustomAddr {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CustomAddrRepr {
+            kind: self.kind(),
+            bytes: self.as_bytes().to_vec(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomAddr {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = CustomAddrRepr::deserialize(deserializer)?;
+        CustomAddr::new(repr.kind, &repr.bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Serial;
+
+    impl CustomScheme for Serial {
+        fn parse(&self, uri: &Uri) -> Result<(), InvalidCustomBindUri> {
+            match uri.path() {
+                "/" => Err("Missing device path".into()),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn register_scheme() {
+        let kind = register("test-serial", Serial).unwrap();
+        assert_eq!(kind.name(), "test-serial");
+        assert_eq!(CustomKind::lookup("test-serial"), Some(kind));
+        assert_eq!(
+            register("test-serial", Serial).unwrap_err(),
+            RegisterSchemeError::AlreadyRegistered("test-serial".to_string())
+        );
+        assert_eq!(
+            register("inet", Serial).unwrap_err(),
+            RegisterSchemeError::Reserved("inet".to_string())
+        );
+        for invalid in ["", "1serial", "Serial", "se rial"] {
+            assert_eq!(
+                register(invalid, Serial).unwrap_err(),
+                RegisterSchemeError::InvalidName(invalid.to_string())
+            );
+        }
+        assert!("test-unregistered".parse::<CustomKind>().is_err());
+    }
+
+    #[test]
+    fn custom_addr() {
+        let kind = register("test-mesh", Serial).unwrap();
+        let addr = CustomAddr::new(kind, &[0xab, 0x01]).unwrap();
+        assert_eq!(addr.kind(), kind);
+        assert_eq!(addr.as_bytes(), &[0xab, 0x01]);
+        assert_eq!(addr.to_string(), "test-mesh:ab01");
+        assert_eq!(
+            CustomAddr::new(kind, &[0; CustomAddr::MAX_LEN + 1]),
+            Err(CustomAddrTooLong)
+        );
+    }
+}
@@ -22,6 +22,7 @@ bitflags::bitflags! {
         const PING          = 1 << 7; // packet which contains ping frames only
         const TLS_FIN       = 1 << 8; // TLS handshake is required to send and receive 1rtt data
         const PATH_VALIDATE = 1 << 9; // path validated
+        const SCHEDULE      = 1 << 10; // scheduler picked another path
     }
 }
 
@@ -118,7 +119,7 @@ impl SendWakers {
             signals: Signals,
         ) -> Option<Pathway> {
             let mut paths = paths.into_iter().peekable();
-            let first_path = paths.peek().map(|(pathway, _)| pathway).copied().copied();
+            let first_path = paths.peek().map(|(pathway, _)| (*pathway).clone());
 
             paths.for_each(|(_, waker)| {
                 waker.wake_by(signals);
@@ -129,11 +130,11 @@ impl SendWakers {
 
         use std::ops::Bound::*;
 
-        self.last_woken = match self.last_woken {
+        self.last_woken = match self.last_woken.take() {
             Some(last_woken) => wake_all_by(
                 self.paths
-                    .range((Excluded(last_woken), Unbounded))
-                    .chain(self.paths.range((Unbounded, Included(last_woken)))),
+                    .range((Excluded(&last_woken), Unbounded))
+                    .chain(self.paths.range((Unbounded, Included(&last_woken)))),
                 signals,
             ),
             None => wake_all_by(self.paths.range(..), signals),
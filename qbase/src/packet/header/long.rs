@@ -80,6 +80,8 @@ impl VersionNegotiation {
 pub struct Retry {
     token: Vec<u8>,
     integrity: [u8; 16],
+    // 首字节中未使用的4位，同样受完整性校验保护
+    unused_bits: u8,
 }
 
 impl Retry {
@@ -91,6 +93,7 @@ impl Retry {
         let mut retry = Retry {
             token: Vec::from(token),
             integrity: [0; 16],
+            unused_bits: 0,
         };
         retry.integrity.copy_from_slice(integrity);
         retry
@@ -105,6 +108,12 @@ impl Retry {
     pub fn integrity(&self) -> &[u8; 16] {
         &self.integrity
     }
+
+    /// Record the unused bits in the first byte of the received Retry packet, which are
+    /// covered by the integrity tag.
+    pub(crate) fn set_unused_bits(&mut self, first_byte: u8) {
+        self.unused_bits = first_byte & 0x0f;
+    }
 }
 
 /// The specific contents of the initial packet, which just includes a token.
@@ -169,6 +178,73 @@ pub type VersionNegotiationHeader = LongHeader<VersionNegotiation>;
 /// in [RFC9000](https://www.rfc-editor.org/rfc/rfc9000.html) for more details.
 pub type RetryHeader = LongHeader<Retry>;
 
+/// The key and nonce to compute the Retry Integrity Tag of QUIC version 1.
+///
+/// See [Retry Packet Integrity](https://www.rfc-editor.org/rfc/rfc9001.html#name-retry-packet-integrity)
+/// of [RFC9001](https://www.rfc-editor.org/rfc/rfc9001.html) for more details.
+const RETRY_INTEGRITY_KEY: [u8; 16] = [
+    0xbe, 0x0c, 0x69, 0x0b, 0x9f, 0x66, 0x57, 0x5a, 0x1d, 0x76, 0x6b, 0x54, 0xe3, 0x68, 0xc8, 0x4e,
+];
+const RETRY_INTEGRITY_NONCE: [u8; 12] = [
+    0x46, 0x15, 0x99, 0xd3, 0x5d, 0x63, 0x2b, 0xf2, 0x23, 0x98, 0x25, 0xbb,
+];
+
+impl RetryHeader {
+    /// Create a Retry header answering the Initial packet sent to the `origin_dcid`.
+    ///
+    /// The integrity tag is computed over the `origin_dcid` and the Retry packet, the client
+    /// verifies it with [`RetryHeader::verify_integrity`].
+    pub fn new_retry(
+        dcid: ConnectionId,
+        scid: ConnectionId,
+        token: Vec<u8>,
+        origin_dcid: &ConnectionId,
+    ) -> Self {
+        let mut header = io::LongHeaderBuilder::with_cid(dcid, scid).retry(token, [0; 16]);
+        let tag = retry_integrity_key()
+            .seal_in_place_separate_tag(
+                ring::aead::Nonce::assume_unique_for_key(RETRY_INTEGRITY_NONCE),
+                ring::aead::Aad::from(header.pseudo_packet(origin_dcid)),
+                &mut [],
+            )
+            .expect("sealing an empty plaintext never fails");
+        header.specific.integrity.copy_from_slice(tag.as_ref());
+        header
+    }
+
+    /// Verify the integrity tag of the Retry packet, which answers the Initial packet sent to
+    /// the `origin_dcid`.
+    pub fn verify_integrity(&self, origin_dcid: &ConnectionId) -> bool {
+        let mut tag = self.specific.integrity;
+        retry_integrity_key()
+            .open_in_place(
+                ring::aead::Nonce::assume_unique_for_key(RETRY_INTEGRITY_NONCE),
+                ring::aead::Aad::from(self.pseudo_packet(origin_dcid)),
+                &mut tag,
+            )
+            .is_ok()
+    }
+
+    /// The Retry Pseudo-Packet, the `origin_dcid` followed by the Retry packet without the tag.
+    fn pseudo_packet(&self, origin_dcid: &ConnectionId) -> Vec<u8> {
+        use crate::{cid::WriteConnectionId, packet::header::io::WriteHeader};
+
+        let mut pseudo_packet = Vec::new();
+        pseudo_packet.put_connection_id(origin_dcid);
+        let first_byte = pseudo_packet.len();
+        pseudo_packet.put_header(self);
+        pseudo_packet[first_byte] |= self.specific.unused_bits;
+        pseudo_packet.truncate(pseudo_packet.len() - self.specific.integrity.len());
+        pseudo_packet
+    }
+}
+
+fn retry_integrity_key() -> ring::aead::LessSafeKey {
+    let key = ring::aead::UnboundKey::new(&ring::aead::AES_128_GCM, &RETRY_INTEGRITY_KEY)
+        .expect("the key length matches AES-128-GCM");
+    ring::aead::LessSafeKey::new(key)
+}
+
 /// Initial packet header, which is a long header packet.
 ///
 /// See [initial packet](https://www.rfc-editor.org/rfc/rfc9000.html#name-initial-packet)
@@ -323,7 +399,11 @@ pub mod io {
 
         /// Build into a retry header.
         pub fn retry(self, token: Vec<u8>, integrity: [u8; 16]) -> LongHeader<Retry> {
-            self.wrap(Retry { token, integrity })
+            self.wrap(Retry {
+                token,
+                integrity,
+                unused_bits: 0,
+            })
         }
 
         /// Build into an initial header.
@@ -538,4 +618,38 @@ mod tests {
         buf.put_specific(&initial_long_header.specific);
         assert_eq!(buf, vec![0x03, 0x00, 0x00, 0x00,]);
     }
+
+    #[test]
+    fn test_retry_integrity() {
+        use bytes::BytesMut;
+
+        use crate::{
+            cid::ConnectionId,
+            packet::{Packet, header::GetScid, io::be_packet},
+        };
+
+        // RFC9001 A.4, the unused bits of the first byte are all set
+        let mut datagram = BytesMut::from(
+            &[
+                0xff, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08, 0xf0, 0x67, 0xa5, 0x50, 0x2a, 0x42, 0x62,
+                0xb5, 0x74, 0x6f, 0x6b, 0x65, 0x6e, 0x04, 0xa2, 0x65, 0xba, 0x2e, 0xff, 0x4d, 0x82,
+                0x90, 0x58, 0xfb, 0x3f, 0x0f, 0x24, 0x96, 0xba,
+            ][..],
+        );
+        let Ok(Packet::Retry(retry)) = be_packet(&mut datagram, 0) else {
+            panic!("unexpected packet");
+        };
+        let origin_dcid =
+            ConnectionId::from_slice(&[0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08]);
+        assert!(retry.verify_integrity(&origin_dcid));
+        assert!(!retry.verify_integrity(&ConnectionId::from_slice(&[0x83, 0x94])));
+
+        let retry = super::RetryHeader::new_retry(
+            ConnectionId::default(),
+            *retry.scid(),
+            b"token".to_vec(),
+            &origin_dcid,
+        );
+        assert!(retry.verify_integrity(&origin_dcid));
+    }
 }
@@ -61,7 +61,8 @@ pub fn be_packet(datagram: &mut BytesMut, dcid_len: usize) -> Result<Packet, Err
             datagram.clear();
             Ok(Packet::VN(header))
         }
-        Header::Retry(header) => {
+        Header::Retry(mut header) => {
+            header.set_unused_bits(input[0]);
             datagram.clear();
             Ok(Packet::Retry(header))
         }
@@ -354,6 +355,7 @@ frame_packages! {
     impl<Target: WriteFrame<Self>> Package<Target> for PaddingFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for PingFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for AckFrame {}
+    impl<Target: WriteFrame<Self>> Package<Target> for PathAckFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for ConnectionCloseFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for NewTokenFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for MaxDataFrame {}
@@ -361,6 +363,8 @@ frame_packages! {
     impl<Target: WriteFrame<Self>> Package<Target> for HandshakeDoneFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for PathChallengeFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for PathResponseFrame {}
+    impl<Target: WriteFrame<Self>> Package<Target> for PathAbandonFrame {}
+    impl<Target: WriteFrame<Self>> Package<Target> for PathStatusFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for StreamCtlFrame {}
     impl<Target: WriteFrame<Self>> Package<Target> for ReliableFrame {}
     impl<Target: WriteDataFrame<Self, D>, D: ContinuousData> Package<Target> for (StreamFrame, D) {}
@@ -509,7 +513,12 @@ impl<D: ContinuousData> RecordFrame<Frame<D>, D> for PacketProperties {
         self.ack_eliciting |= !frame.specs().contain(Spec::NonAckEliciting);
         self.in_flight |= !frame.specs().contain(Spec::CongestionControlFree);
         self.probe_new_path |= frame.specs().contain(Spec::ProbeNewPath);
-        if let Frame::Ack(ack_frame) = frame {
+        let ack_frame = match frame {
+            Frame::Ack(ack_frame) => Some(ack_frame),
+            Frame::PathAck(path_ack_frame) => Some(path_ack_frame.ack()),
+            _ => None,
+        };
+        if let Some(ack_frame) = ack_frame {
             self.largest_ack = Some(match self.largest_ack {
                 Some(largest_ack) => largest_ack.max(ack_frame.largest()),
                 None => ack_frame.largest(),
@@ -218,6 +218,20 @@ impl ArcKeys {
         self.lock_guard().set(keys);
     }
 
+    /// Replace the keys of the [`ArcKeys`], whether they are ready or not.
+    ///
+    /// The Initial keys are derived from the destination connection ID of the client's first
+    /// Initial packet, the client switches to the keys derived from the new destination
+    /// connection ID after the server answers with a Retry packet.
+    pub fn replace_keys(&self, keys: Keys) {
+        let mut state = self.lock_guard();
+        match &mut *state {
+            KeysState::Ready(ready) => *ready = keys,
+            KeysState::Pending(..) => state.set(keys),
+            KeysState::Invalid => {}
+        }
+    }
+
     /// Retire the keys, which means that the keys are no longer available.
     ///
     /// This is used when the connection enters the closing state or draining state.
@@ -380,6 +394,66 @@ impl ArcOneRttPacketKeys {
     }
 }
 
+/// A 1-RTT packet key bound to a path identifier of multipath QUIC.
+///
+/// With multipath, the path identifier participates in the nonce calculation,
+/// so that the packet number spaces of different paths can safely share the
+/// same packet key. The path 0 nonce is the same as the one of single path QUIC.
+///
+/// See [Nonce Calculation](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-nonce-calculation)
+/// of [Multipath Extension for QUIC](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/)
+/// for more details.
+pub struct PathPacketKey {
+    path_id: u32,
+    key: Arc<dyn PacketKey>,
+}
+
+impl PathPacketKey {
+    /// Bind the packet key to the path with `path_id`.
+    ///
+    /// The key is returned as is for path 0.
+    pub fn bind(path_id: u32, key: Arc<dyn PacketKey>) -> Arc<dyn PacketKey> {
+        match path_id {
+            0 => key,
+            path_id => Arc::new(Self { path_id, key }),
+        }
+    }
+}
+
+impl PacketKey for PathPacketKey {
+    fn encrypt_in_place(
+        &self,
+        packet_number: u64,
+        header: &[u8],
+        payload: &mut [u8],
+    ) -> Result<rustls::quic::Tag, rustls::Error> {
+        self.key
+            .encrypt_in_place_for_path(self.path_id, packet_number, header, payload)
+    }
+
+    fn decrypt_in_place<'a>(
+        &self,
+        packet_number: u64,
+        header: &[u8],
+        payload: &'a mut [u8],
+    ) -> Result<&'a [u8], rustls::Error> {
+        self.key
+            .decrypt_in_place_for_path(self.path_id, packet_number, header, payload)
+    }
+
+    fn tag_len(&self) -> usize {
+        self.key.tag_len()
+    }
+
+    fn confidentiality_limit(&self) -> u64 {
+        self.key.confidentiality_limit()
+    }
+
+    fn integrity_limit(&self) -> u64 {
+        self.key.integrity_limit()
+    }
+}
+
 /// The header protection keys for 1-RTT packets.
 #[derive(Clone)]
 pub struct HeaderProtectionKeys {
@@ -6,12 +6,15 @@ use std::{
     time::Duration,
 };
 
+use bytes::Bytes;
+
 use crate::{
     cid::ConnectionId,
     error::{Error, ErrorKind, QuicError},
     frame::FrameType,
     role::Role,
     time::MaxIdleTimer,
+    varint::VarInt,
 };
 
 pub mod core;
@@ -194,6 +197,24 @@ impl Parameters {
         }
     }
 
+    /// Return the local value of the custom transport parameter `id`.
+    ///
+    /// See [`core::Parameters::set_custom`].
+    pub fn get_local_custom(&self, id: VarInt) -> Option<Bytes> {
+        match self.role() {
+            Role::Client => self.client()?.get_custom(id).cloned(),
+            Role::Server => self.server()?.get_custom(id).cloned(),
+        }
+    }
+
+    /// Return the value of the custom transport parameter `id` sent by the peer.
+    pub fn get_remote_custom(&self, id: VarInt) -> Option<Bytes> {
+        match self.role() {
+            Role::Client => self.server()?.get_custom(id).cloned(),
+            Role::Server => self.client()?.get_custom(id).cloned(),
+        }
+    }
+
     // fn set_retry_scid(&mut self, cid: ConnectionId) {
     //     assert_eq!(self.role(), Role::Server);
     //     self.server.set_retry_source_connection_id(cid);
@@ -235,11 +256,13 @@ impl Parameters {
             PeerParameters::Client(p) => {
                 assert_eq!(self.role(), Role::Server);
                 assert!(self.client.is_empty());
+                validate_ack_delays(&p)?;
                 self.client = Arc::new(p);
             }
             PeerParameters::Server(p) => {
                 assert_eq!(self.role(), Role::Client);
                 assert!(self.server.is_empty());
+                validate_ack_delays(&p)?;
                 self.server = Arc::new(p);
             }
         }
@@ -329,7 +352,7 @@ impl Parameters {
         match self.requirements {
             Requirements::Client {
                 initial_scid,
-                retry_scid: _,
+                retry_scid,
                 origin_dcid,
             } => {
                 let Some(initial_scid) = initial_scid else {
@@ -345,10 +368,14 @@ impl Parameters {
                         "Initial Source Connection ID from server mismatch",
                     ));
                 }
-                // 并不正确，要和intiial_scid一样地去验证
-                // if self.server.retry_source_connection_id() != retry_scid {
-                //     return Err(param_error("Retry Source Connection ID mismatch"));
-                // }
+                // 没有收到Retry包时，服务器也不能携带该参数
+                if self
+                    .server
+                    .get::<ConnectionId>(ParameterId::RetrySourceConnectionId)
+                    != retry_scid
+                {
+                    return Err(param_error("Retry Source Connection ID mismatch"));
+                }
                 if self
                     .server
                     .get::<ConnectionId>(ParameterId::OriginalDestinationConnectionId)
@@ -404,6 +431,30 @@ impl Parameters {
     }
 }
 
+/// The min_ack_delay in the peer's `params` must not exceed its max_ack_delay, see
+/// [draft-ietf-quic-ack-frequency](https://www.ietf.org/archive/id/draft-ietf-quic-ack-frequency-11.html#name-negotiating-extension-use).
+fn validate_ack_delays<R>(params: &core::Parameters<R>) -> Result<(), QuicError> {
+    let Some(min_ack_delay) = params.get::<VarInt>(ParameterId::MinAckDelay) else {
+        return Ok(());
+    };
+    let max_ack_delay = params
+        .get::<Duration>(ParameterId::MaxAckDelay)
+        .expect("unreachable: default value will be got if the value unset");
+    // min_ack_delay以微秒为单位，max_ack_delay以毫秒为单位
+    if u128::from(min_ack_delay.into_inner()) > max_ack_delay.as_micros() {
+        return Err(QuicError::new(
+            ErrorKind::TransportParameter,
+            FrameType::Crypto.into(),
+            format!(
+                "MinAckDelay {}us is greater than MaxAckDelay {}ms",
+                min_ack_delay.into_inner(),
+                max_ack_delay.as_millis()
+            ),
+        ));
+    }
+    Ok(())
+}
+
 /// Shared transport parameter sets for both endpoints.
 ///
 /// The local transport parameters are set initially, while
@@ -496,7 +547,6 @@ mod tests {
     use std::sync::Arc;
 
     use super::*;
-    use crate::varint::VarInt;
 
     fn create_test_client_params() -> ClientParameters {
         let mut params = ClientParameters::default();
@@ -616,6 +666,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_min_ack_delay_exceeds_max_ack_delay() {
+        let client_params = |min_ack_delay: u32| {
+            let mut params = create_test_client_params();
+            params
+                .set(ParameterId::MaxAckDelay, Duration::from_millis(10))
+                .unwrap();
+            params
+                .set(ParameterId::MinAckDelay, VarInt::from_u32(min_ack_delay))
+                .unwrap();
+            params
+        };
+
+        let mut params = Parameters::new_server(create_test_server_params());
+        assert!(params.recv_remote_params(client_params(10_000)).is_ok());
+
+        let mut params = Parameters::new_server(create_test_server_params());
+        assert_eq!(
+            params.recv_remote_params(client_params(10_001)),
+            Err(QuicError::new(
+                ErrorKind::TransportParameter,
+                FrameType::Crypto.into(),
+                "MinAckDelay 10001us is greater than MaxAckDelay 10ms",
+            ))
+        );
+    }
+
     #[test]
     fn test_write_parameters() {
         let client_params = create_test_client_params();
@@ -630,6 +707,31 @@ mod tests {
         assert_eq!(guard.role(), Role::Client);
     }
 
+    #[test]
+    fn test_custom_parameters() {
+        use crate::param::io::WriteParameters;
+
+        let mut client_params = create_test_client_params();
+        assert_eq!(
+            client_params.set_custom(VarInt::from_u32(0x04), Bytes::from_static(b"max data")),
+            Err(error::Error::DefinedParameterId(
+                ParameterId::InitialMaxData
+            ))
+        );
+        client_params
+            .set_custom(VarInt::from_u32(0x3f0e), Bytes::from_static(b"extension"))
+            .unwrap();
+
+        let mut buf = Vec::new();
+        buf.put_parameters(&client_params);
+        let parsed = ClientParameters::parse_from_bytes(&buf).unwrap();
+        assert_eq!(
+            parsed.get_custom(VarInt::from_u32(0x3f0e)),
+            Some(&Bytes::from_static(b"extension"))
+        );
+        assert_eq!(parsed, client_params);
+    }
+
     #[tokio::test]
     async fn test_arc_parameters_error_handling() {
         let arc_params = ArcParameters::from(Parameters::new_client(
@@ -207,6 +207,24 @@ pub enum ParameterId {
     MaxDatagramFrameSize = 0x0020,
     #[param(value_type = Boolean)]
     GreaseQuicBit = 0x2ab2,
+    /// The maximum path identifier the endpoint is willing to maintain, multipath is only
+    /// enabled when both endpoints send this parameter.
+    ///
+    /// See [draft-ietf-quic-multipath](https://www.ietf.org/archive/id/draft-ietf-quic-multipath-10.html#name-initial_max_path_id-transpo).
+    #[param(value_type = VarInt, bound = 0..=0xffffffff)]
+    InitialMaxPathId = 0x0f739bbc1b666d0c,
+    /// The minimum amount of time in microseconds the endpoint is able to delay an acknowledgement,
+    /// sending it announces the endpoint accepts ACK_FREQUENCY and IMMEDIATE_ACK frames.
+    ///
+    /// See [draft-ietf-quic-ack-frequency](https://www.ietf.org/archive/id/draft-ietf-quic-ack-frequency-11.html#name-negotiating-extension-use).
+    #[param(value_type = VarInt, bound = 0..=0xffffff)]
+    MinAckDelay = 0xff04de1b,
+    /// Sending it announces the endpoint accepts ADD_ADDRESS and PUNCH_ME_NOW frames, hole
+    /// punching is only used when both endpoints send this parameter.
+    ///
+    /// See [draft-seemann-quic-nat-traversal](https://www.ietf.org/archive/id/draft-seemann-quic-nat-traversal-02.html#name-nat_traversal-transport-par).
+    #[param(value_type = Boolean)]
+    NatTraversal = 0x3d7e9f0bca12fea6,
     /// Genemta extension parameter.
     #[param(value_type = Bytes, default = 0u32)]
     ClientName = 0xffee,
@@ -240,6 +258,8 @@ impl std::fmt::LowerHex for ParameterId {
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct Parameters<Role> {
     pub(super) map: HashMap<ParameterId, ParameterValue>,
+    // 应用自定义的扩展参数，以及收到的未知参数，其值均为原始字节
+    pub(super) custom: HashMap<VarInt, Bytes>,
     _role: PhantomData<Role>,
 }
 
@@ -259,6 +279,34 @@ impl<Role> Parameters<Role> {
     pub fn is_empty(&self) -> bool {
         self.map.is_empty()
     }
+
+    /// Set a custom transport parameter, which is not defined by the transport.
+    ///
+    /// The value is carried in the transport parameters as it is, applications negotiate the
+    /// extension by setting it locally and reading the peer's value with
+    /// [`Parameters::get_custom`].
+    ///
+    /// Return an error if the `id` is one of the [`ParameterId`]s, use [`Parameters::set`] for them.
+    pub fn set_custom(&mut self, id: VarInt, value: impl Into<Bytes>) -> Result<(), Error> {
+        if let Ok(defined) = ParameterId::try_from(id) {
+            return Err(Error::DefinedParameterId(defined));
+        }
+        self.custom.insert(id, value.into());
+        Ok(())
+    }
+
+    /// Return the value of the custom transport parameter, if it is set or received.
+    ///
+    /// The transport parameters received from the peer keep all the parameters the transport
+    /// does not know as custom parameters.
+    pub fn get_custom(&self, id: VarInt) -> Option<&Bytes> {
+        self.custom.get(&id)
+    }
+
+    /// Return all the custom transport parameters.
+    pub fn custom(&self) -> impl Iterator<Item = (VarInt, &Bytes)> {
+        self.custom.iter().map(|(id, value)| (*id, value))
+    }
 }
 
 impl<R: IntoRole + Default> Parameters<R> {
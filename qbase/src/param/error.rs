@@ -18,6 +18,8 @@ pub enum Error {
     IncompleteParameterId(String),
     #[error("Parameter {0} is not defined")]
     UnknownParameterId(VarInt),
+    #[error("{0:?} is defined by the transport, not a custom parameter")]
+    DefinedParameterId(ParameterId),
     #[error("Lack {1:?} for {0}")]
     LackParameterId(Role, ParameterId),
     #[error("{0:?} is not belong to {1}")]
@@ -141,6 +141,11 @@ impl<Role, T: bytes::BufMut> WriteParameters<Role> for T {
         for (id, value) in &params.map {
             self.put_parameter(*id, value);
         }
+        for (id, value) in &params.custom {
+            self.put_varint(id);
+            self.put_varint(&VarInt::try_from(value.len()).expect("param too large"));
+            self.put_slice(value);
+        }
     }
 }
 
@@ -162,9 +167,12 @@ impl<R: IntoRole + RequiredParameters + Default> Parameters<R> {
 
             let param_id = match ParameterId::try_from(param_id) {
                 Ok(param_id) => param_id,
-                Err(unknown @ Error::UnknownParameterId(..)) => {
-                    tracing::warn!("{unknown}, ignore");
-                    continue; // Ignore unknown parameters
+                Err(Error::UnknownParameterId(id)) => {
+                    // Unknown parameters are kept as custom parameters for the application
+                    parameters
+                        .custom
+                        .insert(id, Bytes::copy_from_slice(param_value));
+                    continue;
                 }
                 Err(e) => return Err(e.into()),
             };
@@ -195,9 +203,12 @@ impl ServerParameters {
 
             let param_id = match ParameterId::try_from(param_id) {
                 Ok(param_id) => param_id,
-                Err(unknown @ Error::UnknownParameterId(..)) => {
-                    tracing::warn!("{unknown}, ignore");
-                    continue; // Ignore unknown parameters
+                Err(Error::UnknownParameterId(id)) => {
+                    // Unknown parameters are kept as custom parameters for the application
+                    parameters
+                        .custom
+                        .insert(id, Bytes::copy_from_slice(param_value));
+                    continue;
                 }
                 Err(e) => return Err(e.into()),
             };
@@ -2,7 +2,7 @@ use std::sync::{Arc, Mutex};
 
 use qbase::{
     Epoch,
-    frame::AckFrame,
+    frame::{AckFrame, AckFrequencyFrame},
     net::tx::{ArcSendWaker, Signals},
 };
 use qevent::quic::recovery::PacketLostTrigger;
@@ -19,6 +19,9 @@ use crate::{
 
 const INIT_CWND: usize = MSS * 10;
 const PACKET_THRESHOLD: usize = 3;
+// 请求对端稀疏ack时的上限，以免对端积攒太多未确认的包
+const MAX_ACK_ELICITING_THRESHOLD: u64 = 64;
+const MAX_REQUESTED_ACK_DELAY: Duration = Duration::from_millis(25);
 
 /// Imple RFC 9002 Appendix A. Loss Recovery
 /// See [Appendix A](https://datatracker.ietf.org/doc/html/rfc9002#name-loss-recovery-pseudocode)
@@ -30,7 +33,13 @@ pub struct CongestionController {
     // The number of times a PTO has been sent without receiving an acknowledgment.
     // Use to pto backoff
     pto_count: u32,
+    // The time of the earliest ack-eliciting packet sent since the last acknowledgment.
+    unacked_since: Option<Instant>,
+    // The time the last acknowledgment of newly acked packets was received.
+    last_acked: Option<Instant>,
     max_ack_delay: Duration,
+    // The max ack delay requested from the peer by the ACK_FREQUENCY frame.
+    requested_max_ack_delay: Option<Duration>,
     packet_spaces: [PacketSpace; Epoch::count()],
     // pacer is used to control the burst rate
     pacer: pacing::Pacer,
@@ -63,7 +72,10 @@ impl CongestionController {
             rtt: ArcRtt::new(),
             loss_detection_timer: None,
             pto_count: 0,
+            unacked_since: None,
+            last_acked: None,
             max_ack_delay,
+            requested_max_ack_delay: None,
             packet_spaces: [
                 PacketSpace::with_epoch(Epoch::Initial, Duration::ZERO),
                 PacketSpace::with_epoch(Epoch::Handshake, Duration::ZERO),
@@ -106,6 +118,7 @@ impl CongestionController {
         if in_flight {
             if ack_eliciting {
                 self.packet_spaces[epoch].time_of_last_ack_eliciting_packet = Some(now);
+                self.unacked_since.get_or_insert(now);
                 self.need_send_ack_eliciting_packets[epoch] =
                     self.need_send_ack_eliciting_packets[epoch].saturating_sub(1);
             }
@@ -186,6 +199,8 @@ impl CongestionController {
         match self.packet_spaces[epoch].on_ack_rcvd(ack_frame, &mut self.algorithm) {
             None => return,
             Some(newly_acked_packets) => {
+                self.unacked_since = None;
+                self.last_acked = Some(now);
                 let (largest_pn, largest_time_sent) = newly_acked_packets.largest;
                 if largest_pn == ack_frame.largest() && newly_acked_packets.include_ack_eliciting {
                     self.rtt.update(
@@ -464,13 +479,43 @@ impl CongestionController {
         self.set_loss_detection_timer();
     }
 
+    fn abandon(&mut self) {
+        for &epoch in Epoch::iter() {
+            let inflight = self.packet_spaces[epoch].drain_inflight(&mut self.algorithm);
+            if !inflight.is_empty() {
+                self.trackers[epoch]
+                    .may_loss(PacketLostTrigger::PtoExpired, &mut inflight.into_iter());
+            }
+        }
+        self.loss_detection_timer = None;
+    }
+
+    fn retry(&mut self) {
+        self.abandon();
+        self.pto_count = 0;
+    }
+
     fn get_pto(&self, epoch: Epoch) -> Duration {
         let mut pto_time = self.rtt.base_pto(self.pto_count);
         if epoch == Epoch::Data {
-            pto_time += self.max_ack_delay * (1 << self.pto_count);
+            let max_ack_delay = self
+                .requested_max_ack_delay
+                .map_or(self.max_ack_delay, |delay| delay.max(self.max_ack_delay));
+            pto_time += max_ack_delay * (1 << self.pto_count);
         }
         pto_time
     }
+
+    /// Acknowledge about 4 times per congestion window, and 4 times per RTT, but never more
+    /// often than the peer is able to.
+    fn ack_frequency(&self, min_ack_delay: Duration) -> (u64, Duration) {
+        let cwnd_packets = self.algorithm.congestion_window() / self.path_status.mtu().max(1);
+        let threshold = (cwnd_packets as u64 / 4).clamp(1, MAX_ACK_ELICITING_THRESHOLD);
+        let max_ack_delay = (self.rtt.smoothed_rtt() / 4)
+            .min(MAX_REQUESTED_ACK_DELAY)
+            .max(min_ack_delay);
+        (threshold, max_ack_delay)
+    }
 }
 
 #[derive(Clone)]
@@ -594,6 +639,26 @@ impl super::Transport for ArcCC {
         guard.discard_epoch(epoch);
     }
 
+    fn abandon(&self) {
+        let mut guard = self.0.lock().unwrap();
+        guard.abandon();
+    }
+
+    fn retry(&self) {
+        let mut guard = self.0.lock().unwrap();
+        guard.retry();
+    }
+
+    fn unacked_duration(&self) -> Option<Duration> {
+        let guard = self.0.lock().unwrap();
+        guard.unacked_since.map(|since| since.elapsed())
+    }
+
+    fn since_last_acked(&self) -> Option<Duration> {
+        let guard = self.0.lock().unwrap();
+        guard.last_acked.map(|acked| acked.elapsed())
+    }
+
     fn need_send_ack_eliciting(&self, epoch: Epoch) -> usize {
         let guard = self.0.lock().unwrap();
         guard.need_send_ack_eliciting_packets[epoch]
@@ -603,6 +668,39 @@ impl super::Transport for ArcCC {
         let guard = self.0.lock().unwrap();
         guard.path_status.release_anti_amplification_limit();
     }
+
+    fn smoothed_rtt(&self) -> Duration {
+        let guard = self.0.lock().unwrap();
+        guard.rtt.smoothed_rtt()
+    }
+
+    fn congestion_window(&self) -> usize {
+        let guard = self.0.lock().unwrap();
+        guard.algorithm.congestion_window()
+    }
+
+    fn on_ack_frequency_rcvd(&self, frame: &AckFrequencyFrame) {
+        let mut guard = self.0.lock().unwrap();
+        guard.packet_spaces[Epoch::Data]
+            .rcvd_packets
+            .on_ack_frequency_rcvd(frame);
+    }
+
+    fn ack_immediately(&self, epoch: Epoch) {
+        let mut guard = self.0.lock().unwrap();
+        guard.packet_spaces[epoch].rcvd_packets.ack_immediately();
+        guard.tx_waker.wake_by(Signals::TRANSPORT);
+    }
+
+    fn ack_frequency(&self, min_ack_delay: Duration) -> (u64, Duration) {
+        let guard = self.0.lock().unwrap();
+        guard.ack_frequency(min_ack_delay)
+    }
+
+    fn on_ack_frequency_sent(&self, max_ack_delay: Duration) {
+        let mut guard = self.0.lock().unwrap();
+        guard.requested_max_ack_delay = Some(max_ack_delay);
+    }
 }
 
 #[cfg(test)]
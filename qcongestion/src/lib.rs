@@ -1,4 +1,8 @@
-use qbase::{Epoch, frame::AckFrame, net::tx::Signals};
+use qbase::{
+    Epoch,
+    frame::{AckFrame, AckFrequencyFrame},
+    net::tx::Signals,
+};
 use qevent::quic::recovery::PacketLostTrigger;
 use thiserror::Error;
 use tokio::time::{Duration, Instant};
@@ -76,6 +80,45 @@ pub trait Transport {
 
     /// Releases the anti-amplification limit for this path.
     fn grant_anti_amplification(&self);
+
+    /// Returns how long ack-eliciting packets have been sent on this path without
+    /// any of them being acknowledged, or `None` if nothing is waiting for an ack.
+    fn unacked_duration(&self) -> Option<Duration>;
+
+    /// Returns how long ago packets sent on this path were last acknowledged, or `None` if
+    /// none has ever been.
+    fn since_last_acked(&self) -> Option<Duration>;
+
+    /// Declares all in-flight packets lost when the path is abandoned,
+    /// so that their frames can be retransmitted on other paths.
+    fn abandon(&self);
+
+    /// Declares the in-flight packets lost and resets the loss recovery state when the server
+    /// answers them with a Retry packet, so that their frames are sent again to the new
+    /// destination connection ID.
+    fn retry(&self);
+
+    /// Returns the current smoothed RTT estimate of this path.
+    fn smoothed_rtt(&self) -> Duration;
+
+    /// Returns the current congestion window of this path, in bytes.
+    fn congestion_window(&self) -> usize;
+
+    /// Honours the ACK_FREQUENCY frame from the peer when acknowledging the packets
+    /// received on this path.
+    fn on_ack_frequency_rcvd(&self, frame: &AckFrequencyFrame);
+
+    /// Sends an AckFrame for the given epoch as soon as possible, as the IMMEDIATE_ACK frame asks.
+    fn ack_immediately(&self, space: Epoch);
+
+    /// Returns the ack-eliciting threshold and the max ack delay to request from the peer
+    /// with the ACK_FREQUENCY frame, based on the congestion window and the RTT of this path.
+    ///
+    /// The requested delay is never less than the peer's `min_ack_delay`.
+    fn ack_frequency(&self, min_ack_delay: Duration) -> (u64, Duration);
+
+    /// Records the max ack delay requested from the peer, which is taken into account in the PTO.
+    fn on_ack_frequency_sent(&self, max_ack_delay: Duration);
 }
 
 /// The [`Feedback`] trait defines the interface for packet tracking
@@ -1,6 +1,9 @@
 use std::{cmp::Ordering, collections::VecDeque, time::Duration};
 
-use qbase::{Epoch, frame::AckFrame};
+use qbase::{
+    Epoch,
+    frame::{AckFrame, AckFrequencyFrame},
+};
 use tokio::time::Instant;
 
 use crate::algorithm::Control;
@@ -63,6 +66,9 @@ impl Ord for SentPacket {
 /// The [`RcvdRecords`] struct is used to maintain records of received packets for each epoch.
 /// It tracks acknowledged packets and determines when an ACK frame should be sent.
 /// It also retires packets that have been acknowledged by an ACK frame that has already sent and which has been confirmed by the peer.
+///
+/// The peer may ask for sparser acknowledgements with the ACK_FREQUENCY frame, see
+/// [`RcvdRecords::on_ack_frequency_rcvd`].
 #[derive(Debug)]
 pub(crate) struct RcvdRecords {
     epoch: Epoch,
@@ -70,6 +76,11 @@ pub(crate) struct RcvdRecords {
     latest_rcvd_time: Option<Instant>,
     largest_rcvd_packet: Option<(u64, Instant)>,
     max_ack_delay: Duration,
+    // 自上次发送ack以来收到的ack-eliciting包的数量
+    unacked_ack_eliciting: u64,
+    // 由ACK_FREQUENCY帧设置，未设置时仅依据max_ack_delay发送ack
+    ack_eliciting_threshold: Option<u64>,
+    reordering_threshold: u64,
 }
 
 impl RcvdRecords {
@@ -80,9 +91,29 @@ impl RcvdRecords {
             latest_rcvd_time: None,
             largest_rcvd_packet: None,
             max_ack_delay,
+            unacked_ack_eliciting: 0,
+            ack_eliciting_threshold: None,
+            reordering_threshold: 1,
         }
     }
 
+    /// Honour the acknowledgement behaviour requested by the peer.
+    ///
+    /// An ACK is sent after receiving more than `ack_eliciting_threshold` ack-eliciting packets,
+    /// or after the requested max ack delay. A packet received at least `reordering_threshold`
+    /// packets behind the largest received one is acknowledged immediately, unless the threshold
+    /// is 0.
+    pub(crate) fn on_ack_frequency_rcvd(&mut self, frame: &AckFrequencyFrame) {
+        self.ack_eliciting_threshold = Some(frame.ack_eliciting_threshold());
+        self.max_ack_delay = frame.request_max_ack_delay();
+        self.reordering_threshold = frame.reordering_threshold();
+    }
+
+    /// Acknowledge the received packets immediately, as the IMMEDIATE_ACK frame asks.
+    pub(crate) fn ack_immediately(&mut self) {
+        self.ack_immedietly = true;
+    }
+
     pub(crate) fn on_pkt_rcvd(&mut self, pn: u64) {
         // An endpoint MUST acknowledge all ack-eliciting Initial and Handshake packets immediately
         if self.epoch == Epoch::Initial || self.epoch == Epoch::Handshake {
@@ -97,9 +128,11 @@ impl RcvdRecords {
         if self.latest_rcvd_time.is_none() {
             self.latest_rcvd_time = Some(now);
         }
-        self.ack_immedietly |= self
-            .largest_rcvd_packet
-            .is_some_and(|(largest_pn, _)| pn < largest_pn);
+        self.ack_immedietly |= self.reordering_threshold > 0
+            && self.largest_rcvd_packet.is_some_and(|(largest_pn, _)| {
+                largest_pn.saturating_sub(pn) >= self.reordering_threshold
+            });
+        self.unacked_ack_eliciting += 1;
 
         self.largest_rcvd_packet =
             self.largest_rcvd_packet
@@ -120,6 +153,13 @@ impl RcvdRecords {
             return self.largest_rcvd_packet;
         }
 
+        if self
+            .ack_eliciting_threshold
+            .is_some_and(|threshold| self.unacked_ack_eliciting > threshold)
+        {
+            return self.largest_rcvd_packet;
+        }
+
         if self
             .latest_rcvd_time
             .is_some_and(|t| t + self.max_ack_delay < now)
@@ -135,6 +175,7 @@ impl RcvdRecords {
         self.largest_rcvd_packet = None;
         self.latest_rcvd_time = None;
         self.ack_immedietly = false;
+        self.unacked_ack_eliciting = 0;
     }
 }
 
@@ -292,6 +333,18 @@ impl PacketSpace {
         packet_numbers.into_iter()
     }
 
+    /// Discard all sent packets, returning the packet numbers of those still in flight.
+    pub(crate) fn drain_inflight(&mut self, algorithm: &mut Box<dyn Control>) -> Vec<u64> {
+        let inflight = self
+            .sent_packets
+            .iter()
+            .filter(|sent| sent.state == State::Inflight)
+            .map(|sent| sent.packet_number)
+            .collect();
+        self.discard(algorithm);
+        inflight
+    }
+
     pub(crate) fn discard(&mut self, algorithm: &mut Box<dyn Control>) {
         let mut remove_from_inflight = self
             .sent_packets
@@ -407,4 +460,39 @@ mod tests {
         rcvd_records.on_pkt_rcvd(11);
         assert_eq!(rcvd_records.need_ack().unwrap().0, 15);
     }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_rcvd_records_with_ack_frequency() {
+        use qbase::{frame::AckFrequencyFrame, varint::VarInt};
+
+        let mut rcvd_records = RcvdRecords::new(Epoch::Data, Duration::from_millis(25));
+        // ack every 4 ack-eliciting packets, within 100ms, ignoring reordering
+        rcvd_records.on_ack_frequency_rcvd(&AckFrequencyFrame::new(
+            VarInt::from_u32(0),
+            VarInt::from_u32(3),
+            VarInt::from_u32(100_000),
+            VarInt::from_u32(0),
+        ));
+
+        tokio::time::pause();
+        for pn in [0, 2, 1] {
+            rcvd_records.on_pkt_rcvd(pn);
+        }
+        tokio::time::advance(Duration::from_millis(50)).await;
+        assert_eq!(rcvd_records.need_ack(), None);
+        rcvd_records.on_pkt_rcvd(3);
+        assert_eq!(rcvd_records.need_ack().unwrap().0, 3);
+        rcvd_records.on_ack_sent(3, 3);
+
+        rcvd_records.on_pkt_rcvd(5);
+        tokio::time::advance(Duration::from_millis(50)).await;
+        assert_eq!(rcvd_records.need_ack(), None);
+        tokio::time::advance(Duration::from_millis(51)).await;
+        assert_eq!(rcvd_records.need_ack().unwrap().0, 5);
+        rcvd_records.on_ack_sent(5, 5);
+
+        rcvd_records.on_pkt_rcvd(6);
+        rcvd_records.ack_immediately();
+        assert_eq!(rcvd_records.need_ack().unwrap().0, 6);
+    }
 }
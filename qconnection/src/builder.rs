@@ -3,6 +3,7 @@ use std::{
     time::Duration,
 };
 
+use bytes::BufMut;
 pub use qbase::{
     cid::ConnectionId,
     packet::{
@@ -17,13 +18,18 @@ pub use qbase::{
 use qbase::{
     cid::GenUniqueCid,
     error::Error,
+    frame::{ConnectionCloseFrame, ExtensionFrames},
     net::tx::{ArcSendWakers, Signals},
-    packet::keys::ArcZeroRttKeys,
+    packet::{
+        AssemblePacket, LongHeaderBuilder, PacketNumber, PacketWriter,
+        keys::{ArcZeroRttKeys, Keys},
+    },
     param::{ArcParameters, ParameterId, Parameters},
     role::{IntoRole, Role},
     sid::handy::DemandConcurrency,
     time::ArcDeferIdleTimer,
-    token::ArcTokenRegistry,
+    token::{ArcTokenRegistry, TokenRegistry},
+    varint::VarInt,
 };
 use qcongestion::HandshakeStatus;
 use qevent::{
@@ -46,12 +52,18 @@ use tracing::Instrument as _;
 pub use crate::tls::{AuthClient, NoopClientAuther};
 use crate::{
     ArcLocalCids, ArcReliableFrameDeque, ArcRemoteCids, CidRegistry, Components, Connection,
-    ConnectionState, DataJournal, DataStreams, FlowController, Handshake, RawHandshake,
-    RouterRegistry, SpecificComponents,
+    ConnectionState, DataStreams, FlowController, Handshake, RawHandshake, RouterRegistry,
+    SpecificComponents,
     events::{ArcEventBroker, EmitEvent, Event},
-    path::ArcPathContexts,
+    path::{
+        ArcAckFrequency, ArcMultipath, ArcPathContexts, ArcPuncher, ArcScheduler, PathCidFrames,
+        SchedulerPolicy,
+    },
     space::{
-        Spaces, data::DataSpace, handshake::HandshakeSpace, initial::InitialSpace,
+        Spaces,
+        data::DataSpace,
+        handshake::HandshakeSpace,
+        initial::{ArcRetry, InitialSpace},
         spawn_deliver_and_parse,
     },
     state::ArcConnState,
@@ -125,6 +137,8 @@ pub struct ConnectionFoundation<Foundation, TlsConfig> {
     router: Arc<Router>,
     streams_ctrl: Box<dyn ControlStreamsConcurrency>,
     defer_idle_timeout: Duration,
+    scheduler_policy: SchedulerPolicy,
+    extension_frames: ExtensionFrames,
 }
 
 pub type ClientConnectionFoundation = ConnectionFoundation<ClientFoundation, TlsClientConfig>;
@@ -142,6 +156,8 @@ impl ClientFoundation {
             router: Router::global().clone(),
             streams_ctrl: Box::new(DemandConcurrency), // ZST cause no alloc
             defer_idle_timeout: Duration::ZERO,
+            scheduler_policy: SchedulerPolicy::default(),
+            extension_frames: ExtensionFrames::default(),
         }
     }
 }
@@ -182,6 +198,8 @@ impl ServerFoundation {
             router: Router::global().clone(),
             streams_ctrl: Box::new(DemandConcurrency), // ZST cause no alloc
             defer_idle_timeout: Duration::ZERO,
+            scheduler_policy: SchedulerPolicy::default(),
+            extension_frames: ExtensionFrames::default(),
         }
     }
 }
@@ -218,14 +236,19 @@ impl<Foundation, TlsConfig> ConnectionFoundation<Foundation, TlsConfig> {
         self.defer_idle_timeout = timeout;
         self
     }
+
+    pub fn with_scheduler_policy(mut self, policy: SchedulerPolicy) -> Self {
+        self.scheduler_policy = policy;
+        self
+    }
+
+    pub fn with_extension_frames(mut self, extension_frames: ExtensionFrames) -> Self {
+        self.extension_frames = extension_frames;
+        self
+    }
 }
 
-fn initial_keys_with(
-    crypto_provider: &Arc<CryptoProvider>,
-    origin_dcid: &ConnectionId,
-    side: rustls::Side,
-    version: rustls::quic::Version,
-) -> rustls::quic::Keys {
+fn initial_suite(crypto_provider: &Arc<CryptoProvider>) -> rustls::quic::Suite {
     crypto_provider
         .cipher_suites
         .iter()
@@ -237,26 +260,61 @@ fn initial_keys_with(
         })
         .flatten()
         .expect("crypto provider does not provide supported cipher suite")
-        .keys(origin_dcid, side, version)
+}
+
+fn initial_keys_with(
+    crypto_provider: &Arc<CryptoProvider>,
+    origin_dcid: &ConnectionId,
+    side: rustls::Side,
+    version: rustls::quic::Version,
+) -> rustls::quic::Keys {
+    initial_suite(crypto_provider).keys(origin_dcid, side, version)
+}
+
+/// Assemble an Initial packet carrying the `ccf` into the `buffer`, to close the connection which
+/// the client initiates from the `scid` to the `origin_dcid`, before the server creates it.
+///
+/// The packet is padded to fill the whole `buffer`, and protected by the server's Initial keys
+/// derived from the `origin_dcid`, so the server does not need any state of the connection to
+/// refuse it.
+pub fn assemble_stateless_close(
+    crypto_provider: &Arc<CryptoProvider>,
+    (origin_dcid, scid): (&ConnectionId, &ConnectionId),
+    ccf: &ConnectionCloseFrame,
+    buffer: &mut [u8],
+) -> Option<usize> {
+    let keys = Keys::from(initial_keys_with(
+        crypto_provider,
+        origin_dcid,
+        rustls::Side::Server,
+        crate::tls::QUIC_VERSION,
+    ));
+    let header = LongHeaderBuilder::with_cid(*scid, *origin_dcid).initial(vec![]);
+    let mut packet =
+        PacketWriter::new_long(&header, buffer, (0, PacketNumber::encode(0, 0)), keys.local)
+            .ok()?;
+    packet.assemble_packet(&mut { ccf }).ok()?;
+    // 接收方会丢弃小于1200字节的携带Initial包的数据报，用填充帧占满整个buffer
+    let padding = packet.remaining_mut();
+    packet.put_bytes(0, padding);
+    Some(packet.encrypt_and_protect_packet().0)
 }
 
 impl ConnectionFoundation<ClientFoundation, TlsClientConfig> {
     pub fn with_cids(self, origin_dcid: ConnectionId) -> PendingConnection {
-        let initial_keys = initial_keys_with(
-            self.tls_config.crypto_provider(),
-            &origin_dcid,
-            rustls::Side::Client,
-            crate::tls::QUIC_VERSION,
-        );
+        let initial_suite = initial_suite(self.tls_config.crypto_provider());
+        let initial_keys =
+            initial_suite.keys(&origin_dcid, rustls::Side::Client, crate::tls::QUIC_VERSION);
 
         let rcvd_pkt_q = Arc::new(RcvdPacketQueue::new());
 
         let tx_wakers = ArcSendWakers::default();
         let reliable_frames = ArcReliableFrameDeque::with_capacity_and_wakers(8, tx_wakers.clone());
 
-        let router_registry = self
-            .router
-            .registry_on_issuing_scid(rcvd_pkt_q.clone(), reliable_frames.clone());
+        let router_registry = self.router.registry_on_issuing_scid(
+            rcvd_pkt_q.clone(),
+            PathCidFrames::new(0, reliable_frames.clone()),
+        );
         let initial_scid = router_registry.gen_unique_cid();
 
         let mut clinet_params = self.foundation.client_params;
@@ -287,6 +345,8 @@ impl ConnectionFoundation<ClientFoundation, TlsClientConfig> {
             interfaces: self.ifaces,
             rcvd_pkt_q,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             role: Role::Client,
             origin_dcid,
             initial_scid,
@@ -300,7 +360,9 @@ impl ConnectionFoundation<ClientFoundation, TlsClientConfig> {
             initial_keys,
             zero_rtt_keys,
             streams_ctrl: self.streams_ctrl,
-            specific: SpecificComponents::Client {},
+            specific: SpecificComponents::Client {
+                retry: ArcRetry::new(initial_suite),
+            },
             qlogger: Arc::new(NoopLogger),
         }
     }
@@ -308,9 +370,31 @@ impl ConnectionFoundation<ClientFoundation, TlsClientConfig> {
 
 impl ConnectionFoundation<ServerFoundation, TlsServerConfig> {
     pub fn with_cids(self, origin_dcid: ConnectionId) -> PendingConnection {
+        self.with_initial_dcid(origin_dcid, None)
+    }
+
+    /// Accept the connection whose Initial packet is sent to the source connection ID of the
+    /// Retry packet the server answered before, instead of the `origin_dcid`.
+    ///
+    /// The Initial keys are derived from the `retry_scid`, and both connection IDs are
+    /// carried in the transport parameters for the client to authenticate.
+    pub fn with_retried_cids(
+        self,
+        origin_dcid: ConnectionId,
+        retry_scid: ConnectionId,
+    ) -> PendingConnection {
+        self.with_initial_dcid(origin_dcid, Some(retry_scid))
+    }
+
+    fn with_initial_dcid(
+        self,
+        origin_dcid: ConnectionId,
+        retry_scid: Option<ConnectionId>,
+    ) -> PendingConnection {
+        let initial_dcid = retry_scid.unwrap_or(origin_dcid);
         let initial_keys = initial_keys_with(
             self.tls_config.crypto_provider(),
-            &origin_dcid,
+            &initial_dcid,
             rustls::Side::Server,
             crate::tls::QUIC_VERSION,
         );
@@ -320,15 +404,19 @@ impl ConnectionFoundation<ServerFoundation, TlsServerConfig> {
         let tx_wakers = ArcSendWakers::default();
         let reliable_frames = ArcReliableFrameDeque::with_capacity_and_wakers(8, tx_wakers.clone());
 
-        let router_registry = self
-            .router
-            .registry_on_issuing_scid(rcvd_pkt_q.clone(), reliable_frames.clone());
+        let router_registry = self.router.registry_on_issuing_scid(
+            rcvd_pkt_q.clone(),
+            PathCidFrames::new(0, reliable_frames.clone()),
+        );
         let initial_scid = router_registry.gen_unique_cid();
-        let odcid_router_entry = self.router.insert(origin_dcid.into(), rcvd_pkt_q.clone());
+        let odcid_router_entry = self.router.insert(initial_dcid.into(), rcvd_pkt_q.clone());
 
         let mut server_params = self.foundation.server_params;
         _ = server_params.set(ParameterId::InitialSourceConnectionId, initial_scid);
         _ = server_params.set(ParameterId::OriginalDestinationConnectionId, origin_dcid);
+        if let Some(retry_scid) = retry_scid {
+            _ = server_params.set(ParameterId::RetrySourceConnectionId, retry_scid);
+        }
 
         let tls_session = ServerTlsSession::init(
             Arc::new(self.tls_config),
@@ -342,6 +430,8 @@ impl ConnectionFoundation<ServerFoundation, TlsServerConfig> {
             interfaces: self.ifaces,
             rcvd_pkt_q,
             defer_idle_timeout: self.defer_idle_timeout,
+            scheduler_policy: self.scheduler_policy,
+            extension_frames: self.extension_frames,
             role: Role::Server,
             origin_dcid,
             initial_scid,
@@ -368,6 +458,8 @@ pub struct PendingConnection {
     interfaces: Arc<QuicInterfaces>,
     rcvd_pkt_q: Arc<RcvdPacketQueue>,
     defer_idle_timeout: Duration,
+    scheduler_policy: SchedulerPolicy,
+    extension_frames: ExtensionFrames,
     role: Role,
     origin_dcid: ConnectionId,
     initial_scid: ConnectionId,
@@ -433,6 +525,10 @@ impl PendingConnection {
         let qlog_span = self.qlogger.new_trace(self.role.into(), group_id.clone());
         let tracing_span = tracing::info_span!("connection", role = %self.role, odcid = %group_id);
         let _span = (qlog_span.enter(), tracing_span.clone().entered());
+        // The server learns the server name from the ClientHello, see the tls module
+        if let TokenRegistry::Client((server_name, ..)) = &*self.token_registry {
+            qlog_span.on_server_name(server_name);
+        }
 
         let conn_state = ArcConnState::new();
         let event_broker = ArcEventBroker::new(conn_state.clone(), event_broker);
@@ -443,12 +539,14 @@ impl PendingConnection {
             event_broker.clone(),
         );
 
-        let local_cids = ArcLocalCids::new(self.initial_scid, self.router_registry);
+        let local_cid_limit = self
+            .parameters
+            .get_local(ParameterId::ActiveConnectionIdLimit)
+            .expect("unreachable: default value will be got if the value unset");
+        let local_cids = ArcLocalCids::new(self.initial_scid, self.router_registry.clone());
         let remote_cids = ArcRemoteCids::new(
-            self.parameters
-                .get_local(ParameterId::ActiveConnectionIdLimit)
-                .expect("unreachable: default value will be got if the value unset"),
-            self.reliable_frames.clone(),
+            local_cid_limit,
+            PathCidFrames::new(0, self.reliable_frames.clone()),
         );
         let cid_registry = CidRegistry::new(self.role, self.origin_dcid, local_cids, remote_cids);
 
@@ -484,12 +582,29 @@ impl PendingConnection {
             ),
         };
 
+        let multipath = ArcMultipath::new(
+            self.role,
+            cid_registry.clone(),
+            self.router_registry,
+            self.reliable_frames.clone(),
+            spaces.data().clone(),
+            local_cid_limit,
+        );
+        let paths = ArcPathContexts::new(
+            self.tx_wakers.clone(),
+            event_broker.clone(),
+            multipath.clone(),
+        );
         let components = Components {
             interfaces: self.interfaces,
             rcvd_pkt_q: self.rcvd_pkt_q,
             conn_state,
             defer_idle_timer: ArcDeferIdleTimer::new(self.defer_idle_timeout),
-            paths: ArcPathContexts::new(self.tx_wakers.clone(), event_broker.clone()),
+            paths: paths.clone(),
+            scheduler: ArcScheduler::new(self.scheduler_policy, paths),
+            ack_frequency: ArcAckFrequency::default(),
+            puncher: ArcPuncher::default(),
+            multipath,
             send_lock: self.send_lock,
             tls_handshake: ArcTlsHandshake::new(self.tls_session),
             quic_handshake,
@@ -499,6 +614,7 @@ impl PendingConnection {
             spaces,
             crypto_streams,
             reliable_frames: self.reliable_frames,
+            extension_frames: self.extension_frames,
             data_streams,
             flow_ctrl,
             datagram_flow,
@@ -508,6 +624,7 @@ impl PendingConnection {
 
         spawn_tls_handshake(&components, self.tx_wakers.clone());
         spawn_deliver_and_parse(&components);
+        tokio::spawn(components.follow_locations());
 
         let connection_state = Arc::new(ConnectionState {
             state: Ok(components).into(),
@@ -535,8 +652,9 @@ fn spawn_tls_handshake(components: &Components, tx_wakers: ArcSendWakers) {
             components.parameters.clone(),
             components.data_streams.clone(),
             components.flow_ctrl.clone(),
-            components.spaces.data().journal().clone(),
+            components.spaces.data().clone(),
             components.cid_registry.local.clone(),
+            components.multipath.clone(),
             tx_wakers,
         ),
     );
@@ -555,17 +673,21 @@ fn tls_fin_handler(
     parameters: ArcParameters,
     data_streams: DataStreams,
     flow_ctrl: FlowController,
-    data_journal: DataJournal,
+    data_space: Arc<DataSpace>,
     local_cids: ArcLocalCids,
+    multipath: ArcMultipath,
     tx_wakers: ArcSendWakers,
 ) -> impl FnOnce(&TlsHandshakeInfo) -> Result<(), Error> + Send {
+    #[allow(clippy::too_many_arguments)]
     fn apply_parameters<Role: IntoRole>(
         data_streams: &DataStreams,
         flow_ctrl: &FlowController,
         // datagram_flow
-        data_journal: &DataJournal,
+        data_space: &DataSpace,
         local_cids: &ArcLocalCids,
+        multipath: &ArcMultipath,
         zero_rtt_rejected: bool,
+        local_max_path_id: Option<VarInt>,
         remote_parameters: Arc<qbase::param::core::Parameters<Role>>,
     ) -> Result<(), Error> {
         // accept InitialMaxStreamsBidi, InitialMaxStreamUni,
@@ -579,16 +701,26 @@ fn tls_fin_handler(
                 .expect("unreachable: default value will be got if the value unset"),
         );
         // accept ActiveConnectionIdLimit
-        local_cids.set_limit(
-            remote_parameters
-                .get(ParameterId::ActiveConnectionIdLimit)
-                .expect("unreachable: default value will be got if the value unset"),
-        )?;
-        data_journal.of_rcvd_packets().revise_max_ack_delay(
-            remote_parameters
-                .get(ParameterId::MaxAckDelay)
-                .expect("unreachable: default value will be got if the value unset"),
-        );
+        let remote_cid_limit = remote_parameters
+            .get(ParameterId::ActiveConnectionIdLimit)
+            .expect("unreachable: default value will be got if the value unset");
+        local_cids.set_limit(remote_cid_limit)?;
+        let max_ack_delay = remote_parameters
+            .get(ParameterId::MaxAckDelay)
+            .expect("unreachable: default value will be got if the value unset");
+        data_space
+            .journal()
+            .of_rcvd_packets()
+            .revise_max_ack_delay(max_ack_delay);
+        // accept InitialMaxPathId, multipath is enabled only if both endpoints sent it
+        if let (Some(local), Some(remote)) = (
+            local_max_path_id,
+            remote_parameters.get::<VarInt>(ParameterId::InitialMaxPathId),
+        ) {
+            let local_max = u32::try_from(local.into_inner()).unwrap_or(u32::MAX);
+            let remote_max = u32::try_from(remote.into_inner()).unwrap_or(u32::MAX);
+            multipath.enable(local_max, remote_max, remote_cid_limit, max_ack_delay)?;
+        }
 
         Ok(())
     }
@@ -600,6 +732,7 @@ fn tls_fin_handler(
             .unwrap_or(false);
 
         let parameters = parameters.lock_guard()?;
+        let local_max_path_id = parameters.get_local::<VarInt>(ParameterId::InitialMaxPathId);
 
         if zero_rtt_rejected {
             debug_assert_eq!(parameters.role(), Role::Client);
@@ -620,9 +753,11 @@ fn tls_fin_handler(
                 apply_parameters(
                     &data_streams,
                     &flow_ctrl,
-                    &data_journal,
+                    &data_space,
                     &local_cids,
+                    &multipath,
                     zero_rtt_rejected,
+                    local_max_path_id,
                     remote_parameters,
                 )?;
             }
@@ -639,9 +774,11 @@ fn tls_fin_handler(
                 apply_parameters(
                     &data_streams,
                     &flow_ctrl,
-                    &data_journal,
+                    &data_space,
                     &local_cids,
+                    &multipath,
                     zero_rtt_rejected,
+                    local_max_path_id,
                     remote_parameters,
                 )?;
             }
@@ -11,8 +11,11 @@ pub mod prelude {
     pub use qbase::{
         cid::ConnectionId,
         error::{AppError, Error, ErrorKind, QuicError},
-        frame::ConnectionCloseFrame,
-        net::{addr::*, route::*},
+        frame::{
+            ConnectionCloseFrame, ExtensionFrame, ExtensionFrameDef, ExtensionFrameType,
+            ExtensionFrames,
+        },
+        net::{addr::*, route::*, scheme},
         param::ParameterId,
         role::{Client, IntoRole, Role, Server},
         sid::{ControlStreamsConcurrency, ProductStreamsConcurrencyController, StreamId},
@@ -29,7 +32,9 @@ pub mod prelude {
         pub use qinterface::{factory::handy::*, iface::handy::*};
     }
 
-    pub use crate::{Connection, StreamReader, StreamWriter, tls::AuthClient};
+    pub use crate::{
+        Connection, StreamReader, StreamWriter, path::SchedulerPolicy, tls::AuthClient,
+    };
 }
 
 pub mod builder;
@@ -43,14 +48,20 @@ use std::{
     sync::{Arc, RwLock, atomic::AtomicBool},
 };
 
+use bytes::Bytes;
 use enum_dispatch::enum_dispatch;
 use events::{ArcEventBroker, EmitEvent, Event};
-use path::ArcPathContexts;
+use path::{
+    ArcAckFrequency, ArcMultipath, ArcPathContexts, ArcPuncher, ArcScheduler, PathCidFrames,
+};
 use qbase::{
     cid,
     error::{AppError, Error, QuicError},
     flow,
-    frame::{ConnectionCloseFrame, CryptoFrame, Frame, ReliableFrame, StreamFrame},
+    frame::{
+        ConnectionCloseFrame, CryptoFrame, ExtensionFrame, ExtensionFrames, Frame, ReliableFrame,
+        SendFrame, StreamFrame,
+    },
     net::{
         addr::BindUri,
         route::{EndpointAddr, Link, Pathway},
@@ -60,6 +71,7 @@ use qbase::{
     sid::StreamId,
     time::ArcDeferIdleTimer,
     token::{ArcTokenRegistry, TokenRegistry},
+    varint::VarInt,
 };
 use qevent::{
     quic::{Owner, connectivity::ConnectionClosed},
@@ -85,7 +97,7 @@ use tls::ArcSendLock;
 use tracing::Instrument as _;
 
 use crate::{
-    path::error::{CreatePathFailure, PathDeactivated},
+    path::error::{CreatePathFailure, MigrateFailure, PathDeactivated, PunchFailure},
     termination::Terminator,
     tls::ArcTlsHandshake,
 };
@@ -122,11 +134,11 @@ pub type HandshakeJournal = journal::Journal<CryptoFrame>;
 pub type DataJournal = journal::Journal<GuaranteedFrame>;
 
 pub type ArcReliableFrameDeque = reliable::ArcReliableFrameDeque<ReliableFrame>;
-pub type RouterRegistry = route::RouterRegistry<ArcReliableFrameDeque>;
+pub type RouterRegistry = route::RouterRegistry<PathCidFrames>;
 pub type ArcLocalCids = cid::ArcLocalCids<RouterRegistry>;
-pub type ArcRemoteCids = cid::ArcRemoteCids<ArcReliableFrameDeque>;
+pub type ArcRemoteCids = cid::ArcRemoteCids<PathCidFrames>;
 pub type CidRegistry = cid::Registry<ArcLocalCids, ArcRemoteCids>;
-pub type ArcDcidCell = cid::ArcCidCell<ArcReliableFrameDeque>;
+pub type ArcDcidCell = cid::ArcCidCell<PathCidFrames>;
 
 pub type FlowController = flow::FlowController<ArcReliableFrameDeque>;
 pub type Credit<'a> = flow::Credit<'a, ArcReliableFrameDeque>;
@@ -146,6 +158,10 @@ pub struct Components {
     conn_state: ArcConnState,
     defer_idle_timer: ArcDeferIdleTimer,
     paths: ArcPathContexts,
+    scheduler: ArcScheduler,
+    ack_frequency: ArcAckFrequency,
+    puncher: ArcPuncher,
+    multipath: ArcMultipath,
     send_lock: ArcSendLock,
     tls_handshake: ArcTlsHandshake,
     quic_handshake: Handshake,
@@ -155,6 +171,7 @@ pub struct Components {
     spaces: Spaces,
     crypto_streams: [CryptoStream; 3],
     reliable_frames: ArcReliableFrameDeque,
+    extension_frames: ExtensionFrames,
     data_streams: DataStreams,
     flow_ctrl: FlowController,
     datagram_flow: DatagramFlow,
@@ -164,7 +181,9 @@ pub struct Components {
 
 #[derive(Clone)]
 pub enum SpecificComponents {
-    Client {},
+    Client {
+        retry: space::initial::ArcRetry,
+    },
     Server {
         using_odcid: Arc<AtomicBool>,
         odcid_router_entry: Arc<RouterEntry>,
@@ -243,6 +262,11 @@ impl Components {
             .in_current_span()
     }
 
+    pub fn streams_idle(&self) -> impl Future<Output = ()> + Send {
+        let data_streams = self.data_streams.clone();
+        async move { data_streams.idle().await }
+    }
+
     #[cfg(feature = "unreliable")]
     #[deprecated]
     pub fn unreliable_reader(&self) -> io::Result<DatagramReader> {
@@ -266,6 +290,20 @@ impl Components {
         .in_current_span()
     }
 
+    pub fn send_extension_frame(&self, frame: ExtensionFrame) {
+        self.reliable_frames.send_frame([frame]);
+    }
+
+    pub fn peer_custom_parameter(
+        &self,
+        id: VarInt,
+    ) -> impl Future<Output = Result<Option<Bytes>, Error>> + Send {
+        let parameters = self.parameters.clone();
+        async move { Ok(parameters.remote_ready().await?.get_remote_custom(id)) }
+            .instrument_in_current()
+            .in_current_span()
+    }
+
     pub fn add_local_endpoint(&self, _bind: BindUri, _addr: EndpointAddr) {
         todo!("Implement this method to add a local endpoint.")
     }
@@ -497,6 +535,13 @@ impl Connection {
         self.0.application_close(reason, code)
     }
 
+    /// Close the connection with a transport error, such as `CONNECTION_REFUSED`.
+    ///
+    /// Return `false` if the connection is already closed.
+    pub fn close_with_error(&self, error: QuicError) -> bool {
+        self.0.enter_closing(error)
+    }
+
     pub async fn open_bi_stream(
         &self,
     ) -> Result<Option<(StreamId, (StreamReader, StreamWriter))>, Error> {
@@ -559,29 +604,110 @@ impl Connection {
             .try_map_components(|core_conn| core_conn.del_path(pathway))
     }
 
+    /// Actively migrate the connection to the interface bound on `bind_uri`.
+    ///
+    /// The interface must have been bound in [`QuicInterfaces`], and be kept alive during the
+    /// migration. The returned future resolves when the new path is validated and the old paths are
+    /// retired.
+    ///
+    /// Only the client can initiate migration, and only after the handshake is confirmed. If the
+    /// server sent the `disable_active_migration` transport parameter, the migration is refused.
+    pub async fn migrate(&self, bind_uri: BindUri) -> Result<(), MigrateFailure> {
+        self.0
+            .try_map_components(|core_conn| core_conn.migrate(bind_uri))?
+            .await
+    }
+
+    /// Replace the relayed paths of the connection with direct paths to the peers, and return
+    /// the pathways of the new paths.
+    ///
+    /// The relayed paths are removed once the direct paths are validated. Like
+    /// [`Connection::migrate`], only the client can replace paths, after the handshake is confirmed.
+    pub async fn replace_relayed_paths(&self) -> Result<Vec<Pathway>, MigrateFailure> {
+        self.0
+            .try_map_components(|core_conn| core_conn.replace_relayed_paths())?
+            .await
+    }
+
+    /// Advertise the address of the interface bound on `bind_uri` to the client as a candidate
+    /// for hole punching.
+    ///
+    /// Only the server advertises addresses, and only if both endpoints sent the `nat_traversal`
+    /// transport parameter. See [`Connection::punch`].
+    pub async fn advertise_address(&self, bind_uri: BindUri) -> Result<(), PunchFailure> {
+        self.0
+            .try_map_components(|core_conn| core_conn.advertise_address(bind_uri))?
+            .await
+    }
+
+    /// Punch holes to the addresses the server advertised, from the interfaces the connection is
+    /// using, and return the pathways validated.
+    ///
+    /// The punched paths join the connection as direct paths. Only the client starts hole
+    /// punching, and only if both endpoints sent the `nat_traversal` transport parameter.
+    pub async fn punch(&self) -> Result<Vec<Pathway>, PunchFailure> {
+        self.0
+            .try_map_components(|core_conn| core_conn.punch())?
+            .await
+    }
+
     pub fn is_active(&self) -> bool {
         self.0.try_map_components(|_| true).unwrap_or_default()
     }
 
+    /// Start recording the qlog of this connection, even if it was not sampled.
+    ///
+    /// Return `false` if the qlog logger does not support it, only loggers that decide per
+    /// connection, such as [`SampledLogger`], do.
+    ///
+    /// [`SampledLogger`]: qevent::telemetry::sampling::SampledLogger
+    pub fn start_qlog(&self) -> bool {
+        self.0.qlog_span.set_recording(true)
+    }
+
+    /// Stop recording the qlog of this connection, see [`Connection::start_qlog`].
+    pub fn stop_qlog(&self) -> bool {
+        self.0.qlog_span.set_recording(false)
+    }
+
     pub fn origin_dcid(&self) -> Result<cid::ConnectionId, Error> {
         self.0
             .try_map_components(|core_conn| core_conn.cid_registry.origin_dcid())
     }
 
-    pub async fn handshaked(&self) -> bool {
-        if let Ok(f) = self
+    pub fn handshaked(&self) -> impl Future<Output = bool> + Send + 'static {
+        let handshaked = self
             .0
             .try_map_components(|core_conn| core_conn.conn_state.handshaked())
-        {
-            return f.await;
+            .ok();
+        async move {
+            match handshaked {
+                Some(f) => f.await,
+                None => false,
+            }
         }
-        false
     }
 
-    pub async fn terminated(&self) {
-        if let Ok(f) = self
+    pub fn terminated(&self) -> impl Future<Output = ()> + Send + 'static {
+        let terminated = self
             .0
             .try_map_components(|core_conn| core_conn.conn_state.terminated())
+            .ok();
+        async move {
+            if let Some(f) = terminated {
+                f.await
+            }
+        }
+    }
+
+    /// Wait for all data streams to finish.
+    ///
+    /// A stream is finished once all its data has been acknowledged and received, or it has been
+    /// reset. The future resolves immediately if the connection is not active.
+    pub async fn streams_idle(&self) {
+        if let Ok(f) = self
+            .0
+            .try_map_components(|core_conn| core_conn.streams_idle())
         {
             f.await
         }
@@ -606,6 +732,25 @@ impl Connection {
             .await
     }
 
+    /// Send an extension frame to the peer.
+    ///
+    /// The frame is sent in 0-RTT or 1-RTT packets, and sent again if lost when its type is
+    /// retransmittable. The peer must have registered the frame type, or it closes the connection.
+    pub fn send_extension_frame(&self, frame: ExtensionFrame) -> Result<(), Error> {
+        self.0
+            .try_map_components(|core_conn| core_conn.send_extension_frame(frame))
+    }
+
+    /// Return the custom transport parameter sent by the peer, once its transport parameters
+    /// are received.
+    ///
+    /// See [`qbase::param::core::Parameters::set_custom`].
+    pub async fn peer_custom_parameter(&self, id: VarInt) -> Result<Option<Bytes>, Error> {
+        self.0
+            .try_map_components(|core_conn| core_conn.peer_custom_parameter(id))?
+            .await
+    }
+
     pub fn add_local_endpoint(&self, bind: BindUri, addr: EndpointAddr) -> Result<(), Error> {
         self.0
             .try_map_components(|core_conn| core_conn.add_local_endpoint(bind, addr))
@@ -1,7 +1,7 @@
 use std::{
     io,
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicBool, AtomicU16, Ordering},
     },
 };
@@ -9,7 +9,9 @@ use std::{
 use qbase::{
     Epoch,
     error::Error,
-    frame::{PathChallengeFrame, PathResponseFrame, ReceiveFrame},
+    frame::{
+        PathAvailability, PathChallengeFrame, PathResponseFrame, PathStatusFrame, ReceiveFrame,
+    },
     net::{
         addr::BindUri,
         route::{Link, PacketHeader, Pathway},
@@ -17,24 +19,34 @@ use qbase::{
     },
     packet::PacketContains,
     param::ParameterId,
+    role::Role,
     time::{ArcDeferIdleTimer, ArcMaxIdleTimer, MaxIdleTimer},
 };
 use qcongestion::{Algorithm, ArcCC, Feedback, HandshakeStatus, MSS, PathStatus, Transport};
 use qevent::{quic::connectivity::PathAssigned, telemetry::Instrument};
-use qinterface::{QuicIoExt, iface::QuicInterface};
-use tokio::time::Duration;
+use qinterface::{QuicIO, QuicIoExt, iface::QuicInterface};
+use tokio::{sync::Semaphore, time::Duration};
 
 mod aa;
+mod ack_frequency;
 mod burst;
 mod drive;
 pub mod error;
+mod migrate;
+mod multipath;
 pub mod paths;
+mod punch;
+mod scheduler;
 pub mod util;
 mod validate;
 pub use aa::*;
+pub use ack_frequency::ArcAckFrequency;
 pub use burst::PacketSpace;
 pub use error::*;
+pub use multipath::{ArcMultipath, PathCidFrames};
 pub use paths::*;
+pub use punch::ArcPuncher;
+pub use scheduler::{ArcScheduler, SchedulerPolicy};
 use tokio_util::task::AbortOnDropHandle;
 use tracing::Instrument as _;
 pub use util::*;
@@ -43,8 +55,9 @@ use crate::{ArcDcidCell, Components, path::burst::BurstError};
 // pub mod burst;
 
 pub struct Path {
-    interface: QuicInterface,
+    interface: Mutex<QuicInterface>,
     validated: AtomicBool,
+    validation: Semaphore,
     link: Link,
     pathway: Pathway,
     cc: ArcCC,
@@ -58,6 +71,10 @@ pub struct Path {
     tx_waker: ArcSendWaker,
     pmtu: Arc<AtomicU16>,
     status: PathStatus,
+    // multipath: the path identifier of the packet number space used to send and receive
+    path_id: u32,
+    // multipath: the latest PATH_STATUS sequence number and status advertised by the peer
+    remote_availability: Mutex<Option<(u64, PathAvailability)>>,
 }
 
 impl Components {
@@ -68,19 +85,73 @@ impl Components {
         pathway: Pathway,
         is_probed: bool,
     ) -> Result<Arc<Path>, CreatePathFailure> {
+        // 握手期间收到的长包头数据包都属于路径0
+        let path_id = is_probed.then_some(0);
+        self.get_or_try_create_path_with(bind_uri, link, pathway, is_probed, path_id)
+    }
+
+    /// Get or create the path receiving the 1-RTT packets of the path identifier `path_id`.
+    ///
+    /// The returned path may use another path identifier, if this endpoint opened it first.
+    pub(crate) fn get_or_try_create_probed_path(
+        &self,
+        bind_uri: BindUri,
+        link: Link,
+        pathway: Pathway,
+        path_id: u32,
+    ) -> Result<Arc<Path>, CreatePathFailure> {
+        let path = self.get_or_try_create_path_with(
+            bind_uri.clone(),
+            link.clone(),
+            pathway.clone(),
+            true,
+            Some(path_id),
+        )?;
+        // 双方可能同时在同一对地址上打开了不同的路径ID（如打洞时），以客户端打开的路径ID为准
+        if path.path_id() != path_id && self.role() == Role::Server && !path.is_validated() {
+            self.paths.remove(&pathway, &PathDeactivated::Replaced);
+            return self.get_or_try_create_path_with(bind_uri, link, pathway, true, Some(path_id));
+        }
+        Ok(path)
+    }
+
+    // 对端发起的路径使用收到的数据包的路径ID，本端发起的路径打开一个新的路径ID
+    fn get_or_try_create_path_with(
+        &self,
+        bind_uri: BindUri,
+        link: Link,
+        pathway: Pathway,
+        is_probed: bool,
+        path_id: Option<u32>,
+    ) -> Result<Arc<Path>, CreatePathFailure> {
+        // 经代理打洞的路径直接与对端的外部地址收发，中继转发的包以中继的地址为源地址，
+        // 收到的包按链路归属于这些路径
+        if is_probed && self.paths.get(&pathway).is_none() {
+            if let Some(indirect) = self.paths.get_indirect(&link) {
+                return Ok(indirect);
+            }
+        }
+
         let try_create = || {
             let interface = self
                 .interfaces
                 .get(&bind_uri)
                 .ok_or(CreatePathFailure::NoInterface(bind_uri))?;
-            let dcid_cell = self.cid_registry.remote.apply_dcid();
+            let path_id = match path_id {
+                Some(path_id) => self.multipath.accept_path_id(path_id).map(|_| path_id)?,
+                None => self.multipath.open_path_id()?,
+            };
+            let dcid_cell = self
+                .multipath
+                .apply_dcid(path_id)
+                .ok_or(CreatePathFailure::NoPathId)?;
             let max_ack_delay = self
                 .parameters
                 .lock_guard()?
                 .get_local(ParameterId::MaxAckDelay)
                 .expect("unreachable: default value will be got if the value unset");
 
-            let is_initial_path = self.conn_state.try_entry_attempted(self, link)?;
+            let is_initial_path = self.conn_state.try_entry_attempted(self, link.clone())?;
             qevent::event!(PathAssigned {
                 path_id: pathway.to_string(),
                 path_local: link.src(),
@@ -89,8 +160,8 @@ impl Components {
 
             let path = Arc::new(Path::new(
                 interface,
-                link,
-                pathway,
+                link.clone(),
+                pathway.clone(),
                 dcid_cell,
                 max_ack_delay,
                 self.parameters.max_idle_timer(),
@@ -107,13 +178,17 @@ impl Components {
                             .tracker(self.crypto_streams[Epoch::Handshake].clone()),
                     ),
                     Arc::new(self.spaces.data().tracker(
+                        path_id,
                         self.crypto_streams[Epoch::Data].clone(),
                         self.data_streams.clone(),
                         self.reliable_frames.clone(),
+                        self.scheduler.clone(),
                     )),
                 ],
                 self.quic_handshake.status(),
+                path_id,
             ));
+            self.ack_frequency.apply_to(&path);
 
             let validate = {
                 let path = path.clone();
@@ -146,18 +221,30 @@ impl Components {
             let drive = {
                 let path = path.clone();
                 let tls_handshake = self.tls_handshake.clone();
-                async move { path.drive(tls_handshake).await }
+                let paths = self.paths.clone();
+                async move { path.drive(tls_handshake, paths).await }
             };
 
+            let request_ack_frequency = self.ack_frequency.clone().request_on(
+                path.clone(),
+                self.paths.clone(),
+                self.parameters.clone(),
+                self.reliable_frames.clone(),
+            );
+
             let burst = {
                 let path = path.clone();
-                let mut packages = self.packages();
+                let mut packages = self.packages(pathway.clone());
                 let burst = path.new_burst(self);
                 async move {
                     let mut buffers = vec![];
                     loop {
                         match burst.burst(&mut packages, &mut buffers).await {
-                            Ok(segments) => path.send_packets(&segments).await?,
+                            Ok(segments) => match path.send_packets(&segments).await {
+                                // 发送期间接口被重新绑定，下一次burst时换用新的绑定；若地址已变化，则停止在此路径上发送，等待连接迁移到新地址上的路径
+                                Err(_) if path.is_rebound() => continue,
+                                result => result?,
+                            },
                             Err(BurstError::Signals(s)) => path.tx_waker.wait_for(s).await,
                             Err(BurstError::PathDeactived) => return io::Result::Ok(()),
                         }
@@ -169,6 +256,7 @@ impl Components {
                 Err(tokio::select! {
                     Ok(Err(e)) = AbortOnDropHandle::new(tokio::spawn(validate.instrument_in_current().in_current_span())) => PathDeactivated::from(e),
                     Ok(Err(e)) = AbortOnDropHandle::new(tokio::spawn(drive.instrument_in_current().in_current_span())) => e,
+                    Ok(Err(e)) = AbortOnDropHandle::new(tokio::spawn(request_ack_frequency.instrument_in_current().in_current_span())) => e,
                     Ok(Err(e)) = AbortOnDropHandle::new(tokio::spawn(burst.instrument_in_current().in_current_span())) => PathDeactivated::from(e),
                 })
             };
@@ -177,11 +265,12 @@ impl Components {
                 Instrument::instrument(task, qevent::span!(@current, path=pathway.to_string()))
                     .in_current_span();
 
-            tracing::info!(%pathway, %link, is_probed, is_initial_path, "Add new path");
+            tracing::info!(%pathway, %link, is_probed, is_initial_path, path_id, "Add new path");
 
             Ok((path, task))
         };
-        self.paths.get_or_try_create_with(pathway, try_create)
+        self.paths
+            .get_or_try_create_with(pathway.clone(), try_create)
     }
 }
 
@@ -197,6 +286,7 @@ impl Path {
         defer_idle_timer: ArcDeferIdleTimer,
         feedbacks: [Arc<dyn Feedback>; 3],
         handshake_status: Arc<HandshakeStatus>,
+        path_id: u32,
     ) -> Self {
         let pmtu = Arc::new(AtomicU16::new(MSS as u16));
         let path_status = PathStatus::new(handshake_status, pmtu.clone());
@@ -210,12 +300,13 @@ impl Path {
             tx_waker.clone(),
         );
         Self {
-            interface,
+            interface: Mutex::new(interface),
             link,
             pathway,
             cc,
             dcid_cell,
             validated: AtomicBool::new(false),
+            validation: Semaphore::new(0),
             anti_amplifier: AntiAmplifier::new(tx_waker.clone()),
             max_idle_timer: ArcMaxIdleTimer::from(max_idle_timer),
             heartbeat: ArcHeartbeat::new(defer_idle_timer, Duration::from_secs(1)),
@@ -225,6 +316,8 @@ impl Path {
             tx_waker,
             pmtu,
             status: path_status,
+            path_id,
+            remote_availability: Mutex::new(None),
         }
     }
 
@@ -232,6 +325,62 @@ impl Path {
         &self.cc
     }
 
+    pub fn link(&self) -> Link {
+        self.link.clone()
+    }
+
+    pub fn pathway(&self) -> Pathway {
+        self.pathway.clone()
+    }
+
+    pub fn bind_uri(&self) -> BindUri {
+        self.interface.lock().unwrap().bind_uri()
+    }
+
+    /// The interface of the path.
+    ///
+    /// If the interface has been rebound at the source address of the path, for example after
+    /// its receive task failed, the path keeps working on the new binding. If the address
+    /// changed, the rebound handle is returned and can no longer be used.
+    fn interface(&self) -> QuicInterface {
+        let mut interface = self.interface.lock().unwrap();
+        if interface.is_rebound() {
+            match interface.reborrow() {
+                Ok(rebound)
+                    if rebound
+                        .real_addr()
+                        .is_ok_and(|addr| addr == self.link.src()) =>
+                {
+                    *interface = rebound
+                }
+                _ => {}
+            }
+        }
+        interface.clone()
+    }
+
+    fn is_rebound(&self) -> bool {
+        self.interface.lock().unwrap().is_rebound()
+    }
+
+    /// The multipath path identifier of the path, the packets are sent and received in the
+    /// packet number space of it.
+    ///
+    /// It is always 0 if multipath is not enabled.
+    pub fn path_id(&self) -> u32 {
+        self.path_id
+    }
+
+    /// The status of the path advertised by the peer with PATH_STATUS frames,
+    /// [`PathAvailability::Available`] if the peer never advertised it.
+    pub fn remote_availability(&self) -> PathAvailability {
+        self.remote_availability
+            .lock()
+            .unwrap()
+            .map(|(_, availability)| availability)
+            .unwrap_or_default()
+    }
+
     pub fn on_packet_rcvd(
         &self,
         epoch: Epoch,
@@ -253,6 +402,15 @@ impl Path {
             .on_pkt_rcvd(epoch, pn, packet_contains.ack_eliciting());
     }
 
+    /// Called when the path is removed from the connection.
+    ///
+    /// Packets in flight on this path are declared lost so that their frames can be
+    /// retransmitted on other paths, and tasks waiting for validation are woken up.
+    pub(super) fn on_deactivated(&self) {
+        self.cc().abandon();
+        self.validation.close();
+    }
+
     pub fn grant_anti_amplification(&self) {
         self.anti_amplifier.grant();
         self.cc().grant_anti_amplification();
@@ -268,8 +426,8 @@ impl Path {
         if self.anti_amplifier.balance().is_err() {
             self.status.enter_anti_amplification_limit();
         }
-        let hdr = PacketHeader::new(self.pathway, self.link, 64, None, self.mtu() as _);
-        self.interface.sendmmsg(bufs, hdr).await
+        let hdr = PacketHeader::new(self.pathway(), self.link(), 64, None, self.mtu() as _);
+        self.interface().sendmmsg(bufs, hdr).await
     }
 }
 
@@ -288,6 +446,19 @@ impl ReceiveFrame<PathChallengeFrame> for Path {
     }
 }
 
+impl ReceiveFrame<PathStatusFrame> for Path {
+    type Output = ();
+
+    fn recv_frame(&self, frame: &PathStatusFrame) -> Result<Self::Output, Error> {
+        let mut remote_availability = self.remote_availability.lock().unwrap();
+        // only the frame with the largest sequence number takes effect
+        if remote_availability.map_or(true, |(seq, _)| frame.sequence() > seq) {
+            *remote_availability = Some((frame.sequence(), frame.availability()));
+        }
+        Ok(())
+    }
+}
+
 impl ReceiveFrame<PathResponseFrame> for Path {
     type Output = ();
 
@@ -0,0 +1,200 @@
+use std::sync::{Arc, Mutex};
+
+use qbase::{
+    Epoch,
+    error::{ErrorKind, QuicError},
+    frame::{AckFrequencyFrame, GetFrameType, ImmediateAckFrame, ReliableFrame, SendFrame},
+    param::{ArcParameters, ParameterId},
+    varint::VarInt,
+};
+use qcongestion::Transport;
+use tokio::time::{self, Duration, Instant};
+
+use super::{ArcPathContexts, Path, PathDeactivated};
+use crate::ArcReliableFrameDeque;
+
+#[derive(Debug, Default)]
+struct AckFrequency {
+    // 发送端：下一个ACK_FREQUENCY帧的序号，上次请求的阈值、延迟以及请求的时间
+    next_sequence: u64,
+    requested: Option<(u64, Duration, Instant)>,
+    // 接收端：生效中的，即序号最大的ACK_FREQUENCY帧
+    rcvd: Option<AckFrequencyFrame>,
+}
+
+/// The connection level state of the ACK frequency extension, see
+/// [draft-ietf-quic-ack-frequency](https://datatracker.ietf.org/doc/draft-ietf-quic-ack-frequency/).
+///
+/// The extension is used only if the endpoints advertise the [`ParameterId::MinAckDelay`]
+/// transport parameter: an endpoint asks for sparser acknowledgements only if the peer
+/// advertised it, and accepts ACK_FREQUENCY frames only if it advertised it itself.
+#[derive(Debug, Default, Clone)]
+pub struct ArcAckFrequency(Arc<Mutex<AckFrequency>>);
+
+impl ArcAckFrequency {
+    /// Return the ACK_FREQUENCY frame to send, if the acknowledgement behaviour suitable for the
+    /// connection has changed since the last request, and whether the peer should also be asked
+    /// to acknowledge immediately.
+    ///
+    /// The sequence number is shared by all the paths, so a single request is computed for the
+    /// whole connection: the smallest threshold and delay suitable for any validated path.
+    /// Requests are sent at most once per smoothed RTT of the slowest path.
+    fn request(
+        &self,
+        paths: &ArcPathContexts,
+        min_ack_delay: Duration,
+    ) -> Option<(AckFrequencyFrame, bool)> {
+        let validated = paths
+            .iter()
+            .filter(|path| path.is_validated())
+            .collect::<Vec<_>>();
+        let (threshold, max_ack_delay) = validated
+            .iter()
+            .map(|path| path.cc().ack_frequency(min_ack_delay))
+            .reduce(|(t1, d1), (t2, d2)| (t1.min(t2), d1.min(d2)))?;
+        let smoothed_rtt = validated
+            .iter()
+            .map(|path| path.cc().smoothed_rtt())
+            .max()?;
+
+        let mut guard = self.0.lock().unwrap();
+        let now = Instant::now();
+        if guard.requested.is_some_and(|(t, d, at)| {
+            (t, d) == (threshold, max_ack_delay) || now < at + smoothed_rtt
+        }) {
+            return None;
+        }
+
+        // 要求对端更频繁地确认时，此前按旧要求推迟的确认也应立即发出
+        let ack_immediately = guard
+            .requested
+            .is_some_and(|(t, d, _)| threshold < t || max_ack_delay < d);
+        let sequence = guard.next_sequence;
+        guard.next_sequence += 1;
+        guard.requested = Some((threshold, max_ack_delay, now));
+        drop(guard);
+
+        for path in paths.iter() {
+            path.cc().on_ack_frequency_sent(max_ack_delay);
+        }
+        let frame = AckFrequencyFrame::new(
+            VarInt::from_u64(sequence).expect("sequence never exceeds VarInt::MAX"),
+            VarInt::from_u64(threshold).expect("threshold is small"),
+            VarInt::from_u128(max_ack_delay.as_micros())
+                .expect("requested max ack delay never exceeds VarInt::MAX"),
+            // 乱序的包由丢包检测处理，无需对端立即确认
+            VarInt::from_u32(0),
+        );
+        Some((frame, ack_immediately))
+    }
+
+    /// Keep asking the peer for the acknowledgement behaviour suitable for the connection, as
+    /// the congestion windows and RTTs of its paths change.
+    ///
+    /// Each path drives the requests at its own pace, until it is deactivated. Return immediately
+    /// if the peer does not support the extension.
+    pub(super) async fn request_on(
+        self,
+        path: Arc<Path>,
+        paths: ArcPathContexts,
+        parameters: ArcParameters,
+        reliable_frames: ArcReliableFrameDeque,
+    ) -> Result<(), PathDeactivated> {
+        let min_ack_delay = match parameters.remote_ready().await {
+            Ok(parameters) => parameters.get_remote::<VarInt>(ParameterId::MinAckDelay),
+            Err(_) => None,
+        };
+        let Some(min_ack_delay) = min_ack_delay else {
+            return Ok(());
+        };
+
+        let min_ack_delay = Duration::from_micros(min_ack_delay.into_inner());
+        loop {
+            time::sleep(path.cc().smoothed_rtt()).await;
+            if !path.is_validated() {
+                continue;
+            }
+            match self.request(&paths, min_ack_delay) {
+                Some((frame, true)) => reliable_frames
+                    .send_frame([ReliableFrame::from(frame), ImmediateAckFrame.into()]),
+                Some((frame, false)) => reliable_frames.send_frame([frame]),
+                None => {}
+            }
+        }
+    }
+
+    /// Apply the ACK_FREQUENCY frame received from the peer to all the paths, if it is the newest.
+    ///
+    /// The peer must not send the frame if the `min_ack_delay` was not advertised, or ask for a
+    /// delay less than the advertised one, these are connection errors of type PROTOCOL_VIOLATION.
+    pub fn recv_frame(
+        &self,
+        frame: &AckFrequencyFrame,
+        min_ack_delay: Option<Duration>,
+        paths: &ArcPathContexts,
+    ) -> Result<(), QuicError> {
+        let Some(min_ack_delay) = min_ack_delay else {
+            return Err(QuicError::new(
+                ErrorKind::ProtocolViolation,
+                frame.frame_type().into(),
+                "ACK_FREQUENCY frame received without min_ack_delay advertised",
+            ));
+        };
+        if frame.request_max_ack_delay() < min_ack_delay {
+            return Err(QuicError::new(
+                ErrorKind::ProtocolViolation,
+                frame.frame_type().into(),
+                "requested max ack delay is less than min_ack_delay",
+            ));
+        }
+
+        let mut guard = self.0.lock().unwrap();
+        if guard
+            .rcvd
+            .is_some_and(|rcvd| frame.sequence() <= rcvd.sequence())
+        {
+            return Ok(());
+        }
+        guard.rcvd = Some(*frame);
+        drop(guard);
+
+        for path in paths.iter() {
+            path.cc().on_ack_frequency_rcvd(frame);
+        }
+        Ok(())
+    }
+
+    /// Handle the IMMEDIATE_ACK frame received from the peer on the `path`.
+    ///
+    /// Like the ACK_FREQUENCY frame, the peer must not send it if the `min_ack_delay` was not
+    /// advertised, this is a connection error of type PROTOCOL_VIOLATION.
+    pub fn recv_immediate_ack(
+        &self,
+        frame: &ImmediateAckFrame,
+        min_ack_delay: Option<Duration>,
+        path: &Path,
+    ) -> Result<(), QuicError> {
+        if min_ack_delay.is_none() {
+            return Err(QuicError::new(
+                ErrorKind::ProtocolViolation,
+                frame.frame_type().into(),
+                "IMMEDIATE_ACK frame received without min_ack_delay advertised",
+            ));
+        }
+        path.cc().ack_immediately(Epoch::Data);
+        Ok(())
+    }
+
+    /// Apply the ACK_FREQUENCY frames in effect, both received and requested, to a new path.
+    pub(super) fn apply_to(&self, path: &Path) {
+        let guard = self.0.lock().unwrap();
+        let (rcvd, requested) = (guard.rcvd, guard.requested);
+        drop(guard);
+        if let Some(frame) = rcvd {
+            path.cc().on_ack_frequency_rcvd(&frame);
+        }
+        if let Some((_, max_ack_delay, _)) = requested {
+            path.cc().on_ack_frequency_sent(max_ack_delay);
+        }
+    }
+}
@@ -10,7 +10,10 @@ use qbase::{
     Epoch, GetEpoch,
     cid::{BorrowedCid, ConnectionId},
     frame::PingFrame,
-    net::tx::{ArcSendWaker, Signals},
+    net::{
+        route::Pathway,
+        tx::{ArcSendWaker, Signals},
+    },
     packet::{
         AssemblePacket, Package, PacketProperties, ProductHeader,
         header::{
@@ -28,9 +31,14 @@ use qinterface::QuicIO;
 use qrecovery::journal::{AckPackege, ArcRcvdJournal, Journal};
 
 use crate::{
-    ArcDcidCell, ArcReliableFrameDeque, CidRegistry, Components,
-    path::{AntiAmplifier, Constraints},
-    space::{Spaces, data::DataSpace, handshake::HandshakeSpace, initial::InitialSpace},
+    ArcDcidCell, CidRegistry, Components, DataJournal, SpecificComponents,
+    path::{AntiAmplifier, ArcScheduler, Constraints, PathCidFrames},
+    space::{
+        Spaces,
+        data::{DataSpace, PathDataSpace},
+        handshake::HandshakeSpace,
+        initial::{ArcRetry, InitialSpace},
+    },
     tls::ArcTlsHandshake,
     tx::PacketWriter,
 };
@@ -61,12 +69,14 @@ pub trait PacketSpace<H> {
 pub struct Burst {
     path: Arc<super::Path>,
     initial_token: Vec<u8>,
+    retry: Option<ArcRetry>,
     cid_registry: CidRegistry,
     spin: bool,
 
     spaces: Spaces,
 
     tls_handshake: ArcTlsHandshake,
+    scheduler: ArcScheduler,
 }
 
 impl super::Path {
@@ -79,10 +89,15 @@ impl super::Path {
                 }
                 TokenRegistry::Server(..) => vec![],
             },
+            retry: match &components.specific {
+                SpecificComponents::Client { retry } => Some(retry.clone()),
+                SpecificComponents::Server { .. } => None,
+            },
             cid_registry: components.cid_registry.clone(),
             spin: false, // TODO
             spaces: components.spaces.clone(),
             tls_handshake: components.tls_handshake.clone(),
+            scheduler: components.scheduler.clone(),
         }
     }
 }
@@ -98,7 +113,8 @@ pub struct PacketsAssembler<'a> {
     cc: &'a ArcCC,
     constraints: Constraints,
     cid_registry: &'a CidRegistry,
-    borrowed_dcid: Result<BorrowedCid<'a, ArcReliableFrameDeque>, Signals>,
+    borrowed_dcid: Result<BorrowedCid<'a, PathCidFrames>, Signals>,
+    retried_dcid: Option<ConnectionId>,
     initial_token: &'a [u8],
     spin: SpinBit,
 }
@@ -110,7 +126,7 @@ impl<'a> PacketsAssembler<'a> {
         anti_amplifier: &AntiAmplifier,
         cc: &'a ArcCC,
         tx_waker: ArcSendWaker,
-        initial_token: &'a [u8],
+        (retried_dcid, initial_token): (Option<ConnectionId>, &'a [u8]),
         spin: impl Into<SpinBit>,
     ) -> Result<PacketsAssembler<'a>, BurstError> {
         let send_quota = cc.send_quota()?;
@@ -128,6 +144,7 @@ impl<'a> PacketsAssembler<'a> {
             borrowed_dcid,
             cc,
             constraints,
+            retried_dcid,
             initial_token,
             spin: spin.into(),
         })
@@ -148,7 +165,8 @@ impl<'a> PacketsAssembler<'a> {
     ///
     /// gm-quic implements multi-path handshake feature, the client creates many paths and sends initial packets.
     ///
-    /// Client will only use origin_dcid to send initial and zero rtt packets.
+    /// Client will only use origin_dcid to send initial and zero rtt packets, or the source connection ID
+    /// of the Retry packet once it accepted one.
     ///
     /// The client and server must negotiate a handshake path and assign the initial dcid to this path
     /// to prevent the unique connection ID from being obtained by an invalid path, causing the connection to fail.
@@ -160,7 +178,9 @@ impl<'a> PacketsAssembler<'a> {
     /// This manifests itself during the handshake as sending the initial packet only on the first path.
     fn initial_dcid(&self) -> Result<ConnectionId, Signals> {
         match self.cid_registry.role() {
-            Role::Client => Ok(self.cid_registry.origin_dcid()),
+            Role::Client => Ok(self
+                .retried_dcid
+                .unwrap_or_else(|| self.cid_registry.origin_dcid())),
             Role::Server => self.applied_dcid(),
         }
     }
@@ -239,7 +259,7 @@ pub struct DataSources {
 }
 
 impl Components {
-    pub(super) fn packages(&self) -> DataSources {
+    pub(super) fn packages(&self, pathway: Pathway) -> DataSources {
         let initial_packages = self.crypto_streams[Epoch::Initial]
             .outgoing()
             .package(Epoch::Initial);
@@ -261,7 +281,10 @@ impl Components {
                 .outgoing()
                 .package(Epoch::Data),
             // repeat to send multi reliable frames in one packet
-            Repeat(self.reliable_frames.clone()),
+            Repeat(
+                self.scheduler
+                    .reliable_frames(pathway, self.reliable_frames.clone()),
+            ),
             // repeat to send multi stream frames in one packet
             Repeat(
                 self.data_streams
@@ -291,17 +314,36 @@ where
     ArcRcvdJournal::ack_package(space.as_ref().as_ref(), cc.need_ack(space.epoch()))
 }
 
+fn path_ack_package<'s>(space: &'s PathDataSpace, cc: &ArcCC) -> AckPackege<'s> {
+    // avoid deadlock, same as ack_package
+    let journal: &DataJournal = space.as_ref();
+    ArcRcvdJournal::path_ack_package(journal.as_ref(), space.path_id(), cc.need_ack(Epoch::Data))
+}
+
 impl Burst {
+    /// Return the packet number space of this path to send 1-RTT packets, whose received
+    /// packets are also acknowledged by the packets sent on this path.
+    fn one_rtt_space(&self) -> PathDataSpace {
+        self.spaces.data().path_space(self.path.path_id())
+    }
+
     fn assembler<'a>(&'a self) -> Result<PacketsAssembler<'a>, BurstError> {
-        PacketsAssembler::new(
+        let assembler = PacketsAssembler::new(
             &self.cid_registry,
             &self.path.dcid_cell,
             &self.path.anti_amplifier,
             &self.path.cc,
             self.path.tx_waker.clone(),
-            &self.initial_token,
+            match self.retry.as_ref().and_then(ArcRetry::get) {
+                Some((dcid, token)) => (Some(dcid), token),
+                None => (None, &self.initial_token),
+            },
             self.spin,
-        )
+        );
+        let congested =
+            matches!(&assembler, Err(BurstError::Signals(s)) if s.contains(Signals::CONGESTION));
+        self.scheduler.on_send_quota(&self.path, congested);
+        assembler
     }
 
     fn load_spaces(
@@ -372,13 +414,22 @@ where
         }
 
         if tls_fin {
-            let result = if path.validated.load(Acquire) {
+            let one_rtt_space = self.one_rtt_space();
+            let validated = path.validated.load(Acquire);
+            let scheduled = validated && self.scheduler.may_send_data(path);
+            if validated && !scheduled {
+                signals |= Signals::SCHEDULE;
+            }
+            let duplicated_frames =
+                validated.then(|| self.scheduler.duplicated_frames(path.pathway()));
+            let result = if scheduled {
                 assembler.assemble::<OneRttHeader, _, _>(
-                    data_space,
+                    &one_rtt_space,
                     &mut Packages((
-                        ack_package(data_space, &path.cc),
+                        path_ack_package(&one_rtt_space, &path.cc),
                         &path.challenge_sndbuf,
                         &path.response_sndbuf,
+                        duplicated_frames,
                         one_rtt_data_sources,
                         loaded_initial.then_some(PadToFull),
                         PadProbe,
@@ -387,11 +438,12 @@ where
                 )
             } else {
                 assembler.assemble::<OneRttHeader, _, _>(
-                    data_space,
+                    &one_rtt_space,
                     &mut Packages((
-                        ack_package(data_space, &path.cc),
+                        path_ack_package(&one_rtt_space, &path.cc),
                         &path.challenge_sndbuf,
                         &path.response_sndbuf,
+                        duplicated_frames,
                         loaded_initial.then_some(PadToFull),
                         PadProbe,
                     )),
@@ -400,7 +452,13 @@ where
             };
 
             match result {
-                Ok(bytes_sent) => buffer = buffer[bytes_sent..].as_mut(),
+                Ok(bytes_sent) => {
+                    if scheduled {
+                        self.scheduler.on_data_sent(path, bytes_sent);
+                        self.scheduler.duplicate_loaded_frames(path);
+                    }
+                    buffer = buffer[bytes_sent..].as_mut()
+                }
                 Err(s) => signals |= s,
             }
         }
@@ -452,10 +510,11 @@ impl Burst {
         for &epoch in Epoch::iter().rev() {
             let result = match epoch {
                 Epoch::Data => {
-                    let ack_package = ack_package(spaces.data().as_ref(), &path.cc);
+                    let one_rtt_space = self.one_rtt_space();
+                    let ack_package = path_ack_package(&one_rtt_space, &path.cc);
                     let ping_package = ping_package(&path.cc, epoch);
                     assembler.assemble::<OneRttHeader, _, _>(
-                        spaces.data().as_ref(),
+                        &one_rtt_space,
                         &mut Packages((ack_package, ping_package, PadToFull)),
                         buffer,
                     )
@@ -490,12 +549,11 @@ impl Burst {
     }
 
     fn load_heartbeat(&self, buffer: &mut [u8]) -> Result<usize, BurstError> {
-        let Self { spaces, path, .. } = self;
-
         let mut assembler = self.assembler()?;
+        let one_rtt_space = self.one_rtt_space();
         Ok(assembler.assemble::<OneRttHeader, _, _>(
-            spaces.data().as_ref(),
-            &path.heartbeat,
+            &one_rtt_space,
+            &self.path.heartbeat,
             buffer,
         )?)
     }
@@ -505,10 +563,11 @@ impl Burst {
         data_sources: &mut DataSources,
         buffers: &'b mut Vec<Vec<u8>>,
     ) -> Result<Vec<io::IoSlice<'b>>, BurstError> {
-        let Ok(max_segments) = self.path.interface.max_segments() else {
+        let interface = self.path.interface();
+        let Ok(max_segments) = interface.max_segments() else {
             return Err(BurstError::PathDeactived);
         };
-        let Ok(max_segment_size) = self.path.interface.max_segment_size() else {
+        let Ok(max_segment_size) = interface.max_segment_size() else {
             return Err(BurstError::PathDeactived);
         };
 
@@ -2,15 +2,31 @@ use qbase::{Epoch, net::tx::Signals};
 use qcongestion::Transport;
 use tokio::time::{self, Duration};
 
-use crate::{path::PathDeactivated, tls::ArcTlsHandshake};
+use crate::{
+    path::{ArcPathContexts, PathDeactivated},
+    tls::ArcTlsHandshake,
+};
 
 impl super::Path {
-    pub async fn drive(&self, tls_handshake: ArcTlsHandshake) -> Result<(), PathDeactivated> {
+    pub async fn drive(
+        &self,
+        tls_handshake: ArcTlsHandshake,
+        paths: ArcPathContexts,
+    ) -> Result<(), PathDeactivated> {
         let mut interval = time::interval(Duration::from_millis(10));
         loop {
             interval.tick().await;
             if matches!(tls_handshake.is_finished(), Ok(true)) {
-                self.max_idle_timer.run_out(self.cc.get_pto(Epoch::Data))?;
+                let pto = self.cc.get_pto(Epoch::Data);
+                self.max_idle_timer.run_out(pto)?;
+                // The peer may have migrated away from this path, stop sending on it
+                // as soon as another path proves to be alive.
+                if self.is_validated()
+                    && self.cc.unacked_duration().is_some_and(|d| d > pto * 3)
+                    && paths.is_superseded(&self.pathway, pto)
+                {
+                    return Err(PathDeactivated::Unresponsive);
+                }
             }
             if self.heartbeat.need_trigger() {
                 self.tx_waker.wake_by(Signals::TRANSPORT);
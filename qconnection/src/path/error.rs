@@ -1,3 +1,5 @@
+use std::io;
+
 use derive_more::From;
 use qbase::{error::Error as QuicError, net::addr::BindUri, time::IdleTimedOut};
 use qcongestion::TooManyPtos;
@@ -11,6 +13,8 @@ pub enum CreatePathFailure {
     NoInterface(BindUri),
     #[error("Connection is closed: {0}")]
     ConnectionClosed(QuicError),
+    #[error("No path id available for new path")]
+    NoPathId,
 }
 
 #[derive(Debug, From, Error)]
@@ -25,4 +29,59 @@ pub enum PathDeactivated {
     Io(#[source] std::io::Error),
     #[error("Manually removed by application")]
     App,
+    #[error("Connection migrated to another path")]
+    Migrated,
+    #[error("Path is unresponsive while other paths are still alive")]
+    Unresponsive,
+    #[error("Replaced by the path opened by client with another path id")]
+    Replaced,
+    #[error("Path is abandoned by peer with error code {0}")]
+    #[from(skip)]
+    Abandoned(u64),
+}
+
+#[derive(Debug, Error)]
+pub enum MigrateFailure {
+    #[error("Only client can initiate connection migration")]
+    NotClient,
+    #[error("Connection is closed before handshake confirmed")]
+    NotHandshaked,
+    #[error("Peer has disabled active migration")]
+    ActiveMigrationDisabled,
+    #[error("No active path to migrate from")]
+    NoActivePath,
+    #[error("No path is relayed")]
+    NoRelayedPath,
+    #[error("Failed to get the address of interface {0}: {1}")]
+    InterfaceUnavailable(BindUri, #[source] io::Error),
+    #[error("Failed to create new path: {0}")]
+    CreatePath(#[from] CreatePathFailure),
+    #[error("New path deactivated before it was validated")]
+    ValidationFailed,
+    #[error("Connection is closed: {0}")]
+    ConnectionClosed(#[from] QuicError),
+}
+
+#[derive(Debug, Error)]
+pub enum PunchFailure {
+    #[error("Only client can start hole punching")]
+    NotClient,
+    #[error("Only server can advertise addresses")]
+    NotServer,
+    #[error("Connection is closed before handshake confirmed")]
+    NotHandshaked,
+    #[error("NAT traversal is not negotiated with peer")]
+    NotNegotiated,
+    #[error("Failed to get the address of interface {0}: {1}")]
+    InterfaceUnavailable(BindUri, #[source] io::Error),
+    #[error("Interface {0} is not bound on an internet address")]
+    NotInternet(BindUri),
+    #[error("No address pair to punch")]
+    NoCandidate,
+    #[error("Failed to create new path: {0}")]
+    CreatePath(#[from] CreatePathFailure),
+    #[error("All punched paths deactivated before they were validated")]
+    AllFailed,
+    #[error("Connection is closed: {0}")]
+    ConnectionClosed(#[from] QuicError),
 }
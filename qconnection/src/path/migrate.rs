@@ -0,0 +1,297 @@
+use std::{future::Future, sync::Arc};
+
+use qbase::{
+    net::{
+        addr::{BindUri, RealAddr},
+        route::{EndpointAddr, Link, Pathway, SocketEndpointAddr},
+    },
+    param::ParameterId,
+    role::Role,
+};
+use qevent::telemetry::Instrument;
+use qinterface::{QuicIO, local::Locations};
+use tokio::task::JoinSet;
+use tracing::Instrument as _;
+
+use super::{CreatePathFailure, MigrateFailure, Path, PathDeactivated};
+use crate::Components;
+
+impl Components {
+    /// Move the connection to the interface bound on `bind_uri`.
+    ///
+    /// See [section 9](https://www.rfc-editor.org/rfc/rfc9000.html#name-connection-migration)
+    /// of [RFC9000](https://www.rfc-editor.org/rfc/rfc9000.html) for more details.
+    ///
+    /// The new path is created towards the same peer endpoint as the current path. As every path
+    /// applies for its own connection ID and owns its own congestion controller, the new path uses a
+    /// fresh DCID and starts with the initial congestion state.
+    ///
+    /// Once the new path is validated, all other paths will be removed, and their connection IDs
+    /// will be retired.
+    pub fn migrate(
+        &self,
+        bind_uri: BindUri,
+    ) -> impl Future<Output = Result<(), MigrateFailure>> + Send {
+        let components = self.clone();
+        async move {
+            if components.role() != Role::Client {
+                return Err(MigrateFailure::NotClient);
+            }
+            // An endpoint MUST NOT initiate connection migration before the handshake is confirmed
+            if !components.conn_state.handshaked().await {
+                return Err(MigrateFailure::NotHandshaked);
+            }
+
+            let disable_active_migration = components
+                .parameters
+                .lock_guard()?
+                .get_remote::<bool>(ParameterId::DisableActiveMigration)
+                .unwrap_or(false);
+            if disable_active_migration {
+                return Err(MigrateFailure::ActiveMigrationDisabled);
+            }
+
+            let current_path = components
+                .paths
+                .handshake_path()
+                .filter(|path| components.paths.get(&path.pathway).is_some())
+                .or_else(|| components.paths.iter().find(|path| path.is_validated()))
+                .ok_or(MigrateFailure::NoActivePath)?;
+
+            let interface = components
+                .interfaces
+                .get(&bind_uri)
+                .ok_or_else(|| CreatePathFailure::NoInterface(bind_uri.clone()))?;
+            let local_addr = interface
+                .real_addr()
+                .map_err(|e| MigrateFailure::InterfaceUnavailable(bind_uri.clone(), e))?;
+
+            let link = Link::new(local_addr.clone(), current_path.link.dst());
+            let pathway = Pathway::new(local_addr.into(), current_path.pathway.remote());
+            if pathway == current_path.pathway {
+                return Ok(());
+            }
+
+            tracing::info!(from = %current_path.pathway, to = %pathway, "Migrating connection");
+            let new_path = components.get_or_try_create_path(bind_uri, link, pathway, false)?;
+
+            tokio::select! {
+                validated = new_path.wait_validated() => if !validated {
+                    return Err(MigrateFailure::ValidationFailed);
+                },
+                _ = components.conn_state.terminated() => {
+                    return Err(components.parameters.lock_guard().err().map_or(
+                        MigrateFailure::ValidationFailed,
+                        MigrateFailure::ConnectionClosed,
+                    ));
+                }
+            }
+
+            components.retire_paths_except(&new_path);
+            Ok(())
+        }
+        .instrument_in_current()
+        .in_current_span()
+    }
+
+    /// Replace the paths relayed by [`SocketEndpointAddr::Relay`] endpoints with direct paths to
+    /// the peers, and return the pathways of the new paths.
+    ///
+    /// A direct path is created from the same interface towards the address of the peer. Once it
+    /// is validated, the relayed path is removed and its connection ID is retired, relayed paths
+    /// whose direct path fails to validate are kept. The peer must be reachable directly, for
+    /// example after hole punching.
+    ///
+    /// Like [`Components::migrate`], only the client migrates, and only if the server did not
+    /// disable active migration.
+    ///
+    /// [`SocketEndpointAddr::Relay`]: qbase::net::route::SocketEndpointAddr::Relay
+    pub fn replace_relayed_paths(
+        &self,
+    ) -> impl Future<Output = Result<Vec<Pathway>, MigrateFailure>> + Send {
+        let components = self.clone();
+        async move {
+            if components.role() != Role::Client {
+                return Err(MigrateFailure::NotClient);
+            }
+            if !components.conn_state.handshaked().await {
+                return Err(MigrateFailure::NotHandshaked);
+            }
+
+            let disable_active_migration = components
+                .parameters
+                .lock_guard()?
+                .get_remote::<bool>(ParameterId::DisableActiveMigration)
+                .unwrap_or(false);
+            if disable_active_migration {
+                return Err(MigrateFailure::ActiveMigrationDisabled);
+            }
+
+            // 创建路径会写入paths，遍历时持有其分片的读锁，必须先收集起来
+            let relayed_paths = components
+                .paths
+                .iter()
+                .filter_map(|path| match path.pathway.remote() {
+                    EndpointAddr::Socket(SocketEndpointAddr::Relay { peer, .. }) => {
+                        Some((path, peer))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            let mut replacing = JoinSet::new();
+            for (relayed_path, peer) in relayed_paths {
+                let link = Link::new(relayed_path.link.src(), peer.into());
+                let pathway = Pathway::new(relayed_path.pathway.local(), peer.into());
+                tracing::info!(from = %relayed_path.pathway, to = %pathway, "Replacing relayed path");
+                let new_path = components.get_or_try_create_path(
+                    relayed_path.bind_uri(),
+                    link,
+                    pathway.clone(),
+                    false,
+                )?;
+                replacing.spawn(
+                    async move { (relayed_path, new_path.wait_validated().await, pathway) }
+                        .instrument_in_current()
+                        .in_current_span(),
+                );
+            }
+            if replacing.is_empty() {
+                return Err(MigrateFailure::NoRelayedPath);
+            }
+
+            let mut replaced = vec![];
+            let join_all = async {
+                while let Some(result) = replacing.join_next().await {
+                    let Ok((relayed_path, true, pathway)) = result else {
+                        continue;
+                    };
+                    components
+                        .paths
+                        .remove(&relayed_path.pathway, &PathDeactivated::Migrated);
+                    relayed_path.dcid_cell.retire();
+                    replaced.push(pathway);
+                }
+            };
+            tokio::select! {
+                _ = join_all => {}
+                _ = components.conn_state.terminated() => {
+                    return Err(components.parameters.lock_guard().err().map_or(
+                        MigrateFailure::ValidationFailed,
+                        MigrateFailure::ConnectionClosed,
+                    ));
+                }
+            }
+
+            match replaced.is_empty() {
+                true => Err(MigrateFailure::ValidationFailed),
+                false => Ok(replaced),
+            }
+        }
+        .instrument_in_current()
+        .in_current_span()
+    }
+
+    /// Keep the paths of the connection on the current addresses of their interfaces.
+    ///
+    /// An interface is rebound when its address changes, for example `iface://v4.wlan0:0` after
+    /// a DHCP renewal, and publishes the new address to [`Locations`]. Every path on the interface
+    /// still using the old address is then replaced by a new path from the new address towards the
+    /// same peer endpoint, the old path is retired once the new one is validated. Paths on an
+    /// interface rebound at the same address are kept, they continue on the new binding.
+    ///
+    /// Like [`Components::migrate`], only the client migrates, and only if the server did not
+    /// disable active migration.
+    pub fn follow_locations(&self) -> impl Future<Output = ()> + Send {
+        let components = self.clone();
+        async move {
+            if components.role() != Role::Client {
+                return;
+            }
+
+            let mut locations = Locations::global().subscribe();
+            let follow = async {
+                if !components.conn_state.handshaked().await {
+                    return;
+                }
+                let disable_active_migration = match components.parameters.lock_guard() {
+                    Ok(parameters) => parameters
+                        .get_remote::<bool>(ParameterId::DisableActiveMigration)
+                        .unwrap_or(false),
+                    Err(_) => return,
+                };
+                if disable_active_migration {
+                    return;
+                }
+
+                let mut migrations = JoinSet::new();
+                while let Some((bind_uri, _)) = locations.recv().await {
+                    while migrations.try_join_next().is_some() {}
+                    let Some(local_addr) = components
+                        .interfaces
+                        .get(&bind_uri)
+                        .and_then(|interface| interface.real_addr().ok())
+                    else {
+                        continue;
+                    };
+                    let stale_paths = components
+                        .paths
+                        .iter()
+                        .filter(|path| path.bind_uri() == bind_uri && path.link.src() != local_addr)
+                        .collect::<Vec<_>>();
+                    for old_path in stale_paths {
+                        migrations.spawn(
+                            components
+                                .clone()
+                                .replace_path(bind_uri.clone(), local_addr.clone(), old_path)
+                                .instrument_in_current()
+                                .in_current_span(),
+                        );
+                    }
+                }
+            };
+
+            tokio::select! {
+                _ = follow => {}
+                _ = components.conn_state.terminated() => {}
+            }
+        }
+        .instrument_in_current()
+        .in_current_span()
+    }
+
+    async fn replace_path(self, bind_uri: BindUri, local_addr: RealAddr, old_path: Arc<Path>) {
+        let link = Link::new(local_addr.clone(), old_path.link.dst());
+        let pathway = Pathway::new(local_addr.into(), old_path.pathway.remote());
+        tracing::info!(from = %old_path.pathway, to = %pathway, "Interface rebound, migrating path");
+
+        let new_path = match self.get_or_try_create_path(bind_uri, link, pathway.clone(), false) {
+            Ok(new_path) => new_path,
+            Err(e) => {
+                tracing::warn!(%pathway, "Failed to create path on the new address: {e}");
+                return;
+            }
+        };
+        if !new_path.wait_validated().await {
+            tracing::warn!(%pathway, "Path on the new address failed to validate");
+            return;
+        }
+
+        self.paths
+            .remove(&old_path.pathway, &PathDeactivated::Migrated);
+        old_path.dcid_cell.retire();
+    }
+
+    fn retire_paths_except(&self, new_path: &Arc<Path>) {
+        let old_paths = self
+            .paths
+            .iter()
+            .filter(|path| !Arc::ptr_eq(path, new_path))
+            .collect::<Vec<_>>();
+        for old_path in old_paths {
+            self.paths
+                .remove(&old_path.pathway, &PathDeactivated::Migrated);
+            old_path.dcid_cell.retire();
+        }
+    }
+}
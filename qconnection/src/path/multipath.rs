@@ -0,0 +1,427 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use qbase::{
+    cid::ConnectionId,
+    error::{Error, ErrorKind, QuicError},
+    frame::{
+        FrameType, GetFrameType, MaxPathIdFrame, NewConnectionIdFrame, PathAbandonFrame,
+        PathCidsBlockedFrame, PathNewConnectionIdFrame, PathRetireConnectionIdFrame,
+        PathsBlockedFrame, ReceiveFrame, ReliableFrame, RetireConnectionIdFrame, SendFrame,
+    },
+    role::Role,
+    varint::VarInt,
+};
+
+use super::CreatePathFailure;
+use crate::{
+    ArcDcidCell, ArcLocalCids, ArcReliableFrameDeque, ArcRemoteCids, CidRegistry, RouterRegistry,
+    space::data::DataSpace,
+};
+
+/// Sends the frames issuing and retiring the connection IDs of a path of multipath.
+///
+/// The path 0 uses the NEW_CONNECTION_ID and RETIRE_CONNECTION_ID frames, the other paths use
+/// the PATH_NEW_CONNECTION_ID and PATH_RETIRE_CONNECTION_ID frames.
+#[derive(Debug, Clone)]
+pub struct PathCidFrames {
+    path_id: u32,
+    frames: ArcReliableFrameDeque,
+}
+
+impl PathCidFrames {
+    pub fn new(path_id: u32, frames: ArcReliableFrameDeque) -> Self {
+        Self { path_id, frames }
+    }
+}
+
+impl SendFrame<NewConnectionIdFrame> for PathCidFrames {
+    fn send_frame<I: IntoIterator<Item = NewConnectionIdFrame>>(&self, iter: I) {
+        match self.path_id {
+            0 => self.frames.send_frame(iter),
+            path_id => self.frames.send_frame(iter.into_iter().map(|frame| {
+                ReliableFrame::PathNewConnectionId(PathNewConnectionIdFrame::new(
+                    VarInt::from_u32(path_id),
+                    frame,
+                ))
+            })),
+        }
+    }
+}
+
+impl SendFrame<RetireConnectionIdFrame> for PathCidFrames {
+    fn send_frame<I: IntoIterator<Item = RetireConnectionIdFrame>>(&self, iter: I) {
+        match self.path_id {
+            0 => self.frames.send_frame(iter),
+            path_id => self.frames.send_frame(iter.into_iter().map(|frame| {
+                ReliableFrame::PathRetireConnectionId(PathRetireConnectionIdFrame::new(
+                    VarInt::from_u32(path_id),
+                    frame,
+                ))
+            })),
+        }
+    }
+}
+
+// 路径n(n>0)的连接ID，序号各自从0开始
+struct PathCids {
+    local: ArcLocalCids,
+    remote: ArcRemoteCids,
+}
+
+#[derive(Default)]
+struct Multipath {
+    enabled: bool,
+    // 本端允许的最大路径ID，即通告给对端的initial_max_path_id或MAX_PATH_ID
+    local_max: u32,
+    // 对端允许的最大路径ID
+    remote_max: u32,
+    // 对端的active_connection_id_limit参数
+    remote_cid_limit: u64,
+    cids: BTreeMap<u32, PathCids>,
+    // 已经被打开过的路径ID，包括本端和对端打开的，路径ID不能被重复使用
+    opened: BTreeSet<u32>,
+    abandoned: BTreeSet<u32>,
+}
+
+struct MultipathState {
+    role: Role,
+    state: Mutex<Multipath>,
+    cid_registry: CidRegistry,
+    router_registry: RouterRegistry,
+    reliable_frames: ArcReliableFrameDeque,
+    data_space: Arc<DataSpace>,
+    // 本端的active_connection_id_limit参数
+    local_cid_limit: u64,
+}
+
+/// The connection level state of the multipath extension, see
+/// [draft-ietf-quic-multipath](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/).
+///
+/// Each path has a path identifier, which has its own packet number space and connection IDs.
+/// The path 0 uses the connection IDs of the connection, the connection IDs of the other paths
+/// are issued with PATH_NEW_CONNECTION_ID frames, once both endpoints allowed the path identifier.
+///
+/// A path identifier is opened by the first path using it, and abandoned with a PATH_ABANDON frame
+/// once no path uses it anymore, an abandoned path identifier is never used again. A new path
+/// identifier is allowed to the peer with a MAX_PATH_ID frame for each abandoned one.
+#[derive(Clone)]
+pub struct ArcMultipath(Arc<MultipathState>);
+
+impl ArcMultipath {
+    pub fn new(
+        role: Role,
+        cid_registry: CidRegistry,
+        router_registry: RouterRegistry,
+        reliable_frames: ArcReliableFrameDeque,
+        data_space: Arc<DataSpace>,
+        local_cid_limit: u64,
+    ) -> Self {
+        Self(Arc::new(MultipathState {
+            role,
+            state: Mutex::default(),
+            cid_registry,
+            router_registry,
+            reliable_frames,
+            data_space,
+            local_cid_limit,
+        }))
+    }
+
+    /// Enable multipath after both endpoints sent the initial_max_path_id parameter, and issue
+    /// the connection IDs of the path identifiers allowed by both endpoints.
+    ///
+    /// `remote_cid_limit` and `max_ack_delay` are the peer's active_connection_id_limit and
+    /// max_ack_delay parameters.
+    pub fn enable(
+        &self,
+        local_max: u32,
+        remote_max: u32,
+        remote_cid_limit: u64,
+        max_ack_delay: Duration,
+    ) -> Result<(), Error> {
+        self.0.data_space.enable_multipath(max_ack_delay);
+        let mut state = self.0.state.lock().unwrap();
+        state.enabled = true;
+        state.local_max = local_max;
+        state.remote_max = remote_max;
+        state.remote_cid_limit = remote_cid_limit;
+        for path_id in 1..=local_max.min(remote_max) {
+            self.0.path_cids(&mut state, path_id)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.state.lock().unwrap().enabled
+    }
+
+    /// Return the path identifier of the local connection ID `dcid`, which the packet
+    /// received is sent to.
+    ///
+    /// The connection IDs of the connection and the unknown ones belong to the path 0.
+    pub fn path_id_of(&self, dcid: &ConnectionId) -> u32 {
+        let state = self.0.state.lock().unwrap();
+        state
+            .cids
+            .iter()
+            .find(|(_, cids)| cids.local.sequence_of(dcid).is_some())
+            .map_or(0, |(path_id, _)| *path_id)
+    }
+
+    /// Open an unused path identifier for the path created by this endpoint.
+    ///
+    /// The client opens the smallest path identifier available and the server opens the largest
+    /// one, to avoid both endpoints opening the same path identifier at the same time.
+    /// Without multipath, all paths use the path 0.
+    pub fn open_path_id(&self) -> Result<u32, CreatePathFailure> {
+        let mut state = self.0.state.lock().unwrap();
+        if !state.enabled {
+            return Ok(0);
+        }
+        let mut available = (1..=state.local_max.min(state.remote_max)).filter(|path_id| {
+            !state.opened.contains(path_id) && !state.abandoned.contains(path_id)
+        });
+        let path_id = match self.0.role {
+            Role::Client => available.next(),
+            Role::Server => available.next_back(),
+        }
+        .ok_or(CreatePathFailure::NoPathId)?;
+        state.opened.insert(path_id);
+        Ok(path_id)
+    }
+
+    /// Accept the path identifier `path_id` opened by the peer for a new path.
+    pub fn accept_path_id(&self, path_id: u32) -> Result<(), CreatePathFailure> {
+        let mut state = self.0.state.lock().unwrap();
+        if path_id == 0 {
+            return Ok(());
+        }
+        if state.abandoned.contains(&path_id) {
+            return Err(CreatePathFailure::NoPathId);
+        }
+        state.opened.insert(path_id);
+        Ok(())
+    }
+
+    /// Apply for a connection ID of the path identifier `path_id` for a new path.
+    ///
+    /// Return None if the path identifier is not allowed by both endpoints or has been abandoned.
+    pub fn apply_dcid(&self, path_id: u32) -> Option<ArcDcidCell> {
+        if path_id == 0 {
+            return Some(self.0.cid_registry.remote.apply_dcid());
+        }
+        let mut state = self.0.state.lock().unwrap();
+        if path_id > state.local_max.min(state.remote_max) {
+            return None;
+        }
+        let cids = self.0.path_cids(&mut state, path_id).ok()??;
+        Some(cids.remote.apply_dcid())
+    }
+
+    /// Check the path identifier in the multipath frame received, return it if the frame should
+    /// be processed, or None if the path identifier has been abandoned.
+    ///
+    /// Receiving a multipath frame without multipath enabled, or whose path identifier exceeds
+    /// the maximum path identifier allowed, is a connection error of type PROTOCOL_VIOLATION.
+    pub fn check_path_id(
+        &self,
+        frame_type: FrameType,
+        path_id: VarInt,
+    ) -> Result<Option<u32>, QuicError> {
+        let state = self.0.state.lock().unwrap();
+        check_path_id(&state, frame_type, path_id)
+            .map(|path_id| (!state.abandoned.contains(&path_id)).then_some(path_id))
+    }
+
+    /// Abandon the path identifier `path_id` with a PATH_ABANDON frame, if it is not abandoned yet.
+    ///
+    /// The connection IDs and the packet number space of the path identifier are discarded, and a
+    /// new path identifier is allowed to the peer with a MAX_PATH_ID frame, unless the maximum path
+    /// identifier is already 2^32-1. The path 0 is never abandoned, since it uses the connection IDs
+    /// of the connection.
+    pub fn abandon(&self, path_id: u32) {
+        let mut state = self.0.state.lock().unwrap();
+        if path_id == 0 || !state.enabled || !state.abandoned.insert(path_id) {
+            return;
+        }
+        if let Some(cids) = state.cids.remove(&path_id) {
+            cids.local.clear();
+        }
+        self.0.data_space.remove_path_journal(path_id);
+        tracing::debug!(path_id, "Abandon path id");
+
+        // 使用NO_ERROR，路径被放弃的原因已在本地记录
+        let path_abandon = ReliableFrame::PathAbandon(PathAbandonFrame::new(
+            VarInt::from_u32(path_id),
+            VarInt::from_u32(0),
+        ));
+        // 路径ID已达上限，不再提高MAX_PATH_ID
+        let Some(local_max) = state.local_max.checked_add(1) else {
+            self.0.reliable_frames.send_frame([path_abandon]);
+            return;
+        };
+        state.local_max = local_max;
+        let max_path_id = VarInt::from_u32(local_max);
+        self.0.reliable_frames.send_frame([
+            path_abandon,
+            ReliableFrame::MaxPathId(MaxPathIdFrame::new(max_path_id)),
+        ]);
+        if state.local_max <= state.remote_max {
+            let path_id = state.local_max;
+            // 连接ID数量超限时，对端会关闭连接
+            _ = self.0.path_cids(&mut state, path_id);
+        }
+    }
+}
+
+fn check_path_id(
+    state: &Multipath,
+    frame_type: FrameType,
+    path_id: VarInt,
+) -> Result<u32, QuicError> {
+    if !state.enabled {
+        return Err(QuicError::new(
+            ErrorKind::ProtocolViolation,
+            frame_type.into(),
+            "multipath is not negotiated",
+        ));
+    }
+    match u32::try_from(path_id.into_inner()) {
+        Ok(path_id) if path_id <= state.local_max => Ok(path_id),
+        _ => Err(QuicError::new(
+            ErrorKind::ProtocolViolation,
+            frame_type.into(),
+            format!(
+                "path id {path_id} exceeds the max path id {}",
+                state.local_max
+            ),
+        )),
+    }
+}
+
+impl MultipathState {
+    // 获取路径的连接ID，首次获取时向对端签发连接ID
+    fn path_cids<'s>(
+        &self,
+        state: &'s mut Multipath,
+        path_id: u32,
+    ) -> Result<Option<&'s PathCids>, Error> {
+        if state.abandoned.contains(&path_id) {
+            return Ok(None);
+        }
+        if !state.cids.contains_key(&path_id) {
+            let frames = PathCidFrames::new(path_id, self.reliable_frames.clone());
+            let local =
+                ArcLocalCids::without_scid(self.router_registry.with_issued_cids(frames.clone()));
+            local.set_limit(state.remote_cid_limit)?;
+            let remote = ArcRemoteCids::new(self.local_cid_limit, frames);
+            state.cids.insert(path_id, PathCids { local, remote });
+        }
+        Ok(state.cids.get(&path_id))
+    }
+}
+
+impl ReceiveFrame<PathNewConnectionIdFrame> for ArcMultipath {
+    type Output = ();
+
+    fn recv_frame(&self, frame: &PathNewConnectionIdFrame) -> Result<Self::Output, Error> {
+        let mut state = self.0.state.lock().unwrap();
+        match check_path_id(&state, frame.frame_type(), frame.path_id())? {
+            0 => {
+                drop(state);
+                self.0
+                    .cid_registry
+                    .remote
+                    .recv_frame(frame.new_cid_frame())?;
+            }
+            path_id => {
+                if let Some(cids) = self.0.path_cids(&mut state, path_id)? {
+                    cids.remote.recv_frame(frame.new_cid_frame())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ReceiveFrame<PathRetireConnectionIdFrame> for ArcMultipath {
+    type Output = ();
+
+    fn recv_frame(&self, frame: &PathRetireConnectionIdFrame) -> Result<Self::Output, Error> {
+        let state = self.0.state.lock().unwrap();
+        match check_path_id(&state, frame.frame_type(), frame.path_id())? {
+            0 => {
+                drop(state);
+                self.0
+                    .cid_registry
+                    .local
+                    .recv_frame(frame.retire_cid_frame())
+            }
+            path_id => match state.cids.get(&path_id) {
+                Some(cids) => cids.local.recv_frame(frame.retire_cid_frame()),
+                // 路径ID已被放弃，或从未签发过连接ID
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+impl ReceiveFrame<MaxPathIdFrame> for ArcMultipath {
+    type Output = ();
+
+    fn recv_frame(&self, frame: &MaxPathIdFrame) -> Result<Self::Output, Error> {
+        let mut state = self.0.state.lock().unwrap();
+        if !state.enabled {
+            return Err(QuicError::new(
+                ErrorKind::ProtocolViolation,
+                frame.frame_type().into(),
+                "multipath is not negotiated",
+            )
+            .into());
+        }
+        // 较小的MAX_PATH_ID可能是乱序到达的，忽略即可
+        let max_path_id = u32::try_from(frame.max_path_id().into_inner()).unwrap_or(u32::MAX);
+        if max_path_id <= state.remote_max {
+            return Ok(());
+        }
+        let allowed = state.local_max.min(state.remote_max);
+        state.remote_max = max_path_id;
+        for path_id in allowed + 1..=state.local_max.min(max_path_id) {
+            self.0.path_cids(&mut state, path_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl ReceiveFrame<PathsBlockedFrame> for ArcMultipath {
+    type Output = ();
+
+    fn recv_frame(&self, frame: &PathsBlockedFrame) -> Result<Self::Output, Error> {
+        let state = self.0.state.lock().unwrap();
+        if !state.enabled {
+            return Err(QuicError::new(
+                ErrorKind::ProtocolViolation,
+                frame.frame_type().into(),
+                "multipath is not negotiated",
+            )
+            .into());
+        }
+        // 每放弃一个路径ID都会允许一个新的路径ID，无需额外处理
+        Ok(())
+    }
+}
+
+impl ReceiveFrame<PathCidsBlockedFrame> for ArcMultipath {
+    type Output = ();
+
+    fn recv_frame(&self, frame: &PathCidsBlockedFrame) -> Result<Self::Output, Error> {
+        let state = self.0.state.lock().unwrap();
+        check_path_id(&state, frame.frame_type(), frame.path_id())?;
+        // 每退役一个连接ID都会签发一个新的连接ID，无需额外处理
+        Ok(())
+    }
+}
@@ -10,14 +10,17 @@ use qbase::{
     Epoch,
     cid::ConnectionId,
     error::{ErrorKind, QuicError},
-    net::{route::Pathway, tx::ArcSendWakers},
+    net::{
+        route::{Link, Pathway},
+        tx::ArcSendWakers,
+    },
 };
 use qcongestion::Transport;
 use qevent::telemetry::Instrument;
 use tokio_util::task::AbortOnDropHandle;
 use tracing::Instrument as _;
 
-use super::Path;
+use super::{ArcMultipath, Path};
 use crate::{
     ArcRemoteCids,
     events::{ArcEventBroker, EmitEvent, Event},
@@ -31,20 +34,31 @@ pub struct PathContext {
     _task: AbortOnDropHandle<()>,
 }
 
+impl Drop for PathContext {
+    fn drop(&mut self) {
+        self.path.on_deactivated();
+    }
+}
+
 #[derive(Clone)]
 pub struct ArcPathContexts {
     paths: Arc<DashMap<Pathway, PathContext>>,
+    // 路径标识不能由链路推出的路径（如经代理或中继的路径）：链路 -> 路径标识，收到的包按链路找到它们
+    indirect: Arc<DashMap<Link, Pathway>>,
     tx_wakers: ArcSendWakers,
     broker: ArcEventBroker,
+    multipath: ArcMultipath,
     initial_path: Arc<Mutex<Option<Weak<Path>>>>,
 }
 
 impl ArcPathContexts {
-    pub fn new(tx_wakers: ArcSendWakers, broker: ArcEventBroker) -> Self {
+    pub fn new(tx_wakers: ArcSendWakers, broker: ArcEventBroker, multipath: ArcMultipath) -> Self {
         Self {
             paths: Default::default(),
+            indirect: Default::default(),
             tx_wakers,
             broker,
+            multipath,
             initial_path: Arc::default(),
         }
     }
@@ -81,11 +95,14 @@ impl ArcPathContexts {
     where
         T: Future<Output = Result<(), PathDeactivated>> + Send + 'static,
     {
-        match self.paths.entry(pathway) {
+        match self.paths.entry(pathway.clone()) {
             dashmap::Entry::Occupied(occupied_entry) => Ok(occupied_entry.get().path.clone()),
             dashmap::Entry::Vacant(vacant_entry) => {
                 let (path, task) = try_create()?;
-                self.tx_wakers.insert(pathway, &path.tx_waker);
+                self.tx_wakers.insert(pathway.clone(), &path.tx_waker);
+                if Pathway::from(path.link()) != pathway {
+                    self.indirect.insert(path.link(), pathway.clone());
+                }
                 let paths = self.clone();
                 let task = AbortOnDropHandle::new(tokio::spawn(
                     async move {
@@ -106,10 +123,29 @@ impl ArcPathContexts {
         self.paths.get(pathway).map(|p| p.path.clone())
     }
 
+    /// Returns the path on the `link` whose pathway is not the one of the link, such as a path to
+    /// a [`SocketEndpointAddr::Agent`] or [`SocketEndpointAddr::Relay`] endpoint, the packets
+    /// received on the link belong to it.
+    ///
+    /// [`SocketEndpointAddr::Agent`]: qbase::net::route::SocketEndpointAddr::Agent
+    /// [`SocketEndpointAddr::Relay`]: qbase::net::route::SocketEndpointAddr::Relay
+    pub fn get_indirect(&self, link: &Link) -> Option<Arc<Path>> {
+        let pathway = self.indirect.get(link)?.clone();
+        self.get(&pathway)
+    }
+
     pub fn remove(&self, pathway: &Pathway, reason: &PathDeactivated) {
-        if self.paths.remove(pathway).is_some() {
+        if let Some((_, removed)) = self.paths.remove(pathway) {
             self.tx_wakers.remove(pathway);
+            self.indirect
+                .remove_if(&removed.link(), |_, indirect| indirect == pathway);
             tracing::warn!(%pathway, %reason, "Path deactivated");
+            // 没有路径再使用该路径ID时，放弃该路径ID
+            let path_id = removed.path_id();
+            drop(removed);
+            if !self.paths.iter().any(|p| p.path_id() == path_id) {
+                self.multipath.abandon(path_id);
+            }
             if self.is_empty() {
                 let error = QuicError::with_default_fty(
                     ErrorKind::NoViablePath,
@@ -124,6 +160,22 @@ impl ArcPathContexts {
         self.paths.is_empty()
     }
 
+    /// Returns whether the peer may have migrated from the path on `pathway` to another
+    /// validated path, which has its packets acknowledged lately, within `within`, and no
+    /// ack-eliciting packet waiting for an ack longer than that.
+    ///
+    /// An idle path proves nothing, it never supersedes a path. With multipath, the peer abandons
+    /// the paths it leaves explicitly, and no path is considered superseded.
+    pub fn is_superseded(&self, pathway: &Pathway, within: Duration) -> bool {
+        !self.multipath.is_enabled()
+            && self.paths.iter().any(|p| {
+                p.key() != pathway
+                    && p.is_validated()
+                    && p.cc().since_last_acked().is_some_and(|d| d < within)
+                    && p.cc().unacked_duration().map_or(true, |d| d < within)
+            })
+    }
+
     pub fn max_pto_duration(&self) -> Option<Duration> {
         self.paths.iter().map(|p| p.cc().get_pto(Epoch::Data)).max()
     }
@@ -141,5 +193,6 @@ impl ArcPathContexts {
 
     pub fn clear(&self) {
         self.paths.clear();
+        self.indirect.clear();
     }
 }
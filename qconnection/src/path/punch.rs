@@ -0,0 +1,500 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use qbase::{
+    error::{Error, ErrorKind, QuicError},
+    frame::{AddAddressFrame, GetFrameType, PunchMeNowFrame, SendFrame},
+    net::{
+        addr::{BindUri, RealAddr},
+        route::{Link, Pathway},
+    },
+    param::ParameterId,
+    role::Role,
+    varint::VarInt,
+};
+use qevent::telemetry::Instrument;
+use qinterface::QuicIO;
+use tokio::{sync::Notify, task::JoinSet};
+use tracing::Instrument as _;
+
+use super::{CreatePathFailure, PunchFailure};
+use crate::Components;
+
+/// 客户端保留的对端通告地址数上限，超过时丢弃最早通告的地址
+const MAX_REMOTE_CANDIDATES: usize = 16;
+/// 服务端一轮打洞中创建的路径数上限，防止客户端借服务端向任意地址发送探测包
+const MAX_PUNCHING_PATHS: usize = 8;
+/// 服务端接受新一轮打洞的最小间隔
+const MIN_ROUND_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+struct PunchRound {
+    round: u64,
+    started: Instant,
+    pathways: Vec<Pathway>,
+}
+
+#[derive(Debug, Default)]
+struct Candidates {
+    // 本端通告过的地址：序号 -> (接口, 地址)
+    local: BTreeMap<u64, (BindUri, SocketAddr)>,
+    next_sequence: u64,
+    // 对端通告的地址：序号 -> 地址
+    remote: BTreeMap<u64, SocketAddr>,
+    next_round: u64,
+    // 服务端最近接受的一轮打洞
+    punching: Option<PunchRound>,
+}
+
+#[derive(Debug, Default)]
+struct Puncher {
+    candidates: Mutex<Candidates>,
+    remote_added: Notify,
+}
+
+/// The connection level state of the NAT traversal extension, see
+/// [draft-seemann-quic-nat-traversal](https://datatracker.ietf.org/doc/draft-seemann-quic-nat-traversal/).
+///
+/// The server advertises the addresses it can be reached at in ADD_ADDRESS frames, the client
+/// pairs them with its own addresses and asks the server to punch with PUNCH_ME_NOW frames, then
+/// both endpoints probe the pair at the same time, so that the NATs on both sides see outgoing
+/// packets before the incoming ones.
+///
+/// The extension is used only if both endpoints advertise the [`ParameterId::NatTraversal`]
+/// transport parameter.
+#[derive(Debug, Default, Clone)]
+pub struct ArcPuncher(Arc<Puncher>);
+
+impl ArcPuncher {
+    fn add_local(&self, bind_uri: BindUri, addr: SocketAddr) -> Option<AddAddressFrame> {
+        let mut candidates = self.0.candidates.lock().unwrap();
+        if candidates
+            .local
+            .values()
+            .any(|(uri, local)| *uri == bind_uri && *local == addr)
+        {
+            return None;
+        }
+        let sequence = candidates.next_sequence;
+        candidates.next_sequence += 1;
+        candidates.local.insert(sequence, (bind_uri, addr));
+        Some(AddAddressFrame::new(
+            VarInt::from_u64(sequence).expect("sequence never exceeds VarInt::MAX"),
+            addr,
+        ))
+    }
+
+    fn local(&self, sequence: VarInt) -> Option<(BindUri, SocketAddr)> {
+        let candidates = self.0.candidates.lock().unwrap();
+        candidates.local.get(&sequence.into_inner()).cloned()
+    }
+
+    fn add_remote(&self, frame: &AddAddressFrame) {
+        let mut candidates = self.0.candidates.lock().unwrap();
+        let sequence = frame.sequence().into_inner();
+        if !candidates.remote.contains_key(&sequence)
+            && candidates.remote.len() >= MAX_REMOTE_CANDIDATES
+        {
+            // 保留最新通告的地址
+            match candidates.remote.first_key_value() {
+                Some((&oldest, _)) if oldest < sequence => _ = candidates.remote.pop_first(),
+                _ => return,
+            }
+        }
+        candidates.remote.insert(sequence, frame.addr());
+        drop(candidates);
+        self.0.remote_added.notify_waiters();
+    }
+
+    async fn remote(&self) -> Vec<(VarInt, SocketAddr)> {
+        loop {
+            let remote_added = self.0.remote_added.notified();
+            let remote = {
+                let candidates = self.0.candidates.lock().unwrap();
+                candidates
+                    .remote
+                    .iter()
+                    .map(|(sequence, addr)| (VarInt::from_u64(*sequence).unwrap(), *addr))
+                    .collect::<Vec<_>>()
+            };
+            if !remote.is_empty() {
+                return remote;
+            }
+            remote_added.await;
+        }
+    }
+
+    /// Admit the `pathways` the client asks to punch in the `round`, return the ones to create.
+    ///
+    /// At most [`MAX_PUNCHING_PATHS`] pathways are punched in a round. A new round is ignored if it
+    /// comes within [`MIN_ROUND_INTERVAL`] of the previous one, or while any pathway of the previous
+    /// round is still `punching`.
+    fn admit_round(
+        &self,
+        round: VarInt,
+        pathways: impl IntoIterator<Item = Pathway>,
+        punching: impl Fn(&Pathway) -> bool,
+    ) -> Vec<Pathway> {
+        let mut candidates = self.0.candidates.lock().unwrap();
+        let round = round.into_inner();
+        let current = match &mut candidates.punching {
+            Some(current) if current.round == round => current,
+            Some(previous)
+                if previous.round > round
+                    || previous.started.elapsed() < MIN_ROUND_INTERVAL
+                    || previous.pathways.iter().any(&punching) =>
+            {
+                return vec![];
+            }
+            punch_round => punch_round.insert(PunchRound {
+                round,
+                started: Instant::now(),
+                pathways: vec![],
+            }),
+        };
+        let room = MAX_PUNCHING_PATHS.saturating_sub(current.pathways.len());
+        let admitted = pathways
+            .into_iter()
+            .filter(|pathway| !current.pathways.contains(pathway))
+            .take(room)
+            .collect::<Vec<_>>();
+        current.pathways.extend(admitted.iter().cloned());
+        admitted
+    }
+
+    fn next_round(&self) -> VarInt {
+        let mut candidates = self.0.candidates.lock().unwrap();
+        let round = candidates.next_round;
+        candidates.next_round += 1;
+        VarInt::from_u64(round).expect("round never exceeds VarInt::MAX")
+    }
+}
+
+impl Components {
+    fn is_nat_traversal_negotiated(&self) -> Result<bool, Error> {
+        let parameters = self.parameters.lock_guard()?;
+        Ok(parameters
+            .get_local::<bool>(ParameterId::NatTraversal)
+            .unwrap_or(false)
+            && parameters
+                .get_remote::<bool>(ParameterId::NatTraversal)
+                .unwrap_or(false))
+    }
+
+    /// Advertise the address of the interface bound on `bind_uri` to the client as a candidate
+    /// for hole punching, in an ADD_ADDRESS frame.
+    ///
+    /// The interface must have been bound in [`QuicInterfaces`], and be kept alive as long as
+    /// the client may punch to it. Advertising the same address again is a no-op.
+    ///
+    /// Only the server advertises addresses, and only after the handshake is confirmed.
+    ///
+    /// [`QuicInterfaces`]: qinterface::iface::QuicInterfaces
+    pub fn advertise_address(
+        &self,
+        bind_uri: BindUri,
+    ) -> impl Future<Output = Result<(), PunchFailure>> + Send {
+        let components = self.clone();
+        async move {
+            if components.role() != Role::Server {
+                return Err(PunchFailure::NotServer);
+            }
+            if !components.conn_state.handshaked().await {
+                return Err(PunchFailure::NotHandshaked);
+            }
+            if !components.is_nat_traversal_negotiated()? {
+                return Err(PunchFailure::NotNegotiated);
+            }
+
+            let interface = components
+                .interfaces
+                .get(&bind_uri)
+                .ok_or_else(|| CreatePathFailure::NoInterface(bind_uri.clone()))?;
+            let addr = match interface.real_addr() {
+                Ok(RealAddr::Internet(addr)) => addr,
+                Ok(_) => return Err(PunchFailure::NotInternet(bind_uri)),
+                Err(e) => return Err(PunchFailure::InterfaceUnavailable(bind_uri, e)),
+            };
+
+            if let Some(frame) = components.puncher.add_local(bind_uri, addr) {
+                tracing::debug!(sequence = %frame.sequence(), %addr, "Advertise address");
+                components.reliable_frames.send_frame([frame]);
+            }
+            Ok(())
+        }
+        .instrument_in_current()
+        .in_current_span()
+    }
+
+    /// Punch holes from the interfaces of the current paths to the addresses the server
+    /// advertised, and return the pathways validated.
+    ///
+    /// This waits until the server advertised at least one address, every pair of addresses of
+    /// the same family without a path yet is punched in one round: the client sends a
+    /// PUNCH_ME_NOW frame for the pair and creates the path at once, the server probes the client's
+    /// address, and the reflexive addresses it observes the client at, from the paired address on
+    /// receipt. The punched paths then join the connection as any other validated path.
+    ///
+    /// Only the client starts hole punching, and only after the handshake is confirmed.
+    pub fn punch(&self) -> impl Future<Output = Result<Vec<Pathway>, PunchFailure>> + Send {
+        let components = self.clone();
+        async move {
+            if components.role() != Role::Client {
+                return Err(PunchFailure::NotClient);
+            }
+            if !components.conn_state.handshaked().await {
+                return Err(PunchFailure::NotHandshaked);
+            }
+            if !components.is_nat_traversal_negotiated()? {
+                return Err(PunchFailure::NotNegotiated);
+            }
+
+            let remote = tokio::select! {
+                remote = components.puncher.remote() => remote,
+                _ = components.conn_state.terminated() => {
+                    return Err(components.parameters.lock_guard().err().map_or(
+                        PunchFailure::NoCandidate,
+                        PunchFailure::ConnectionClosed,
+                    ));
+                }
+            };
+            let mut local: Vec<(BindUri, SocketAddr)> = vec![];
+            for path in components.paths.iter() {
+                if let RealAddr::Internet(addr) = path.link.src() {
+                    let candidate = (path.bind_uri(), addr);
+                    if !local.contains(&candidate) {
+                        local.push(candidate);
+                    }
+                }
+            }
+
+            let round = components.puncher.next_round();
+            let mut punching = JoinSet::new();
+            for (bind_uri, local_addr) in local {
+                for &(sequence, remote_addr) in &remote {
+                    if local_addr.is_ipv4() != remote_addr.is_ipv4() {
+                        continue;
+                    }
+                    let pathway = Pathway::new(local_addr.into(), remote_addr.into());
+                    if components.paths.get(&pathway).is_some() {
+                        continue;
+                    }
+
+                    tracing::info!(%round, %pathway, "Punching");
+                    components
+                        .reliable_frames
+                        .send_frame([PunchMeNowFrame::new(round, sequence, local_addr)]);
+                    let link = Link::new(local_addr.into(), remote_addr.into());
+                    let path = components.get_or_try_create_path(
+                        bind_uri.clone(),
+                        link,
+                        pathway.clone(),
+                        false,
+                    )?;
+                    punching.spawn(
+                        async move { path.wait_validated().await.then_some(pathway) }
+                            .instrument_in_current()
+                            .in_current_span(),
+                    );
+                }
+            }
+            if punching.is_empty() {
+                return Err(PunchFailure::NoCandidate);
+            }
+
+            let mut punched = vec![];
+            let join_all = async {
+                while let Some(result) = punching.join_next().await {
+                    if let Ok(Some(pathway)) = result {
+                        punched.push(pathway);
+                    }
+                }
+            };
+            tokio::select! {
+                _ = join_all => {}
+                _ = components.conn_state.terminated() => {
+                    return Err(components.parameters.lock_guard().err().map_or(
+                        PunchFailure::AllFailed,
+                        PunchFailure::ConnectionClosed,
+                    ));
+                }
+            }
+
+            match punched.is_empty() {
+                true => Err(PunchFailure::AllFailed),
+                false => Ok(punched),
+            }
+        }
+        .instrument_in_current()
+        .in_current_span()
+    }
+
+    /// Handle the ADD_ADDRESS frame received from the server.
+    ///
+    /// Only the client receives ADD_ADDRESS frames, and only if it advertised the
+    /// [`ParameterId::NatTraversal`], otherwise it is a connection error of type
+    /// PROTOCOL_VIOLATION.
+    pub(crate) fn recv_add_address_frame(
+        &self,
+        frame: &AddAddressFrame,
+        nat_traversal: bool,
+    ) -> Result<(), QuicError> {
+        if !nat_traversal || self.role() != Role::Client {
+            return Err(QuicError::new(
+                ErrorKind::ProtocolViolation,
+                frame.frame_type().into(),
+                "unexpected ADD_ADDRESS frame",
+            ));
+        }
+        tracing::debug!(sequence = %frame.sequence(), addr = %frame.addr(), "Peer advertised address");
+        self.puncher.add_remote(frame);
+        Ok(())
+    }
+
+    /// Handle the PUNCH_ME_NOW frame received from the client, by creating the paths from the
+    /// paired address to the client's address, and to the reflexive addresses the client is
+    /// observed at on the current paths, which probe the client at once.
+    ///
+    /// The client only knows the addresses before its NAT, the reflexive addresses are the ones
+    /// reachable once the client's NAT has seen the client punching towards the paired address.
+    ///
+    /// The rounds are limited so that the client can not make the server probe arbitrary
+    /// addresses: see [`ArcPuncher::admit_round`].
+    ///
+    /// Only the server receives PUNCH_ME_NOW frames, and only if it advertised the
+    /// [`ParameterId::NatTraversal`], otherwise it is a connection error of type
+    /// PROTOCOL_VIOLATION.
+    pub(crate) fn recv_punch_me_now_frame(
+        &self,
+        frame: &PunchMeNowFrame,
+        nat_traversal: bool,
+    ) -> Result<(), QuicError> {
+        if !nat_traversal || self.role() != Role::Server {
+            return Err(QuicError::new(
+                ErrorKind::ProtocolViolation,
+                frame.frame_type().into(),
+                "unexpected PUNCH_ME_NOW frame",
+            ));
+        }
+        // 地址可能已被撤回，忽略即可
+        let Some((bind_uri, local_addr)) = self.puncher.local(frame.paired_with()) else {
+            return Ok(());
+        };
+        if local_addr.is_ipv4() != frame.addr().is_ipv4() {
+            return Ok(());
+        }
+
+        // 客户端通告的是NAT内的地址，NAT之后它只能从观察到的外部地址被访问到，两者都要打洞
+        let mut remote = vec![frame.addr()];
+        for path in self.paths.iter() {
+            if let RealAddr::Internet(observed) = path.link.dst() {
+                if observed.is_ipv4() == local_addr.is_ipv4() && !remote.contains(&observed) {
+                    remote.push(observed);
+                }
+            }
+        }
+
+        let pathway = |remote_addr: SocketAddr| Pathway::new(local_addr.into(), remote_addr.into());
+        let pathways = remote
+            .iter()
+            .map(|&remote_addr| pathway(remote_addr))
+            .filter(|pathway| self.paths.get(pathway).is_none());
+        let admitted = self
+            .puncher
+            .admit_round(frame.round(), pathways, |pathway| {
+                self.paths
+                    .get(pathway)
+                    .is_some_and(|path| !path.is_validated())
+            });
+
+        for remote_addr in remote {
+            let pathway = pathway(remote_addr);
+            if !admitted.contains(&pathway) {
+                continue;
+            }
+            let link = Link::new(local_addr.into(), remote_addr.into());
+            tracing::info!(round = %frame.round(), %pathway, "Punching");
+            if let Err(e) =
+                self.get_or_try_create_path(bind_uri.clone(), link, pathway.clone(), false)
+            {
+                tracing::warn!(%pathway, "Failed to create path to punch: {e}");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pathway(port: u16) -> Pathway {
+        let local: SocketAddr = "192.0.2.1:4433".parse().unwrap();
+        let remote = SocketAddr::new("198.51.100.1".parse().unwrap(), port);
+        Pathway::new(local.into(), remote.into())
+    }
+
+    #[test]
+    fn keep_newest_remote_candidates() {
+        let puncher = ArcPuncher::default();
+        let addr: SocketAddr = "198.51.100.1:4433".parse().unwrap();
+        for sequence in 0..MAX_REMOTE_CANDIDATES as u32 + 4 {
+            puncher.add_remote(&AddAddressFrame::new(VarInt::from_u32(sequence), addr));
+        }
+        // 比保留的地址都旧的地址被忽略
+        puncher.add_remote(&AddAddressFrame::new(VarInt::from_u32(0), addr));
+
+        let candidates = puncher.0.candidates.lock().unwrap();
+        assert_eq!(candidates.remote.len(), MAX_REMOTE_CANDIDATES);
+        assert_eq!(candidates.remote.first_key_value().unwrap().0, &4);
+    }
+
+    #[test]
+    fn limit_punch_rounds() {
+        let puncher = ArcPuncher::default();
+        let round = |round: u32| VarInt::from_u32(round);
+
+        let admitted = puncher.admit_round(round(0), (0..6).map(pathway), |_| true);
+        assert_eq!(admitted.len(), 6);
+        // 同一轮的后续帧共享路径数上限
+        let admitted = puncher.admit_round(round(0), (6..12).map(pathway), |_| true);
+        assert_eq!(admitted, (6..8).map(pathway).collect::<Vec<_>>());
+        // 上一轮还在打洞，新的一轮被忽略
+        assert!(
+            puncher
+                .admit_round(round(1), [pathway(20)], |_| true)
+                .is_empty()
+        );
+        // 上一轮已经结束，但间隔太短
+        assert!(
+            puncher
+                .admit_round(round(1), [pathway(20)], |_| false)
+                .is_empty()
+        );
+
+        puncher
+            .0
+            .candidates
+            .lock()
+            .unwrap()
+            .punching
+            .as_mut()
+            .unwrap()
+            .started -= MIN_ROUND_INTERVAL;
+        assert_eq!(
+            puncher.admit_round(round(1), [pathway(20)], |_| false),
+            [pathway(20)]
+        );
+        // 过时的轮次被忽略
+        assert!(
+            puncher
+                .admit_round(round(0), [pathway(21)], |_| false)
+                .is_empty()
+        );
+    }
+}
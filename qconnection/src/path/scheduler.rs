@@ -0,0 +1,580 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use qbase::{
+    Epoch,
+    frame::{PathAvailability, ReliableFrame},
+    net::{route::Pathway, tx::Signals},
+    packet::Package,
+};
+use qcongestion::{MSS, Transport};
+
+use super::{ArcPathContexts, Path};
+use crate::ArcReliableFrameDeque;
+
+/// A path is considered failed by [`SchedulerPolicy::PrimaryBackup`] once its ack-eliciting
+/// packets have been unacknowledged for this many PTOs.
+const FAILOVER_PTO_COUNT: u32 = 3;
+
+/// The maximum number of control frames waiting to be duplicated on a path,
+/// the oldest ones are discarded first.
+const MAX_DUPLICATED_FRAMES: usize = 64;
+
+/// The maximum number of duplicated control frames whose copies are tracked, and of lost
+/// frames waiting to be retransmitted, the oldest ones are forgotten first.
+const MAX_TRACKED_FRAMES: usize = 256;
+
+/// How the paths of a connection share the data that is not bound to a path,
+/// such as stream data and control frames.
+///
+/// Packets that belong to a path, like acknowledgments, path challenges and probes,
+/// are always sent on their own path regardless of the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchedulerPolicy {
+    /// Every validated path sends data as soon as it is ready to send.
+    #[default]
+    Greedy,
+    /// Data is sent on the path with the smallest smoothed RTT among the paths
+    /// that are not blocked by congestion control.
+    MinRtt,
+    /// Data is spread over the paths in proportion to their congestion windows.
+    WeightedRoundRobin,
+    /// Data is sent on a single primary path, the other paths take over only when the
+    /// primary path becomes unresponsive or the peer marks it as backup.
+    PrimaryBackup,
+    /// Data is scheduled like [`SchedulerPolicy::MinRtt`], and control frames sent for the
+    /// first time are additionally duplicated on all other validated paths, trading bandwidth
+    /// for latency.
+    ///
+    /// A duplicated frame is retransmitted only after all of its copies are lost, and the
+    /// retransmission is sent once without being duplicated again.
+    Redundant,
+}
+
+/// The state of a validated path that the scheduling decision is based on.
+#[derive(Debug, Clone)]
+struct PathSnapshot {
+    pathway: Pathway,
+    smoothed_rtt: Duration,
+    availability: PathAvailability,
+    responsive: bool,
+}
+
+impl PathSnapshot {
+    fn new(path: &Path) -> Self {
+        let cc = path.cc();
+        let failover_after = cc.get_pto(Epoch::Data) * FAILOVER_PTO_COUNT;
+        Self {
+            pathway: path.pathway(),
+            smoothed_rtt: cc.smoothed_rtt(),
+            availability: path.remote_availability(),
+            responsive: cc.unacked_duration().map_or(true, |d| d < failover_after),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct SchedulerState {
+    // paths whose congestion controller refused to send the last time they tried
+    congested: HashSet<Pathway>,
+    // WeightedRoundRobin: bytes sent on each path, normalized by its congestion window
+    virtual_times: HashMap<Pathway, f64>,
+    // PrimaryBackup: the path currently carrying the data
+    primary: Option<Pathway>,
+    // Redundant: control frames loaded by a path, waiting to be duplicated on other paths
+    loaded: HashMap<Pathway, Vec<ReliableFrame>>,
+    // Redundant: control frames waiting to be sent again on each path
+    duplicates: HashMap<Pathway, VecDeque<ReliableFrame>>,
+    // Redundant: duplicated control frames and the number of their copies not yet lost
+    copies: VecDeque<(ReliableFrame, usize)>,
+    // Redundant: lost control frames queued to be retransmitted, they are not duplicated
+    retransmissions: VecDeque<ReliableFrame>,
+}
+
+fn minimums_by<K: PartialOrd>(
+    paths: &[&PathSnapshot],
+    key: impl Fn(&PathSnapshot) -> K,
+) -> Vec<Pathway> {
+    let Some(min) = paths
+        .iter()
+        .map(|p| key(p))
+        .reduce(|min, k| if k < min { k } else { min })
+    else {
+        return vec![];
+    };
+    paths
+        .iter()
+        .filter(|p| key(p) <= min)
+        .map(|p| p.pathway.clone())
+        .collect()
+}
+
+/// Record that a copy of the `frame` has been sent on another path.
+fn on_duplicate_sent(copies: &mut VecDeque<(ReliableFrame, usize)>, frame: &ReliableFrame) {
+    if let Some((_, copies)) = copies.iter_mut().find(|(f, _)| f == frame) {
+        *copies += 1;
+    }
+}
+
+impl SchedulerState {
+    /// Record the control frames loaded by the path on `pathway`, the retransmissions among
+    /// them are sent once, the others are kept to be duplicated on other paths.
+    fn on_frames_loaded(&mut self, pathway: Pathway, frames: Vec<ReliableFrame>) {
+        for frame in frames {
+            if let Some(idx) = self.retransmissions.iter().position(|f| *f == frame) {
+                self.retransmissions.remove(idx);
+                continue;
+            }
+            if self.copies.len() == MAX_TRACKED_FRAMES {
+                self.copies.pop_front();
+            }
+            self.copies.push_back((frame.clone(), 1));
+            self.loaded.entry(pathway.clone()).or_default().push(frame);
+        }
+    }
+
+    /// Return whether the lost `frame` should be retransmitted.
+    ///
+    /// A duplicated frame is retransmitted only when its last copy is lost, as any other copy
+    /// may still reach the peer.
+    fn on_frame_lost(&mut self, frame: &ReliableFrame) -> bool {
+        if let Some(idx) = self.copies.iter().position(|(f, _)| f == frame) {
+            self.copies[idx].1 -= 1;
+            if self.copies[idx].1 > 0 {
+                return false;
+            }
+            self.copies.remove(idx);
+        }
+        if self.retransmissions.len() == MAX_TRACKED_FRAMES {
+            self.retransmissions.pop_front();
+        }
+        self.retransmissions.push_back(frame.clone());
+        true
+    }
+
+    /// Paths that are able to send right now, preferring the paths the peer did not mark as backup.
+    fn ready<'p>(&self, paths: &'p [PathSnapshot]) -> Vec<&'p PathSnapshot> {
+        let ready = paths
+            .iter()
+            .filter(|p| !self.congested.contains(&p.pathway))
+            .collect::<Vec<_>>();
+        if ready
+            .iter()
+            .any(|p| p.availability == PathAvailability::Available)
+        {
+            return ready
+                .into_iter()
+                .filter(|p| p.availability == PathAvailability::Available)
+                .collect();
+        }
+        ready
+    }
+
+    /// Return the paths that are preferred to send data, any path may send if it is empty.
+    fn select(&mut self, policy: SchedulerPolicy, paths: &[PathSnapshot]) -> Vec<Pathway> {
+        match policy {
+            SchedulerPolicy::Greedy => vec![],
+            SchedulerPolicy::MinRtt | SchedulerPolicy::Redundant => {
+                minimums_by(&self.ready(paths), |p| p.smoothed_rtt)
+            }
+            SchedulerPolicy::WeightedRoundRobin => {
+                self.virtual_times
+                    .retain(|pathway, _| paths.iter().any(|p| p.pathway == *pathway));
+                // a path joining the schedule starts from the least virtual time,
+                // rather than taking all the data until it catches up
+                let least = self
+                    .virtual_times
+                    .values()
+                    .copied()
+                    .reduce(f64::min)
+                    .unwrap_or_default();
+                for path in paths {
+                    self.virtual_times
+                        .entry(path.pathway.clone())
+                        .or_insert(least);
+                }
+                let virtual_times = &self.virtual_times;
+                minimums_by(&self.ready(paths), |p| virtual_times[&p.pathway])
+            }
+            SchedulerPolicy::PrimaryBackup => {
+                let has_available = paths
+                    .iter()
+                    .any(|p| p.responsive && p.availability == PathAvailability::Available);
+                let primary = self
+                    .primary
+                    .as_ref()
+                    .and_then(|primary| paths.iter().find(|p| p.pathway == *primary))
+                    .filter(|p| {
+                        p.responsive
+                            && (p.availability == PathAvailability::Available || !has_available)
+                    });
+                if primary.is_none() {
+                    let failover = paths.iter().filter(|p| p.responsive).min_by_key(|p| {
+                        (p.availability == PathAvailability::Backup, p.smoothed_rtt)
+                    });
+                    // keep the primary path if no path is responsive
+                    if let Some(failover) = failover {
+                        self.primary = Some(failover.pathway.clone());
+                    }
+                }
+                self.primary
+                    .iter()
+                    .filter(|primary| paths.iter().any(|p| p.pathway == **primary))
+                    .cloned()
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The scheduler decides which paths send the data shared by all paths of a connection.
+///
+/// Each path runs its own burst loop, before loading the shared data sources it asks the
+/// scheduler with [`ArcScheduler::may_send_data`]. A path not allowed to send waits for
+/// [`Signals::SCHEDULE`], which is signaled when the scheduler prefers it.
+#[derive(Clone)]
+pub struct ArcScheduler {
+    policy: SchedulerPolicy,
+    paths: ArcPathContexts,
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl ArcScheduler {
+    pub fn new(policy: SchedulerPolicy, paths: ArcPathContexts) -> Self {
+        Self {
+            policy,
+            paths,
+            state: Default::default(),
+        }
+    }
+
+    pub fn policy(&self) -> SchedulerPolicy {
+        self.policy
+    }
+
+    // The lock is never held while accessing paths, to avoid deadlocks with the congestion controllers.
+    fn state(&self) -> MutexGuard<'_, SchedulerState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Record whether the congestion controller of the `path` allows it to send.
+    ///
+    /// A congested path let the other paths take over the data.
+    pub(super) fn on_send_quota(&self, path: &Path, congested: bool) {
+        if self.policy == SchedulerPolicy::Greedy {
+            return;
+        }
+        let mut state = self.state();
+        let became_congested = if congested {
+            state.congested.insert(path.pathway())
+        } else {
+            state.congested.remove(&path.pathway);
+            false
+        };
+        drop(state);
+        if became_congested {
+            self.paths
+                .iter()
+                .filter(|p| p.pathway != path.pathway)
+                .for_each(|p| p.tx_waker.wake_by(Signals::SCHEDULE));
+        }
+    }
+
+    /// Return whether the `path` may send the data shared by all paths.
+    ///
+    /// If not, the preferred paths are woken up to send the data instead.
+    pub(super) fn may_send_data(&self, path: &Path) -> bool {
+        if self.policy == SchedulerPolicy::Greedy {
+            return true;
+        }
+        let paths = self
+            .paths
+            .iter()
+            .filter(|p| p.is_validated())
+            .map(|p| PathSnapshot::new(&p))
+            .collect::<Vec<_>>();
+        let selected = self.state().select(self.policy, &paths);
+        if selected.is_empty() || selected.contains(&path.pathway) {
+            return true;
+        }
+        selected
+            .iter()
+            .filter_map(|pathway| self.paths.get(pathway))
+            .for_each(|p| p.tx_waker.wake_by(Signals::SCHEDULE));
+        false
+    }
+
+    /// Record the bytes of the packet carrying shared data sent on the `path`.
+    pub(super) fn on_data_sent(&self, path: &Path, bytes: usize) {
+        if self.policy != SchedulerPolicy::WeightedRoundRobin {
+            return;
+        }
+        let cwnd = path.cc().congestion_window().max(MSS);
+        if let Some(virtual_time) = self.state().virtual_times.get_mut(&path.pathway) {
+            *virtual_time += bytes as f64 / cwnd as f64;
+        }
+    }
+
+    /// The reliable frames loaded by the path on `pathway`.
+    ///
+    /// With [`SchedulerPolicy::Redundant`], the loaded frames are kept to be duplicated
+    /// on other paths by [`ArcScheduler::duplicate_loaded_frames`].
+    pub(crate) fn reliable_frames(
+        &self,
+        pathway: Pathway,
+        frames: ArcReliableFrameDeque,
+    ) -> ScheduledReliableFrames {
+        ScheduledReliableFrames {
+            scheduler: self.clone(),
+            pathway,
+            frames,
+        }
+    }
+
+    /// Queue the reliable frames just loaded by the `path` to all other validated paths.
+    pub(super) fn duplicate_loaded_frames(&self, path: &Path) {
+        if self.policy != SchedulerPolicy::Redundant {
+            return;
+        }
+        let Some(frames) = self.state().loaded.remove(&path.pathway) else {
+            return;
+        };
+        let others = self
+            .paths
+            .iter()
+            .filter(|p| p.pathway != path.pathway && p.is_validated())
+            .collect::<Vec<_>>();
+
+        let mut state = self.state();
+        state
+            .duplicates
+            .retain(|pathway, _| others.iter().any(|p| p.pathway == *pathway));
+        for other in &others {
+            let duplicates = state.duplicates.entry(other.pathway()).or_default();
+            duplicates.extend(frames.iter().cloned());
+            let overflow = duplicates.len().saturating_sub(MAX_DUPLICATED_FRAMES);
+            duplicates.drain(..overflow);
+        }
+        drop(state);
+
+        others
+            .iter()
+            .for_each(|p| p.tx_waker.wake_by(Signals::TRANSPORT));
+    }
+
+    /// Return whether the lost reliable `frame` should be queued again to be retransmitted.
+    ///
+    /// With [`SchedulerPolicy::Redundant`], a duplicated frame is retransmitted once after all
+    /// of its copies are lost, other policies always retransmit the lost frames.
+    pub(crate) fn on_frame_lost(&self, frame: &ReliableFrame) -> bool {
+        if self.policy != SchedulerPolicy::Redundant {
+            return true;
+        }
+        self.state().on_frame_lost(frame)
+    }
+
+    /// The control frames duplicated on the path on `pathway`.
+    pub(super) fn duplicated_frames(&self, pathway: Pathway) -> DuplicatedFrames<'_> {
+        DuplicatedFrames {
+            scheduler: self,
+            pathway,
+        }
+    }
+}
+
+/// The reliable frames shared by all paths, see [`ArcScheduler::reliable_frames`].
+pub struct ScheduledReliableFrames {
+    scheduler: ArcScheduler,
+    pathway: Pathway,
+    frames: ArcReliableFrameDeque,
+}
+
+impl<P: ?Sized> Package<P> for ScheduledReliableFrames
+where
+    for<'a> &'a ReliableFrame: Package<P>,
+{
+    fn dump(&mut self, packet: &mut P) -> Result<(), Signals> {
+        if self.scheduler.policy != SchedulerPolicy::Redundant {
+            return self.frames.try_load_frames_into(packet);
+        }
+        let mut loaded = vec![];
+        let result = self
+            .frames
+            .try_load_frames_into_and(packet, |frame| loaded.push(frame.clone()));
+        if !loaded.is_empty() {
+            let mut state = self.scheduler.state();
+            state.on_frames_loaded(self.pathway.clone(), loaded);
+        }
+        result
+    }
+}
+
+/// The duplicated control frames to send on a path, see [`ArcScheduler::duplicated_frames`].
+///
+/// The duplicates are reliable frames too, they are retransmitted once all copies are lost.
+pub struct DuplicatedFrames<'s> {
+    scheduler: &'s ArcScheduler,
+    pathway: Pathway,
+}
+
+impl<P: ?Sized> Package<P> for DuplicatedFrames<'_>
+where
+    for<'a> &'a ReliableFrame: Package<P>,
+{
+    fn dump(&mut self, packet: &mut P) -> Result<(), Signals> {
+        let mut state = self.scheduler.state();
+        let SchedulerState {
+            duplicates, copies, ..
+        } = &mut *state;
+        let Some(frames) = duplicates
+            .get_mut(&self.pathway)
+            .filter(|frames| !frames.is_empty())
+        else {
+            return Err(Signals::TRANSPORT);
+        };
+        while let Some(mut frame) = frames.front() {
+            frame.dump(packet)?;
+            let frame = frames.pop_front().unwrap();
+            on_duplicate_sent(copies, &frame);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+
+    fn pathway(port: u16) -> Pathway {
+        let local: SocketAddr = "127.0.0.1:4433".parse().unwrap();
+        let remote = SocketAddr::new(local.ip(), port);
+        Pathway::new(local.into(), remote.into())
+    }
+
+    fn snapshot(port: u16, rtt_ms: u64) -> PathSnapshot {
+        PathSnapshot {
+            pathway: pathway(port),
+            smoothed_rtt: Duration::from_millis(rtt_ms),
+            availability: PathAvailability::Available,
+            responsive: true,
+        }
+    }
+
+    #[test]
+    fn greedy_selects_nothing() {
+        let mut state = SchedulerState::default();
+        let paths = [snapshot(1, 10), snapshot(2, 20)];
+        assert!(state.select(SchedulerPolicy::Greedy, &paths).is_empty());
+    }
+
+    #[test]
+    fn min_rtt_skips_congested_and_backup() {
+        let mut state = SchedulerState::default();
+        let mut paths = [snapshot(1, 10), snapshot(2, 20), snapshot(3, 30)];
+        assert_eq!(
+            state.select(SchedulerPolicy::MinRtt, &paths),
+            vec![pathway(1)]
+        );
+
+        state.congested.insert(pathway(1));
+        assert_eq!(
+            state.select(SchedulerPolicy::MinRtt, &paths),
+            vec![pathway(2)]
+        );
+
+        paths[1].availability = PathAvailability::Backup;
+        assert_eq!(
+            state.select(SchedulerPolicy::Redundant, &paths),
+            vec![pathway(3)]
+        );
+
+        // all paths are congested, any path may send
+        state.congested.extend([pathway(2), pathway(3)]);
+        assert!(state.select(SchedulerPolicy::MinRtt, &paths).is_empty());
+    }
+
+    #[test]
+    fn weighted_round_robin_follows_virtual_time() {
+        let mut state = SchedulerState::default();
+        let paths = [snapshot(1, 10), snapshot(2, 20)];
+        let policy = SchedulerPolicy::WeightedRoundRobin;
+        assert_eq!(state.select(policy, &paths), vec![pathway(1), pathway(2)]);
+
+        *state.virtual_times.get_mut(&pathway(1)).unwrap() += 0.5;
+        assert_eq!(state.select(policy, &paths), vec![pathway(2)]);
+        *state.virtual_times.get_mut(&pathway(2)).unwrap() += 1.0;
+        assert_eq!(state.select(policy, &paths), vec![pathway(1)]);
+
+        // a new path starts from the least virtual time
+        let paths = [snapshot(1, 10), snapshot(2, 20), snapshot(3, 30)];
+        assert_eq!(state.select(policy, &paths), vec![pathway(1), pathway(3)]);
+
+        // removed paths are forgotten
+        let paths = [snapshot(3, 30)];
+        assert_eq!(state.select(policy, &paths), vec![pathway(3)]);
+        assert_eq!(state.virtual_times.len(), 1);
+    }
+
+    #[test]
+    fn redundant_retransmits_once() {
+        use qbase::frame::{HandshakeDoneFrame, MaxDataFrame};
+
+        let mut state = SchedulerState::default();
+        let fresh = ReliableFrame::from(HandshakeDoneFrame);
+        let other = ReliableFrame::from(MaxDataFrame::new(100u32.into()));
+        state.on_frames_loaded(pathway(1), vec![fresh.clone()]);
+        assert_eq!(state.loaded[&pathway(1)], vec![fresh.clone()]);
+        on_duplicate_sent(&mut state.copies, &fresh);
+        on_duplicate_sent(&mut state.copies, &fresh);
+
+        // retransmit only after all three copies are lost
+        assert!(!state.on_frame_lost(&fresh));
+        assert!(!state.on_frame_lost(&fresh));
+        assert!(state.on_frame_lost(&fresh));
+
+        // the retransmission is not duplicated
+        state.loaded.clear();
+        state.on_frames_loaded(pathway(2), vec![fresh.clone(), other.clone()]);
+        assert_eq!(state.loaded[&pathway(2)], vec![other.clone()]);
+
+        // a frame never duplicated is retransmitted immediately
+        state.copies.clear();
+        assert!(state.on_frame_lost(&other));
+    }
+
+    #[test]
+    fn primary_backup_failover() {
+        let mut state = SchedulerState::default();
+        let policy = SchedulerPolicy::PrimaryBackup;
+        let mut paths = [snapshot(1, 30), snapshot(2, 10), snapshot(3, 20)];
+        paths[1].availability = PathAvailability::Backup;
+        assert_eq!(state.select(policy, &paths), vec![pathway(3)]);
+
+        // the primary path is sticky
+        paths[0].smoothed_rtt = Duration::from_millis(5);
+        assert_eq!(state.select(policy, &paths), vec![pathway(3)]);
+
+        // congestion does not cause failover
+        state.congested.insert(pathway(3));
+        assert_eq!(state.select(policy, &paths), vec![pathway(3)]);
+
+        paths[2].responsive = false;
+        assert_eq!(state.select(policy, &paths), vec![pathway(1)]);
+
+        // fail over to the backup path when no available path is responsive
+        paths[0].responsive = false;
+        assert_eq!(state.select(policy, &paths), vec![pathway(2)]);
+
+        // keep the primary path if nothing is responsive
+        paths[1].responsive = false;
+        assert_eq!(state.select(policy, &paths), vec![pathway(2)]);
+
+        // the primary path is gone
+        assert!(state.select(policy, &paths[..1]).is_empty());
+    }
+}
@@ -17,10 +17,24 @@ pub enum ValidateFailure {
 
 impl super::Path {
     pub fn validated(&self) {
-        self.validated.store(true, Ordering::Release);
+        if !self.validated.swap(true, Ordering::AcqRel) {
+            // 等待者取得许可后随即归还，一个许可足以唤醒所有等待者
+            self.validation.add_permits(1);
+        }
         self.tx_waker.wake_by(Signals::PATH_VALIDATE);
     }
 
+    pub fn is_validated(&self) -> bool {
+        self.validated.load(Ordering::Acquire)
+    }
+
+    /// Wait for the path to be validated.
+    ///
+    /// Return `false` if the path is deactivated before it is validated.
+    pub async fn wait_validated(&self) -> bool {
+        self.is_validated() || self.validation.acquire().await.is_ok()
+    }
+
     pub async fn validate(&self) -> Result<(), ValidateFailure> {
         let challenge = PathChallengeFrame::random();
         let start = Instant::now();
@@ -8,8 +8,8 @@ use bytes::Bytes;
 use qbase::{
     error::Error,
     frame::{
-        AckFrame, ConnectionCloseFrame, CryptoFrame, FrameFeature, GetFrameType, ReceiveFrame,
-        ReliableFrame, StreamCtlFrame, StreamFrame,
+        AckFrame, ConnectionCloseFrame, CryptoFrame, FrameFeature, GetFrameType, PathAckFrame,
+        ReceiveFrame, ReliableFrame, StreamCtlFrame, StreamFrame,
     },
     packet::{
         AssemblePacket, Package, PacketSpace, PacketWriter, ProductHeader,
@@ -355,6 +355,43 @@ impl ReceiveFrame<AckFrame> for AckDataSpace {
     }
 }
 
+/// Like [`AckDataSpace`], but handles the PATH_ACK frames for the packet number
+/// spaces of multipath paths.
+struct AckPathDataSpaces {
+    space: Arc<data::DataSpace>,
+    data_streams: DataStreams,
+    crypto_stream: CryptoStream,
+}
+
+impl AckPathDataSpaces {
+    fn new(
+        space: &Arc<data::DataSpace>,
+        data_streams: &DataStreams,
+        crypto_stream: &CryptoStream,
+    ) -> Self {
+        Self {
+            space: space.clone(),
+            data_streams: data_streams.clone(),
+            crypto_stream: crypto_stream.clone(),
+        }
+    }
+}
+
+impl ReceiveFrame<PathAckFrame> for AckPathDataSpaces {
+    type Output = ();
+
+    fn recv_frame(&self, frame: &PathAckFrame) -> Result<Self::Output, Error> {
+        let Ok(path_id) = u32::try_from(frame.path_id().into_inner()) else {
+            return Ok(());
+        };
+        // 路径ID已被放弃时，其包号空间也已被丢弃
+        let Some(journal) = self.space.existing_path_journal(path_id) else {
+            return Ok(());
+        };
+        AckDataSpace::new(&journal, &self.data_streams, &self.crypto_stream).recv_frame(frame.ack())
+    }
+}
+
 pub fn spawn_deliver_and_parse(components: &Components) {
     let received_packets_queue = &components.rcvd_pkt_q;
     initial::spawn_deliver_and_parse(
@@ -363,6 +400,11 @@ pub fn spawn_deliver_and_parse(components: &Components) {
         components,
         components.event_broker.clone(),
     );
+    initial::spawn_deliver_and_handle_retry(
+        received_packets_queue.retry().clone(),
+        components.spaces.initial.clone(),
+        components,
+    );
     handshake::spawn_deliver_and_parse(
         received_packets_queue.handshake().clone(),
         components.spaces.handshake.clone(),
@@ -1,9 +1,16 @@
-use std::sync::{Arc, atomic::Ordering::SeqCst};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, atomic::Ordering::SeqCst},
+    time::Duration,
+};
 
 use qbase::{
     Epoch, GetEpoch,
     error::{Error, QuicError},
-    frame::{ConnectionCloseFrame, Frame, FrameReader, ReceiveFrame, SendFrame},
+    frame::{
+        ConnectionCloseFrame, ExtensionFrame, Frame, FrameReader, GetFrameType, ReceiveFrame,
+        ReliableFrame, SendFrame,
+    },
     net::{
         addr::BindUri,
         route::{Link, Pathway},
@@ -13,10 +20,12 @@ use qbase::{
         self, PacketContains,
         header::{GetDcid, GetType, OneRttHeader, long::ZeroRttHeader},
         io::PacketSpace,
-        keys::{ArcOneRttKeys, ArcZeroRttKeys, DirectionalKeys},
+        keys::{ArcOneRttKeys, ArcZeroRttKeys, DirectionalKeys, PathPacketKey},
         r#type::Type,
     },
+    param::ParameterId,
     util::BoundQueue,
+    varint::VarInt,
 };
 use qcongestion::{ArcCC, Feedback, Transport};
 use qevent::{
@@ -36,8 +45,13 @@ use crate::{
     ArcReliableFrameDeque, Components, DataJournal, DataStreams, GuaranteedFrame,
     SpecificComponents,
     events::{ArcEventBroker, EmitEvent, Event},
-    path::{self, Path, error::CreatePathFailure},
-    space::{AckDataSpace, FlowControlledDataStreams, assemble_closing_packet, pipe},
+    path::{
+        self, ArcScheduler, Path,
+        error::{CreatePathFailure, PathDeactivated},
+    },
+    space::{
+        AckDataSpace, AckPathDataSpaces, FlowControlledDataStreams, assemble_closing_packet, pipe,
+    },
     termination::Terminator,
     tx::{PacketWriter, TrivialPacketWriter},
 };
@@ -54,8 +68,18 @@ pub struct DataSpace {
     zero_rtt_keys: ArcZeroRttKeys,
     one_rtt_keys: ArcOneRttKeys,
     journal: DataJournal,
+    // the peer's max_ack_delay for the paths other than path 0, set if multipath is enabled
+    multipath: OnceLock<Duration>,
+    path_journals: PathJournals,
 }
 
+/// The 1-RTT packet number spaces of the multipath paths other than path 0.
+///
+/// The path 0 uses the [`DataJournal`] of the [`DataSpace`] directly, which is
+/// also shared by the 0-RTT packets.
+#[derive(Default, Clone)]
+struct PathJournals(Arc<Mutex<HashMap<u32, DataJournal>>>);
+
 impl AsRef<DataJournal> for DataSpace {
     fn as_ref(&self) -> &DataJournal {
         &self.journal
@@ -68,6 +92,47 @@ impl DataSpace {
             zero_rtt_keys,
             one_rtt_keys: ArcOneRttKeys::new_pending(),
             journal: DataJournal::with_capacity(16, None),
+            multipath: OnceLock::new(),
+            path_journals: PathJournals::default(),
+        }
+    }
+
+    /// Enable the packet number spaces of the paths other than path 0, after multipath is
+    /// negotiated, `max_ack_delay` is the peer's max_ack_delay parameter.
+    pub fn enable_multipath(&self, max_ack_delay: Duration) {
+        _ = self.multipath.set(max_ack_delay);
+    }
+
+    /// Get the journal of the packet number space of the path `path_id`.
+    pub fn path_journal(&self, path_id: u32) -> DataJournal {
+        match path_id {
+            0 => self.journal.clone(),
+            path_id => self.path_journals.get_or_create(path_id, || {
+                DataJournal::with_capacity(16, self.multipath.get().copied())
+            }),
+        }
+    }
+
+    /// Get the journal of the packet number space of the path `path_id`,
+    /// None if the path has been abandoned or never been used.
+    pub fn existing_path_journal(&self, path_id: u32) -> Option<DataJournal> {
+        match path_id {
+            0 => Some(self.journal.clone()),
+            path_id => self.path_journals.get(path_id),
+        }
+    }
+
+    /// Discard the packet number space of the abandoned path `path_id`.
+    pub fn remove_path_journal(&self, path_id: u32) {
+        self.path_journals.remove(path_id);
+    }
+
+    /// Get the packet number space of the path `path_id` to send 1-RTT packets.
+    pub fn path_space(&self, path_id: u32) -> PathDataSpace {
+        PathDataSpace {
+            path_id,
+            one_rtt_keys: self.one_rtt_keys.clone(),
+            journal: self.path_journal(path_id),
         }
     }
 
@@ -92,10 +157,11 @@ impl DataSpace {
     pub async fn decrypt_1rtt_packet(
         &self,
         packet: CipherOneRttPacket,
+        path_id: u32,
     ) -> Option<Result<PlainOneRttPacket, QuicError>> {
         match self.one_rtt_keys.get_remote_keys().await {
-            Some((hpk, pk)) => packet.decrypt_short_packet(hpk.as_ref(), &pk, |pn| {
-                self.journal.of_rcvd_packets().decode_pn(pn)
+            Some((hpk, pk)) => packet.decrypt_short_packet(hpk.as_ref(), &pk, path_id, |pn| {
+                self.path_journal(path_id).of_rcvd_packets().decode_pn(pn)
             }),
             None => {
                 packet.drop_on_key_unavailable();
@@ -174,15 +240,18 @@ impl DataSpace {
 
     pub fn tracker(
         &self,
+        path_id: u32,
         crypto_stream: CryptoStream,
         streams: DataStreams,
         reliable_frames: ArcReliableFrameDeque,
+        scheduler: ArcScheduler,
     ) -> DataTracker {
         DataTracker {
-            journal: self.journal.clone(),
+            journal: self.path_journal(path_id),
             crypto_stream,
             streams,
             reliable_frames,
+            scheduler,
         }
     }
 }
@@ -257,11 +326,18 @@ pub fn spawn_deliver_and_parse(
     event_broker: ArcEventBroker,
 ) {
     let (ack_frames_entry, rcvd_ack_frames) = mpsc::unbounded_channel();
+    let (path_ack_frames_entry, rcvd_path_ack_frames) = mpsc::unbounded_channel();
     // 连接级的
     let (max_data_frames_entry, rcvd_max_data_frames) = mpsc::unbounded_channel();
     let (data_blocked_frames_entry, rcvd_data_blocked_frames) = mpsc::unbounded_channel();
     let (new_cid_frames_entry, rcvd_new_cid_frames) = mpsc::unbounded_channel();
     let (retire_cid_frames_entry, rcvd_retire_cid_frames) = mpsc::unbounded_channel();
+    // 多路径的
+    let (path_new_cid_frames_entry, rcvd_path_new_cid_frames) = mpsc::unbounded_channel();
+    let (path_retire_cid_frames_entry, rcvd_path_retire_cid_frames) = mpsc::unbounded_channel();
+    let (max_path_id_frames_entry, rcvd_max_path_id_frames) = mpsc::unbounded_channel();
+    let (paths_blocked_frames_entry, rcvd_paths_blocked_frames) = mpsc::unbounded_channel();
+    let (path_cids_blocked_frames_entry, rcvd_path_cids_blocked_frames) = mpsc::unbounded_channel();
     let (handshake_done_frames_entry, rcvd_handshake_done_frames) = mpsc::unbounded_channel();
     let (new_token_frames_entry, rcvd_new_token_frames) = mpsc::unbounded_channel();
     // 数据级的
@@ -287,6 +363,31 @@ pub fn spawn_deliver_and_parse(
         components.cid_registry.remote.clone(),
         event_broker.clone(),
     );
+    pipe(
+        rcvd_path_new_cid_frames,
+        components.multipath.clone(),
+        event_broker.clone(),
+    );
+    pipe(
+        rcvd_path_retire_cid_frames,
+        components.multipath.clone(),
+        event_broker.clone(),
+    );
+    pipe(
+        rcvd_max_path_id_frames,
+        components.multipath.clone(),
+        event_broker.clone(),
+    );
+    pipe(
+        rcvd_paths_blocked_frames,
+        components.multipath.clone(),
+        event_broker.clone(),
+    );
+    pipe(
+        rcvd_path_cids_blocked_frames,
+        components.multipath.clone(),
+        event_broker.clone(),
+    );
     pipe(
         rcvd_max_data_frames,
         components.flow_ctrl.sender.clone(),
@@ -334,6 +435,15 @@ pub fn spawn_deliver_and_parse(
         ),
         event_broker.clone(),
     );
+    pipe(
+        rcvd_path_ack_frames,
+        AckPathDataSpaces::new(
+            &space,
+            &components.data_streams,
+            &components.crypto_streams[space.epoch()],
+        ),
+        event_broker.clone(),
+    );
     pipe(
         rcvd_new_token_frames,
         components.token_registry.clone(),
@@ -343,12 +453,112 @@ pub fn spawn_deliver_and_parse(
     let dispatch_data_frame = {
         let event_broker = event_broker.clone();
         let rcvd_joural = space.journal.of_rcvd_packets();
+        // The ack of a packet number space is handled by the path sending packets in it,
+        // the path where the ack is received is preferred if several paths share the space.
+        let sender_cc = {
+            let paths = components.paths.clone();
+            move |path_id: u32, path: &Path| match path.path_id() == path_id {
+                true => Some(path.cc().clone()),
+                false => paths
+                    .iter()
+                    .find(|p| p.path_id() == path_id)
+                    .map(|p| p.cc().clone()),
+            }
+        };
+        let space = space.clone();
+        let paths = components.paths.clone();
+        let multipath = components.multipath.clone();
+        let extension_frames = components.extension_frames.clone();
+        let reliable_frames = components.reliable_frames.clone();
+        let ack_frequency = components.ack_frequency.clone();
+        // 本地参数在连接创建时即已确定
+        let min_ack_delay = components.parameters.lock_guard().ok().and_then(|params| {
+            params
+                .get_local::<VarInt>(ParameterId::MinAckDelay)
+                .map(|delay| Duration::from_micros(delay.into_inner()))
+        });
+        let nat_traversal = components
+            .parameters
+            .lock_guard()
+            .ok()
+            .is_some_and(|params| {
+                params
+                    .get_local::<bool>(ParameterId::NatTraversal)
+                    .unwrap_or(false)
+            });
+        let puncher = components.clone();
         move |frame: Frame, pty: packet::Type, path: &Path| match frame {
             Frame::Ack(f) => {
-                path.cc().on_ack_rcvd(Epoch::Data, &f);
+                if let Some(cc) = sender_cc(0, path) {
+                    cc.on_ack_rcvd(Epoch::Data, &f);
+                }
                 rcvd_joural.on_rcvd_ack(&f);
                 _ = ack_frames_entry.send(f)
             }
+            Frame::PathAck(f) => match multipath.check_path_id(f.frame_type(), f.path_id()) {
+                Ok(Some(path_id)) => {
+                    if let Some(cc) = sender_cc(path_id, path) {
+                        cc.on_ack_rcvd(Epoch::Data, f.ack());
+                    }
+                    if let Some(journal) = space.existing_path_journal(path_id) {
+                        journal.of_rcvd_packets().on_rcvd_ack(f.ack());
+                    }
+                    _ = path_ack_frames_entry.send(f)
+                }
+                Ok(None) => {}
+                Err(error) => event_broker.emit(Event::Failed(error)),
+            },
+            Frame::PathAbandon(f) => match multipath.check_path_id(f.frame_type(), f.path_id()) {
+                Ok(Some(path_id)) => {
+                    // 回应对端的PATH_ABANDON，再移除使用该路径ID的路径
+                    multipath.abandon(path_id);
+                    // 遍历时持有DashMap的读锁，不能在遍历中移除路径
+                    let abandoned = paths
+                        .iter()
+                        .filter(|p| p.path_id() == path_id)
+                        .map(|p| p.pathway())
+                        .collect::<Vec<_>>();
+                    for pathway in abandoned {
+                        paths.remove(&pathway, &PathDeactivated::Abandoned(f.error_code()));
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => event_broker.emit(Event::Failed(error)),
+            },
+            Frame::PathStatus(f) => match multipath.check_path_id(f.frame_type(), f.path_id()) {
+                Ok(Some(path_id)) => {
+                    for p in paths.iter().filter(|p| p.path_id() == path_id) {
+                        _ = p.recv_frame(&f);
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => event_broker.emit(Event::Failed(error)),
+            },
+            Frame::PathNewConnectionId(f) => _ = path_new_cid_frames_entry.send(f),
+            Frame::PathRetireConnectionId(f) => _ = path_retire_cid_frames_entry.send(f),
+            Frame::MaxPathId(f) => _ = max_path_id_frames_entry.send(f),
+            Frame::PathsBlocked(f) => _ = paths_blocked_frames_entry.send(f),
+            Frame::PathCidsBlocked(f) => _ = path_cids_blocked_frames_entry.send(f),
+            Frame::AckFrequency(f) => {
+                if let Err(error) = ack_frequency.recv_frame(&f, min_ack_delay, &paths) {
+                    event_broker.emit(Event::Failed(error));
+                }
+            }
+            Frame::ImmediateAck(f) => {
+                if let Err(error) = ack_frequency.recv_immediate_ack(&f, min_ack_delay, path) {
+                    event_broker.emit(Event::Failed(error));
+                }
+            }
+            Frame::AddAddress(f) => {
+                if let Err(error) = puncher.recv_add_address_frame(&f, nat_traversal) {
+                    event_broker.emit(Event::Failed(error));
+                }
+            }
+            Frame::PunchMeNow(f) => {
+                if let Err(error) = puncher.recv_punch_me_now_frame(&f, nat_traversal) {
+                    event_broker.emit(Event::Failed(error));
+                }
+            }
             Frame::NewToken(f) => _ = new_token_frames_entry.send(f),
             Frame::MaxData(f) => _ = max_data_frames_entry.send(f),
             Frame::NewConnectionId(f) => _ = new_cid_frames_entry.send(f),
@@ -366,6 +576,16 @@ pub fn spawn_deliver_and_parse(
             #[cfg(feature = "unreliable")]
             Frame::Datagram(f, data) => _ = datagram_frames_entry.send((f, data)),
             Frame::Close(f) if matches!(pty, Type::Short(_)) => event_broker.emit(Event::Closed(f)),
+            Frame::Extension(f) => {
+                // 只有注册过的扩展帧才能被解析出来
+                let Some(def) = extension_frames.get(f.extension_type().value()) else {
+                    return;
+                };
+                let reply = |frame: ExtensionFrame| reliable_frames.send_frame([frame]);
+                if let Err(error) = def.handle(&f, &reply) {
+                    event_broker.emit(Event::Failed(error));
+                }
+            }
             _ => {}
         }
     };
@@ -385,18 +605,26 @@ pub fn spawn_deliver_and_parse(
                         return Ok(());
                     };
 
-                    let path =
-                        match components.get_or_try_create_path(bind_uri, link, pathway, true) {
-                            Ok(path) => path,
-                            Err(CreatePathFailure::ConnectionClosed(..)) => {
-                                packet.drop_on_conenction_closed();
-                                return Ok(());
-                            }
-                            Err(CreatePathFailure::NoInterface(..)) => {
-                                packet.drop_on_interface_not_found();
-                                return Ok(());
-                            }
-                        };
+                    let path = match components.get_or_try_create_path(
+                        bind_uri,
+                        link,
+                        pathway.clone(),
+                        true,
+                    ) {
+                        Ok(path) => path,
+                        Err(CreatePathFailure::ConnectionClosed(..)) => {
+                            packet.drop_on_conenction_closed();
+                            return Ok(());
+                        }
+                        Err(CreatePathFailure::NoInterface(..)) => {
+                            packet.drop_on_interface_not_found();
+                            return Ok(());
+                        }
+                        Err(CreatePathFailure::NoPathId) => {
+                            packet.drop_on_path_id_unavailable();
+                            return Ok(());
+                        }
+                    };
 
                     // the origin dcid doesnot own a sequences number, once we received a packet which dcid != odcid,
                     // we should stop using the odcid, and drop the subsequent packets with odcid.
@@ -423,6 +651,7 @@ pub fn spawn_deliver_and_parse(
 
                     let mut frames = QuicFramesCollector::<PacketReceived>::new();
                     let packet_contains = FrameReader::new(packet.body(), packet.get_type())
+                        .with_extensions(components.extension_frames.clone())
                         .try_fold(PacketContains::default(), |packet_contains, frame| {
                             let (frame, frame_type) = frame?;
                             frames.extend(Some(&frame));
@@ -462,22 +691,39 @@ pub fn spawn_deliver_and_parse(
             tls_handshake.finished().await;
             while let Some((packet, (bind_uri, pathway, link))) = one_rtt_packets.recv().await {
                 let parse = async {
-                    let Some(packet) = space.decrypt_1rtt_packet(packet).await.transpose()? else {
+                    let path_id = components.multipath.path_id_of(packet.dcid());
+                    let Some(packet) = space
+                        .decrypt_1rtt_packet(packet, path_id)
+                        .await
+                        .transpose()?
+                    else {
                         return Ok(());
                     };
 
-                    let path =
-                        match components.get_or_try_create_path(bind_uri, link, pathway, true) {
-                            Ok(path) => path,
-                            Err(CreatePathFailure::ConnectionClosed(..)) => {
-                                packet.drop_on_conenction_closed();
-                                return Ok(());
-                            }
-                            Err(CreatePathFailure::NoInterface(..)) => {
-                                packet.drop_on_interface_not_found();
-                                return Ok(());
-                            }
-                        };
+                    let path = match components.get_or_try_create_probed_path(
+                        bind_uri,
+                        link,
+                        pathway.clone(),
+                        path_id,
+                    ) {
+                        Ok(path) => path,
+                        Err(CreatePathFailure::ConnectionClosed(..)) => {
+                            packet.drop_on_conenction_closed();
+                            return Ok(());
+                        }
+                        Err(CreatePathFailure::NoInterface(..)) => {
+                            packet.drop_on_interface_not_found();
+                            return Ok(());
+                        }
+                        Err(CreatePathFailure::NoPathId) => {
+                            packet.drop_on_path_id_unavailable();
+                            return Ok(());
+                        }
+                    };
+                    if path.path_id() != path_id {
+                        packet.drop_on_path_id_unavailable();
+                        return Ok(());
+                    }
 
                     // the origin dcid doesnot own a sequences number, once we received a packet which dcid != odcid,
                     // we should stop using the odcid, and drop the subsequent packets with odcid.
@@ -508,6 +754,7 @@ pub fn spawn_deliver_and_parse(
 
                     let mut frames = QuicFramesCollector::<PacketReceived>::new();
                     let packet_contains = FrameReader::new(packet.body(), packet.get_type())
+                        .with_extensions(components.extension_frames.clone())
                         .try_fold(PacketContains::default(), |packet_contains, frame| {
                             let (frame, frame_type) = frame?;
                             frames.extend(Some(&frame));
@@ -516,7 +763,7 @@ pub fn spawn_deliver_and_parse(
                         })?;
                     packet.log_received(frames);
 
-                    space.journal.of_rcvd_packets().on_rcvd_pn(
+                    space.path_journal(path_id).of_rcvd_packets().on_rcvd_pn(
                         packet.pn(),
                         packet_contains.ack_eliciting(),
                         path.cc().get_pto(Epoch::Data),
@@ -560,11 +807,84 @@ pub fn spawn_deliver_and_parse(
     });
 }
 
+impl PathJournals {
+    fn get_or_create(&self, path_id: u32, create: impl FnOnce() -> DataJournal) -> DataJournal {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(path_id)
+            .or_insert_with(create)
+            .clone()
+    }
+
+    fn get(&self, path_id: u32) -> Option<DataJournal> {
+        self.0.lock().unwrap().get(&path_id).cloned()
+    }
+
+    fn remove(&self, path_id: u32) {
+        self.0.lock().unwrap().remove(&path_id);
+    }
+}
+
+/// The 1-RTT packet number space of a multipath path, see [`DataSpace::path_space`].
+pub struct PathDataSpace {
+    path_id: u32,
+    one_rtt_keys: ArcOneRttKeys,
+    journal: DataJournal,
+}
+
+impl PathDataSpace {
+    pub fn path_id(&self) -> u32 {
+        self.path_id
+    }
+}
+
+impl AsRef<DataJournal> for PathDataSpace {
+    fn as_ref(&self) -> &DataJournal {
+        &self.journal
+    }
+}
+
+impl GetEpoch for PathDataSpace {
+    fn epoch(&self) -> Epoch {
+        Epoch::Data
+    }
+}
+
+impl path::PacketSpace<OneRttHeader> for PathDataSpace {
+    type JournalFrame = GuaranteedFrame;
+
+    fn new_packet<'b, 's>(
+        &'s self,
+        header: OneRttHeader,
+        cc: &ArcCC,
+        buffer: &'b mut [u8],
+    ) -> Result<PacketWriter<'b, 's, GuaranteedFrame>, Signals> {
+        let (hpk, pk) = self.one_rtt_keys.get_local_keys().ok_or(Signals::KEYS)?;
+        let (key_phase, pk) = pk.lock_guard().get_local();
+        let (retran_timeout, expire_timeout) = cc.retransmit_and_expire_time(Epoch::Data);
+        PacketWriter::new_short(
+            header,
+            buffer,
+            DirectionalKeys {
+                header: hpk,
+                packet: PathPacketKey::bind(self.path_id, pk),
+            },
+            key_phase,
+            self.journal.as_ref(),
+            retran_timeout,
+            expire_timeout,
+        )
+    }
+}
+
 pub struct DataTracker {
+    // the journal of the packet number space of the path
     journal: DataJournal,
     crypto_stream: CryptoStream,
     streams: DataStreams,
     reliable_frames: ArcReliableFrameDeque,
+    scheduler: ArcScheduler,
 }
 
 impl Feedback for DataTracker {
@@ -584,9 +904,20 @@ impl Feedback for DataTracker {
                         may_lost_frames.extend([&frame]);
                         self.streams.may_loss_data(&frame);
                     }
+                    GuaranteedFrame::Reliable(ReliableFrame::Extension(frame))
+                        if !frame.extension_type().is_retransmittable() =>
+                    {
+                        may_lost_frames.extend([&frame]);
+                    }
+                    // 立即确认的请求已经过时，无需重传
+                    GuaranteedFrame::Reliable(frame @ ReliableFrame::ImmediateAck(_)) => {
+                        may_lost_frames.extend([&frame]);
+                    }
                     GuaranteedFrame::Reliable(frame) => {
                         may_lost_frames.extend([&frame]);
-                        self.reliable_frames.send_frame([frame]);
+                        if self.scheduler.on_frame_lost(&frame) {
+                            self.reliable_frames.send_frame([frame]);
+                        }
                     }
                 };
             }
@@ -652,7 +983,7 @@ impl DataSpace {
     pub fn recv_packet(&self, packet: CipherOneRttPacket) -> Option<ConnectionCloseFrame> {
         let (hpk, pk) = self.one_rtt_keys.remote_keys()?;
         let packet = packet
-            .decrypt_short_packet(hpk.as_ref(), &pk, |pn| {
+            .decrypt_short_packet(hpk.as_ref(), &pk, 0, |pn| {
                 self.journal.of_rcvd_packets().decode_pn(pn)
             })
             .and_then(Result::ok)?;
@@ -1,16 +1,20 @@
 use std::{
     ops::Deref,
-    sync::{Arc, atomic::Ordering::SeqCst},
+    sync::{Arc, OnceLock, atomic::Ordering::SeqCst},
 };
 
 use qbase::{
     Epoch, GetEpoch,
+    cid::ConnectionId,
     error::{Error, QuicError},
     frame::{ConnectionCloseFrame, CryptoFrame, Frame, FrameReader},
     net::tx::Signals,
     packet::{
         PacketContains,
-        header::{GetDcid, GetScid, GetType, long::InitialHeader},
+        header::{
+            GetDcid, GetScid, GetType,
+            long::{InitialHeader, RetryHeader},
+        },
         io::PacketSpace,
         keys::{ArcKeys, Keys},
     },
@@ -195,7 +199,12 @@ pub fn spawn_deliver_and_parse(
                     return Ok(());
                 };
 
-                let path = match components.get_or_try_create_path(bind_uri, link, pathway, true) {
+                let path = match components.get_or_try_create_path(
+                    bind_uri,
+                    link,
+                    pathway.clone(),
+                    true,
+                ) {
                     Ok(path) => path,
                     Err(CreatePathFailure::ConnectionClosed(..)) => {
                         packet.drop_on_conenction_closed();
@@ -205,6 +214,10 @@ pub fn spawn_deliver_and_parse(
                         packet.drop_on_interface_not_found();
                         return Ok(());
                     }
+                    Err(CreatePathFailure::NoPathId) => {
+                        packet.drop_on_path_id_unavailable();
+                        return Ok(());
+                    }
                 };
 
                 // the origin dcid doesnot own a sequences number, once we received a packet which dcid != odcid,
@@ -291,6 +304,108 @@ pub fn spawn_deliver_and_parse(
     );
 }
 
+/// The Retry packet accepted by the client.
+///
+/// The client accepts at most one Retry packet, after that it sends the Initial packets to the
+/// source connection ID of the Retry packet with the token in it, and protects them with the
+/// Initial keys derived from the new destination connection ID.
+#[derive(Clone)]
+pub struct ArcRetry {
+    suite: rustls::quic::Suite,
+    retried: Arc<OnceLock<(ConnectionId, Vec<u8>)>>,
+}
+
+impl ArcRetry {
+    pub fn new(suite: rustls::quic::Suite) -> Self {
+        Self {
+            suite,
+            retried: Arc::default(),
+        }
+    }
+
+    /// Return the destination connection ID and the token to send the Initial packets with,
+    /// if a Retry packet has been accepted.
+    pub fn get(&self) -> Option<(ConnectionId, &[u8])> {
+        self.retried
+            .get()
+            .map(|(dcid, token)| (*dcid, token.as_slice()))
+    }
+
+    fn accept(&self, retry: &RetryHeader) -> Option<Keys> {
+        self.retried
+            .set((*retry.scid(), retry.token().clone()))
+            .ok()?;
+        let keys = self
+            .suite
+            .keys(retry.scid(), rustls::Side::Client, crate::tls::QUIC_VERSION);
+        Some(keys.into())
+    }
+}
+
+pub fn spawn_deliver_and_handle_retry(
+    packets: BoundQueue<(RetryHeader, Way)>,
+    space: Arc<InitialSpace>,
+    components: &Components,
+) {
+    let components = components.clone();
+    let conn_state = components.conn_state.clone();
+    let handle_retry = async move {
+        while let Some((retry, (_, pathway, _))) = packets.recv().await {
+            // 服务器不会接受Retry包
+            let SpecificComponents::Client { retry: arc_retry } = &components.specific else {
+                continue;
+            };
+
+            // rfc9000 17.2.5.2:
+            // Clients MUST discard Retry packets that have a Retry Integrity Tag that cannot be validated.
+            // A client MUST discard a Retry packet that contains a Source Connection ID field that is
+            // identical to the Destination Connection ID field of its Initial packet.
+            let origin_dcid = components.cid_registry.origin_dcid();
+            if retry.token().is_empty()
+                || *retry.scid() == origin_dcid
+                || !retry.verify_integrity(&origin_dcid)
+            {
+                tracing::debug!(%pathway, "Dropped an invalid Retry packet");
+                continue;
+            }
+
+            let Ok(mut parameters) = components.parameters.lock_guard() else {
+                return;
+            };
+            // rfc9000 17.2.5.2:
+            // After the client has received and processed an Initial or Retry packet from the server,
+            // it MUST discard any subsequent Retry packets.
+            if parameters.initial_scid_from_peer().is_some() {
+                continue;
+            }
+            let Some(keys) = arc_retry.accept(&retry) else {
+                continue;
+            };
+            parameters.retry_scid_from_server_need_equal(*retry.scid());
+            drop(parameters);
+
+            space.keys.replace_keys(keys);
+            // 服务器丢弃了此前的Initial包和0-RTT包，重新发送它们的数据
+            let paths = components.paths.iter().collect::<Vec<_>>();
+            for path in paths {
+                path.cc().retry();
+            }
+            tracing::debug!(%pathway, scid = format!("{:x}", retry.scid()), "Accepted the Retry packet");
+        }
+    };
+
+    tokio::spawn(
+        async move {
+            tokio::select! {
+                _ = handle_retry => {},
+                _ = conn_state.terminated() => {}
+            };
+        }
+        .instrument_in_current()
+        .in_current_span(),
+    );
+}
+
 pub struct InitialTracker {
     journal: InitialJournal,
     crypto_stream: CryptoStream,
@@ -238,6 +238,7 @@ impl ServerTlsSession {
         let host = self.server_name.as_ref().ok_or_else(|| {
             QuicError::with_default_fty(ErrorKind::ConnectionRefused, "Missing SNI in client hello")
         })?;
+        qevent::telemetry::Span::current().on_server_name(host);
 
         if !self
             .client_auther
@@ -0,0 +1,823 @@
+//! Offline analysis of qlog files.
+//!
+//! [`Analyzer`] reads qlog JSON-SEQ files written by either the legacy (draft-03) exporters or the
+//! main schema exporters, deserializes the events into the [`legacy`] or [`quic`] event types, and
+//! folds them into per-connection [`ConnectionSummary`]s.
+//!
+//! Events this module does not understand, such as events of other protocols, are counted and
+//! skipped rather than failing the whole file.
+//!
+//! [`legacy`]: crate::legacy
+//! [`quic`]: crate::quic
+mod summary;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+pub use summary::{
+    CloseSummary, ConnectionSummary, CwndSample, RttSample, StreamSummary, write_csv, write_json,
+};
+
+use crate::{
+    EvnetData, QlogFileSeq, legacy,
+    quic::{
+        QuicFrame,
+        connectivity::{BaseConnectionStates, ConnectionState, GranularConnectionStates},
+        recovery::{EventType, PacketLostTrigger, TimerType},
+        transport::PacketSentTrigger,
+    },
+};
+
+/// The schema of a qlog file.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Schema {
+    /// qlog draft-03, written by [`LegacySeqLogger`] and [`legacy::exporter::IoExpoter`].
+    ///
+    /// [`LegacySeqLogger`]: crate::telemetry::handy::LegacySeqLogger
+    /// [`legacy::exporter::IoExpoter`]: crate::legacy::exporter::IoExpoter
+    Legacy,
+    /// The qlog main schema, written by [`SeqLogger`] and [`exporter::IoExporter`].
+    ///
+    /// [`SeqLogger`]: crate::telemetry::handy::SeqLogger
+    /// [`exporter::IoExporter`]: crate::exporter::IoExporter
+    Main,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnalyzeError {
+    #[error("failed to read qlog file: {0}")]
+    Io(#[from] io::Error),
+    #[error("record {record} is not valid JSON: {source}")]
+    Json {
+        record: usize,
+        source: serde_json::Error,
+    },
+    #[error("qlog file is empty")]
+    MissingHeader,
+    #[error("not a JSON-SEQ qlog file of a supported schema")]
+    UnsupportedSchema,
+}
+
+/// The time format of the events in a file, only the delta format needs special handling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeFormat {
+    Absolute,
+    Delta,
+}
+
+/// The header of a JSON-SEQ qlog file.
+///
+/// The header is read loosely from the raw JSON, the typed headers insist on fields which are not
+/// required by the specifications.
+#[derive(Debug, Clone)]
+struct Header {
+    schema: Schema,
+    time_format: TimeFormat,
+    group_id: Option<String>,
+    vantage_point: Option<String>,
+}
+
+impl Header {
+    fn parse(header: &Value) -> Result<Self, AnalyzeError> {
+        let schema =
+            if header.get("file_schema").and_then(Value::as_str) == Some(QlogFileSeq::SCHEMA) {
+                Schema::Main
+            } else if header.get("qlog_version").is_some() {
+                Schema::Legacy
+            } else {
+                return Err(AnalyzeError::UnsupportedSchema);
+            };
+
+        let trace = header.get("trace");
+        let common_fields = trace.and_then(|trace| trace.get("common_fields"));
+        let time_format = match common_fields
+            .and_then(|fields| fields.get("time_format"))
+            .and_then(Value::as_str)
+        {
+            Some("delta" | "relative_to_previous_event") => TimeFormat::Delta,
+            _ => TimeFormat::Absolute,
+        };
+        let group_id = common_fields
+            .and_then(|fields| fields.get("group_id"))
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        let vantage_point = trace
+            .and_then(|trace| trace.get("vantage_point"))
+            .and_then(|vantage_point| vantage_point.get("type"))
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+
+        Ok(Self {
+            schema,
+            time_format,
+            group_id,
+            vantage_point,
+        })
+    }
+}
+
+/// A stream frame sent or received, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct StreamChunk {
+    stream_id: u64,
+    offset: u64,
+    length: u64,
+}
+
+/// The metrics of a recovery metrics updated event which are analyzed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Metrics {
+    latest_rtt: Option<f32>,
+    smoothed_rtt: Option<f32>,
+    min_rtt: Option<f32>,
+    pto_count: Option<u16>,
+    congestion_window: Option<u64>,
+    bytes_in_flight: Option<u64>,
+}
+
+/// What the analyzer observes from an event, shared by both schemas.
+#[derive(Debug, Clone, PartialEq)]
+enum Observation {
+    Started,
+    HandshakeDone,
+    PacketSent {
+        retransmission: bool,
+        streams: Vec<StreamChunk>,
+    },
+    PacketReceived {
+        streams: Vec<StreamChunk>,
+    },
+    PacketLost {
+        pto: bool,
+    },
+    PacketsAcked,
+    PtoExpired,
+    Metrics(Metrics),
+    Closed(CloseSummary),
+    Ignored,
+}
+
+fn stream_chunks(frames: &Option<Vec<QuicFrame>>) -> Vec<StreamChunk> {
+    frames
+        .iter()
+        .flatten()
+        .filter_map(|frame| match frame {
+            QuicFrame::Stream {
+                stream_id,
+                offset,
+                length,
+                ..
+            } => Some(StreamChunk {
+                stream_id: *stream_id,
+                offset: *offset,
+                length: *length,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+fn legacy_stream_chunks(frames: &Option<Vec<legacy::quic::QuicFrame>>) -> Vec<StreamChunk> {
+    frames
+        .iter()
+        .flatten()
+        .filter_map(|frame| match frame {
+            legacy::quic::QuicFrame::Stream {
+                stream_id,
+                offset,
+                length,
+                ..
+            } => Some(StreamChunk {
+                stream_id: *stream_id,
+                offset: *offset,
+                length: *length,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Render a plain enum or code as its qlog representation, for example `"idle_timeout"`.
+pub(crate) fn label<T: Serialize>(value: &T) -> Option<String> {
+    match serde_json::to_value(value).ok()? {
+        Value::String(label) => Some(label),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+impl From<&EvnetData> for Observation {
+    fn from(data: &EvnetData) -> Self {
+        match data {
+            EvnetData::ConnectionStarted(_) => Observation::Started,
+            EvnetData::ConnectionStateUpdated(updated) => match updated.new() {
+                ConnectionState::Base(BaseConnectionStates::Attempted) => Observation::Started,
+                ConnectionState::Base(BaseConnectionStates::HandshakeComplete)
+                | ConnectionState::Granular(GranularConnectionStates::HandshakeConfirmed) => {
+                    Observation::HandshakeDone
+                }
+                _ => Observation::Ignored,
+            },
+            EvnetData::PacketSent(sent) => Observation::PacketSent {
+                retransmission: matches!(
+                    sent.trigger(),
+                    Some(
+                        PacketSentTrigger::RetransmitReordered
+                            | PacketSentTrigger::RetransmitTimeout
+                            | PacketSentTrigger::PtoProbe
+                            | PacketSentTrigger::RetransmitCrypto
+                    )
+                ),
+                streams: stream_chunks(sent.frames()),
+            },
+            EvnetData::PacketReceived(received) => Observation::PacketReceived {
+                streams: stream_chunks(received.frames()),
+            },
+            EvnetData::PacketLost(lost) => Observation::PacketLost {
+                pto: *lost.trigger() == Some(PacketLostTrigger::PtoExpired),
+            },
+            EvnetData::PacketsAcked(_) => Observation::PacketsAcked,
+            EvnetData::LossTimerUpdated(timer)
+                if *timer.timer_type() == Some(TimerType::Pto)
+                    && *timer.event_type() == EventType::Expired =>
+            {
+                Observation::PtoExpired
+            }
+            EvnetData::RecoveryMetricsUpdated(metrics) => Observation::Metrics(Metrics {
+                latest_rtt: *metrics.latest_rtt(),
+                smoothed_rtt: *metrics.smoothed_rtt(),
+                min_rtt: *metrics.min_rtt(),
+                pto_count: *metrics.pto_count(),
+                congestion_window: *metrics.congestion_window(),
+                bytes_in_flight: *metrics.bytes_in_flight(),
+            }),
+            EvnetData::ConnectionClosed(closed) => Observation::Closed(CloseSummary {
+                owner: closed.owner().as_ref().and_then(label),
+                code: closed
+                    .connection_code()
+                    .as_ref()
+                    .and_then(label)
+                    .or_else(|| closed.application_code().as_ref().and_then(label)),
+                reason: closed.reason().clone(),
+                trigger: closed.trigger().as_ref().and_then(label),
+            }),
+            _ => Observation::Ignored,
+        }
+    }
+}
+
+impl From<&legacy::EventData> for Observation {
+    fn from(data: &legacy::EventData) -> Self {
+        use legacy::{
+            EventData,
+            quic::{
+                ConnectionState, LossTimerEventType, LossTimerType, RecoveryPacketLostTrigger,
+                TransportPacketSentTrigger,
+            },
+        };
+
+        match data {
+            EventData::ConnectionStarted(_) => Observation::Started,
+            EventData::ConnectionStateUpdated(updated) => match updated.new() {
+                ConnectionState::Attempted => Observation::Started,
+                ConnectionState::HandshakeComplete | ConnectionState::HandshakeConfirmed => {
+                    Observation::HandshakeDone
+                }
+                _ => Observation::Ignored,
+            },
+            EventData::PacketSent(sent) => Observation::PacketSent {
+                retransmission: matches!(
+                    sent.trigger(),
+                    Some(
+                        TransportPacketSentTrigger::RetransmitReordered
+                            | TransportPacketSentTrigger::RetransmitTimeout
+                            | TransportPacketSentTrigger::PtoProbe
+                            | TransportPacketSentTrigger::RetransmitCrypto
+                    )
+                ),
+                streams: legacy_stream_chunks(sent.frames()),
+            },
+            EventData::PacketReceived(received) => Observation::PacketReceived {
+                streams: legacy_stream_chunks(received.frames()),
+            },
+            EventData::PacketLost(lost) => Observation::PacketLost {
+                pto: *lost.trigger() == Some(RecoveryPacketLostTrigger::PtoExpired),
+            },
+            EventData::PacketsAcked(_) => Observation::PacketsAcked,
+            EventData::LossTimerUpdated(timer)
+                if *timer.timer_type() == Some(LossTimerType::Pto)
+                    && *timer.event_type() == LossTimerEventType::Expired =>
+            {
+                Observation::PtoExpired
+            }
+            EventData::MetricsUpdated(metrics) => Observation::Metrics(Metrics {
+                latest_rtt: *metrics.latest_rtt(),
+                smoothed_rtt: *metrics.smoothed_rtt(),
+                min_rtt: *metrics.min_rtt(),
+                pto_count: *metrics.pto_count(),
+                congestion_window: *metrics.congestion_window(),
+                bytes_in_flight: *metrics.bytes_in_flight(),
+            }),
+            EventData::ConnectionClosed(closed) => Observation::Closed(CloseSummary {
+                owner: closed.owner().as_ref().and_then(label),
+                code: closed
+                    .connection_code()
+                    .as_ref()
+                    .and_then(label)
+                    .or_else(|| closed.application_code().as_ref().and_then(label)),
+                reason: closed.reason().clone(),
+                trigger: closed.trigger().as_ref().and_then(label),
+            }),
+            _ => Observation::Ignored,
+        }
+    }
+}
+
+/// The progress of a stream, in bytes.
+#[derive(Debug, Default, Clone)]
+struct StreamProgress {
+    sent_end: u64,
+    received_end: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    first_time: Option<f64>,
+    last_time: f64,
+}
+
+impl StreamProgress {
+    fn touch(&mut self, time: f64) {
+        self.first_time.get_or_insert(time);
+        self.last_time = time;
+    }
+
+    /// Returns the bytes of the chunk which were never sent before.
+    fn on_sent(&mut self, time: f64, chunk: StreamChunk) -> u64 {
+        self.touch(time);
+        let end = chunk.offset + chunk.length;
+        let new_bytes = end.saturating_sub(chunk.offset.max(self.sent_end));
+        self.sent_end = self.sent_end.max(end);
+        self.bytes_sent += new_bytes;
+        new_bytes
+    }
+
+    /// Returns the bytes of the chunk which were never received before.
+    fn on_received(&mut self, time: f64, chunk: StreamChunk) -> u64 {
+        self.touch(time);
+        let end = chunk.offset + chunk.length;
+        let new_bytes = end.saturating_sub(chunk.offset.max(self.received_end));
+        self.received_end = self.received_end.max(end);
+        self.bytes_received += new_bytes;
+        new_bytes
+    }
+}
+
+/// The state of a connection being analyzed.
+#[derive(Debug, Default, Clone)]
+struct Connection {
+    first_time: Option<f64>,
+    last_time: f64,
+    started_at: Option<f64>,
+    handshake_done_at: Option<f64>,
+    packets_sent: u64,
+    packets_received: u64,
+    packets_lost: u64,
+    packets_retransmitted: u64,
+    stream_bytes_sent: u64,
+    stream_bytes_retransmitted: u64,
+    stream_bytes_received: u64,
+    streams: BTreeMap<u64, StreamProgress>,
+    in_pto_episode: bool,
+    pto_episodes: u64,
+    max_pto_count: u16,
+    rtt: Vec<RttSample>,
+    congestion_window: Vec<CwndSample>,
+    close: Option<CloseSummary>,
+    skipped_events: u64,
+}
+
+impl Connection {
+    fn on_pto(&mut self) {
+        if !self.in_pto_episode {
+            self.in_pto_episode = true;
+            self.pto_episodes += 1;
+        }
+    }
+
+    fn observe(&mut self, time: f64, observation: Observation) {
+        let start = *self.first_time.get_or_insert(time);
+        self.last_time = self.last_time.max(time);
+        let offset = time - start;
+
+        match observation {
+            Observation::Started => {
+                self.started_at.get_or_insert(time);
+            }
+            Observation::HandshakeDone => {
+                self.handshake_done_at.get_or_insert(time);
+            }
+            Observation::PacketSent {
+                retransmission,
+                streams,
+            } => {
+                self.packets_sent += 1;
+                let mut retransmitted_bytes = 0;
+                for chunk in streams {
+                    let progress = self.streams.entry(chunk.stream_id).or_default();
+                    let new_bytes = progress.on_sent(time, chunk);
+                    self.stream_bytes_sent += chunk.length;
+                    retransmitted_bytes += chunk.length - new_bytes;
+                }
+                self.stream_bytes_retransmitted += retransmitted_bytes;
+                if retransmission || retransmitted_bytes > 0 {
+                    self.packets_retransmitted += 1;
+                }
+            }
+            Observation::PacketReceived { streams } => {
+                self.packets_received += 1;
+                for chunk in streams {
+                    let progress = self.streams.entry(chunk.stream_id).or_default();
+                    self.stream_bytes_received += progress.on_received(time, chunk);
+                }
+            }
+            Observation::PacketLost { pto } => {
+                self.packets_lost += 1;
+                if pto {
+                    self.on_pto();
+                }
+            }
+            Observation::PacketsAcked => self.in_pto_episode = false,
+            Observation::PtoExpired => self.on_pto(),
+            Observation::Metrics(metrics) => {
+                if let Some(pto_count) = metrics.pto_count {
+                    self.max_pto_count = self.max_pto_count.max(pto_count);
+                    match pto_count {
+                        0 => self.in_pto_episode = false,
+                        _ => self.on_pto(),
+                    }
+                }
+                if metrics.latest_rtt.is_some()
+                    || metrics.smoothed_rtt.is_some()
+                    || metrics.min_rtt.is_some()
+                {
+                    self.rtt.push(RttSample {
+                        time: offset,
+                        latest_rtt: metrics.latest_rtt,
+                        smoothed_rtt: metrics.smoothed_rtt,
+                        min_rtt: metrics.min_rtt,
+                    });
+                }
+                if metrics.congestion_window.is_some() || metrics.bytes_in_flight.is_some() {
+                    self.congestion_window.push(CwndSample {
+                        time: offset,
+                        congestion_window: metrics.congestion_window,
+                        bytes_in_flight: metrics.bytes_in_flight,
+                    });
+                }
+            }
+            Observation::Closed(close) => self.close = Some(close),
+            Observation::Ignored => {}
+        }
+    }
+}
+
+/// Fold qlog files into per-connection summaries.
+///
+/// Connections are identified by their group id and the vantage point of the trace, so the client
+/// and the server side of a connection are summarized separately.
+///
+/// # Example
+///
+/// ``` no_run
+/// use qevent::analyzer::{Analyzer, write_csv};
+///
+/// let mut analyzer = Analyzer::new();
+/// analyzer.feed_file("logs/8f1a2b3c_client.sqlog")?;
+/// write_csv(&analyzer.summaries(), std::io::stdout())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Analyzer {
+    connections: BTreeMap<(String, Option<String>), Connection>,
+}
+
+impl Analyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a qlog file, the file stem is used as the group id if the events do not have one.
+    pub fn feed_file(&mut self, path: impl AsRef<Path>) -> Result<Schema, AnalyzeError> {
+        let path = path.as_ref();
+        let fallback_group = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        self.feed(fs::File::open(path)?, &fallback_group)
+    }
+
+    /// Feed a JSON-SEQ ([RFC7464]) qlog stream.
+    ///
+    /// Events without a group id, neither in the event nor in the common fields of the trace, are
+    /// assigned to `fallback_group`. Records which are not separated by the record separator are
+    /// accepted too, as long as each of them is on its own line.
+    ///
+    /// [RFC7464]: https://www.rfc-editor.org/rfc/rfc7464
+    pub fn feed(
+        &mut self,
+        mut reader: impl Read,
+        fallback_group: &str,
+    ) -> Result<Schema, AnalyzeError> {
+        const RS: char = '\x1e';
+
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut records = text
+            .split([RS, '\n'])
+            .map(str::trim)
+            .filter(|record| !record.is_empty())
+            .enumerate()
+            .map(|(record, json)| {
+                serde_json::from_str::<Value>(json)
+                    .map_err(|source| AnalyzeError::Json { record, source })
+            });
+
+        let header = Header::parse(&records.next().ok_or(AnalyzeError::MissingHeader)??)?;
+        let default_group = header
+            .group_id
+            .clone()
+            .unwrap_or_else(|| fallback_group.to_owned());
+
+        let mut previous_time = 0.0;
+        for record in records {
+            let record = record?;
+            // read before deserializing, so that the skipped events are counted to the right connection
+            let group_id = record
+                .get("group_id")
+                .and_then(Value::as_str)
+                .map_or_else(|| default_group.clone(), str::to_owned);
+            let observation = match header.schema {
+                Schema::Main => serde_json::from_value::<crate::Event>(record)
+                    .ok()
+                    .map(|event| (*event.time(), Observation::from(event.data()))),
+                Schema::Legacy => serde_json::from_value::<legacy::Event>(record)
+                    .ok()
+                    .map(|event| (*event.time(), Observation::from(event.data()))),
+            };
+
+            let connection = self
+                .connections
+                .entry((group_id, header.vantage_point.clone()))
+                .or_default();
+            match observation {
+                Some((time, observation)) => {
+                    let time = match header.time_format {
+                        TimeFormat::Absolute => time,
+                        TimeFormat::Delta => previous_time + time,
+                    };
+                    previous_time = time;
+                    connection.observe(time, observation);
+                }
+                None => connection.skipped_events += 1,
+            }
+        }
+
+        Ok(header.schema)
+    }
+
+    /// The summaries of all the connections fed so far, ordered by group id and vantage point.
+    pub fn summaries(&self) -> Vec<ConnectionSummary> {
+        self.connections
+            .iter()
+            .map(|((group_id, vantage_point), connection)| {
+                ConnectionSummary::new(group_id, vantage_point.as_deref(), connection)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use qbase::cid::ConnectionId;
+
+    use super::*;
+    use crate::{
+        GroupID, TraceSeq, VantagePoint, VantagePointType,
+        quic::{
+            PacketHeader, PacketType,
+            connectivity::{ConnectionClosed, ConnectionStateUpdated},
+            recovery::{PacketLost, RecoveryMetricsUpdated},
+            transport::{PacketReceived, PacketSent, PacketsAcked},
+        },
+    };
+
+    fn main_schema_file(events: Vec<crate::Event>) -> String {
+        let header = crate::build!(QlogFileSeq {
+            log_file: crate::build!(crate::LogFile {
+                file_schema: QlogFileSeq::SCHEMA,
+                serialization_format: QlogFileSeq::SERIALIZATION_FORMAT,
+            }),
+            trace: crate::build!(TraceSeq {
+                vantage_point: crate::build!(VantagePoint {
+                    r#type: VantagePointType::Client,
+                }),
+            }),
+        });
+        std::iter::once(serde_json::to_string(&header).unwrap())
+            .chain(events.iter().map(|e| serde_json::to_string(e).unwrap()))
+            .map(|record| format!("\x1e{record}\n"))
+            .collect()
+    }
+
+    fn event(time: f64, data: impl Into<EvnetData>) -> crate::Event {
+        let group_id = GroupID::from(ConnectionId::from_slice(&[0xab, 0xcd]));
+        crate::build!(crate::Event {
+            time: time,
+            data: data.into(),
+            group_id: group_id,
+        })
+    }
+
+    fn one_rtt(pn: u64) -> PacketHeader {
+        crate::build!(PacketHeader {
+            packet_type: PacketType::OneRTT,
+            packet_number: pn,
+        })
+    }
+
+    fn stream_frame(offset: u64, length: u64) -> QuicFrame {
+        QuicFrame::Stream {
+            stream_id: 0,
+            offset,
+            length,
+            fin: false,
+            raw: None,
+        }
+    }
+
+    fn sent(pn: u64, offset: u64, length: u64) -> PacketSent {
+        let header = one_rtt(pn);
+        crate::build!(PacketSent {
+            header: header,
+            frames: vec![stream_frame(offset, length)],
+        })
+    }
+
+    #[test]
+    fn summarize_main_schema() {
+        let lost_header = one_rtt(1);
+        let received_header = one_rtt(0);
+        let events = vec![
+            event(
+                1000.0,
+                crate::build!(ConnectionStateUpdated {
+                    new: BaseConnectionStates::Attempted,
+                }),
+            ),
+            event(
+                1030.0,
+                crate::build!(ConnectionStateUpdated {
+                    new: GranularConnectionStates::HandshakeConfirmed,
+                }),
+            ),
+            event(1040.0, sent(0, 0, 1000)),
+            event(1041.0, sent(1, 1000, 1000)),
+            event(
+                1060.0,
+                crate::build!(PacketLost {
+                    header: lost_header,
+                    trigger: PacketLostTrigger::PtoExpired,
+                }),
+            ),
+            event(
+                1060.0,
+                crate::build!(RecoveryMetricsUpdated {
+                    smoothed_rtt: 20.0,
+                    latest_rtt: 20.0,
+                    min_rtt: 20.0,
+                    pto_count: 1_u16,
+                }),
+            ),
+            event(1061.0, sent(2, 1000, 1000)),
+            event(
+                1080.0,
+                crate::build!(PacketsAcked {
+                    packet_nubers: vec![0, 2],
+                }),
+            ),
+            event(
+                1080.0,
+                crate::build!(RecoveryMetricsUpdated {
+                    congestion_window: 12000_u64,
+                    bytes_in_flight: 0_u64,
+                    pto_count: 0_u16,
+                }),
+            ),
+            event(
+                1090.0,
+                crate::build!(PacketReceived {
+                    header: received_header,
+                    frames: vec![stream_frame(0, 500)],
+                }),
+            ),
+            event(
+                1100.0,
+                crate::build!(ConnectionClosed {
+                    owner: crate::quic::Owner::Local,
+                    reason: "done",
+                    trigger: crate::quic::connectivity::ConnectionCloseTrigger::Application,
+                }),
+            ),
+        ];
+
+        let mut analyzer = Analyzer::new();
+        let schema = analyzer
+            .feed(main_schema_file(events).as_bytes(), "fallback")
+            .unwrap();
+        assert_eq!(schema, Schema::Main);
+
+        let summaries = analyzer.summaries();
+        assert_eq!(summaries.len(), 1);
+        let summary = &summaries[0];
+        assert_eq!(summary.group_id, "abcd");
+        assert_eq!(summary.vantage_point.as_deref(), Some("client"));
+        assert_eq!(summary.duration_ms, 100.0);
+        assert_eq!(summary.handshake_latency_ms, Some(30.0));
+        assert_eq!(summary.packets_sent, 3);
+        assert_eq!(summary.packets_received, 1);
+        assert_eq!(summary.packets_lost, 1);
+        assert_eq!(summary.packets_retransmitted, 1);
+        assert_eq!(summary.stream_bytes_sent, 3000);
+        assert_eq!(summary.stream_bytes_retransmitted, 1000);
+        assert_eq!(summary.stream_bytes_received, 500);
+        assert_eq!(summary.pto_episodes, 1);
+        assert_eq!(summary.max_pto_count, 1);
+        assert_eq!(summary.min_rtt_ms, Some(20.0));
+        assert_eq!(summary.max_congestion_window, Some(12000));
+        assert_eq!(summary.rtt.len(), 1);
+        assert_eq!(summary.congestion_window.len(), 1);
+        assert_eq!(summary.streams.len(), 1);
+        assert_eq!(summary.streams[0].bytes_sent, 2000);
+        let close = summary.close.as_ref().unwrap();
+        assert_eq!(close.owner.as_deref(), Some("local"));
+        assert_eq!(close.trigger.as_deref(), Some("application"));
+        assert_eq!(close.reason.as_deref(), Some("done"));
+
+        let mut csv = Vec::new();
+        write_csv(&summaries, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("group_id,vantage_point,"));
+        assert!(lines.next().unwrap().starts_with("abcd,client,"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn summarize_legacy_schema() {
+        let file = concat!(
+            "\x1e{\"qlog_version\":\"0.3\",\"qlog_format\":\"JSON-SEQ\",",
+            "\"trace\":{\"vantage_point\":{\"type\":\"server\"}}}\n",
+            "\x1e{\"time\":10.0,\"name\":\"connectivity:connection_state_updated\",",
+            "\"data\":{\"new\":\"attempted\"}}\n",
+            "\x1e{\"time\":35.5,\"name\":\"connectivity:connection_state_updated\",",
+            "\"data\":{\"new\":\"handshake_confirmed\"}}\n",
+            "\x1e{\"time\":36.0,\"name\":\"http:frame_created\",\"data\":{}}\n",
+            "\x1e{\"time\":40.0,\"name\":\"connectivity:connection_closed\",",
+            "\"data\":{\"owner\":\"remote\",\"connection_code\":0,\"trigger\":\"error\"}}\n",
+        );
+
+        let mut analyzer = Analyzer::new();
+        let schema = analyzer.feed(file.as_bytes(), "conn").unwrap();
+        assert_eq!(schema, Schema::Legacy);
+
+        let summaries = analyzer.summaries();
+        assert_eq!(summaries.len(), 1);
+        let summary = &summaries[0];
+        assert_eq!(summary.group_id, "conn");
+        assert_eq!(summary.vantage_point.as_deref(), Some("server"));
+        assert_eq!(summary.handshake_latency_ms, Some(25.5));
+        assert_eq!(summary.skipped_events, 1);
+        let close = summary.close.as_ref().unwrap();
+        assert_eq!(close.owner.as_deref(), Some("remote"));
+        assert_eq!(close.code.as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn reject_unknown_file() {
+        let mut analyzer = Analyzer::new();
+        assert!(matches!(
+            analyzer.feed("".as_bytes(), "empty"),
+            Err(AnalyzeError::MissingHeader)
+        ));
+        assert!(matches!(
+            analyzer.feed("{\"foo\":1}\n".as_bytes(), "foo"),
+            Err(AnalyzeError::UnsupportedSchema)
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use super::Connection;
+
+/// How and why a connection was closed.
+#[derive(Debug, Default, Clone, Serialize, PartialEq, Eq)]
+pub struct CloseSummary {
+    /// `local` or `remote`.
+    pub owner: Option<String>,
+    /// The transport error or the application error code.
+    pub code: Option<String>,
+    pub reason: Option<String>,
+    pub trigger: Option<String>,
+}
+
+/// A sample of the RTT estimations, in milliseconds.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct RttSample {
+    /// Milliseconds since the first event of the connection.
+    pub time: f64,
+    pub latest_rtt: Option<f32>,
+    pub smoothed_rtt: Option<f32>,
+    pub min_rtt: Option<f32>,
+}
+
+/// A sample of the congestion controller, in bytes.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct CwndSample {
+    /// Milliseconds since the first event of the connection.
+    pub time: f64,
+    pub congestion_window: Option<u64>,
+    pub bytes_in_flight: Option<u64>,
+}
+
+/// The stream data transferred on a stream.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct StreamSummary {
+    pub stream_id: u64,
+    /// Stream bytes sent, retransmissions excluded.
+    pub bytes_sent: u64,
+    /// Stream bytes received, duplicates excluded.
+    pub bytes_received: u64,
+    /// Milliseconds between the first and the last frame of the stream.
+    pub duration_ms: f64,
+    /// Bits per second of both directions over `duration_ms`.
+    pub throughput_bps: Option<f64>,
+}
+
+/// The summary of a connection, from the perspective of a single vantage point.
+///
+/// Ratios are `0` when there is nothing to divide, throughputs are `None` when the duration is `0`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ConnectionSummary {
+    pub group_id: String,
+    pub vantage_point: Option<String>,
+    /// The time of the first event, as written in the file.
+    pub start_time: f64,
+    /// Milliseconds between the first and the last event.
+    pub duration_ms: f64,
+    /// Milliseconds from the connection attempt until the handshake is complete or confirmed.
+    pub handshake_latency_ms: Option<f64>,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub packets_lost: u64,
+    /// `packets_lost / packets_sent`.
+    pub loss_ratio: f64,
+    /// Packets sent with a retransmission trigger or carrying stream data sent before.
+    pub packets_retransmitted: u64,
+    /// Stream bytes put on the wire, retransmissions included.
+    pub stream_bytes_sent: u64,
+    pub stream_bytes_retransmitted: u64,
+    /// `stream_bytes_retransmitted / stream_bytes_sent`.
+    pub retransmission_ratio: f64,
+    /// Stream bytes received, duplicates excluded.
+    pub stream_bytes_received: u64,
+    /// Bits per second of the unique stream bytes sent over `duration_ms`.
+    pub send_throughput_bps: Option<f64>,
+    /// Bits per second of the unique stream bytes received over `duration_ms`.
+    pub recv_throughput_bps: Option<f64>,
+    /// Runs of consecutive PTOs, each ended by an acknowledgment.
+    pub pto_episodes: u64,
+    pub max_pto_count: u16,
+    pub min_rtt_ms: Option<f32>,
+    /// The last smoothed RTT.
+    pub smoothed_rtt_ms: Option<f32>,
+    pub max_congestion_window: Option<u64>,
+    pub close: Option<CloseSummary>,
+    /// Events which could not be deserialized into the qevent types.
+    pub skipped_events: u64,
+    pub rtt: Vec<RttSample>,
+    pub congestion_window: Vec<CwndSample>,
+    pub streams: Vec<StreamSummary>,
+}
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    match denominator {
+        0 => 0.0,
+        denominator => numerator as f64 / denominator as f64,
+    }
+}
+
+fn throughput(bytes: u64, duration_ms: f64) -> Option<f64> {
+    (duration_ms > 0.0).then(|| bytes as f64 * 8.0 * 1000.0 / duration_ms)
+}
+
+impl ConnectionSummary {
+    pub(super) fn new(
+        group_id: &str,
+        vantage_point: Option<&str>,
+        connection: &Connection,
+    ) -> Self {
+        let start_time = connection.first_time.unwrap_or_default();
+        let duration_ms = connection.last_time - start_time;
+        let unique_bytes_sent =
+            connection.stream_bytes_sent - connection.stream_bytes_retransmitted;
+
+        let streams = connection
+            .streams
+            .iter()
+            .map(|(&stream_id, progress)| {
+                let duration_ms =
+                    progress.last_time - progress.first_time.unwrap_or(progress.last_time);
+                StreamSummary {
+                    stream_id,
+                    bytes_sent: progress.bytes_sent,
+                    bytes_received: progress.bytes_received,
+                    duration_ms,
+                    throughput_bps: throughput(
+                        progress.bytes_sent + progress.bytes_received,
+                        duration_ms,
+                    ),
+                }
+            })
+            .collect();
+
+        Self {
+            group_id: group_id.to_owned(),
+            vantage_point: vantage_point.map(str::to_owned),
+            start_time,
+            duration_ms,
+            handshake_latency_ms: connection
+                .handshake_done_at
+                .map(|done| done - connection.started_at.unwrap_or(start_time)),
+            packets_sent: connection.packets_sent,
+            packets_received: connection.packets_received,
+            packets_lost: connection.packets_lost,
+            loss_ratio: ratio(connection.packets_lost, connection.packets_sent),
+            packets_retransmitted: connection.packets_retransmitted,
+            stream_bytes_sent: connection.stream_bytes_sent,
+            stream_bytes_retransmitted: connection.stream_bytes_retransmitted,
+            retransmission_ratio: ratio(
+                connection.stream_bytes_retransmitted,
+                connection.stream_bytes_sent,
+            ),
+            stream_bytes_received: connection.stream_bytes_received,
+            send_throughput_bps: throughput(unique_bytes_sent, duration_ms),
+            recv_throughput_bps: throughput(connection.stream_bytes_received, duration_ms),
+            pto_episodes: connection.pto_episodes,
+            max_pto_count: connection.max_pto_count,
+            min_rtt_ms: connection
+                .rtt
+                .iter()
+                .filter_map(|sample| sample.min_rtt)
+                .reduce(f32::min),
+            smoothed_rtt_ms: connection
+                .rtt
+                .iter()
+                .rev()
+                .find_map(|sample| sample.smoothed_rtt),
+            max_congestion_window: connection
+                .congestion_window
+                .iter()
+                .filter_map(|sample| sample.congestion_window)
+                .max(),
+            close: connection.close.clone(),
+            skipped_events: connection.skipped_events,
+            rtt: connection.rtt.clone(),
+            congestion_window: connection.congestion_window.clone(),
+            streams,
+        }
+    }
+}
+
+/// Write the summaries as a JSON array, timelines and streams included.
+pub fn write_json(summaries: &[ConnectionSummary], output: impl Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(output, summaries)?;
+    Ok(())
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "group_id",
+    "vantage_point",
+    "start_time",
+    "duration_ms",
+    "handshake_latency_ms",
+    "packets_sent",
+    "packets_received",
+    "packets_lost",
+    "loss_ratio",
+    "packets_retransmitted",
+    "stream_bytes_sent",
+    "stream_bytes_retransmitted",
+    "retransmission_ratio",
+    "stream_bytes_received",
+    "send_throughput_bps",
+    "recv_throughput_bps",
+    "pto_episodes",
+    "max_pto_count",
+    "min_rtt_ms",
+    "smoothed_rtt_ms",
+    "max_congestion_window",
+    "close_owner",
+    "close_code",
+    "close_reason",
+    "close_trigger",
+    "skipped_events",
+];
+
+/// Quote a CSV field if needed, see [RFC4180].
+///
+/// [RFC4180]: https://www.rfc-editor.org/rfc/rfc4180
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Write the summaries as CSV, one row per connection.
+///
+/// Timelines and streams have no fixed columns, so they are only available in [`write_json`].
+/// Empty fields mean the value is unknown.
+pub fn write_csv(summaries: &[ConnectionSummary], mut output: impl Write) -> io::Result<()> {
+    writeln!(output, "{}", CSV_COLUMNS.join(","))?;
+    for summary in summaries {
+        let close = summary.close.clone().unwrap_or_default();
+        let fields = [
+            summary.group_id.clone(),
+            optional(summary.vantage_point.as_ref()),
+            summary.start_time.to_string(),
+            summary.duration_ms.to_string(),
+            optional(summary.handshake_latency_ms),
+            summary.packets_sent.to_string(),
+            summary.packets_received.to_string(),
+            summary.packets_lost.to_string(),
+            summary.loss_ratio.to_string(),
+            summary.packets_retransmitted.to_string(),
+            summary.stream_bytes_sent.to_string(),
+            summary.stream_bytes_retransmitted.to_string(),
+            summary.retransmission_ratio.to_string(),
+            summary.stream_bytes_received.to_string(),
+            optional(summary.send_throughput_bps),
+            optional(summary.recv_throughput_bps),
+            summary.pto_episodes.to_string(),
+            summary.max_pto_count.to_string(),
+            optional(summary.min_rtt_ms),
+            optional(summary.smoothed_rtt_ms),
+            optional(summary.max_congestion_window),
+            optional(close.owner),
+            optional(close.code),
+            optional(close.reason),
+            optional(close.trigger),
+            summary.skipped_events.to_string(),
+        ];
+        debug_assert_eq!(fields.len(), CSV_COLUMNS.len());
+        let row = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>();
+        writeln!(output, "{}", row.join(","))?;
+    }
+    Ok(())
+}
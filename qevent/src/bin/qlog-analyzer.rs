@@ -0,0 +1,98 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, ValueEnum};
+use qevent::analyzer::{Analyzer, write_csv, write_json};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "qlog-analyzer",
+    about = "Summarize the connections in qlog JSON-SEQ files"
+)]
+struct Options {
+    #[arg(
+        long,
+        short,
+        value_enum,
+        default_value = "csv",
+        help = "Output format, the timelines and streams are only in json"
+    )]
+    format: Format,
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        help = "Write to a file instead of stdout"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        required = true,
+        help = "qlog files, or directories whose .sqlog files will be analyzed"
+    )]
+    paths: Vec<PathBuf>,
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            collect_files(&entry, files)?;
+        } else if entry.extension().is_some_and(|ext| ext == "sqlog") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn run(options: Options) -> Result<(), Box<dyn std::error::Error>> {
+    let mut files = vec![];
+    for path in &options.paths {
+        collect_files(path, &mut files)?;
+    }
+
+    let mut analyzer = Analyzer::new();
+    for file in &files {
+        analyzer
+            .feed_file(file)
+            .map_err(|error| format!("{}: {error}", file.display()))?;
+    }
+    let summaries = analyzer.summaries();
+
+    let mut output: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    };
+    match options.format {
+        Format::Csv => write_csv(&summaries, &mut output)?,
+        Format::Json => write_json(&summaries, &mut output)?,
+    }
+    output.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Options::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("qlog-analyzer: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
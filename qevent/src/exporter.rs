@@ -0,0 +1,139 @@
+use std::io;
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
+};
+
+use crate::{Event, QlogFileSeq, telemetry::ExportEvent};
+
+/// Write the header and then the events in JSON-SEQ ([RFC7464]) until all the senders are dropped.
+///
+/// Events are written in the qlog main schema as they are, no event is dropped.
+///
+/// [RFC7464]: https://www.rfc-editor.org/rfc/rfc7464
+pub(crate) async fn write_json_seq<O>(
+    output: &mut O,
+    header: &impl Serialize,
+    events: &mut mpsc::UnboundedReceiver<Event>,
+) -> io::Result<()>
+where
+    O: AsyncWrite + Unpin + ?Sized,
+{
+    const RS: u8 = 0x1E;
+
+    output.write_u8(RS).await?;
+    output
+        .write_all(serde_json::to_string(header)?.as_bytes())
+        .await?;
+    output.write_u8(b'\n').await?;
+
+    while let Some(event) = events.recv().await {
+        let event = serde_json::to_string(&event)?;
+        output.write_u8(RS).await?;
+        output.write_all(event.as_bytes()).await?;
+        output.write_u8(b'\n').await?;
+    }
+
+    output.flush().await
+}
+
+/// Export the events to an [`AsyncWrite`] in the qlog main schema, with JSON-SEQ serialization.
+///
+/// Different from [`legacy::exporter::IoExpoter`], events are not rolled back to the legacy schema,
+/// so the events which have no legacy form are exported too.
+///
+/// [`legacy::exporter::IoExpoter`]: crate::legacy::exporter::IoExpoter
+pub struct IoExporter(mpsc::UnboundedSender<Event>);
+
+impl IoExporter {
+    pub fn new<O>(qlog_file_seq: QlogFileSeq, mut output: O) -> Self
+    where
+        O: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            if let Err(error) = write_json_seq(&mut output, &qlog_file_seq, &mut rx).await {
+                tracing::error!(
+                    ?error,
+                    ?qlog_file_seq,
+                    "failed to write qlog, subsequent qlogs in this exporter will be ignored."
+                );
+            }
+        });
+        Self(tx)
+    }
+}
+
+impl ExportEvent for IoExporter {
+    fn emit(&self, event: Event) {
+        _ = self.0.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        EvnetData, LogFile, TraceSeq, VantagePoint, VantagePointType, build,
+        loglevel::Warning,
+        quic::connectivity::{MtuUpdated, PathAssigned, ServerListening},
+    };
+
+    const GOLDEN_SEQ: &str = include_str!("../tests/golden/main_schema.sqlog");
+
+    #[tokio::test]
+    async fn golden_json_seq() {
+        let header = build!(QlogFileSeq {
+            log_file: LogFile {
+                file_schema: QlogFileSeq::SCHEMA,
+                serialization_format: QlogFileSeq::SERIALIZATION_FORMAT,
+                title: "golden",
+                event_schemas: EvnetData::SCHEMAS.map(String::from).to_vec(),
+            },
+            trace: TraceSeq {
+                vantage_point: VantagePoint {
+                    r#type: VantagePointType::Server
+                },
+            },
+        });
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let events: [(f64, EvnetData); 4] = [
+            (
+                1.0,
+                build!(ServerListening {
+                    ip_v4: "127.0.0.1".to_owned(),
+                    port_v4: 443u16
+                })
+                .into(),
+            ),
+            // path_assigned and mtu_updated have no legacy form
+            (
+                2.0,
+                build!(PathAssigned {
+                    path_id: "new path".to_owned()
+                })
+                .into(),
+            ),
+            (3.0, build!(MtuUpdated { new: 1200u32 }).into()),
+            (
+                4.5,
+                build!(Warning {
+                    message: "something went wrong",
+                    code: 1u64
+                })
+                .into(),
+            ),
+        ];
+        for (time, data) in events {
+            tx.send(build!(crate::Event { time, data })).unwrap();
+        }
+        drop(tx);
+
+        let mut output = Vec::new();
+        write_json_seq(&mut output, &header, &mut rx).await.unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), GOLDEN_SEQ);
+    }
+}
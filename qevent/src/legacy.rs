@@ -5,6 +5,7 @@ use std::collections::HashMap;
 
 use derive_builder::Builder;
 use derive_more::{From, Into};
+use getset::Getters;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -132,8 +133,9 @@ pub struct Configuration {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct Event {
     time: f64,
     #[serde(flatten)]
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use derive_builder::Builder;
 use derive_more::{From, Into};
+use getset::Getters;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -56,8 +57,9 @@ impl ConnectivityConnectionStarted {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct ConnectivityConnectionClosed {
     /// which side closed the connection
     #[builder(default)]
@@ -123,8 +125,9 @@ pub struct ConnectivitySpinBitUpdated {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct ConnectivityConnectionStateUpdated {
     #[builder(default)]
     old: Option<ConnectionState>,
@@ -209,9 +212,9 @@ pub enum SecurityKeyRetiredTrigger {
 #[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
 pub struct TransportVersionInformation {
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     server_versions: Vec<QuicVersion>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     client_versions: Vec<QuicVersion>,
     chosen_version: Option<QuicVersion>,
 }
@@ -312,8 +315,9 @@ pub struct TransportParametersRestored {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct TransportPacketSent {
     header: PacketHeader,
 
@@ -335,7 +339,7 @@ pub struct TransportPacketSent {
 
     /// only if header.packet_type === "version_negotiation"
     #[builder(default)]
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     supported_versions: Vec<QuicVersion>,
 
     #[builder(default)]
@@ -358,8 +362,9 @@ pub enum TransportPacketSentTrigger {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct TransportPacketReceived {
     header: PacketHeader,
 
@@ -382,7 +387,7 @@ pub struct TransportPacketReceived {
 
     /// only if header.packet_type === "version_negotiation"
     #[builder(default)]
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     supported_versions: Vec<QuicVersion>,
 
     #[builder(default)]
@@ -706,8 +711,9 @@ pub struct RecoveryParametersSet {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct RecoveryMetricsUpdated {
     /// Loss detection, see recovery draft-23, Appendix A.3
     /// all following rtt fields are expressed in ms
@@ -771,8 +777,9 @@ pub enum RecoveryCongestionStateUpdatedTrigger {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct RecoveryLossTimerUpdated {
     /// called "mode" in draft-23 A.9.
     #[builder(default)]
@@ -804,12 +811,13 @@ pub enum LossTimerEventType {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(
     default,
     setter(into, strip_option),
     build_fn(private, name = "fallible_build")
 )]
+#[getset(get = "pub")]
 pub struct RecoveryPacketLost {
     /// should include at least the packet_type and packet_number
     header: Option<PacketHeader>,
@@ -1,3 +1,5 @@
+pub mod analyzer;
+pub mod exporter;
 pub mod legacy;
 pub mod loglevel;
 pub mod quic;
@@ -13,6 +15,7 @@ use std::{collections::HashMap, fmt::Display, net::SocketAddr};
 use bytes::Bytes;
 use derive_builder::Builder;
 use derive_more::{Display, From, Into};
+use getset::Getters;
 use qbase::{cid::ConnectionId, role::Role, util::ContinuousData};
 use quic::ConnectionID;
 use serde::{Deserialize, Serialize};
@@ -50,11 +53,12 @@ pub struct QlogFile {
 pub struct QlogFileSeq {
     #[serde(flatten)]
     log_file: LogFile,
-    trace_seq: TraceSeq,
+    trace: TraceSeq,
 }
 
 impl QlogFileSeq {
     pub const SCHEMA: &'static str = "urn:ietf:params:qlog:file:sequential";
+    pub const SERIALIZATION_FORMAT: &'static str = "application/qlog+json-seq";
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -184,8 +188,9 @@ pub struct TraceError {
 ///
 /// Events can contain any amount of custom fields.
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct Event {
     time: f64,
     #[serde(flatten)]
@@ -379,7 +384,12 @@ pub struct SystemInformation {
     thread_id: Option<u32>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The importance levels of the events, see [Section 9.2 of the qlog main schema].
+///
+/// The levels are ordered from the most to the least important.
+///
+/// [Section 9.2 of the qlog main schema]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09#section-9.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum EventImportance {
     Core = 1,
     Base = 2,
@@ -470,6 +480,14 @@ pub enum EvnetData {
     Verbose(loglevel::Verbose),
 }
 
+impl EvnetData {
+    /// The URIs of the event schemas which all the events belong to.
+    pub const SCHEMAS: [&'static str; 2] = [
+        "urn:ietf:params:qlog:events:quic",
+        "urn:ietf:params:qlog:events:loglevel",
+    ];
+}
+
 pub trait BeSpecificEventData {
     fn scheme() -> &'static str;
 
@@ -496,8 +514,18 @@ impl<S: BeSpecificEventData> BeEventData for S {
 }
 
 macro_rules! imp_be_events {
-    ( $($importance:ident $event:ty => $prefix:ident $schme:literal ;)* ) => {
-        $( imp_be_events!{@impl_one $importance $event => $prefix $schme ; } )*
+    ( $($importance:ident $root:ident :: $category:ident :: $event:ident => $prefix:ident $schme:literal ;)* ) => {
+        $( imp_be_events!{@impl_one $importance $root::$category::$event => $prefix $schme ; } )*
+
+        /// The importance and the category of a known event, such as `quic:packet_sent`.
+        ///
+        /// The category is the module of the event, such as `transport` or `loglevel`.
+        pub(crate) fn event_meta(name: &str) -> Option<(EventImportance, &'static str)> {
+            match name {
+                $( $schme => Some((EventImportance::$importance, stringify!($category))), )*
+                _ => None,
+            }
+        }
     };
     (@impl_one $importance:ident $event:ty => urn $schme:literal ; ) => {
         impl BeSpecificEventData for $event {
@@ -547,11 +575,11 @@ imp_be_events! {
     Core  quic::recovery::PacketLost                 => urn "quic:packet_lost";
     Extra quic::recovery::MarkedForRetransmit        => urn "quic:marked_for_retransmit";
     Extra quic::recovery::ECNStateUpdated            => urn "quic:ecn_state_updated";
-    Core  loglevel::Error                            => urn "loglevel:error";
-    Base  loglevel::Warning                          => urn "loglevel:warning";
-    Extra loglevel::Info                             => urn "loglevel:info";
-    Extra loglevel::Debug                            => urn "loglevel:debug";
-    Extra loglevel::Verbose                          => urn "loglevel:verbose";
+    Core  crate::loglevel::Error                     => urn "loglevel:error";
+    Base  crate::loglevel::Warning                   => urn "loglevel:warning";
+    Extra crate::loglevel::Info                      => urn "loglevel:info";
+    Extra crate::loglevel::Debug                     => urn "loglevel:debug";
+    Extra crate::loglevel::Verbose                   => urn "loglevel:verbose";
 }
 
 /// serialize/deserialize as hex string, but store as bytes in memory
@@ -567,12 +595,13 @@ impl Display for HexString {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Getters, Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[builder(
     default,
     setter(into, strip_option),
     build_fn(private, name = "fallible_build")
 )]
+#[getset(get = "pub")]
 pub struct RawInfo {
     /// the full byte length of the entity (e.g., packet or frame),
     /// including possible headers and trailers
@@ -7,9 +7,12 @@ use derive_builder::Builder;
 use derive_more::{From, Into, LowerHex};
 use qbase::{
     frame::{
-        AckFrame, ConnectionCloseFrame, CryptoFrame, DatagramFrame, EncodeSize, Frame,
-        MaxStreamsFrame, NewTokenFrame, PathChallengeFrame, PathResponseFrame, PingFrame,
-        ReliableFrame, StreamCtlFrame, StreamFrame, StreamsBlockedFrame,
+        AckFrame, AckFrequencyFrame, AddAddressFrame, ConnectionCloseFrame, CryptoFrame,
+        DatagramFrame, EncodeSize, ExtensionFrame, Frame, FrameType, MaxPathIdFrame,
+        MaxStreamsFrame, NewTokenFrame, PathAbandonFrame, PathAckFrame, PathAvailability,
+        PathChallengeFrame, PathCidsBlockedFrame, PathNewConnectionIdFrame, PathResponseFrame,
+        PathRetireConnectionIdFrame, PathStatusFrame, PathsBlockedFrame, PingFrame,
+        PunchMeNowFrame, ReliableFrame, StreamCtlFrame, StreamFrame, StreamsBlockedFrame,
     },
     net::addr::RealAddr,
     packet::header::{
@@ -370,7 +373,7 @@ pub struct Token {
 
     /// decoded fields included in the token
     /// (typically: peer's IP address, creation time)
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     details: HashMap<String, serde_json::Value>,
 
     raw: Option<RawInfo>,
@@ -614,6 +617,76 @@ pub enum QuicFrame {
         length: Option<u64>,
         raw: Option<RawInfo>,
     },
+    /// Frames of the multipath extension, see
+    /// [draft-ietf-quic-multipath](https://datatracker.ietf.org/doc/draft-ietf-quic-multipath/).
+    PathAck {
+        path_id: u64,
+        /// in ms
+        ack_delay: Option<f32>,
+        acked_ranges: Vec<[u64; 2]>,
+        ect1: Option<u64>,
+        ect0: Option<u64>,
+        ce: Option<u64>,
+
+        /// total frame length, including frame header
+        length: Option<u32>,
+        payload_length: Option<u32>,
+    },
+    PathAbandon {
+        path_id: u64,
+        error_code: u64,
+    },
+    PathStatusBackup {
+        path_id: u64,
+        path_status_sequence_number: u64,
+    },
+    PathStatusAvailable {
+        path_id: u64,
+        path_status_sequence_number: u64,
+    },
+    PathNewConnectionId {
+        path_id: u64,
+        sequence_number: u32,
+        retire_prior_to: u32,
+        connection_id_length: Option<u8>,
+        connection_id: ConnectionID,
+        stateless_reset_token: Option<StatelessResetToken>,
+    },
+    PathRetireConnectionId {
+        path_id: u64,
+        sequence_number: u32,
+    },
+    MaxPathId {
+        maximum_path_id: u64,
+    },
+    PathsBlocked {
+        maximum_path_id: u64,
+    },
+    PathCidsBlocked {
+        path_id: u64,
+        next_sequence_number: u64,
+    },
+    /// Frames of the ack frequency extension, see
+    /// [draft-ietf-quic-ack-frequency](https://datatracker.ietf.org/doc/draft-ietf-quic-ack-frequency/).
+    AckFrequency {
+        sequence_number: u64,
+        ack_eliciting_threshold: u64,
+        /// in ms
+        requested_max_ack_delay: f32,
+        reordering_threshold: u64,
+    },
+    ImmediateAck {},
+    /// Frames of the NAT traversal extension, see
+    /// [draft-seemann-quic-nat-traversal](https://datatracker.ietf.org/doc/draft-seemann-quic-nat-traversal/).
+    AddAddress {
+        sequence_number: u64,
+        address: PathEndpointInfo,
+    },
+    PunchMeNow {
+        round: u64,
+        paired_with_sequence_number: u64,
+        address: PathEndpointInfo,
+    },
 }
 
 impl From<&PingFrame> for QuicFrame {
@@ -768,6 +841,150 @@ impl From<&AckFrame> for QuicFrame {
     }
 }
 
+impl From<&PathAckFrame> for QuicFrame {
+    fn from(frame: &PathAckFrame) -> Self {
+        match QuicFrame::from(frame.ack()) {
+            QuicFrame::Ack {
+                ack_delay,
+                acked_ranges,
+                ect1,
+                ect0,
+                ce,
+                length: _,
+                payload_length,
+            } => QuicFrame::PathAck {
+                path_id: frame.path_id().into_inner(),
+                ack_delay,
+                acked_ranges,
+                ect1,
+                ect0,
+                ce,
+                length: Some(frame.encoding_size() as u32),
+                payload_length,
+            },
+            _ => unreachable!("AckFrame is always converted to QuicFrame::Ack"),
+        }
+    }
+}
+
+impl From<&PathAbandonFrame> for QuicFrame {
+    fn from(frame: &PathAbandonFrame) -> Self {
+        QuicFrame::PathAbandon {
+            path_id: frame.path_id().into_inner(),
+            error_code: frame.error_code(),
+        }
+    }
+}
+
+impl From<&PathStatusFrame> for QuicFrame {
+    fn from(frame: &PathStatusFrame) -> Self {
+        let path_id = frame.path_id().into_inner();
+        let path_status_sequence_number = frame.sequence();
+        match frame.availability() {
+            PathAvailability::Backup => QuicFrame::PathStatusBackup {
+                path_id,
+                path_status_sequence_number,
+            },
+            PathAvailability::Available => QuicFrame::PathStatusAvailable {
+                path_id,
+                path_status_sequence_number,
+            },
+        }
+    }
+}
+
+impl From<&PathNewConnectionIdFrame> for QuicFrame {
+    fn from(frame: &PathNewConnectionIdFrame) -> Self {
+        let new_cid = frame.new_cid_frame();
+        QuicFrame::PathNewConnectionId {
+            path_id: frame.path_id().into_inner(),
+            sequence_number: new_cid.sequence() as u32,
+            retire_prior_to: new_cid.retire_prior_to() as u32,
+            connection_id_length: Some(new_cid.connection_id().len() as u8),
+            connection_id: (*new_cid.connection_id()).into(),
+            stateless_reset_token: Some((**new_cid.reset_token()).into()),
+        }
+    }
+}
+
+impl From<&PathRetireConnectionIdFrame> for QuicFrame {
+    fn from(frame: &PathRetireConnectionIdFrame) -> Self {
+        QuicFrame::PathRetireConnectionId {
+            path_id: frame.path_id().into_inner(),
+            sequence_number: frame.retire_cid_frame().sequence() as u32,
+        }
+    }
+}
+
+impl From<&MaxPathIdFrame> for QuicFrame {
+    fn from(frame: &MaxPathIdFrame) -> Self {
+        QuicFrame::MaxPathId {
+            maximum_path_id: frame.max_path_id().into_inner(),
+        }
+    }
+}
+
+impl From<&PathsBlockedFrame> for QuicFrame {
+    fn from(frame: &PathsBlockedFrame) -> Self {
+        QuicFrame::PathsBlocked {
+            maximum_path_id: frame.max_path_id().into_inner(),
+        }
+    }
+}
+
+impl From<&PathCidsBlockedFrame> for QuicFrame {
+    fn from(frame: &PathCidsBlockedFrame) -> Self {
+        QuicFrame::PathCidsBlocked {
+            path_id: frame.path_id().into_inner(),
+            next_sequence_number: frame.next_sequence().into_inner(),
+        }
+    }
+}
+
+impl From<&AckFrequencyFrame> for QuicFrame {
+    fn from(frame: &AckFrequencyFrame) -> Self {
+        QuicFrame::AckFrequency {
+            sequence_number: frame.sequence(),
+            ack_eliciting_threshold: frame.ack_eliciting_threshold(),
+            requested_max_ack_delay: frame.request_max_ack_delay().as_secs_f32() * 1000.0,
+            reordering_threshold: frame.reordering_threshold(),
+        }
+    }
+}
+
+impl From<&AddAddressFrame> for QuicFrame {
+    fn from(frame: &AddAddressFrame) -> Self {
+        QuicFrame::AddAddress {
+            sequence_number: frame.sequence().into_inner(),
+            address: frame.addr().into(),
+        }
+    }
+}
+
+impl From<&PunchMeNowFrame> for QuicFrame {
+    fn from(frame: &PunchMeNowFrame) -> Self {
+        QuicFrame::PunchMeNow {
+            round: frame.round().into_inner(),
+            paired_with_sequence_number: frame.paired_with().into_inner(),
+            address: frame.addr().into(),
+        }
+    }
+}
+
+impl From<&ExtensionFrame> for QuicFrame {
+    fn from(frame: &ExtensionFrame) -> Self {
+        let body = frame.body();
+        QuicFrame::Unknow {
+            frame_type_bytes: frame.extension_type().value().into_inner(),
+            raw: Some(crate::build!(RawInfo {
+                length: frame.encoding_size() as u64,
+                payload_length: body.len() as u64,
+                data: body,
+            })),
+        }
+    }
+}
+
 impl From<&ReliableFrame> for QuicFrame {
     fn from(frame: &ReliableFrame) -> Self {
         match frame {
@@ -792,6 +1009,18 @@ impl From<&ReliableFrame> for QuicFrame {
             }
             ReliableFrame::HandshakeDone(_handshake_done_frame) => QuicFrame::HandshakeDone {},
             ReliableFrame::StreamCtl(stream_ctl_frame) => QuicFrame::from(stream_ctl_frame),
+            ReliableFrame::PathAbandon(path_abandon_frame) => path_abandon_frame.into(),
+            ReliableFrame::PathStatus(path_status_frame) => path_status_frame.into(),
+            ReliableFrame::PathNewConnectionId(frame) => frame.into(),
+            ReliableFrame::PathRetireConnectionId(frame) => frame.into(),
+            ReliableFrame::MaxPathId(frame) => frame.into(),
+            ReliableFrame::PathsBlocked(frame) => frame.into(),
+            ReliableFrame::PathCidsBlocked(frame) => frame.into(),
+            ReliableFrame::AckFrequency(ack_frequency_frame) => ack_frequency_frame.into(),
+            ReliableFrame::ImmediateAck(_immediate_ack_frame) => QuicFrame::ImmediateAck {},
+            ReliableFrame::AddAddress(add_address_frame) => add_address_frame.into(),
+            ReliableFrame::PunchMeNow(punch_me_now_frame) => punch_me_now_frame.into(),
+            ReliableFrame::Extension(extension_frame) => extension_frame.into(),
         }
     }
 }
@@ -920,6 +1149,19 @@ impl<D: ContinuousData> From<&Frame<D>> for QuicFrame {
             Frame::Stream(frame, bytes) => (frame, bytes).into(),
             Frame::Crypto(frame, bytes) => (frame, bytes).into(),
             Frame::Datagram(frame, bytes) => (frame, bytes).into(),
+            Frame::PathAck(frame) => frame.into(),
+            Frame::PathAbandon(frame) => frame.into(),
+            Frame::PathStatus(frame) => frame.into(),
+            Frame::PathNewConnectionId(frame) => frame.into(),
+            Frame::PathRetireConnectionId(frame) => frame.into(),
+            Frame::MaxPathId(frame) => frame.into(),
+            Frame::PathsBlocked(frame) => frame.into(),
+            Frame::PathCidsBlocked(frame) => frame.into(),
+            Frame::AckFrequency(frame) => frame.into(),
+            Frame::ImmediateAck(_) => QuicFrame::ImmediateAck {},
+            Frame::AddAddress(frame) => frame.into(),
+            Frame::PunchMeNow(frame) => frame.into(),
+            Frame::Extension(frame) => frame.into(),
         }
     }
 }
@@ -1476,6 +1718,81 @@ mod rollback {
                     raw: raw.and_then(|raw| raw.data),
                 },
                 QuicFrame::Datagram { length, raw } => legacy::QuicFrame::Datagram { length, raw },
+                // The legacy schema knows nothing about the multipath extension.
+                QuicFrame::PathAck { ect0, length, .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathAck(ect0.is_some() as u8))
+                        .into_inner(),
+                    raw_length: length,
+                    raw: None,
+                },
+                QuicFrame::PathAbandon { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathAbandon).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::PathStatusBackup { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathStatus(0)).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::PathStatusAvailable { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathStatus(1)).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::PathNewConnectionId { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathNewConnectionId).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::PathRetireConnectionId { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathRetireConnectionId).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::MaxPathId { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::MaxPathId).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::PathsBlocked { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathsBlocked).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::PathCidsBlocked { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PathCidsBlocked).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                // Neither the ack frequency extension.
+                QuicFrame::AckFrequency { .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::AckFrequency).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::ImmediateAck {} => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::ImmediateAck).into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                // Neither the NAT traversal extension.
+                QuicFrame::AddAddress { address, .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::AddAddress(
+                        address.ip_v6.is_some() as u8
+                    ))
+                    .into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
+                QuicFrame::PunchMeNow { address, .. } => legacy::QuicFrame::Unknown {
+                    raw_frame_type: VarInt::from(FrameType::PunchMeNow(
+                        address.ip_v6.is_some() as u8
+                    ))
+                    .into_inner(),
+                    raw_length: None,
+                    raw: None,
+                },
             }
         }
     }
@@ -2,6 +2,7 @@ use std::net::SocketAddr;
 
 use derive_builder::Builder;
 use derive_more::From;
+use getset::Getters;
 use qbase::{
     error::{AppError, Error, ErrorKind, QuicError},
     frame::{AppCloseFrame, ConnectionCloseFrame, QuicCloseFrame},
@@ -60,8 +61,9 @@ impl ServerListeningBuilder {
 ///
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct ConnectionStarted {
     ip_version: IpVersion,
     src_ip: IPAddress,
@@ -140,12 +142,13 @@ impl ConnectionStarted {
 ///
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(
     default,
     setter(into, strip_option),
     build_fn(private, name = "fallible_build")
 )]
+#[getset(get = "pub")]
 pub struct ConnectionClosed {
     /// which side closed the connection
     owner: Option<Owner>,
@@ -305,8 +308,9 @@ pub struct SpinBitUpdated {
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 /// [QUIC-TRANSPORT]: https://www.rfc-editor.org/rfc/rfc9000
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct ConnectionStateUpdated {
     #[builder(default)]
     old: Option<ConnectionState>,
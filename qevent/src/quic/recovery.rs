@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use derive_builder::Builder;
+use getset::Getters;
 use serde::{Deserialize, Serialize};
 
 use super::{PacketHeader, PacketNumberSpace, QuicFrame};
@@ -75,12 +76,13 @@ pub struct RecoveryParametersSet {
 ///
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(
     default,
     setter(into, strip_option),
     build_fn(private, name = "fallible_build")
 )]
+#[getset(get = "pub")]
 pub struct RecoveryMetricsUpdated {
     /// Loss detection, see RFC 9002 Appendix A.3
     /// all following rtt fields are expressed in ms
@@ -158,8 +160,9 @@ pub struct CongestionStateUpdated {
 ///
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct LossTimerUpdated {
     /// called "mode" in RFC 9002 A.9.
     #[builder(default)]
@@ -199,12 +202,13 @@ pub enum EventType {
 ///
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Default, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(
     default,
     setter(into, strip_option),
     build_fn(private, name = "fallible_build")
 )]
+#[getset(get = "pub")]
 pub struct PacketLost {
     /// should include at least the packet_type and packet_number
     header: Option<PacketHeader>,
@@ -212,6 +216,7 @@ pub struct PacketLost {
     /// not all implementations will keep track of full
     /// packets, so these are optional
     frames: Option<Vec<QuicFrame>>,
+    #[serde(default)]
     is_mtu_probe_packet: bool,
     trigger: Option<PacketLostTrigger>,
 }
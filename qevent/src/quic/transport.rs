@@ -2,6 +2,7 @@ use std::{collections::HashMap, time::Duration};
 
 use derive_builder::Builder;
 use derive_more::From;
+use getset::Getters;
 use qbase::param::{ClientParameters, ParameterId, ServerParameters};
 use serde::{Deserialize, Serialize};
 
@@ -35,9 +36,9 @@ use crate::{HexString, PathID, RawInfo};
 )]
 pub struct VersionInformation {
     // Vec for `? filed: [ +ty]``, Option<Vec> for `* filed: [* ty]`
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     server_versions: Vec<QuicVersion>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     client_versions: Vec<QuicVersion>,
     chosen_version: Option<QuicVersion>,
 }
@@ -173,6 +174,16 @@ pub struct ParametersSet {
     /// can only be restored at the client.
     /// servers MUST NOT restore this parameter!
     grease_quic_bit: Option<bool>,
+
+    // draft-ietf-quic-multipath
+    initial_max_path_id: Option<u64>,
+
+    // draft-ietf-quic-ack-frequency
+    /// in microseconds
+    min_ack_delay: Option<u64>,
+
+    // draft-seemann-quic-nat-traversal
+    nat_traversal: Option<bool>,
 }
 
 macro_rules! extract_parameter {
@@ -193,6 +204,22 @@ macro_rules! extract_parameter {
     };
 }
 
+// 传输不认识的参数，即应用自定义的扩展参数，记录为unknown_parameters
+fn unknown_parameters<R>(
+    params: &qbase::param::core::Parameters<R>,
+) -> Option<Vec<UnknownParameter>> {
+    let unknown = params
+        .custom()
+        .map(|(id, value)| {
+            UnknownParameter::builder()
+                .id(id.into_inner())
+                .value(value.clone())
+                .build()
+        })
+        .collect::<Vec<_>>();
+    (!unknown.is_empty()).then_some(unknown)
+}
+
 impl ParametersSetBuilder {
     /// helper method to set all client parameters at once
     pub fn client_parameters(&mut self, params: &ClientParameters) -> &mut Self {
@@ -213,7 +240,12 @@ impl ParametersSetBuilder {
             InitialMaxStreamsUni as u64 from params to self.initial_max_streams_uni,
             MaxDatagramFrameSize as u64 from params to self.max_datagram_frame_size,
             GreaseQuicBit as bool from params to self.grease_quic_bit,
+            InitialMaxPathId as u64 from params to self.initial_max_path_id,
+            MinAckDelay as u64 from params to self.min_ack_delay,
+            NatTraversal as bool from params to self.nat_traversal,
         }
+        self.unknown_parameters =
+            (self.unknown_parameters.take()).or_else(|| Some(unknown_parameters(params)));
         self
     }
 
@@ -242,7 +274,12 @@ impl ParametersSetBuilder {
             PreferredAddress as PreferredAddress from params to self.preferred_address,
             MaxDatagramFrameSize as u64 from params to self.max_datagram_frame_size,
             GreaseQuicBit as bool from params to self.grease_quic_bit,
+            InitialMaxPathId as u64 from params to self.initial_max_path_id,
+            MinAckDelay as u64 from params to self.min_ack_delay,
+            NatTraversal as bool from params to self.nat_traversal,
         }
+        self.unknown_parameters =
+            (self.unknown_parameters.take()).or_else(|| Some(unknown_parameters(params)));
         self
     }
 }
@@ -347,8 +384,9 @@ impl ParametersRestoredBuilder {
 ///
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct PacketSent {
     header: PacketHeader,
     #[builder(default)]
@@ -361,7 +399,7 @@ pub struct PacketSent {
 
     /// only if header.packet_type === "version_negotiation"
     #[builder(default)]
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     supported_versions: Vec<QuicVersion>,
     #[builder(default)]
     raw: Option<RawInfo>,
@@ -390,8 +428,9 @@ pub enum PacketSentTrigger {
 ///
 /// [QLOG-MAIN]: https://datatracker.ietf.org/doc/html/draft-ietf-quic-qlog-main-schema-09
 #[serde_with::skip_serializing_none]
-#[derive(Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Builder, Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[builder(setter(into, strip_option), build_fn(private, name = "fallible_build"))]
+#[getset(get = "pub")]
 pub struct PacketReceived {
     header: PacketHeader,
     #[builder(default)]
@@ -404,7 +443,7 @@ pub struct PacketReceived {
 
     /// only if header.packet_type === "version_negotiation"
     #[builder(default)]
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     supported_versions: Vec<QuicVersion>,
     #[builder(default)]
     raw: Option<RawInfo>,
@@ -552,7 +591,7 @@ pub enum PacketBufferedTrigger {
 )]
 pub struct PacketsAcked {
     packet_number_space: Option<PacketNumberSpace>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     packet_nubers: Vec<u64>,
 }
 /// The datagrams_sent event indicates when one or more UDP-level
@@ -574,16 +613,16 @@ pub struct UdpDatagramsSent {
 
     /// The RawInfo fields do not include the UDP headers,
     /// only the UDP payload
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     raw: Vec<RawInfo>,
 
     /// ECN bits in the IP header
     /// if not set, defaults to the value used on the last
     /// QUICDatagramsSent event
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     ecn: Vec<ECN>,
 
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     datagram_ids: Vec<u32>,
 }
 
@@ -606,16 +645,16 @@ pub struct UdpDatagramsReceived {
 
     /// The RawInfo fields do not include the UDP headers,
     /// only the UDP payload
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     raw: Vec<RawInfo>,
 
     /// ECN bits in the IP header
     /// if not set, defaults to the value used on the last
     /// QUICDatagramsSent event
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     ecn: Vec<ECN>,
 
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     datagram_ids: Vec<u32>,
 }
 
@@ -1059,6 +1098,9 @@ mod rollback {
                 // ?unknown_parameters: ,
                 // ?max_datagram_frame_size: ps.max_datagram_frame_size,
                 // ?grease_quic_bit: ps.grease_quic_bit,
+                // ?initial_max_path_id: ps.initial_max_path_id,
+                // ?min_ack_delay: ps.min_ack_delay,
+                // ?nat_traversal: ps.nat_traversal,
             })
         }
     }
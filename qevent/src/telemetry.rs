@@ -1,5 +1,8 @@
-pub(crate) mod filter;
+pub mod filter;
 pub mod handy;
+pub mod metrics;
+pub mod rotating;
+pub mod sampling;
 
 #[doc(hidden)]
 pub mod macro_support;
@@ -35,6 +38,22 @@ pub trait ExportEvent: Send + Sync {
     fn filter_raw_data(&self) -> bool {
         false
     }
+
+    /// Start or stop recording the trace at runtime.
+    ///
+    /// Return `false` if the exporter does not support it, see [`SampledLogger`].
+    ///
+    /// [`SampledLogger`]: sampling::SampledLogger
+    fn set_recording(&self, recording: bool) -> bool {
+        _ = recording;
+        false
+    }
+
+    /// Called once the server name of the traced connection is known, which is at the beginning
+    /// for clients and after the ClientHello for servers.
+    fn on_server_name(&self, server_name: &str) {
+        _ = server_name;
+    }
 }
 
 #[derive(Clone)]
@@ -68,6 +87,16 @@ impl Span {
         self.exporter.filter_raw_data()
     }
 
+    #[inline]
+    pub fn set_recording(&self, recording: bool) -> bool {
+        self.exporter.set_recording(recording)
+    }
+
+    #[inline]
+    pub fn on_server_name(&self, server_name: &str) {
+        self.exporter.on_server_name(server_name)
+    }
+
     #[inline]
     pub fn load<T: DeserializeOwned>(&self, name: &'static str) -> T {
         let Some(value) = self.fields.get(name) else {
@@ -1,23 +1,209 @@
+//! Decide which events are built and exported.
+//!
+//! Without the `telemetry` feature no event is ever built. With it, each exporter decides at
+//! runtime through [`ExportEvent::filter_event`] and [`ExportEvent::filter_raw_data`], and the
+//! loggers of this crate can be given an [`EventFilter`].
+use super::ExportEvent;
+use crate::{Event, EventImportance};
+
 #[inline]
 #[cfg(feature = "telemetry")]
-pub fn event(scheme: &'static str) -> bool {
+pub(crate) fn event(scheme: &'static str) -> bool {
     super::current_span::CURRENT_SPAN.with(|span| span.borrow().filter_event(scheme))
 }
 
 #[inline]
 #[cfg(not(feature = "telemetry"))]
-pub fn event(_scheme: &'static str) -> bool {
+pub(crate) fn event(_scheme: &'static str) -> bool {
     false
 }
 
 #[inline]
-#[cfg(all(feature = "telemetry", feature = "raw_data"))]
-pub fn raw_data() -> bool {
+#[cfg(feature = "telemetry")]
+pub(crate) fn raw_data() -> bool {
     super::current_span::CURRENT_SPAN.with(|span| span.borrow().filter_raw_data())
 }
 
 #[inline]
-#[cfg(not(all(feature = "telemetry", feature = "raw_data")))]
-pub fn raw_data() -> bool {
+#[cfg(not(feature = "telemetry"))]
+pub(crate) fn raw_data() -> bool {
     false
 }
+
+const SCHEME_PREFIX: &str = "urn:ietf:params:qlog:events:";
+
+/// A runtime filter of the events, by importance level and by allow and deny lists.
+///
+/// The lists hold patterns, each pattern is either:
+/// - an event name, such as `quic:packet_sent`,
+/// - a namespace, such as `quic` or `loglevel`,
+/// - a category, that is the group of the events in the QUIC event definitions: `connectivity`,
+///   `transport`, `security` or `recovery`,
+/// - or a prefix of event names ending with `*`, such as `quic:stream_*`.
+///
+/// An event is exported if its importance is not lower than the level, it matches no denied
+/// pattern, and it matches an allowed pattern if any is given.
+///
+/// The default filter exports every event, with raw data only if the `raw_data` feature is
+/// enabled.
+#[derive(Debug, Clone)]
+pub struct EventFilter {
+    importance: EventImportance,
+    allowed: Vec<String>,
+    denied: Vec<String>,
+    raw_data: bool,
+}
+
+impl Default for EventFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventFilter {
+    pub fn new() -> Self {
+        Self {
+            importance: EventImportance::Extra,
+            allowed: vec![],
+            denied: vec![],
+            raw_data: cfg!(feature = "raw_data"),
+        }
+    }
+
+    /// Only export the events at least as important as `importance`, for example
+    /// [`EventImportance::Core`] exports only the core events.
+    pub fn with_importance(mut self, importance: EventImportance) -> Self {
+        self.importance = importance;
+        self
+    }
+
+    /// Only export the events matching one of the allowed patterns.
+    pub fn with_allowed(mut self, pattern: impl Into<String>) -> Self {
+        self.allowed.push(pattern.into());
+        self
+    }
+
+    /// Never export the events matching the pattern, even if they are allowed.
+    pub fn with_denied(mut self, pattern: impl Into<String>) -> Self {
+        self.denied.push(pattern.into());
+        self
+    }
+
+    /// Capture the raw bytes of the packets and frames, when the events carry them.
+    pub fn with_raw_data(mut self, raw_data: bool) -> Self {
+        self.raw_data = raw_data;
+        self
+    }
+
+    /// Whether the event with the scheme should be exported.
+    pub fn event(&self, scheme: &str) -> bool {
+        let name = scheme.strip_prefix(SCHEME_PREFIX).unwrap_or(scheme);
+        let meta = crate::event_meta(name);
+        if meta.is_some_and(|(importance, _)| importance > self.importance) {
+            return false;
+        }
+
+        let category = meta.map(|(_, category)| category);
+        let matches = |pattern: &String| {
+            if let Some(prefix) = pattern.strip_suffix('*') {
+                return name.starts_with(prefix);
+            }
+            pattern == name
+                || Some(pattern.as_str()) == category
+                || name
+                    .split_once(':')
+                    .is_some_and(|(namespace, _)| pattern == namespace)
+        };
+        !self.denied.iter().any(matches)
+            && (self.allowed.is_empty() || self.allowed.iter().any(matches))
+    }
+
+    /// Whether the raw data of the events should be captured.
+    pub fn raw_data(&self) -> bool {
+        self.raw_data
+    }
+}
+
+/// Apply an [`EventFilter`] on an exporter.
+///
+/// Whether the raw data is captured is decided by the filter alone.
+pub(crate) struct Filtered<E> {
+    pub(crate) filter: EventFilter,
+    pub(crate) exporter: E,
+}
+
+impl<E: ExportEvent> ExportEvent for Filtered<E> {
+    #[inline]
+    fn emit(&self, event: Event) {
+        self.exporter.emit(event)
+    }
+
+    #[inline]
+    fn filter_event(&self, scheme: &'static str) -> bool {
+        self.filter.event(scheme) && self.exporter.filter_event(scheme)
+    }
+
+    #[inline]
+    fn filter_raw_data(&self) -> bool {
+        self.filter.raw_data()
+    }
+
+    fn set_recording(&self, recording: bool) -> bool {
+        self.exporter.set_recording(recording)
+    }
+
+    fn on_server_name(&self, server_name: &str) {
+        self.exporter.on_server_name(server_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BeSpecificEventData,
+        quic::{recovery::PacketLost, transport::*},
+    };
+
+    fn scheme<E: BeSpecificEventData>() -> &'static str {
+        E::scheme()
+    }
+
+    #[test]
+    fn filter_by_importance() {
+        let filter = EventFilter::new().with_importance(EventImportance::Core);
+        assert!(filter.event(scheme::<PacketSent>()));
+        assert!(!filter.event(scheme::<StreamDataMoved>()));
+        assert!(!filter.event(scheme::<PacketsAcked>()));
+
+        let filter = EventFilter::new().with_importance(EventImportance::Base);
+        assert!(filter.event(scheme::<StreamDataMoved>()));
+        assert!(!filter.event(scheme::<PacketsAcked>()));
+
+        assert!(EventFilter::new().event(scheme::<PacketsAcked>()));
+    }
+
+    #[test]
+    fn filter_by_patterns() {
+        let filter = EventFilter::new()
+            .with_allowed("recovery")
+            .with_allowed("quic:stream_*")
+            .with_denied("quic:packet_lost");
+        assert!(filter.event(scheme::<StreamDataMoved>()));
+        assert!(filter.event(scheme::<StreamStateUpdated>()));
+        assert!(filter.event(scheme::<crate::quic::recovery::RecoveryMetricsUpdated>()));
+        assert!(!filter.event(scheme::<PacketLost>()));
+        assert!(!filter.event(scheme::<PacketSent>()));
+
+        let filter = EventFilter::new().with_denied("loglevel");
+        assert!(!filter.event(scheme::<crate::loglevel::Warning>()));
+        assert!(filter.event(scheme::<PacketSent>()));
+    }
+
+    #[test]
+    fn raw_data_by_feature() {
+        assert_eq!(EventFilter::new().raw_data(), cfg!(feature = "raw_data"));
+        assert!(EventFilter::new().with_raw_data(true).raw_data());
+        assert!(!EventFilter::new().with_raw_data(false).raw_data());
+    }
+}
@@ -9,7 +9,10 @@ use tokio::{
     sync::mpsc,
 };
 
-use super::{ExportEvent, Log, Span};
+use super::{
+    ExportEvent, Log, Span,
+    filter::{EventFilter, Filtered},
+};
 use crate::{Event, GroupID, VantagePoint, VantagePointType, span};
 
 pub struct NoopExporter;
@@ -32,6 +35,11 @@ impl ExportEvent for mpsc::UnboundedSender<Event> {
     fn emit(&self, event: Event) {
         _ = self.send(event);
     }
+
+    fn filter_event(&self, _: &'static str) -> bool {
+        // The receiver is gone, for example the qlog file could not be created
+        !self.is_closed()
+    }
 }
 
 pub struct NoopLogger;
@@ -50,6 +58,10 @@ impl<L: Log + ?Sized> Log for Arc<L> {
     }
 }
 
+/// Where the qlog files are written.
+///
+/// If the file of a trace can not be created, the storage can return a writer whose writes fail,
+/// the connection is then not traced.
 pub trait TelemetryStorage {
     fn join(
         &self,
@@ -102,19 +114,30 @@ impl TelemetryStorage for tokio::io::Stderr {
 
 pub struct LegacySeqLogger<S> {
     storage: S,
+    filter: EventFilter,
 }
 
 impl<S: Clone> Clone for LegacySeqLogger<S> {
     fn clone(&self) -> Self {
         Self {
             storage: self.storage.clone(),
+            filter: self.filter.clone(),
         }
     }
 }
 
 impl<S> LegacySeqLogger<S> {
     pub fn new(storage: S) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            filter: EventFilter::new(),
+        }
+    }
+
+    /// Only write the events passing the filter, see [`EventFilter`].
+    pub fn with_filter(mut self, filter: EventFilter) -> Self {
+        self.filter = filter;
+        self
     }
 }
 
@@ -159,7 +182,83 @@ impl<S: TelemetryStorage> Log for LegacySeqLogger<S> {
             log_file.shutdown().await
         });
 
-        crate::span!(Arc::new(tx), group_id = group_id)
+        let exporter = Filtered {
+            filter: self.filter.clone(),
+            exporter: tx,
+        };
+        crate::span!(Arc::new(exporter), group_id = group_id)
+    }
+}
+
+/// Write the qlog of each connection in the qlog main schema, serialized as JSON-SEQ.
+///
+/// Different from [`LegacySeqLogger`], events are written as they are, without being rolled back to
+/// the legacy 0.3 schema, so no events are lost. Tools which only understand the legacy schema, such
+/// as [qvis], may not be able to read the files.
+///
+/// [qvis]: https://qvis.quictools.info/
+pub struct SeqLogger<S> {
+    storage: S,
+    filter: EventFilter,
+}
+
+impl<S: Clone> Clone for SeqLogger<S> {
+    fn clone(&self) -> Self {
+        Self {
+            storage: self.storage.clone(),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<S> SeqLogger<S> {
+    pub fn new(storage: S) -> Self {
+        Self {
+            storage,
+            filter: EventFilter::new(),
+        }
+    }
+
+    /// Only write the events passing the filter, see [`EventFilter`].
+    pub fn with_filter(mut self, filter: EventFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+impl<S: TelemetryStorage> Log for SeqLogger<S> {
+    fn new_trace(&self, vantage_point: VantagePointType, group_id: GroupID) -> Span {
+        use crate::{EvnetData, QlogFileSeq, exporter::write_json_seq};
+
+        let file_name = format!("{group_id}_{vantage_point}.sqlog");
+        let file = self.storage.join(&file_name);
+
+        let qlog_file_seq = crate::build!(QlogFileSeq {
+            log_file: crate::LogFile {
+                file_schema: QlogFileSeq::SCHEMA,
+                serialization_format: QlogFileSeq::SERIALIZATION_FORMAT,
+                title: file_name,
+                event_schemas: EvnetData::SCHEMAS.map(String::from).to_vec(),
+            },
+            trace: crate::TraceSeq {
+                vantage_point: VantagePoint {
+                    r#type: vantage_point
+                },
+            }
+        });
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+        tokio::spawn(async move {
+            let mut log_file = io::BufWriter::new(file.await);
+            write_json_seq(&mut log_file, &qlog_file_seq, &mut rx).await?;
+            log_file.shutdown().await
+        });
+
+        let exporter = Filtered {
+            filter: self.filter.clone(),
+            exporter: tx,
+        };
+        crate::span!(Arc::new(exporter), group_id = group_id)
     }
 }
 
@@ -206,6 +305,51 @@ mod tests {
         telemetry::{Instrument, Log, Span, handy::LegacySeqLogger},
     };
 
+    #[tokio::test]
+    #[cfg(feature = "telemetry")]
+    async fn seq_logger_keeps_all_events() {
+        use std::path::PathBuf;
+
+        use super::SeqLogger;
+        use crate::quic::connectivity::PathAssigned;
+
+        let dir = std::env::temp_dir().join(format!("qevent-seq-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let logger = SeqLogger::new(PathBuf::from(&dir));
+        let span = logger.new_trace(
+            crate::VantagePointType::Client,
+            crate::GroupID::from("seq_logger".to_string()),
+        );
+        span.in_scope(|| {
+            // path_assigned has no legacy form, it would be dropped by LegacySeqLogger
+            crate::event!(PathAssigned {
+                path_id: "new path".to_owned()
+            });
+        });
+        drop(span);
+
+        let file = dir.join("seq_logger_client.sqlog");
+        let mut content = String::new();
+        for _ in 0..1000 {
+            content = tokio::fs::read_to_string(&file).await.unwrap_or_default();
+            if content.matches('\x1e').count() == 2 && content.ends_with('\n') {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        let records = content
+            .split('\x1e')
+            .filter(|record| !record.is_empty())
+            .map(|record| serde_json::from_str::<serde_json::Value>(record).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["file_schema"], crate::QlogFileSeq::SCHEMA);
+        assert_eq!(records[1]["name"], "quic:path_assigned");
+
+        _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
     #[tokio::test]
     #[cfg(feature = "telemetry")]
     async fn legacy_seq_exporter() {
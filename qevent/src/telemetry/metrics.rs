@@ -0,0 +1,702 @@
+//! Aggregate qlog events into counters and histograms.
+//!
+//! Recording every event of every connection is too costly for production. [`MetricsLogger`] only
+//! subscribes to the handful of events it aggregates (packets, recovery metrics, connection state
+//! and closure), folds them into process wide metrics labeled by vantage point, and renders them
+//! in the [Prometheus text exposition format] on demand.
+//!
+//! [Prometheus text exposition format]: https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use tokio::{
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use super::{ExportEvent, Log, Span};
+use crate::{
+    BeSpecificEventData, Event, EvnetData, GroupID, VantagePointType,
+    analyzer::label,
+    quic::{
+        connectivity::{
+            BaseConnectionStates, ConnectionClosed, ConnectionCode, ConnectionStarted,
+            ConnectionState, ConnectionStateUpdated, GranularConnectionStates,
+        },
+        recovery::{PacketLost, RecoveryMetricsUpdated},
+        transport::{PacketDropped, PacketReceived, PacketSent},
+    },
+    span,
+};
+
+/// Upper bounds of the duration histograms, in seconds.
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+/// Upper bounds of the RTT histogram, in seconds.
+const RTT_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// Upper bounds of the congestion window histogram, in bytes.
+const CWND_BUCKETS: &[f64] = &[
+    4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0,
+];
+
+#[derive(Default)]
+struct Counter(AtomicU64);
+
+impl Counter {
+    fn add(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+struct Histogram {
+    bounds: &'static [f64],
+    /// Non-cumulative counts, the last one is the `+Inf` bucket.
+    buckets: Box<[AtomicU64]>,
+    /// The bits of the f64 sum of all observations.
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    fn observe(&self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        _ = self
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |sum| {
+                Some((f64::from_bits(sum) + value).to_bits())
+            });
+    }
+}
+
+/// The metrics of all connections seen from a vantage point.
+struct Metrics {
+    connections: Counter,
+    /// Traces dropped, used to derive the number of active connections.
+    finished: Counter,
+    handshakes: Counter,
+    /// Closed connections, by owner and error code.
+    closed: Mutex<BTreeMap<(String, String), u64>>,
+    packets_sent: Counter,
+    packets_received: Counter,
+    packets_lost: Counter,
+    packets_dropped: Counter,
+    bytes_sent: Counter,
+    bytes_received: Counter,
+    ptos: Counter,
+    handshake_duration: Histogram,
+    connection_duration: Histogram,
+    rtt: Histogram,
+    congestion_window: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            connections: Counter::default(),
+            finished: Counter::default(),
+            handshakes: Counter::default(),
+            closed: Mutex::default(),
+            packets_sent: Counter::default(),
+            packets_received: Counter::default(),
+            packets_lost: Counter::default(),
+            packets_dropped: Counter::default(),
+            bytes_sent: Counter::default(),
+            bytes_received: Counter::default(),
+            ptos: Counter::default(),
+            handshake_duration: Histogram::new(DURATION_BUCKETS),
+            connection_duration: Histogram::new(DURATION_BUCKETS),
+            rtt: Histogram::new(RTT_BUCKETS),
+            congestion_window: Histogram::new(CWND_BUCKETS),
+        }
+    }
+}
+
+const VANTAGE_POINTS: [VantagePointType; 4] = [
+    VantagePointType::Client,
+    VantagePointType::Server,
+    VantagePointType::Network,
+    VantagePointType::Unknow,
+];
+
+fn vantage_point_index(vantage_point: VantagePointType) -> usize {
+    match vantage_point {
+        VantagePointType::Client => 0,
+        VantagePointType::Server => 1,
+        VantagePointType::Network => 2,
+        VantagePointType::Unknow => 3,
+    }
+}
+
+type CounterFamily = (&'static str, &'static str, fn(&Metrics) -> &Counter);
+
+const COUNTERS: &[CounterFamily] = &[
+    ("quic_connections_total", "Connections traced.", |m| {
+        &m.connections
+    }),
+    (
+        "quic_handshakes_completed_total",
+        "Handshakes completed or confirmed.",
+        |m| &m.handshakes,
+    ),
+    ("quic_packets_sent_total", "Packets sent.", |m| {
+        &m.packets_sent
+    }),
+    ("quic_packets_received_total", "Packets received.", |m| {
+        &m.packets_received
+    }),
+    ("quic_packets_lost_total", "Packets declared lost.", |m| {
+        &m.packets_lost
+    }),
+    (
+        "quic_packets_dropped_total",
+        "Packets dropped before being processed.",
+        |m| &m.packets_dropped,
+    ),
+    ("quic_sent_bytes_total", "Bytes of the packets sent.", |m| {
+        &m.bytes_sent
+    }),
+    (
+        "quic_received_bytes_total",
+        "Bytes of the packets received.",
+        |m| &m.bytes_received,
+    ),
+    ("quic_pto_total", "Probe timeouts expired.", |m| &m.ptos),
+];
+
+type HistogramFamily = (&'static str, &'static str, fn(&Metrics) -> &Histogram);
+
+const HISTOGRAMS: &[HistogramFamily] = &[
+    (
+        "quic_handshake_duration_seconds",
+        "Time from the first event of a connection until its handshake is done.",
+        |m| &m.handshake_duration,
+    ),
+    (
+        "quic_connection_duration_seconds",
+        "Time from the first event of a connection until it is closed.",
+        |m| &m.connection_duration,
+    ),
+    ("quic_rtt_seconds", "Latest RTT samples.", |m| &m.rtt),
+    (
+        "quic_congestion_window_bytes",
+        "Congestion window updates.",
+        |m| &m.congestion_window,
+    ),
+];
+
+/// The `code` label of a closed connection.
+///
+/// Error codes are chosen by the peers and the applications, only the names of the transport
+/// errors are kept, the others are collapsed so that the label values stay bounded.
+fn close_code(closed: &ConnectionClosed) -> &'static str {
+    match (closed.connection_code(), closed.application_code()) {
+        (Some(ConnectionCode::TransportError(error)), _) => {
+            use crate::quic::TransportError::*;
+            match error {
+                NoError => "no_error",
+                InternalError => "internal_error",
+                ConnectionRefused => "connection_refused",
+                FlowControlError => "flow_control_error",
+                StreamLimitError => "stream_limit_error",
+                StreamStateError => "stream_state_error",
+                FinalSizeError => "final_size_error",
+                FrameEncodingError => "frame_encoding_error",
+                TransportParameterError => "transport_parameter_error",
+                ConnectionIdLimitError => "connection_id_limit_error",
+                ProtocolViolation => "protocol_violation",
+                InvalidToken => "invalid_token",
+                ApplicationError => "application_error",
+                CryptoBufferExceeded => "crypto_buffer_exceeded",
+                KeyUpdateError => "key_update_error",
+                AeadLimitReached => "aead_limit_reached",
+                NoViablePath => "no_viable_path",
+            }
+        }
+        (Some(ConnectionCode::CryptoError(_)), _) => "crypto_error",
+        (Some(ConnectionCode::Value(_)), _) => "other",
+        (None, Some(_)) => "application_error",
+        (None, None) => "none",
+    }
+}
+
+/// Escape a label value, see the [text format].
+///
+/// [text format]: https://prometheus.io/docs/instrumenting/exposition_formats/#comments-help-text-and-type-information
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// A [`Log`] that aggregates the events of all connections into metrics.
+///
+/// Clones share the same metrics. Use [`MetricsLogger::render`] to get the Prometheus text
+/// exposition of the metrics, or [`MetricsLogger::serve`] to expose them for scraping.
+#[derive(Clone)]
+pub struct MetricsLogger {
+    metrics: Arc<[Metrics; 4]>,
+}
+
+impl Default for MetricsLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricsLogger {
+    pub fn new() -> Self {
+        Self {
+            metrics: Arc::new(std::array::from_fn(|_| Metrics::new())),
+        }
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    ///
+    /// Only vantage points which have traced connections are present.
+    pub fn render(&self) -> String {
+        let traced = VANTAGE_POINTS
+            .iter()
+            .zip(self.metrics.iter())
+            .filter(|(_, metrics)| metrics.connections.get() > 0)
+            .collect::<Vec<_>>();
+
+        let mut out = String::new();
+        let header = |out: &mut String, name: &str, help: &str, r#type: &str| {
+            _ = writeln!(out, "# HELP {name} {help}");
+            _ = writeln!(out, "# TYPE {name} {type}");
+        };
+
+        for (name, help, counter) in COUNTERS {
+            header(&mut out, name, help, "counter");
+            for (vantage_point, metrics) in &traced {
+                let value = counter(metrics).get();
+                _ = writeln!(out, "{name}{{vantage_point=\"{vantage_point}\"}} {value}");
+            }
+        }
+
+        let name = "quic_connections_active";
+        header(&mut out, name, "Connections whose trace is alive.", "gauge");
+        for (vantage_point, metrics) in &traced {
+            // the counters are read separately, connections may finish in between
+            let active = metrics
+                .connections
+                .get()
+                .saturating_sub(metrics.finished.get());
+            _ = writeln!(out, "{name}{{vantage_point=\"{vantage_point}\"}} {active}");
+        }
+
+        let name = "quic_connections_closed_total";
+        header(
+            &mut out,
+            name,
+            "Connections closed, by owner and error code.",
+            "counter",
+        );
+        for (vantage_point, metrics) in &traced {
+            for ((owner, code), value) in metrics.closed.lock().unwrap().iter() {
+                _ = writeln!(
+                    out,
+                    "{name}{{vantage_point=\"{vantage_point}\",owner=\"{}\",code=\"{}\"}} {value}",
+                    escape(owner),
+                    escape(code)
+                );
+            }
+        }
+
+        for (name, help, histogram) in HISTOGRAMS {
+            header(&mut out, name, help, "histogram");
+            for (vantage_point, metrics) in &traced {
+                let histogram = histogram(metrics);
+                let mut cumulative = 0;
+                for (bucket, count) in histogram.buckets.iter().enumerate() {
+                    cumulative += count.load(Ordering::Relaxed);
+                    let le = match histogram.bounds.get(bucket) {
+                        Some(bound) => bound.to_string(),
+                        None => "+Inf".to_owned(),
+                    };
+                    _ = writeln!(
+                        out,
+                        "{name}_bucket{{vantage_point=\"{vantage_point}\",le=\"{le}\"}} {cumulative}"
+                    );
+                }
+                let sum = f64::from_bits(histogram.sum.load(Ordering::Relaxed));
+                _ = writeln!(out, "{name}_sum{{vantage_point=\"{vantage_point}\"}} {sum}");
+                _ = writeln!(
+                    out,
+                    "{name}_count{{vantage_point=\"{vantage_point}\"}} {cumulative}"
+                );
+            }
+        }
+
+        out
+    }
+
+    /// Answer every HTTP request accepted from the listener with the rendered metrics.
+    ///
+    /// This is a minimal HTTP/1.1 endpoint for Prometheus to scrape, the request path is ignored.
+    /// It only returns if accepting a connection fails.
+    pub async fn serve(self, listener: TcpListener) -> io::Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let logger = self.clone();
+            tokio::spawn(async move { _ = logger.respond(stream).await });
+        }
+    }
+
+    async fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+        const MAX_REQUEST_HEAD: usize = 8192;
+
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            if request.len() > MAX_REQUEST_HEAD {
+                return Ok(());
+            }
+            match stream.read(&mut buf).await? {
+                0 => return Ok(()),
+                read => request.extend_from_slice(&buf[..read]),
+            }
+        }
+
+        let (status, body) = if request.starts_with(b"GET ") {
+            ("200 OK", self.render())
+        } else {
+            ("405 Method Not Allowed", String::new())
+        };
+        let response = format!(
+            "HTTP/1.1 {status}\r\n\
+             Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+impl Log for MetricsLogger {
+    fn new_trace(&self, vantage_point: VantagePointType, group_id: GroupID) -> Span {
+        let trace = TraceMetrics {
+            metrics: self.metrics.clone(),
+            vantage_point: vantage_point_index(vantage_point),
+            state: Mutex::default(),
+        };
+        trace.metrics().connections.add(1);
+        span!(Arc::new(trace), group_id = group_id)
+    }
+}
+
+#[derive(Default)]
+struct TraceState {
+    started_at: Option<f64>,
+    handshake_done: bool,
+    closed: bool,
+    pto_count: u16,
+}
+
+/// The exporter of a single connection, which folds its events into the shared metrics.
+struct TraceMetrics {
+    metrics: Arc<[Metrics; 4]>,
+    vantage_point: usize,
+    state: Mutex<TraceState>,
+}
+
+impl TraceMetrics {
+    fn metrics(&self) -> &Metrics {
+        &self.metrics[self.vantage_point]
+    }
+}
+
+impl Drop for TraceMetrics {
+    fn drop(&mut self) {
+        self.metrics().finished.add(1);
+    }
+}
+
+impl ExportEvent for TraceMetrics {
+    fn emit(&self, event: Event) {
+        let metrics = self.metrics();
+        let time = *event.time();
+        let mut state = self.state.lock().unwrap();
+        let started_at = *state.started_at.get_or_insert(time);
+        let elapsed = (time - started_at) / 1000.0;
+
+        match event.data() {
+            EvnetData::ConnectionStateUpdated(updated) => match updated.new() {
+                ConnectionState::Base(BaseConnectionStates::HandshakeComplete)
+                | ConnectionState::Granular(GranularConnectionStates::HandshakeConfirmed)
+                    if !state.handshake_done =>
+                {
+                    state.handshake_done = true;
+                    metrics.handshakes.add(1);
+                    metrics.handshake_duration.observe(elapsed);
+                }
+                _ => {}
+            },
+            EvnetData::PacketSent(sent) => {
+                metrics.packets_sent.add(1);
+                if let Some(length) = sent.raw().as_ref().and_then(|raw| *raw.length()) {
+                    metrics.bytes_sent.add(length);
+                }
+            }
+            EvnetData::PacketReceived(received) => {
+                metrics.packets_received.add(1);
+                if let Some(length) = received.raw().as_ref().and_then(|raw| *raw.length()) {
+                    metrics.bytes_received.add(length);
+                }
+            }
+            EvnetData::PacketLost(_) => metrics.packets_lost.add(1),
+            EvnetData::PacketDropped(_) => metrics.packets_dropped.add(1),
+            EvnetData::RecoveryMetricsUpdated(updated) => {
+                if let Some(latest_rtt) = updated.latest_rtt() {
+                    metrics.rtt.observe(f64::from(*latest_rtt) / 1000.0);
+                }
+                if let Some(congestion_window) = updated.congestion_window() {
+                    metrics.congestion_window.observe(*congestion_window as f64);
+                }
+                if let Some(pto_count) = *updated.pto_count() {
+                    if pto_count > state.pto_count {
+                        metrics.ptos.add(u64::from(pto_count - state.pto_count));
+                    }
+                    state.pto_count = pto_count;
+                }
+            }
+            EvnetData::ConnectionClosed(closed) if !state.closed => {
+                state.closed = true;
+                let owner = closed.owner().as_ref().and_then(label);
+                let key = (
+                    owner.unwrap_or_else(|| "unknown".to_owned()),
+                    close_code(closed).to_owned(),
+                );
+                *metrics.closed.lock().unwrap().entry(key).or_default() += 1;
+                metrics.connection_duration.observe(elapsed);
+            }
+            _ => {}
+        }
+    }
+
+    fn filter_event(&self, scheme: &'static str) -> bool {
+        [
+            ConnectionStarted::scheme(),
+            ConnectionStateUpdated::scheme(),
+            ConnectionClosed::scheme(),
+            PacketSent::scheme(),
+            PacketReceived::scheme(),
+            PacketLost::scheme(),
+            PacketDropped::scheme(),
+            RecoveryMetricsUpdated::scheme(),
+        ]
+        .contains(&scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use qbase::cid::ConnectionId;
+
+    use super::*;
+    use crate::quic::{
+        Owner, PacketHeader, PacketType, connectivity::ConnectionCloseTrigger,
+        recovery::PacketLostTrigger,
+    };
+
+    fn event(time: f64, data: impl Into<EvnetData>) -> Event {
+        crate::build!(Event {
+            time: time,
+            data: data.into(),
+        })
+    }
+
+    fn one_rtt(pn: u64) -> PacketHeader {
+        crate::build!(PacketHeader {
+            packet_type: PacketType::OneRTT,
+            packet_number: pn,
+        })
+    }
+
+    fn trace(logger: &MetricsLogger, vantage_point: VantagePointType) -> Span {
+        let group_id = GroupID::from(ConnectionId::from_slice(&[0xab, 0xcd]));
+        logger.new_trace(vantage_point, group_id)
+    }
+
+    #[test]
+    fn aggregate_connections() {
+        let logger = MetricsLogger::new();
+        let client = trace(&logger, VantagePointType::Client);
+        let server = trace(&logger, VantagePointType::Server);
+        assert!(client.filter_event(PacketSent::scheme()));
+        assert!(!client.filter_event(crate::quic::transport::PacketsAcked::scheme()));
+
+        client.emit(event(
+            1000.0,
+            crate::build!(ConnectionStateUpdated {
+                new: BaseConnectionStates::Attempted,
+            }),
+        ));
+        client.emit(event(
+            1030.0,
+            crate::build!(ConnectionStateUpdated {
+                new: GranularConnectionStates::HandshakeConfirmed,
+            }),
+        ));
+        for pn in 0..3 {
+            let header = one_rtt(pn);
+            client.emit(event(
+                1040.0,
+                crate::build!(PacketSent {
+                    header: header,
+                    raw: crate::build!(crate::RawInfo { length: 1200_u64 }),
+                }),
+            ));
+        }
+        let header = one_rtt(1);
+        client.emit(event(
+            1060.0,
+            crate::build!(PacketLost {
+                header: header,
+                trigger: PacketLostTrigger::PtoExpired,
+            }),
+        ));
+        client.emit(event(
+            1060.0,
+            crate::build!(RecoveryMetricsUpdated {
+                latest_rtt: 20.0,
+                congestion_window: 12000_u64,
+                pto_count: 2_u16,
+            }),
+        ));
+        client.emit(event(
+            1070.0,
+            crate::build!(RecoveryMetricsUpdated { pto_count: 0_u16 }),
+        ));
+        client.emit(event(
+            1100.0,
+            crate::build!(ConnectionClosed {
+                owner: Owner::Local,
+                trigger: ConnectionCloseTrigger::Application,
+            }),
+        ));
+        drop(client);
+
+        let header = one_rtt(0);
+        server.emit(event(
+            1000.0,
+            crate::build!(PacketReceived {
+                header: header,
+                raw: crate::build!(crate::RawInfo { length: 1200_u64 }),
+            }),
+        ));
+
+        let text = logger.render();
+        for line in [
+            "# TYPE quic_packets_sent_total counter",
+            "quic_connections_total{vantage_point=\"client\"} 1",
+            "quic_connections_total{vantage_point=\"server\"} 1",
+            "quic_connections_active{vantage_point=\"client\"} 0",
+            "quic_connections_active{vantage_point=\"server\"} 1",
+            "quic_handshakes_completed_total{vantage_point=\"client\"} 1",
+            "quic_packets_sent_total{vantage_point=\"client\"} 3",
+            "quic_sent_bytes_total{vantage_point=\"client\"} 3600",
+            "quic_packets_lost_total{vantage_point=\"client\"} 1",
+            "quic_pto_total{vantage_point=\"client\"} 2",
+            "quic_packets_received_total{vantage_point=\"server\"} 1",
+            "quic_received_bytes_total{vantage_point=\"server\"} 1200",
+            "quic_connections_closed_total{vantage_point=\"client\",owner=\"local\",code=\"none\"} 1",
+            "# TYPE quic_handshake_duration_seconds histogram",
+            "quic_handshake_duration_seconds_bucket{vantage_point=\"client\",le=\"0.025\"} 0",
+            "quic_handshake_duration_seconds_bucket{vantage_point=\"client\",le=\"0.05\"} 1",
+            "quic_handshake_duration_seconds_bucket{vantage_point=\"client\",le=\"+Inf\"} 1",
+            "quic_handshake_duration_seconds_count{vantage_point=\"client\"} 1",
+            "quic_connection_duration_seconds_sum{vantage_point=\"client\"} 0.1",
+            "quic_rtt_seconds_bucket{vantage_point=\"client\",le=\"0.025\"} 1",
+            "quic_congestion_window_bytes_bucket{vantage_point=\"client\",le=\"16384\"} 1",
+            "quic_rtt_seconds_count{vantage_point=\"server\"} 0",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing `{line}` in:\n{text}"
+            );
+        }
+        assert!(!text.contains("vantage_point=\"network\""));
+    }
+
+    #[test]
+    fn bounded_close_codes() {
+        use crate::quic::{ApplicationCode, TransportError};
+
+        let closed = |connection_code: Option<ConnectionCode>,
+                      application_code: Option<ApplicationCode>| {
+            crate::build!(ConnectionClosed {
+                owner: Owner::Remote,
+                ?connection_code: connection_code,
+                ?application_code: application_code,
+            })
+        };
+        let cases = [
+            (
+                closed(Some(TransportError::ProtocolViolation.into()), None),
+                "protocol_violation",
+            ),
+            (closed(Some(ConnectionCode::Value(0x1234)), None), "other"),
+            (
+                closed(None, Some(ApplicationCode::Value(0x100))),
+                "application_error",
+            ),
+            (closed(None, None), "none"),
+        ];
+        for (closed, code) in cases {
+            assert_eq!(close_code(&closed), code);
+        }
+    }
+
+    #[tokio::test]
+    async fn serve_metrics() {
+        let logger = MetricsLogger::new();
+        let _trace = trace(&logger, VantagePointType::Server);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(logger.serve(listener));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(response.contains("quic_connections_total{vantage_point=\"server\"} 1\n"));
+    }
+}
@@ -0,0 +1,526 @@
+//! A [`TelemetryStorage`] for long running processes.
+//!
+//! [`RotatingStorage`] keeps the qlog files of a directory within a total size budget and a file
+//! count, deleting the oldest finished traces to make room for new ones, and optionally compresses
+//! the traces as they are written.
+//!
+//! [`TelemetryStorage`]: super::handy::TelemetryStorage
+use std::{
+    collections::VecDeque,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    task::{Context, Poll, ready},
+    time::SystemTime,
+};
+
+use tokio::{
+    io::{self, AsyncWrite},
+    task::JoinHandle,
+};
+
+use super::handy;
+
+/// How the traces are compressed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Write plain `.sqlog` files.
+    #[default]
+    None,
+    /// Write `.sqlog.gz` files, requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Write `.sqlog.zst` files, requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    fn extension(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => ".gz",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    fn encode(
+        self,
+        writer: impl AsyncWrite + Send + Unpin + 'static,
+    ) -> Box<dyn AsyncWrite + Send + Unpin> {
+        match self {
+            Compression::None => Box::new(writer),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                Box::new(async_compression::tokio::write::GzipEncoder::new(writer))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                Box::new(async_compression::tokio::write::ZstdEncoder::new(writer))
+            }
+        }
+    }
+}
+
+/// Whether the file looks like a trace written by a [`RotatingStorage`], compressed or not.
+fn is_trace(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| {
+            [".sqlog", ".sqlog.gz", ".sqlog.zst"]
+                .iter()
+                .any(|ext| name.ends_with(ext))
+        })
+}
+
+struct TraceFile {
+    path: PathBuf,
+    size: AtomicU64,
+    writing: AtomicBool,
+}
+
+/// Store each trace in a file of a directory, within a size budget and a file count.
+///
+/// The traces already in the directory when the first trace is created are taken into account,
+/// oldest first. When a trace is created or a write would exceed the budget, the oldest finished
+/// traces are deleted. If there is still no room, because the budget is taken by traces still
+/// being written, the new trace is skipped, or the trace being written is cut short.
+///
+/// Unlike the storage of [`PathBuf`], failing to create the directory or the file, for example
+/// when the disk is full, never panics: the writes to the trace fail, and the connection is simply
+/// not traced.
+///
+/// Clones share the same budget.
+#[derive(Clone)]
+pub struct RotatingStorage {
+    dir: PathBuf,
+    max_total_size: u64,
+    max_files: usize,
+    compression: Compression,
+    /// `None` until the directory is scanned.
+    files: Arc<Mutex<Option<VecDeque<Arc<TraceFile>>>>>,
+    used: Arc<AtomicU64>,
+}
+
+impl RotatingStorage {
+    /// The default size budget, 1 GiB.
+    pub const DEFAULT_MAX_TOTAL_SIZE: u64 = 1 << 30;
+    /// The default file count.
+    pub const DEFAULT_MAX_FILES: usize = 1024;
+
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_total_size: Self::DEFAULT_MAX_TOTAL_SIZE,
+            max_files: Self::DEFAULT_MAX_FILES,
+            compression: Compression::None,
+            files: Arc::default(),
+            used: Arc::default(),
+        }
+    }
+
+    /// The maximum bytes of all traces in the directory, after compression.
+    pub fn with_max_total_size(mut self, max_total_size: u64) -> Self {
+        self.max_total_size = max_total_size;
+        self
+    }
+
+    /// The maximum number of traces kept in the directory.
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// The bytes currently taken by the traces in the directory.
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Acquire)
+    }
+
+    fn scan(&self) -> io::Result<VecDeque<Arc<TraceFile>>> {
+        fs::create_dir_all(&self.dir)?;
+        let mut traces = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() || !is_trace(&entry.path()) {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            traces.push((modified, entry.path(), metadata.len()));
+        }
+        traces.sort();
+        let used = traces.iter().map(|(_, _, size)| size).sum();
+        self.used.store(used, Ordering::Release);
+        Ok(traces
+            .into_iter()
+            .map(|(_, path, size)| {
+                Arc::new(TraceFile {
+                    path,
+                    size: AtomicU64::new(size),
+                    writing: AtomicBool::new(false),
+                })
+            })
+            .collect())
+    }
+
+    /// Delete the oldest finished traces until there is room for `new_files` more files and
+    /// `new_bytes` more bytes.
+    fn make_room(
+        &self,
+        files: &mut VecDeque<Arc<TraceFile>>,
+        new_files: usize,
+        new_bytes: u64,
+    ) -> io::Result<()> {
+        while files.len() + new_files > self.max_files
+            || self.used() + new_bytes > self.max_total_size
+        {
+            let Some(index) = files
+                .iter()
+                .position(|trace| !trace.writing.load(Ordering::Acquire))
+            else {
+                return Err(io::Error::other(format!(
+                    "qlog storage {} is full of traces being written",
+                    self.dir.display()
+                )));
+            };
+            match fs::remove_file(&files[index].path) {
+                Ok(()) => {}
+                Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+                Err(error) => return Err(error),
+            }
+            let oldest = files.remove(index).expect("index is valid");
+            self.used
+                .fetch_sub(oldest.size.load(Ordering::Acquire), Ordering::AcqRel);
+        }
+        Ok(())
+    }
+
+    fn create(&self, file_name: &str) -> io::Result<(fs::File, Arc<TraceFile>)> {
+        let mut files = self.files.lock().unwrap();
+        let files = match &mut *files {
+            Some(files) => files,
+            files => files.insert(self.scan()?),
+        };
+
+        let path = self.dir.join(file_name);
+        // The file is truncated below, forget the trace of the same connection written before.
+        if let Some(index) = files.iter().position(|trace| trace.path == path) {
+            let previous = files.remove(index).expect("index is valid");
+            self.used
+                .fetch_sub(previous.size.load(Ordering::Acquire), Ordering::AcqRel);
+        }
+        self.make_room(files, 1, 0)?;
+
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&path)?;
+        let trace = Arc::new(TraceFile {
+            path,
+            size: AtomicU64::new(0),
+            writing: AtomicBool::new(true),
+        });
+        files.push_back(trace.clone());
+        Ok((file, trace))
+    }
+
+    /// Take `bytes` from the budget if there is room, the check and the take are atomic so that
+    /// concurrent writers can not exceed the budget together.
+    fn try_take(&self, bytes: u64) -> bool {
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(bytes)
+                    .filter(|&used| used <= self.max_total_size)
+            })
+            .is_ok()
+    }
+
+    /// Give back the `bytes` taken from the budget but not written.
+    fn give_back(&self, bytes: u64) {
+        self.used.fetch_sub(bytes, Ordering::AcqRel);
+    }
+
+    /// Take `bytes` from the budget, deleting the oldest finished traces to make room, blocking.
+    fn reserve(&self, bytes: u64) -> io::Result<()> {
+        if self.try_take(bytes) {
+            return Ok(());
+        }
+        let mut files = self.files.lock().unwrap();
+        let Some(files) = &mut *files else {
+            return Err(io::Error::other(format!(
+                "qlog storage {} is not scanned yet",
+                self.dir.display()
+            )));
+        };
+        // 其他写入者不持有锁就可以占用预算，腾出空间后可能仍然抢不到，需要重试
+        loop {
+            self.make_room(files, 0, bytes)?;
+            if self.try_take(bytes) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl handy::TelemetryStorage for RotatingStorage {
+    fn join(
+        &self,
+        file_name: &str,
+    ) -> impl Future<Output = impl AsyncWrite + Send + Unpin + 'static> + Send + 'static {
+        let storage = self.clone();
+        let file_name = format!("{file_name}{}", self.compression.extension());
+        async move {
+            let created = {
+                let storage = storage.clone();
+                let file_name = file_name.clone();
+                tokio::task::spawn_blocking(move || storage.create(&file_name))
+                    .await
+                    .map_err(io::Error::other)
+                    .and_then(|created| created)
+            };
+            let (file, trace) = match created {
+                Ok(created) => created,
+                Err(error) => {
+                    tracing::warn!(target: "qlog", "failed to create qlog file {file_name}: {error}, the connection will not be traced");
+                    return Box::new(Untraced(error.kind())) as Box<dyn AsyncWrite + Send + Unpin>;
+                }
+            };
+            let compression = storage.compression;
+            let writer = TraceWriter {
+                file: tokio::fs::File::from_std(file),
+                trace,
+                storage,
+                reserving: None,
+            };
+            compression.encode(writer)
+        }
+    }
+}
+
+/// The trace file could not be created, every write fails so that the logger stops.
+struct Untraced(io::ErrorKind);
+
+impl Untraced {
+    fn error(&self) -> io::Error {
+        io::Error::new(self.0, "the qlog file was not created")
+    }
+}
+
+impl AsyncWrite for Untraced {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, _: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(Err(self.error()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Err(self.error()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Err(self.error()))
+    }
+}
+
+/// Account the bytes written to a trace file in the budget of the storage.
+struct TraceWriter {
+    file: tokio::fs::File,
+    trace: Arc<TraceFile>,
+    storage: RotatingStorage,
+    /// Deleting old traces to make room for the write, which blocks on the file system.
+    reserving: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl TraceWriter {
+    /// Take `bytes` from the budget before writing them.
+    fn poll_reserve(&mut self, cx: &mut Context<'_>, bytes: u64) -> Poll<io::Result<()>> {
+        if self.reserving.is_none() && self.storage.try_take(bytes) {
+            return Poll::Ready(Ok(()));
+        }
+        let reserving = self.reserving.get_or_insert_with(|| {
+            let storage = self.storage.clone();
+            tokio::task::spawn_blocking(move || storage.reserve(bytes))
+        });
+        let result = ready!(Pin::new(reserving).poll(cx));
+        self.reserving = None;
+        Poll::Ready(result.map_err(io::Error::other)?)
+    }
+}
+
+impl AsyncWrite for TraceWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let reserved = buf.len() as u64;
+        ready!(this.poll_reserve(cx, reserved))?;
+        let poll = Pin::new(&mut this.file).poll_write(cx, buf);
+        // 预先占用的预算中未写入的部分需要归还，Pending时下次poll会重新占用
+        let written = match &poll {
+            Poll::Ready(Ok(written)) => *written as u64,
+            _ => 0,
+        };
+        this.trace.size.fetch_add(written, Ordering::AcqRel);
+        this.storage.give_back(reserved - written);
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().file).poll_shutdown(cx)
+    }
+}
+
+impl Drop for TraceWriter {
+    fn drop(&mut self) {
+        self.trace.writing.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qevent-{name}-{}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn traces(dir: &Path) -> Vec<String> {
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    async fn write_trace(storage: &RotatingStorage, name: &str, content: &[u8]) -> io::Result<()> {
+        let mut file = handy::TelemetryStorage::join(storage, name).await;
+        file.write_all(content).await?;
+        file.shutdown().await
+    }
+
+    #[tokio::test]
+    async fn retain_newest_files() {
+        let dir = temp_dir("retain");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("old_client.sqlog"), b"old").unwrap();
+        fs::write(dir.join("notes.txt"), b"not a trace").unwrap();
+
+        let storage = RotatingStorage::new(&dir).with_max_files(2);
+        for name in ["a_client.sqlog", "b_client.sqlog", "c_client.sqlog"] {
+            write_trace(&storage, name, b"events").await.unwrap();
+        }
+
+        assert_eq!(
+            traces(&dir),
+            ["b_client.sqlog", "c_client.sqlog", "notes.txt"]
+        );
+        assert_eq!(storage.used(), 12);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keep_within_budget() {
+        let dir = temp_dir("budget");
+        let storage = RotatingStorage::new(&dir).with_max_total_size(100);
+
+        write_trace(&storage, "a_client.sqlog", &[0; 60])
+            .await
+            .unwrap();
+        write_trace(&storage, "b_client.sqlog", &[0; 60])
+            .await
+            .unwrap();
+        assert_eq!(traces(&dir), ["b_client.sqlog"]);
+        assert_eq!(storage.used(), 60);
+
+        // The trace being written can not be deleted to make room for itself.
+        let mut writing = handy::TelemetryStorage::join(&storage, "c_client.sqlog").await;
+        writing.write_all(&[0; 90]).await.unwrap();
+        writing.flush().await.unwrap();
+        assert!(writing.write_all(&[0; 20]).await.is_err());
+        drop(writing);
+        assert_eq!(traces(&dir), ["c_client.sqlog"]);
+        assert!(storage.used() <= 100);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn concurrent_writers_share_budget() {
+        let dir = temp_dir("concurrent");
+        let storage = RotatingStorage::new(&dir).with_max_total_size(100);
+
+        let mut a = handy::TelemetryStorage::join(&storage, "a_client.sqlog").await;
+        let mut b = handy::TelemetryStorage::join(&storage, "b_client.sqlog").await;
+        let (a, b) = tokio::join!(a.write_all(&[0; 60]), b.write_all(&[0; 60]));
+        assert!(a.is_ok() != b.is_ok());
+        assert_eq!(storage.used(), 60);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reserve_before_scan() {
+        let storage = RotatingStorage::new(temp_dir("unscanned")).with_max_total_size(0);
+        assert!(storage.reserve(0).is_ok());
+        assert!(storage.reserve(1).is_err());
+    }
+
+    #[tokio::test]
+    async fn skip_unwritable_directory() {
+        let dir = temp_dir("unwritable");
+        fs::write(&dir, b"a file, not a directory").unwrap();
+
+        let storage = RotatingStorage::new(dir.join("qlog"));
+        assert!(
+            write_trace(&storage, "a_client.sqlog", b"events")
+                .await
+                .is_err()
+        );
+        assert_eq!(storage.used(), 0);
+        fs::remove_file(&dir).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[tokio::test]
+    async fn compress_traces() {
+        use tokio::io::AsyncReadExt;
+
+        let dir = temp_dir("gzip");
+        let storage = RotatingStorage::new(&dir).with_compression(Compression::Gzip);
+        write_trace(&storage, "a_client.sqlog", b"\x1e{}\n")
+            .await
+            .unwrap();
+        assert_eq!(traces(&dir), ["a_client.sqlog.gz"]);
+
+        let compressed = tokio::fs::File::open(dir.join("a_client.sqlog.gz"))
+            .await
+            .unwrap();
+        let mut decoder = async_compression::tokio::bufread::GzipDecoder::new(
+            tokio::io::BufReader::new(compressed),
+        );
+        let mut content = vec![];
+        decoder.read_to_end(&mut content).await.unwrap();
+        assert_eq!(content, b"\x1e{}\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
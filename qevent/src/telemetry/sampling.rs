@@ -0,0 +1,475 @@
+//! Record only the connections worth recording.
+//!
+//! [`SampledLogger`] wraps another [`Log`] and decides, per connection, whether the events are
+//! passed on to it, according to a [`SamplingPolicy`]: a share of the connections, connections to
+//! some server names or from some peer addresses, or connections closed with an error. Recording
+//! can also be started or stopped at runtime, see [`Span::set_recording`].
+use std::{
+    collections::VecDeque,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+
+use super::{ExportEvent, Log, Span};
+use crate::{
+    BeEventData, Event, EvnetData, GroupID, VantagePointType,
+    quic::{
+        ApplicationCode, TransportError,
+        connectivity::{ConnectionCloseTrigger, ConnectionClosed, ConnectionCode},
+    },
+    span,
+};
+
+/// Which connections a [`SampledLogger`] records.
+///
+/// A connection is recorded as soon as one of the rules matches. Until then its events are
+/// buffered, so a recorded trace is complete from the beginning, as long as the buffer did not
+/// overflow.
+#[derive(Debug, Clone)]
+pub struct SamplingPolicy {
+    ratio: f64,
+    server_names: Vec<String>,
+    peer_ips: Vec<IpAddr>,
+    errors: bool,
+    max_buffered_events: usize,
+}
+
+impl Default for SamplingPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SamplingPolicy {
+    /// The default maximum number of events buffered for an undecided connection.
+    pub const DEFAULT_MAX_BUFFERED_EVENTS: usize = 4096;
+
+    /// A policy that records nothing, unless recording is started at runtime.
+    pub fn new() -> Self {
+        Self {
+            ratio: 0.0,
+            server_names: vec![],
+            peer_ips: vec![],
+            errors: false,
+            max_buffered_events: Self::DEFAULT_MAX_BUFFERED_EVENTS,
+        }
+    }
+
+    /// Record a random share of the connections, from `0.0` (none) to `1.0` (all).
+    pub fn with_ratio(mut self, ratio: f64) -> Self {
+        self.ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Record the connections to the server name.
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_names.push(server_name.into());
+        self
+    }
+
+    /// Record the connections with the peer address, the client address for servers.
+    pub fn with_peer_ip(mut self, peer_ip: IpAddr) -> Self {
+        self.peer_ips.push(peer_ip);
+        self
+    }
+
+    /// Record the connections closed abnormally: with a transport error, a non-zero application
+    /// error code, an idle timeout or a stateless reset.
+    ///
+    /// Events are buffered until the connection is closed, and discarded if it is closed cleanly.
+    pub fn with_errors(mut self, errors: bool) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// The maximum number of events buffered for an undecided connection, the oldest events are
+    /// discarded first.
+    pub fn with_max_buffered_events(mut self, max_buffered_events: usize) -> Self {
+        self.max_buffered_events = max_buffered_events;
+        self
+    }
+
+    fn sample(&self) -> bool {
+        rand::random::<f64>() < self.ratio
+    }
+}
+
+/// Whether the connection was closed abnormally, see [`SamplingPolicy::with_errors`].
+fn is_abnormal(closed: &ConnectionClosed) -> bool {
+    let trigger = matches!(
+        closed.trigger(),
+        Some(
+            ConnectionCloseTrigger::IdleTimeout
+                | ConnectionCloseTrigger::Error
+                | ConnectionCloseTrigger::VersionMismatch
+                | ConnectionCloseTrigger::StatelessReset
+        )
+    );
+    let connection_code = match closed.connection_code() {
+        Some(ConnectionCode::TransportError(error)) => *error != TransportError::NoError,
+        Some(ConnectionCode::CryptoError(_)) => true,
+        Some(ConnectionCode::Value(value)) => *value != 0,
+        None => false,
+    };
+    let application_code = match closed.application_code() {
+        Some(ApplicationCode::ApplicationError(_)) => true,
+        Some(ApplicationCode::Value(value)) => *value != 0,
+        None => false,
+    };
+    trigger || connection_code || application_code
+}
+
+/// A [`Log`] that passes the events of a connection to the inner logger only if the connection
+/// is sampled by the [`SamplingPolicy`], or recording is started at runtime.
+///
+/// The trace of the inner logger is created when recording starts, and kept until the connection
+/// is gone, so stopping and starting again appends to the same trace.
+pub struct SampledLogger<L> {
+    inner: Arc<L>,
+    policy: Arc<SamplingPolicy>,
+}
+
+impl<L> Clone for SampledLogger<L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<L> SampledLogger<L> {
+    pub fn new(inner: L, policy: SamplingPolicy) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            policy: Arc::new(policy),
+        }
+    }
+}
+
+impl<L: Log + Send + Sync + 'static> Log for SampledLogger<L> {
+    fn new_trace(&self, vantage_point: VantagePointType, group_id: GroupID) -> Span {
+        let policy = &self.policy;
+        let trace = SampledTrace {
+            inner: self.inner.clone(),
+            policy: policy.clone(),
+            vantage_point,
+            group_id: group_id.clone(),
+            state: Mutex::new(TraceState {
+                recording: false,
+                span: None,
+                buffer: VecDeque::new(),
+                peer_checked: false,
+                server_name_checked: false,
+                closed: false,
+            }),
+        };
+        {
+            let mut state = trace.state.lock().unwrap();
+            if policy.sample() {
+                trace.start(&mut state);
+            }
+        }
+        span!(Arc::new(trace), group_id = group_id)
+    }
+}
+
+struct TraceState {
+    recording: bool,
+    /// The trace of the inner logger, created when recording starts for the first time.
+    span: Option<Span>,
+    buffer: VecDeque<Event>,
+    peer_checked: bool,
+    server_name_checked: bool,
+    closed: bool,
+}
+
+struct SampledTrace<L> {
+    inner: Arc<L>,
+    policy: Arc<SamplingPolicy>,
+    vantage_point: VantagePointType,
+    group_id: GroupID,
+    state: Mutex<TraceState>,
+}
+
+impl<L: Log> SampledTrace<L> {
+    fn start(&self, state: &mut TraceState) {
+        let span = state.span.get_or_insert_with(|| {
+            self.inner
+                .new_trace(self.vantage_point, self.group_id.clone())
+        });
+        for event in state.buffer.drain(..) {
+            if span.filter_event(event.data().scheme()) {
+                span.emit(event);
+            }
+        }
+        state.recording = true;
+    }
+
+    /// Whether the events of an unrecorded connection should be kept, because a rule may match
+    /// later.
+    fn undecided(&self, state: &TraceState) -> bool {
+        let policy = &self.policy;
+        !state.recording
+            && !state.closed
+            && (policy.errors
+                || (!policy.peer_ips.is_empty() && !state.peer_checked)
+                || (!policy.server_names.is_empty() && !state.server_name_checked))
+    }
+}
+
+impl<L: Log + Send + Sync> ExportEvent for SampledTrace<L> {
+    fn emit(&self, event: Event) {
+        let mut state = self.state.lock().unwrap();
+        if state.recording {
+            if let Some(span) = &state.span {
+                span.emit(event);
+            }
+            return;
+        }
+        if !self.undecided(&state) {
+            return;
+        }
+
+        let matched = match event.data() {
+            EvnetData::ConnectionStarted(started) => {
+                state.peer_checked = true;
+                String::from(started.dst_ip().clone())
+                    .parse::<IpAddr>()
+                    .is_ok_and(|peer_ip| self.policy.peer_ips.contains(&peer_ip))
+            }
+            EvnetData::ConnectionClosed(closed) => {
+                state.closed = true;
+                self.policy.errors && is_abnormal(closed)
+            }
+            _ => false,
+        };
+
+        if state.buffer.len() >= self.policy.max_buffered_events {
+            state.buffer.pop_front();
+        }
+        state.buffer.push_back(event);
+        if matched {
+            self.start(&mut state);
+        } else if !self.undecided(&state) {
+            state.buffer = VecDeque::new();
+        }
+    }
+
+    fn filter_event(&self, scheme: &'static str) -> bool {
+        let state = self.state.lock().unwrap();
+        match &state.span {
+            Some(span) if state.recording => span.filter_event(scheme),
+            _ => self.undecided(&state),
+        }
+    }
+
+    fn filter_raw_data(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        match &state.span {
+            Some(span) if state.recording => span.filter_raw_data(),
+            _ => false,
+        }
+    }
+
+    fn set_recording(&self, recording: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if recording {
+            self.start(&mut state);
+        } else {
+            state.recording = false;
+            // Stopped explicitly, do not let the rules start it again.
+            state.closed = true;
+            state.buffer = VecDeque::new();
+        }
+        true
+    }
+
+    fn on_server_name(&self, server_name: &str) {
+        let mut state = self.state.lock().unwrap();
+        if !self.undecided(&state) || state.server_name_checked {
+            return;
+        }
+        state.server_name_checked = true;
+        if self
+            .policy
+            .server_names
+            .iter()
+            .any(|name| name == server_name)
+        {
+            self.start(&mut state);
+        } else if !self.undecided(&state) {
+            state.buffer = VecDeque::new();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use qbase::cid::ConnectionId;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::quic::{
+        IpVersion, Owner, PacketHeader, PacketType,
+        connectivity::{BaseConnectionStates, ConnectionStarted, ConnectionStateUpdated},
+        transport::PacketSent,
+    };
+
+    /// Collect the events of all traces.
+    struct Collector(mpsc::UnboundedSender<Event>);
+
+    impl Log for Collector {
+        fn new_trace(&self, _: VantagePointType, group_id: GroupID) -> Span {
+            span!(Arc::new(self.0.clone()), group_id = group_id)
+        }
+    }
+
+    fn logger(
+        policy: SamplingPolicy,
+    ) -> (SampledLogger<Collector>, mpsc::UnboundedReceiver<Event>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (SampledLogger::new(Collector(tx), policy), rx)
+    }
+
+    fn trace<L: Log>(logger: &L) -> Span {
+        let group_id = GroupID::from(ConnectionId::from_slice(&[0xab, 0xcd]));
+        logger.new_trace(VantagePointType::Server, group_id)
+    }
+
+    fn event(data: impl Into<EvnetData>) -> Event {
+        crate::build!(Event {
+            time: 0.0,
+            data: data.into(),
+        })
+    }
+
+    fn started(peer_ip: &str) -> Event {
+        event(crate::build!(ConnectionStarted {
+            ip_version: IpVersion::V4,
+            src_ip: "127.0.0.1".to_owned(),
+            dst_ip: peer_ip.to_owned(),
+        }))
+    }
+
+    fn attempted() -> Event {
+        event(crate::build!(ConnectionStateUpdated {
+            new: BaseConnectionStates::Attempted,
+        }))
+    }
+
+    fn sent() -> Event {
+        let header = crate::build!(PacketHeader {
+            packet_type: PacketType::OneRTT,
+            packet_number: 0u64,
+        });
+        event(crate::build!(PacketSent { header: header }))
+    }
+
+    fn sent_scheme() -> &'static str {
+        <PacketSent as crate::BeSpecificEventData>::scheme()
+    }
+
+    fn closed(code: TransportError) -> Event {
+        let connection_code = ConnectionCode::TransportError(code);
+        event(crate::build!(ConnectionClosed {
+            owner: Owner::Local,
+            connection_code: connection_code,
+        }))
+    }
+
+    fn drain(rx: &mut mpsc::UnboundedReceiver<Event>) -> Vec<Event> {
+        std::iter::from_fn(|| rx.try_recv().ok()).collect()
+    }
+
+    #[test]
+    fn sample_by_ratio() {
+        let (all, mut rx) = logger(SamplingPolicy::new().with_ratio(1.0));
+        let span = trace(&all);
+        assert!(span.filter_event(sent_scheme()));
+        span.emit(sent());
+        assert_eq!(drain(&mut rx).len(), 1);
+
+        let (none, mut rx) = logger(SamplingPolicy::new());
+        let span = trace(&none);
+        assert!(!span.filter_event(sent_scheme()));
+        span.emit(sent());
+        assert!(drain(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn sample_by_peer_ip() {
+        let policy = SamplingPolicy::new().with_peer_ip("10.0.0.1".parse().unwrap());
+        let (logger, mut rx) = logger(policy);
+
+        let matched = trace(&logger);
+        matched.emit(attempted());
+        assert!(drain(&mut rx).is_empty());
+        matched.emit(started("10.0.0.1"));
+        matched.emit(sent());
+        assert_eq!(drain(&mut rx).len(), 3);
+
+        let unmatched = trace(&logger);
+        unmatched.emit(attempted());
+        unmatched.emit(started("10.0.0.2"));
+        assert!(!unmatched.filter_event(sent_scheme()));
+        assert!(drain(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn sample_by_server_name() {
+        let (logger, mut rx) = logger(SamplingPolicy::new().with_server_name("example.com"));
+
+        let matched = trace(&logger);
+        matched.emit(attempted());
+        matched.on_server_name("example.com");
+        matched.emit(sent());
+        assert_eq!(drain(&mut rx).len(), 2);
+
+        let unmatched = trace(&logger);
+        unmatched.emit(attempted());
+        unmatched.on_server_name("other.com");
+        assert!(!unmatched.filter_event(sent_scheme()));
+        assert!(drain(&mut rx).is_empty());
+    }
+
+    #[test]
+    fn flush_on_error() {
+        let policy = SamplingPolicy::new()
+            .with_errors(true)
+            .with_max_buffered_events(2);
+        let (logger, mut rx) = logger(policy);
+
+        let clean = trace(&logger);
+        clean.emit(attempted());
+        clean.emit(closed(TransportError::NoError));
+        assert!(!clean.filter_event(sent_scheme()));
+        assert!(drain(&mut rx).is_empty());
+
+        let failed = trace(&logger);
+        failed.emit(attempted());
+        failed.emit(sent());
+        failed.emit(closed(TransportError::ProtocolViolation));
+        let events = drain(&mut rx);
+        // The oldest event is discarded when the buffer overflows
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1].data(), EvnetData::ConnectionClosed(_)));
+    }
+
+    #[test]
+    fn start_and_stop_at_runtime() {
+        let (logger, mut rx) = logger(SamplingPolicy::new());
+        let span = trace(&logger);
+        span.emit(sent());
+        assert!(span.set_recording(true));
+        span.emit(sent());
+        assert!(span.set_recording(false));
+        span.emit(sent());
+        assert!(span.set_recording(true));
+        span.emit(sent());
+        assert_eq!(drain(&mut rx).len(), 2);
+
+        let unsupported = trace(&crate::telemetry::handy::NoopLogger);
+        assert!(!unsupported.set_recording(true));
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use qbase::net::{
+    addr::BindUri,
+    route::{Link, PacketHeader},
+};
+use qinterface::{
+    QuicIO, QuicIoExt,
+    iface::handy::{qudp, uring},
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "io-bench",
+    about = "Compare the throughput of qudp and io_uring on loopback"
+)]
+struct Options {
+    #[arg(
+        long,
+        short,
+        default_value_t = 1_000_000,
+        help = "Number of packets to send"
+    )]
+    packets: usize,
+    #[arg(long, short, default_value_t = 1200, help = "Size of each packet")]
+    size: usize,
+    #[arg(long, default_value = "inet://127.0.0.1:0", help = "Address to bind")]
+    bind: String,
+}
+
+struct Report {
+    sent: usize,
+    received: usize,
+    elapsed: Duration,
+}
+
+async fn bench(
+    sender: &dyn QuicIO,
+    receiver: &dyn QuicIO,
+    packets: usize,
+    size: usize,
+) -> io::Result<Report> {
+    let link = Link::new(sender.real_addr()?, receiver.real_addr()?);
+    let hdr = PacketHeader::new(link.clone().into(), link, 64, None, size as u16);
+    let payload = vec![0u8; size];
+    let batch = vec![io::IoSlice::new(&payload); sender.max_segments()?];
+
+    let start = Instant::now();
+    let send = async {
+        let mut sent = 0;
+        while sent < packets {
+            let count = batch.len().min(packets - sent);
+            sender.sendmmsg(&batch[..count], hdr.clone()).await?;
+            sent += count;
+            // 让出执行权，让接收方及时取出数据
+            tokio::task::yield_now().await;
+        }
+        io::Result::Ok(sent)
+    };
+    let receive = async {
+        let (mut bufs, mut hdrs) = (vec![], vec![]);
+        let mut received = 0;
+        // 回环接口会丢包，一段时间收不到数据包即认为发送结束
+        while received < packets {
+            match tokio::time::timeout(
+                Duration::from_millis(200),
+                receiver.recvmmsg(&mut bufs, &mut hdrs),
+            )
+            .await
+            {
+                Ok(pkts) => received += pkts?.count(),
+                Err(_elapsed) => break,
+            }
+        }
+        io::Result::Ok((received, start.elapsed()))
+    };
+    let (sent, (received, elapsed)) = tokio::try_join!(send, receive)?;
+    Ok(Report {
+        sent,
+        received,
+        elapsed,
+    })
+}
+
+fn print(name: &str, size: usize, report: Report) {
+    let secs = report.elapsed.as_secs_f64();
+    println!(
+        "{name:>8}: sent {} received {} packets in {:.3}s, {:.3} Mpps, {:.3} Gbit/s",
+        report.sent,
+        report.received,
+        secs,
+        report.received as f64 / secs / 1e6,
+        (report.received * size * 8) as f64 / secs / 1e9,
+    );
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> io::Result<()> {
+    let options = Options::parse();
+    let bind_uri = BindUri::from(options.bind.as_str());
+
+    let (sender, receiver) = (
+        qudp::UdpSocketController::bind(bind_uri.clone())?,
+        qudp::UdpSocketController::bind(bind_uri.clone())?,
+    );
+    let report = bench(&sender, &receiver, options.packets, options.size).await?;
+    print("qudp", options.size, report);
+
+    let (sender, receiver) = (
+        uring::UdpSocketController::bind(bind_uri.clone())?,
+        uring::UdpSocketController::bind(bind_uri)?,
+    );
+    let report = bench(&sender, &receiver, options.packets, options.size).await?;
+    print("io_uring", options.size, report);
+
+    Ok(())
+}
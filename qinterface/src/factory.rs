@@ -8,6 +8,29 @@ use crate::QuicIO;
 
 pub trait ProductQuicIO: Send + Sync {
     fn bind(&self, bind_uri: BindUri) -> io::Result<Box<dyn QuicIO>>;
+
+    /// Build the interface for the `bind_uri` on a `socket` already bound, for example one handed
+    /// over by another process, see [`QuicIO::try_clone_fds`].
+    ///
+    /// Factories that can not take over sockets return [`io::ErrorKind::Unsupported`], which is
+    /// the default.
+    #[cfg(unix)]
+    fn bind_inherited(
+        &self,
+        bind_uri: BindUri,
+        socket: std::net::UdpSocket,
+    ) -> io::Result<Box<dyn QuicIO>> {
+        _ = socket;
+        Err(inherit_unsupported(&bind_uri))
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn inherit_unsupported(bind_uri: &BindUri) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Failed to bind {bind_uri}: the factory can not take over a bound socket"),
+    )
 }
 
 impl<F, Q> ProductQuicIO for F
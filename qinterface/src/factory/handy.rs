@@ -1,8 +1,8 @@
 use std::io;
 
-use qbase::net::addr::BindUri;
+use qbase::net::addr::{BindUri, BindUriSchema};
 
-use crate::{factory::ProductQuicIO, iface::handy::*};
+use crate::{QuicIO, factory::ProductQuicIO, iface::handy::*};
 
 #[cfg(all(feature = "qudp", any(unix, windows)))]
 pub static DEFAULT_QUIC_IO_FACTORY: fn(BindUri) -> io::Result<qudp::UdpSocketController> =
@@ -12,7 +12,44 @@ pub static DEFAULT_QUIC_IO_FACTORY: fn(BindUri) -> io::Result<qudp::UdpSocketCon
 pub static DEFAULT_QUIC_IO_FACTORY: fn(BindUri) -> io::Result<unsupported::Unsupported> =
     unsupported::Unsupported::bind;
 
+/// The [`ProductQuicIO`] used by the client and server by default, selects the implementation by
+/// the scheme of [`BindUri`].
+///
+/// `unix` scheme interfaces are bound with [`unix::UnixSocketController`] on unix platforms,
+/// others with [`DEFAULT_QUIC_IO_FACTORY`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SchemeQuicIoFactory;
+
+impl ProductQuicIO for SchemeQuicIoFactory {
+    fn bind(&self, bind_uri: BindUri) -> io::Result<Box<dyn QuicIO>> {
+        match bind_uri.scheme() {
+            #[cfg(unix)]
+            BindUriSchema::Unix => Ok(Box::new(unix::UnixSocketController::bind(bind_uri)?)),
+            _ => Ok(Box::new(DEFAULT_QUIC_IO_FACTORY(bind_uri)?)),
+        }
+    }
+
+    #[cfg(unix)]
+    fn bind_inherited(
+        &self,
+        bind_uri: BindUri,
+        socket: std::net::UdpSocket,
+    ) -> io::Result<Box<dyn QuicIO>> {
+        #[cfg(feature = "qudp")]
+        if bind_uri.scheme() != BindUriSchema::Unix {
+            return Ok(Box::new(qudp::UdpSocketController::from_std(
+                bind_uri, socket,
+            )?));
+        }
+        _ = socket;
+        Err(super::inherit_unsupported(&bind_uri))
+    }
+}
+
 fn _assert_impl_quic_io_factory() {
     fn assert_impl<F: ProductQuicIO + Copy>(_: F) {}
     assert_impl(DEFAULT_QUIC_IO_FACTORY);
+    assert_impl(SchemeQuicIoFactory);
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    assert_impl(uring::UringQuicIoFactory);
 }
@@ -17,6 +17,7 @@ use qbase::{
 use thiserror::Error;
 use tokio::net::UdpSocket;
 
+use self::shard::ShardedIO;
 use crate::{QuicIO, QuicIoExt, factory::ProductQuicIO, local::Locations};
 
 pub mod global;
@@ -24,13 +25,14 @@ pub mod monitor;
 // handy（qudp）是可选的
 mod context;
 pub mod handy;
+mod shard;
 
 pub use global::QuicInterfaces;
 
 struct Interface {
     bind_uri: BindUri,
     factory: Arc<dyn ProductQuicIO>,
-    io: io::Result<Box<dyn QuicIO>>,
+    io: io::Result<ShardedIO>,
     /// Unique ID generator from [`QuicInterfaces`]
     ifaces: Arc<QuicInterfaces>,
     /// Unique identifier for this binding
@@ -58,9 +60,9 @@ pub struct InterfaceUnavailable {
 }
 
 impl Interface {
-    fn borrow<T>(&self, f: impl FnOnce(&dyn QuicIO) -> T) -> io::Result<T> {
+    fn borrow<T>(&self, f: impl FnOnce(&ShardedIO) -> T) -> io::Result<T> {
         match self.io.as_ref() {
-            Ok(iface) => Ok(f(iface.as_ref())),
+            Ok(iface) => Ok(f(iface)),
             Err(e) => Err(io::Error::new(
                 io::ErrorKind::NotConnected,
                 InterfaceUnavailable {
@@ -73,7 +75,7 @@ impl Interface {
 
     fn rebind(&mut self) {
         self.io = Err(io::ErrorKind::AddrNotAvailable.into());
-        self.io = self.factory.bind(self.bind_uri.clone());
+        self.io = ShardedIO::bind(self.factory.as_ref(), self.bind_uri.clone());
         self.bind_id = self.ifaces.bind_id_generator.generate();
     }
 }
@@ -101,7 +103,7 @@ impl RwInterface {
         ifaces: Arc<QuicInterfaces>,
     ) -> Self {
         Self::from(Interface {
-            io: factory.bind(bind_uri.clone()),
+            io: ShardedIO::bind(factory.as_ref(), bind_uri.clone()),
             bind_id: ifaces.bind_id_generator.generate(),
             bind_uri,
             factory,
@@ -132,6 +134,8 @@ impl RwInterface {
         let endpoint_addr = match real_addr {
             RealAddr::Internet(addr) => EndpointAddr::Socket(addr.into()),
             RealAddr::Bluetooth(addr) => EndpointAddr::Ble(addr.into()),
+            RealAddr::Unix(path) => EndpointAddr::Unix(path),
+            RealAddr::Custom(addr) => EndpointAddr::Custom(addr),
             _ => return,
         };
         Locations::global().insert(iface.bind_uri.clone(), endpoint_addr);
@@ -142,6 +146,22 @@ impl RwInterface {
         self.write().rebind();
         self.publish_endpoint_addr();
     }
+
+    /// The number of sockets opened for this interface, see [`BindUri::shards`].
+    fn shards(&self) -> usize {
+        self.read().io.as_ref().map_or(1, ShardedIO::shards)
+    }
+
+    fn poll_recv_shard(
+        &self,
+        shard: usize,
+        cx: &mut Context,
+        pkts: &mut [BytesMut],
+        hdrs: &mut [PacketHeader],
+    ) -> Poll<io::Result<usize>> {
+        self.read()
+            .borrow(|iface| iface.poll_recv_shard(shard, cx, pkts, hdrs))?
+    }
 }
 
 impl QuicIO for RwInterface {
@@ -190,6 +210,12 @@ impl QuicIO for RwInterface {
     fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
         self.read().borrow(|iface| iface.poll_close(cx))?
     }
+
+    #[cfg(unix)]
+    #[inline]
+    fn try_clone_fds(&self) -> io::Result<Vec<std::os::fd::OwnedFd>> {
+        self.read().borrow(|iface| iface.try_clone_fds())?
+    }
 }
 
 #[derive(Debug, Error)]
@@ -227,8 +253,11 @@ impl InterfaceFailure {
 
 impl RwInterface {
     async fn is_alive(&self) -> Result<(), InterfaceFailure> {
-        if self.bind_uri().scheme() == BindUriSchema::Ble {
-            return Err(InterfaceFailure::BleProtocol);
+        match self.bind_uri().scheme() {
+            BindUriSchema::Ble => return Err(InterfaceFailure::BleProtocol),
+            // unix socket不受网络接口变化的影响，自定义的传输自行负责其存活检测
+            BindUriSchema::Unix | BindUriSchema::Custom(..) => return Ok(()),
+            _ => {}
         }
 
         let real_addr = match self
@@ -357,10 +386,31 @@ impl QuicIO for QuicInterface {
     fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
         self.borrow(|iface| iface.poll_close(cx))?
     }
+
+    #[cfg(unix)]
+    #[inline]
+    fn try_clone_fds(&self) -> io::Result<Vec<std::os::fd::OwnedFd>> {
+        self.borrow(|iface| iface.try_clone_fds())?
+    }
 }
 
 impl QuicInterface {
-    pub(super) fn borrow<T>(&self, f: impl FnOnce(&dyn QuicIO) -> T) -> io::Result<T> {
+    /// Whether the interface has been rebound since this handle was borrowed, for example
+    /// because its address changed.
+    ///
+    /// A rebound handle can no longer be used, borrow a new one from [`QuicInterfaces`].
+    #[inline]
+    pub fn is_rebound(&self) -> bool {
+        self.iface.read().bind_id != self.bind_id
+    }
+
+    /// Borrow a new handle of the interface, usable after the interface has been rebound.
+    #[inline]
+    pub fn reborrow(&self) -> io::Result<QuicInterface> {
+        self.iface.borrow()
+    }
+
+    pub(super) fn borrow<T>(&self, f: impl FnOnce(&ShardedIO) -> T) -> io::Result<T> {
         if self.iface.read().bind_id != self.bind_id {
             return Err(io::Error::new(
                 io::ErrorKind::NotConnected,
@@ -8,6 +8,11 @@ use std::{
     task::{Context, Poll},
 };
 
+use bytes::BytesMut;
+use qbase::net::{
+    addr::{BindUri, RealAddr},
+    route::PacketHeader,
+};
 use tokio::sync::watch;
 use tokio_util::task::AbortOnDropHandle;
 
@@ -93,10 +98,35 @@ impl<F: Future + Unpin> Future for ReceiveTask<F> {
 }
 
 async fn receive_and_deliver(iface: Weak<RwInterface>) -> io::Result<()> {
+    let shards = match iface.upgrade() {
+        Some(iface) => iface.shards(),
+        None => return Ok(()),
+    };
+    if shards == 1 {
+        return receive_shard_and_deliver(iface, 0).await;
+    }
+
+    // 每个shard由各自的任务接收，任一shard出错时整个接收任务失败，其余shard的任务随之中止
+    let tasks = (0..shards).map(|shard| {
+        let task = AbortOnDropHandle::new(tokio::spawn(receive_shard_and_deliver(
+            iface.clone(),
+            shard,
+        )));
+        async move { task.await.map_err(io::Error::other)? }
+    });
+    futures::future::try_join_all(tasks).await?;
+    Ok(())
+}
+
+async fn receive_shard_and_deliver(iface: Weak<RwInterface>, shard: usize) -> io::Result<()> {
     let (mut bufs, mut hdrs) = (vec![], vec![]);
     loop {
         let pkts = match iface.upgrade() {
-            Some(iface) => iface.recvmpkt(bufs.as_mut(), hdrs.as_mut()).await?,
+            Some(iface) => {
+                Shard::new(&iface, shard)
+                    .recvmpkt(bufs.as_mut(), hdrs.as_mut())
+                    .await?
+            }
             None => return Ok(()),
         };
         for (pkt, way) in pkts {
@@ -104,3 +134,62 @@ async fn receive_and_deliver(iface: Weak<RwInterface>) -> io::Result<()> {
         }
     }
 }
+
+/// One shard of the interface, receiving from it only receives the packets of this shard.
+struct Shard<'a> {
+    iface: &'a RwInterface,
+    shard: usize,
+}
+
+impl<'a> Shard<'a> {
+    fn new(iface: &'a RwInterface, shard: usize) -> Self {
+        Self { iface, shard }
+    }
+}
+
+impl QuicIO for Shard<'_> {
+    #[inline]
+    fn bind_uri(&self) -> BindUri {
+        self.iface.bind_uri()
+    }
+
+    #[inline]
+    fn real_addr(&self) -> io::Result<RealAddr> {
+        self.iface.real_addr()
+    }
+
+    #[inline]
+    fn max_segment_size(&self) -> io::Result<usize> {
+        self.iface.max_segment_size()
+    }
+
+    #[inline]
+    fn max_segments(&self) -> io::Result<usize> {
+        self.iface.max_segments()
+    }
+
+    #[inline]
+    fn poll_send(
+        &self,
+        cx: &mut Context,
+        pkts: &[io::IoSlice],
+        hdr: PacketHeader,
+    ) -> Poll<io::Result<usize>> {
+        self.iface.poll_send(cx, pkts, hdr)
+    }
+
+    #[inline]
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        pkts: &mut [BytesMut],
+        hdrs: &mut [PacketHeader],
+    ) -> Poll<io::Result<usize>> {
+        self.iface.poll_recv_shard(self.shard, cx, pkts, hdrs)
+    }
+
+    #[inline]
+    fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.iface.poll_close(cx)
+    }
+}
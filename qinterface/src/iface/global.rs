@@ -73,9 +73,13 @@ impl QuicInterfaces {
             .and_then(|ctx| ctx.iface().upgrade()?.borrow().ok())
     }
 
+    /// Remove the interface, it stops receiving packets even if it is still referenced.
     #[inline]
     pub fn remove(&self, bind_uri: &BindUri) {
-        self.interfaces.remove(bind_uri);
+        if self.interfaces.remove(bind_uri).is_some() {
+            // NOTE: QuicInterfaces and Locations must be kept in sync.
+            Locations::global().remove(bind_uri);
+        }
     }
 
     #[inline]
@@ -11,7 +11,6 @@ pub mod qudp {
         addr::{BindUri, RealAddr, TryIntoSocketAddrError},
         route::{Link, Pathway},
     };
-    use qudp::BATCH_SIZE;
 
     use crate::{PacketHeader, QuicIO};
 
@@ -24,14 +23,19 @@ pub mod qudp {
         pub fn bind(bind_uri: BindUri) -> io::Result<Self> {
             match SocketAddr::try_from(&bind_uri) {
                 Ok(socket_addr) => Ok(Self {
+                    // 分片的各个socket绑定在同一个端口上，需要SO_REUSEPORT
+                    inner: match bind_uri.shards() {
+                        1 => qudp::UdpSocketController::bind(socket_addr)?,
+                        _ => qudp::UdpSocketController::bind_reuse_port(socket_addr)?,
+                    },
                     bind_uri,
-                    inner: qudp::UdpSocketController::bind(socket_addr)?,
                 }),
                 Err(error) => match error {
                     TryIntoSocketAddrError::NotSocketBindUri => Err(io::Error::new(
                         io::ErrorKind::InvalidInput,
                         format!(
-                            "Failed to bind {bind_uri}: BLE is not supported by UdpSocketController",
+                            "Failed to bind {bind_uri}: {} is not supported by UdpSocketController",
+                            bind_uri.scheme()
                         ),
                     )),
                     e @ (TryIntoSocketAddrError::InterfaceNotFound
@@ -42,6 +46,15 @@ pub mod qudp {
                 },
             }
         }
+
+        /// Take over a socket already bound for the `bind_uri`, for example one handed over by
+        /// another process.
+        pub fn from_std(bind_uri: BindUri, socket: std::net::UdpSocket) -> io::Result<Self> {
+            Ok(Self {
+                inner: qudp::UdpSocketController::from_std(socket)?,
+                bind_uri,
+            })
+        }
     }
 
     impl QuicIO for UdpSocketController {
@@ -54,7 +67,7 @@ pub mod qudp {
         }
 
         fn max_segments(&self) -> io::Result<usize> {
-            Ok(BATCH_SIZE)
+            Ok(self.inner.max_gso_segments())
         }
 
         fn max_segment_size(&self) -> io::Result<usize> {
@@ -68,13 +81,8 @@ pub mod qudp {
             hdr: PacketHeader,
         ) -> Poll<io::Result<usize>> {
             debug_assert_eq!(hdr.ecn(), None);
-            // TODO: (qinterface/qconnection) Better adaptability to interface rebinding
-            // debug_assert_eq!(
-            //     hdr.link().src(),
-            //     self.real_addr()?,
-            //     "Interface changed? bind_uri={}",
-            //     self.bind_uri
-            // );
+            // 接口重新绑定后，旧的QuicInterface无法再借用到此socket，连接会迁移到新地址上的路径，
+            // 因此这里不会发出源地址已失效的包。注意绑定在通配地址上时，link的src可能是具体地址
             let hdr = qudp::DatagramHeader::new(
                 hdr.link().src().try_into().expect("Must be SocketAddr"),
                 hdr.link().dst().try_into().expect("Must be SocketAddr"),
@@ -120,6 +128,46 @@ pub mod qudp {
         fn poll_close(&self, _cx: &mut Context) -> Poll<io::Result<()>> {
             Poll::Ready(Ok(()))
         }
+
+        #[cfg(unix)]
+        fn try_clone_fds(&self) -> io::Result<Vec<std::os::fd::OwnedFd>> {
+            use std::os::fd::AsFd;
+            Ok(vec![self.inner.as_fd().try_clone_to_owned()?])
+        }
+    }
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub mod uring;
+
+#[cfg(unix)]
+pub mod unix;
+
+/// Wake all the tasks waiting on the same event, while the event source can only hold one waker.
+#[cfg(unix)]
+#[derive(Default)]
+struct Wakers(std::sync::Mutex<Vec<std::task::Waker>>);
+
+#[cfg(unix)]
+impl Wakers {
+    fn register(&self, waker: &std::task::Waker) {
+        let mut wakers = self.0.lock().unwrap();
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+}
+
+#[cfg(unix)]
+impl std::task::Wake for Wakers {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &std::sync::Arc<Self>) {
+        for waker in self.0.lock().unwrap().drain(..) {
+            waker.wake();
+        }
     }
 }
 
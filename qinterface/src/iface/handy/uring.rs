@@ -0,0 +1,694 @@
+use std::{
+    alloc::{self, Layout},
+    collections::VecDeque,
+    io, mem,
+    net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6, UdpSocket},
+    os::fd::{AsRawFd, RawFd},
+    ptr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU16, Ordering},
+    },
+    task::{Context, Poll, Waker, ready},
+};
+
+use bytes::BytesMut;
+use io_uring::{IoUring, cqueue, opcode, squeue, types};
+use qbase::net::{
+    addr::{BindUri, RealAddr, TryIntoSocketAddrError},
+    route::{Link, Pathway},
+};
+use tokio::io::{Interest, unix::AsyncFd};
+
+use super::Wakers;
+use crate::{PacketHeader, QuicIO, factory::ProductQuicIO};
+
+/// 一次发送或接收的最大数据包数量
+const BATCH_SIZE: usize = 64;
+/// 数据包的最大长度
+const MAX_SEGMENT_SIZE: usize = 1500;
+/// 发送缓冲区数量，即同时在途的发送操作的上限
+const SEND_SLOTS: usize = 256;
+/// 接收缓冲区环的大小，必须是2的幂
+const RECV_BUFS: u16 = 1024;
+/// socket的接收缓冲区大小，与qudp相同
+const RECV_BUFFER_SIZE: libc::c_int = 2 * 1024 * 1024;
+/// 每个接收缓冲区的大小，多发recvmsg会在数据前放置io_uring_recvmsg_out、源地址和pktinfo
+const RECV_BUF_SIZE: usize = 2048;
+/// 接收缓冲区环的组ID
+const RECV_BUF_GROUP: u16 = 0;
+/// 多发接收操作的user_data，发送操作的user_data是发送缓冲区的索引
+const RECV_USER_DATA: u64 = u64::MAX;
+
+/// An UDP socket driven by io_uring, for Linux only.
+///
+/// Compared to the `qudp` backend, it keeps a multishot `recvmsg` in flight to receive into a
+/// registered ring of provided buffers, and sends from preallocated buffers, so that no syscall is
+/// needed for each received batch, and sending only costs one `io_uring_enter`.
+///
+/// Select it for the endpoint by passing [`UringQuicIoFactory`], or [`UdpSocketController::bind`]
+/// if the sockets handed over by another process are not taken over, to `with_iface_factory`.
+pub struct UdpSocketController {
+    // 字段按声明顺序析构：先注销AsyncFd，再关闭io_uring（取消所有在途的操作），最后关闭socket
+    ready: AsyncFd<RingFd>,
+    ring: Mutex<Ring>,
+    wakers: Arc<Wakers>,
+    socket: UdpSocket,
+    local_addr: SocketAddr,
+    bind_uri: BindUri,
+}
+
+impl UdpSocketController {
+    pub fn bind(bind_uri: BindUri) -> io::Result<Self> {
+        let socket_addr = match SocketAddr::try_from(&bind_uri) {
+            Ok(socket_addr) => socket_addr,
+            Err(TryIntoSocketAddrError::NotSocketBindUri) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Failed to bind {bind_uri}: {} is not supported by io_uring",
+                        bind_uri.scheme()
+                    ),
+                ));
+            }
+            Err(
+                e @ (TryIntoSocketAddrError::InterfaceNotFound
+                | TryIntoSocketAddrError::LinkNotFound),
+            ) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Failed to bind {bind_uri}: {e}"),
+                ));
+            }
+        };
+        if bind_uri.shards() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to bind {bind_uri}: sharding is not supported by io_uring"),
+            ));
+        }
+
+        Self::from_std(bind_uri, UdpSocket::bind(socket_addr)?)
+    }
+
+    /// Take over a socket already bound for the `bind_uri`, for example one handed over by
+    /// another process.
+    pub fn from_std(bind_uri: BindUri, socket: UdpSocket) -> io::Result<Self> {
+        setsockopt(&socket, libc::SOL_SOCKET, libc::SO_RCVBUF, RECV_BUFFER_SIZE)?;
+        let local_addr = socket.local_addr()?;
+        // 绑定在通配地址上时，需要通过pktinfo得知数据包的目的地址
+        match local_addr {
+            SocketAddr::V4(_) => setsockopt(&socket, libc::IPPROTO_IP, libc::IP_PKTINFO, 1)?,
+            SocketAddr::V6(_) => {
+                setsockopt(&socket, libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO, 1)?
+            }
+        }
+        let ring = Ring::new(socket.as_raw_fd())?;
+        // SAFETY: 在ring之前析构，io_uring的fd在此期间一直有效
+        let ready = unsafe {
+            AsyncFd::register_with_interest(RingFd(ring.uring.as_raw_fd()), Interest::READABLE)?
+        };
+
+        Ok(Self {
+            ready,
+            ring: Mutex::new(ring),
+            wakers: Default::default(),
+            socket,
+            local_addr,
+            bind_uri,
+        })
+    }
+
+    /// Wait for the completions of io_uring, all the waiting tasks are woken up once there are.
+    fn poll_completions(&self, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.wakers.register(cx.waker());
+        let waker = Waker::from(self.wakers.clone());
+        let mut guard = ready!(self.ready.poll_read_ready(&mut Context::from_waker(&waker)))?;
+        guard.clear_ready();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl QuicIO for UdpSocketController {
+    fn bind_uri(&self) -> BindUri {
+        self.bind_uri.clone()
+    }
+
+    fn real_addr(&self) -> io::Result<RealAddr> {
+        Ok(RealAddr::Internet(self.local_addr))
+    }
+
+    fn max_segment_size(&self) -> io::Result<usize> {
+        Ok(MAX_SEGMENT_SIZE)
+    }
+
+    fn max_segments(&self) -> io::Result<usize> {
+        Ok(BATCH_SIZE)
+    }
+
+    fn poll_send(
+        &self,
+        cx: &mut Context,
+        pkts: &[io::IoSlice],
+        hdr: PacketHeader,
+    ) -> Poll<io::Result<usize>> {
+        debug_assert_eq!(hdr.ecn(), None);
+        let dst: SocketAddr = hdr.link().dst().try_into().expect("Must be SocketAddr");
+        if let Some(pkt) = pkts.iter().find(|pkt| pkt.len() > MAX_SEGMENT_SIZE) {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Packet of {} bytes exceeds the max segment size", pkt.len()),
+            )));
+        }
+
+        loop {
+            let mut ring = self.ring.lock().unwrap();
+            ring.reap();
+            if !ring.free_slots.is_empty() {
+                return Poll::Ready(ring.send(pkts, dst));
+            }
+            drop(ring);
+            // 发送缓冲区都在途，等待发送完成
+            ready!(self.poll_completions(cx))?;
+        }
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        pkts: &mut [BytesMut],
+        hdrs: &mut [PacketHeader],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut ring = self.ring.lock().unwrap();
+            ring.reap();
+            if !ring.received.is_empty() {
+                let local_addr = self.local_addr;
+                let rcvd = ring.take_received(pkts, hdrs, |src, dst, seg_size| {
+                    let dst = RealAddr::Internet(SocketAddr::new(
+                        dst.unwrap_or(local_addr.ip()),
+                        local_addr.port(),
+                    ));
+                    let way = Pathway::new(src.clone().into(), dst.clone().into());
+                    let link = Link::new(src, dst);
+                    PacketHeader::new(way.flip(), link.flip(), 64, None, seg_size)
+                });
+                return Poll::Ready(Ok(rcvd));
+            }
+            if let Some(error) = ring.recv_error.take() {
+                return Poll::Ready(Err(error));
+            }
+            ring.arm_recv()?;
+            drop(ring);
+            ready!(self.poll_completions(cx))?;
+        }
+    }
+
+    fn poll_close(&self, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn try_clone_fds(&self) -> io::Result<Vec<std::os::fd::OwnedFd>> {
+        use std::os::fd::AsFd;
+        Ok(vec![self.socket.as_fd().try_clone_to_owned()?])
+    }
+}
+
+/// The [`ProductQuicIO`] binding the interfaces with [`UdpSocketController`], which also takes
+/// over the sockets handed over by another process.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UringQuicIoFactory;
+
+impl ProductQuicIO for UringQuicIoFactory {
+    fn bind(&self, bind_uri: BindUri) -> io::Result<Box<dyn QuicIO>> {
+        Ok(Box::new(UdpSocketController::bind(bind_uri)?))
+    }
+
+    fn bind_inherited(&self, bind_uri: BindUri, socket: UdpSocket) -> io::Result<Box<dyn QuicIO>> {
+        Ok(Box::new(UdpSocketController::from_std(bind_uri, socket)?))
+    }
+}
+
+struct RingFd(RawFd);
+
+impl AsRawFd for RingFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+struct Ring {
+    // 必须最先析构，内核取消所有在途操作后才能释放缓冲区
+    uring: IoUring,
+    socket: RawFd,
+
+    // 接收：多发recvmsg从注册的缓冲区环中取缓冲区
+    recv_bufs: BufRing,
+    recv_msghdr: Box<libc::msghdr>,
+    recv_armed: bool,
+    // (缓冲区ID, 缓冲区中的数据长度)
+    received: VecDeque<(u16, usize)>,
+    recv_error: Option<io::Error>,
+
+    // 发送：数据包先拷贝到预分配的缓冲区，发送完成后缓冲区才能复用
+    send_bufs: AlignedBuf,
+    send_addrs: Box<[libc::sockaddr_storage]>,
+    free_slots: Vec<u16>,
+}
+
+// SAFETY: 裸指针指向Ring独占的内存，Ring总是在Mutex中被访问
+unsafe impl Send for Ring {}
+
+impl Ring {
+    fn new(socket: RawFd) -> io::Result<Self> {
+        let uring = IoUring::builder()
+            // 每个发送产生一个完成事件，再加上接收
+            .setup_cqsize((SEND_SLOTS * 2 + RECV_BUFS as usize * 2) as u32)
+            .build((SEND_SLOTS + 1).next_power_of_two() as u32)?;
+
+        let recv_bufs = BufRing::new(RECV_BUFS, RECV_BUF_SIZE)?;
+        // SAFETY: 缓冲区环的内存由BufRing持有，在uring之后释放
+        unsafe {
+            uring.submitter().register_buf_ring_with_flags(
+                recv_bufs.entries.ptr as u64,
+                RECV_BUFS,
+                RECV_BUF_GROUP,
+                0,
+            )?;
+        }
+        // 多发recvmsg只使用msg_namelen和msg_controllen，控制消息只有一个pktinfo
+        let mut recv_msghdr: Box<libc::msghdr> = Box::new(unsafe { mem::zeroed() });
+        recv_msghdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as _;
+        recv_msghdr.msg_controllen =
+            unsafe { libc::CMSG_SPACE(mem::size_of::<libc::in6_pktinfo>() as _) } as _;
+
+        let send_bufs = AlignedBuf::new(SEND_SLOTS * MAX_SEGMENT_SIZE)?;
+
+        Ok(Self {
+            uring,
+            socket,
+            recv_bufs,
+            recv_msghdr,
+            recv_armed: false,
+            received: VecDeque::new(),
+            recv_error: None,
+            send_bufs,
+            send_addrs: vec![unsafe { mem::zeroed() }; SEND_SLOTS].into_boxed_slice(),
+            free_slots: (0..SEND_SLOTS as u16).rev().collect(),
+        })
+    }
+
+    /// Process all the completions.
+    fn reap(&mut self) {
+        let cqes = self
+            .uring
+            .completion()
+            .map(|cqe| (cqe.user_data(), cqe.result(), cqe.flags()))
+            .collect::<Vec<_>>();
+        for (user_data, result, flags) in cqes {
+            if user_data == RECV_USER_DATA {
+                if !cqueue::more(flags) {
+                    self.recv_armed = false;
+                }
+                match cqueue::buffer_select(flags) {
+                    Some(bid) if result >= 0 => self.received.push_back((bid, result as usize)),
+                    Some(bid) => self.recv_bufs.recycle(bid),
+                    // 缓冲区用尽，接收操作终止，取出数据后重新发起
+                    None if result == -libc::ENOBUFS => {}
+                    None if result < 0 => {
+                        self.recv_error = Some(io::Error::from_raw_os_error(-result))
+                    }
+                    None => {}
+                }
+                continue;
+            }
+
+            // 发送失败时poll_send早已返回，错误无法归属到之后的发送，和丢包一样处理
+            if result < 0 {
+                let error = io::Error::from_raw_os_error(-result);
+                tracing::debug!("Failed to send packet via io_uring: {error}");
+            }
+            self.free_slots.push(user_data as u16);
+        }
+    }
+
+    fn arm_recv(&mut self) -> io::Result<()> {
+        if self.recv_armed {
+            return Ok(());
+        }
+        let entry = opcode::RecvMsgMulti::new(
+            types::Fd(self.socket),
+            &*self.recv_msghdr as *const libc::msghdr,
+            RECV_BUF_GROUP,
+        )
+        .build()
+        .user_data(RECV_USER_DATA);
+        self.push(&entry)?;
+        self.uring.submit()?;
+        self.recv_armed = true;
+        Ok(())
+    }
+
+    fn take_received(
+        &mut self,
+        pkts: &mut [BytesMut],
+        hdrs: &mut [PacketHeader],
+        header: impl Fn(RealAddr, Option<IpAddr>, u16) -> PacketHeader,
+    ) -> usize {
+        let mut rcvd = 0;
+        while rcvd < pkts.len().min(hdrs.len()) {
+            let Some((bid, len)) = self.received.pop_front() else {
+                break;
+            };
+            let buf = &self.recv_bufs.buf(bid)[..len];
+            let msg = types::RecvMsgOut::parse(buf, &self.recv_msghdr)
+                .ok()
+                .filter(|msg| !msg.is_payload_truncated());
+            let src = msg.as_ref().and_then(|msg| socket_addr(msg.name_data()));
+            if let (Some(msg), Some(src)) = (&msg, src) {
+                let payload = msg.payload_data();
+                let size = payload.len().min(pkts[rcvd].len());
+                pkts[rcvd][..size].copy_from_slice(&payload[..size]);
+                hdrs[rcvd] = header(src.into(), pktinfo_dst(msg.control_data()), size as u16);
+                rcvd += 1;
+            }
+            self.recv_bufs.recycle(bid);
+        }
+        rcvd
+    }
+
+    fn send(&mut self, pkts: &[io::IoSlice], dst: SocketAddr) -> io::Result<usize> {
+        let (addr, addr_len) = sockaddr(dst);
+        let count = pkts.len().min(self.free_slots.len()).min(BATCH_SIZE);
+        for pkt in &pkts[..count] {
+            let slot = self.free_slots.pop().expect("Checked free slots");
+            let offset = slot as usize * MAX_SEGMENT_SIZE;
+            // SAFETY: 缓冲区不在途，内核不会访问
+            let buf = unsafe { self.send_bufs.slice_mut(offset, pkt.len()) };
+            buf.copy_from_slice(pkt);
+            self.send_addrs[slot as usize] = addr;
+
+            // 数据包不超过MAX_SEGMENT_SIZE，零拷贝发送的页锁定和额外的通知事件得不偿失
+            let entry = opcode::Send::new(types::Fd(self.socket), buf.as_ptr(), buf.len() as u32)
+                .dest_addr(&self.send_addrs[slot as usize] as *const _ as *const libc::sockaddr)
+                .dest_addr_len(addr_len)
+                .build()
+                .user_data(slot as u64);
+            self.push(&entry)?;
+        }
+        self.uring.submit()?;
+        Ok(count)
+    }
+
+    fn push(&mut self, entry: &squeue::Entry) -> io::Result<()> {
+        loop {
+            // SAFETY: 操作引用的缓冲区和地址都由Ring持有，在操作完成前不会被复用或释放
+            if unsafe { self.uring.submission().push(entry) }.is_ok() {
+                return Ok(());
+            }
+            // 提交队列满了，先提交再重试
+            self.uring.submit()?;
+        }
+    }
+}
+
+/// A page aligned buffer, shared with the kernel.
+struct AlignedBuf {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+impl AlignedBuf {
+    fn new(size: usize) -> io::Result<Self> {
+        let layout = Layout::from_size_align(size, 4096)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // SAFETY: size不为0
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            return Err(io::ErrorKind::OutOfMemory.into());
+        }
+        Ok(Self { ptr, layout })
+    }
+
+    /// # Safety
+    ///
+    /// The range must not be accessed by the kernel at the same time.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn slice_mut(&self, offset: usize, len: usize) -> &mut [u8] {
+        assert!(offset + len <= self.layout.size());
+        std::slice::from_raw_parts_mut(self.ptr.add(offset), len)
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        // SAFETY: 由alloc_zeroed以相同的layout分配
+        unsafe { alloc::dealloc(self.ptr, self.layout) }
+    }
+}
+
+/// The ring of provided buffers registered to io_uring, see `io_uring_register_buf_ring(3)`.
+struct BufRing {
+    entries: AlignedBuf,
+    bufs: AlignedBuf,
+    buf_size: usize,
+    mask: u16,
+    tail: u16,
+}
+
+impl BufRing {
+    fn new(count: u16, buf_size: usize) -> io::Result<Self> {
+        debug_assert!(count.is_power_of_two());
+        let entries = AlignedBuf::new(count as usize * mem::size_of::<types::BufRingEntry>())?;
+        let bufs = AlignedBuf::new(count as usize * buf_size)?;
+        let mut ring = Self {
+            entries,
+            bufs,
+            buf_size,
+            mask: count - 1,
+            tail: 0,
+        };
+        for bid in 0..count {
+            ring.recycle(bid);
+        }
+        Ok(ring)
+    }
+
+    fn buf(&self, bid: u16) -> &[u8] {
+        // SAFETY: 缓冲区已由内核交还，直到recycle之前内核都不会写入
+        unsafe {
+            std::slice::from_raw_parts(
+                self.bufs.ptr.add(bid as usize * self.buf_size),
+                self.buf_size,
+            )
+        }
+    }
+
+    /// Give the buffer back to the kernel.
+    fn recycle(&mut self, bid: u16) {
+        let base = self.entries.ptr as *mut types::BufRingEntry;
+        // SAFETY: 索引经过mask，位于缓冲区环中；tail字段与第一个条目的resv字段重叠，写入条目时不能覆盖它
+        unsafe {
+            let entry = &mut *base.add((self.tail & self.mask) as usize);
+            entry.set_addr(self.bufs.ptr.add(bid as usize * self.buf_size) as u64);
+            entry.set_len(self.buf_size as u32);
+            entry.set_bid(bid);
+            self.tail = self.tail.wrapping_add(1);
+            let tail = types::BufRingEntry::tail(base) as *const AtomicU16;
+            (*tail).store(self.tail, Ordering::Release);
+        }
+    }
+}
+
+fn setsockopt(
+    socket: &UdpSocket,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    // SAFETY: 参数是有效的c_int
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    match ret {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+fn sockaddr(addr: SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    // SAFETY: sockaddr_storage足以容纳sockaddr_in和sockaddr_in6
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sin = libc::sockaddr_in {
+                sin_family: libc::AF_INET as libc::sa_family_t,
+                sin_port: addr.port().to_be(),
+                sin_addr: libc::in_addr {
+                    s_addr: u32::from_ne_bytes(addr.ip().octets()),
+                },
+                sin_zero: [0; 8],
+            };
+            unsafe { ptr::write(&mut storage as *mut _ as *mut libc::sockaddr_in, sin) };
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sin6 = libc::sockaddr_in6 {
+                sin6_family: libc::AF_INET6 as libc::sa_family_t,
+                sin6_port: addr.port().to_be(),
+                sin6_flowinfo: addr.flowinfo(),
+                sin6_addr: libc::in6_addr {
+                    s6_addr: addr.ip().octets(),
+                },
+                sin6_scope_id: addr.scope_id(),
+            };
+            unsafe { ptr::write(&mut storage as *mut _ as *mut libc::sockaddr_in6, sin6) };
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
+
+/// The destination address of a received packet, from its `IP_PKTINFO` or `IPV6_PKTINFO` control
+/// message.
+fn pktinfo_dst(control: &[u8]) -> Option<IpAddr> {
+    let mut msghdr: libc::msghdr = unsafe { mem::zeroed() };
+    msghdr.msg_control = control.as_ptr() as *mut libc::c_void;
+    msghdr.msg_controllen = control.len() as _;
+    // SAFETY: CMSG宏不会越过msg_controllen读取，控制消息在缓冲区中按cmsghdr对齐
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msghdr);
+        while !cmsg.is_null() {
+            let data = libc::CMSG_DATA(cmsg);
+            match ((*cmsg).cmsg_level, (*cmsg).cmsg_type) {
+                (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                    let info = ptr::read_unaligned(data as *const libc::in_pktinfo);
+                    return Some(IpAddr::from(info.ipi_addr.s_addr.to_ne_bytes()));
+                }
+                (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+                    let info = ptr::read_unaligned(data as *const libc::in6_pktinfo);
+                    return Some(IpAddr::from(info.ipi6_addr.s6_addr));
+                }
+                _ => cmsg = libc::CMSG_NXTHDR(&msghdr, cmsg),
+            }
+        }
+    }
+    None
+}
+
+fn socket_addr(name: &[u8]) -> Option<SocketAddr> {
+    if name.len() < mem::size_of::<libc::sa_family_t>() {
+        return None;
+    }
+    // SAFETY: 读取前检查了长度，read_unaligned不要求对齐
+    let family = unsafe { ptr::read_unaligned(name.as_ptr() as *const libc::sa_family_t) };
+    match family as libc::c_int {
+        libc::AF_INET if name.len() >= mem::size_of::<libc::sockaddr_in>() => {
+            let sin = unsafe { ptr::read_unaligned(name.as_ptr() as *const libc::sockaddr_in) };
+            Some(SocketAddr::V4(SocketAddrV4::new(
+                sin.sin_addr.s_addr.to_ne_bytes().into(),
+                u16::from_be(sin.sin_port),
+            )))
+        }
+        libc::AF_INET6 if name.len() >= mem::size_of::<libc::sockaddr_in6>() => {
+            let sin6 = unsafe { ptr::read_unaligned(name.as_ptr() as *const libc::sockaddr_in6) };
+            Some(SocketAddr::V6(SocketAddrV6::new(
+                sin6.sin6_addr.s6_addr.into(),
+                u16::from_be(sin6.sin6_port),
+                sin6.sin6_flowinfo,
+                sin6.sin6_scope_id,
+            )))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::QuicIoExt;
+
+    #[test]
+    fn sockaddr_roundtrip() {
+        for addr in ["127.0.0.1:4433", "[::1]:443", "[fe80::1%2]:8080"] {
+            let addr: SocketAddr = addr.parse().unwrap();
+            let (storage, len) = sockaddr(addr);
+            let name = unsafe {
+                std::slice::from_raw_parts(&storage as *const _ as *const u8, len as usize)
+            };
+            assert_eq!(socket_addr(name), Some(addr));
+        }
+        assert_eq!(socket_addr(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn wildcard_local_addr() -> io::Result<()> {
+        let a = UdpSocketController::bind(BindUri::from("inet://127.0.0.1:0"))?;
+        let b = UdpSocketController::bind(BindUri::from("inet://0.0.0.0:0"))?;
+        let b_addr = SocketAddr::new([127, 0, 0, 1].into(), b.local_addr.port());
+
+        let link = Link::new(a.real_addr()?, RealAddr::Internet(b_addr));
+        let hdr = PacketHeader::new(link.clone().into(), link, 64, None, 1200);
+        a.sendmmsg(&[io::IoSlice::new(b"ping")], hdr).await?;
+
+        let (mut bufs, mut hdrs) = (vec![], vec![]);
+        let (_, hdr) = b.recvmmsg(&mut bufs, &mut hdrs).await?.next().unwrap();
+        // 绑定在通配地址上，本地地址来自pktinfo
+        assert_eq!(hdr.link().src(), RealAddr::Internet(b_addr));
+        assert_eq!(hdr.pathway().local(), b_addr.into());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn send_and_receive() -> io::Result<()> {
+        let a = UdpSocketController::bind(BindUri::from("inet://127.0.0.1:0"))?;
+        let b = UdpSocketController::bind(BindUri::from("inet://127.0.0.1:0"))?;
+        let (a_addr, b_addr) = (a.real_addr()?, b.real_addr()?);
+
+        let link = Link::new(a_addr.clone(), b_addr.clone());
+        let hdr = PacketHeader::new(link.clone().into(), link, 64, None, 1200);
+        // 超过接收缓冲区环大小的数据包，验证缓冲区能被循环使用
+        let payloads = (0..RECV_BUFS as usize * 2)
+            .map(|i| (i as u32).to_be_bytes())
+            .collect::<Vec<_>>();
+
+        let receive = async {
+            let (mut bufs, mut hdrs) = (vec![], vec![]);
+            let mut received = vec![];
+            while received.len() < payloads.len() {
+                for (pkt, hdr) in b.recvmmsg(&mut bufs, &mut hdrs).await? {
+                    assert_eq!(hdr.link().dst(), a_addr);
+                    assert_eq!(hdr.link().src(), b_addr);
+                    received.push(pkt.to_vec());
+                }
+            }
+            io::Result::Ok(received)
+        };
+        let send = async {
+            for chunk in payloads.chunks(16) {
+                let pkts = chunk
+                    .iter()
+                    .map(|p| io::IoSlice::new(p))
+                    .collect::<Vec<_>>();
+                a.sendmmsg(&pkts, hdr.clone()).await?;
+                // 给接收方取出数据的机会，避免回环接口丢包
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            io::Result::Ok(())
+        };
+        let (received, ()) = tokio::try_join!(receive, send)?;
+        assert_eq!(
+            received,
+            payloads.iter().map(|p| p.to_vec()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
@@ -5,9 +5,26 @@ use std::{
 };
 
 use netdev::Interface;
-use tokio::{sync::watch, time::MissedTickBehavior};
+use tokio::{
+    sync::{broadcast, watch},
+    time::MissedTickBehavior,
+};
 use tokio_util::task::AbortOnDropHandle;
 
+#[cfg(target_os = "linux")]
+mod netlink;
+
+/// A change of a network interface, reported by [`InterfacesMonitor::events`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceEvent {
+    /// The interface with the name appeared.
+    Added(String),
+    /// The interface with the name disappeared.
+    Removed(String),
+    /// The interface with the name gained or lost IP addresses.
+    AddressChanged(String),
+}
+
 struct Devices(RwLock<HashMap<String, Interface>>);
 
 impl Default for Devices {
@@ -21,8 +38,9 @@ impl Devices {
         self.0.read().unwrap()
     }
 
-    fn update(&self) {
-        *self.0.write().unwrap() = netdev::get_interfaces()
+    /// Refresh the interfaces, return the changes since the last refresh.
+    fn update(&self) -> Vec<InterfaceEvent> {
+        let new: HashMap<String, Interface> = netdev::get_interfaces()
             .into_iter()
             .map(|mut iface| {
                 // compatibility with windows interface names
@@ -35,12 +53,63 @@ impl Devices {
             })
             .map(|iface| (iface.name.clone(), iface))
             .collect();
+
+        let mut devices = self.0.write().unwrap();
+        let mut events = Vec::new();
+        for (name, old) in devices.iter() {
+            match new.get(name) {
+                None => events.push(InterfaceEvent::Removed(name.clone())),
+                Some(new) if new.ipv4 != old.ipv4 || new.ipv6 != old.ipv6 => {
+                    events.push(InterfaceEvent::AddressChanged(name.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        events.extend(
+            new.keys()
+                .filter(|name| !devices.contains_key(*name))
+                .map(|name| InterfaceEvent::Added(name.clone())),
+        );
+        *devices = new;
+        events
     }
 }
 
-pub struct InterfacesMonitor {
+/// The senders notified when the interfaces are refreshed.
+#[derive(Clone)]
+struct Notifier {
     devices: Arc<Devices>,
     updated_tx: watch::Sender<()>,
+    events_tx: broadcast::Sender<InterfaceEvent>,
+}
+
+impl Notifier {
+    /// Refresh the interfaces and publish the changes.
+    ///
+    /// Subscribers of the `watch` channel are notified if anything changed, or unconditionally
+    /// if `always` is set.
+    fn refresh(&self, always: bool) -> bool {
+        let events = self.devices.update();
+        let changed = !events.is_empty();
+        for event in events {
+            tracing::debug!(?event, "Interface changed");
+            // 没有订阅者时发送失败，无妨
+            _ = self.events_tx.send(event);
+        }
+        if changed || always {
+            return self.updated_tx.send(()).is_ok();
+        }
+        true
+    }
+}
+
+/// Monitor the network interfaces of the host.
+///
+/// On Linux the interfaces are refreshed as soon as the kernel reports a change through
+/// rtnetlink, and polled every few seconds as well in case any notification is missed.
+/// On other platforms, or if the netlink socket is unavailable, they are only polled.
+pub struct InterfacesMonitor {
+    notifier: Notifier,
     updated_rx: watch::Receiver<()>,
     _task: AbortOnDropHandle<()>,
 }
@@ -52,44 +121,65 @@ impl InterfacesMonitor {
     }
 
     pub fn new() -> Self {
-        let devices = Arc::new(Devices::default());
-
         let (updated_tx, updated_rx) = watch::channel(());
+        let (events_tx, _) = broadcast::channel(64);
+        let notifier = Notifier {
+            devices: Arc::new(Devices::default()),
+            updated_tx,
+            events_tx,
+        };
+        // 立即获取一次网卡信息，订阅者只会收到此后的变化
+        notifier.devices.update();
 
         let task = AbortOnDropHandle::new(tokio::spawn({
-            let devices = devices.clone();
-            let timer_tx = updated_tx.clone();
-            // let event_tx = updated_tx.clone();
+            let timer_notifier = notifier.clone();
+            #[cfg(target_os = "linux")]
+            let event_notifier = notifier.clone();
             async move {
-                tokio::spawn(async move {
-                    let mut interval = tokio::time::interval(Duration::from_secs(5));
-                    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                #[cfg(target_os = "linux")]
+                let event_task = AbortOnDropHandle::new(tokio::spawn(async move {
+                    let mut monitor = match netlink::NetlinkMonitor::new() {
+                        Ok(monitor) => monitor,
+                        Err(e) => {
+                            tracing::warn!(
+                                "Failed to monitor interfaces via netlink, fallback to polling: {e}"
+                            );
+                            return;
+                        }
+                    };
                     loop {
-                        interval.tick().await;
-                        devices.update();
-                        if timer_tx.send(()).is_err() {
-                            break;
+                        if let Err(e) = monitor.changed().await {
+                            tracing::warn!(
+                                "Failed to monitor interfaces via netlink, fallback to polling: {e}"
+                            );
+                            return;
                         }
+                        if !event_notifier.refresh(false) {
+                            return;
+                        }
+                    }
+                }));
+
+                const POLL_INTERVAL: Duration = Duration::from_secs(5);
+                let mut interval = tokio::time::interval_at(
+                    tokio::time::Instant::now() + POLL_INTERVAL,
+                    POLL_INTERVAL,
+                );
+                interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    if !timer_notifier.refresh(true) {
+                        break;
                     }
-                });
-
-                // tokio::spawn(async move {
-                //     if let Ok(monitor) = netwatch::netmon::Monitor::new().await {
-                //         let cb = move |is_major| {
-                //             if is_major {
-                //                 _ = event_tx.send(());
-                //             }
-                //             Box::pin(async move {}) as futures::future::BoxFuture<'static, ()>
-                //         };
-                //         _ = monitor.subscribe(cb).await;
-                //     }
-                // });
+                }
+
+                #[cfg(target_os = "linux")]
+                drop(event_task);
             }
         }));
 
         Self {
-            devices,
-            updated_tx,
+            notifier,
             updated_rx,
             _task: task,
         }
@@ -99,13 +189,21 @@ impl InterfacesMonitor {
         self.updated_rx.clone()
     }
 
+    /// Subscribe the precise changes of the interfaces.
+    ///
+    /// Each change is also followed by a notification of the [`subscribe`]d channel.
+    ///
+    /// [`subscribe`]: InterfacesMonitor::subscribe
+    pub fn events(&self) -> broadcast::Receiver<InterfaceEvent> {
+        self.notifier.events_tx.subscribe()
+    }
+
     pub fn on_interface_changed(&self) {
-        self.devices.update();
-        self.updated_tx.send(()).unwrap();
+        assert!(self.notifier.refresh(true));
     }
 
     pub fn devices(&self) -> RwLockReadGuard<'_, HashMap<String, Interface>> {
-        self.devices.get()
+        self.notifier.devices.get()
     }
 }
 
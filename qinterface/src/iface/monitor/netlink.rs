@@ -0,0 +1,146 @@
+use std::{
+    io, mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use tokio::io::{Interest, unix::AsyncFd};
+
+/// A `NETLINK_ROUTE` socket subscribed to the link and address multicast groups.
+///
+/// The kernel notifies it as soon as an interface appears, disappears, or gains or loses an
+/// address, which is far more timely than polling the interfaces.
+pub struct NetlinkMonitor {
+    socket: AsyncFd<OwnedFd>,
+    buf: Vec<u8>,
+}
+
+impl NetlinkMonitor {
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups =
+            (libc::RTMGRP_LINK | libc::RTMGRP_IPV4_IFADDR | libc::RTMGRP_IPV6_IFADDR) as u32;
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: the fd is owned by the OwnedFd, which stays open until the AsyncFd is dropped
+        let socket = unsafe { AsyncFd::register_with_interest(fd, Interest::READABLE)? };
+        Ok(Self {
+            socket,
+            buf: vec![0; 16 * 1024],
+        })
+    }
+
+    /// Wait until the kernel reports a change of the interfaces or their addresses.
+    ///
+    /// All the pending notifications are drained, so a burst of changes is reported once.
+    pub async fn changed(&mut self) -> io::Result<()> {
+        loop {
+            let mut guard = self.socket.readable().await?;
+            let mut changed = false;
+            loop {
+                let ret = unsafe {
+                    libc::recv(
+                        guard.get_inner().as_raw_fd(),
+                        self.buf.as_mut_ptr() as *mut libc::c_void,
+                        self.buf.len(),
+                        libc::MSG_DONTWAIT,
+                    )
+                };
+                if ret >= 0 {
+                    changed |= is_interface_changed(&self.buf[..ret as usize]);
+                    continue;
+                }
+                let error = io::Error::last_os_error();
+                match error.raw_os_error() {
+                    Some(libc::EINTR) => continue,
+                    // 内核的通知溢出了接收缓冲区，有通知丢失，只能认为发生了变化
+                    Some(libc::ENOBUFS) => changed = true,
+                    _ if error.kind() == io::ErrorKind::WouldBlock => {
+                        guard.clear_ready();
+                        break;
+                    }
+                    _ => return Err(error),
+                }
+            }
+            if changed {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Check whether the netlink messages in the `buf` contain any link or address notification.
+fn is_interface_changed(mut buf: &[u8]) -> bool {
+    const NLMSG_HDRLEN: usize = mem::size_of::<libc::nlmsghdr>();
+    while buf.len() >= NLMSG_HDRLEN {
+        let len = u32::from_ne_bytes(buf[0..4].try_into().unwrap()) as usize;
+        let ty = u16::from_ne_bytes(buf[4..6].try_into().unwrap());
+        if len < NLMSG_HDRLEN || len > buf.len() {
+            break;
+        }
+        if matches!(
+            ty,
+            libc::RTM_NEWLINK | libc::RTM_DELLINK | libc::RTM_NEWADDR | libc::RTM_DELADDR
+        ) {
+            return true;
+        }
+        // 消息按4字节对齐
+        buf = &buf[((len + 3) & !3).min(buf.len())..];
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(ty: u16, payload: usize) -> Vec<u8> {
+        let len = mem::size_of::<libc::nlmsghdr>() + payload;
+        let mut msg = vec![0; (len + 3) & !3];
+        msg[0..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        msg[4..6].copy_from_slice(&ty.to_ne_bytes());
+        msg
+    }
+
+    #[test]
+    fn parse_notifications() {
+        assert!(!is_interface_changed(&[]));
+        assert!(!is_interface_changed(&message(libc::RTM_NEWROUTE, 5)));
+        assert!(is_interface_changed(&message(libc::RTM_NEWADDR, 5)));
+
+        let mut buf = message(libc::RTM_NEWROUTE, 5);
+        buf.extend(message(libc::RTM_DELLINK, 16));
+        assert!(is_interface_changed(&buf));
+
+        // 长度字段被截断的消息
+        let mut truncated = message(libc::RTM_NEWLINK, 16);
+        truncated.truncate(20);
+        assert!(!is_interface_changed(&truncated));
+    }
+
+    #[tokio::test]
+    async fn subscribe() {
+        assert!(NetlinkMonitor::new().is_ok());
+    }
+}
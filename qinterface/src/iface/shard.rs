@@ -0,0 +1,224 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    sync::Mutex,
+    task::{Context, Poll, ready},
+};
+
+use bytes::BytesMut;
+use qbase::net::{
+    addr::{BindUri, RealAddr},
+    route::PacketHeader,
+};
+
+use crate::{QuicIO, factory::ProductQuicIO};
+
+/// 转向表的最大条目数，超过后淘汰最久未收到数据包的对端，避免被大量伪造的源地址撑满
+const MAX_STEERING_ENTRIES: usize = 4096;
+
+/// The shard each peer was last received on, the least recently received peer is evicted once
+/// the table is full.
+#[derive(Default)]
+struct Steering {
+    // 对端地址 -> (最近收到其数据包的shard, 收到的时刻)
+    peers: HashMap<RealAddr, (usize, u64)>,
+    // 按收到的先后排列的对端，时刻已被刷新的记录是过期的
+    recency: VecDeque<(RealAddr, u64)>,
+    clock: u64,
+}
+
+impl Steering {
+    fn shard_of(&self, peer: &RealAddr) -> Option<usize> {
+        self.peers.get(peer).map(|&(shard, _)| shard)
+    }
+
+    fn steer(&mut self, peer: RealAddr, shard: usize) {
+        self.clock += 1;
+        self.recency.push_back((peer.clone(), self.clock));
+        self.peers.insert(peer, (shard, self.clock));
+
+        if self.peers.len() > MAX_STEERING_ENTRIES {
+            while let Some((peer, received)) = self.recency.pop_front() {
+                if self.peers.get(&peer).is_some_and(|&(_, at)| at == received) {
+                    self.peers.remove(&peer);
+                    break;
+                }
+            }
+        }
+        if self.recency.len() > 2 * MAX_STEERING_ENTRIES {
+            let peers = &self.peers;
+            self.recency
+                .retain(|(peer, received)| peers.get(peer).is_some_and(|&(_, at)| at == *received));
+        }
+    }
+}
+
+/// The sockets opened for one logical interface, see [`BindUri::shards`].
+///
+/// Each shard is received on by its own task. Packets to a peer are sent through the shard the
+/// peer was last received on, so that the peer keeps seeing the same socket even if the kernel
+/// would pick another one for the reply.
+pub(crate) struct ShardedIO {
+    shards: Vec<Box<dyn QuicIO>>,
+    steering: Mutex<Steering>,
+}
+
+impl ShardedIO {
+    pub(super) fn bind(factory: &dyn ProductQuicIO, bind_uri: BindUri) -> io::Result<Self> {
+        let count = bind_uri.shards();
+        let first = factory.bind(bind_uri.clone())?;
+        let mut shards = Vec::with_capacity(count);
+        if count > 1 {
+            // 其余的shard必须绑定在第一个shard实际分配到的端口上
+            let port = match first.real_addr()? {
+                RealAddr::Internet(addr) => addr.port(),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!(
+                            "Failed to bind {bind_uri}: only internet interfaces can be sharded"
+                        ),
+                    ));
+                }
+            };
+            let shard_uri = bind_uri.with_port(port);
+            shards.push(first);
+            for _ in 1..count {
+                shards.push(factory.bind(shard_uri.clone())?);
+            }
+        } else {
+            shards.push(first);
+        }
+        Ok(Self {
+            shards,
+            steering: Mutex::default(),
+        })
+    }
+
+    pub(super) fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub(super) fn poll_recv_shard(
+        &self,
+        shard: usize,
+        cx: &mut Context,
+        pkts: &mut [BytesMut],
+        hdrs: &mut [PacketHeader],
+    ) -> Poll<io::Result<usize>> {
+        let rcvd = ready!(self.shards[shard].poll_recv(cx, pkts, hdrs))?;
+        if self.shards.len() > 1 {
+            let mut steering = self.steering.lock().unwrap();
+            for hdr in &hdrs[..rcvd] {
+                steering.steer(hdr.link().dst(), shard);
+            }
+        }
+        Poll::Ready(Ok(rcvd))
+    }
+}
+
+impl QuicIO for ShardedIO {
+    #[inline]
+    fn bind_uri(&self) -> BindUri {
+        self.shards[0].bind_uri()
+    }
+
+    #[inline]
+    fn real_addr(&self) -> io::Result<RealAddr> {
+        self.shards[0].real_addr()
+    }
+
+    #[inline]
+    fn max_segment_size(&self) -> io::Result<usize> {
+        self.shards[0].max_segment_size()
+    }
+
+    #[inline]
+    fn max_segments(&self) -> io::Result<usize> {
+        self.shards[0].max_segments()
+    }
+
+    fn poll_send(
+        &self,
+        cx: &mut Context,
+        pkts: &[io::IoSlice],
+        hdr: PacketHeader,
+    ) -> Poll<io::Result<usize>> {
+        // 尚未收到过对端的数据包（例如客户端发起连接）时，从第一个shard发出
+        let shard = match self.shards.len() {
+            1 => 0,
+            _ => self
+                .steering
+                .lock()
+                .unwrap()
+                .shard_of(&hdr.link().dst())
+                .unwrap_or(0),
+        };
+        self.shards[shard].poll_send(cx, pkts, hdr)
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        pkts: &mut [BytesMut],
+        hdrs: &mut [PacketHeader],
+    ) -> Poll<io::Result<usize>> {
+        for shard in 0..self.shards.len() {
+            if let Poll::Ready(result) = self.poll_recv_shard(shard, cx, pkts, hdrs) {
+                return Poll::Ready(result);
+            }
+        }
+        Poll::Pending
+    }
+
+    fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>> {
+        for shard in &self.shards {
+            ready!(shard.poll_close(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    #[cfg(unix)]
+    fn try_clone_fds(&self) -> io::Result<Vec<std::os::fd::OwnedFd>> {
+        let mut fds = Vec::with_capacity(self.shards.len());
+        for shard in &self.shards {
+            fds.extend(shard.try_clone_fds()?);
+        }
+        Ok(fds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::*;
+
+    fn peer(port: u16) -> RealAddr {
+        RealAddr::Internet(SocketAddr::from(([127, 0, 0, 1], port)))
+    }
+
+    #[test]
+    fn evict_least_recently_received() {
+        let mut steering = Steering::default();
+        for port in 0..MAX_STEERING_ENTRIES as u16 {
+            steering.steer(peer(port), 1);
+        }
+        // the first peer is received again, the second one becomes the least recent
+        steering.steer(peer(0), 2);
+        steering.steer(peer(u16::MAX), 3);
+
+        assert_eq!(steering.peers.len(), MAX_STEERING_ENTRIES);
+        assert_eq!(steering.shard_of(&peer(0)), Some(2));
+        assert_eq!(steering.shard_of(&peer(1)), None);
+        assert_eq!(steering.shard_of(&peer(2)), Some(1));
+        assert_eq!(steering.shard_of(&peer(u16::MAX)), Some(3));
+
+        // the outdated records do not pile up
+        for _ in 0..4 * MAX_STEERING_ENTRIES {
+            steering.steer(peer(0), 2);
+        }
+        assert!(steering.recency.len() <= 2 * MAX_STEERING_ENTRIES);
+        assert_eq!(steering.peers.len(), MAX_STEERING_ENTRIES);
+    }
+}
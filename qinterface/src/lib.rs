@@ -88,6 +88,19 @@ pub trait QuicIO: Send + Sync {
     /// the implementation should ensure that [`QuicIO`] does not
     /// leak any resources when it is dropped.
     fn poll_close(&self, cx: &mut Context) -> Poll<io::Result<()>>;
+
+    /// Duplicate the file descriptors of the sockets of this interface, so that they can be
+    /// handed over to another process.
+    ///
+    /// Implementations not backed by sockets return [`io::ErrorKind::Unsupported`], which is the
+    /// default.
+    #[cfg(unix)]
+    fn try_clone_fds(&self) -> io::Result<Vec<std::os::fd::OwnedFd>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Interface {} can not be handed over", self.bind_uri()),
+        ))
+    }
 }
 
 pub trait QuicIoExt: QuicIO {
@@ -99,7 +112,8 @@ pub trait QuicIoExt: QuicIO {
     ) -> impl Future<Output = io::Result<()>> + Send {
         async move {
             while !bufs.is_empty() {
-                let sent = core::future::poll_fn(|cx| self.poll_send(cx, bufs, hdr)).await?;
+                let sent =
+                    core::future::poll_fn(|cx| self.poll_send(cx, bufs, hdr.clone())).await?;
                 bufs = &bufs[sent..];
             }
             Ok(())
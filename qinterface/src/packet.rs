@@ -7,7 +7,7 @@ use qbase::{
             decrypt_packet, remove_protection_of_long_packet, remove_protection_of_short_packet,
         },
         header::long::InitialHeader,
-        keys::ArcOneRttPacketKeys,
+        keys::{ArcOneRttPacketKeys, PathPacketKey},
         number::{InvalidPacketNumber, PacketNumber},
     },
 };
@@ -161,10 +161,15 @@ where
         }))
     }
 
+    /// Remove the header protection and decrypt the 1-RTT packet.
+    ///
+    /// `path_id` is the multipath path identifier the packet belongs to, it is
+    /// always 0 if multipath is not negotiated.
     pub fn decrypt_short_packet(
         mut self,
         hpk: &dyn HeaderProtectionKey,
         pk: &ArcOneRttPacketKeys,
+        path_id: u32,
         pn_decoder: impl FnOnce(PacketNumber) -> Result<u64, InvalidPacketNumber>,
     ) -> Option<Result<PlainPacket<H>, QuicError>> {
         let pkt_buf = self.payload.as_mut();
@@ -187,7 +192,7 @@ where
                 return None;
             }
         };
-        let pk = pk.lock_guard().get_remote(key_phase, decoded_pn);
+        let pk = PathPacketKey::bind(path_id, pk.lock_guard().get_remote(key_phase, decoded_pn));
         let body_offset = self.payload_offset + undecoded_pn.size();
         let body_length = match decrypt_packet(pk.as_ref(), decoded_pn, pkt_buf, body_offset) {
             Ok(body_length) => body_length,
@@ -301,6 +306,19 @@ where
         )
     }
 
+    pub fn drop_on_path_id_unavailable(self) {
+        qevent::event!(
+            PacketDropped {
+                header: self.qlog_header(),
+                raw: self.raw_info(),
+                trigger: PacketDroppedTrigger::Genera
+            },
+            details = Map {
+                reason: "path id unavailable"
+            }
+        )
+    }
+
     pub fn log_received(&self, frames: impl Into<Vec<QuicFrame>>) {
         qevent::event!(PacketReceived {
             header: self.qlog_header(),
@@ -16,7 +16,7 @@ pub struct RcvdPacketQueue {
     handshake: PacketQueue<long::HandshakeHeader>,
     zero_rtt: PacketQueue<long::ZeroRttHeader>,
     one_rtt: PacketQueue<short::OneRttHeader>,
-    // pub retry:
+    retry: BoundQueue<(long::RetryHeader, Way)>,
 }
 
 impl Default for RcvdPacketQueue {
@@ -32,6 +32,7 @@ impl RcvdPacketQueue {
             handshake: BoundQueue::new(8),
             zero_rtt: BoundQueue::new(8),
             one_rtt: BoundQueue::new(128),
+            retry: BoundQueue::new(2),
         }
     }
 
@@ -51,11 +52,16 @@ impl RcvdPacketQueue {
         &self.one_rtt
     }
 
+    pub fn retry(&self) -> &BoundQueue<(long::RetryHeader, Way)> {
+        &self.retry
+    }
+
     pub fn close_all(&self) {
         self.initial.close();
         self.handshake.close();
         self.zero_rtt.close();
         self.one_rtt.close();
+        self.retry.close();
     }
 
     pub async fn deliver(&self, packet: Packet, way: Way) {
@@ -79,7 +85,9 @@ impl RcvdPacketQueue {
                 }
             },
             Packet::VN(_vn) => {}
-            Packet::Retry(_retry) => {}
+            Packet::Retry(retry) => {
+                _ = self.retry.send((retry, way)).await;
+            }
         }
     }
 }
@@ -43,7 +43,7 @@ impl Router {
         signpost: Signpost,
         queue: Arc<RcvdPacketQueue>,
     ) -> RouterEntry {
-        self.table.insert(signpost, queue.clone());
+        self.table.insert(signpost.clone(), queue.clone());
         RouterEntry {
             signpost,
             queue: Arc::downgrade(&queue),
@@ -66,13 +66,8 @@ impl Router {
             let signpost = Signpost::from(*dcid);
             self.table.get(&signpost).map(|queue| queue.clone())
         } else {
-            match link.dst() {
-                RealAddr::Internet(socket_addr) => {
-                    let signpost = Signpost::from(socket_addr);
-                    self.table.get(&signpost).map(|queue| queue.clone())
-                }
-                _ => None,
-            }
+            let signpost = Signpost::from(link.dst());
+            self.table.get(&signpost).map(|queue| queue.clone())
         }
     }
 
@@ -98,6 +93,18 @@ impl Router {
         rcvd_pkt_q.deliver(packet, way).await;
     }
 
+    /// Deliver the packet only if it can be routed to a connection, returns whether it was.
+    ///
+    /// Unlike [`Router::deliver`], packets that cannot be routed are dropped instead of being
+    /// passed to the connectionless handler.
+    pub async fn deliver_routed(&self, packet: Packet, way: Way) -> bool {
+        let Some(rcvd_pkt_q) = self.find_entry(&packet, &way.2) else {
+            return false;
+        };
+        rcvd_pkt_q.deliver(packet, way).await;
+        true
+    }
+
     pub fn on_connectless_packets<H>(&self, handler: H)
     where
         H: FnMut(Packet, Way) + Send + 'static,
@@ -118,10 +125,10 @@ impl Router {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct Signpost {
     cid: ConnectionId,
-    peer: Option<SocketAddr>,
+    peer: Option<RealAddr>,
 }
 
 impl From<ConnectionId> for Signpost {
@@ -133,8 +140,8 @@ impl From<ConnectionId> for Signpost {
     }
 }
 
-impl From<SocketAddr> for Signpost {
-    fn from(value: SocketAddr) -> Self {
+impl From<RealAddr> for Signpost {
+    fn from(value: RealAddr) -> Self {
         Self {
             cid: ConnectionId::default(),
             peer: Some(value),
@@ -142,6 +149,12 @@ impl From<SocketAddr> for Signpost {
     }
 }
 
+impl From<SocketAddr> for Signpost {
+    fn from(value: SocketAddr) -> Self {
+        Self::from(RealAddr::Internet(value))
+    }
+}
+
 #[must_use = "When RouterEntry dropped, this will remove the entry from the router table"]
 pub struct RouterEntry {
     signpost: Signpost,
@@ -151,7 +164,7 @@ pub struct RouterEntry {
 
 impl RouterEntry {
     pub fn signpost(&self) -> Signpost {
-        self.signpost
+        self.signpost.clone()
     }
 
     pub fn remove(&self) {
@@ -176,6 +189,18 @@ pub struct RouterRegistry<TX> {
     issued_cids: TX,
 }
 
+impl<TX> RouterRegistry<TX> {
+    /// Return a registry routing to the same connection, whose issued connection IDs are sent
+    /// through `issued_cids` instead.
+    pub fn with_issued_cids<U>(&self, issued_cids: U) -> RouterRegistry<U> {
+        RouterRegistry {
+            router: self.router.clone(),
+            rcvd_pkts_q: self.rcvd_pkts_q.clone(),
+            issued_cids,
+        }
+    }
+}
+
 impl<T> GenUniqueCid for RouterRegistry<T>
 where
     T: Send + Sync + 'static,
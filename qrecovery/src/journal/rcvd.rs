@@ -5,7 +5,7 @@ use std::{
 
 use bytes::BufMut;
 use qbase::{
-    frame::AckFrame,
+    frame::{AckFrame, PathAckFrame},
     net::tx::Signals,
     packet::{InvalidPacketNumber, Package, PacketNumber, PacketWriter},
     util::{IndexDeque, IndexError},
@@ -369,6 +369,23 @@ impl ArcRcvdJournal {
         AckPackege {
             journal: self,
             need_ack,
+            path_id: None,
+        }
+    }
+
+    /// Like [`ArcRcvdJournal::ack_package`], but the ack is sent in a PATH_ACK frame
+    /// for the packet number space of the multipath path `path_id`.
+    ///
+    /// The path 0 is still acknowledged with the ACK frame.
+    pub fn path_ack_package<'r>(
+        &'r self,
+        path_id: u32,
+        need_ack: Option<(u64, Instant)>,
+    ) -> AckPackege<'r> {
+        AckPackege {
+            journal: self,
+            need_ack,
+            path_id: (path_id != 0).then(|| VarInt::from_u32(path_id)),
         }
     }
 }
@@ -376,15 +393,22 @@ impl ArcRcvdJournal {
 pub struct AckPackege<'r> {
     journal: &'r ArcRcvdJournal,
     need_ack: Option<(u64, Instant)>,
+    path_id: Option<VarInt>,
 }
 
 impl<'r, Target> Package<Target> for AckPackege<'r>
 where
     Target: AsRef<PacketWriter<'r>> + ?Sized,
     AckFrame: Package<Target>,
+    PathAckFrame: Package<Target>,
 {
     fn dump(&mut self, target: &mut Target) -> Result<(), Signals> {
-        self.need_ack
+        // the PATH_ACK frame has a longer frame type and an extra path identifier
+        let extra_size = self
+            .path_id
+            .map_or(0, |path_id| 3 + path_id.encoding_size());
+        let mut ack_frame = self
+            .need_ack
             .or_else(|| self.journal.need_ack())
             .ok_or(Signals::TRANSPORT)
             .and_then(|(largest_ack, rcvd_time)| {
@@ -392,11 +416,14 @@ where
                     target.as_ref().packet_number(),
                     largest_ack,
                     rcvd_time,
-                    target.as_ref().remaining_mut(),
+                    target.as_ref().remaining_mut().saturating_sub(extra_size),
                 )
-            })?
-            .dump(target)
-            .unwrap();
+            })?;
+        match self.path_id {
+            Some(path_id) => PathAckFrame::new(path_id, ack_frame).dump(target),
+            None => ack_frame.dump(target),
+        }
+        .unwrap();
         Ok(())
     }
 }
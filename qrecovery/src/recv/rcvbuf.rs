@@ -5,19 +5,31 @@ use std::collections::VecDeque;
 use bytes::{Buf, BufMut, Bytes};
 
 /// 一段连续的数据片段，每个片段都是Bytes
+///
+/// 乱序读取后，片段的数据被取走(data为空)，但仍保留其位置，以免重传的数据被再次存入、交付
 #[derive(Debug, Default)]
 struct Segment {
     offset: u64,
+    end: u64,
     data: Bytes,
 }
 
 impl Segment {
     fn new_with_data(offset: u64, data: Bytes) -> Self {
-        Segment { offset, data }
+        let end = offset + data.len() as u64;
+        Segment { offset, end, data }
     }
 
     fn end(&self) -> u64 {
-        self.offset + self.data.len() as u64
+        self.end
+    }
+
+    fn len(&self) -> usize {
+        (self.end - self.offset) as usize
+    }
+
+    fn is_consumed(&self) -> bool {
+        self.data.is_empty()
     }
 }
 
@@ -127,7 +139,7 @@ impl RecvBuf {
                 //             | new_seg........|
                 // 绝大多数情况下都会先进入这一个分支
                 Ok(exist_seg_index) => {
-                    let length_covered = data.len().min(self.segments[exist_seg_index].data.len());
+                    let length_covered = data.len().min(self.segments[exist_seg_index].len());
                     data.advance(length_covered);
                     start += length_covered as u64;
                 }
@@ -223,7 +235,7 @@ impl RecvBuf {
         let (ControlFlow::Continue(continuous_end) | ControlFlow::Break(continuous_end)) =
             self.segments.iter().try_fold(self.nread, |offset, seg| {
                 if seg.offset == offset {
-                    ControlFlow::Continue(offset + seg.data.len() as u64)
+                    ControlFlow::Continue(seg.end())
                 } else {
                     ControlFlow::Break(offset)
                 }
@@ -269,6 +281,7 @@ impl RecvBuf {
     ///
     pub fn try_read(&mut self, dst: &mut impl BufMut) -> usize {
         let origin = dst.remaining_mut();
+        self.skip_consumed();
         while let Some(seg) = self.segments.front_mut() {
             if seg.offset != self.nread || !dst.has_remaining_mut() {
                 break;
@@ -281,10 +294,115 @@ impl RecvBuf {
                 seg.offset += read as u64;
             } else {
                 self.segments.pop_front();
+                self.skip_consumed();
             }
         }
         origin - dst.remaining_mut()
     }
+
+    /// Try to read a chunk of continuous data from [`RecvBuf`], at most `max` bytes, without
+    /// copying.
+    ///
+    /// Returns the offset of the chunk and the chunk, or [`None`] if the following data is not
+    /// continuous or there is no data.
+    ///
+    /// # Example
+    ///
+    /// ``` rust
+    /// # use bytes::Bytes;
+    /// # use qrecovery::recv::RecvBuf;
+    /// let mut recvbuf = RecvBuf::default();
+    /// recvbuf.recv(0, Bytes::from("012"));
+    /// recvbuf.recv(3, Bytes::from("345"));
+    /// recvbuf.recv(7, Bytes::from("789"));
+    ///
+    /// assert_eq!(recvbuf.try_read_chunk(2), Some((0, Bytes::from("01"))));
+    /// assert_eq!(recvbuf.try_read_chunk(usize::MAX), Some((2, Bytes::from("2"))));
+    /// assert_eq!(recvbuf.try_read_chunk(usize::MAX), Some((3, Bytes::from("345"))));
+    /// assert_eq!(recvbuf.try_read_chunk(usize::MAX), None);
+    /// ```
+    pub fn try_read_chunk(&mut self, max: usize) -> Option<(u64, Bytes)> {
+        self.skip_consumed();
+        if !self.is_readable() {
+            return None;
+        }
+        self.take_chunk(0, max)
+    }
+
+    /// Returns whether there is any unread data, continuous or not.
+    pub fn is_readable_unordered(&self) -> bool {
+        self.segments.iter().any(|seg| !seg.is_consumed())
+    }
+
+    /// Try to read a chunk of received data from [`RecvBuf`] in any order, at most `max` bytes,
+    /// without copying.
+    ///
+    /// The data with the smallest offset is read first, no matter whether the data before it has
+    /// been received. The data read is never delivered again, even if the peer retransmits it.
+    ///
+    /// Returns the offset of the chunk and the chunk, or [`None`] if there is no unread data.
+    ///
+    /// # Example
+    ///
+    /// ``` rust
+    /// # use bytes::Bytes;
+    /// # use qrecovery::recv::RecvBuf;
+    /// let mut recvbuf = RecvBuf::default();
+    /// recvbuf.recv(3, Bytes::from("345"));
+    /// assert_eq!(recvbuf.try_read_unordered(usize::MAX), Some((3, Bytes::from("345"))));
+    /// assert_eq!(recvbuf.nread(), 0);
+    ///
+    /// recvbuf.recv(0, Bytes::from("012345"));
+    /// assert_eq!(recvbuf.try_read_unordered(usize::MAX), Some((0, Bytes::from("012"))));
+    /// assert_eq!(recvbuf.try_read_unordered(usize::MAX), None);
+    /// assert_eq!(recvbuf.nread(), 6);
+    /// ```
+    pub fn try_read_unordered(&mut self, max: usize) -> Option<(u64, Bytes)> {
+        let index = self.segments.iter().position(|seg| !seg.is_consumed())?;
+        self.take_chunk(index, max)
+    }
+
+    // 取走第index段头部最多max字节的数据；若不是从nread开始的，已取走的部分仍保留位置
+    fn take_chunk(&mut self, index: usize, max: usize) -> Option<(u64, Bytes)> {
+        let seg = &mut self.segments[index];
+        let offset = seg.offset;
+        let chunk = seg.data.split_to(max.min(seg.data.len()));
+        if chunk.is_empty() {
+            return None;
+        }
+
+        if seg.data.is_empty() {
+            if offset == self.nread {
+                self.segments.remove(index);
+            }
+        } else {
+            seg.offset += chunk.len() as u64;
+            if offset != self.nread {
+                let consumed = Segment {
+                    offset,
+                    end: seg.offset,
+                    data: Bytes::new(),
+                };
+                self.segments.insert(index, consumed);
+            }
+        }
+        if offset == self.nread {
+            self.nread += chunk.len() as u64;
+        }
+        self.skip_consumed();
+        Some((offset, chunk))
+    }
+
+    // 乱序读走的数据，一旦前面的数据都被读取，就连续起来了，nread跳过它们
+    fn skip_consumed(&mut self) {
+        while let Some(seg) = self.segments.front() {
+            if seg.offset != self.nread || !seg.is_consumed() {
+                break;
+            }
+            self.nread = seg.end();
+            self.segments.pop_front();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -433,4 +551,29 @@ mod tests {
         assert_eq!(buf.remaining_mut(), 9);
         assert_eq!(dst[..11], b"hello world"[..]);
     }
+
+    #[test]
+    fn test_recvbuf_read_unordered() {
+        let mut rcvbuf = RecvBuf::default();
+        rcvbuf.recv(6, Bytes::from("world"));
+        rcvbuf.recv(11, Bytes::from("!"));
+        assert!(!rcvbuf.is_readable());
+        assert!(rcvbuf.is_readable_unordered());
+
+        assert_eq!(rcvbuf.try_read_unordered(3), Some((6, Bytes::from("wor"))));
+        assert_eq!(rcvbuf.try_read_unordered(3), Some((9, Bytes::from("ld"))));
+
+        // the retransmitted data that has been read is not delivered again
+        assert_eq!(rcvbuf.recv(4, Bytes::from("o world")), 0);
+        assert_eq!(rcvbuf.try_read_unordered(10), Some((4, Bytes::from("o "))));
+        assert_eq!(rcvbuf.try_read_unordered(10), Some((11, Bytes::from("!"))));
+        assert!(!rcvbuf.is_readable_unordered());
+        assert_eq!(rcvbuf.nread(), 0);
+
+        rcvbuf.recv(0, Bytes::from("hello"));
+        assert_eq!(rcvbuf.try_read_chunk(10), Some((0, Bytes::from("hell"))));
+        assert_eq!(rcvbuf.nread(), 12);
+        assert!(rcvbuf.is_empty());
+        assert_eq!(rcvbuf.largest_offset(), 12);
+    }
 }
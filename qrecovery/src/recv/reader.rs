@@ -1,10 +1,12 @@
 use std::{
+    future,
     io::{self},
     ops::DerefMut,
     pin::Pin,
     task::{Context, Poll},
 };
 
+use bytes::Bytes;
 use qbase::{
     frame::{MaxStreamDataFrame, SendFrame, StopSendingFrame},
     varint::VARINT_MAX,
@@ -51,6 +53,9 @@ pub trait StopSending {
 /// Alternatively, if the [`read`] result an error, its indicates that the stream has been `reset`, or
 /// closed duo to other reasons. It's also okay to drop the [`Reader`] after that.
 ///
+/// Besides, the data can be read as [`Bytes`] chunks without copying by [`read_chunk`], or in any
+/// order by [`read_chunk_unordered`], with the offset of each chunk in the stream.
+///
 /// You can call [`stop`] to tell the peer to stop sending data with the given error code, the [`Reader`]
 /// will be consumed, and the error code will be sent to the peer.
 ///
@@ -79,6 +84,8 @@ pub trait StopSending {
 /// [`TcpStream`]: tokio::net::TcpStream
 /// [`read`]: tokio::io::AsyncReadExt::read
 /// [`stop`]: Reader::stop
+/// [`read_chunk`]: Reader::read_chunk
+/// [`read_chunk_unordered`]: Reader::read_chunk_unordered
 /// [`RESET_STREAM frame`]: https://www.rfc-editor.org/rfc/rfc9000.html#name-reset_stream-frames
 #[derive(Debug)]
 pub struct Reader<TX> {
@@ -135,6 +142,105 @@ where
 
 impl<TX: Unpin> Unpin for Reader<TX> {}
 
+impl<TX> Reader<TX>
+where
+    TX: SendFrame<MaxStreamDataFrame>,
+{
+    fn poll_read_chunk_by(
+        &mut self,
+        cx: &mut Context<'_>,
+        max: usize,
+        ordered: bool,
+    ) -> Poll<io::Result<Option<(u64, Bytes)>>> {
+        debug_assert!(max > 0, "read a chunk of at most 0 bytes");
+        let _span = (self.qlog_span.enter(), self.tracing_span.enter());
+
+        let mut recver = self.inner.recver();
+        let receiving_state = recver.as_mut().map_err(|e| e.clone())?;
+        match receiving_state {
+            Recver::Recv(r) => r.poll_read_chunk(cx, max, ordered).map_ok(Some),
+            Recver::SizeKnown(r) => r.poll_read_chunk(cx, max, ordered).map_ok(Some),
+            Recver::DataRcvd(r) => {
+                let chunk = r.read_chunk(max, ordered);
+                if r.is_all_read() {
+                    r.upgrade();
+                    *receiving_state = Recver::DataRead;
+                }
+                Poll::Ready(Ok(chunk))
+            }
+            Recver::DataRead => Poll::Ready(Ok(None)),
+            Recver::ResetRcvd(_) | Recver::ResetRead(_) => {
+                Poll::Ready(Err(read_reset(receiving_state)))
+            }
+        }
+    }
+
+    /// Attempt to read the next chunk of the ordered data, at most `max` bytes, without copying.
+    ///
+    /// Return the offset of the chunk in the stream and the chunk, or [`None`] if all data has been
+    /// read and the stream is closed.
+    pub fn poll_read_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+        max: usize,
+    ) -> Poll<io::Result<Option<(u64, Bytes)>>> {
+        self.poll_read_chunk_by(cx, max, true)
+    }
+
+    /// Read the next chunk of the ordered data, at most `max` bytes, without copying.
+    ///
+    /// See [`Reader::poll_read_chunk`] for more.
+    pub async fn read_chunk(&mut self, max: usize) -> io::Result<Option<(u64, Bytes)>> {
+        future::poll_fn(|cx| self.poll_read_chunk(cx, max)).await
+    }
+
+    /// Attempt to read a chunk of the received data in any order, at most `max` bytes, without
+    /// copying.
+    ///
+    /// The chunks are delivered as soon as they are received, even if the data before them is
+    /// still missing, so the application can place the data itself by the offset, without being
+    /// blocked by the lost packets. The data read is never delivered again.
+    ///
+    /// The unordered reads should not be mixed with the ordered ones, the data read unordered will
+    /// be skipped by the ordered reads.
+    ///
+    /// Return the offset of the chunk in the stream and the chunk, or [`None`] if all data has been
+    /// read and the stream is closed.
+    pub fn poll_read_chunk_unordered(
+        &mut self,
+        cx: &mut Context<'_>,
+        max: usize,
+    ) -> Poll<io::Result<Option<(u64, Bytes)>>> {
+        self.poll_read_chunk_by(cx, max, false)
+    }
+
+    /// Read a chunk of the received data in any order, at most `max` bytes, without copying.
+    ///
+    /// See [`Reader::poll_read_chunk_unordered`] for more.
+    pub async fn read_chunk_unordered(&mut self, max: usize) -> io::Result<Option<(u64, Bytes)>> {
+        future::poll_fn(|cx| self.poll_read_chunk_unordered(cx, max)).await
+    }
+}
+
+fn read_reset<TX>(receiving_state: &mut Recver<TX>) -> io::Error {
+    match receiving_state {
+        Recver::ResetRcvd(r) => {
+            qevent::event!(StreamStateUpdated {
+                stream_id: r.stream_id().id(),
+                stream_type: r.stream_id().dir(),
+                old: GranularStreamStates::ResetReceived,
+                new: GranularStreamStates::ResetRead,
+                stream_side: StreamSide::Receiving
+            });
+            let reset_stream_error = (&*r).into();
+            *receiving_state = Recver::ResetRead(reset_stream_error);
+            io::Error::new(io::ErrorKind::BrokenPipe, reset_stream_error)
+        }
+        Recver::ResetRead(r) => io::Error::new(io::ErrorKind::BrokenPipe, *r),
+        _ => unreachable!("the stream is not reset"),
+    }
+}
+
 impl<TX> AsyncRead for Reader<TX>
 where
     TX: SendFrame<MaxStreamDataFrame>,
@@ -161,22 +267,9 @@ where
                 Poll::Ready(Ok(()))
             }
             Recver::DataRead => Poll::Ready(Ok(())),
-            Recver::ResetRcvd(r) => {
-                qevent::event!(StreamStateUpdated {
-                    stream_id: r.stream_id().id(),
-                    stream_type: r.stream_id().dir(),
-                    old: GranularStreamStates::ResetReceived,
-                    new: GranularStreamStates::ResetRead,
-                    stream_side: StreamSide::Receiving
-                });
-                let reset_stream_error = (&*r).into();
-                *receiving_state = Recver::ResetRead(reset_stream_error);
-                Poll::Ready(Err(io::Error::new(
-                    io::ErrorKind::BrokenPipe,
-                    reset_stream_error,
-                )))
+            Recver::ResetRcvd(_) | Recver::ResetRead(_) => {
+                Poll::Ready(Err(read_reset(receiving_state)))
             }
-            Recver::ResetRead(r) => Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, *r))),
         }
     }
 }
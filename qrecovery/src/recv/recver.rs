@@ -25,6 +25,8 @@ pub(super) struct Recv<TX> {
     stream_id: StreamId,
     rcvbuf: rcvbuf::RecvBuf,
     read_waker: Option<Waker>,
+    // 等待读取的是否乱序读，乱序读时，收到任何新数据都要唤醒
+    read_unordered: bool,
     stop_state: Option<u64>,
     broker: TX,
     largest: u64,
@@ -50,25 +52,47 @@ where
                 from: StreamDataLocation::Transport,
                 to: StreamDataLocation::Application,
             });
-
-            let threshold = 1_000_000;
-            if self.rcvbuf.nread() + threshold > self.max_stream_data {
-                let max_stream_data = (self.rcvbuf.nread() + threshold * 2).min(VARINT_MAX);
-                if max_stream_data > self.max_stream_data {
-                    self.max_stream_data = max_stream_data;
-                    self.broker.send_frame([MaxStreamDataFrame::new(
-                        self.stream_id,
-                        VarInt::from_u64(max_stream_data).unwrap(),
-                    )]);
-                }
-            }
-
+            self.update_window();
             Poll::Ready(Ok(()))
         } else {
             self.read_waker = Some(cx.waker().clone());
+            self.read_unordered = false;
             Poll::Pending
         }
     }
+
+    pub(super) fn poll_read_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+        max: usize,
+        ordered: bool,
+    ) -> Poll<io::Result<(u64, Bytes)>> {
+        match read_chunk(&mut self.rcvbuf, self.stream_id, max, ordered) {
+            Some(chunk) => {
+                self.update_window();
+                Poll::Ready(Ok(chunk))
+            }
+            None => {
+                self.read_waker = Some(cx.waker().clone());
+                self.read_unordered = !ordered;
+                Poll::Pending
+            }
+        }
+    }
+
+    fn update_window(&mut self) {
+        let threshold = 1_000_000;
+        if self.rcvbuf.nread() + threshold > self.max_stream_data {
+            let max_stream_data = (self.rcvbuf.nread() + threshold * 2).min(VARINT_MAX);
+            if max_stream_data > self.max_stream_data {
+                self.max_stream_data = max_stream_data;
+                self.broker.send_frame([MaxStreamDataFrame::new(
+                    self.stream_id,
+                    VarInt::from_u64(max_stream_data).unwrap(),
+                )]);
+            }
+        }
+    }
 }
 
 impl<TX> Recv<TX>
@@ -128,6 +152,7 @@ impl<TX: Clone> Recv<TX> {
             stop_state: self.stop_state.take(),
             broker: self.broker.clone(),
             read_waker: self.read_waker.take(),
+            read_unordered: self.read_unordered,
         })
     }
 }
@@ -138,6 +163,7 @@ impl<TX> Recv<TX> {
             stream_id,
             rcvbuf: rcvbuf::RecvBuf::default(),
             read_waker: None,
+            read_unordered: false,
             stop_state: None,
             broker,
             largest: 0,
@@ -187,7 +213,8 @@ impl<TX> Recv<TX> {
         if self.largest < data_end {
             self.largest = data_end;
         }
-        if self.rcvbuf.is_readable() {
+        if self.rcvbuf.is_readable() || (self.read_unordered && self.rcvbuf.is_readable_unordered())
+        {
             if let Some(waker) = self.read_waker.take() {
                 waker.wake()
             }
@@ -239,6 +266,7 @@ pub struct SizeKnown<TX> {
     stream_id: StreamId,
     rcvbuf: rcvbuf::RecvBuf,
     read_waker: Option<Waker>,
+    read_unordered: bool,
     stop_state: Option<u64>,
     broker: TX,
     final_size: u64,
@@ -298,7 +326,8 @@ impl<TX> SizeKnown<TX> {
             },
             fresh_data
         );
-        if self.rcvbuf.is_readable() {
+        if self.rcvbuf.is_readable() || (self.read_unordered && self.rcvbuf.is_readable_unordered())
+        {
             if let Some(waker) = self.read_waker.take() {
                 waker.wake()
             }
@@ -339,10 +368,27 @@ impl<TX> SizeKnown<TX> {
             Poll::Ready(Ok(()))
         } else {
             self.read_waker = Some(cx.waker().clone());
+            self.read_unordered = false;
             Poll::Pending
         }
     }
 
+    pub(super) fn poll_read_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+        max: usize,
+        ordered: bool,
+    ) -> Poll<io::Result<(u64, Bytes)>> {
+        match read_chunk(&mut self.rcvbuf, self.stream_id, max, ordered) {
+            Some(chunk) => Poll::Ready(Ok(chunk)),
+            None => {
+                self.read_waker = Some(cx.waker().clone());
+                self.read_unordered = !ordered;
+                Poll::Pending
+            }
+        }
+    }
+
     pub(super) fn recv_reset(&mut self, reset_frame: &ResetStreamFrame) -> Result<(), QuicError> {
         let final_size = reset_frame.final_size();
         if final_size != self.final_size {
@@ -449,11 +495,37 @@ impl DataRcvd {
         });
     }
 
+    /// Read a chunk of the remaining data, [`None`] indicates the end.
+    pub(super) fn read_chunk(&mut self, max: usize, ordered: bool) -> Option<(u64, Bytes)> {
+        read_chunk(&mut self.rcvbuf, self.stream_id, max, ordered)
+    }
+
     pub(super) fn is_all_read(&self) -> bool {
         self.rcvbuf.is_empty()
     }
 }
 
+fn read_chunk(
+    rcvbuf: &mut rcvbuf::RecvBuf,
+    stream_id: StreamId,
+    max: usize,
+    ordered: bool,
+) -> Option<(u64, Bytes)> {
+    let (offset, chunk) = if ordered {
+        rcvbuf.try_read_chunk(max)?
+    } else {
+        rcvbuf.try_read_unordered(max)?
+    };
+    qevent::event!(StreamDataMoved {
+        stream_id,
+        offset,
+        length: chunk.len() as u64,
+        from: StreamDataLocation::Transport,
+        to: StreamDataLocation::Application,
+    });
+    Some((offset, chunk))
+}
+
 fn log_reset_event(stream_id: StreamId, old: GranularStreamStates) {
     qevent::event!(StreamStateUpdated {
         stream_id: stream_id.id(),
@@ -57,6 +57,23 @@ impl<F> ArcReliableFrameDeque<F> {
 
     /// Try to load the frame in deque and encode it into the `packet`.
     pub fn try_load_frames_into<P: ?Sized>(&self, packet: &mut P) -> Result<(), Signals>
+    where
+        for<'a> &'a F: Package<P>,
+    {
+        self.try_load_frames_into_and(packet, |_| {})
+    }
+
+    /// Like [`try_load_frames_into`], but `on_loaded` is called with every frame
+    /// that has been encoded into the `packet`.
+    ///
+    /// `on_loaded` is called with the deque locked, it must not access the deque.
+    ///
+    /// [`try_load_frames_into`]: ArcReliableFrameDeque::try_load_frames_into
+    pub fn try_load_frames_into_and<P: ?Sized>(
+        &self,
+        packet: &mut P,
+        mut on_loaded: impl FnMut(&F),
+    ) -> Result<(), Signals>
     where
         for<'a> &'a F: Package<P>,
     {
@@ -66,6 +83,7 @@ impl<F> ArcReliableFrameDeque<F> {
         }
         while let Some(mut frame) = deque.front() {
             frame.dump(packet)?;
+            on_loaded(frame);
             deque.pop_front();
         }
         Ok(())
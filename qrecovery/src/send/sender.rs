@@ -4,6 +4,7 @@ use std::{
     task::{Context, Poll, Waker},
 };
 
+use bytes::Bytes;
 use qbase::{
     error::Error,
     frame::{ResetStreamError, ResetStreamFrame, SendFrame, StreamFrame},
@@ -22,6 +23,35 @@ use qevent::{
 
 use super::sndbuf::SendBuf;
 
+/// The data written by the application into the [`SendBuf`] of a stream.
+pub(super) trait WriteChunk {
+    fn size(&self) -> usize;
+
+    /// Write the first `n` bytes into the [`SendBuf`], return the number of bytes written.
+    fn write_into(self, sndbuf: &mut SendBuf, n: usize) -> usize;
+}
+
+impl WriteChunk for &[u8] {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn write_into(self, sndbuf: &mut SendBuf, n: usize) -> usize {
+        sndbuf.write(&self[..n])
+    }
+}
+
+/// The written part is split off from the [`Bytes`] and shared with the [`SendBuf`].
+impl WriteChunk for &mut Bytes {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn write_into(self, sndbuf: &mut SendBuf, n: usize) -> usize {
+        sndbuf.write_bytes(self.split_to(n))
+    }
+}
+
 fn log_reset_event(sid: StreamId, from_state: GranularStreamStates) {
     qevent::event!(StreamStateUpdated {
         stream_id: sid.id(),
@@ -89,7 +119,7 @@ impl<TX> ReadySender<TX> {
     pub(super) fn poll_write(
         &mut self,
         cx: &mut Context<'_>,
-        buf: &[u8],
+        buf: impl WriteChunk,
     ) -> Poll<io::Result<usize>> {
         if self.shutdown_waker.is_some() {
             Poll::Ready(Err(io::Error::new(
@@ -99,7 +129,7 @@ impl<TX> ReadySender<TX> {
         } else {
             let stream_data = self.sndbuf.written();
             if stream_data < self.max_stream_data {
-                let n = std::cmp::min((self.max_stream_data - stream_data) as usize, buf.len());
+                let n = std::cmp::min((self.max_stream_data - stream_data) as usize, buf.size());
                 qevent::event!(StreamDataMoved {
                     stream_id: self.stream_id,
                     offset: self.sndbuf.written(),
@@ -108,7 +138,7 @@ impl<TX> ReadySender<TX> {
                     to: StreamDataLocation::Transport,
                 });
                 self.tx_wakers.wake_all_by(Signals::WRITTEN);
-                Poll::Ready(Ok(self.sndbuf.write(&buf[..n])))
+                Poll::Ready(Ok(buf.write_into(&mut self.sndbuf, n)))
             } else {
                 self.writable_waker = Some(cx.waker().clone());
                 Poll::Pending
@@ -232,7 +262,7 @@ impl<TX> SendingSender<TX> {
     pub(super) fn poll_write(
         &mut self,
         cx: &mut Context<'_>,
-        buf: &[u8],
+        buf: impl WriteChunk,
     ) -> Poll<io::Result<usize>> {
         if self.shutdown_waker.is_some() {
             Poll::Ready(Err(io::Error::new(
@@ -242,7 +272,7 @@ impl<TX> SendingSender<TX> {
         } else {
             let stream_data = self.sndbuf.written();
             if stream_data < self.max_stream_data {
-                let n = std::cmp::min((self.max_stream_data - stream_data) as usize, buf.len());
+                let n = std::cmp::min((self.max_stream_data - stream_data) as usize, buf.size());
                 qevent::event!(StreamDataMoved {
                     stream_id: self.stream_id,
                     offset: self.sndbuf.written(),
@@ -251,7 +281,7 @@ impl<TX> SendingSender<TX> {
                     to: StreamDataLocation::Transport,
                 });
                 self.tx_wakers.wake_all_by(Signals::WRITTEN);
-                Poll::Ready(Ok(self.sndbuf.write(&buf[..n])))
+                Poll::Ready(Ok(buf.write_into(&mut self.sndbuf, n)))
             } else {
                 self.writable_waker = Some(cx.waker().clone());
                 Poll::Pending
@@ -683,7 +713,7 @@ mod tests {
         let broker = MockBroker::default();
         let mut sender = ReadySender::new(stream_id, buf_size, broker, Default::default());
 
-        let data = b"test";
+        let data: &[u8] = b"test";
         let mut cx = Context::from_waker(futures::task::noop_waker_ref());
 
         if let Poll::Ready(result) = sender.poll_write(&mut cx, data) {
@@ -697,6 +727,24 @@ mod tests {
         assert!(result.is_pending());
     }
 
+    #[test]
+    fn test_ready_sender_poll_write_bytes() {
+        let stream_id = StreamId::new(Role::Client, Dir::Bi, 0);
+        let broker = MockBroker::default();
+        let mut sender = ReadySender::new(stream_id, 10, broker, Default::default());
+
+        let mut data = Bytes::from_static(b"more than ten bytes");
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let result = sender.poll_write(&mut cx, &mut data);
+        assert!(matches!(result, Poll::Ready(Ok(10))));
+        assert_eq!(data, Bytes::from_static(b"ten bytes"));
+        assert_eq!(sender.sndbuf.written(), 10);
+
+        let result = sender.poll_write(&mut cx, &mut data);
+        assert!(result.is_pending());
+        assert_eq!(data.len(), 9);
+    }
+
     #[test]
     fn test_sender_state_transitions() {
         let stream_id = StreamId::new(Role::Client, Dir::Bi, 0);
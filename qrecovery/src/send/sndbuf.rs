@@ -5,6 +5,7 @@ use std::{
     ops::Range,
 };
 
+use bytes::{Buf, Bytes, BytesMut};
 use qbase::net::tx::Signals;
 
 /// To indicate the state of a data segment, it is colored.
@@ -563,6 +564,126 @@ impl BufMap {
     }
 }
 
+/// The continuous data written to a [`SendBuf`], stored as a queue of chunks.
+///
+/// Chunks written by [`SendBuf::write_bytes`] are kept as they are, reference-counted, without
+/// copying. Data written by [`SendBuf::write`] is copied and gathered into the `tail`, which is
+/// frozen into a chunk once a shared chunk is appended behind it.
+///
+/// The position of each chunk is kept aside, a stream buffering thousands of chunks locates the
+/// data to send by binary search, rather than walking through the chunks for every frame.
+#[derive(Default, Debug)]
+struct Chunks {
+    chunks: VecDeque<Bytes>,
+    // 每块的起始位置，从最初写入的数据算起，前进时不必更新
+    starts: VecDeque<u64>,
+    // 头部数据的位置
+    front: u64,
+    // 所有块的长度之和，不含tail
+    chunks_len: usize,
+    tail: BytesMut,
+}
+
+impl Chunks {
+    /// Chunks shorter than this are copied into the tail, so that a stream written in tiny
+    /// chunks is not sent in tiny frames.
+    const MIN_SHARED_LEN: usize = 1024;
+
+    fn with_capacity(n: usize) -> Self {
+        Self {
+            tail: BytesMut::with_capacity(n),
+            ..Default::default()
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.chunks_len + self.tail.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chunks.is_empty() && self.tail.is_empty()
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) {
+        self.tail.extend_from_slice(data);
+    }
+
+    fn push_chunk(&mut self, chunk: Bytes) {
+        self.starts.push_back(self.front + self.chunks_len as u64);
+        self.chunks_len += chunk.len();
+        self.chunks.push_back(chunk);
+    }
+
+    fn push(&mut self, chunk: Bytes) {
+        if chunk.len() < Self::MIN_SHARED_LEN {
+            self.tail.extend_from_slice(&chunk);
+            return;
+        }
+        if !self.tail.is_empty() {
+            let tail = self.tail.split().freeze();
+            self.push_chunk(tail);
+        }
+        self.push_chunk(chunk);
+    }
+
+    // 丢弃头部n字节已被确认的数据，整块的直接释放，部分的则切片前进
+    fn advance(&mut self, mut n: usize) {
+        self.front += n as u64;
+        while n > 0 {
+            match self.chunks.front_mut() {
+                Some(chunk) if chunk.len() <= n => {
+                    n -= chunk.len();
+                    self.chunks_len -= chunk.len();
+                    self.chunks.pop_front();
+                    self.starts.pop_front();
+                }
+                Some(chunk) => {
+                    chunk.advance(n);
+                    self.chunks_len -= n;
+                    self.starts[0] = self.front;
+                    return;
+                }
+                None => {
+                    self.tail.advance(n);
+                    return;
+                }
+            }
+        }
+    }
+
+    // 找到start所在的段及其后一段，start在段内的偏移
+    fn locate(&self, start: usize) -> (&[u8], &[u8], usize) {
+        if start >= self.chunks_len {
+            return (&self.tail, &[], start - self.chunks_len);
+        }
+        let pos = self.front + start as u64;
+        let idx = self
+            .starts
+            .partition_point(|&chunk_start| chunk_start <= pos)
+            - 1;
+        let next = self
+            .chunks
+            .get(idx + 1)
+            .map_or(&self.tail[..], |chunk| &chunk[..]);
+        (&self.chunks[idx], next, (pos - self.starts[idx]) as usize)
+    }
+
+    // 从start开始，最多能连续取出多少字节：一次只跨越两段，以便用两个切片表示
+    fn contiguous(&self, start: usize) -> usize {
+        let (segment, next, offset) = self.locate(start);
+        (segment.len() + next.len()).saturating_sub(offset)
+    }
+
+    fn slices(&self, range: Range<usize>) -> (&[u8], &[u8]) {
+        let (segment, next, offset) = self.locate(range.start);
+        let end = offset + range.len();
+        if end <= segment.len() {
+            return (&segment[offset..end], &[]);
+        }
+        (&segment[offset..], &next[..end - segment.len()])
+    }
+}
+
 /// Data to be reliably sent to the peer will first be cached in [`SendBuf`].
 ///
 /// SendBuf will record the status of data that has been or has not been sent.
@@ -576,15 +697,22 @@ impl BufMap {
 /// The data picked up may not continuous, the [`receive buffer`] will assemble the data into continuous before
 /// passing them to the application layer.
 ///
+/// The data can be written by copying([`write`]), or by sharing the [`Bytes`] the application already
+/// holds([`write_bytes`]). Shared chunks are kept until acknowledged, the data picked up for sending and
+/// retransmission is sliced from them.
+///
 /// [`pick_up`]: SendBuf::pick_up
 /// [`on_data_acked`]: SendBuf::on_data_acked
 /// [`may_loss_data`]: SendBuf::may_loss_data
+/// [`write`]: SendBuf::write
+/// [`write_bytes`]: SendBuf::write_bytes
 /// [`receive buffer`]: crate::recv::RecvBuf
 #[derive(Default, Debug)]
 pub struct SendBuf {
     offset: u64,
-    // 写入数据的环形队列，与接收队列不同的是，它是连续的
-    data: VecDeque<u8>,
+    // 写入数据的块队列，与接收队列不同的是，它是连续的
+    data: Chunks,
+    capacity: usize,
     state: BufMap,
 }
 
@@ -593,7 +721,8 @@ impl SendBuf {
     pub fn with_capacity(n: usize) -> Self {
         Self {
             offset: 0,
-            data: VecDeque::with_capacity(n),
+            data: Chunks::with_capacity(n),
+            capacity: n,
             state: BufMap::default(),
         }
     }
@@ -613,7 +742,22 @@ impl SendBuf {
         // 写的数据量受流量控制限制，Crypto流则受Crypto流自身控制
         let n = data.len();
         if n > 0 {
-            self.data.extend(data);
+            self.data.extend_from_slice(data);
+            self.state.extend_to(self.written() + n as u64);
+        }
+        n
+    }
+
+    /// Write a chunk of data to the [`SendBuf`] without copying it.
+    ///
+    /// The chunk is kept until all of it has been acknowledged by the peer. Small chunks are
+    /// copied, like [`SendBuf::write`] does.
+    ///
+    /// Return the number of bytes written, always equal to the length of the `chunk`.
+    pub fn write_bytes(&mut self, chunk: Bytes) -> usize {
+        let n = chunk.len();
+        if n > 0 {
+            self.data.push(chunk);
             self.state.extend_to(self.written() + n as u64);
         }
         n
@@ -634,9 +778,9 @@ impl SendBuf {
         self.state.sent()
     }
 
-    /// Return the number of bytes can be written without reallocation.
+    /// Return the number of bytes can be written without exceeding the capacity.
     pub fn remaining_mut(&self) -> usize {
-        self.data.capacity() - self.data.len()
+        self.capacity.saturating_sub(self.data.len())
     }
 
     // 无需close：不在写入即可，具体到某个状态，才有close
@@ -662,8 +806,8 @@ impl SendBuf {
     /// Otherwise, return a tuple:
     /// * `u64`: offset, the starting position of the data.
     /// * `bool`: whether the data is new(not retransmitted).
-    /// * `(&[u8], &[u8])`: the data picked up, duo to the internal buffer is a ring buffer, the data
-    ///   picked up is in two parts, the begin of the second slice are the end of the first slice
+    /// * `(&[u8], &[u8])`: the data picked up, duo to the internal buffer is a queue of chunks, the
+    ///   data picked up is in two parts, the begin of the second slice are the end of the first slice
     pub fn pick_up<P>(
         &mut self,
         predicate: P,
@@ -673,16 +817,17 @@ impl SendBuf {
     where
         P: Fn(u64) -> Option<usize>,
     {
+        let (data, offset) = (&self.data, self.offset);
         self.state
-            .pick(predicate, flow_limit, max_data)
+            .pick(
+                |pos| predicate(pos).map(|n| n.min(data.contiguous((pos - offset) as usize))),
+                flow_limit,
+                max_data,
+            )
             .map(|(range, is_fresh)| {
-                let start = (range.start - self.offset) as usize;
-                let end = (range.end - self.offset) as usize;
-
-                let (l, r) = self.data.as_slices();
-                let s1 = &l[start.min(l.len())..l.len().min(end)];
-                let s2 = &r[start.saturating_sub(l.len())..end.saturating_sub(l.len())];
-                (range.start, is_fresh, (s1, s2))
+                let start = (range.start - offset) as usize;
+                let end = (range.end - offset) as usize;
+                (range.start, is_fresh, data.slices(start..end))
             })
     }
 
@@ -696,7 +841,7 @@ impl SendBuf {
         // 对于头部连续确认接收到的，还要前进，以免浪费空间
         let min_unrecved_pos = self.state.shift();
         if self.offset < min_unrecved_pos {
-            self.data.drain(..(min_unrecved_pos - self.offset) as usize);
+            self.data.advance((min_unrecved_pos - self.offset) as usize);
             self.offset = min_unrecved_pos;
         }
     }
@@ -726,9 +871,10 @@ impl SendBuf {
 
 #[cfg(test)]
 mod tests {
+    use bytes::Bytes;
     use qbase::net::tx::Signals;
 
-    use super::{BufMap, Color, State};
+    use super::{BufMap, Color, SendBuf, State};
 
     #[test]
     fn test_state() {
@@ -1232,4 +1378,86 @@ mod tests {
         buf_map.may_loss(&(0..46));
         assert_eq!(buf_map.0, vec![State::encode(2, Color::Lost)])
     }
+
+    #[test]
+    fn test_sndbuf_write_bytes() {
+        let mut sndbuf = SendBuf::with_capacity(16);
+        let chunk = Bytes::from(vec![1u8; 2000]);
+        assert_eq!(sndbuf.write(b"hello"), 5);
+        assert_eq!(sndbuf.write_bytes(chunk.clone()), 2000);
+        assert_eq!(sndbuf.write_bytes(Bytes::from_static(b"world")), 5);
+        assert_eq!(sndbuf.written(), 2010);
+
+        // the copied data and the shared chunk, never more than two parts
+        let (offset, is_fresh, (s1, s2)) = sndbuf
+            .pick_up(|_| Some(1500), usize::MAX, u64::MAX)
+            .unwrap();
+        assert_eq!((offset, is_fresh), (0, true));
+        assert_eq!((s1, s2.len()), (&b"hello"[..], 1495));
+        assert_eq!(s2.as_ptr(), chunk.as_ptr());
+
+        let (offset, _, (s1, s2)) = sndbuf
+            .pick_up(|_| Some(1500), usize::MAX, u64::MAX)
+            .unwrap();
+        assert_eq!(offset, 1500);
+        assert_eq!((s1.len(), s2), (505, &b"world"[..]));
+
+        // retransmission is sliced from the shared chunk too
+        sndbuf.may_loss_data(&(0..1500));
+        let (offset, is_fresh, (s1, s2)) = sndbuf
+            .pick_up(|_| Some(1000), usize::MAX, u64::MAX)
+            .unwrap();
+        assert_eq!((offset, is_fresh), (0, false));
+        assert_eq!((s1.len(), s2.as_ptr()), (5, chunk.as_ptr()));
+
+        sndbuf.on_data_acked(&(0..1000));
+        let (offset, _, (s1, s2)) = sndbuf
+            .pick_up(|_| Some(1000), usize::MAX, u64::MAX)
+            .unwrap();
+        assert_eq!(offset, 1000);
+        assert_eq!(s1.as_ptr(), chunk[995..].as_ptr());
+        assert!(s2.is_empty());
+
+        sndbuf.on_data_acked(&(1000..2010));
+        assert!(sndbuf.is_all_rcvd());
+    }
+
+    #[test]
+    fn test_sndbuf_many_chunks() {
+        let mut sndbuf = SendBuf::with_capacity(0);
+        for byte in 0..100u8 {
+            sndbuf.write_bytes(Bytes::from(vec![byte; 1024]));
+        }
+        assert_eq!(sndbuf.data.len(), 100 * 1024);
+
+        // frames span the end of a chunk and the start of the next, chunks are acknowledged
+        // partially in between, the data is located from where the buffer starts now
+        let expected = |offset: u64, len: usize| {
+            (offset..offset + len as u64)
+                .map(|pos| (pos / 1024) as u8)
+                .collect::<Vec<_>>()
+        };
+        while let Ok((offset, is_fresh, (s1, s2))) =
+            sndbuf.pick_up(|_| Some(1500), usize::MAX, u64::MAX)
+        {
+            assert!(is_fresh);
+            assert_eq!([s1, s2].concat(), expected(offset, s1.len() + s2.len()));
+            let end = offset + (s1.len() + s2.len()) as u64;
+            let acked = (offset + 700).min(end);
+            sndbuf.on_data_acked(&(offset..acked));
+            assert_eq!(sndbuf.data.len(), 100 * 1024 - acked as usize);
+            if acked == end {
+                continue;
+            }
+
+            sndbuf.may_loss_data(&(acked..end));
+            let (offset, is_fresh, (s1, s2)) = sndbuf
+                .pick_up(|_| Some(1500), usize::MAX, u64::MAX)
+                .unwrap();
+            assert_eq!((offset, is_fresh), (acked, false));
+            assert_eq!([s1, s2].concat(), expected(offset, (end - acked) as usize));
+            sndbuf.on_data_acked(&(acked..end));
+        }
+        assert!(sndbuf.is_all_rcvd());
+    }
 }
@@ -1,14 +1,15 @@
 use std::{
-    io,
+    future, io,
     ops::DerefMut,
     pin::Pin,
     task::{Context, Poll},
 };
 
+use bytes::Bytes;
 use qbase::frame::{ResetStreamFrame, SendFrame};
 use tokio::io::AsyncWrite;
 
-use super::sender::{ArcSender, Sender};
+use super::sender::{ArcSender, Sender, WriteChunk};
 
 pub trait CancelStream {
     /// Cancels the stream with the given error code.
@@ -39,6 +40,9 @@ pub trait CancelStream {
 /// The [`flush`] and [`shutdown`] calls will be blocked until all data written to [`Writer`] has
 /// been sent and acknowledged by the peer.
 ///
+/// Data held as [`Bytes`] can be written without copying by [`write_bytes`] and [`write_chunks`],
+/// the stream keeps the chunks until the peer acknowledges them.
+///
 /// # Note
 ///
 /// The stream must be cancelled or shutdowned before the [`Writer`] dropped.
@@ -79,6 +83,8 @@ pub trait CancelStream {
 /// [`flush`]: tokio::io::AsyncWriteExt::flush
 /// [`shutdown`]: tokio::io::AsyncWriteExt::shutdown
 /// [`cancel`]: Writer::cancel
+/// [`write_bytes`]: Writer::write_bytes
+/// [`write_chunks`]: Writer::write_chunks
 /// [`STOP_SENDING frame`]: https://www.rfc-editor.org/rfc/rfc9000.html#name-stop_sending-frames
 #[derive(Debug)]
 pub struct Writer<TX> {
@@ -134,20 +140,19 @@ where
 
 impl<TX: Unpin> Unpin for Writer<TX> {}
 
-impl<TX: Clone> AsyncWrite for Writer<TX> {
-    /// 往sndbuf里面写数据，直到写满MAX_STREAM_DATA，等通告窗口更新再写
-    fn poll_write(
-        self: Pin<&mut Self>,
+impl<TX: Clone> Writer<TX> {
+    fn poll_write_chunk(
+        &self,
         cx: &mut Context<'_>,
-        buf: &[u8],
+        chunk: impl WriteChunk,
     ) -> Poll<io::Result<usize>> {
         let _span = (self.qlog_span.enter(), self.tracing_span.enter());
 
         let mut sender = self.inner.sender();
         let sending_state = sender.as_mut().map_err(|e| e.clone())?;
         match sending_state {
-            Sender::Ready(s) => s.poll_write(cx, buf),
-            Sender::Sending(s) => s.poll_write(cx, buf),
+            Sender::Ready(s) => s.poll_write(cx, chunk),
+            Sender::Sending(s) => s.poll_write(cx, chunk),
             Sender::DataSent(_) => Poll::Ready(Err(io::Error::new(
                 io::ErrorKind::Unsupported,
                 "EOS has been sent",
@@ -165,6 +170,55 @@ impl<TX: Clone> AsyncWrite for Writer<TX> {
         }
     }
 
+    /// Attempt to write a chunk of data to the stream without copying it.
+    ///
+    /// As much of the `chunk` as the flow control allows is split off and kept by the stream,
+    /// reference-counted, until the peer acknowledges it; the rest is left in `chunk`. The data
+    /// sent and retransmitted is sliced from the kept chunks.
+    ///
+    /// Return the number of bytes written, or [`Poll::Pending`] if nothing can be written now.
+    pub fn poll_write_bytes(
+        &mut self,
+        cx: &mut Context<'_>,
+        chunk: &mut Bytes,
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_chunk(cx, chunk)
+    }
+
+    /// Write the whole chunk of data to the stream without copying it.
+    ///
+    /// See [`Writer::poll_write_bytes`] for more.
+    pub async fn write_bytes(&mut self, mut chunk: Bytes) -> io::Result<()> {
+        while !chunk.is_empty() {
+            future::poll_fn(|cx| self.poll_write_bytes(cx, &mut chunk)).await?;
+        }
+        Ok(())
+    }
+
+    /// Write the chunks of data to the stream in order, without copying them.
+    ///
+    /// See [`Writer::poll_write_bytes`] for more.
+    pub async fn write_chunks(
+        &mut self,
+        chunks: impl IntoIterator<Item = Bytes>,
+    ) -> io::Result<()> {
+        for chunk in chunks {
+            self.write_bytes(chunk).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<TX: Clone> AsyncWrite for Writer<TX> {
+    /// 往sndbuf里面写数据，直到写满MAX_STREAM_DATA，等通告窗口更新再写
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_chunk(cx, buf)
+    }
+
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         let _span = (self.qlog_span.enter(), self.tracing_span.enter());
 
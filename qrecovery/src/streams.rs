@@ -129,6 +129,11 @@ where
     pub fn accept_uni(&self) -> AcceptUniStream<'_, Ext<TX>> {
         self.0.accept_uni()
     }
+
+    /// Wait for all streams to finish, see [`raw::DataStreams::poll_idle`] for more.
+    pub async fn idle(&self) {
+        std::future::poll_fn(|cx| self.0.poll_idle(cx)).await
+    }
 }
 
 impl<TX> ReceiveFrame<StreamCtlFrame> for DataStreams<TX>
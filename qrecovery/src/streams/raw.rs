@@ -1,9 +1,9 @@
 use std::{
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicBool, Ordering::*},
     },
-    task::{Context, Poll, ready},
+    task::{Context, Poll, Waker, ready},
 };
 
 use bytes::BufMut;
@@ -124,6 +124,8 @@ pub struct DataStreams<TX> {
     listener: ArcListener<Ext<TX>>,
     tls_fin: AtomicBool,
     tx_wakers: ArcSendWakers,
+    // 等待所有流结束的任务，每当有流被移除时唤醒
+    idle_wakers: Mutex<Vec<Waker>>,
 
     initial_max_stream_data_bidi_local: u64,
     initial_max_stream_data_bidi_remote: u64,
@@ -343,6 +345,7 @@ where
 
             if is_all_rcvd {
                 set.remove(&frame.stream_id());
+                self.wake_idle();
             }
         }
     }
@@ -375,6 +378,7 @@ where
                         .remote
                         .on_end_of_stream(reset_frame.stream_id());
                 }
+                self.wake_idle();
             }
             // 如果流是双向的，接收部分的流独立地管理结束。其实是上层应用决定接收的部分是否同时结束
         }
@@ -417,6 +421,7 @@ where
                         self.stream_ids.remote.on_end_of_stream(sid);
                     }
                     set.remove(&sid);
+                    self.wake_idle();
                 }
                 return Ok(fresh_data);
             }
@@ -459,6 +464,7 @@ where
                         if s.is_terminated() {
                             self.stream_ids.remote.on_end_of_stream(reset.stream_id());
                         }
+                        self.wake_idle();
                     }
                 }
             }
@@ -578,6 +584,45 @@ where
         output.on_conn_error(error);
         input.on_conn_error(error);
         listener.on_conn_error(error);
+        self.wake_idle();
+    }
+
+    /// Poll whether all streams in the connection have finished.
+    ///
+    /// A stream is finished when both its sending and receiving parts are terminated, that is, all
+    /// data has been acknowledged or received, or the stream has been reset. Streams that are
+    /// created by peer but not accepted yet are also counted.
+    ///
+    /// The streams are also considered finished if a connection error occurred.
+    pub fn poll_idle(&self, cx: &mut Context<'_>) -> Poll<()> {
+        // 先注册waker再检查，避免检查后、注册前流被移除而错过唤醒
+        {
+            let mut wakers = self.idle_wakers.lock().unwrap();
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+
+        let no_output = self
+            .output
+            .streams()
+            .as_ref()
+            .map_or(true, |set| set.is_empty());
+        let no_input = self
+            .input
+            .streams()
+            .as_ref()
+            .map_or(true, |set| set.is_empty());
+        if no_output && no_input {
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+
+    fn wake_idle(&self) {
+        for waker in self.idle_wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
     }
 }
 
@@ -641,6 +686,7 @@ where
             ctrl_frames,
             tls_fin: AtomicBool::new(false),
             tx_wakers,
+            idle_wakers: Mutex::default(),
             initial_max_stream_data_bidi_local: local_params
                 .get::<u64>(ParameterId::InitialMaxStreamDataBidiLocal)
                 .expect("unreachable: default value will be got if the value unset"),
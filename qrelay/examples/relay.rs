@@ -0,0 +1,46 @@
+use clap::Parser;
+use qbase::net::addr::BindUri;
+use qinterface::factory::{ProductQuicIO, handy::DEFAULT_QUIC_IO_FACTORY};
+use qrelay::{Bandwidth, RelayServer};
+use tracing_subscriber::prelude::*;
+
+#[derive(Parser, Debug)]
+#[command(name = "relay")]
+struct Options {
+    #[arg(
+        short,
+        long,
+        default_value = "inet://0.0.0.0:4434",
+        help = "What BindUri to relay packets on"
+    )]
+    listen: BindUri,
+    #[arg(long, help = "Bandwidth limit of each peer, in bytes per second")]
+    bandwidth: Option<u64>,
+    #[arg(
+        long,
+        default_value_t = 64 * 1024,
+        help = "Maximum number of bytes a peer can burst"
+    )]
+    burst: u64,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> std::io::Result<()> {
+    let options = Options::parse();
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer().with_filter(
+                tracing_subscriber::EnvFilter::builder()
+                    .with_default_directive(tracing::level_filters::LevelFilter::INFO.into())
+                    .from_env_lossy(),
+            ),
+        )
+        .init();
+
+    let mut relay = RelayServer::new(DEFAULT_QUIC_IO_FACTORY.bind(options.listen)?);
+    if let Some(bytes_per_second) = options.bandwidth {
+        relay = relay.with_peer_bandwidth(Bandwidth::new(bytes_per_second, options.burst));
+    }
+    tracing::info!("Relaying packets on {}", relay.local_addr()?);
+    relay.run().await
+}
@@ -0,0 +1,96 @@
+use std::time::Instant;
+
+/// The bandwidth a peer is allowed to use on the relay.
+///
+/// It is a token bucket of `burst` bytes, refilled at `bytes_per_second` bytes per second. A
+/// datagram relayed from or to the peer takes as many tokens as its size, and is dropped if the
+/// bucket does not have enough tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bandwidth {
+    bytes_per_second: f64,
+    burst: f64,
+}
+
+impl Bandwidth {
+    /// Create a new [`Bandwidth`].
+    ///
+    /// The `burst` should be at least the size of the largest datagram, otherwise no datagram of
+    /// that size can ever get through.
+    pub fn new(bytes_per_second: u64, burst: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second as f64,
+            burst: burst as f64,
+        }
+    }
+
+    /// Returns the number of bytes refilled every second.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second as u64
+    }
+
+    /// Returns the maximum number of bytes that can be relayed at once.
+    pub fn burst(&self) -> u64 {
+        self.burst as u64
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn full(bandwidth: Bandwidth, now: Instant) -> Self {
+        Self {
+            tokens: bandwidth.burst,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, bandwidth: Bandwidth, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * bandwidth.bytes_per_second).min(bandwidth.burst);
+        self.updated_at = now;
+    }
+
+    /// Whether `size` bytes can be taken now, without taking them.
+    pub(crate) fn can_take(&mut self, bandwidth: Bandwidth, size: usize, now: Instant) -> bool {
+        self.refill(bandwidth, now);
+        self.tokens >= size as f64
+    }
+
+    /// Take `size` bytes, which must have been checked by [`TokenBucket::can_take`].
+    pub(crate) fn take(&mut self, size: usize) {
+        self.tokens -= size as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let bandwidth = Bandwidth::new(1000, 1500);
+        let now = Instant::now();
+        let mut bucket = TokenBucket::full(bandwidth, now);
+
+        assert!(bucket.can_take(bandwidth, 1200, now));
+        bucket.take(1200);
+        assert!(!bucket.can_take(bandwidth, 1200, now));
+        assert!(bucket.can_take(bandwidth, 300, now));
+
+        // 0.9秒后补充900字节
+        let later = now + Duration::from_millis(900);
+        assert!(bucket.can_take(bandwidth, 1200, later));
+        bucket.take(1200);
+
+        // 不会超过突发上限
+        let much_later = later + Duration::from_secs(60);
+        assert!(bucket.can_take(bandwidth, 1500, much_later));
+        assert!(!bucket.can_take(bandwidth, 1501, much_later));
+    }
+}
@@ -0,0 +1,113 @@
+use std::{io, net::SocketAddr};
+
+use qbase::net::{
+    addr::RealAddr,
+    route::{Link, PacketHeader, Pathway},
+};
+use qinterface::{QuicIO, QuicIoExt};
+
+// 控制报文首字节为0，不带QUIC的固定位，不会与QUIC包混淆
+//   0x00 "REGISTER"          注册为可被中继的节点
+//   0x00 "BIND " <addr>      请求经中继与已注册的节点通信
+const CONTROL_PREFIX: u8 = 0x00;
+const REGISTER: &[u8] = b"REGISTER";
+const BIND: &[u8] = b"BIND ";
+
+/// The control datagrams the peers send to the relay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Register the sender, so that other peers can bind to it.
+    Register,
+    /// Bind the sender to the registered peer, the packets from the sender whose connection IDs
+    /// are not known yet are relayed to the peer.
+    Bind(SocketAddr),
+}
+
+impl Control {
+    /// Parse a control datagram, returns `None` if the datagram is not a control datagram.
+    pub fn parse(datagram: &[u8]) -> Option<Self> {
+        let (&CONTROL_PREFIX, body) = datagram.split_first()? else {
+            return None;
+        };
+        if body == REGISTER {
+            return Some(Control::Register);
+        }
+        let peer = body.strip_prefix(BIND)?;
+        std::str::from_utf8(peer)
+            .ok()?
+            .parse()
+            .ok()
+            .map(Control::Bind)
+    }
+
+    /// Encode the control datagram.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut datagram = vec![CONTROL_PREFIX];
+        match self {
+            Control::Register => datagram.extend_from_slice(REGISTER),
+            Control::Bind(peer) => {
+                datagram.extend_from_slice(BIND);
+                datagram.extend_from_slice(peer.to_string().as_bytes());
+            }
+        }
+        datagram
+    }
+
+    /// Send the control datagram to the `relay` from the interface.
+    pub async fn send_to(
+        &self,
+        iface: &(impl QuicIO + ?Sized),
+        relay: SocketAddr,
+    ) -> io::Result<()> {
+        let local = iface.real_addr()?;
+        let pathway = Pathway::new(local.clone().into(), relay.into());
+        let link = Link::new(local, RealAddr::Internet(relay));
+        let datagram = self.encode();
+        let hdr = PacketHeader::new(pathway, link, 64, None, datagram.len() as u16);
+        iface.sendmmsg(&[io::IoSlice::new(&datagram)], hdr).await
+    }
+}
+
+/// Register the interface on the `relay`, so that other peers can reach it through the relay.
+///
+/// This also opens the mapping of the local NAT for the relay. The registration expires if the
+/// interface stays idle on the relay for a while, register again periodically to keep it.
+pub async fn register(iface: &(impl QuicIO + ?Sized), relay: SocketAddr) -> io::Result<()> {
+    Control::Register.send_to(iface, relay).await
+}
+
+/// Bind the interface to the `peer` registered on the `relay`, before connecting to the
+/// [`SocketEndpointAddr::Relay`] from the interface.
+///
+/// The relay does not acknowledge the binding, the datagram may be lost, send it again until the
+/// packets are relayed back from the peer, binding again is harmless.
+///
+/// [`SocketEndpointAddr::Relay`]: qbase::net::route::SocketEndpointAddr::Relay
+pub async fn bind(
+    iface: &(impl QuicIO + ?Sized),
+    relay: SocketAddr,
+    peer: SocketAddr,
+) -> io::Result<()> {
+    Control::Bind(peer).send_to(iface, relay).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control() {
+        let peer = "1.2.3.4:443".parse().unwrap();
+        for control in [Control::Register, Control::Bind(peer)] {
+            assert_eq!(Control::parse(&control.encode()), Some(control));
+        }
+        assert_eq!(Control::Bind(peer).encode(), b"\0BIND 1.2.3.4:443");
+
+        assert_eq!(Control::parse(&[]), None);
+        assert_eq!(Control::parse(&[0]), None);
+        assert_eq!(Control::parse(b"\0BIND"), None);
+        assert_eq!(Control::parse(b"\0BIND localhost"), None);
+        // QUIC包总是带着固定位
+        assert_eq!(Control::parse(b"\x40REGISTER"), None);
+    }
+}
@@ -0,0 +1,531 @@
+//! A packet relay for the QUIC peers that can not reach each other directly, for example when
+//! hole punching fails between two NATs.
+//!
+//! Both peers talk to the [`RelayServer`], which forwards the QUIC packets between them by their
+//! connection IDs, without decrypting them. The peer to be reached [`register`]s on the relay, the
+//! other peer [`bind`]s to it and connects to a [`SocketEndpointAddr::Relay`], whose relayed path
+//! can be replaced with a direct path later.
+//!
+//! [`SocketEndpointAddr::Relay`]: qbase::net::route::SocketEndpointAddr::Relay
+mod bandwidth;
+mod control;
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    io,
+    net::SocketAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub use bandwidth::Bandwidth;
+use bandwidth::TokenBucket;
+pub use control::{Control, bind, register};
+use qbase::{
+    cid::{ConnectionId, MAX_CID_SIZE},
+    net::{
+        addr::RealAddr,
+        route::{Link, PacketHeader, Pathway},
+    },
+};
+use qinterface::{QuicIO, QuicIoExt};
+
+#[derive(Debug)]
+struct Peer {
+    registered: bool,
+    // BIND指定的节点，连接ID未知时转发给它
+    counterpart: Option<SocketAddr>,
+    bucket: Option<TokenBucket>,
+    last_seen: Instant,
+}
+
+impl Peer {
+    fn new(now: Instant) -> Self {
+        Self {
+            registered: false,
+            counterpart: None,
+            bucket: None,
+            last_seen: now,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    peers: HashMap<SocketAddr, Peer>,
+    // 连接ID -> (目标节点, 最近使用的时间)，只从长包头的源连接ID学到，归发送它的节点所有
+    cids: HashMap<ConnectionId, (SocketAddr, Instant)>,
+}
+
+impl State {
+    /// The peer to forward the packets from `src` with unknown connection IDs to: the peer `src`
+    /// is bound to, or the only peer bound to `src`.
+    fn counterpart_of(&self, src: &SocketAddr) -> Option<SocketAddr> {
+        if let Some(counterpart) = self.peers.get(src)?.counterpart {
+            return Some(counterpart);
+        }
+        let mut bound = self
+            .peers
+            .iter()
+            .filter(|(_, peer)| peer.counterpart.as_ref() == Some(src))
+            .map(|(addr, _)| *addr);
+        // 多个节点绑定时无法判断归属，丢弃而不是猜测
+        match (bound.next(), bound.next()) {
+            (Some(counterpart), None) => Some(counterpart),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the destination connection ID, and the source connection ID of long header packets.
+///
+/// Only the first packet of a datagram is parsed, the coalesced packets share the same
+/// connection IDs.
+fn parse_cids(
+    datagram: &[u8],
+    short_dcid_len: usize,
+) -> Option<(ConnectionId, Option<ConnectionId>)> {
+    fn cid(input: &[u8]) -> Option<(ConnectionId, &[u8])> {
+        let (&len, input) = input.split_first()?;
+        let len = len as usize;
+        if len > MAX_CID_SIZE || input.len() < len {
+            return None;
+        }
+        let (cid, remain) = input.split_at(len);
+        Some((ConnectionId::from_slice(cid), remain))
+    }
+
+    let (&first, remain) = datagram.split_first()?;
+    if first & 0x80 != 0 {
+        // 长包头，版本协商包的固定位可能未置位
+        let (dcid, remain) = cid(remain.get(4..)?)?;
+        let (scid, _) = cid(remain)?;
+        Some((dcid, Some(scid)))
+    } else if first & 0x40 != 0 {
+        let dcid = remain.get(..short_dcid_len)?;
+        Some((ConnectionId::from_slice(dcid), None))
+    } else {
+        None
+    }
+}
+
+/// A relay forwarding QUIC packets between the registered peers by their connection IDs.
+///
+/// A peer [`register`]s to be reachable through the relay, and another peer [`bind`]s to it before
+/// connecting. The relay learns the connection IDs from the source connection IDs of the long
+/// header packets, a connection ID belongs to the peer which sent it first and is never taken over
+/// by another peer. A packet with an unknown destination connection ID, such as the first Initial
+/// packet or a packet using a connection ID issued in an encrypted NEW_CONNECTION_ID frame, is
+/// forwarded to the peer the sender is bound to, or to the only peer bound to the sender; it is
+/// dropped if several peers are bound to the sender. Packets from unknown peers are dropped, so
+/// that the relay is not an open relay.
+///
+/// Peers and connection IDs idle for the idle timeout are forgotten, registered peers should
+/// register again periodically to stay reachable.
+///
+/// The bandwidth of each peer can be limited with [`RelayServer::with_peer_bandwidth`] and
+/// [`RelayServer::limit_peer`].
+pub struct RelayServer {
+    io: Box<dyn QuicIO>,
+    short_dcid_len: usize,
+    idle_timeout: Duration,
+    peer_bandwidth: Option<Bandwidth>,
+    limits: Mutex<HashMap<SocketAddr, Option<Bandwidth>>>,
+    state: Mutex<State>,
+}
+
+impl std::fmt::Debug for RelayServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RelayServer")
+            .field("bind_uri", &self.io.bind_uri())
+            .field("short_dcid_len", &self.short_dcid_len)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("peer_bandwidth", &self.peer_bandwidth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RelayServer {
+    /// Create a relay on the interface, with no bandwidth limits.
+    pub fn new(io: Box<dyn QuicIO>) -> Self {
+        Self {
+            io,
+            short_dcid_len: 8,
+            idle_timeout: Duration::from_secs(30),
+            peer_bandwidth: None,
+            limits: Mutex::default(),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Specify the length of the destination connection IDs of short header packets.
+    ///
+    /// Short headers do not carry the length, all the relayed peers must issue connection IDs of
+    /// this length. Default to 8, the length gm-quic issues.
+    pub fn with_short_dcid_len(mut self, len: usize) -> Self {
+        assert!(
+            len <= MAX_CID_SIZE,
+            "connection ID must not exceed 20 bytes"
+        );
+        self.short_dcid_len = len;
+        self
+    }
+
+    /// Specify how long an idle peer or connection ID is kept, default to 30 seconds.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Limit the bandwidth of every peer, counting the bytes relayed from and to the peer.
+    pub fn with_peer_bandwidth(mut self, bandwidth: Bandwidth) -> Self {
+        self.peer_bandwidth = Some(bandwidth);
+        self
+    }
+
+    /// Override the bandwidth limit of the `peer`, `None` lifts the limit of the peer.
+    ///
+    /// This takes effect at once, even when the relay is running.
+    pub fn limit_peer(&self, peer: SocketAddr, bandwidth: Option<Bandwidth>) {
+        self.limits.lock().unwrap().insert(peer, bandwidth);
+        if let Some(peer) = self.state.lock().unwrap().peers.get_mut(&peer) {
+            peer.bucket = None;
+        }
+    }
+
+    /// Returns the address of the relay, which the peers register and bind on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self.io.real_addr()? {
+            RealAddr::Internet(addr) => Ok(addr),
+            addr => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Relay address {addr} is not an internet address"),
+            )),
+        }
+    }
+
+    fn bandwidth_of(&self, peer: &SocketAddr) -> Option<Bandwidth> {
+        match self.limits.lock().unwrap().get(peer) {
+            Some(bandwidth) => *bandwidth,
+            None => self.peer_bandwidth,
+        }
+    }
+
+    fn control(&self, state: &mut State, src: SocketAddr, control: Control, now: Instant) {
+        match control {
+            Control::Register => {
+                tracing::debug!(%src, "Peer registered");
+                let peer = state.peers.entry(src).or_insert_with(|| Peer::new(now));
+                peer.registered = true;
+                peer.last_seen = now;
+            }
+            Control::Bind(dst) if dst == src => {}
+            Control::Bind(dst) => {
+                if !state.peers.get(&dst).is_some_and(|peer| peer.registered) {
+                    tracing::debug!(%src, %dst, "Bind to unregistered peer");
+                    return;
+                }
+                tracing::debug!(%src, %dst, "Peer bound");
+                let peer = state.peers.entry(src).or_insert_with(|| Peer::new(now));
+                peer.counterpart = Some(dst);
+                peer.last_seen = now;
+            }
+        }
+    }
+
+    /// Charge `size` bytes to the buckets of both peers, returns false if either runs short.
+    fn charge(&self, state: &mut State, peers: [SocketAddr; 2], size: usize, now: Instant) -> bool {
+        let bandwidths = peers.map(|peer| self.bandwidth_of(&peer));
+        for (peer, bandwidth) in peers.iter().zip(bandwidths) {
+            let (Some(peer), Some(bandwidth)) = (state.peers.get_mut(peer), bandwidth) else {
+                continue;
+            };
+            let bucket = peer
+                .bucket
+                .get_or_insert_with(|| TokenBucket::full(bandwidth, now));
+            if !bucket.can_take(bandwidth, size, now) {
+                return false;
+            }
+        }
+        for (peer, bandwidth) in peers.iter().zip(bandwidths) {
+            if let (Some(peer), Some(_)) = (state.peers.get_mut(peer), bandwidth) {
+                peer.bucket.as_mut().expect("checked above").take(size);
+            }
+        }
+        true
+    }
+
+    /// Handle a datagram from `src`, returns the peer to forward it to.
+    fn relay(&self, datagram: &[u8], src: SocketAddr, now: Instant) -> Option<SocketAddr> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(control) = Control::parse(datagram) {
+            self.control(&mut state, src, control, now);
+            return None;
+        }
+
+        let (dcid, scid) = parse_cids(datagram, self.short_dcid_len)?;
+        let Some(peer) = state.peers.get_mut(&src) else {
+            tracing::trace!(%src, "Drop packet from unknown peer");
+            return None;
+        };
+        peer.last_seen = now;
+
+        if let Some(scid) = scid.filter(|scid| !scid.is_empty()) {
+            match state.cids.entry(scid) {
+                Entry::Occupied(mut owned) if owned.get().0 == src => owned.get_mut().1 = now,
+                Entry::Occupied(_) => {
+                    tracing::debug!(%src, "Drop packet with connection ID owned by another peer");
+                    return None;
+                }
+                Entry::Vacant(vacant) => _ = vacant.insert((src, now)),
+            }
+        }
+        let dst = match state.cids.get_mut(&dcid) {
+            Some((dst, last_seen)) if *dst != src => {
+                *last_seen = now;
+                *dst
+            }
+            _ => state.counterpart_of(&src)?,
+        };
+        if !state.peers.contains_key(&dst) {
+            return None;
+        }
+
+        if !self.charge(&mut state, [src, dst], datagram.len(), now) {
+            tracing::trace!(%src, %dst, "Drop packet exceeding bandwidth");
+            return None;
+        }
+        Some(dst)
+    }
+
+    fn prune(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        let State { peers, cids } = &mut *state;
+        peers.retain(|_, peer| now.saturating_duration_since(peer.last_seen) < self.idle_timeout);
+        cids.retain(|_, (dst, last_seen)| {
+            now.saturating_duration_since(*last_seen) < self.idle_timeout && peers.contains_key(dst)
+        });
+    }
+
+    /// Relay the packets until the interface fails.
+    pub async fn run(&self) -> io::Result<()> {
+        let local = self.io.real_addr()?;
+        let mut prune = tokio::time::interval(self.idle_timeout / 2);
+        let (mut bufs, mut hdrs) = (vec![], vec![]);
+        loop {
+            tokio::select! {
+                received = self.io.recvmmsg(&mut bufs, &mut hdrs) => {
+                    let now = Instant::now();
+                    let relayed = received?
+                        .filter_map(|(datagram, hdr)| {
+                            let RealAddr::Internet(src) = hdr.link().dst() else {
+                                return None;
+                            };
+                            let dst = self.relay(&datagram, src, now)?;
+                            Some((datagram, dst))
+                        })
+                        .collect::<Vec<_>>();
+                    for (datagram, dst) in relayed {
+                        let pathway = Pathway::new(local.clone().into(), dst.into());
+                        let link = Link::new(local.clone(), RealAddr::Internet(dst));
+                        let hdr = PacketHeader::new(pathway, link, 64, None, datagram.len() as u16);
+                        if let Err(e) = self.io.sendmmsg(&[io::IoSlice::new(&datagram)], hdr).await {
+                            tracing::debug!(%dst, "Failed to relay packet: {e}");
+                        }
+                    }
+                }
+                _ = prune.tick() => self.prune(Instant::now()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::BytesMut;
+    use qbase::net::addr::BindUri;
+    use qinterface::factory::{ProductQuicIO, handy::DEFAULT_QUIC_IO_FACTORY};
+
+    use super::*;
+
+    fn bind() -> Box<dyn QuicIO> {
+        DEFAULT_QUIC_IO_FACTORY
+            .bind("inet://127.0.0.1:0".parse::<BindUri>().unwrap())
+            .unwrap()
+    }
+
+    fn addr_of(io: &dyn QuicIO) -> SocketAddr {
+        match io.real_addr().unwrap() {
+            RealAddr::Internet(addr) => addr,
+            _ => unreachable!(),
+        }
+    }
+
+    fn long_packet(dcid: &[u8], scid: &[u8], size: usize) -> Vec<u8> {
+        let mut packet = vec![0xc0, 0, 0, 0, 1];
+        packet.push(dcid.len() as u8);
+        packet.extend_from_slice(dcid);
+        packet.push(scid.len() as u8);
+        packet.extend_from_slice(scid);
+        packet.resize(size, 0);
+        packet
+    }
+
+    fn short_packet(dcid: &[u8], size: usize) -> Vec<u8> {
+        let mut packet = vec![0x40];
+        packet.extend_from_slice(dcid);
+        packet.resize(size, 0);
+        packet
+    }
+
+    #[test]
+    fn test_parse_cids() {
+        let packet = long_packet(&[1; 8], &[2; 4], 64);
+        assert_eq!(
+            parse_cids(&packet, 8),
+            Some((
+                ConnectionId::from_slice(&[1; 8]),
+                Some(ConnectionId::from_slice(&[2; 4]))
+            ))
+        );
+        assert_eq!(
+            parse_cids(&short_packet(&[3; 8], 64), 8),
+            Some((ConnectionId::from_slice(&[3; 8]), None))
+        );
+        assert_eq!(parse_cids(&short_packet(&[3; 4], 5), 8), None);
+        assert_eq!(parse_cids(&long_packet(&[1; 21], &[], 64), 8), None);
+        assert_eq!(parse_cids(&[0x00, 1, 2, 3], 8), None);
+    }
+
+    #[tokio::test]
+    async fn test_relay_by_cid() {
+        let relay = RelayServer::new(bind());
+        let (a, b, c) = (
+            "1.1.1.1:1".parse().unwrap(),
+            "2.2.2.2:2".parse().unwrap(),
+            "3.3.3.3:3".parse().unwrap(),
+        );
+        let now = Instant::now();
+
+        let initial = long_packet(&[9; 8], &[1; 8], 1200);
+        // 未注册也未绑定的节点发来的包被丢弃
+        assert_eq!(relay.relay(&initial, a, now), None);
+        // 不能绑定到未注册的节点
+        assert_eq!(relay.relay(&Control::Bind(b).encode(), a, now), None);
+        assert_eq!(relay.relay(&initial, a, now), None);
+
+        relay.relay(&Control::Register.encode(), b, now);
+        relay.relay(&Control::Bind(b).encode(), a, now);
+        assert_eq!(relay.relay(&initial, a, now), Some(b));
+        assert_eq!(
+            relay.relay(&long_packet(&[1; 8], &[2; 8], 1200), b, now),
+            Some(a)
+        );
+        assert_eq!(relay.relay(&short_packet(&[2; 8], 64), a, now), Some(b));
+        // 未知的连接ID按绑定关系转发
+        assert_eq!(relay.relay(&short_packet(&[4; 8], 64), a, now), Some(b));
+        assert_eq!(relay.relay(&short_packet(&[5; 8], 64), b, now), Some(a));
+
+        // 另一个节点绑定后，已知的连接ID仍按原样转发，b的未知连接ID无法判断归属而被丢弃
+        relay.relay(&Control::Bind(b).encode(), c, now);
+        assert_eq!(
+            relay.relay(&long_packet(&[8; 8], &[3; 8], 1200), c, now),
+            Some(b)
+        );
+        assert_eq!(relay.relay(&short_packet(&[6; 8], 64), b, now), None);
+        assert_eq!(relay.relay(&short_packet(&[3; 8], 64), b, now), Some(c));
+        assert_eq!(relay.relay(&short_packet(&[1; 8], 64), b, now), Some(a));
+
+        // 不能冒用其他节点的连接ID
+        assert_eq!(
+            relay.relay(&long_packet(&[2; 8], &[1; 8], 1200), c, now),
+            None
+        );
+        assert_eq!(relay.relay(&short_packet(&[1; 8], 64), b, now), Some(a));
+
+        // 闲置的节点和连接ID被遗忘
+        relay.prune(now + Duration::from_secs(60));
+        assert_eq!(relay.relay(&short_packet(&[2; 8], 64), a, now), None);
+    }
+
+    #[tokio::test]
+    async fn test_peer_bandwidth() {
+        let relay = RelayServer::new(bind()).with_peer_bandwidth(Bandwidth::new(1000, 1500));
+        let (a, b) = ("1.1.1.1:1".parse().unwrap(), "2.2.2.2:2".parse().unwrap());
+        let now = Instant::now();
+        relay.relay(&Control::Register.encode(), b, now);
+        relay.relay(&Control::Bind(b).encode(), a, now);
+
+        let initial = long_packet(&[9; 8], &[1; 8], 1200);
+        assert_eq!(relay.relay(&initial, a, now), Some(b));
+        // a和b的带宽都已用掉1200字节
+        assert_eq!(relay.relay(&initial, a, now), None);
+        assert_eq!(
+            relay.relay(&long_packet(&[1; 8], &[2; 8], 1200), b, now),
+            None
+        );
+        let later = now + Duration::from_secs(1);
+        assert_eq!(relay.relay(&initial, a, later), Some(b));
+
+        relay.limit_peer(a, None);
+        relay.limit_peer(b, None);
+        for _ in 0..10 {
+            assert_eq!(relay.relay(&initial, a, later), Some(b));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relay_loopback() {
+        let relay = Arc::new(RelayServer::new(bind()));
+        let relay_addr = relay.local_addr().unwrap();
+        tokio::spawn({
+            let relay = relay.clone();
+            async move { relay.run().await }
+        });
+
+        let (a, b) = (bind(), bind());
+        register(b.as_ref(), relay_addr).await.unwrap();
+        bind_to(a.as_ref(), relay_addr, addr_of(b.as_ref())).await;
+
+        let initial = long_packet(&[9; 8], &[1; 8], 1200);
+        send(a.as_ref(), relay_addr, &initial).await;
+        let (datagram, src) = recv(b.as_ref()).await;
+        assert_eq!((datagram, src), (initial, RealAddr::Internet(relay_addr)));
+
+        let reply = short_packet(&[1; 8], 64);
+        send(b.as_ref(), relay_addr, &reply).await;
+        let (datagram, src) = recv(a.as_ref()).await;
+        assert_eq!((datagram, src), (reply, RealAddr::Internet(relay_addr)));
+    }
+
+    async fn recv(io: &dyn QuicIO) -> (Vec<u8>, RealAddr) {
+        let (mut bufs, mut hdrs) = (vec![], vec![]);
+        let (datagram, hdr): (BytesMut, PacketHeader) = io
+            .recvmmsg(&mut bufs, &mut hdrs)
+            .await
+            .unwrap()
+            .next()
+            .unwrap();
+        (datagram.to_vec(), hdr.link().dst())
+    }
+
+    async fn bind_to(io: &dyn QuicIO, relay: SocketAddr, peer: SocketAddr) {
+        super::bind(io, relay, peer).await.unwrap();
+    }
+
+    async fn send(io: &dyn QuicIO, dst: SocketAddr, datagram: &[u8]) {
+        let local = io.real_addr().unwrap();
+        let hdr = PacketHeader::new(
+            Pathway::new(local.clone().into(), dst.into()),
+            Link::new(local, RealAddr::Internet(dst)),
+            64,
+            None,
+            datagram.len() as u16,
+        );
+        io.sendmmsg(&[io::IoSlice::new(datagram)], hdr)
+            .await
+            .unwrap();
+    }
+}
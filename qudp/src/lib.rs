@@ -4,7 +4,10 @@ use std::{
     io::{self, IoSlice, IoSliceMut},
     net::SocketAddr,
     pin::Pin,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
     task::{Context, Poll, Wake, Waker, ready},
 };
 
@@ -12,6 +15,10 @@ use socket2::{Domain, Socket, Type};
 use tokio::io::Interest;
 const DEFAULT_TTL: libc::c_int = 64;
 pub const BATCH_SIZE: usize = 64;
+/// The maximum number of segments the kernel coalesces into one GRO buffer (`UDP_GRO_CNT_MAX`).
+pub const GRO_SEGMENTS: usize = 64;
+/// The maximum number of segments sent in one GSO buffer (`UDP_MAX_SEGMENTS`).
+pub const GSO_SEGMENTS: usize = 64;
 cfg_if::cfg_if! {
     if #[cfg(unix)]{
         #[path = "unix.rs"]
@@ -59,15 +66,66 @@ impl Default for DatagramHeader {
     }
 }
 
+/// The segmentation offloads of the socket, detected at runtime when the socket is bound.
+#[derive(Debug, Default)]
+pub struct Offload {
+    // 网卡不支持GSO时发送会失败(EIO)，此时关闭GSO
+    gso: AtomicBool,
+    gro: bool,
+}
+
+impl Offload {
+    pub fn new(gso: bool, gro: bool) -> Self {
+        Self {
+            gso: AtomicBool::new(gso),
+            gro,
+        }
+    }
+
+    #[inline]
+    pub fn gso(&self) -> bool {
+        self.gso.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn gro(&self) -> bool {
+        self.gro
+    }
+
+    fn disable_gso(&self) {
+        if self.gso.swap(false, Ordering::Relaxed) {
+            tracing::warn!(
+                "GSO is not supported by the network device, fallback to send segments one by one"
+            );
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct UdpSocketController {
     io: tokio::net::UdpSocket,
+    offload: Offload,
+    // GRO合并后的数据包先接收到这里，再按段拆分到调用者的缓冲区中
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    gro_bufs: Mutex<unix::GroBuffers>,
     read: Arc<Wakers>,
     write: Arc<Wakers>,
 }
 
 impl UdpSocketController {
     pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        Self::bind_with(addr, false)
+    }
+
+    /// Bind the socket with `SO_REUSEPORT`, so that several sockets can be bound on the same
+    /// address, and the kernel distributes the received datagrams among them.
+    ///
+    /// Only supported on unix platforms.
+    pub fn bind_reuse_port(addr: SocketAddr) -> io::Result<Self> {
+        Self::bind_with(addr, true)
+    }
+
+    fn bind_with(addr: SocketAddr, reuse_port: bool) -> io::Result<Self> {
         let domain = if addr.is_ipv4() {
             Domain::IPV4
         } else {
@@ -76,20 +134,54 @@ impl UdpSocketController {
 
         let socket = Socket::new(domain, Type::DGRAM, None)?;
         socket.set_nonblocking(true)?;
+        if reuse_port {
+            Self::set_reuse_port(&socket)?;
+        }
         Self::config(&socket, addr)?;
+        let offload = Self::offload(&socket);
         let io = tokio::net::UdpSocket::from_std(socket.into())?;
         let usc = Self {
             io,
+            offload,
+            #[cfg(any(target_os = "android", target_os = "linux"))]
+            gro_bufs: Default::default(),
             read: Default::default(),
             write: Default::default(),
         };
         Ok(usc)
     }
 
+    /// Take over a socket which is already bound and configured, for example one inherited
+    /// from another process.
+    ///
+    /// The socket options are kept as they are, only the offloads are detected again.
+    pub fn from_std(socket: std::net::UdpSocket) -> io::Result<Self> {
+        let socket = Socket::from(socket);
+        socket.set_nonblocking(true)?;
+        let offload = Self::offload(&socket);
+        let io = tokio::net::UdpSocket::from_std(socket.into())?;
+        Ok(Self {
+            io,
+            offload,
+            #[cfg(any(target_os = "android", target_os = "linux"))]
+            gro_bufs: Default::default(),
+            read: Default::default(),
+            write: Default::default(),
+        })
+    }
+
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.io.local_addr()
     }
 
+    /// The maximum number of segments that can be sent in one buffer, 1 if GSO is unavailable.
+    pub fn max_gso_segments(&self) -> usize {
+        match self.offload.gso() {
+            true => GSO_SEGMENTS,
+            false => 1,
+        }
+    }
+
     pub fn poll_send_ready(&self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         self.write.register(cx.waker());
         self.io
@@ -128,6 +220,11 @@ impl UdpSocketController {
         hdrs: &mut [DatagramHeader],
     ) -> Poll<io::Result<usize>> {
         loop {
+            // GRO拆分出的段还没有取完时，不需要等待socket可读
+            #[cfg(any(target_os = "android", target_os = "linux"))]
+            if self.has_gro_segments() {
+                return Poll::Ready(self.recvmsg(bufs, hdrs));
+            }
             ready!(self.poll_recv_ready(cx)?);
             let f = || self.recvmsg(bufs, hdrs);
             let ret = self.io.try_io(Interest::READABLE, f);
@@ -161,6 +258,13 @@ impl Wake for Wakers {
     }
 }
 
+#[cfg(unix)]
+impl std::os::fd::AsFd for UdpSocketController {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.io.as_fd()
+    }
+}
+
 impl Drop for UdpSocketController {
     fn drop(&mut self) {
         self.read.wake_by_ref();
@@ -171,6 +275,11 @@ impl Drop for UdpSocketController {
 pub trait Io {
     fn config(io: &socket2::Socket, addr: SocketAddr) -> io::Result<()>;
 
+    fn set_reuse_port(io: &socket2::Socket) -> io::Result<()>;
+
+    /// Detect the GSO support and try to enable GRO for the socket.
+    fn offload(io: &socket2::Socket) -> Offload;
+
     fn sendmsg(&self, bufs: &[IoSlice<'_>], hdr: &DatagramHeader) -> io::Result<usize>;
 
     fn recvmsg(&self, bufs: &mut [IoSliceMut<'_>], hdr: &mut [DatagramHeader])
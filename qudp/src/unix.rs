@@ -13,7 +13,7 @@ use nix::{
 };
 use socket2::Socket;
 
-use crate::{DEFAULT_TTL, DatagramHeader, Io, UdpSocketController};
+use crate::{DEFAULT_TTL, DatagramHeader, Io, Offload, UdpSocketController};
 
 const OPTION_ON: bool = true;
 const OPTION_OFF: bool = false;
@@ -49,6 +49,27 @@ impl Io for UdpSocketController {
         socket.bind(&addr.into())
     }
 
+    fn set_reuse_port(socket: &Socket) -> io::Result<()> {
+        nix::sys::socket::setsockopt(&socket.as_fd(), sockopt::ReusePort, &OPTION_ON)?;
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn offload(socket: &Socket) -> Offload {
+        let fd = socket.as_raw_fd();
+        // nix只在linux上提供UDP_SEGMENT和UDP_GRO，android的内核同样支持，直接通过libc设置
+        // 能读取UDP_SEGMENT说明内核支持GSO(Linux 4.18+)，网卡不支持时发送会返回EIO，届时再关闭
+        let gso = getsockopt_udp(fd, libc::UDP_SEGMENT).is_ok();
+        let gro = setsockopt_udp(fd, libc::UDP_GRO, 1).is_ok();
+        tracing::debug!(gso, gro, "Detected UDP segmentation offload");
+        Offload::new(gso, gro)
+    }
+
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    fn offload(_: &Socket) -> Offload {
+        Offload::default()
+    }
+
     #[cfg(any(
         target_os = "android",
         target_os = "linux",
@@ -62,24 +83,22 @@ impl Io for UdpSocketController {
         };
 
         use super::BATCH_SIZE;
-        let slices: Vec<_> = buffers
-            .iter()
-            .take(BATCH_SIZE)
-            .map(std::slice::from_ref)
-            .collect();
-
-        let batch_size = slices.len();
-        if batch_size == 0 {
+        let buffers = &buffers[..buffers.len().min(BATCH_SIZE)];
+        if buffers.is_empty() {
             return Ok(0);
         }
-        #[cfg(feature = "gso")]
-        let (cmsgs, space) = (
-            vec![nix::sys::socket::ControlMessage::UdpGsoSegments(
-                &hdr.seg_size,
-            )],
-            Some(cmsg_space!(libc::c_int)),
-        );
-        #[cfg(not(feature = "gso"))]
+
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        if self.offload.gso() {
+            return self.sendmsg_gso(buffers, hdr);
+        }
+        // 只有缓冲区大于段大小时才需要分段发送
+        let seg_size = hdr.seg_size as usize;
+        if seg_size > 0 && buffers.iter().any(|buf| buf.len() > seg_size) {
+            return self.sendmsg_segments(buffers, hdr);
+        }
+
+        let slices: Vec<_> = buffers.iter().map(std::slice::from_ref).collect();
         let (cmsgs, space) = (Vec::new(), None);
 
         macro_rules! send_batch {
@@ -169,6 +188,11 @@ impl Io for UdpSocketController {
         use nix::sys::socket::{MsgFlags, recvmmsg};
 
         use super::BATCH_SIZE;
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        if self.offload.gro() {
+            return self.recvmsg_gro(bufs, recv_hdrs);
+        }
+
         let mut msgs: Vec<_> = bufs
             .iter_mut()
             .map(|buf| [std::io::IoSliceMut::new(&mut buf[..])])
@@ -262,6 +286,324 @@ impl Io for UdpSocketController {
     }
 }
 
+#[cfg(any(
+    target_os = "android",
+    target_os = "linux",
+    target_os = "freebsd",
+    target_os = "netbsd"
+))]
+impl UdpSocketController {
+    /// Send the buffers larger than the segment size without GSO, by splitting them into
+    /// segments and sending the segments one by one.
+    ///
+    /// Return the number of buffers that are completely sent. If the socket blocks in the middle
+    /// of a buffer, the segments of it already sent will be sent again, which is harmless for
+    /// QUIC as duplicate packets are discarded.
+    fn sendmsg_segments(&self, buffers: &[IoSlice<'_>], hdr: &DatagramHeader) -> io::Result<usize> {
+        let seg_size = hdr.seg_size as usize;
+        for (sent, buffer) in buffers.iter().enumerate() {
+            let segments = buffer
+                .chunks(seg_size)
+                .map(IoSlice::new)
+                .collect::<Vec<_>>();
+            let mut segments = &segments[..];
+            while !segments.is_empty() {
+                match self.sendmsg(segments, hdr) {
+                    Ok(n) => segments = &segments[n..],
+                    Err(_) if sent > 0 => return Ok(sent),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(buffers.len())
+    }
+}
+
+/// The buffer size large enough to receive any datagram coalesced by GRO.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const MAX_DATAGRAM_SIZE: usize = u16::MAX as usize;
+
+/// The maximum total size of the segments sent in one GSO buffer, which is the maximum payload
+/// of a UDP datagram over IPv4.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const MAX_GSO_PAYLOAD: usize = u16::MAX as usize - 8 - 20;
+
+/// The buffer receiving the datagrams coalesced by GRO, and the segments split from them but
+/// not yet taken out.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[derive(Debug)]
+pub(crate) struct GroBuffers {
+    // 每个数据包占用MAX_DATAGRAM_SIZE字节，内核只写入实际收到的部分
+    buf: Vec<u8>,
+    // 上次接收时平均每个数据包合并的段数，据此决定一次接收多少个数据包
+    segments_per_datagram: usize,
+    // (首部, 段在缓冲区中的范围)
+    segments: std::collections::VecDeque<(DatagramHeader, std::ops::Range<usize>)>,
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+impl Default for GroBuffers {
+    fn default() -> Self {
+        Self {
+            buf: Vec::new(),
+            segments_per_datagram: crate::GRO_SEGMENTS,
+            segments: Default::default(),
+        }
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+impl UdpSocketController {
+    /// Send the `buffers` with GSO, return the number of buffers sent.
+    ///
+    /// The consecutive buffers of the same size are sent in one GSO buffer, see [`gso_batch`].
+    fn sendmsg_gso(&self, buffers: &[IoSlice<'_>], hdr: &DatagramHeader) -> io::Result<usize> {
+        let fd = self.io.as_raw_fd();
+        self.sendmsg_gso_with(buffers, hdr, |segments, seg_size| {
+            sendmsg_with_segment_size(fd, segments, hdr.dst, seg_size)
+        })
+    }
+
+    /// [`Self::sendmsg_gso`] with the function sending one GSO buffer, if it fails with `EIO`
+    /// GSO is disabled and the `buffers` are sent again without it.
+    fn sendmsg_gso_with(
+        &self,
+        buffers: &[IoSlice<'_>],
+        hdr: &DatagramHeader,
+        send: impl Fn(&[IoSlice<'_>], u16) -> io::Result<usize>,
+    ) -> io::Result<usize> {
+        let mut sent = 0;
+        while sent < buffers.len() {
+            let rest = &buffers[sent..];
+            let (count, seg_size) = gso_batch(rest, hdr.seg_size, self.max_gso_segments());
+
+            match send(&rest[..count], seg_size) {
+                Ok(_) => sent += count,
+                Err(_) if sent > 0 => return Ok(sent),
+                Err(e) if e.raw_os_error() == Some(libc::EIO) => {
+                    // 网卡不支持GSO(例如不支持校验和卸载)，关闭GSO后重新发送
+                    self.offload.disable_gso();
+                    return self.sendmsg(buffers, hdr);
+                }
+                Err(e)
+                    if matches!(
+                        e.raw_os_error(),
+                        Some(libc::EINTR | libc::EAGAIN | libc::ENOBUFS)
+                    ) =>
+                {
+                    return Err(io::Error::new(io::ErrorKind::WouldBlock, e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Whether there are segments of the coalesced datagrams not yet taken out, they can be
+    /// received without waiting for the socket to be readable.
+    pub(crate) fn has_gro_segments(&self) -> bool {
+        self.offload.gro() && !self.gro_bufs.lock().unwrap().segments.is_empty()
+    }
+
+    /// Receive the datagrams coalesced by GRO, and split them into segments in the `bufs`.
+    ///
+    /// The segment size reported by the kernel is parsed into [`DatagramHeader::seg_size`] by
+    /// [`parse_cmsg`], each coalesced datagram is split by it. The number of datagrams received
+    /// at once is sized from the segments per datagram of the last receive, to fill the `bufs`
+    /// without reserving a buffer of [`MAX_DATAGRAM_SIZE`] for every one of them. The segments
+    /// that do not fit in the `bufs` are kept for the next receive.
+    fn recvmsg_gro(
+        &self,
+        bufs: &mut [std::io::IoSliceMut<'_>],
+        recv_hdrs: &mut [DatagramHeader],
+    ) -> io::Result<usize> {
+        use nix::sys::socket::{MsgFlags, MultiHeaders, recvmmsg};
+
+        use crate::BATCH_SIZE;
+
+        let capacity = bufs.len().min(recv_hdrs.len());
+        let mut gro_bufs = self.gro_bufs.lock().unwrap();
+        let GroBuffers {
+            buf,
+            segments_per_datagram,
+            segments,
+        } = &mut *gro_bufs;
+
+        if segments.is_empty() {
+            let batch_size = capacity
+                .div_ceil(*segments_per_datagram)
+                .clamp(1, BATCH_SIZE);
+            if buf.len() < batch_size * MAX_DATAGRAM_SIZE {
+                // 大块内存由操作系统按需映射，没有写入的部分不占用物理内存
+                *buf = vec![0; batch_size * MAX_DATAGRAM_SIZE];
+            }
+
+            let local_addr = self.local_addr()?;
+            let mut msgs: Vec<_> = buf
+                .chunks_mut(MAX_DATAGRAM_SIZE)
+                .take(batch_size)
+                .map(|slot| [std::io::IoSliceMut::new(slot)])
+                .collect();
+            let cmsg_buffer = cmsg_space!(libc::in_pktinfo, libc::in6_pktinfo, libc::c_int);
+            let mut data =
+                MultiHeaders::<SockaddrStorage>::preallocate(batch_size, Some(cmsg_buffer));
+            let results = match recvmmsg(
+                self.io.as_raw_fd(),
+                &mut data,
+                &mut msgs,
+                MsgFlags::MSG_DONTWAIT,
+                None,
+            ) {
+                Ok(results) => results,
+                Err(e @ (nix::errno::Errno::EAGAIN | nix::errno::Errno::EINTR)) => {
+                    return Err(io::Error::new(io::ErrorKind::WouldBlock, e));
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let mut datagrams = 0;
+            for (idx, recv_msg) in results.enumerate() {
+                let mut recv_hdr = DatagramHeader {
+                    src: recv_msg.address.unwrap().to_socketaddr(),
+                    dst: local_addr,
+                    ttl: 0,
+                    ecn: None,
+                    seg_size: recv_msg.bytes as u16,
+                };
+                for cmsg in recv_msg.cmsgs().unwrap() {
+                    parse_cmsg(cmsg, &mut recv_hdr);
+                }
+                recv_hdr.dst.set_port(local_addr.port());
+
+                let offset = idx * MAX_DATAGRAM_SIZE;
+                let stride = (recv_hdr.seg_size as usize).max(1);
+                segments.extend((0..recv_msg.bytes).step_by(stride).map(|start| {
+                    let end = (start + stride).min(recv_msg.bytes);
+                    (recv_hdr, offset + start..offset + end)
+                }));
+                datagrams += 1;
+            }
+            if datagrams > 0 {
+                *segments_per_datagram = segments.len().div_ceil(datagrams);
+            }
+        }
+
+        let mut count = 0;
+        while count < capacity {
+            let Some((recv_hdr, range)) = segments.pop_front() else {
+                break;
+            };
+            let segment = &buf[range];
+            let size = segment.len().min(bufs[count].len());
+            bufs[count][..size].copy_from_slice(&segment[..size]);
+            recv_hdrs[count] = DatagramHeader {
+                seg_size: size as u16,
+                ..recv_hdr
+            };
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+/// The number of buffers at the front of `buffers` sent in one GSO buffer, and their segment
+/// size.
+///
+/// The consecutive buffers of the same size are sent together, the last one of them may be
+/// smaller, so that the packets assembled one by one are still sent with a few syscalls. A buffer
+/// larger than `seg_size` is sent alone and segmented by `seg_size`.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn gso_batch(buffers: &[IoSlice<'_>], seg_size: u16, max_segments: usize) -> (usize, u16) {
+    match buffers[0].len() {
+        len if len > seg_size as usize => (1, seg_size),
+        len => {
+            let max_segments = max_segments.min(MAX_GSO_PAYLOAD / len.max(1));
+            let equals = buffers
+                .iter()
+                .take(max_segments)
+                .take_while(|buf| buf.len() == len)
+                .count();
+            let smaller = buffers
+                .get(equals)
+                .is_some_and(|buf| equals < max_segments && buf.len() < len);
+            (equals + smaller as usize, len as u16)
+        }
+    }
+}
+
+/// Send the `segments` in one buffer with the `UDP_SEGMENT` control message, which nix provides
+/// only on linux.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn sendmsg_with_segment_size(
+    fd: std::os::fd::RawFd,
+    segments: &[IoSlice<'_>],
+    dst: SocketAddr,
+    seg_size: u16,
+) -> io::Result<usize> {
+    use std::mem::size_of;
+
+    let addr = socket2::SockAddr::from(dst);
+    // cmsghdr需要按其字段对齐
+    let mut control = [0u64; 4];
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_name = addr.as_ptr() as *mut libc::c_void;
+    msg.msg_namelen = addr.len();
+    // IoSlice在unix上与iovec的内存布局相同
+    msg.msg_iov = segments.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = segments.len() as _;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(size_of::<u16>() as _) } as _;
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_UDP;
+        (*cmsg).cmsg_type = libc::UDP_SEGMENT;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(size_of::<u16>() as _) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<u16>(), seg_size);
+    }
+
+    match unsafe { libc::sendmsg(fd, &msg, 0) } {
+        -1 => Err(io::Error::last_os_error()),
+        sent => Ok(sent as usize),
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn getsockopt_udp(fd: std::os::fd::RawFd, name: libc::c_int) -> io::Result<libc::c_int> {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_UDP,
+            name,
+            (&mut value as *mut libc::c_int).cast(),
+            &mut len,
+        )
+    };
+    match ret {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(value),
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn setsockopt_udp(fd: std::os::fd::RawFd, name: libc::c_int, value: libc::c_int) -> io::Result<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_UDP,
+            name,
+            (&value as *const libc::c_int).cast(),
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    match ret {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
 fn parse_cmsg(cmsg: ControlMessageOwned, hdr: &mut DatagramHeader) {
     match cmsg {
         ControlMessageOwned::Ipv4PacketInfo(pktinfo) => {
@@ -272,6 +614,17 @@ fn parse_cmsg(cmsg: ControlMessageOwned, hdr: &mut DatagramHeader) {
             let ip = IpAddr::V6(Ipv6Addr::from(pktinfo6.ipi6_addr.s6_addr));
             hdr.dst.set_ip(ip);
         }
+        #[cfg(target_os = "linux")]
+        ControlMessageOwned::UdpGroSegments(stride) => hdr.seg_size = stride as u16,
+        #[cfg(target_os = "android")]
+        ControlMessageOwned::Unknown(cmsg)
+            if cmsg.cmsg_header.cmsg_level == libc::SOL_UDP
+                && cmsg.cmsg_header.cmsg_type == libc::UDP_GRO =>
+        {
+            if let Ok(stride) = cmsg.data_bytes[..].try_into() {
+                hdr.seg_size = libc::c_int::from_ne_bytes(stride) as u16;
+            }
+        }
         _ => {}
     }
 }
@@ -302,3 +655,167 @@ impl ToSocketAddr for SockaddrStorage {
         }
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn slices(bufs: &[Vec<u8>]) -> Vec<IoSlice<'_>> {
+        bufs.iter().map(|buf| IoSlice::new(buf)).collect()
+    }
+
+    fn payloads(lens: &[usize]) -> Vec<Vec<u8>> {
+        lens.iter()
+            .enumerate()
+            .map(|(i, &len)| vec![i as u8; len])
+            .collect()
+    }
+
+    fn localhost() -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 0))
+    }
+
+    #[test]
+    fn gso_batch_groups_equal_buffers() {
+        let bufs = payloads(&[100, 100, 100, 50, 100, 100]);
+        let bufs = slices(&bufs);
+        // 同样大小的缓冲区连同其后一个更小的缓冲区一起发送
+        assert_eq!(gso_batch(&bufs, 1200, 64), (4, 100));
+        assert_eq!(gso_batch(&bufs[4..], 1200, 64), (2, 100));
+        // 不超过最大段数
+        assert_eq!(gso_batch(&bufs, 1200, 2), (2, 100));
+        assert_eq!(gso_batch(&bufs, 1200, 3), (3, 100));
+
+        let bufs = payloads(&[50, 100]);
+        assert_eq!(gso_batch(&slices(&bufs), 1200, 64), (1, 50));
+
+        // 总大小不超过一个UDP数据包
+        let bufs = payloads(&[1200; 64]);
+        assert_eq!(
+            gso_batch(&slices(&bufs), 1200, 64),
+            (MAX_GSO_PAYLOAD / 1200, 1200)
+        );
+
+        // 大于段大小的缓冲区单独发送，按段大小分段
+        let bufs = payloads(&[3000, 100]);
+        assert_eq!(gso_batch(&slices(&bufs), 1200, 64), (1, 1200));
+    }
+
+    #[tokio::test]
+    async fn gro_splits_coalesced_datagram() {
+        let receiver = UdpSocketController::bind(localhost()).unwrap();
+        if !receiver.offload.gro() {
+            return;
+        }
+        let dst = receiver.local_addr().unwrap();
+        let sender = std::net::UdpSocket::bind(localhost()).unwrap();
+
+        // 一个GSO缓冲区在回环上不会被分段，以合并的数据包交付给开启了GRO的socket
+        let sent = payloads(&[100, 100, 100, 50]);
+        let segments = sent.concat();
+        sendmsg_with_segment_size(sender.as_raw_fd(), &[IoSlice::new(&segments)], dst, 100)
+            .unwrap();
+
+        // 每次只取两段，剩余的段留到下一次接收
+        let mut received = vec![];
+        while received.len() < sent.len() {
+            let mut bufs = vec![vec![0u8; 1500]; 2];
+            let mut hdrs = vec![DatagramHeader::default(); 2];
+            let mut slices = bufs
+                .iter_mut()
+                .map(|buf| std::io::IoSliceMut::new(buf))
+                .collect::<Vec<_>>();
+            let count = tokio::time::timeout(
+                Duration::from_secs(1),
+                std::future::poll_fn(|cx| receiver.poll_recv(cx, &mut slices, &mut hdrs)),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            for (buf, hdr) in bufs.iter().zip(&hdrs).take(count) {
+                assert_eq!(hdr.src, sender.local_addr().unwrap());
+                received.push(buf[..hdr.seg_size as usize].to_vec());
+            }
+        }
+        assert_eq!(received, sent);
+        assert!(!receiver.has_gro_segments());
+    }
+
+    #[tokio::test]
+    async fn gso_sends_grouped_buffers() {
+        let sender = UdpSocketController::bind(localhost()).unwrap();
+        if !sender.offload.gso() {
+            return;
+        }
+        let receiver = std::net::UdpSocket::bind(localhost()).unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        let sent = payloads(&[100, 100, 100, 50, 100, 100]);
+        let hdr = DatagramHeader::new(
+            sender.local_addr().unwrap(),
+            receiver.local_addr().unwrap(),
+            64,
+            None,
+            1200,
+        );
+        let calls = std::cell::RefCell::new(vec![]);
+        let fd = sender.io.as_raw_fd();
+        let count = sender
+            .sendmsg_gso_with(&slices(&sent), &hdr, |segments, seg_size| {
+                calls.borrow_mut().push((segments.len(), seg_size));
+                sendmsg_with_segment_size(fd, segments, hdr.dst, seg_size)
+            })
+            .unwrap();
+        assert_eq!(count, sent.len());
+        assert_eq!(calls.into_inner(), [(4, 100), (2, 100)]);
+
+        // 没有开启GRO的socket收到分段后的数据包
+        let mut buf = [0u8; 1500];
+        for payload in &sent {
+            let len = receiver.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], &payload[..]);
+        }
+    }
+
+    #[tokio::test]
+    async fn gso_disabled_after_eio() {
+        let sender = UdpSocketController::bind(localhost()).unwrap();
+        sender
+            .offload
+            .gso
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        let receiver = std::net::UdpSocket::bind(localhost()).unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        let sent = payloads(&[100, 100, 50]);
+        let hdr = DatagramHeader::new(
+            sender.local_addr().unwrap(),
+            receiver.local_addr().unwrap(),
+            64,
+            None,
+            1200,
+        );
+        // 模拟网卡不支持GSO
+        let count = sender
+            .sendmsg_gso_with(&slices(&sent), &hdr, |_, _| {
+                Err(io::Error::from_raw_os_error(libc::EIO))
+            })
+            .unwrap();
+        assert_eq!(count, sent.len());
+        assert!(!sender.offload.gso());
+        assert_eq!(sender.max_gso_segments(), 1);
+
+        // 关闭GSO后逐个发送
+        let mut buf = [0u8; 1500];
+        for payload in &sent {
+            let len = receiver.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], &payload[..]);
+        }
+    }
+}
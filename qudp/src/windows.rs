@@ -10,7 +10,7 @@ use libc::c_uchar;
 use socket2::Socket;
 use windows_sys::Win32::Networking::WinSock::{self, SOCKET};
 
-use crate::{DEFAULT_TTL, Io, UdpSocketController};
+use crate::{DEFAULT_TTL, Io, Offload, UdpSocketController};
 
 const CMSG_LEN: usize = 128;
 #[derive(Copy, Clone)]
@@ -51,6 +51,18 @@ impl Io for UdpSocketController {
         Ok(())
     }
 
+    fn set_reuse_port(_: &Socket) -> std::io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "SO_REUSEPORT is not supported on windows",
+        ))
+    }
+
+    fn offload(_: &Socket) -> Offload {
+        // TODO: USO/URO
+        Offload::default()
+    }
+
     fn sendmsg(
         &self,
         bufs: &[std::io::IoSlice<'_>],